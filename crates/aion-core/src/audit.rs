@@ -0,0 +1,149 @@
+//! Append-only record of privileged actions (`audit.log` in the state dir, `crate::paths`).
+//!
+//! Notes:
+//! - One JSON object per line: `ts` (Unix seconds), `session` when a chat session is
+//!   active, and the `event` with its fields.
+//! - Events: commands run (with directory and exit code), files written, and network requests to hosts
+//!   other than the configured provider.
+//! - Like `redact`, the logger is process-wide: `init` once the config is loaded, then
+//!   write paths call `record` without passing it around. Nothing is recorded before
+//!   `init`, when `logging.audit` is off, or in an ephemeral process (see
+//!   `crate::persistence`).
+//! - Recording never fails the action: the first write error is printed as a warning and
+//!   later ones are ignored.
+//! - Lines pass through `redact` before they are written.
+
+use crate::config::AppConfig;
+use crate::paths;
+use crate::persistence;
+use crate::redact;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const AUDIT_FILE_NAME: &str = "audit.log";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    Command {
+        command: String,
+        /// Where it ran; missing in entries written before this was recorded.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<PathBuf>,
+        exit_code: Option<i32>,
+    },
+    FileWrite { path: PathBuf },
+    Network { url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub ts: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+struct Logger {
+    path: PathBuf,
+    session: Option<String>,
+}
+
+static GLOBAL: RwLock<Option<Logger>> = RwLock::new(None);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Where the audit log is kept.
+pub fn audit_file_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(AUDIT_FILE_NAME))
+}
+
+/// Start (or stop) recording according to `cfg`. The active session id is kept.
+pub fn init(cfg: &AppConfig) {
+    let mut global = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    let session = global.take().and_then(|l| l.session);
+    *global = match audit_file_path() {
+        Ok(path) if cfg.audit_enabled() && persistence::policy().keeps_history() => Some(Logger { path, session }),
+        _ => None,
+    };
+}
+
+/// Tag later entries with chat session `id`.
+pub fn set_session(id: &str) {
+    let mut global = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(logger) = global.as_mut() {
+        logger.session = Some(id.to_string());
+    }
+}
+
+pub fn record(event: AuditEvent) {
+    let global = GLOBAL.read().unwrap_or_else(|e| e.into_inner());
+    let Some(logger) = global.as_ref() else { return };
+
+    let entry = AuditEntry { ts: now(), session: logger.session.clone(), event };
+    if let Err(e) = append(&logger.path, &entry) {
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            eprintln!("Warning: audit log not written: {:#}", e);
+        }
+    }
+}
+
+pub fn command(command: &str, cwd: Option<&Path>, exit_code: Option<i32>) {
+    record(AuditEvent::Command { command: command.to_string(), cwd: cwd.map(Path::to_path_buf), exit_code });
+}
+
+pub fn file_write(path: &Path) {
+    record(AuditEvent::FileWrite { path: path.to_path_buf() });
+}
+
+pub fn network(url: &str) {
+    record(AuditEvent::Network { url: url.to_string() });
+}
+
+fn append(path: &Path, entry: &AuditEntry) -> Result<()> {
+    let line = serde_json::to_string(entry).context("failed to serialize audit entry")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", redact::redact(&line)).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The last `n` entries, oldest first. Lines that do not parse are skipped.
+pub fn tail(n: usize) -> Result<Vec<AuditEntry>> {
+    let path = audit_file_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let entries: Vec<AuditEntry> = text.lines().filter_map(|l| serde_json::from_str(l).ok()).collect();
+    Ok(entries[entries.len().saturating_sub(n)..].to_vec())
+}
+
+/// Delete the audit log; `false` when there was none.
+pub fn clear() -> Result<bool> {
+    crate::read_only::check("clear_audit")?;
+    let path = audit_file_path()?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    Ok(true)
+}
@@ -0,0 +1,298 @@
+//! What `aion export` bundles and `aion import` restores: AION's state, for moving to
+//! another machine.
+//!
+//! Notes:
+//! - Parts (`Part`): config.toml, templates.toml, the recent models (`recent`), locale
+//!   files in `locales/`, and, only when asked for, the saved sessions (`sessions/`, not
+//!   their index, which `sessions::index` rebuilds). Caches, logs, and the audit log stay.
+//! - An item's name is its path under the config dir, `/`-separated, or under the state
+//!   dir for the recent models and sessions (`crate::paths`); that is also its name in
+//!   the archive. `Part::of` is the only way back from a name, so an archive cannot write
+//!   outside those dirs or anything that is not AION state.
+//! - The config, templates, and sessions go through the redactor on the way out: a
+//!   secret in them arrives as `***REDACTED***`, to be filled in again.
+//! - `plan` checks everything before `Plan::apply` writes anything: the config must parse
+//!   and validate once upgraded (`config::migrate`; the upgraded file is what is written),
+//!   and no existing file is replaced without `force`.
+//! - The archive format is the caller's (the CLI writes a gzipped tar, `.tar.gz`).
+//!
+//! ```
+//! use aion_core::backup::{collect, plan, Home, Part};
+//! use aion_core::config::AppConfig;
+//! use aion_core::redact::Redactor;
+//!
+//! let root = std::env::temp_dir().join(format!("aion-backup-{}", std::process::id()));
+//! let old = Home::at(root.join("old"));
+//! std::fs::create_dir_all(old.dir.join("locales")).unwrap();
+//! std::fs::create_dir_all(old.dir.join("sessions")).unwrap();
+//! // A config from before `version` existed, upgraded on the way in.
+//! let mut config = toml::Table::try_from(AppConfig::new_default()).unwrap();
+//! config.remove("version");
+//! std::fs::write(&old.config_file, toml::to_string(&config).unwrap()).unwrap();
+//! std::fs::write(old.dir.join("templates.toml"), "[fetch]\nprompt = \"curl https://x.test/?token=abcdef123456\"\n").unwrap();
+//! std::fs::write(old.dir.join("recent_models.json"), "{\"ollama\": [\"mistral\"]}").unwrap();
+//! std::fs::write(old.dir.join("locales/pirate.toml"), "[meta]\nname = \"Pirate\"\n").unwrap();
+//! std::fs::write(old.dir.join("sessions/abc.json"), "{}").unwrap();
+//! std::fs::write(old.dir.join("sessions/index.json"), "[]").unwrap();
+//! std::fs::write(old.dir.join("audit.log"), "").unwrap();
+//!
+//! let items = collect(&old, Part::DEFAULT, &Redactor::default()).unwrap();
+//! let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+//! assert_eq!(names, ["config.toml", "templates.toml", "recent_models.json", "locales/pirate.toml"]);
+//! let items = collect(&old, &Part::ALL, &Redactor::default()).unwrap();
+//! assert_eq!(items.last().unwrap().name, "sessions/abc.json");
+//! assert!(String::from_utf8_lossy(&items[1].bytes).contains("***REDACTED***"));
+//!
+//! // Names that are not AION state, or leave the config dir, belong to no part.
+//! assert_eq!(Part::of("locales/pirate.toml"), Some(Part::Locales));
+//! for name in ["../config.toml", "locales/../../x.toml", "sessions/index.json", "audit.log", "/etc/passwd"] {
+//!     assert_eq!(Part::of(name), None, "{}", name);
+//! }
+//!
+//! // Round trip into an empty config dir.
+//! let new = Home::at(root.join("new"));
+//! let restored = plan(&new, items.clone(), false).unwrap();
+//! assert_eq!(restored.migrations, ["add the missing `version` field"]);
+//! restored.apply().unwrap();
+//! for item in &items[1..] {
+//!     assert_eq!(std::fs::read(new.path(&item.name)).unwrap(), item.bytes, "{}", item.name);
+//! }
+//! let config: toml::Table = toml::from_str(&std::fs::read_to_string(&new.config_file).unwrap()).unwrap();
+//! assert_eq!(config["version"].as_integer(), Some(AppConfig::CURRENT_VERSION.into()));
+//!
+//! // Again: every file is there already, so nothing is planned without force.
+//! let err = plan(&new, items.clone(), false).unwrap_err().to_string();
+//! assert!(err.contains("5 files already exist") && err.contains("--force"));
+//! assert!(plan(&new, items.clone(), true).unwrap().files.iter().all(|f| f.replaces));
+//!
+//! // A config that does not validate stops the import before anything is written.
+//! let mut bad = items[0].clone();
+//! bad.bytes = b"version = 1\nlanguage = 7\n".to_vec();
+//! assert!(plan(&Home::at(root.join("bad")), vec![bad], false).is_err());
+//! assert!(!root.join("bad").exists());
+//! std::fs::remove_dir_all(&root).unwrap();
+//! ```
+
+use crate::config::io::config_file_path;
+use crate::config::{migrate, AppConfig};
+use crate::paths;
+use crate::redact::Redactor;
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A kind of state that is exported and imported as a whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Part {
+    Config,
+    Templates,
+    /// Recently used models.
+    Models,
+    Locales,
+    Sessions,
+}
+
+impl Part {
+    pub const ALL: [Part; 5] = [Part::Config, Part::Templates, Part::Models, Part::Locales, Part::Sessions];
+    /// Exported unless sessions are asked for.
+    pub const DEFAULT: &'static [Part] = &[Part::Config, Part::Templates, Part::Models, Part::Locales];
+
+    /// The part the item `name` belongs to; `None` when it is none of AION's.
+    pub fn of(name: &str) -> Option<Part> {
+        match name {
+            CONFIG => return Some(Part::Config),
+            TEMPLATES => return Some(Part::Templates),
+            MODELS => return Some(Part::Models),
+            _ => {}
+        }
+        let (dir, file) = name.split_once('/')?;
+        let plain = !file.is_empty() && !file.starts_with('.') && !file.contains(['/', '\\']);
+        match (dir, Path::new(file).extension().and_then(|e| e.to_str())) {
+            (LOCALES, Some("toml")) if plain => Some(Part::Locales),
+            (SESSIONS, Some("json" | "jsonl")) if plain && file != SESSION_INDEX => Some(Part::Sessions),
+            _ => None,
+        }
+    }
+
+    /// Whether the part goes through the redactor on export.
+    fn redacted(self) -> bool {
+        matches!(self, Part::Config | Part::Templates | Part::Sessions)
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Part::Config => "config",
+            Part::Templates => "templates",
+            Part::Models => "models",
+            Part::Locales => "locales",
+            Part::Sessions => "sessions",
+        })
+    }
+}
+
+// The names the owning modules use; templates.toml is the CLI's.
+const CONFIG: &str = "config.toml";
+const TEMPLATES: &str = "templates.toml";
+const MODELS: &str = "recent_models.json";
+const LOCALES: &str = "locales";
+const SESSIONS: &str = "sessions";
+const SESSION_INDEX: &str = "index.json";
+
+/// Where the state lives: the config dir, the config file (which `--config` can put
+/// elsewhere), and the state dir.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Home {
+    pub dir: PathBuf,
+    pub config_file: PathBuf,
+    pub state: PathBuf,
+}
+
+impl Home {
+    /// This process's.
+    pub fn current() -> Result<Self> {
+        Ok(Self { dir: paths::config_dir()?, config_file: config_file_path()?, state: paths::state_dir()? })
+    }
+
+    /// A config dir with its config file and state in it.
+    pub fn at(dir: PathBuf) -> Self {
+        let config_file = dir.join(CONFIG);
+        Self { state: dir.clone(), dir, config_file }
+    }
+
+    /// Where the item `name` lives.
+    pub fn path(&self, name: &str) -> PathBuf {
+        let root = match Part::of(name) {
+            Some(Part::Config) => return self.config_file.clone(),
+            Some(Part::Models | Part::Sessions) => &self.state,
+            _ => &self.dir,
+        };
+        name.split('/').fold(root.clone(), |path, part| path.join(part))
+    }
+}
+
+/// One file of the state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub part: Part,
+    /// See the module notes.
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// The items of `parts` that exist under `home`, in `Part` order, redacted.
+pub fn collect(home: &Home, parts: &[Part], redactor: &Redactor) -> Result<Vec<Item>> {
+    let mut names = Vec::new();
+    for part in Part::ALL.into_iter().filter(|p| parts.contains(p)) {
+        match part {
+            Part::Config => names.push(CONFIG.to_string()),
+            Part::Templates => names.push(TEMPLATES.to_string()),
+            Part::Models => names.push(MODELS.to_string()),
+            Part::Locales => names.extend(listed(&home.dir.join(LOCALES), LOCALES)?),
+            Part::Sessions => names.extend(listed(&home.state.join(SESSIONS), SESSIONS)?),
+        }
+    }
+    let mut items = Vec::new();
+    for name in names {
+        let Some(part) = Part::of(&name) else { continue };
+        let path = home.path(&name);
+        if !path.is_file() {
+            continue;
+        }
+        let bytes = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let bytes = match (part.redacted(), String::from_utf8(bytes)) {
+            (true, Ok(text)) => redactor.redact(&text).into_bytes(),
+            (_, Ok(text)) => text.into_bytes(),
+            (_, Err(e)) => e.into_bytes(),
+        };
+        items.push(Item { part, name, bytes });
+    }
+    Ok(items)
+}
+
+/// `dir/<file>` names of the files in `dir`, sorted; none when it does not exist.
+fn listed(path: &Path, dir: &str) -> Result<Vec<String>> {
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut names: Vec<String> =
+        entries.flatten().map(|e| format!("{}/{}", dir, e.file_name().to_string_lossy())).collect();
+    names.sort();
+    Ok(names)
+}
+
+/// A file `Plan::apply` writes.
+#[derive(Debug, Clone)]
+pub struct Planned {
+    pub path: PathBuf,
+    pub item: Item,
+    /// A file is there already (only planned with `force`).
+    pub replaces: bool,
+}
+
+/// An import, checked.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub files: Vec<Planned>,
+    /// The config migrations applied on the way in.
+    pub migrations: Vec<&'static str>,
+}
+
+/// Check that `items` can go into `home` (see the module notes). Items that belong to no
+/// part are an error, so a caller that skips unknown archive entries must do so first.
+pub fn plan(home: &Home, items: Vec<Item>, force: bool) -> Result<Plan> {
+    let mut files = Vec::new();
+    let mut migrations = Vec::new();
+    for mut item in items {
+        if Part::of(&item.name) != Some(item.part) {
+            bail!("{} is not part of AION's state", item.name);
+        }
+        if item.part == Part::Config {
+            let (bytes, applied) = upgraded_config(&item.bytes)?;
+            item.bytes = bytes;
+            migrations = applied;
+        }
+        let path = home.path(&item.name);
+        files.push(Planned { replaces: path.exists(), path, item });
+    }
+    let existing: Vec<String> = files.iter().filter(|f| f.replaces).map(|f| f.path.display().to_string()).collect();
+    if !force && !existing.is_empty() {
+        bail!(
+            "{} files already exist (use --force to replace them):\n  {}",
+            existing.len(),
+            existing.join("\n  ")
+        );
+    }
+    Ok(Plan { files, migrations })
+}
+
+/// The config in `bytes`, upgraded to the current version, if it validates.
+fn upgraded_config(bytes: &[u8]) -> Result<(Vec<u8>, Vec<&'static str>)> {
+    let text = std::str::from_utf8(bytes).context("the config is not UTF-8")?;
+    let mut table: toml::Table = toml::from_str(text).context("failed to parse the config")?;
+    let applied = migrate::upgrade(&mut table).context("failed to upgrade the config")?;
+    let config: AppConfig = toml::Value::Table(table.clone()).try_into().context("failed to parse the config")?;
+    config.validate().context("the config does not validate")?;
+    let bytes = match applied.is_empty() {
+        true => bytes.to_vec(),
+        false => toml::to_string(&table)?.into_bytes(),
+    };
+    Ok((bytes, applied))
+}
+
+impl Plan {
+    /// Write the files, creating directories as needed.
+    pub fn apply(&self) -> Result<()> {
+        for file in &self.files {
+            if let Some(parent) = file.path.parent() {
+                fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+            }
+            fs::write(&file.path, &file.item.bytes).with_context(|| format!("failed to write {}", file.path.display()))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Statistics for `aion bench`: one model's timed runs reduced to medians and 95th
+//! percentiles.
+//!
+//! Notes:
+//! - Percentiles interpolate between the two nearest ranks, so the median of an even count
+//!   is the mean of the middle two and a single run is every percentile.
+//! - Tokens per second are output tokens over the time after the first token, the rate
+//!   the reply is generated at; without a first-token time, over the whole request. Runs
+//!   whose provider reported no usage have no rate and no cost.
+//! - Prices come from the caller (`Price`, per million tokens); nothing here knows them.
+//! - Failed runs (errors, timeouts) are only counted.
+//!
+//! ```
+//! use aion_core::bench::{percentile, summarize, Price, Run};
+//! use aion_core::providers::Usage;
+//! use std::time::Duration;
+//!
+//! assert_eq!(percentile(&[4.0, 1.0, 3.0, 2.0], 50.0), Some(2.5));
+//! assert_eq!(percentile(&[7.0], 95.0), Some(7.0));
+//! assert_eq!(percentile(&[], 50.0), None);
+//! let hundred: Vec<f64> = (1..=100).map(f64::from).collect();
+//! assert!((percentile(&hundred, 95.0).unwrap() - 95.05).abs() < 1e-9);
+//!
+//! let ms = Duration::from_millis;
+//! let usage = |output_tokens| Some(Usage { input_tokens: 10, output_tokens });
+//! let runs = [
+//!     Run { first_token: Some(ms(100)), total: ms(1_100), usage: usage(50) },
+//!     Run { first_token: Some(ms(300)), total: ms(2_300), usage: usage(40) },
+//!     Run { first_token: None, total: ms(500), usage: None },
+//! ];
+//! let price = "1,2".parse::<Price>().unwrap();
+//! let stats = summarize(&runs, 1, Some(price));
+//!
+//! assert_eq!((stats.runs, stats.failed), (3, 1));
+//! assert_eq!(stats.first_token_ms.unwrap().median, 200.0);
+//! assert_eq!(stats.total_ms.unwrap().median, 1_100.0);
+//! // 50 tokens in 1 s after the first, 40 in 2 s.
+//! assert_eq!(stats.tokens_per_sec.unwrap().median, 35.0);
+//! assert_eq!(stats.usage, Usage { input_tokens: 20, output_tokens: 90 });
+//! // (10 × 1 + 45 × 2) / 1M, averaged over the two runs with usage.
+//! assert!((stats.cost_per_run.unwrap() - 0.0001).abs() < 1e-12);
+//!
+//! let none = summarize(&[], 2, None);
+//! assert_eq!((none.runs, none.total_ms, none.cost_per_run), (0, None, None));
+//! assert!("1".parse::<Price>().is_err() && "-1,2".parse::<Price>().is_err());
+//! ```
+
+use crate::providers::Usage;
+use serde::Serialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// One completed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    /// Until the first chunk of the reply; `None` when none arrived before the end.
+    pub first_token: Option<Duration>,
+    pub total: Duration,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Spread {
+    pub median: f64,
+    pub p95: f64,
+}
+
+/// US dollars per million input and output tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl Price {
+    pub fn cost(&self, usage: Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input + usage.output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// `IN,OUT`, e.g. `0.15,0.60`.
+impl FromStr for Price {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (input, output) = s.split_once(',').ok_or("expected IN,OUT (dollars per million tokens)")?;
+        let parse = |v: &str| match v.trim().parse::<f64>() {
+            Ok(n) if n.is_finite() && n >= 0.0 => Ok(n),
+            _ => Err(format!("'{}' is not a price", v.trim())),
+        };
+        Ok(Price { input: parse(input)?, output: parse(output)? })
+    }
+}
+
+/// One model's runs, summarized. Times are in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    /// Completed runs.
+    pub runs: usize,
+    pub failed: usize,
+    pub first_token_ms: Option<Spread>,
+    pub total_ms: Option<Spread>,
+    pub tokens_per_sec: Option<Spread>,
+    /// Summed over the runs that reported usage.
+    pub usage: Usage,
+    /// Mean over the runs that reported usage, when priced.
+    pub cost_per_run: Option<f64>,
+}
+
+/// The `p`th percentile (0 to 100) of `values`, in any order.
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p.clamp(0.0, 100.0) / 100.0 * last as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64))
+}
+
+pub fn spread(values: &[f64]) -> Option<Spread> {
+    Some(Spread { median: percentile(values, 50.0)?, p95: percentile(values, 95.0)? })
+}
+
+pub fn summarize(runs: &[Run], failed: usize, price: Option<Price>) -> Stats {
+    let ms = |d: Duration| d.as_secs_f64() * 1_000.0;
+    let first_token: Vec<f64> = runs.iter().filter_map(|r| r.first_token).map(ms).collect();
+    let total: Vec<f64> = runs.iter().map(|r| ms(r.total)).collect();
+    let rates: Vec<f64> = runs
+        .iter()
+        .filter_map(|r| {
+            let generating = r.first_token.map_or(r.total, |t| r.total.saturating_sub(t));
+            let secs = generating.as_secs_f64();
+            (secs > 0.0).then(|| r.usage.map(|u| u.output_tokens as f64 / secs)).flatten()
+        })
+        .collect();
+    let usages: Vec<Usage> = runs.iter().filter_map(|r| r.usage).collect();
+    let usage = usages.iter().fold(Usage::default(), |sum, u| Usage {
+        input_tokens: sum.input_tokens + u.input_tokens,
+        output_tokens: sum.output_tokens + u.output_tokens,
+    });
+    let cost_per_run = price.filter(|_| !usages.is_empty()).map(|p| p.cost(usage) / usages.len() as f64);
+
+    Stats {
+        runs: runs.len(),
+        failed,
+        first_token_ms: spread(&first_token),
+        total_ms: spread(&total),
+        tokens_per_sec: spread(&rates),
+        usage,
+        cost_per_run,
+    }
+}
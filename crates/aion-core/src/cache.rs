@@ -0,0 +1,269 @@
+//! Cached replies for repeated identical requests (`[cache]` in config.toml).
+//!
+//! Notes:
+//! - Opt-in: nothing is read or stored unless `cache.enabled`.
+//! - The key is a 128-bit FNV-1a hash of the provider kind, endpoint, model, configured
+//!   `params` (only when set), and the full message list (which carries the system
+//!   prompt). FNV rather than `std`'s hasher, because the key has to stay the same
+//!   across builds and Rust versions.
+//! - Entries are `<key>.json` files under `cache.dir` (default `responses/` in the cache
+//!   dir, `crate::paths`) and expire `ttl_secs` after they were stored. Expired entries are
+//!   deleted when they are next looked up, or by `clear`.
+//! - Without `caps.write_files`, or in an ephemeral process (see `crate::persistence`),
+//!   entries are kept in memory for the process only.
+//! - Stored replies pass through `redact`, like session files.
+//!
+//! ```
+//! use aion_core::cache::{self, ResponseCache};
+//! use aion_core::config::{AppConfig, GenParams};
+//! use aion_core::providers::{ChatResponse, Message};
+//! use std::fs;
+//!
+//! let mut cfg = AppConfig::new_default();
+//! let messages = [Message::system("be brief"), Message::user("hi")];
+//!
+//! // The same request has the same key in every build; anything sent changes it.
+//! let key = cache::key(&cfg.provider, &messages);
+//! assert_eq!(key, "144a636a7c981337dc6efb891afa4868");
+//! let mut other = cfg.clone();
+//! other.language = "ar".into();
+//! other.provider.api_key_env = Some("OTHER_KEY".into());
+//! assert_eq!(cache::key(&other.provider, &messages), key);
+//! other.provider.model = "llama3".into();
+//! assert_ne!(cache::key(&other.provider, &messages), key);
+//! assert_ne!(cache::key(&cfg.provider, &[Message::system("be brief"), Message::user("hi!")]), key);
+//! assert_ne!(cache::key(&cfg.provider, &messages[1..]), key);
+//! let mut tuned = cfg.clone();
+//! tuned.provider.params = GenParams { temperature: Some(0.2), ..Default::default() };
+//! assert_ne!(cache::key(&tuned.provider, &messages), key);
+//!
+//! // On disk: an entry is served until `ttl_secs` after it was stored, then deleted.
+//! let dir = std::env::temp_dir().join(format!("aion-cache-{}", std::process::id()));
+//! cfg.cache = aion_core::config::CacheConfig { enabled: true, ttl_secs: 3600, dir: Some(dir.clone()) };
+//! cfg.caps.write_files = true;
+//! let reply = ChatResponse { content: "hello".into(), ..Default::default() };
+//! let cache = ResponseCache::open(&cfg).unwrap().unwrap();
+//! cache.put(&key, &reply).unwrap();
+//! assert_eq!(cache.get(&key).unwrap().content, "hello");
+//! assert!(cache.get(&cache::key(&other.provider, &messages)).is_none());
+//!
+//! let path = dir.join(format!("{}.json", key));
+//! let stored = fs::read_to_string(&path).unwrap();
+//! let stored_at: u64 = serde_json::from_str::<serde_json::Value>(&stored).unwrap()["stored_at"].as_u64().unwrap();
+//! let aged = |secs: u64| stored.replace(&format!("\"stored_at\": {}", stored_at), &format!("\"stored_at\": {}", stored_at - secs));
+//! fs::write(&path, aged(3590)).unwrap();
+//! assert!(cache.get(&key).is_some());
+//! fs::write(&path, aged(3600)).unwrap();
+//! let stats = cache::stats(&cfg).unwrap();
+//! assert_eq!((stats.entries, stats.expired), (1, 1));
+//! assert!(cache.get(&key).is_none());
+//! assert!(!path.exists());
+//!
+//! // In memory (no `caps.write_files`): the same rule, and nothing on disk.
+//! cfg.caps.write_files = false;
+//! let cache = ResponseCache::open(&cfg).unwrap().unwrap();
+//! cache.put(&key, &reply).unwrap();
+//! assert_eq!(cache.get(&key).unwrap().content, "hello");
+//! assert!(!path.exists());
+//! cfg.cache.ttl_secs = 0;
+//! let cache = ResponseCache::open(&cfg).unwrap().unwrap();
+//! cache.put(&key, &reply).unwrap();
+//! assert!(cache.get(&key).is_none());
+//!
+//! cfg.cache.enabled = false;
+//! assert!(ResponseCache::open(&cfg).unwrap().is_none());
+//! fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use crate::audit;
+use crate::config::{AppConfig, ProviderConfig};
+use crate::paths;
+use crate::persistence;
+use crate::providers::{ChatResponse, Message};
+use crate::redact;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CACHE_DIR_NAME: &str = "responses";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    stored_at: u64,
+    response: ChatResponse,
+}
+
+/// An open cache; `None` from `open` when caching is off.
+#[derive(Debug)]
+pub struct ResponseCache {
+    /// `None` keeps entries in memory (`caps.write_files` is off, or ephemeral).
+    dir: Option<PathBuf>,
+    ttl_secs: u64,
+    memory: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub dir: PathBuf,
+    pub entries: usize,
+    pub expired: usize,
+    pub bytes: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The directory entries are stored in for `cfg`.
+pub fn cache_dir(cfg: &AppConfig) -> Result<PathBuf> {
+    match &cfg.cache.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(paths::cache_dir()?.join(CACHE_DIR_NAME)),
+    }
+}
+
+/// Cache key for sending `messages` with `provider`.
+pub fn key(provider: &ProviderConfig, messages: &[Message]) -> String {
+    let mut material = serde_json::json!({
+        "provider": provider.kind,
+        "base_url": provider.effective_base_url(),
+        "model": provider.model,
+        "messages": messages,
+    });
+    // Only when set, so keys stored before `provider.params` existed still match.
+    if !provider.params.is_empty() {
+        material["params"] = serde_json::json!(provider.params);
+    }
+    if !provider.extra_options.is_empty() {
+        material["extra_options"] = serde_json::json!(crate::config::extra::to_json(&provider.extra_options));
+    }
+    format!("{:032x}", fnv1a_128(material.to_string().as_bytes()))
+}
+
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    bytes.iter().fold(OFFSET, |hash, b| (hash ^ u128::from(*b)).wrapping_mul(PRIME))
+}
+
+impl ResponseCache {
+    /// The cache for `cfg`, or `None` when `cache.enabled` is off.
+    pub fn open(cfg: &AppConfig) -> Result<Option<Self>> {
+        if !cfg.cache.enabled {
+            return Ok(None);
+        }
+        let keep = cfg.caps.write_files && persistence::policy().keeps_history();
+        let dir = if keep { Some(cache_dir(cfg)?) } else { None };
+        Ok(Some(Self { dir, ttl_secs: cfg.cache.ttl_secs, memory: Mutex::new(HashMap::new()) }))
+    }
+
+    fn expired(&self, entry: &Entry) -> bool {
+        now().saturating_sub(entry.stored_at) >= self.ttl_secs
+    }
+
+    /// The stored reply for `key`, unless it is missing or expired.
+    pub fn get(&self, key: &str) -> Option<ChatResponse> {
+        let entry = match &self.dir {
+            Some(dir) => {
+                let path = entry_path(dir, key);
+                let entry = read_entry(&path)?;
+                if self.expired(&entry) {
+                    let _ = fs::remove_file(&path);
+                    return None;
+                }
+                entry
+            }
+            None => {
+                let memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+                memory.get(key).filter(|e| !self.expired(e)).cloned()?
+            }
+        };
+        Some(entry.response)
+    }
+
+    pub fn put(&self, key: &str, response: &ChatResponse) -> Result<()> {
+        let entry = Entry {
+            stored_at: now(),
+            response: ChatResponse {
+                content: redact::redact(&response.content),
+                usage: response.usage,
+                fallback: None,
+                stop: response.stop,
+            },
+        };
+        let Some(dir) = &self.dir else {
+            let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+            memory.insert(key.to_string(), entry);
+            return Ok(());
+        };
+
+        fs::create_dir_all(dir).with_context(|| format!("failed to create cache directory: {}", dir.display()))?;
+        let path = entry_path(dir, key);
+        let text = serde_json::to_string_pretty(&entry).context("failed to serialize cache entry")?;
+        fs::write(&path, text).with_context(|| format!("failed to write cache entry: {}", path.display()))?;
+        audit::file_write(&path);
+        Ok(())
+    }
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+fn read_entry(path: &Path) -> Option<Entry> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Cache files in `dir` (missing directory: none).
+fn entry_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Entry counts and size of the on-disk cache for `cfg`.
+pub fn stats(cfg: &AppConfig) -> Result<CacheStats> {
+    let dir = cache_dir(cfg)?;
+    let mut stats = CacheStats { dir: dir.clone(), entries: 0, expired: 0, bytes: 0 };
+    for path in entry_files(&dir)? {
+        stats.entries += 1;
+        stats.bytes += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let expired = read_entry(&path).is_none_or(|e| now().saturating_sub(e.stored_at) >= cfg.cache.ttl_secs);
+        if expired {
+            stats.expired += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// The files `clear` removes.
+pub fn entries(cfg: &AppConfig) -> Result<Vec<PathBuf>> {
+    entry_files(&cache_dir(cfg)?)
+}
+
+/// Delete every cache entry; returns how many were removed.
+pub fn clear(cfg: &AppConfig) -> Result<usize> {
+    crate::read_only::check("clear_cache")?;
+    let files = entries(cfg)?;
+    for path in &files {
+        fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    Ok(files.len())
+}
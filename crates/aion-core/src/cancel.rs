@@ -0,0 +1,104 @@
+//! Cancellation tokens.
+//!
+//! Notes:
+//! - A `CancelToken` is shared between the caller and the work it starts (provider
+//!   requests, command execution). Work checks it between steps or awaits `cancelled()`.
+//! - Wiring tokens to Ctrl+C is up to the application (the `aion` binary's `cancel`).
+//!
+//! ```
+//! use aion_core::cancel::CancelToken;
+//!
+//! let token = CancelToken::new();
+//! let shared = token.clone();
+//! assert!(!shared.is_cancelled());
+//!
+//! let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! rt.block_on(async {
+//!     // A waiter started before the cancel wakes up; one started after returns at once.
+//!     let waiter = tokio::spawn(async move { shared.cancelled().await });
+//!     tokio::task::yield_now().await;
+//!     token.cancel();
+//!     waiter.await.unwrap();
+//!     token.cancelled().await;
+//! });
+//! assert!(token.is_cancelled());
+//! ```
+//!
+//! A cancel reaches a request in flight: a scripted reply that would stream for minutes
+//! stops with `ProviderError::Cancelled` as soon as the token is cancelled.
+//!
+//! ```
+//! # #[cfg(all(feature = "providers", debug_assertions))]
+//! # {
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::providers::mock::ScriptedClient;
+//! use aion_core::providers::{ChatClient, Message, ProviderError};
+//! use std::time::{Duration, Instant};
+//!
+//! let script = std::env::temp_dir().join(format!("aion-cancel-{}.json", std::process::id()));
+//! std::fs::write(&script, r#"{"steps":[{"chunks":["one ","two ","three"],"delay_ms":60000}]}"#).unwrap();
+//! let client = ScriptedClient::load(&script).unwrap();
+//!
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! let (result, received, took) = rt.block_on(async {
+//!     let token = CancelToken::new();
+//!     let canceller = token.clone();
+//!     tokio::spawn(async move {
+//!         tokio::time::sleep(Duration::from_millis(50)).await;
+//!         canceller.cancel();
+//!     });
+//!     let mut received = Vec::new();
+//!     let begun = Instant::now();
+//!     let result = client.chat(&[Message::user("hi")], &token, &mut |c| received.push(c.to_string())).await;
+//!     (result, received, begun.elapsed())
+//! });
+//!
+//! let err = result.unwrap_err();
+//! assert!(matches!(err.downcast_ref::<ProviderError>(), Some(ProviderError::Cancelled)), "{}", err);
+//! assert!(received.is_empty());
+//! assert!(took < Duration::from_secs(5), "{:?}", took);
+//! # std::fs::remove_file(&script).unwrap();
+//! # }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled. Intended for `tokio::select!`.
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a cancel between the two isn't missed.
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
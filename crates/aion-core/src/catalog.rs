@@ -0,0 +1,223 @@
+//! Well-known model names per provider, for catching typos in a model name before the
+//! first request does ("gpt-4o-mimi": did you mean gpt-4o-mini?).
+//!
+//! Notes:
+//! - Built in: a short list per provider (`BUILTIN`). Whenever a provider's models are
+//!   listed anyway (`aion models`, the wizard's Ollama step), the list is kept in
+//!   `model_catalog.json` in the cache dir (`store`) and used with the built-in one.
+//!   Nothing is fetched just for the catalog.
+//! - Advice only: an unknown name is still accepted, since new models appear all the
+//!   time. `suggest` has something to say only when a known name is within a small edit
+//!   distance (`distance`, case-insensitive), scaled to the length of the name.
+//! - A tag (`llama3:8b`, `meta-llama/llama-3.1-8b-instruct:free`) is kept: the name before
+//!   it is what is compared, and the suggestion carries the same tag.
+//! - Used by the wizard's model hint, `aion config set provider.model`, and `/model`.
+//!
+//! ```
+//! use aion_core::catalog::{distance, Catalog};
+//! use aion_core::config::ProviderKind;
+//!
+//! assert_eq!(distance("lama3", "llama3"), 1);
+//! assert_eq!(distance("kitten", "sitting"), 3);
+//!
+//! let catalog = Catalog::builtin();
+//! let typos = [
+//!     (ProviderKind::OpenAI, "gpt-4o-mimi", "gpt-4o-mini"),
+//!     (ProviderKind::OpenAI, "gpt4o", "gpt-4o"),
+//!     (ProviderKind::OpenAI, "GPT-4.1-Mini", "gpt-4.1-mini"),
+//!     (ProviderKind::Ollama, "lama3", "llama3"),
+//!     (ProviderKind::Ollama, "mistrl", "mistral"),
+//!     (ProviderKind::Ollama, "qwen2.5-codr:7b", "qwen2.5-coder:7b"),
+//!     (ProviderKind::Claude, "claude-3-5-sonet-latest", "claude-3-5-sonnet-latest"),
+//!     (ProviderKind::OpenRouter, "openai/gpt-4o-mni", "openai/gpt-4o-mini"),
+//! ];
+//! for (kind, typo, meant) in typos {
+//!     assert_eq!(catalog.suggest(&kind, typo).as_deref(), Some(meant), "{}", typo);
+//! }
+//!
+//! // Known names, tagged ones, and names nothing is close to: nothing to say.
+//! for (kind, name) in [(ProviderKind::OpenAI, "gpt-4o"), (ProviderKind::Ollama, "llama3.1:70b"), (ProviderKind::Ollama, "my-finetune")] {
+//!     assert_eq!(catalog.suggest(&kind, name), None, "{}", name);
+//! }
+//! // Short names need to be closer.
+//! assert_eq!(catalog.suggest(&ProviderKind::OpenAI, "o9x"), None);
+//!
+//! // Names listed by the provider count as known.
+//! let catalog = catalog.with(&ProviderKind::Ollama, &["lama3-custom".to_string()]);
+//! assert_eq!(catalog.suggest(&ProviderKind::Ollama, "lama3-custom"), None);
+//! ```
+
+use crate::config::ProviderKind;
+use crate::paths;
+use crate::read_only;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const CATALOG_FILE_NAME: &str = "model_catalog.json";
+
+/// Names kept per provider from a listing; Ollama libraries and OpenRouter are large.
+const MAX_STORED: usize = 500;
+
+/// Well-known model names, by provider.
+pub const BUILTIN: &[(ProviderKind, &[&str])] = &[
+    (
+        ProviderKind::OpenAI,
+        &[
+            "gpt-4o", "gpt-4o-mini", "gpt-4.1", "gpt-4.1-mini", "gpt-4.1-nano", "gpt-4-turbo", "gpt-3.5-turbo", "o1",
+            "o1-mini", "o3", "o3-mini", "o4-mini",
+        ],
+    ),
+    (
+        ProviderKind::Claude,
+        &[
+            "claude-3-5-sonnet-latest", "claude-3-5-haiku-latest", "claude-3-7-sonnet-latest", "claude-3-opus-latest",
+            "claude-3-5-sonnet-20241022", "claude-3-5-haiku-20241022", "claude-3-7-sonnet-20250219",
+            "claude-3-opus-20240229", "claude-3-haiku-20240307", "claude-sonnet-4-20250514", "claude-opus-4-20250514",
+        ],
+    ),
+    (
+        ProviderKind::OpenRouter,
+        &[
+            "openai/gpt-4o", "openai/gpt-4o-mini", "openai/gpt-4.1-mini", "anthropic/claude-3.5-sonnet",
+            "anthropic/claude-3.5-haiku", "google/gemini-flash-1.5", "google/gemini-pro-1.5",
+            "meta-llama/llama-3.1-8b-instruct", "meta-llama/llama-3.1-70b-instruct", "mistralai/mistral-7b-instruct",
+            "mistralai/mixtral-8x7b-instruct", "deepseek/deepseek-chat", "qwen/qwen-2.5-72b-instruct",
+        ],
+    ),
+    (
+        ProviderKind::Ollama,
+        &[
+            "llama3", "llama3.1", "llama3.2", "llama3.3", "mistral", "mixtral", "qwen2.5", "qwen2.5-coder", "phi3",
+            "phi4", "gemma2", "gemma3", "codellama", "deepseek-r1", "deepseek-coder-v2", "llava", "tinyllama",
+            "starcoder2", "nomic-embed-text",
+        ],
+    ),
+];
+
+/// The names known per provider: built in, and any the provider listed.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    by_provider: BTreeMap<String, Vec<String>>,
+}
+
+impl Catalog {
+    /// Only `BUILTIN`.
+    pub fn builtin() -> Self {
+        let mut catalog = Self::default();
+        for (kind, names) in BUILTIN {
+            catalog.by_provider.insert(kind.id().to_string(), names.iter().map(|n| n.to_string()).collect());
+        }
+        catalog
+    }
+
+    /// `BUILTIN` and the stored listings. An unreadable file counts as none.
+    pub fn load() -> Self {
+        let stored: BTreeMap<String, Vec<String>> = catalog_file_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        let mut catalog = Self::builtin();
+        for (id, names) in stored {
+            catalog.by_provider.entry(id).or_default().extend(names);
+        }
+        catalog
+    }
+
+    /// This catalog with `names` known for `kind` too.
+    pub fn with(mut self, kind: &ProviderKind, names: &[String]) -> Self {
+        self.by_provider.entry(kind.id().to_string()).or_default().extend(names.iter().cloned());
+        self
+    }
+
+    /// The names known for `kind`.
+    pub fn names(&self, kind: &ProviderKind) -> &[String] {
+        self.by_provider.get(kind.id()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The known name `input` is probably a typo of; `None` when it is known, or when
+    /// nothing known is close.
+    pub fn suggest(&self, kind: &ProviderKind, input: &str) -> Option<String> {
+        let input = input.trim();
+        let (base, tag) = split_tag(input);
+        if base.is_empty() {
+            return None;
+        }
+        let names = self.names(kind);
+        if names.iter().any(|n| n == input || split_tag(n).0 == base) {
+            return None;
+        }
+        let base = base.to_lowercase();
+        let allowed = (base.chars().count() / 4).clamp(1, 2);
+        let (best, d) = names
+            .iter()
+            .map(|n| (n, distance(&base, &split_tag(n).0.to_lowercase())))
+            .min_by_key(|(_, d)| *d)?;
+        if d > allowed {
+            return None;
+        }
+        let best = split_tag(best).0;
+        Some(match tag {
+            Some(tag) => format!("{}:{}", best, tag),
+            None => best.to_string(),
+        })
+    }
+
+    /// The advice for `input`, when there is any.
+    pub fn notice(&self, kind: &ProviderKind, input: &str) -> Option<String> {
+        self.suggest(kind, input)
+            .map(|meant| format!("unknown {} model \"{}\"; did you mean {}? (kept as typed)", kind, input.trim(), meant))
+    }
+}
+
+/// `name` and its tag (after the last `:`), if it has one.
+fn split_tag(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once(':') {
+        Some((base, tag)) if !base.is_empty() => (base, Some(tag)),
+        _ => (name, None),
+    }
+}
+
+/// `Catalog::load().suggest(kind, input)`.
+pub fn suggest(kind: &ProviderKind, input: &str) -> Option<String> {
+    Catalog::load().suggest(kind, input)
+}
+
+pub fn catalog_file_path() -> Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(CATALOG_FILE_NAME))
+}
+
+/// Keep `names`, as the provider just listed them, as `kind`'s. Not in read-only mode.
+pub fn store(kind: &ProviderKind, names: &[String]) -> Result<()> {
+    if read_only::active().is_some() || names.is_empty() {
+        return Ok(());
+    }
+    let path = catalog_file_path()?;
+    let mut stored: BTreeMap<String, Vec<String>> =
+        fs::read_to_string(&path).ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or_default();
+    let kept: Vec<String> = names.iter().take(MAX_STORED).cloned().collect();
+    if stored.get(kind.id()) == Some(&kept) {
+        return Ok(());
+    }
+    stored.insert(kind.id().to_string(), kept);
+    paths::ensure(&paths::cache_dir()?)?;
+    let text = serde_json::to_string_pretty(&stored).context("failed to serialize the model catalog")?;
+    fs::write(&path, text + "\n").with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Edits (insertions, deletions, substitutions) between `a` and `b`.
+pub fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
@@ -0,0 +1,402 @@
+//! Slash commands: the `Command` trait and the registry both chat front ends dispatch through.
+//!
+//! Notes:
+//! - A command is a value implementing `Command<C>`, where `C` is whatever the front end
+//!   hands it to act on (the `aion` binary's `SessionContext`; a mock in tests). Commands
+//!   are registered one by one with `Registry::register`; the registry is the single
+//!   source for dispatch, `/help`, Tab completion, and "did you mean" suggestions.
+//! - The `ArgSpec` drives parsing and the usage line: `/save <path> [--all]` is built from
+//!   the argument placeholder, the flags, and the options, and every parse error for that
+//!   command reads `usage: /save <path> [--all]`.
+//! - Parsing checks the argument count, flags, and options; the command checks values
+//!   (a provider name, a number) when it runs and reports them as `InvalidValue`.
+//! - `/help` lines are localized: `commands.help.<name>` in the locale, the command's
+//!   `summary` otherwise. Aliases dispatch like the name but are not listed or completed.
+//!
+//! ```
+//! use aion_core::commands::{ArgSpec, ArgValues, Args, Command, CommandError, CompletionData, Registry};
+//! use async_trait::async_trait;
+//!
+//! #[derive(Default)]
+//! struct Notes(Vec<String>);
+//!
+//! struct Save;
+//!
+//! #[async_trait(?Send)]
+//! impl Command<Notes> for Save {
+//!     fn name(&self) -> &'static str { "save" }
+//!     fn aliases(&self) -> &'static [&'static str] { &["write"] }
+//!     fn summary(&self) -> &'static str { "Write the last reply to a file" }
+//!     fn spec(&self) -> ArgSpec {
+//!         ArgSpec { arg: "<path>", values: ArgValues::Free, flags: &["--all"], ..ArgSpec::NONE }
+//!     }
+//!     async fn execute(&self, args: Args, notes: &mut Notes) -> Result<(), CommandError> {
+//!         notes.0.push(format!("{} all={}", args.arg.as_deref().unwrap_or_default(), args.flag("--all")));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let registry = Registry::new().register(Save);
+//! assert_eq!(registry.find("write").unwrap().usage(), "/save <path> [--all]");
+//!
+//! let run = |input: &str, notes: &mut Notes| {
+//!     let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//!     rt.block_on(registry.dispatch(input, notes))
+//! };
+//! let mut notes = Notes::default();
+//! run("/save out.md --all", &mut notes).unwrap();
+//! run("/write out.md", &mut notes).unwrap();
+//! assert_eq!(notes.0, ["out.md all=true", "out.md all=false"]);
+//!
+//! let err = |input: &str| run(input, &mut Notes::default()).unwrap_err().to_string();
+//! assert_eq!(err("/save"), "usage: /save <path> [--all]");
+//! assert_eq!(err("/save a b"), "usage: /save <path> [--all]");
+//! assert_eq!(err("/sav x"), "unknown command /sav (did you mean /save?)");
+//! assert_eq!(err("/frobnicate"), "unknown command /frobnicate");
+//!
+//! assert_eq!(registry.complete("/s", &CompletionData::default()), ["/save "]);
+//! // The bundled `en` locale has `commands.help.save`, which takes the place of `summary`.
+//! let help = "Write the last reply (or with --all, the transcript) to a file";
+//! assert_eq!(registry.help_lines("en"), [format!("  {:<36} {}", "/save <path> [--all]", help)]);
+//! ```
+//!
+//! Lookup goes by name or alias, never by prefix; a name or alias taken twice is a
+//! programming error, caught when the registry is built:
+//!
+//! ```
+//! use aion_core::commands::{Args, Command, CommandError, Registry};
+//! use async_trait::async_trait;
+//! use std::panic::{catch_unwind, AssertUnwindSafe};
+//!
+//! struct Named(&'static str, &'static [&'static str]);
+//!
+//! #[async_trait(?Send)]
+//! impl Command<()> for Named {
+//!     fn name(&self) -> &'static str { self.0 }
+//!     fn aliases(&self) -> &'static [&'static str] { self.1 }
+//!     fn summary(&self) -> &'static str { "" }
+//!     async fn execute(&self, _: Args, _: &mut ()) -> Result<(), CommandError> { Ok(()) }
+//! }
+//!
+//! let registry = Registry::new().register(Named("quit", &["exit", "q"])).register(Named("clear", &[]));
+//! assert_eq!(registry.find("quit").map(|c| c.name()), Some("quit"));
+//! assert_eq!(registry.find("q").map(|c| c.name()), Some("quit"));
+//! assert_eq!(registry.find("clear").map(|c| c.name()), Some("clear"));
+//! assert!(registry.find("qu").is_none() && registry.find("/quit").is_none() && registry.find("").is_none());
+//! assert!(matches!(registry.parse("  /exit  "), Ok((c, _)) if c.name() == "quit"));
+//! assert_eq!(registry.suggest("clera"), Some("clear"));
+//! assert_eq!(registry.iter().map(|c| c.name()).collect::<Vec<_>>(), ["quit", "clear"]);
+//!
+//! let twice = |registry: Registry<()>, command: Named| {
+//!     let err = catch_unwind(AssertUnwindSafe(|| registry.register(command))).err().expect("registered twice");
+//!     err.downcast_ref::<String>().cloned().unwrap_or_default()
+//! };
+//! let base = || Registry::new().register(Named("quit", &["exit"]));
+//! assert_eq!(twice(base(), Named("quit", &[])), "/quit is registered twice");
+//! assert_eq!(twice(base(), Named("exit", &[])), "/exit is registered twice");
+//! assert_eq!(twice(base(), Named("leave", &["quit"])), "/quit is registered twice");
+//! assert_eq!(twice(base(), Named("leave", &["exit"])), "/exit is registered twice");
+//! ```
+
+use crate::catalog::distance;
+use crate::config::style::{self, TONES};
+use crate::config::ProviderKind;
+use crate::i18n;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// Which values an argument position accepts, for validation and completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgValues {
+    None,
+    Free,
+    Providers,
+    /// Recently used models of the current provider.
+    Models,
+    Locales,
+    Templates,
+    OnOff,
+    /// `style::TONES` and `off`.
+    Tones,
+    /// One of these words, or nothing.
+    Actions(&'static [&'static str]),
+    /// A number, or nothing.
+    Count,
+}
+
+/// What a command takes after its name.
+#[derive(Debug, Clone, Copy)]
+pub struct ArgSpec {
+    /// Placeholder for the argument in the usage line, e.g. `<path>` or `on|off`.
+    pub arg: &'static str,
+    pub values: ArgValues,
+    /// Boolean flags, e.g. `--save`.
+    pub flags: &'static [&'static str],
+    /// Options that take the next word as their value, with its placeholder:
+    /// `("--model", "<name>")`.
+    pub options: &'static [(&'static str, &'static str)],
+    /// Flags come first; text after the first argument is passed through as free-form
+    /// input, spacing kept.
+    pub rest: bool,
+}
+
+impl ArgSpec {
+    /// No argument, flags, or options.
+    pub const NONE: ArgSpec = ArgSpec { arg: "", values: ArgValues::None, flags: &[], options: &[], rest: false };
+
+    /// `/name <arg> [--flag] [--option <value>]`.
+    pub fn usage(&self, name: &str) -> String {
+        let mut parts = vec![format!("/{}", name)];
+        parts.extend((!self.arg.is_empty()).then(|| self.arg.to_string()));
+        parts.extend(self.flags.iter().map(|f| format!("[{}]", f)));
+        parts.extend(self.options.iter().map(|(o, value)| format!("[{} {}]", o, value)));
+        parts.join(" ")
+    }
+}
+
+/// A command line split by its `ArgSpec`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Args {
+    /// The first argument.
+    pub arg: Option<String>,
+    /// Text after the first argument (`ArgSpec::rest`).
+    pub rest: Option<String>,
+    /// Everything after the leading flags (`ArgSpec::rest`), for commands that take the
+    /// whole line, spaces included.
+    pub text: String,
+    flags: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CommandError {
+    #[error("unknown command /{name}{}", .suggestion.map(|s| format!(" (did you mean /{s}?)")).unwrap_or_default())]
+    Unknown {
+        name: String,
+        suggestion: Option<&'static str>,
+    },
+
+    #[error("usage: {0}")]
+    Usage(String),
+
+    #[error("invalid value '{value}', expected one of: {expected}")]
+    InvalidValue { value: String, expected: String },
+}
+
+/// A slash command run against `C`.
+#[async_trait(?Send)]
+pub trait Command<C: ?Sized>: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Other names it answers to; not listed by `/help` or completed.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// English `/help` text; the locale's `commands.help.<name>` replaces it.
+    fn summary(&self) -> &'static str;
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec::NONE
+    }
+
+    fn usage(&self) -> String {
+        self.spec().usage(self.name())
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut C) -> Result<(), CommandError>;
+}
+
+/// Known values offered by Tab completion.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionData {
+    pub locales: Vec<String>,
+    pub models: Vec<String>,
+    pub templates: Vec<String>,
+}
+
+/// Commands in the order registered, which is the `/help` order.
+pub struct Registry<C: ?Sized> {
+    commands: Vec<Box<dyn Command<C>>>,
+}
+
+impl<C: ?Sized> Default for Registry<C> {
+    fn default() -> Self {
+        Self { commands: Vec::new() }
+    }
+}
+
+impl<C: ?Sized> Registry<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `command`. Panics when its name or an alias is taken already.
+    pub fn register(mut self, command: impl Command<C> + 'static) -> Self {
+        for name in std::iter::once(command.name()).chain(command.aliases().iter().copied()) {
+            assert!(self.find(name).is_none(), "/{} is registered twice", name);
+        }
+        self.commands.push(Box::new(command));
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Command<C>> {
+        self.commands.iter().map(|c| c.as_ref())
+    }
+
+    /// The command with this name or alias.
+    pub fn find(&self, name: &str) -> Option<&dyn Command<C>> {
+        self.iter().find(|c| c.name() == name || c.aliases().contains(&name))
+    }
+
+    /// The command `input` names, with its arguments.
+    pub fn parse(&self, input: &str) -> Result<(&dyn Command<C>, Args), CommandError> {
+        let body = input.trim().trim_start_matches('/');
+        let (name, remainder) = split_word(body);
+        let command = self
+            .find(name)
+            .ok_or_else(|| CommandError::Unknown { name: name.to_string(), suggestion: self.suggest(name) })?;
+        Ok((command, split_args(&command.spec(), remainder).ok_or_else(|| CommandError::Usage(command.usage()))?))
+    }
+
+    /// Parse `input` and run it against `ctx`.
+    pub async fn dispatch(&self, input: &str, ctx: &mut C) -> Result<(), CommandError> {
+        let (command, args) = self.parse(input)?;
+        command.execute(args, ctx).await
+    }
+
+    /// One line per command: usage and summary in `lang`.
+    pub fn help_lines(&self, lang: &str) -> Vec<String> {
+        self.iter()
+            .map(|c| {
+                let summary = i18n::text(lang, &format!("commands.help.{}", c.name()), c.summary(), &[]);
+                format!("  {:<36} {}", c.usage(), summary)
+            })
+            .collect()
+    }
+
+    /// Tab completion: full-line candidates for the current input.
+    pub fn complete(&self, input: &str, data: &CompletionData) -> Vec<String> {
+        let Some(body) = input.strip_prefix('/') else {
+            return Vec::new();
+        };
+
+        match body.split_once(' ') {
+            None => self
+                .iter()
+                .filter(|c| c.name().starts_with(body))
+                .map(|c| {
+                    let sep = if c.spec().values == ArgValues::None { "" } else { " " };
+                    format!("/{}{}", c.name(), sep)
+                })
+                .collect(),
+            Some((name, rest)) => {
+                // Only the first argument position has a known value set.
+                if rest.contains(' ') {
+                    return Vec::new();
+                }
+                let values: Vec<String> = match self.find(name).map(|c| c.spec().values) {
+                    Some(ArgValues::Providers) => provider_names().iter().map(|s| s.to_string()).collect(),
+                    Some(ArgValues::Models) => data.models.clone(),
+                    Some(ArgValues::Locales) => data.locales.clone(),
+                    Some(ArgValues::Templates) => data.templates.clone(),
+                    Some(ArgValues::OnOff) => vec!["on".to_string(), "off".to_string()],
+                    Some(ArgValues::Tones) => {
+                        style::names(&TONES).into_iter().chain(["off"]).map(String::from).collect()
+                    }
+                    Some(ArgValues::Actions(words)) => words.iter().map(|w| w.to_string()).collect(),
+                    _ => Vec::new(),
+                };
+                values
+                    .into_iter()
+                    .filter(|v| v.starts_with(rest))
+                    .map(|v| format!("/{} {}", name, v))
+                    .collect()
+            }
+        }
+    }
+
+    /// Closest command name to an unknown one, if it is plausibly a typo.
+    pub fn suggest(&self, name: &str) -> Option<&'static str> {
+        self.iter()
+            .map(|c| (c.name(), distance(name, c.name())))
+            .filter(|(_, d)| *d <= 2)
+            .min_by_key(|(_, d)| *d)
+            .map(|(n, _)| n)
+    }
+}
+
+pub fn is_command(input: &str) -> bool {
+    input.starts_with('/')
+}
+
+/// Provider names accepted by `/provider` and `--provider`.
+pub fn provider_names() -> Vec<&'static str> {
+    ProviderKind::ALL.iter().map(ProviderKind::id).collect()
+}
+
+/// `remainder` split by `spec`; `None` when it does not fit (the usage error).
+fn split_args(spec: &ArgSpec, remainder: &str) -> Option<Args> {
+    let mut out = Args::default();
+    let mut words_found = Vec::new();
+    if spec.rest {
+        // Leading flags, then the first word is the argument; everything after it keeps
+        // its original spacing.
+        let mut text = remainder.trim();
+        loop {
+            let (word, tail) = split_word(text);
+            if !spec.flags.contains(&word) {
+                break;
+            }
+            out.flags.push(word.to_string());
+            text = tail.trim();
+        }
+        out.text = text.to_string();
+        let (first, tail) = split_word(text);
+        if !first.is_empty() {
+            words_found.push(first);
+        }
+        let tail = tail.trim();
+        if !tail.is_empty() {
+            out.rest = Some(tail.to_string());
+        }
+    } else {
+        let mut words = remainder.split_whitespace();
+        while let Some(w) = words.next() {
+            if spec.flags.contains(&w) {
+                out.flags.push(w.to_string());
+            } else if spec.options.iter().any(|(o, _)| *o == w) {
+                out.options.insert(w.to_string(), words.next()?.to_string());
+            } else {
+                words_found.push(w);
+            }
+        }
+    }
+
+    out.arg = match (spec.values, words_found.as_slice()) {
+        (ArgValues::None | ArgValues::Actions(_) | ArgValues::Count, []) => None,
+        (ArgValues::None, _) => return None,
+        (_, [one]) => Some(one.to_string()),
+        _ => return None,
+    };
+    Some(out)
+}
+
+/// Split off the first whitespace-delimited word, returning it and the untouched tail.
+pub fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    }
+}
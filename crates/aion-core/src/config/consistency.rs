@@ -0,0 +1,144 @@
+//! Feature/capability combinations that are valid but do not work together.
+//!
+//! Notes:
+//! - Warnings, not errors: such a config loads and runs, the feature just has nothing
+//!   to work with. `validate_strict` (`--strict`) turns the first one into
+//!   `ConfigError::Inconsistent`.
+//! - Each warning carries a short explanation and the suggested fix.
+//! - Adding a rule means a variant in `ConfigWarning` and a line in
+//!   `consistency_warnings`.
+//!
+//! ```
+//! use aion_core::config::{AppConfig, ConfigError, ConfigWarning::*};
+//!
+//! // (web_in_terminal, safe_execute, system_scan) against (network, run_commands, read_files).
+//! let config = |(web, safe, scan): (bool, bool, bool), (network, commands, read): (bool, bool, bool)| {
+//!     let mut cfg = AppConfig::new_default();
+//!     (cfg.features.web_in_terminal, cfg.features.safe_execute, cfg.features.system_scan) = (web, safe, scan);
+//!     (cfg.caps.network, cfg.caps.run_commands, cfg.caps.read_files) = (network, commands, read);
+//!     cfg
+//! };
+//! let cases = [
+//!     ((true, true, true), (true, true, true), vec![]),
+//!     ((false, false, false), (false, false, false), vec![]),
+//!     ((true, false, false), (false, true, true), vec![WebWithoutNetwork]),
+//!     ((false, true, false), (true, false, true), vec![SafeExecuteWithoutCommands]),
+//!     ((false, false, true), (true, false, false), vec![SystemScanWithoutAccess]),
+//!     // Either kind of access is enough for the scan.
+//!     ((false, false, true), (false, false, true), vec![]),
+//!     ((false, false, true), (false, true, false), vec![]),
+//!     ((true, true, true), (false, false, false), vec![WebWithoutNetwork, SafeExecuteWithoutCommands, SystemScanWithoutAccess]),
+//! ];
+//! for (features, caps, expected) in cases {
+//!     let cfg = config(features, caps);
+//!     assert_eq!(cfg.consistency_warnings(), expected, "features {:?}, caps {:?}", features, caps);
+//!     // Warnings never fail plain validation.
+//!     cfg.validate().unwrap();
+//! }
+//!
+//! // `--strict`: the first warning is the error; a clean config still passes.
+//! let cfg = config((true, true, true), (false, false, false));
+//! assert!(matches!(cfg.validate_strict(), Err(ConfigError::Inconsistent(WebWithoutNetwork))));
+//! let cfg = config((false, true, true), (false, false, true));
+//! assert!(matches!(cfg.validate_strict(), Err(ConfigError::Inconsistent(SafeExecuteWithoutCommands))));
+//! config((true, true, true), (true, true, true)).validate_strict().unwrap();
+//! // Errors `validate` finds come first.
+//! let mut cfg = config((true, true, true), (false, false, false));
+//! cfg.provider.model.clear();
+//! assert!(matches!(cfg.validate_strict(), Err(ConfigError::EmptyModel)));
+//!
+//! assert_eq!(
+//!     WebWithoutNetwork.to_string(),
+//!     "web_in_terminal is on but network access is disabled (set caps.network = true, or features.web_in_terminal = false)"
+//! );
+//! ```
+
+use super::{AppConfig, ConfigError};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigWarning {
+    /// `features.web_in_terminal` with `caps.network` off.
+    WebWithoutNetwork,
+    /// `features.safe_execute` with `caps.run_commands` off.
+    SafeExecuteWithoutCommands,
+    /// `features.system_scan` with both `caps.read_files` and `caps.run_commands` off.
+    SystemScanWithoutAccess,
+}
+
+impl ConfigWarning {
+    pub const ALL: [ConfigWarning; 3] = [
+        ConfigWarning::WebWithoutNetwork,
+        ConfigWarning::SafeExecuteWithoutCommands,
+        ConfigWarning::SystemScanWithoutAccess,
+    ];
+
+    /// Stable name, as in JSON output.
+    pub fn id(self) -> &'static str {
+        match self {
+            ConfigWarning::WebWithoutNetwork => "web_without_network",
+            ConfigWarning::SafeExecuteWithoutCommands => "safe_execute_without_commands",
+            ConfigWarning::SystemScanWithoutAccess => "system_scan_without_access",
+        }
+    }
+
+    /// The feature switch the warning is about, as a dotted key.
+    pub fn feature(self) -> &'static str {
+        match self {
+            ConfigWarning::WebWithoutNetwork => "features.web_in_terminal",
+            ConfigWarning::SafeExecuteWithoutCommands => "features.safe_execute",
+            ConfigWarning::SystemScanWithoutAccess => "features.system_scan",
+        }
+    }
+
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ConfigWarning::WebWithoutNetwork => "web_in_terminal is on but network access is disabled",
+            ConfigWarning::SafeExecuteWithoutCommands => "safe_execute is on but running commands is disabled",
+            ConfigWarning::SystemScanWithoutAccess => {
+                "system_scan is on but reading files and running commands are both disabled"
+            }
+        }
+    }
+
+    pub fn fix(self) -> &'static str {
+        match self {
+            ConfigWarning::WebWithoutNetwork => "set caps.network = true, or features.web_in_terminal = false",
+            ConfigWarning::SafeExecuteWithoutCommands => "set caps.run_commands = true, or features.safe_execute = false",
+            ConfigWarning::SystemScanWithoutAccess => "set caps.read_files = true, or features.system_scan = false",
+        }
+    }
+
+    fn applies(self, cfg: &AppConfig) -> bool {
+        let (f, c) = (&cfg.features, &cfg.caps);
+        match self {
+            ConfigWarning::WebWithoutNetwork => f.web_in_terminal && !c.network,
+            ConfigWarning::SafeExecuteWithoutCommands => f.safe_execute && !c.run_commands,
+            ConfigWarning::SystemScanWithoutAccess => f.system_scan && !c.read_files && !c.run_commands,
+        }
+    }
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.explanation(), self.fix())
+    }
+}
+
+impl AppConfig {
+    /// Enabled features whose capabilities are switched off, in `ConfigWarning::ALL` order.
+    pub fn consistency_warnings(&self) -> Vec<ConfigWarning> {
+        ConfigWarning::ALL.into_iter().filter(|w| w.applies(self)).collect()
+    }
+
+    /// `validate`, with consistency warnings as errors (`--strict`).
+    pub fn validate_strict(&self) -> Result<(), ConfigError> {
+        self.validate()?;
+        match self.consistency_warnings().first() {
+            Some(warning) => Err(ConfigError::Inconsistent(*warning)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Key-by-key differences between two config documents.
+//!
+//! Notes:
+//! - Tables are flattened to dotted keys (`provider.model`); arrays and scalars compare
+//!   by their TOML rendering.
+//! - Lines are sorted by key so the output is stable.
+//! - `config_changes` compares two configs as `save_config` would write them, without
+//!   reading the file.
+//! - `unified` is a plain line diff of two texts (used by `--dry-run` previews);
+//!   `new_lines` marks the lines of the second text that the first one lacks.
+//! - A key with no line is unchanged. `is_under` picks the lines of one setting or
+//!   section, which is how the wizard's summary tells its rows apart.
+//!
+//! ```
+//! use aion_core::config::diff::{config_changes, Change};
+//! use aion_core::config::AppConfig;
+//!
+//! let before = AppConfig::new_default();
+//! let mut after = before.clone();
+//! after.provider.model = "llama3".to_string();
+//! after.provider.organization = Some("org-1".to_string());
+//! after.features.retrieval = !before.features.retrieval;
+//!
+//! let changes = config_changes(&before, &after).unwrap();
+//! let keys: Vec<&str> = changes.iter().map(|l| l.key.as_str()).collect();
+//! assert_eq!(keys, ["features.retrieval", "provider.model", "provider.organization"]);
+//! assert_eq!(changes[2].change, Change::Added("\"org-1\"".to_string()));
+//! assert_eq!(changes[1].to_string(), format!("~ provider.model: \"{}\" -> \"llama3\"", before.provider.model));
+//!
+//! assert!(changes[0].is_under("features") && !changes[0].is_under("feature"));
+//! assert!(changes.iter().all(|l| !l.is_under("language")));
+//! ```
+
+use crate::config::AppConfig;
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    Changed { before: String, after: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub key: String,
+    pub change: Change,
+}
+
+impl DiffLine {
+    /// Whether this line is `key`'s, or one inside the table `key`.
+    pub fn is_under(&self, key: &str) -> bool {
+        self.key.strip_prefix(key).is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    }
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.change {
+            Change::Added(v) => write!(f, "+ {} = {}", self.key, v),
+            Change::Removed(v) => write!(f, "- {} = {}", self.key, v),
+            Change::Changed { before, after } => write!(f, "~ {}: {} -> {}", self.key, before, after),
+        }
+    }
+}
+
+/// `config` as a TOML table, the shape `diff` compares.
+pub fn to_table(config: &AppConfig) -> Result<toml::Table> {
+    match toml::Value::try_from(config)? {
+        toml::Value::Table(t) => Ok(t),
+        _ => bail!("config did not serialize to a table"),
+    }
+}
+
+/// Changes from config `before` to config `after`.
+pub fn config_changes(before: &AppConfig, after: &AppConfig) -> Result<Vec<DiffLine>> {
+    Ok(diff(&to_table(before)?, &to_table(after)?))
+}
+
+/// Changes that turn `before` into `after`.
+pub fn diff(before: &toml::Table, after: &toml::Table) -> Vec<DiffLine> {
+    let before = flatten(before);
+    let after = flatten(after);
+
+    let mut lines = Vec::new();
+    for (key, old) in &before {
+        match after.get(key) {
+            None => lines.push(DiffLine { key: key.clone(), change: Change::Removed(old.clone()) }),
+            Some(new) if new != old => lines.push(DiffLine {
+                key: key.clone(),
+                change: Change::Changed { before: old.clone(), after: new.clone() },
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, new) in &after {
+        if !before.contains_key(key) {
+            lines.push(DiffLine { key: key.clone(), change: Change::Added(new.clone()) });
+        }
+    }
+    lines.sort_by(|a, b| a.key.cmp(&b.key));
+    lines
+}
+
+fn flatten(table: &toml::Table) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    flatten_into(&mut out, "", table);
+    out
+}
+
+fn flatten_into(out: &mut BTreeMap<String, String>, prefix: &str, table: &toml::Table) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            toml::Value::Table(inner) => flatten_into(out, &path, inner),
+            other => {
+                out.insert(path, other.to_string());
+            }
+        }
+    }
+}
+
+/* ---------------------------
+   Unified text diff
+---------------------------- */
+
+/// Lines of context around each change in `unified`.
+const CONTEXT: usize = 3;
+
+/// A unified diff of `before` and `after`, or an empty string when they are equal.
+/// Sized for config-like files: the line alignment is a quadratic LCS.
+pub fn unified(before: &str, after: &str, from: &str, to: &str) -> String {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    let ops = line_ops(&a, &b);
+    if ops.iter().all(|(op, _)| *op == ' ') {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", from, to);
+    let mut i = 0;
+    while let Some(first) = (i..ops.len()).find(|&k| ops[k].0 != ' ') {
+        // Grow the hunk until the context after the last change is too long to bridge.
+        let start = first.saturating_sub(CONTEXT).max(i);
+        let mut last = first;
+        for (k, (op, _)) in ops.iter().enumerate().skip(first) {
+            if *op != ' ' {
+                last = k;
+            } else if k - last > 2 * CONTEXT {
+                break;
+            }
+        }
+        let end = (last + CONTEXT + 1).min(ops.len());
+
+        let count = |range: &[(char, &str)], skip: char| range.iter().filter(|(op, _)| *op != skip).count();
+        let (old_start, new_start) = (count(&ops[..start], '+'), count(&ops[..start], '-'));
+        let (old_len, new_len) = (count(&ops[start..end], '+'), count(&ops[start..end], '-'));
+        // An empty range is reported at the line before it, as `diff -u` does.
+        let line_no = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            line_no(old_start, old_len),
+            old_len,
+            line_no(new_start, new_len),
+            new_len
+        ));
+        for (op, line) in &ops[start..end] {
+            out.push(*op);
+            out.push_str(line);
+            out.push('\n');
+        }
+        i = end;
+    }
+    out
+}
+
+/// For each line of `after`, whether it is added or changed relative to `before`.
+/// With an empty `before` every line is new.
+pub fn new_lines(before: &str, after: &str) -> Vec<bool> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    line_ops(&a, &b)
+        .into_iter()
+        .filter(|(op, _)| *op != '-')
+        .map(|(op, _)| op == '+')
+        .collect()
+}
+
+/// Align two line lists: `' '` kept, `'-'` removed, `'+'` added.
+fn line_ops<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(char, &'a str)> {
+    let (n, m) = (a.len(), b.len());
+    // lcs[i][j]: longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::with_capacity(n + m);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ops.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(('-', a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(a[i..].iter().map(|l| ('-', *l)));
+    ops.extend(b[j..].iter().map(|l| ('+', *l)));
+    ops
+}
@@ -0,0 +1,317 @@
+//! The setup wizard's answers, kept apart from `AppConfig` until they are committed.
+//!
+//! Notes:
+//! - Each setting the wizard asks for is a `Slot`: its value as given and whether it was
+//!   set since the draft was made. Model, endpoint, and key variable are `None` while
+//!   empty (a model being retyped), which no `AppConfig` can say.
+//! - `check` is what the wizard shows for a field (the `fields` checks, so a hint and
+//!   `commit` cannot disagree); `done` says whether a field's answer can be saved.
+//! - `commit` writes the set slots over the config the wizard started from and returns it,
+//!   or every problem, each with its `Field`. Settings the wizard does not ask for are
+//!   checked by `AppConfig::validate` with the asked ones at their defaults, so one bad
+//!   answer does not hide them; `validate` stops at the first (`Field::Other`).
+//! - A different provider resets model, endpoint, and key variable to its defaults and,
+//!   when committed, drops organization, project, and extra options, as
+//!   `AppConfig::set_provider_kind` does.
+//! - `preview` is the config as it stands, empty answers left empty, for what only an
+//!   `AppConfig` can show: the summary's changes, the config.toml preview, warnings.
+//!
+//! ```
+//! use aion_core::config::draft::{Field, WizardDraft};
+//! use aion_core::config::{AppConfig, ConfigError, ProviderKind};
+//!
+//! let existing = AppConfig::new_default();
+//! for kind in ProviderKind::ALL {
+//!     let mut draft = WizardDraft::new(&existing);
+//!     draft.set_provider(kind.clone());
+//!     let cfg = draft.commit(&existing).unwrap();
+//!     assert_eq!((&cfg.provider.kind, cfg.provider.model.as_str()), (&kind, kind.default_model()));
+//!     assert_eq!(cfg.provider.base_url.as_deref(), kind.default_base_url());
+//!     assert_eq!(cfg.provider.api_key_env.as_deref(), kind.default_api_key_env());
+//!
+//!     // Cleared answers: every kind needs a model; which need an endpoint or key variable
+//!     // is the kind's.
+//!     for field in [Field::Model, Field::BaseUrl, Field::ApiKeyEnv] {
+//!         draft.set_text(field, "");
+//!     }
+//!     assert!(!draft.done(Field::Model) && draft.done(Field::Language) && draft.done(Field::Provider));
+//!     let errors = draft.commit(&existing).unwrap_err();
+//!     let fields: Vec<Field> = errors.iter().map(|(field, _)| *field).collect();
+//!     let expected = match kind {
+//!         ProviderKind::Ollama => vec![Field::Model, Field::BaseUrl],
+//!         ProviderKind::OpenAI | ProviderKind::Claude => vec![Field::Model, Field::ApiKeyEnv],
+//!         ProviderKind::OpenRouter => vec![Field::Model, Field::BaseUrl, Field::ApiKeyEnv],
+//!     };
+//!     assert_eq!(fields, expected, "{:?}", kind);
+//!     assert!(matches!(errors[0].1, ConfigError::EmptyModel));
+//!
+//!     // Typed values are trimmed; an empty endpoint means the public one where there is one.
+//!     draft.set_text(Field::Model, " my-model ");
+//!     draft.set_text(Field::BaseUrl, "https://llm.example.com/v1");
+//!     draft.set_text(Field::ApiKeyEnv, "MY_KEY");
+//!     let cfg = draft.commit(&existing).unwrap();
+//!     assert_eq!(cfg.provider.model, "my-model");
+//!     assert_eq!(cfg.provider.base_url.as_deref(), Some("https://llm.example.com/v1"));
+//!     assert_eq!(cfg.provider.api_key_env.as_deref(), Some("MY_KEY"));
+//!
+//!     draft.set_text(Field::BaseUrl, "llm.example.com");
+//!     draft.set_text(Field::ApiKeyEnv, "MY KEY");
+//!     let errors = draft.commit(&existing).unwrap_err();
+//!     assert!(matches!(errors[..], [(Field::BaseUrl, ConfigError::InvalidBaseUrl { .. }), (Field::ApiKeyEnv, _)]));
+//! }
+//!
+//! // Nothing set: the existing config comes back as it was.
+//! let mut existing = AppConfig::new_default();
+//! existing.provider.kind = ProviderKind::OpenAI;
+//! existing.provider.model = "gpt-4o".into();
+//! existing.provider.base_url = None;
+//! existing.provider.api_key_env = Some("WORK_KEY".into());
+//! existing.provider.organization = Some("org-1".into());
+//! let draft = WizardDraft::new(&existing);
+//! assert!(!draft.dirty());
+//! let cfg = draft.commit(&existing).unwrap();
+//! assert_eq!((cfg.provider.model.as_str(), cfg.provider.organization.as_deref()), ("gpt-4o", Some("org-1")));
+//!
+//! // The same kind again keeps everything; another one drops the organization.
+//! let mut draft = WizardDraft::new(&existing);
+//! assert!(!draft.set_provider(ProviderKind::OpenAI));
+//! draft.set_text(Field::Model, "gpt-4o");
+//! assert!(draft.dirty() && draft.changed(&existing).is_empty());
+//! assert!(draft.set_provider(ProviderKind::Claude));
+//! assert_eq!(draft.changed(&existing), [Field::Provider, Field::Model, Field::ApiKeyEnv]);
+//! assert!(draft.commit(&existing).unwrap().provider.organization.is_none());
+//!
+//! // A language that is not supported, and a problem the wizard does not ask about.
+//! existing.provider.params.temperature = Some(9.0);
+//! let mut draft = WizardDraft::new(&existing);
+//! draft.set_language("xx");
+//! draft.set_text(Field::Model, "");
+//! let errors = draft.commit(&existing).unwrap_err();
+//! let keys: Vec<&str> = errors.iter().map(|(field, _)| field.key()).collect();
+//! assert_eq!(keys, ["language", "provider.model", "other"]);
+//! assert_eq!(errors[2].1.key().as_deref(), Some("provider.params.temperature"));
+//! assert_eq!(Field::of(&errors[2].1), Field::Other);
+//! ```
+
+use super::fields::{self, Check};
+use super::{allowed_languages, AppConfig, Capabilities, ConfigError, Features, ProviderKind};
+
+/// A setting the wizard asks for; `Other` is any other one, for `commit`'s errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Language,
+    Provider,
+    Model,
+    BaseUrl,
+    ApiKeyEnv,
+    Other,
+}
+
+impl Field {
+    /// The fields the wizard asks for, in the order it asks.
+    pub const ASKED: [Field; 5] = [Field::Language, Field::Provider, Field::Model, Field::BaseUrl, Field::ApiKeyEnv];
+
+    /// The dotted config key, as `ConfigError::key` gives it; `other` for `Other`.
+    pub fn key(self) -> &'static str {
+        match self {
+            Field::Language => "language",
+            Field::Provider => "provider.kind",
+            Field::Model => "provider.model",
+            Field::BaseUrl => "provider.base_url",
+            Field::ApiKeyEnv => "provider.api_key_env",
+            Field::Other => "other",
+        }
+    }
+
+    /// The field `err` is about.
+    pub fn of(err: &ConfigError) -> Field {
+        let key = err.key();
+        Field::ASKED.into_iter().find(|f| key.as_deref() == Some(f.key())).unwrap_or(Field::Other)
+    }
+}
+
+/// One answer: the value, and whether it was set since the draft was made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot<T> {
+    pub value: T,
+    pub dirty: bool,
+}
+
+impl<T> Slot<T> {
+    fn new(value: T) -> Self {
+        Self { value, dirty: false }
+    }
+
+    fn set(&mut self, value: T) {
+        self.value = value;
+        self.dirty = true;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WizardDraft {
+    pub language: Slot<String>,
+    pub provider: Slot<ProviderKind>,
+    /// As typed; `None` while empty.
+    pub model: Slot<Option<String>>,
+    /// As typed; `None` for the provider's default.
+    pub base_url: Slot<Option<String>>,
+    pub api_key_env: Slot<Option<String>>,
+    /// Asked by the plain wizard's summary; written as they are.
+    pub features: Features,
+    pub caps: Capabilities,
+}
+
+impl WizardDraft {
+    /// A draft holding `existing`'s answers, none of them set.
+    pub fn new(existing: &AppConfig) -> Self {
+        let p = &existing.provider;
+        Self {
+            language: Slot::new(existing.language.clone()),
+            provider: Slot::new(p.kind.clone()),
+            model: Slot::new(Some(p.model.clone()).filter(|m| !m.is_empty())),
+            base_url: Slot::new(p.base_url.clone()),
+            api_key_env: Slot::new(p.api_key_env.clone()),
+            features: existing.features.clone(),
+            caps: existing.caps.clone(),
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language.value
+    }
+
+    pub fn kind(&self) -> &ProviderKind {
+        &self.provider.value
+    }
+
+    /// Model, endpoint, or key variable as typed; empty for the other fields.
+    pub fn text(&self, field: Field) -> &str {
+        let slot = match field {
+            Field::Model => &self.model,
+            Field::BaseUrl => &self.base_url,
+            Field::ApiKeyEnv => &self.api_key_env,
+            _ => return "",
+        };
+        slot.value.as_deref().unwrap_or("")
+    }
+
+    /// Whether any answer was set.
+    pub fn dirty(&self) -> bool {
+        self.language.dirty || self.provider.dirty || self.model.dirty || self.base_url.dirty || self.api_key_env.dirty
+    }
+
+    pub fn set_language(&mut self, code: &str) {
+        self.language.set(code.to_string());
+    }
+
+    /// Choose the provider; a different one brings its default model, endpoint, and key
+    /// variable. Returns whether it changed.
+    pub fn set_provider(&mut self, kind: ProviderKind) -> bool {
+        if kind == self.provider.value {
+            return false;
+        }
+        self.model.set(Some(kind.default_model().to_string()));
+        self.base_url.set(kind.default_base_url().map(str::to_string));
+        self.api_key_env.set(kind.default_api_key_env().map(str::to_string));
+        self.provider.set(kind);
+        true
+    }
+
+    /// Set model, endpoint, or key variable as typed; empty clears it.
+    pub fn set_text(&mut self, field: Field, value: &str) {
+        let value = (!value.trim().is_empty()).then(|| value.to_string());
+        match field {
+            Field::Model => self.model.set(value),
+            Field::BaseUrl => self.base_url.set(value),
+            Field::ApiKeyEnv => self.api_key_env.set(value),
+            _ => {}
+        }
+    }
+
+    /// What `commit` will make of `field`'s answer.
+    pub fn check(&self, field: Field) -> Check {
+        match field {
+            Field::Language if allowed_languages().contains(self.language()) => Check::Ok(self.language().to_string()),
+            Field::Language => Check::Error(ConfigError::InvalidLanguage(self.language().to_string())),
+            Field::Provider | Field::Other => Check::Ok(self.kind().name().to_string()),
+            Field::Model => fields::model(self.text(field)),
+            Field::BaseUrl => fields::base_url(self.kind(), self.text(field)),
+            Field::ApiKeyEnv => fields::api_key_env(self.kind(), self.text(field)),
+        }
+    }
+
+    /// Whether `field`'s answer can be saved (warnings can).
+    pub fn done(&self, field: Field) -> bool {
+        !self.check(field).is_error()
+    }
+
+    /// The asked fields whose answer differs from `existing`'s setting.
+    pub fn changed(&self, existing: &AppConfig) -> Vec<Field> {
+        let (cfg, p) = (self.preview(existing), &existing.provider);
+        Field::ASKED
+            .into_iter()
+            .filter(|field| match field {
+                Field::Language => self.language.dirty && cfg.language != existing.language,
+                Field::Provider => self.provider.dirty && cfg.provider.kind != p.kind,
+                Field::Model => self.model.dirty && cfg.provider.model != p.model,
+                Field::BaseUrl => self.base_url.dirty && cfg.provider.base_url != p.base_url,
+                Field::ApiKeyEnv => self.api_key_env.dirty && cfg.provider.api_key_env != p.api_key_env,
+                Field::Other => false,
+            })
+            .collect()
+    }
+
+    /// `existing` with the set answers, trimmed, and empty ones left empty.
+    pub fn preview(&self, existing: &AppConfig) -> AppConfig {
+        let mut cfg = existing.clone();
+        if self.provider.dirty && *self.kind() != existing.provider.kind {
+            cfg.set_provider_kind(self.kind().clone());
+        }
+        let trimmed = |slot: &Slot<Option<String>>| slot.value.as_deref().map(str::trim).map(str::to_string);
+        if self.language.dirty {
+            cfg.language = self.language.value.clone();
+        }
+        if self.model.dirty {
+            cfg.provider.model = trimmed(&self.model).unwrap_or_default();
+        }
+        if self.base_url.dirty {
+            cfg.provider.base_url = trimmed(&self.base_url);
+        }
+        if self.api_key_env.dirty {
+            cfg.provider.api_key_env = trimmed(&self.api_key_env);
+        }
+        cfg.features = self.features.clone();
+        cfg.caps = self.caps.clone();
+        cfg
+    }
+
+    /// The config to save over `existing`, or every problem with the field it is about.
+    pub fn commit(&self, existing: &AppConfig) -> Result<AppConfig, Vec<(Field, ConfigError)>> {
+        let cfg = self.preview(existing);
+        let mut errors: Vec<(Field, ConfigError)> = Vec::new();
+        // The rest is validated with failing answers at their defaults.
+        let mut rest = cfg.clone();
+        let kind = self.kind();
+        for field in Field::ASKED {
+            let Check::Error(e) = self.check(field) else { continue };
+            match field {
+                Field::Language => rest.language = "en".to_string(),
+                Field::Model => rest.provider.model = kind.default_model().to_string(),
+                Field::BaseUrl => rest.provider.base_url = kind.default_base_url().map(str::to_string),
+                Field::ApiKeyEnv => rest.provider.api_key_env = kind.default_api_key_env().map(str::to_string),
+                Field::Provider | Field::Other => {}
+            }
+            errors.push((field, e));
+        }
+        if let Err(e) = rest.validate() {
+            if !errors.iter().any(|(field, _)| *field == Field::of(&e)) {
+                errors.push((Field::of(&e), e));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(cfg),
+            false => Err(errors),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Dotted config keys (`provider.params.temperature`): reading one, setting one from text
+//! typed on a command line, and a chat session's unsaved settings.
+//!
+//! Notes:
+//! - `set` reads the value as TOML, or else as a string, so `0.7`, `true`, `["q"]`, and
+//!   `llama3` all work unquoted; the result is validated as a whole config. A key the
+//!   config does not have is an error.
+//! - `Edits` keeps the keys a session set with `/config set`, as typed. `/config save`
+//!   replays them over the config file as it is then (`apply_to_file`), so what else
+//!   the session changed (`/temp`, `/model` without `--save`, command-line flags) is not
+//!   written, and neither is anything else that changed in memory since it was loaded.
+//!
+//! ```
+//! use aion_core::config::edit::{get, set, Edits};
+//! use aion_core::config::{diff, AppConfig};
+//!
+//! let cfg = AppConfig::new_default();
+//! let updated = set(&cfg, "provider.params.temperature", "0.4").unwrap();
+//! assert_eq!(updated.provider.params.temperature, Some(0.4));
+//! assert_eq!(set(&cfg, "ui.ascii_only", "true").unwrap().ui.ascii_only, true);
+//! assert!(set(&cfg, "provider.params.temperature", "9").is_err());
+//! assert!(set(&cfg, "provider.colour", "red").is_err());
+//! assert!(set(&cfg, "language.code", "ar").is_err());
+//!
+//! let root = toml::Value::Table(diff::to_table(&updated).unwrap());
+//! assert_eq!(get(&root, "provider.params.temperature").and_then(|v| v.as_float()), Some(0.4));
+//!
+//! let mut edits = Edits::default();
+//! let cfg = edits.set(&cfg, "ui.ascii_only", "true").unwrap();
+//! let cfg = edits.set(&cfg, "ui.ascii_only", "false").unwrap();
+//! assert!(!cfg.ui.ascii_only);
+//! assert_eq!(edits.keys().collect::<Vec<_>>(), ["ui.ascii_only"]);
+//! ```
+
+use super::{diff, AppConfig};
+use super::io::load_config;
+use super::diff::DiffLine;
+use anyhow::{anyhow, Context, Result};
+
+/// The value at dotted `key` under `root`.
+pub fn get<'a>(root: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(root, |v, part| v.get(part))
+}
+
+/// `cfg` with the dotted `key` set to `raw`, read as a TOML value or else as a string.
+pub fn set(cfg: &AppConfig, key: &str, raw: &str) -> Result<AppConfig> {
+    let unknown = || anyhow!("no config key named '{}'", key);
+    let before = toml::Value::Table(diff::to_table(cfg)?);
+    let (parents, leaf) = key.rsplit_once('.').map_or(("", key), |(p, l)| (p, l));
+    let build = |value: toml::Value| -> Result<AppConfig> {
+        let mut root = before.clone();
+        // Tables left out for being empty (`provider.params`) are created on the way.
+        let parent = parents
+            .split('.')
+            .filter(|p| !p.is_empty())
+            .try_fold(&mut root, |v, part| {
+                Some(v.as_table_mut()?.entry(part).or_insert_with(|| toml::Value::Table(toml::Table::new())))
+            })
+            .and_then(toml::Value::as_table_mut)
+            .ok_or_else(unknown)?;
+        parent.insert(leaf.to_string(), value);
+        let updated: AppConfig = root.try_into().with_context(|| format!("invalid value for {}", key))?;
+        updated.validate()?;
+        Ok(updated)
+    };
+
+    let string = || toml::Value::String(raw.to_string());
+    let parsed = toml::from_str::<toml::Table>(&format!("value = {}", raw)).ok().and_then(|mut t| t.remove("value"));
+    let empty_list = matches!(&parsed, Some(toml::Value::Array(a)) if a.is_empty());
+    let updated = match parsed {
+        // `42` for a string setting parses as an integer: retry it as the string it was.
+        Some(value) if !value.is_str() => build(value).or_else(|e| build(string()).map_err(|_| e))?,
+        Some(value) => build(value)?,
+        None => build(string())?,
+    };
+
+    // Keys the config does not have are dropped on the way back. Empty lists are not
+    // written either, so setting one is taken at its word.
+    let after = toml::Value::Table(diff::to_table(&updated)?);
+    if get(&before, key).is_none() && get(&after, key).is_none() && !empty_list {
+        return Err(unknown());
+    }
+    Ok(updated)
+}
+
+/// Keys set in a session and not saved yet, in the order they were first set.
+#[derive(Debug, Clone, Default)]
+pub struct Edits {
+    keys: Vec<(String, String)>,
+}
+
+impl Edits {
+    /// `set` on `cfg`, remembering `key` when it succeeds.
+    pub fn set(&mut self, cfg: &AppConfig, key: &str, raw: &str) -> Result<AppConfig> {
+        let updated = set(cfg, key, raw)?;
+        match self.keys.iter_mut().find(|(k, _)| k == key) {
+            Some((_, value)) => *value = raw.to_string(),
+            None => self.keys.push((key.to_string(), raw.to_string())),
+        }
+        Ok(updated)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(|(k, _)| k.as_str())
+    }
+
+    /// The config file's contents with the edits replayed over them, and the changes
+    /// that makes, for the caller to save. Nothing is written.
+    pub fn apply_to_file(&self) -> Result<(AppConfig, Vec<DiffLine>)> {
+        let file = load_config().context("failed to load config")?;
+        let updated = self.keys.iter().try_fold(file.clone(), |cfg, (key, raw)| set(&cfg, key, raw))?;
+        let changes = diff::config_changes(&file, &updated)?;
+        Ok((updated, changes))
+    }
+
+    /// Forget the edits, once saved.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
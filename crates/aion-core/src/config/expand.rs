@@ -0,0 +1,156 @@
+//! `${VAR}` and `~` in config string fields (`provider.base_url`, the fallbacks'
+//! `base_url`, `cache.dir`, `context.file`, `exec.default_cwd`).
+//!
+//! Notes:
+//! - `${NAME}` is replaced by the environment variable `NAME`; an unset one is an error
+//!   naming the field and the variable. `$$` is a literal `$`, and a `$` followed by
+//!   anything else is kept as it is.
+//! - A leading `~` (alone or before a path separator) is the home directory; `~user`
+//!   and a `~` later in the value are left alone.
+//! - Expansion happens in memory, after parsing and before validation (`io::load_config`).
+//!   `apply` returns what it replaced so `restore` can put the templates back before the
+//!   config is written: saving never bakes a variable's current value into the file.
+//!
+//! ```
+//! use aion_core::config::expand::expand;
+//! use std::path::Path;
+//!
+//! let env = |name: &str| (name == "PORT").then(|| "8080".to_string());
+//! let home = Some(Path::new("/home/ada"));
+//!
+//! assert_eq!(expand("provider.base_url", "http://localhost:${PORT}/v1", env, home).unwrap(), "http://localhost:8080/v1");
+//! assert_eq!(expand("cache.dir", "~/aion-cache", env, home).unwrap(), "/home/ada/aion-cache");
+//! assert_eq!(expand("cache.dir", "/srv/~ada/$$HOME", env, home).unwrap(), "/srv/~ada/$HOME");
+//! assert_eq!(expand("cache.dir", "/srv/$PORT", env, home).unwrap(), "/srv/$PORT");
+//!
+//! let err = expand("provider.base_url", "http://${HOST}:${PORT}", env, home).unwrap_err();
+//! assert_eq!(err.to_string(), "provider.base_url uses ${HOST}, which is not set");
+//! assert!(expand("cache.dir", "/srv/${PORT", env, home).is_err());
+//!
+//! // Unset fields are skipped; a saved config gets its templates back.
+//! use aion_core::config::{expand::{apply, restore}, AppConfig};
+//! std::env::set_var("AION_DOC_HOST", "gpu-box");
+//! let mut cfg = AppConfig::new_default();
+//! cfg.provider.base_url = Some("http://${AION_DOC_HOST}:11434".into());
+//! cfg.cache.dir = None;
+//! let expanded = apply(&mut cfg).unwrap();
+//! assert_eq!(cfg.provider.base_url.as_deref(), Some("http://gpu-box:11434"));
+//! restore(&mut cfg, &expanded);
+//! assert_eq!(cfg.provider.base_url.as_deref(), Some("http://${AION_DOC_HOST}:11434"));
+//! ```
+
+use super::{AppConfig, ConfigError};
+use std::path::{is_separator, Path, PathBuf};
+
+/// One field `apply` changed: its value as written in the file and as expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expanded {
+    pub field: String,
+    pub template: String,
+    pub value: String,
+}
+
+/// `value` of `field` with `${NAME}`, `$$`, and a leading `~` expanded. `env` looks up a
+/// variable; `home` is the home directory, if known.
+pub fn expand(
+    field: &str,
+    value: &str,
+    env: impl Fn(&str) -> Option<String>,
+    home: Option<&Path>,
+) -> Result<String, ConfigError> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    if let (Some(home), Some(after)) = (home, value.strip_prefix('~')) {
+        if after.chars().next().is_none_or(is_separator) {
+            out.push_str(&home.to_string_lossy());
+            rest = after;
+        }
+    }
+
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        let tail = &rest[at + 1..];
+        if let Some(after) = tail.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix('{') {
+            let end = after.find('}').ok_or_else(|| ConfigError::MalformedVariable {
+                field: field.to_string(),
+                value: value.to_string(),
+            })?;
+            let name = &after[..end];
+            let found = env(name).ok_or_else(|| ConfigError::UnknownVariable {
+                field: field.to_string(),
+                name: name.to_string(),
+            })?;
+            out.push_str(&found);
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = tail;
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Expand the string fields of `cfg` in place, from the process environment and home
+/// directory. Returns the fields whose value changed.
+pub fn apply(cfg: &mut AppConfig) -> Result<Vec<Expanded>, ConfigError> {
+    let home = dirs::home_dir();
+    let mut changed = Vec::new();
+    visit(cfg, |field, value| {
+        let expanded = expand(field, value, |name| std::env::var(name).ok(), home.as_deref())?;
+        if expanded != *value {
+            let template = std::mem::replace(value, expanded.clone());
+            changed.push(Expanded { field: field.to_string(), template, value: expanded });
+        }
+        Ok(())
+    })?;
+    Ok(changed)
+}
+
+/// Put back the templates of `expanded` in the fields that still hold the expanded
+/// value. A field set to something else since (e.g. by the wizard) keeps it.
+pub fn restore(cfg: &mut AppConfig, expanded: &[Expanded]) {
+    let _ = visit(cfg, |field, value| {
+        if let Some(e) = expanded.iter().find(|e| e.field == field && e.value == *value) {
+            *value = e.template.clone();
+        }
+        Ok(())
+    });
+}
+
+/// Call `f` with each expandable field that is set: its name as in config.toml
+/// (fallbacks counting from 1, as in `ConfigError::InvalidFallback`) and its value.
+/// Paths that are not valid UTF-8 are skipped.
+fn visit(
+    cfg: &mut AppConfig,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), ConfigError>,
+) -> Result<(), ConfigError> {
+    if let Some(url) = cfg.provider.base_url.as_mut() {
+        f("provider.base_url", url)?;
+    }
+    for (i, fallback) in cfg.provider.fallbacks.iter_mut().enumerate() {
+        if let Some(url) = fallback.base_url.as_mut() {
+            f(&format!("provider.fallbacks[{}].base_url", i + 1), url)?;
+        }
+    }
+    if let Some(dir) = cfg.cache.dir.as_mut() {
+        if let Some(mut text) = dir.to_str().map(String::from) {
+            f("cache.dir", &mut text)?;
+            *dir = PathBuf::from(text);
+        }
+    }
+    if let Some(mut text) = cfg.context.file.to_str().map(String::from) {
+        f("context.file", &mut text)?;
+        cfg.context.file = PathBuf::from(text);
+    }
+    if let Some(dir) = cfg.exec.default_cwd.as_mut() {
+        if let Some(mut text) = dir.to_str().map(String::from) {
+            f("exec.default_cwd", &mut text)?;
+            *dir = PathBuf::from(text);
+        }
+    }
+    Ok(())
+}
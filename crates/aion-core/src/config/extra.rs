@@ -0,0 +1,149 @@
+//! `provider.extra_options`: request fields AION does not model, sent as written.
+//!
+//! Notes:
+//! - Ollama gets them inside `options` (`num_gpu`, `mirostat`, `stop`); the others at the
+//!   top level of the request (`seed`, `logit_bias`, OpenRouter's `provider`).
+//! - A name AION sends itself is refused rather than overridden, whether or not it is
+//!   sent on this request: `temperature` belongs in `provider.params`, and `model` or
+//!   `stream` cannot be changed at all. `reserved` lists them per provider.
+//! - Values nest at most `max_depth` levels: Ollama's options are scalars or lists of
+//!   them (no tables); the JSON APIs also take one table of those
+//!   (`logit_bias = { "50256" = -100 }`).
+//!   Dates cannot be sent as JSON.
+//! - `aion config set provider.extra_options.seed 42` works like any other key; the value
+//!   keeps the type TOML gives it.
+//!
+//! ```
+//! use aion_core::config::extra::{to_json, validate};
+//! use aion_core::config::{AppConfig, ConfigError, ProviderKind};
+//!
+//! let table = |text: &str| toml::from_str::<toml::Table>(text).unwrap();
+//! let ok = table("seed = 42\nlogit_bias = { \"50256\" = -100 }");
+//! assert!(validate(&ProviderKind::OpenAI, &ok).is_ok());
+//! assert_eq!(to_json(&ok)["logit_bias"]["50256"], -100);
+//!
+//! // Ollama's options are flat.
+//! assert!(validate(&ProviderKind::Ollama, &table("num_gpu = 1\nstop = [\"###\"]")).is_ok());
+//! let nested = validate(&ProviderKind::Ollama, &ok).unwrap_err();
+//! assert!(matches!(nested, ConfigError::InvalidExtraOption { ref name, .. } if name == "logit_bias"));
+//!
+//! let conflict = validate(&ProviderKind::Ollama, &table("num_ctx = 4096")).unwrap_err();
+//! assert_eq!(conflict.key().as_deref(), Some("provider.extra_options.num_ctx"));
+//! assert!(validate(&ProviderKind::OpenAI, &table("stream = false")).is_err());
+//! assert!(validate(&ProviderKind::Claude, &table("when = 2024-01-01")).is_err());
+//!
+//! let cfg = AppConfig::new_default();
+//! let cfg = aion_core::config::edit::set(&cfg, "provider.extra_options.mirostat", "2").unwrap();
+//! assert_eq!(cfg.provider.extra_options["mirostat"].as_integer(), Some(2));
+//! assert!(aion_core::config::edit::set(&cfg, "provider.extra_options.top_p", "0.5").is_err());
+//! ```
+
+use super::{ConfigError, ProviderKind};
+
+/// Fields AION sends to `kind` where extra options go, and the setting that controls
+/// each (or what it is, when nothing does).
+pub fn reserved(kind: &ProviderKind) -> &'static [(&'static str, &'static str)] {
+    match kind {
+        ProviderKind::Ollama => &[
+            ("temperature", "provider.params.temperature"),
+            ("top_p", "provider.params.top_p"),
+            ("num_predict", "provider.params.max_tokens"),
+            ("num_ctx", "provider.params.num_ctx"),
+        ],
+        ProviderKind::OpenAI | ProviderKind::OpenRouter => &[
+            ("model", "provider.model"),
+            ("messages", "the conversation"),
+            ("stream", "streaming"),
+            ("stream_options", "streaming"),
+            ("temperature", "provider.params.temperature"),
+            ("top_p", "provider.params.top_p"),
+            ("max_tokens", "provider.params.max_tokens"),
+            ("response_format", "style.format"),
+        ],
+        ProviderKind::Claude => &[
+            ("model", "provider.model"),
+            ("messages", "the conversation"),
+            ("system", "the system prompt"),
+            ("stream", "streaming"),
+            ("temperature", "provider.params.temperature"),
+            ("top_p", "provider.params.top_p"),
+            ("max_tokens", "provider.params.max_tokens"),
+        ],
+    }
+}
+
+/// Levels of lists and tables a value may have: 1 allows a list of scalars.
+pub fn max_depth(kind: &ProviderKind) -> usize {
+    match kind {
+        ProviderKind::Ollama => 1,
+        _ => 2,
+    }
+}
+
+/// Whether values may be tables: Ollama's options may not.
+pub fn takes_tables(kind: &ProviderKind) -> bool {
+    !matches!(kind, ProviderKind::Ollama)
+}
+
+/// `extra` as `kind` can take it: no reserved names, no deeper nesting than it accepts.
+pub fn validate(kind: &ProviderKind, extra: &toml::Table) -> Result<(), ConfigError> {
+    for (name, value) in extra {
+        if let Some((_, setting)) = reserved(kind).iter().find(|(field, _)| field == name) {
+            return Err(ConfigError::ExtraOptionConflict { name: name.clone(), setting });
+        }
+        let invalid = |reason: String| ConfigError::InvalidExtraOption { name: name.clone(), reason };
+        if contains_datetime(value) {
+            return Err(invalid("dates and times cannot be sent as JSON".to_string()));
+        }
+        if depth(value) > max_depth(kind) || (!takes_tables(kind) && contains_table(value)) {
+            let levels = match max_depth(kind) {
+                1 => "a value or a list of values".to_string(),
+                n => format!("at most {} levels of lists and tables", n),
+            };
+            return Err(invalid(format!("{} takes {} here", kind.name(), levels)));
+        }
+    }
+    Ok(())
+}
+
+/// `extra` as the JSON fields of a request.
+pub fn to_json(extra: &toml::Table) -> serde_json::Map<String, serde_json::Value> {
+    extra.iter().map(|(name, value)| (name.clone(), json(value))).collect()
+}
+
+fn json(value: &toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => s.clone().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(d) => d.to_string().into(),
+        toml::Value::Array(items) => items.iter().map(json).collect::<Vec<_>>().into(),
+        toml::Value::Table(table) => serde_json::Value::Object(to_json(table)),
+    }
+}
+
+fn depth(value: &toml::Value) -> usize {
+    match value {
+        toml::Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        toml::Value::Table(table) => 1 + table.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+fn contains_table(value: &toml::Value) -> bool {
+    match value {
+        toml::Value::Table(_) => true,
+        toml::Value::Array(items) => items.iter().any(contains_table),
+        _ => false,
+    }
+}
+
+fn contains_datetime(value: &toml::Value) -> bool {
+    match value {
+        toml::Value::Datetime(_) => true,
+        toml::Value::Array(items) => items.iter().any(contains_datetime),
+        toml::Value::Table(table) => table.values().any(contains_datetime),
+        _ => false,
+    }
+}
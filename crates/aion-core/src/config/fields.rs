@@ -0,0 +1,262 @@
+//! Checks for the provider settings people type: model, base_url, api_key_env.
+//!
+//! Notes:
+//! - `AppConfig::validate` (and so `aion init` and `aion config set`) and the wizard's
+//!   hints under its inputs use the same functions, so a hint and the final check cannot
+//!   disagree.
+//! - A `Check` is fine (with what will be used), a warning (saved as typed, but likely a
+//!   mistake), or an error, the only kind `validate` refuses.
+//! - Whether the key variable is set is only ever a warning: a config written on one
+//!   machine may be used on another. Its value is never read.
+//! - `required` derives, from the same checks, which fields a kind cannot leave empty;
+//!   `switch` and `missing` use it for `/provider <kind>`, which asks only for those, and
+//!   the plain wizard for whether to ask for a key variable at all.
+//!
+//! ```
+//! use aion_core::config::fields::{api_key_env, base_url, model, Check};
+//! use aion_core::config::ProviderKind;
+//!
+//! assert!(matches!(base_url(&ProviderKind::Ollama, "http://[::1]:11434"), Check::Ok(_)));
+//! assert!(matches!(base_url(&ProviderKind::Ollama, "localhost:11434"), Check::Error(_)));
+//! assert!(matches!(base_url(&ProviderKind::Ollama, "http://[::1:11434"), Check::Error(_)));
+//! assert!(matches!(model(" llama3.1:8b "), Check::Warning(_)));
+//! assert!(matches!(api_key_env(&ProviderKind::OpenAI, "my.key"), Check::Warning(_)));
+//! assert!(matches!(api_key_env(&ProviderKind::OpenAI, "MY KEY"), Check::Error(_)));
+//! ```
+//!
+//! Switching a provider, per kind:
+//!
+//! ```
+//! use aion_core::config::fields::{missing, required, switch, Required};
+//! use aion_core::config::{AppConfig, ProviderKind};
+//!
+//! let ollama = AppConfig::new_default().provider;
+//! let expected = [
+//!     (ProviderKind::Ollama, vec![Required::BaseUrl]),
+//!     (ProviderKind::OpenAI, vec![Required::ApiKeyEnv]),
+//!     (ProviderKind::Claude, vec![Required::ApiKeyEnv]),
+//!     (ProviderKind::OpenRouter, vec![Required::BaseUrl, Required::ApiKeyEnv]),
+//! ];
+//! for (kind, fields) in expected {
+//!     assert_eq!(required(&kind), fields);
+//!     let from = match kind {
+//!         ProviderKind::Ollama => switch(&ollama, &ProviderKind::Claude, &[]),
+//!         _ => ollama.clone(),
+//!     };
+//!     let mut draft = switch(&from, &kind, &[]);
+//!     assert_eq!(draft.model, kind.default_model());
+//!     assert_eq!(missing(&draft), required(&kind));
+//!     for field in missing(&draft.clone()) {
+//!         field.set(&mut draft, field.default_for(&kind).unwrap());
+//!     }
+//!     assert!(draft.validate().is_ok(), "{:?}", kind);
+//! }
+//!
+//! // A provider of that kind the config already knows keeps its endpoint and key variable.
+//! let mut openai = ollama.clone();
+//! openai.kind = ProviderKind::OpenAI;
+//! openai.model = "gpt-4o".into();
+//! openai.api_key_env = Some("WORK_OPENAI_KEY".into());
+//! openai.base_url = None;
+//! let draft = switch(&ollama, &ProviderKind::OpenAI, &[&openai]);
+//! assert_eq!((draft.model.as_str(), draft.api_key_env.as_deref()), ("gpt-4o", Some("WORK_OPENAI_KEY")));
+//! assert!(missing(&draft).is_empty());
+//! ```
+
+use super::{ConfigError, ProviderConfig, ProviderKind};
+
+/// What a field check found.
+#[derive(Debug)]
+pub enum Check {
+    Ok(String),
+    Warning(String),
+    Error(ConfigError),
+}
+
+impl Check {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Check::Error(_))
+    }
+
+    /// The message to show next to the field.
+    pub fn message(&self) -> String {
+        match self {
+            Check::Ok(m) | Check::Warning(m) => m.clone(),
+            Check::Error(ConfigError::InvalidBaseUrl { reason, .. } | ConfigError::InvalidApiKeyEnv { reason, .. }) => {
+                reason.clone()
+            }
+            Check::Error(e) => e.to_string(),
+        }
+    }
+
+    /// `Err` for an error; warnings pass.
+    pub fn into_result(self) -> Result<(), ConfigError> {
+        match self {
+            Check::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn model(value: &str) -> Check {
+    let name = value.trim();
+    if name.is_empty() {
+        return Check::Error(ConfigError::EmptyModel);
+    }
+    if name != value {
+        return Check::Warning("leading and trailing spaces are removed".to_string());
+    }
+    if name.chars().any(char::is_uppercase) && !name.chars().any(char::is_lowercase) {
+        return Check::Warning("model names are usually lowercase".to_string());
+    }
+    Check::Ok("ok".to_string())
+}
+
+/// `value` as typed; empty means the provider's default, which some providers lack.
+pub fn base_url(kind: &ProviderKind, value: &str) -> Check {
+    let url = value.trim();
+    if url.is_empty() {
+        return match kind {
+            ProviderKind::Ollama | ProviderKind::OpenRouter => Check::Error(ConfigError::MissingBaseUrl),
+            _ => Check::Ok(format!("default: {}", kind.public_base_url())),
+        };
+    }
+    let invalid = |reason: String| Check::Error(ConfigError::InvalidBaseUrl { value: url.to_string(), reason });
+    // Without "://", "localhost:11434" would parse with "localhost" as its scheme.
+    let Some((scheme, _)) = url.split_once("://") else {
+        return invalid("missing scheme (e.g. http://)".to_string());
+    };
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return invalid(format!("unsupported scheme '{}' (use http or https)", scheme));
+    }
+    match url::Url::parse(url) {
+        Ok(parsed) if parsed.host().is_some() => Check::Ok(format!("ok: {}", url.trim_end_matches('/'))),
+        Ok(_) => invalid("missing host".to_string()),
+        Err(e) => invalid(e.to_string()),
+    }
+}
+
+/// The name of the variable holding the API key; not needed by Ollama.
+pub fn api_key_env(kind: &ProviderKind, value: &str) -> Check {
+    let name = value.trim();
+    if name.is_empty() {
+        return match kind {
+            ProviderKind::Ollama => Check::Ok("not needed".to_string()),
+            _ => Check::Error(ConfigError::MissingApiKeyEnv),
+        };
+    }
+    if name.contains(['=', '\0']) || name.chars().any(char::is_whitespace) {
+        return Check::Error(ConfigError::InvalidApiKeyEnv {
+            value: name.to_string(),
+            reason: "a variable name cannot contain '=', spaces, or NUL".to_string(),
+        });
+    }
+    let portable = !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !portable {
+        return Check::Warning(format!("most shells cannot set {} (use letters, digits, and _)", name));
+    }
+    match std::env::var_os(name) {
+        Some(_) => Check::Ok(format!("{} is set", name)),
+        None => Check::Warning(format!("{} is not set in this environment", name)),
+    }
+}
+
+/* --- switching provider --- */
+
+/// A provider field that some kinds cannot leave empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Required {
+    BaseUrl,
+    ApiKeyEnv,
+}
+
+impl Required {
+    /// The key under `provider`, e.g. `base_url`.
+    pub fn key(self) -> &'static str {
+        match self {
+            Required::BaseUrl => "base_url",
+            Required::ApiKeyEnv => "api_key_env",
+        }
+    }
+
+    /// As the wizard labels the field.
+    pub fn label(self) -> &'static str {
+        match self {
+            Required::BaseUrl => "Endpoint (base_url)",
+            Required::ApiKeyEnv => "API key variable (api_key_env)",
+        }
+    }
+
+    /// The value to offer for `kind`.
+    pub fn default_for(self, kind: &ProviderKind) -> Option<&'static str> {
+        match self {
+            Required::BaseUrl => kind.default_base_url(),
+            Required::ApiKeyEnv => kind.default_api_key_env(),
+        }
+    }
+
+    pub fn check(self, kind: &ProviderKind, value: &str) -> Check {
+        match self {
+            Required::BaseUrl => base_url(kind, value),
+            Required::ApiKeyEnv => api_key_env(kind, value),
+        }
+    }
+
+    pub fn value(self, provider: &ProviderConfig) -> Option<&str> {
+        match self {
+            Required::BaseUrl => provider.base_url.as_deref(),
+            Required::ApiKeyEnv => provider.api_key_env.as_deref(),
+        }
+    }
+
+    /// Set the field to `value`, trimmed; empty clears it.
+    pub fn set(self, provider: &mut ProviderConfig, value: &str) {
+        let value = Some(value.trim()).filter(|v| !v.is_empty()).map(str::to_string);
+        match self {
+            Required::BaseUrl => provider.base_url = value,
+            Required::ApiKeyEnv => provider.api_key_env = value,
+        }
+    }
+}
+
+/// The fields `kind` cannot leave empty: those whose check fails on an empty value.
+pub fn required(kind: &ProviderKind) -> Vec<Required> {
+    [Required::BaseUrl, Required::ApiKeyEnv].into_iter().filter(|f| f.check(kind, "").is_error()).collect()
+}
+
+/// The fields `provider`'s kind requires that it leaves empty.
+pub fn missing(provider: &ProviderConfig) -> Vec<Required> {
+    required(&provider.kind)
+        .into_iter()
+        .filter(|f| f.value(provider).is_none_or(|v| v.trim().is_empty()))
+        .collect()
+}
+
+/// `current` moved to `kind`. Model, endpoint, key variable, and extra options come from
+/// the first of `known` of that kind (a fallback, the provider a session started with);
+/// otherwise the model is the kind's default, there are no extra options, and endpoint and
+/// key variable are left for `missing` to report. The rest (parameters, reply language,
+/// scope IDs, fallbacks) is kept.
+pub fn switch(current: &ProviderConfig, kind: &ProviderKind, known: &[&ProviderConfig]) -> ProviderConfig {
+    let mut next = current.clone();
+    if current.kind == *kind {
+        return next;
+    }
+    next.kind = kind.clone();
+    match known.iter().find(|p| p.kind == *kind) {
+        Some(known) => {
+            next.model = known.model.clone();
+            next.base_url = known.base_url.clone();
+            next.api_key_env = known.api_key_env.clone();
+            next.extra_options = known.extra_options.clone();
+        }
+        None => {
+            next.model = kind.default_model().to_string();
+            next.base_url = None;
+            next.api_key_env = None;
+            next.extra_options.clear();
+        }
+    }
+    next
+}
@@ -0,0 +1,246 @@
+//! `include = ["./team-aion.toml"]`: config files layered under the one that lists them.
+//!
+//! Notes:
+//! - The listed files are read and deep-merged in order, then the including file's own
+//!   keys on top: later files win over earlier ones, and the including file over all of
+//!   them. Tables merge key by key; arrays and other values are replaced whole.
+//! - A relative path is resolved against the directory of the file that lists it. A
+//!   path ending in `?` is optional: a missing file is skipped. Any other missing file
+//!   is an error, as is a file that does not parse.
+//! - Included files may include others, up to `MAX_DEPTH` levels. A file that includes
+//!   itself, directly or through others, is an error naming the whole chain.
+//! - Only the including file's `include` key is kept in the result; the included files'
+//!   are consumed by the merge. `save_config` writes back only what differs from the
+//!   included values (`prune`), so saving never copies a shared setting into the file.
+//! - `Included::origins` says which file each included value came from, for the config
+//!   tree (`tree::Source::Included`) and for pointing validation errors at that file.
+//!
+//! ```
+//! use aion_core::config::include::{resolve, IncludeError};
+//! use std::path::Path;
+//!
+//! let root = std::env::temp_dir().join(format!("aion-include-{}", std::process::id()));
+//! std::fs::create_dir_all(root.join("team")).unwrap();
+//! let write = |name: &str, text: &str| std::fs::write(root.join(name), text).unwrap();
+//! write("team/base.toml", "language = \"ar\"\n[provider]\nkind = \"Ollama\"\nmodel = \"llama3\"\n");
+//! write("team/aion.toml", "include = [\"base.toml\"]\n[provider]\nmodel = \"qwen2.5\"\n");
+//! let main = toml::from_str("include = [\"team/aion.toml\", \"local.toml?\"]\n[ui]\nascii_only = true\n").unwrap();
+//!
+//! let layered = resolve(&root.join("config.toml"), main).unwrap();
+//! assert_eq!(layered.table["language"].as_str(), Some("ar"));
+//! assert_eq!(layered.table["provider"]["model"].as_str(), Some("qwen2.5"));
+//! assert_eq!(layered.table["provider"]["kind"].as_str(), Some("Ollama"));
+//! assert_eq!(layered.table["ui"]["ascii_only"].as_bool(), Some(true));
+//! assert_eq!(layered.table["include"].as_array().unwrap().len(), 2);
+//! assert!(layered.included.base.get("ui").is_none());
+//! assert_eq!(layered.included.origin("provider.model"), Some(root.join("team/aion.toml").as_path()));
+//! assert_eq!(layered.included.origin("provider.kind"), Some(root.join("team/base.toml").as_path()));
+//! assert_eq!(layered.included.origin("ui.ascii_only"), None);
+//!
+//! // Missing without `?`, and a cycle through two files.
+//! let missing = toml::from_str("include = [\"nowhere.toml\"]").unwrap();
+//! let err = resolve(&root.join("config.toml"), missing).unwrap_err();
+//! assert!(matches!(err, IncludeError::Missing { .. }), "{}", err);
+//! write("a.toml", "include = [\"b.toml\"]");
+//! write("b.toml", "include = [\"./a.toml\"]");
+//! let cycle = toml::from_str("include = [\"a.toml\"]").unwrap();
+//! let err = resolve(&root.join("config.toml"), cycle).unwrap_err();
+//! assert!(matches!(&err, IncludeError::Cycle(files) if files.len() == 4 && files[1] == files[3]), "{}", err);
+//! assert!(err.to_string().starts_with("include cycle: ") && err.to_string().ends_with("a.toml"));
+//!
+//! // Each file including the next, one level too many.
+//! for n in 0..=8 {
+//!     write(&format!("deep{}.toml", n), &format!("include = [\"deep{}.toml?\"]", n + 1));
+//! }
+//! let deep = toml::from_str("include = [\"deep0.toml\"]").unwrap();
+//! assert!(matches!(resolve(&root.join("config.toml"), deep), Err(IncludeError::TooDeep(_))));
+//! # std::fs::remove_dir_all(&root).unwrap();
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// How many files deep includes may nest, not counting the config file itself.
+pub const MAX_DEPTH: usize = 8;
+
+const KEY: &str = "include";
+
+#[derive(Debug, thiserror::Error)]
+pub enum IncludeError {
+    #[error("{}: `include` must be a list of file paths", .0.display())]
+    NotAList(PathBuf),
+
+    #[error("{} includes {}, which does not exist (end the path with `?` if it is optional)", .from.display(), .path.display())]
+    Missing { from: PathBuf, path: PathBuf },
+
+    #[error("failed to read included config file {}: {reason}", .path.display())]
+    Unreadable { path: PathBuf, reason: String },
+
+    #[error("include cycle: {}", chain(.0))]
+    Cycle(Vec<PathBuf>),
+
+    #[error("includes nest more than {} deep: {}", MAX_DEPTH, chain(.0))]
+    TooDeep(Vec<PathBuf>),
+}
+
+fn chain(files: &[PathBuf]) -> String {
+    let mut out = String::new();
+    for (i, file) in files.iter().enumerate() {
+        let _ = write!(out, "{}{}", if i == 0 { "" } else { " -> " }, file.display());
+    }
+    out
+}
+
+/// What the included files contribute to a config file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Included {
+    /// The included files merged, without the including file's own keys.
+    pub base: toml::Table,
+    /// Dotted key of each included value (as `tree` and `ConfigError::key` write them)
+    /// to the file it came from. Keys the including file sets itself are not here.
+    pub origins: BTreeMap<String, PathBuf>,
+}
+
+impl Included {
+    /// The file `key` came from, when an included one. `provider.fallbacks[1].model` is
+    /// found under `provider.fallbacks`.
+    pub fn origin(&self, key: &str) -> Option<&Path> {
+        let whole = key.split('[').next().unwrap_or(key);
+        self.origins.get(key).or_else(|| self.origins.get(whole)).map(PathBuf::as_path)
+    }
+}
+
+/// A config file with its includes applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layered {
+    /// Everything merged; the file's own `include` key is kept.
+    pub table: toml::Table,
+    pub included: Included,
+}
+
+/// Apply the includes of `table`, read from the file at `path`.
+pub fn resolve(path: &Path, table: toml::Table) -> Result<Layered, IncludeError> {
+    let start = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut stack = vec![start];
+    let own_include = table.get(KEY).cloned();
+    let (mut merged, mut origins, base) = layer(path, table, &mut stack)?;
+    origins.retain(|_, file| file != path);
+    if let Some(list) = own_include {
+        merged.insert(KEY.to_string(), list);
+    }
+    Ok(Layered { table: merged, included: Included { base, origins } })
+}
+
+/// `table` from `path` over its includes: the merged table, where each value came from,
+/// and the includes alone.
+fn layer(
+    path: &Path,
+    mut table: toml::Table,
+    stack: &mut Vec<PathBuf>,
+) -> Result<(toml::Table, BTreeMap<String, PathBuf>, toml::Table), IncludeError> {
+    let entries = match table.remove(KEY) {
+        None => Vec::new(),
+        Some(toml::Value::Array(items)) => items
+            .into_iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| IncludeError::NotAList(path.to_path_buf()))?,
+        Some(_) => return Err(IncludeError::NotAList(path.to_path_buf())),
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut merged = toml::Table::new();
+    let mut origins = BTreeMap::new();
+    for entry in entries {
+        let (name, optional) = match entry.strip_suffix('?') {
+            Some(name) => (name, true),
+            None => (entry.as_str(), false),
+        };
+        let file = dir.join(name);
+        if !file.exists() {
+            if optional {
+                continue;
+            }
+            return Err(IncludeError::Missing { from: path.to_path_buf(), path: file });
+        }
+        let canonical = file.canonicalize().unwrap_or_else(|_| file.clone());
+        if stack.contains(&canonical) {
+            let mut files = stack.clone();
+            files.push(canonical);
+            return Err(IncludeError::Cycle(files));
+        }
+        if stack.len() > MAX_DEPTH {
+            let mut files = stack.clone();
+            files.push(canonical);
+            return Err(IncludeError::TooDeep(files));
+        }
+        let unreadable = |reason: String| IncludeError::Unreadable { path: file.clone(), reason };
+        let text = std::fs::read_to_string(&file).map_err(|e| unreadable(e.to_string()))?;
+        let included: toml::Table = toml::from_str(&text).map_err(|e| unreadable(e.to_string()))?;
+
+        stack.push(canonical);
+        let (table, from, _) = layer(&file, included, stack)?;
+        stack.pop();
+        origins.extend(from);
+        merge(&mut merged, table);
+    }
+    let base = merged.clone();
+    record(&mut origins, "", &table, path);
+    merge(&mut merged, table);
+    Ok((merged, origins, base))
+}
+
+/// `over` merged into `under`: tables key by key, anything else replaced.
+fn merge(under: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (under.get_mut(&key), value) {
+            (Some(toml::Value::Table(inner)), toml::Value::Table(value)) => merge(inner, value),
+            (_, value) => {
+                under.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Mark every value in `table` as coming from `file`.
+fn record(origins: &mut BTreeMap<String, PathBuf>, prefix: &str, table: &toml::Table, file: &Path) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            toml::Value::Table(inner) => record(origins, &path, inner, file),
+            _ => {
+                // Replacing an included table replaces every key in it.
+                origins.retain(|k, _| !k.starts_with(&format!("{}.", path)));
+                origins.insert(path, file.to_path_buf());
+            }
+        }
+    }
+}
+
+/// Drop from `doc` (a rendered config) the values equal to the included ones in `base`,
+/// and the tables left empty. `config` is the same config as a plain table.
+pub fn prune(doc: &mut dyn toml_edit::TableLike, config: &toml::Table, base: &toml::Table) {
+    for (key, included) in base {
+        if key == KEY || key == "version" {
+            continue;
+        }
+        match (config.get(key), included) {
+            (Some(toml::Value::Table(own)), toml::Value::Table(included)) => {
+                let empty = match doc.get_mut(key).and_then(|item| item.as_table_like_mut()) {
+                    Some(inner) => {
+                        prune(inner, own, included);
+                        inner.is_empty()
+                    }
+                    None => false,
+                };
+                if empty {
+                    doc.remove(key);
+                }
+            }
+            (Some(own), included) if own == included => {
+                doc.remove(key);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::audit;
+use crate::paths::config_dir;
+use crate::read_only;
+use crate::recent;
+use crate::config::locate::InvalidConfigFile;
+use crate::config::include::{self, Included, Layered};
+use crate::config::{diff, expand, migrate, AppConfig, ConfigError};
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// `--config <FILE>`: used instead of `config.toml` in the config directory.
+static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// The fields the last `load_config` expanded, for `render_config` to write back as
+/// they were in the file.
+static EXPANDED: Mutex<Vec<expand::Expanded>> = Mutex::new(Vec::new());
+
+/// What the last `load_config` took from included files, for `render_config` to leave
+/// out of the file again.
+static INCLUDED: Mutex<Option<Included>> = Mutex::new(None);
+
+/// Read and write the config at `path` for the rest of the process. Only the config
+/// file moves; templates and locales stay in `config_dir`, state and cache in theirs
+/// (`crate::paths`). Set once,
+/// before anything loads the config; later calls are ignored.
+pub fn set_config_file(path: PathBuf) {
+    let _ = CONFIG_FILE.set(path);
+}
+
+pub fn config_file_path() -> Result<PathBuf> {
+    match CONFIG_FILE.get() {
+        Some(path) => Ok(path.clone()),
+        None => Ok(config_dir()?.join(CONFIG_FILE_NAME)),
+    }
+}
+
+pub fn ensure_config_dir_exists() -> Result<()> {
+    let dir = config_dir()?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create config directory: {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// The config file as a TOML table, before includes, migrations, and validation.
+pub fn load_raw_config() -> Result<toml::Table> {
+    let path = config_file_path()?;
+
+    if !path.exists() {
+        return Err(anyhow::anyhow!("config file does not exist"));
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read config file: {}", path.display()))?;
+
+    toml::from_str(&content).with_context(|| format!("failed to parse config file: {}", path.display()))
+}
+
+/// The config file with its includes merged in (see `include`), before migrations and
+/// validation.
+pub fn load_layered_config() -> Result<Layered> {
+    let path = config_file_path()?;
+    let table = load_raw_config()?;
+    Ok(include::resolve(&path, table)?)
+}
+
+/// Load the config, upgrading older versions and expanding `${VAR}` / `~` in memory
+/// (the file is left as is; see `expand`). Errors in the file's values carry
+/// `InvalidConfigFile`, so they can be shown at their line (see `locate`): in the config
+/// file, or in the included file the value came from. In read-only mode the result is
+/// what the mode allows (see `read_only::apply`).
+pub fn load_config() -> Result<AppConfig> {
+    let path = config_file_path()?;
+    let (mut config, included) = parse_config_file(&path)?;
+    let in_file = |e: ConfigError| {
+        let path = e.key().and_then(|key| included.origin(&key).map(Path::to_path_buf)).unwrap_or_else(|| path.clone());
+        anyhow::Error::new(e).context(InvalidConfigFile { path })
+    };
+    let expanded = expand::apply(&mut config).map_err(in_file)?;
+    *EXPANDED.lock().unwrap_or_else(|e| e.into_inner()) = expanded;
+    config.validate().map_err(in_file)?;
+    *INCLUDED.lock().unwrap_or_else(|e| e.into_inner()) = Some(included);
+    read_only::apply(&mut config);
+    Ok(config)
+}
+
+/// `load_config` without validation, for `aion config set` to repair a file that fails
+/// it (the edited config is validated as a whole). Fields that cannot be expanded are
+/// kept as written.
+pub fn load_config_unvalidated() -> Result<AppConfig> {
+    let (config, included) = parse_config_file(&config_file_path()?)?;
+    *INCLUDED.lock().unwrap_or_else(|e| e.into_inner()) = Some(included);
+    let mut expanded = config.clone();
+    let (mut config, fields) = match expand::apply(&mut expanded) {
+        Ok(fields) => (expanded, fields),
+        Err(_) => (config, Vec::new()),
+    };
+    *EXPANDED.lock().unwrap_or_else(|e| e.into_inner()) = fields;
+    read_only::apply(&mut config);
+    Ok(config)
+}
+
+fn parse_config_file(path: &Path) -> Result<(AppConfig, Included)> {
+    let Layered { mut table, included } = load_layered_config()?;
+    migrate::upgrade(&mut table)?;
+    let config = toml::Value::Table(table)
+        .try_into()
+        .with_context(|| format!("failed to parse config file: {}", path.display()))?;
+    Ok((config, included))
+}
+
+/// The fields the last `load_config` expanded from `${VAR}` / `~`.
+pub fn expanded_fields() -> Vec<expand::Expanded> {
+    EXPANDED.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// What the last `load_config` took from included files (nothing before one).
+pub fn included_fields() -> Included {
+    INCLUDED.lock().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// The exact text `save_config` writes for `config`, stamped with the current version.
+/// Fields `load_config` expanded and still unchanged are written as their template, and
+/// values it took from included files and still unchanged are left out.
+pub fn render_config(config: &AppConfig) -> Result<String> {
+    let mut config = AppConfig {
+        version: AppConfig::CURRENT_VERSION,
+        ..config.clone()
+    };
+    expand::restore(&mut config, &EXPANDED.lock().unwrap_or_else(|e| e.into_inner()));
+    let text = toml::to_string_pretty(&config).context("failed to serialize config to TOML")?;
+    let included = included_fields();
+    if included.base.is_empty() {
+        return Ok(text);
+    }
+    let mut doc: toml_edit::DocumentMut = text.parse().context("failed to serialize config to TOML")?;
+    include::prune(doc.as_table_mut(), &diff::to_table(&config)?, &included.base);
+    Ok(doc.to_string())
+}
+
+/// Check that the config file can be written, without changing it: creates its
+/// directory if needed, then opens the file for appending, or creates and removes a
+/// probe file next to it when there is no file yet.
+pub fn check_writable(path: &Path) -> Result<(), ConfigError> {
+    let read_only = |e: std::io::Error| ConfigError::ReadOnly {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    };
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir).map_err(read_only)?;
+    if path.exists() {
+        OpenOptions::new().append(true).open(path).map_err(read_only)?;
+    } else {
+        let probe = dir.join(format!(".{}.probe", CONFIG_FILE_NAME));
+        fs::write(&probe, b"").map_err(read_only)?;
+        let _ = fs::remove_file(&probe);
+    }
+    Ok(())
+}
+
+/// Write the config. The file is always stamped with the current version, and the
+/// provider/model pair is added to `recent`. A location that cannot be written fails
+/// with `ConfigError::ReadOnly` before anything is touched, and read-only mode with
+/// `read_only::ReadOnlyMode`.
+pub fn save_config(config: &AppConfig) -> Result<()> {
+    read_only::check("save_config")?;
+    let path = config_file_path()?;
+    check_writable(&path)?;
+    let toml_str = render_config(config)?;
+
+    fs::write(&path, toml_str)
+        .with_context(|| format!("failed to write config file: {}", path.display()))?;
+    audit::file_write(&path);
+    // The model list is a convenience; failing to update it does not fail the save.
+    let _ = recent::record(&config.provider.kind, &config.provider.model);
+
+    Ok(())
+}
+
+/// Load the config, or write the default one. When the default cannot be written
+/// (e.g. a read-only config directory), it is used in memory for this run with a
+/// one-line warning; later saves fail with `ConfigError::ReadOnly`. Read-only mode uses
+/// it without trying.
+pub fn load_or_create_config() -> Result<AppConfig> {
+    match load_config() {
+        Ok(config) => Ok(config),
+        Err(_) => {
+            let mut config = AppConfig::new_default();
+            read_only::apply(&mut config);
+            if read_only::active().is_some() {
+                return Ok(config);
+            }
+            if let Err(e) = save_config(&config) {
+                eprintln!("Warning: using default settings for this run; config not saved: {:#}", e);
+            }
+            Ok(config)
+        }
+    }
+}
+
+pub fn config_exists() -> Result<bool> {
+    let path = config_file_path()?;
+    Ok(path.exists())
+}
\ No newline at end of file
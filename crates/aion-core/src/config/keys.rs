@@ -0,0 +1,222 @@
+//! Key bindings for the setup wizard (`[keybindings]` in config.toml).
+//!
+//! Notes:
+//! - Each action maps to one key spec or a list of them: `"q"`, `"ctrl+q"`, `"esc"`,
+//!   `"f2"`, `["esc", "left"]`. Names are case-insensitive except single characters.
+//! - `shift+<letter>` is stored as the uppercase letter, which is what terminals send.
+//! - The defaults are the wizard's original keys; `jump_step_n` is unbound by default.
+//! - Specs are plain data here; the TUI matches them against terminal key events.
+
+use super::ConfigError;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Esc,
+    Backspace,
+    Tab,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+/// One key plus modifiers, e.g. `ctrl+q`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySpec {
+    pub key: Key,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid key '{0}'")]
+pub struct InvalidKeySpec(pub String);
+
+impl FromStr for KeySpec {
+    type Err = InvalidKeySpec;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidKeySpec(s.to_string());
+        let mut spec = KeySpec { key: Key::Enter, ctrl: false, alt: false };
+        let mut shift = false;
+
+        let s = s.trim();
+        // The `+` key itself: "+" or "ctrl++".
+        let (modifiers, name) = match s.strip_suffix("++") {
+            Some(m) => (m, "+"),
+            None if s == "+" => ("", "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        if !modifiers.is_empty() {
+            for m in modifiers.split('+') {
+                match m.to_ascii_lowercase().as_str() {
+                    "ctrl" | "control" => spec.ctrl = true,
+                    "alt" | "meta" => spec.alt = true,
+                    "shift" => shift = true,
+                    _ => return Err(invalid()),
+                }
+            }
+        }
+
+        let mut chars = name.chars();
+        spec.key = match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_control() && !c.is_whitespace() => {
+                Key::Char(if shift { c.to_ascii_uppercase() } else { c })
+            }
+            _ => match name.to_ascii_lowercase().as_str() {
+                "enter" | "return" => Key::Enter,
+                "esc" | "escape" => Key::Esc,
+                "backspace" => Key::Backspace,
+                "tab" => Key::Tab,
+                "delete" | "del" => Key::Delete,
+                "space" => Key::Char(' '),
+                "up" => Key::Up,
+                "down" => Key::Down,
+                "left" => Key::Left,
+                "right" => Key::Right,
+                "home" => Key::Home,
+                "end" => Key::End,
+                "pageup" | "pgup" => Key::PageUp,
+                "pagedown" | "pgdn" => Key::PageDown,
+                lower => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=24).contains(&n) => Key::F(n),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("ctrl+")?;
+        }
+        if self.alt {
+            f.write_str("alt+")?;
+        }
+        match self.key {
+            Key::Char(' ') => f.write_str("space"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Enter => f.write_str("enter"),
+            Key::Esc => f.write_str("esc"),
+            Key::Backspace => f.write_str("backspace"),
+            Key::Tab => f.write_str("tab"),
+            Key::Delete => f.write_str("delete"),
+            Key::Up => f.write_str("up"),
+            Key::Down => f.write_str("down"),
+            Key::Left => f.write_str("left"),
+            Key::Right => f.write_str("right"),
+            Key::Home => f.write_str("home"),
+            Key::End => f.write_str("end"),
+            Key::PageUp => f.write_str("pageup"),
+            Key::PageDown => f.write_str("pagedown"),
+            Key::F(n) => write!(f, "f{}", n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(deserialize_with = "one_or_many")]
+    pub quit: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub back: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub next: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub toggle_colors: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub toggle_animation: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub jump_step_1: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub jump_step_2: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub jump_step_3: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub jump_step_4: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = |specs: &[&str]| specs.iter().map(|s| s.to_string()).collect();
+        Self {
+            quit: keys(&["q"]),
+            back: keys(&["esc", "backspace", "left", "b"]),
+            next: keys(&["enter"]),
+            toggle_colors: keys(&["c", "C"]),
+            toggle_animation: keys(&["a", "A"]),
+            jump_step_1: Vec::new(),
+            jump_step_2: Vec::new(),
+            jump_step_3: Vec::new(),
+            jump_step_4: Vec::new(),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Every action with its configured specs, in a fixed order.
+    pub fn entries(&self) -> [(&'static str, &[String]); 9] {
+        [
+            ("quit", &self.quit),
+            ("back", &self.back),
+            ("next", &self.next),
+            ("toggle_colors", &self.toggle_colors),
+            ("toggle_animation", &self.toggle_animation),
+            ("jump_step_1", &self.jump_step_1),
+            ("jump_step_2", &self.jump_step_2),
+            ("jump_step_3", &self.jump_step_3),
+            ("jump_step_4", &self.jump_step_4),
+        ]
+    }
+
+    /// Every spec parses and no key is bound twice.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut seen: Vec<(KeySpec, &'static str)> = Vec::new();
+        for (action, specs) in self.entries() {
+            for text in specs {
+                let spec: KeySpec = text.parse().map_err(|_| ConfigError::InvalidKey {
+                    action,
+                    spec: text.clone(),
+                })?;
+                if let Some((_, first)) = seen.iter().find(|(s, _)| *s == spec) {
+                    return Err(ConfigError::DuplicateKey {
+                        spec: spec.to_string(),
+                        first,
+                        second: action,
+                    });
+                }
+                seen.push((spec, action));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accept `key = "esc"` as well as `key = ["esc", "left"]`.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
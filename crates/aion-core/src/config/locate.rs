@@ -0,0 +1,332 @@
+//! Config errors shown where they are: the file, the line of the offending key, its
+//! current value, and a command that fixes it.
+//!
+//! Notes:
+//! - `load_config` marks the errors it finds in the file with `InvalidConfigFile`; errors
+//!   from flags or `/config set` have no line to point at and are shown as before.
+//! - Keys are written as `ConfigError::key` gives them (`provider.fallbacks[1].model`).
+//!   They are looked up in the file with `toml_edit`, which keeps spans.
+//! - A key that is not in the file (`provider.base_url` left out) is shown at the
+//!   nearest table that is, e.g. `[provider]`.
+//! - Hints name `aion config set`, which loads the file without validating it so a bad
+//!   value can be replaced.
+//!
+//! ```
+//! use aion_core::config::locate::{locate, Diagnostic};
+//! use aion_core::config::{AppConfig, ConfigError};
+//! use std::path::Path;
+//!
+//! let file = |text: &str| -> (String, ConfigError) {
+//!     let cfg: AppConfig = toml::from_str(text).unwrap();
+//!     (text.to_string(), cfg.validate().unwrap_err())
+//! };
+//! let base = toml::to_string(&AppConfig::new_default()).unwrap();
+//! let render = |text: &str, err: &ConfigError| Diagnostic::new(err, Path::new("/etc/aion.toml"), text).render("en");
+//!
+//! // A value out of range: its line, as written, and a value to set instead.
+//! let (text, err) = file(&base.replace("[provider]\n", "[provider]\nparams = { temperature = 3.5 }\n"));
+//! let line = text.lines().position(|l| l.starts_with("params")).unwrap() + 1;
+//! assert_eq!(
+//!     render(&text, &err),
+//!     format!(
+//!         "  at /etc/aion.toml, line {}\n  provider.params.temperature = 3.5\n  \
+//!          fix: run `aion config set provider.params.temperature <value>` with a value in 0 to 2, \
+//!          or delete it to use the default",
+//!         line
+//!     )
+//! );
+//!
+//! // A key left out is shown at its table, with an example for the provider.
+//! let (text, err) = file(&base.replace("kind = \"Ollama\"", "kind = \"OpenRouter\"").replace("base_url = \"http://localhost:11434\"\n", ""));
+//! let header = text.lines().position(|l| l == "[provider]").unwrap() + 1;
+//! assert!(matches!(err, ConfigError::MissingBaseUrl));
+//! assert_eq!(
+//!     render(&text, &err),
+//!     format!(
+//!         "  at /etc/aion.toml, line {} ([provider])\n  provider.base_url is not set\n  \
+//!          fix: run `aion config set provider.base_url https://openrouter.ai/api/v1`",
+//!         header
+//!     )
+//! );
+//!
+//! // Fallbacks count from 1, as in the error message.
+//! let text = format!("{}\n[[provider.fallbacks]]\nkind = \"openai\"\nmodel = \"\"\n", base);
+//! let (text, err) = file(&text);
+//! assert_eq!(err.key().as_deref(), Some("provider.fallbacks[1].model"));
+//! let found = locate(&text, "provider.fallbacks[1].model").unwrap();
+//! assert_eq!((found.line, found.value.as_deref()), (text.lines().count(), Some("\"\"")));
+//! assert!(render(&text, &err).ends_with("fix: run `aion config set provider.fallbacks[1].model gpt-4.1-mini` \
+//!                                        (see `aion models`), or `aion --setup --step model`"));
+//!
+//! // `${VAR}` errors come from `expand`, with the field as written.
+//! let text = base.replace("http://localhost:11434", "${AION_DOC_UNSET}");
+//! let mut cfg: AppConfig = toml::from_str(&text).unwrap();
+//! let err = aion_core::config::expand::apply(&mut cfg).unwrap_err();
+//! assert!(render(&text, &err).contains("provider.base_url = \"${AION_DOC_UNSET}\"\n  fix: export AION_DOC_UNSET"));
+//!
+//! // Other variants, by the key and line they point at.
+//! for (edit, key, value) in [
+//!     (("language = \"en\"", "language = \"xx\""), "language", "\"xx\""),
+//!     (("model = \"mistral\"", "model = \" \""), "provider.model", "\" \""),
+//!     (("base_url = \"http://localhost:11434\"", "base_url = \"localhost\""), "provider.base_url", "\"localhost\""),
+//!     (("[provider]\n", "[provider]\napi_key_env = \"MY KEY\"\n"), "provider.api_key_env", "\"MY KEY\""),
+//!     (("[provider]\n", "[provider]\nproject = \"a b\"\n"), "provider.project", "\"a b\""),
+//!     (("quit = [\"q\"]", "quit = [\"ctrl+\"]"), "keybindings.quit", "[\"ctrl+\"]"),
+//!     (("[style]\n", "[style]\ntone = \"loud\"\n"), "style.tone", "\"loud\""),
+//!     (("version = 1", "version = 99"), "version", "99"),
+//!     (("next = [\"enter\"]", "next = [\"q\"]"), "keybindings.next", "[\"q\"]"),
+//! ] {
+//!     let text = base.replacen(edit.0, edit.1, 1);
+//!     let (text, err) = file(&text);
+//!     assert_eq!(err.key().as_deref(), Some(key), "{}", err);
+//!     let found = locate(&text, key).unwrap();
+//!     assert!(found.found);
+//!     assert_eq!(found.value.as_deref(), Some(value));
+//!     assert!(text.lines().nth(found.line - 1).unwrap().contains(value));
+//!     assert!(render(&text, &err).contains("fix: "), "{}", key);
+//! }
+//! ```
+
+use super::{ConfigError, ProviderKind};
+use crate::i18n::{self, format::tilde};
+use std::path::{Path, PathBuf};
+use toml_edit::{ImDocument, Item};
+
+/// Context `load_config` puts on the `ConfigError`s it finds in the file at `path`.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("config validation failed")]
+pub struct InvalidConfigFile {
+    pub path: PathBuf,
+}
+
+/// Where a key is in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// 1-based: the key's line, or the header of the nearest table holding it.
+    pub line: usize,
+    /// Whether the key itself was found, rather than a table it belongs in.
+    pub found: bool,
+    /// The dotted name of what is at `line`: the key, or that table.
+    pub key: String,
+    /// The value as written, when the key is set to one (not a table).
+    pub value: Option<String>,
+}
+
+/// Find dotted `key` in the TOML `text`. `None` when the text does not parse, or
+/// neither the key nor any table on its path has a line of its own.
+pub fn locate(text: &str, key: &str) -> Option<Location> {
+    let doc = ImDocument::parse(text).ok()?;
+    let mut item = doc.as_item();
+    let mut best: Option<Location> = None;
+    let mut path: Vec<&str> = Vec::new();
+    for part in key.split('.') {
+        let (name, index) = match part.strip_suffix(']').and_then(|p| p.split_once('[')) {
+            Some((name, n)) => (name, Some(n.parse::<usize>().ok()?.checked_sub(1)?)),
+            None => (part, None),
+        };
+        let Some((found_key, child)) = item.as_table_like().and_then(|t| t.get_key_value(name)) else {
+            return best;
+        };
+        let child = match index {
+            None => child,
+            Some(i) => match child.get(i) {
+                Some(child) => child,
+                None => return best,
+            },
+        };
+        path.push(part);
+        let span = match child {
+            Item::Value(_) => found_key.span(),
+            _ => child.span().or_else(|| found_key.span()),
+        };
+        if let Some(span) = span {
+            best = Some(Location { line: line_of(text, span.start), found: false, key: path.join("."), value: None });
+        }
+        item = child;
+    }
+    let mut location = best?;
+    location.found = location.key == key;
+    if location.found {
+        location.value = item.as_value().and_then(|v| v.span()).map(|span| text[span].trim().to_string());
+    }
+    Some(location)
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+/// A `ConfigError` found in the config file, with where it is and how to fix it.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub key: Option<String>,
+    pub location: Option<Location>,
+    hint: Option<Hint>,
+}
+
+#[derive(Debug, Clone)]
+struct Hint {
+    name: &'static str,
+    fallback: &'static str,
+    vars: Vec<(&'static str, String)>,
+}
+
+impl Diagnostic {
+    /// `err`, found in the file at `path` whose contents are `text`.
+    pub fn new(err: &ConfigError, path: &Path, text: &str) -> Self {
+        let key = err.key();
+        let location = key.as_deref().and_then(|k| locate(text, k));
+        let hint = key.as_deref().and_then(|k| hint(err, k, text));
+        Self { path: path.to_path_buf(), key, location, hint }
+    }
+
+    /// The command or edit that fixes the error, in `lang`.
+    pub fn hint(&self, lang: &str) -> Option<String> {
+        let h = self.hint.as_ref()?;
+        Some(i18n::text(lang, &format!("config_fix.{}", h.name), h.fallback, &h.vars))
+    }
+
+    /// Lines to show under the error message, indented, in `lang`.
+    pub fn render(&self, lang: &str) -> String {
+        let path = ("path", tilde(&self.path));
+        let mut lines = Vec::new();
+        match (&self.location, &self.key) {
+            (Some(at), Some(key)) if at.found => {
+                let line = ("line", at.line.to_string());
+                lines.push(i18n::text(lang, "config_file.at", "at {path}, line {line}", &[path, line]));
+                if let Some(value) = &at.value {
+                    lines.push(format!("{} = {}", key, value));
+                }
+            }
+            (Some(at), Some(key)) => {
+                let vars = [path, ("line", at.line.to_string()), ("table", at.key.clone())];
+                lines.push(i18n::text(lang, "config_file.at_table", "at {path}, line {line} ([{table}])", &vars));
+                lines.push(i18n::text(lang, "config_file.not_set", "{key} is not set", &[("key", key.clone())]));
+            }
+            _ => lines.push(i18n::text(lang, "config_file.in", "in {path}", &[path])),
+        }
+        if let Some(hint) = self.hint(lang) {
+            lines.push(i18n::text(lang, "config_file.fix", "fix: {hint}", &[("hint", hint)]));
+        }
+        lines.iter().map(|l| format!("  {}", l)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// The provider `key` belongs to, from its `kind` in the file (`ollama` when unset).
+fn kind_for(key: &str, text: &str) -> ProviderKind {
+    let table = match key.split_once(".params") {
+        Some((table, _)) => table,
+        None => key.rsplit_once('.').map_or("provider", |(table, _)| table),
+    };
+    locate(text, &format!("{}.kind", table))
+        .and_then(|l| l.value)
+        .and_then(|v| v.trim_matches('"').parse().ok())
+        .unwrap_or(ProviderKind::Ollama)
+}
+
+fn hint(err: &ConfigError, key: &str, text: &str) -> Option<Hint> {
+    let make = |name, fallback, mut vars: Vec<(&'static str, String)>| {
+        vars.push(("key", key.to_string()));
+        Some(Hint { name, fallback, vars })
+    };
+    match err {
+        ConfigError::UnsupportedVersion(_) => make(
+            "unsupported_version",
+            "run `aion self-update` to get a version that reads this file",
+            vec![],
+        ),
+        ConfigError::InvalidLanguage(_) => make(
+            "invalid_language",
+            "run `aion config set language en`, or `aion --setup --step language`",
+            vec![],
+        ),
+        ConfigError::EmptyModel => make(
+            "empty_model",
+            "run `aion config set {key} {example}` (see `aion models`), or `aion --setup --step model`",
+            vec![("example", kind_for(key, text).default_model().to_string())],
+        ),
+        ConfigError::MissingBaseUrl | ConfigError::InvalidBaseUrl { .. } => make(
+            "base_url",
+            "run `aion config set {key} {example}`",
+            vec![("example", kind_for(key, text).public_base_url().to_string())],
+        ),
+        ConfigError::MissingApiKeyEnv | ConfigError::InvalidApiKeyEnv { .. } => {
+            let example = kind_for(key, text).default_api_key_env().unwrap_or("API_KEY");
+            make(
+                "api_key_env",
+                "run `aion config set {key} {example}`, then export {example} with your API key",
+                vec![("example", example.to_string())],
+            )
+        }
+        ConfigError::ParamOutOfRange { range, .. } => make(
+            "param_out_of_range",
+            "run `aion config set {key} <value>` with a value in {range}, or delete it to use the default",
+            vec![("range", range.to_string())],
+        ),
+        ConfigError::InvalidScopeId { .. } => {
+            make("invalid_scope_id", "run `aion config set {key} <id>`, or delete it", vec![])
+        }
+        ConfigError::Inconsistent(w) => make("inconsistent", "{fix}", vec![("fix", w.fix().to_string())]),
+        ConfigError::ReadOnly { .. } => None,
+        ConfigError::InvalidKey { .. } => make(
+            "invalid_key",
+            "run `aion config set {key} '[\"ctrl+q\"]'` with key names like \"q\", \"ctrl+q\", \"esc\", or \"f2\"",
+            vec![],
+        ),
+        ConfigError::DuplicateKey { spec, first, .. } => make(
+            "duplicate_key",
+            "remove '{spec}' from {key} or from keybindings.{first}",
+            vec![("spec", spec.clone()), ("first", first.to_string())],
+        ),
+        ConfigError::InvalidFallback { error, .. } => hint(error, key, text),
+        ConfigError::NestedFallbacks { .. } => make(
+            "nested_fallbacks",
+            "move these fallbacks up to provider.fallbacks, then delete {key}",
+            vec![],
+        ),
+        ConfigError::InvalidStyle { expected, .. } => make(
+            "invalid_style",
+            "run `aion config set {key} {example}` (one of: {expected})",
+            vec![
+                ("example", expected.split(", ").next().unwrap_or_default().to_string()),
+                ("expected", expected.clone()),
+            ],
+        ),
+        ConfigError::UnknownVariable { name, .. } => make(
+            "unknown_variable",
+            "export {name}, or run `aion config set {key} <value>` without it",
+            vec![("name", name.clone())],
+        ),
+        ConfigError::MalformedVariable { .. } => make(
+            "malformed_variable",
+            "close the `${{` in {key} with `}}`, or write $$ for a literal $",
+            vec![],
+        ),
+        ConfigError::ExtraOptionConflict { setting, .. } => make(
+            "extra_option_conflict",
+            "delete {key}; AION sends it from {setting}",
+            vec![("setting", setting.to_string())],
+        ),
+        ConfigError::InvalidExtraOption { .. } => make(
+            "invalid_extra_option",
+            "delete {key}, or give it a value the provider's API takes",
+            vec![],
+        ),
+        ConfigError::InvalidHostPattern { pattern, .. } => make(
+            "invalid_host_pattern",
+            "fix or remove \"{pattern}\" in {key}: a host, `*.example.com`, or a range like `10.0.0.0/8`",
+            vec![("pattern", pattern.clone())],
+        ),
+        ConfigError::InvalidSecretPattern { pattern, .. } => make(
+            "invalid_secret_pattern",
+            "fix or remove \"{pattern}\" in {key}: a regular expression, like `ACME-[0-9]{{8}}`",
+            vec![("pattern", pattern.clone())],
+        ),
+        ConfigError::InvalidBudget { .. } => make(
+            "invalid_budget",
+            "run `aion config set {key} <value>` with a positive amount (prices: \"IN,OUT\" per million tokens), or delete it",
+            vec![],
+        ),
+    }
+}
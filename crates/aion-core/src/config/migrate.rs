@@ -0,0 +1,58 @@
+//! Config file migrations.
+//!
+//! Notes:
+//! - Migrations edit the raw TOML table, so files that no longer match `AppConfig` can
+//!   still be read. Each one upgrades `from` to `from + 1`; `upgrade` runs them in order
+//!   and stamps `version` after each step.
+//! - A file without `version` is treated as version 0 (written by hand or by an early
+//!   build).
+//! - `load_config` upgrades in memory only; `aion config upgrade` writes the result.
+
+use super::AppConfig;
+use anyhow::{bail, Result};
+
+pub struct Migration {
+    pub from: u32,
+    pub summary: &'static str,
+    pub apply: fn(&mut toml::Table),
+}
+
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    summary: "add the missing `version` field",
+    apply: |_| {},
+}];
+
+/// The `version` a config file declares (0 when missing).
+pub fn file_version(table: &toml::Table) -> u32 {
+    table
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+/// Run every migration from the file's version up to `AppConfig::CURRENT_VERSION`.
+/// Returns the summaries of the migrations applied.
+pub fn upgrade(table: &mut toml::Table) -> Result<Vec<&'static str>> {
+    let mut version = file_version(table);
+    if version > AppConfig::CURRENT_VERSION {
+        bail!(
+            "config version {} is newer than this build supports ({}); update AION",
+            version,
+            AppConfig::CURRENT_VERSION
+        );
+    }
+
+    let mut applied = Vec::new();
+    while version < AppConfig::CURRENT_VERSION {
+        let Some(m) = MIGRATIONS.iter().find(|m| m.from == version) else {
+            bail!("no migration from config version {}", version);
+        };
+        (m.apply)(table);
+        version += 1;
+        table.insert("version".to_string(), toml::Value::Integer(i64::from(version)));
+        applied.push(m.summary);
+    }
+    Ok(applied)
+}
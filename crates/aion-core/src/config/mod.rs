@@ -0,0 +1,1081 @@
+pub mod consistency;
+pub mod diff;
+pub mod draft;
+pub mod edit;
+pub mod expand;
+pub mod extra;
+pub mod fields;
+pub mod include;
+pub mod io;
+pub mod keys;
+pub mod locate;
+pub mod migrate;
+pub mod params;
+pub mod style;
+pub mod tree;
+use keys::KeyBindings;
+pub use consistency::ConfigWarning;
+pub use params::GenParams;
+pub use style::StyleConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Written as `OpenAI`, `Claude`, ...; the lowercase ids (and `gpt`, `anthropic`) are
+/// accepted when reading, by serde and by `FromStr` (case-insensitive).
+///
+/// ```
+/// use aion_core::config::ProviderKind;
+///
+/// for kind in ProviderKind::ALL {
+///     for name in [kind.id().to_string(), kind.to_string(), kind.id().to_uppercase(), format!(" {} ", kind.id())] {
+///         assert_eq!(name.parse::<ProviderKind>().unwrap(), kind, "{:?}", name);
+///     }
+///     let json = serde_json::to_string(&kind).unwrap();
+///     assert_eq!(json, format!("\"{}\"", kind.name()));
+///     assert_eq!(serde_json::from_str::<ProviderKind>(&json).unwrap(), kind);
+///     assert_eq!(serde_json::from_str::<ProviderKind>(&format!("\"{}\"", kind.id())).unwrap(), kind);
+/// }
+/// for (alias, kind) in [("gpt", ProviderKind::OpenAI), ("GPT", ProviderKind::OpenAI), ("anthropic", ProviderKind::Claude)] {
+///     assert_eq!(alias.parse::<ProviderKind>().unwrap(), kind);
+///     // An alias is read, but the kind is written back under its own name.
+///     assert_eq!(alias.parse::<ProviderKind>().unwrap().to_string(), kind.name());
+/// }
+/// assert_eq!(serde_json::from_str::<ProviderKind>("\"gpt\"").unwrap(), ProviderKind::OpenAI);
+/// assert_eq!(serde_json::from_str::<ProviderKind>("\"anthropic\"").unwrap(), ProviderKind::Claude);
+///
+/// let err = "llama".parse::<ProviderKind>().unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "unknown provider 'llama' (expected one of: ollama, openai, claude, openrouter, gpt, anthropic)"
+/// );
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProviderKind {
+    #[serde(alias = "openai", alias = "gpt")]
+    OpenAI,
+    #[serde(alias = "claude", alias = "anthropic")]
+    Claude,
+    #[serde(alias = "openrouter")]
+    OpenRouter,
+    #[serde(alias = "ollama")]
+    Ollama,
+}
+
+/// Written as `Tui` / `Cli`; `tui` / `cli` are accepted when reading.
+///
+/// ```
+/// use aion_core::config::UiMode;
+///
+/// for mode in [UiMode::Tui, UiMode::Cli] {
+///     for name in [mode.to_string(), mode.to_string().to_lowercase(), mode.to_string().to_uppercase()] {
+///         assert_eq!(name.parse::<UiMode>().unwrap(), mode, "{:?}", name);
+///     }
+///     let json = serde_json::to_string(&mode).unwrap();
+///     assert_eq!(json, format!("\"{}\"", mode));
+///     assert_eq!(serde_json::from_str::<UiMode>(&json).unwrap(), mode);
+///     assert_eq!(serde_json::from_str::<UiMode>(&json.to_lowercase()).unwrap(), mode);
+/// }
+/// assert_eq!(" tui ".parse::<UiMode>().unwrap(), UiMode::Tui);
+/// assert_eq!("gui".parse::<UiMode>().unwrap_err().to_string(), "unknown ui mode 'gui' (expected one of: tui, cli)");
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum UiMode {
+    #[serde(alias = "tui")]
+    Tui,
+    #[serde(alias = "cli")]
+    Cli,
+}
+
+/// Other names `FromStr` accepts for a provider, besides its id.
+const PROVIDER_ALIASES: &[(&str, ProviderKind)] = &[("gpt", ProviderKind::OpenAI), ("anthropic", ProviderKind::Claude)];
+
+/// A string that names no variant; lists the names that would have worked.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown {what} '{value}' (expected one of: {})", expected.join(", "))]
+pub struct UnknownName {
+    pub what: &'static str,
+    pub value: String,
+    pub expected: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    pub model: String,
+    pub base_url: Option<String>,
+    pub api_key_env: Option<String>,
+    /// Ask the model to reply in `language` (instruction text comes from the locale).
+    #[serde(default)]
+    pub respond_in_config_language: bool,
+    /// Generation parameters; see `params` for how they combine with the defaults.
+    #[serde(default, skip_serializing_if = "GenParams::is_empty")]
+    pub params: GenParams,
+    /// Organization ID for scoped keys, sent as `OpenAI-Organization` (OpenAI only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// Project ID for scoped keys, sent as `OpenAI-Project` (OpenAI only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Model that embeds attachments for `features.retrieval` (see `crate::retrieval`);
+    /// unset uses `model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    /// Request fields AION does not model, passed through as written (see `extra`).
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    pub extra_options: toml::Table,
+    /// Providers tried in order when this one fails or does not answer in time
+    /// (`[[provider.fallbacks]]`, see `providers::failover`). Not read on a fallback.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<ProviderConfig>,
+    /// When this provider cannot be used (its API key is missing), look for a local
+    /// server (Ollama, LM Studio) and suggest switching to it. Not read on a fallback.
+    #[serde(default = "default_true")]
+    pub autodetect: bool,
+}
+
+impl ProviderConfig {
+    /// The endpoint requests go to: the configured base_url, or the provider's public one.
+    pub fn effective_base_url(&self) -> String {
+        match self.base_url.as_deref().map(str::trim) {
+            Some(url) if !url.is_empty() => url.trim_end_matches('/').to_string(),
+            _ => self.kind.public_base_url().to_string(),
+        }
+    }
+
+    /// The model attachments are embedded with: `embedding_model`, else `model`.
+    pub fn embedding_model(&self) -> &str {
+        self.embedding_model.as_deref().filter(|m| !m.trim().is_empty()).unwrap_or(&self.model)
+    }
+
+    /// Extra request headers from `organization` and `project`, for providers that use them.
+    pub fn scope_headers(&self) -> Vec<(&'static str, String)> {
+        if self.kind != ProviderKind::OpenAI {
+            return Vec::new();
+        }
+        [("OpenAI-Organization", &self.organization), ("OpenAI-Project", &self.project)]
+            .into_iter()
+            .filter_map(|(name, value)| value.clone().map(|v| (name, v)))
+            .collect()
+    }
+
+    /// The checks `AppConfig::validate` runs on the provider and on each fallback.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        fields::model(&self.model).into_result()?;
+        fields::base_url(&self.kind, self.base_url.as_deref().unwrap_or("")).into_result()?;
+        fields::api_key_env(&self.kind, self.api_key_env.as_deref().unwrap_or("")).into_result()?;
+        self.validate_scope()?;
+        extra::validate(&self.kind, &self.extra_options)?;
+        self.effective_params(&GenParams::default()).validate()
+    }
+
+    /// The provider followed by its fallbacks, in the order they are tried.
+    pub fn chain(&self) -> impl Iterator<Item = &ProviderConfig> {
+        std::iter::once(self).chain(&self.fallbacks)
+    }
+
+    /// Whether the endpoint is this machine or the local network: `localhost`, or a
+    /// loopback, private, or link-local address (`privacy.trust_local`).
+    pub fn is_local(&self) -> bool {
+        let Ok(url) = url::Url::parse(&self.effective_base_url()) else { return false };
+        match url.host() {
+            Some(url::Host::Domain(name)) => {
+                let name = name.to_ascii_lowercase();
+                name == "localhost" || name.ends_with(".localhost")
+            }
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+            Some(url::Host::Ipv6(ip)) => match ip.to_ipv4_mapped() {
+                Some(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+                // fc00::/7 is unique local, fe80::/10 link-local.
+                None => ip.is_loopback() || ip.segments()[0] & 0xfe00 == 0xfc00 || ip.segments()[0] & 0xffc0 == 0xfe80,
+            },
+            None => false,
+        }
+    }
+
+    /// `organization` and `project` must be usable as header values: printable ASCII,
+    /// no spaces or line breaks.
+    fn validate_scope(&self) -> Result<(), ConfigError> {
+        for (field, value) in [("organization", &self.organization), ("project", &self.project)] {
+            if let Some(value) = value.as_deref().filter(|v| v.is_empty() || !v.chars().all(|c| c.is_ascii_graphic())) {
+                return Err(ConfigError::InvalidScopeId { field, value: value.to_string() });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Features {
+    pub system_scan: bool,
+    pub web_in_terminal: bool,
+    pub command_suggestions: bool,
+    pub safe_execute: bool,
+    /// Send the parts of attached files most like each message instead of the whole files
+    /// (see `crate::retrieval`). Needs an embedding-capable provider.
+    #[serde(default)]
+    pub retrieval: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub read_files: bool,
+    pub write_files: bool,
+    pub network: bool,
+    pub run_commands: bool,
+    /// Globs narrowing `read_files`; empty allows every path (see `crate::guard`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_allow: Vec<String>,
+    /// Globs `read_files` never covers, even when `read_allow` matches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_deny: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write_allow: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write_deny: Vec<String>,
+    /// Hosts narrowing `network`; empty allows every host. The provider's is always allowed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_allow: Vec<String>,
+    /// Hosts never contacted, the provider's included, even when `network_allow` matches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_deny: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Mask API keys and tokens in printed output, errors, and saved sessions.
+    #[serde(default = "default_true")]
+    pub redaction: bool,
+    /// Record commands run and files written in `audit.log`. Unset: on whenever
+    /// `caps.write_files` or `caps.run_commands` is (see `AppConfig::audit_enabled`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit: Option<bool>,
+    /// Log provider requests and replies to `http.log` (see `crate::http_log`).
+    #[serde(default)]
+    pub http_debug: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self { redaction: true, audit: None, http_debug: false }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Opt-in cache of replies to identical requests (see `crate::cache`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// Seconds an entry stays valid after it was stored.
+    pub ttl_secs: u64,
+    /// Where entries are stored; `responses/` in the cache dir (`crate::paths`) when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { enabled: false, ttl_secs: 24 * 60 * 60, dir: None }
+    }
+}
+
+/// Startup output (`aion` without a subcommand).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// The banner, timestamp, and boot status lines.
+    pub show_banner: bool,
+    /// OS and architecture.
+    pub show_env_info: bool,
+    /// Write numbers in the language's own digits where it has them (Arabic-Indic for
+    /// `ar`); see `crate::i18n::format`.
+    pub native_digits: bool,
+    /// Draw the setup wizard with ASCII symbols only, for terminals that show boxes or
+    /// question marks for `●` and box borders (also `--ascii`).
+    pub ascii_only: bool,
+    /// Wrap text in the other direction (an Arabic name in an English line) in Unicode
+    /// directional isolates when printing; off for terminals that show them as boxes.
+    /// See `crate::i18n::bidi`.
+    pub bidi_isolates: bool,
+    /// Ring the bell (and send a desktop notification) when a request or command takes at
+    /// least this many seconds; see `crate::notify`. Unset is never.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_after_secs: Option<u64>,
+    /// When CLI mode shows a reply in `$PAGER` once it is complete (also `--pager`).
+    pub pager: Pager,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            show_banner: true,
+            show_env_info: true,
+            native_digits: false,
+            ascii_only: false,
+            bidi_isolates: true,
+            notify_after_secs: None,
+            pager: Pager::Auto,
+        }
+    }
+}
+
+/// `ui.pager`. Replies are paged only when stdout is a terminal, whatever this says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pager {
+    /// Replies longer than the terminal is high.
+    Auto,
+    Always,
+    Never,
+}
+
+/// Timeouts for background requests (the wizard's model lists and connection checks), and
+/// limits on provider requests (see `crate::providers::limit`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Seconds before a background request is given up on (at least 1).
+    pub timeout_secs: u64,
+    /// Provider requests in flight at once, per provider; 0 is no limit.
+    pub max_concurrent_requests: u32,
+    /// Provider requests started per minute, per provider; 0 is no limit.
+    pub requests_per_minute: u32,
+}
+
+impl NetworkConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 10, max_concurrent_requests: 0, requests_per_minute: 0 }
+    }
+}
+
+/// Saved chat sessions (see `crate::sessions`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    /// After the second exchange, ask the provider for a short title for the session.
+    pub auto_title: bool,
+    /// The oldest exchanges are left out of a request once the conversation passes this
+    /// many tokens, estimated (see `crate::conversation`); 0 sends them all.
+    pub max_context_tokens: u64,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self { auto_title: true, max_context_tokens: 32_000 }
+    }
+}
+
+/// Checks for a newer AION release (see `aion self-update`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatesConfig {
+    /// Look for a new release in the background when AION starts, at most once a day.
+    pub check_on_start: bool,
+}
+
+/// Where locale files are read from, and how large one may be (see `crate::i18n`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Also read `locales/` in the current directory. Off by default: run inside someone
+    /// else's repository, AION would otherwise take its strings from there.
+    pub load_cwd_locales: bool,
+    /// Locale files larger than this are skipped without being read.
+    pub max_file_bytes: u64,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self { load_cwd_locales: false, max_file_bytes: 1024 * 1024 }
+    }
+}
+
+impl I18nConfig {
+    /// `[i18n]` from the config file; the default when there is no file or the table does
+    /// not parse. Read on its own because locales load before the rest of the config is
+    /// validated (and word its errors).
+    pub fn from_config_file() -> Self {
+        io::load_layered_config()
+            .ok()
+            .and_then(|mut layered| layered.table.remove("i18n"))
+            .and_then(|value| value.try_into().ok())
+            .unwrap_or_default()
+    }
+}
+
+/// The project context file sent with chats (see `crate::project_context`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    pub enabled: bool,
+    /// Relative: looked for in the current directory and its parents. Absolute: that file.
+    pub file: PathBuf,
+    /// Larger files are cut to this many bytes, with a notice.
+    pub max_bytes: u64,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self { enabled: true, file: PathBuf::from(".aion/context.md"), max_bytes: 32 * 1024 }
+    }
+}
+
+/// What chat sessions leave on disk (see `crate::persistence`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Start every chat as if `--ephemeral` was passed.
+    pub default_ephemeral: bool,
+    /// Session files not written for this many days are deleted at startup; 0 keeps them.
+    pub retention_days: u32,
+    /// Entries typed at the chat prompt kept for ↑/↓ and Ctrl+R (see `crate::history`).
+    pub max_entries: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self { default_ephemeral: false, retention_days: 0, max_entries: 1000 }
+    }
+}
+
+/// Commands AION runs (see `crate::workdir`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecConfig {
+    /// Where commands run when neither `--cwd` nor `/cd` says; AION's own directory when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_cwd: Option<PathBuf>,
+}
+
+/// The check for secrets in files, shell history, and command output before they are sent
+/// (see `crate::redact::SecretScanner`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// No check when every provider in the chain is local (`ProviderConfig::is_local`).
+    pub trust_local: bool,
+    /// Regexes looked for besides the built-in ones; a `secret` group marks what is masked.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+impl PrivacyConfig {
+    /// Every pattern compiles.
+    fn validate(&self) -> Result<(), ConfigError> {
+        for pattern in &self.patterns {
+            crate::redact::SecretScanner::compile(pattern)
+                .map_err(|reason| ConfigError::InvalidSecretPattern { pattern: pattern.clone(), reason })?;
+        }
+        Ok(())
+    }
+}
+
+/// Spending limits on cloud providers (see `crate::costs`). Unset is no limit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// US dollars one chat session (or one `aion chat` run) may spend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_session_usd: Option<f64>,
+    /// US dollars all requests in a calendar month (UTC) may spend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_month_usd: Option<f64>,
+    /// Prices by model name, `"IN,OUT"` in dollars per million tokens, over the built-in ones.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub prices: BTreeMap<String, String>,
+}
+
+impl BudgetConfig {
+    /// Limits are positive amounts, and prices parse.
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (field, limit) in [("per_session_usd", self.per_session_usd), ("per_month_usd", self.per_month_usd)] {
+            match limit {
+                Some(usd) if !(usd.is_finite() && usd > 0.0) => {
+                    return Err(ConfigError::InvalidBudget {
+                        key: format!("budget.{}", field),
+                        reason: format!("{} is not a positive amount", usd),
+                    })
+                }
+                _ => {}
+            }
+        }
+        for (model, price) in &self.prices {
+            price.parse::<crate::bench::Price>().map_err(|reason| ConfigError::InvalidBudget {
+                key: format!("budget.prices.{}", model),
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub version: u32,
+    /// Config files merged under this one, in order (see `include`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    pub language: String,
+    pub ui_mode: UiMode,
+    pub provider: ProviderConfig,
+    pub features: Features,
+    pub caps: Capabilities,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub keybindings: KeyBindings,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub style: StyleConfig,
+    #[serde(default)]
+    pub updates: UpdatesConfig,
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    #[serde(default)]
+    pub context: ContextConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub exec: ExecConfig,
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("config version is not supported: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("language is invalid: {0}")]
+    InvalidLanguage(String),
+
+    #[error("provider model is empty")]
+    EmptyModel,
+
+    #[error("base_url is required for this provider")]
+    MissingBaseUrl,
+
+    #[error("api_key_env is required for this provider")]
+    MissingApiKeyEnv,
+
+    #[error("base_url {value:?} is not a valid URL: {reason}")]
+    InvalidBaseUrl { value: String, reason: String },
+
+    #[error("api_key_env {value:?} is not a valid variable name: {reason}")]
+    InvalidApiKeyEnv { value: String, reason: String },
+
+    #[error("provider.params.{name} is out of range: {value} (expected {range})")]
+    ParamOutOfRange { name: &'static str, value: String, range: &'static str },
+
+    #[error("provider.{field} is not a valid ID: {value:?} (no spaces, line breaks, or non-ASCII characters)")]
+    InvalidScopeId { field: &'static str, value: String },
+
+    #[error("inconsistent config: {0}")]
+    Inconsistent(ConfigWarning),
+
+    #[error("configuration is read-only: cannot write {path}: {reason} (use --config <file> for a writable location)")]
+    ReadOnly { path: String, reason: String },
+
+    #[error("keybindings.{action}: invalid key '{spec}'")]
+    InvalidKey { action: &'static str, spec: String },
+
+    #[error("keybindings: '{spec}' is bound to both {first} and {second}")]
+    DuplicateKey {
+        spec: String,
+        first: &'static str,
+        second: &'static str,
+    },
+
+    /// `index` counts from 1, in the order the fallbacks are listed.
+    #[error("provider fallback {index}: {error}")]
+    InvalidFallback { index: usize, error: Box<ConfigError> },
+
+    #[error("provider fallback {index} has fallbacks of its own")]
+    NestedFallbacks { index: usize },
+
+    #[error("style.{field} is invalid: {value:?} (expected one of: {expected})")]
+    InvalidStyle { field: &'static str, value: String, expected: String },
+
+    #[error("{field} uses ${{{name}}}, which is not set")]
+    UnknownVariable { field: String, name: String },
+
+    #[error("{field} has a '${{' without a closing '}}': {value:?}")]
+    MalformedVariable { field: String, value: String },
+
+    #[error("provider.extra_options.{name} is sent by AION already (it comes from {setting})")]
+    ExtraOptionConflict { name: String, setting: &'static str },
+
+    #[error("provider.extra_options.{name} cannot be sent: {reason}")]
+    InvalidExtraOption { name: String, reason: String },
+
+    #[error("caps.{list} has an invalid host pattern {pattern:?}: {reason}")]
+    InvalidHostPattern { list: &'static str, pattern: String, reason: String },
+
+    #[error("privacy.patterns has an invalid pattern {pattern:?}: {reason}")]
+    InvalidSecretPattern { pattern: String, reason: String },
+
+    #[error("{key} is invalid: {reason}")]
+    InvalidBudget { key: String, reason: String },
+}
+
+impl ConfigError {
+    /// The dotted key the error is about, as `aion config set` takes it (fallbacks count
+    /// from 1: `provider.fallbacks[1].model`). `None` when it is not about one setting.
+    pub fn key(&self) -> Option<String> {
+        Some(match self {
+            ConfigError::UnsupportedVersion(_) => "version".to_string(),
+            ConfigError::InvalidLanguage(_) => "language".to_string(),
+            ConfigError::EmptyModel => "provider.model".to_string(),
+            ConfigError::MissingBaseUrl | ConfigError::InvalidBaseUrl { .. } => "provider.base_url".to_string(),
+            ConfigError::MissingApiKeyEnv | ConfigError::InvalidApiKeyEnv { .. } => "provider.api_key_env".to_string(),
+            ConfigError::ParamOutOfRange { name, .. } => format!("provider.params.{}", name),
+            ConfigError::InvalidScopeId { field, .. } => format!("provider.{}", field),
+            ConfigError::Inconsistent(w) => w.feature().to_string(),
+            ConfigError::ReadOnly { .. } => return None,
+            ConfigError::InvalidKey { action, .. } => format!("keybindings.{}", action),
+            ConfigError::DuplicateKey { second, .. } => format!("keybindings.{}", second),
+            ConfigError::InvalidFallback { index, error } => {
+                let inner = error.key()?;
+                format!("provider.fallbacks[{}].{}", index, inner.strip_prefix("provider.").unwrap_or(&inner))
+            }
+            ConfigError::NestedFallbacks { index } => format!("provider.fallbacks[{}].fallbacks", index),
+            ConfigError::InvalidStyle { field, .. } => format!("style.{}", field),
+            ConfigError::UnknownVariable { field, .. } | ConfigError::MalformedVariable { field, .. } => field.clone(),
+            ConfigError::ExtraOptionConflict { name, .. } | ConfigError::InvalidExtraOption { name, .. } => {
+                format!("provider.extra_options.{}", name)
+            }
+            ConfigError::InvalidHostPattern { list, .. } => format!("caps.{}", list),
+            ConfigError::InvalidSecretPattern { .. } => "privacy.patterns".to_string(),
+            ConfigError::InvalidBudget { key, .. } => key.clone(),
+        })
+    }
+
+    /// The variant's name (`MissingBaseUrl`), for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigError::UnsupportedVersion(_) => "UnsupportedVersion",
+            ConfigError::InvalidLanguage(_) => "InvalidLanguage",
+            ConfigError::EmptyModel => "EmptyModel",
+            ConfigError::MissingBaseUrl => "MissingBaseUrl",
+            ConfigError::MissingApiKeyEnv => "MissingApiKeyEnv",
+            ConfigError::InvalidBaseUrl { .. } => "InvalidBaseUrl",
+            ConfigError::InvalidApiKeyEnv { .. } => "InvalidApiKeyEnv",
+            ConfigError::ParamOutOfRange { .. } => "ParamOutOfRange",
+            ConfigError::InvalidScopeId { .. } => "InvalidScopeId",
+            ConfigError::Inconsistent(_) => "Inconsistent",
+            ConfigError::ReadOnly { .. } => "ReadOnly",
+            ConfigError::InvalidKey { .. } => "InvalidKey",
+            ConfigError::DuplicateKey { .. } => "DuplicateKey",
+            ConfigError::InvalidFallback { .. } => "InvalidFallback",
+            ConfigError::NestedFallbacks { .. } => "NestedFallbacks",
+            ConfigError::InvalidStyle { .. } => "InvalidStyle",
+            ConfigError::UnknownVariable { .. } => "UnknownVariable",
+            ConfigError::MalformedVariable { .. } => "MalformedVariable",
+            ConfigError::ExtraOptionConflict { .. } => "ExtraOptionConflict",
+            ConfigError::InvalidExtraOption { .. } => "InvalidExtraOption",
+            ConfigError::InvalidHostPattern { .. } => "InvalidHostPattern",
+            ConfigError::InvalidSecretPattern { .. } => "InvalidSecretPattern",
+            ConfigError::InvalidBudget { .. } => "InvalidBudget",
+        }
+    }
+}
+
+/// An action was refused because a `caps` switch is off. `cap` is the field name.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{}", denied_message(.cap))]
+pub struct CapabilityDenied {
+    pub cap: &'static str,
+}
+
+/// Read-only mode names itself rather than the `caps` switch it turned off.
+fn denied_message(cap: &'static str) -> String {
+    match crate::read_only::denied_cap(cap) {
+        Some(denied) => denied.to_string(),
+        None => format!("{} is disabled (caps.{} = false)", Capabilities::describe(cap), cap),
+    }
+}
+
+/// Named `caps` combinations, for `aion init --caps`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum CapsPreset {
+    /// Read files only; no network access.
+    Offline,
+    /// Read files and use the network (the default).
+    Safe,
+    /// `safe` plus writing files.
+    Write,
+    /// Everything, including running commands.
+    Full,
+}
+
+impl CapsPreset {
+    pub const ALL: [CapsPreset; 4] = [CapsPreset::Offline, CapsPreset::Safe, CapsPreset::Write, CapsPreset::Full];
+
+    /// Name as given to `--caps`.
+    pub fn id(self) -> &'static str {
+        match self {
+            CapsPreset::Offline => "offline",
+            CapsPreset::Safe => "safe",
+            CapsPreset::Write => "write",
+            CapsPreset::Full => "full",
+        }
+    }
+
+    /// The preset `caps` is exactly, if any; path patterns make it custom.
+    pub fn matching(caps: &Capabilities) -> Option<CapsPreset> {
+        let flags = |c: &Capabilities| (c.read_files, c.write_files, c.network, c.run_commands);
+        let patterns = [
+            &caps.read_allow,
+            &caps.read_deny,
+            &caps.write_allow,
+            &caps.write_deny,
+            &caps.network_allow,
+            &caps.network_deny,
+        ];
+        if patterns.iter().any(|p| !p.is_empty()) {
+            return None;
+        }
+        Self::ALL.into_iter().find(|preset| flags(&preset.caps()) == flags(caps))
+    }
+
+    pub fn caps(self) -> Capabilities {
+        let (network, write_files, run_commands) = match self {
+            CapsPreset::Offline => (false, false, false),
+            CapsPreset::Safe => (true, false, false),
+            CapsPreset::Write => (true, true, false),
+            CapsPreset::Full => (true, true, true),
+        };
+        Capabilities {
+            read_files: true,
+            write_files,
+            network,
+            run_commands,
+            read_allow: Vec::new(),
+            read_deny: Vec::new(),
+            write_allow: Vec::new(),
+            write_deny: Vec::new(),
+            network_allow: Vec::new(),
+            network_deny: Vec::new(),
+        }
+    }
+}
+
+impl Features {
+    /// Field names, as written in config.toml.
+    pub const NAMES: [&'static str; 5] =
+        ["system_scan", "web_in_terminal", "command_suggestions", "safe_execute", "retrieval"];
+
+    /// Only the features named in `names` switched on.
+    pub fn only<S: AsRef<str>>(names: &[S]) -> Result<Self, UnknownName> {
+        let mut features = Features {
+            system_scan: false,
+            web_in_terminal: false,
+            command_suggestions: false,
+            safe_execute: false,
+            retrieval: false,
+        };
+        for name in names {
+            let flag = match name.as_ref().trim() {
+                "system_scan" => &mut features.system_scan,
+                "web_in_terminal" => &mut features.web_in_terminal,
+                "command_suggestions" => &mut features.command_suggestions,
+                "safe_execute" => &mut features.safe_execute,
+                "retrieval" => &mut features.retrieval,
+                other => {
+                    return Err(UnknownName { what: "feature", value: other.to_string(), expected: Self::NAMES.to_vec() })
+                }
+            };
+            *flag = true;
+        }
+        Ok(features)
+    }
+}
+
+impl Capabilities {
+    /// What the `caps` field named `cap` allows, for messages.
+    pub fn describe(cap: &str) -> &'static str {
+        match cap {
+            "read_files" => "reading files",
+            "write_files" => "writing files",
+            "network" => "network access",
+            "run_commands" => "running commands",
+            _ => "this action",
+        }
+    }
+
+    /// Every `network_allow`/`network_deny` pattern parses (see `crate::guard`).
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (list, patterns) in [("network_allow", &self.network_allow), ("network_deny", &self.network_deny)] {
+            for pattern in patterns {
+                crate::guard::HostPattern::parse(pattern).map_err(|reason| ConfigError::InvalidHostPattern {
+                    list,
+                    pattern: pattern.clone(),
+                    reason,
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProviderKind {
+    /// Every provider, in the order the wizard lists them.
+    pub const ALL: [ProviderKind; 4] =
+        [ProviderKind::Ollama, ProviderKind::OpenAI, ProviderKind::Claude, ProviderKind::OpenRouter];
+
+    /// Lowercase identifier used on the command line and in locale keys (`providers.<id>`).
+    pub fn id(&self) -> &'static str {
+        match self {
+            ProviderKind::Ollama => "ollama",
+            ProviderKind::OpenAI => "openai",
+            ProviderKind::Claude => "claude",
+            ProviderKind::OpenRouter => "openrouter",
+        }
+    }
+
+    /// Display name, as written in config.toml.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProviderKind::Ollama => "Ollama",
+            ProviderKind::OpenAI => "OpenAI",
+            ProviderKind::Claude => "Claude",
+            ProviderKind::OpenRouter => "OpenRouter",
+        }
+    }
+
+    pub fn requires_api_key(&self) -> bool {
+        matches!(self, ProviderKind::OpenAI | ProviderKind::Claude | ProviderKind::OpenRouter)
+    }
+
+    pub fn default_api_key_env(&self) -> Option<&'static str> {
+        match self {
+            ProviderKind::OpenAI => Some("OPENAI_API_KEY"),
+            ProviderKind::Claude => Some("ANTHROPIC_API_KEY"),
+            ProviderKind::OpenRouter => Some("OPENROUTER_API_KEY"),
+            ProviderKind::Ollama => None,
+        }
+    }
+
+    pub fn default_base_url(&self) -> Option<&'static str> {
+        match self {
+            ProviderKind::OpenRouter => Some("https://openrouter.ai/api/v1"),
+            ProviderKind::Ollama => Some("http://localhost:11434"),
+            _ => None,
+        }
+    }
+
+    /// Public API endpoint used when `base_url` is not set in the config.
+    pub fn public_base_url(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenAI => "https://api.openai.com/v1",
+            ProviderKind::Claude => "https://api.anthropic.com/v1",
+            ProviderKind::OpenRouter => "https://openrouter.ai/api/v1",
+            ProviderKind::Ollama => "http://localhost:11434",
+        }
+    }
+
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenAI => "gpt-4.1-mini",
+            ProviderKind::Claude => "claude-3-5-sonnet-latest",
+            ProviderKind::OpenRouter => "openai/gpt-4o-mini",
+            ProviderKind::Ollama => "mistral",
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = UnknownName;
+
+    /// An id (`openai`) or alias (`gpt`), in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        let ids = ProviderKind::ALL.iter().map(|k| (k.id(), k));
+        let aliases = PROVIDER_ALIASES.iter().map(|(n, k)| (*n, k));
+        ids.chain(aliases)
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, k)| k.clone())
+            .ok_or_else(|| UnknownName {
+                what: "provider",
+                value: s.to_string(),
+                expected: ProviderKind::ALL
+                    .iter()
+                    .map(ProviderKind::id)
+                    .chain(PROVIDER_ALIASES.iter().map(|(n, _)| *n))
+                    .collect(),
+            })
+    }
+}
+
+impl fmt::Display for UiMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UiMode::Tui => "Tui",
+            UiMode::Cli => "Cli",
+        })
+    }
+}
+
+impl FromStr for UiMode {
+    type Err = UnknownName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tui" => Ok(UiMode::Tui),
+            "cli" => Ok(UiMode::Cli),
+            _ => Err(UnknownName { what: "ui mode", value: s.to_string(), expected: vec!["tui", "cli"] }),
+        }
+    }
+}
+
+impl AppConfig {
+    pub const CURRENT_VERSION: u32 = 1;
+
+    pub fn new_default() -> Self {
+        let kind = ProviderKind::Ollama;
+        Self {
+            version: Self::CURRENT_VERSION,
+            include: Vec::new(),
+            language: "en".to_string(),
+            ui_mode: UiMode::Tui,
+            provider: ProviderConfig {
+                kind: kind.clone(),
+                model: kind.default_model().to_string(),
+                base_url: kind.default_base_url().map(|s| s.to_string()),
+                api_key_env: kind.default_api_key_env().map(|s| s.to_string()),
+                respond_in_config_language: false,
+                params: GenParams::default(),
+                organization: None,
+                project: None,
+                embedding_model: None,
+                extra_options: toml::Table::new(),
+                fallbacks: Vec::new(),
+                autodetect: true,
+            },
+            features: Features {
+                system_scan: true,
+                web_in_terminal: true,
+                command_suggestions: true,
+                safe_execute: true,
+                retrieval: false,
+            },
+            caps: Capabilities {
+                read_files: true,
+                write_files: false,
+                network: true,
+                run_commands: false,
+                read_allow: Vec::new(),
+                read_deny: Vec::new(),
+                write_allow: Vec::new(),
+                write_deny: Vec::new(),
+                network_allow: Vec::new(),
+                network_deny: Vec::new(),
+            },
+            logging: LoggingConfig::default(),
+            keybindings: KeyBindings::default(),
+            cache: CacheConfig::default(),
+            sessions: SessionsConfig::default(),
+            ui: UiConfig::default(),
+            network: NetworkConfig::default(),
+            style: StyleConfig::default(),
+            updates: UpdatesConfig::default(),
+            i18n: I18nConfig::default(),
+            context: ContextConfig::default(),
+            history: HistoryConfig::default(),
+            exec: ExecConfig::default(),
+            privacy: PrivacyConfig::default(),
+            budget: BudgetConfig::default(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.version != Self::CURRENT_VERSION {
+            return Err(ConfigError::UnsupportedVersion(self.version));
+        }
+
+        let allowed = allowed_languages();
+        if !allowed.contains(self.language.as_str()) {
+            return Err(ConfigError::InvalidLanguage(self.language.clone()));
+        }
+
+        self.provider.validate()?;
+        for (i, fallback) in self.provider.fallbacks.iter().enumerate() {
+            let index = i + 1;
+            if !fallback.fallbacks.is_empty() {
+                return Err(ConfigError::NestedFallbacks { index });
+            }
+            fallback.validate().map_err(|e| ConfigError::InvalidFallback { index, error: Box::new(e) })?;
+        }
+        self.caps.validate()?;
+        self.privacy.validate()?;
+        self.budget.validate()?;
+        self.style.validate()?;
+        self.keybindings.validate()
+    }
+
+    /// Whether privileged actions are written to the audit log (`logging.audit`).
+    pub fn audit_enabled(&self) -> bool {
+        self.logging.audit.unwrap_or(self.caps.write_files || self.caps.run_commands)
+    }
+
+    /// This config with the provider and/or model replaced, e.g. for a single run.
+    /// A different provider brings its own defaults (model, base_url, api_key_env); the
+    /// saved provider keeps its endpoint settings. `model` is applied last.
+    pub fn with_overrides(&self, kind: Option<&ProviderKind>, model: Option<&str>) -> AppConfig {
+        let mut out = self.clone();
+        if let Some(kind) = kind {
+            if *kind != out.provider.kind {
+                out.set_provider_kind(kind.clone());
+            }
+        }
+        if let Some(model) = model {
+            out.provider.model = model.to_string();
+        }
+        out
+    }
+
+    /// Switch to `kind` with its defaults. Extra options are dropped with the rest: they
+    /// are written for one provider's API.
+    pub fn set_provider_kind(&mut self, kind: ProviderKind) {
+        self.provider.kind = kind.clone();
+        self.provider.model = kind.default_model().to_string();
+        self.provider.base_url = kind.default_base_url().map(|s| s.to_string());
+        self.provider.api_key_env = kind.default_api_key_env().map(|s| s.to_string());
+        self.provider.organization = None;
+        self.provider.project = None;
+        self.provider.extra_options.clear();
+    }
+}
+
+pub fn allowed_languages() -> BTreeSet<&'static str> {
+    BTreeSet::from(["en", "ar"])
+}
\ No newline at end of file
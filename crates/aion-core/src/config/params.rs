@@ -0,0 +1,121 @@
+//! Generation parameters (`[provider.params]`) and how their layers combine.
+//!
+//! Notes:
+//! - Three layers, highest first: request-level overrides (the REPL's `/temp`), the
+//!   config's `provider.params`, then the provider's defaults (`default_params`). A field
+//!   left unset in one layer falls through to the next.
+//! - Provider defaults cover what a backend needs to behave well: Ollama gets an explicit
+//!   `num_ctx` (its own default context is small), Claude a `max_tokens` (required by
+//!   its API).
+//! - Each client sends only the fields its API understands; `num_ctx` is Ollama-only.
+//! - `validate` runs on the merged result, so a bad value is caught whichever layer
+//!   it came from.
+//!
+//! ```
+//! use aion_core::config::{GenParams, ProviderConfig};
+//!
+//! let provider = |kind: &str, params: &str| -> ProviderConfig {
+//!     toml::from_str(&format!("kind = \"{}\"\nmodel = \"m\"\n\n[params]\n{}", kind, params)).unwrap()
+//! };
+//! let none = GenParams::default();
+//!
+//! // Nothing set: only the provider's defaults.
+//! assert_eq!(provider("Ollama", "").effective_params(&none), GenParams { num_ctx: Some(8192), ..none });
+//! assert_eq!(provider("Claude", "").effective_params(&none), GenParams { max_tokens: Some(4096), ..none });
+//! assert!(provider("OpenAI", "").effective_params(&none).is_empty());
+//!
+//! // `provider.params` replaces a default field by field and adds to the rest.
+//! let ollama = provider("Ollama", "temperature = 0.3\nnum_ctx = 4096");
+//! assert_eq!(ollama.effective_params(&none), GenParams { temperature: Some(0.3), num_ctx: Some(4096), ..none });
+//! let claude = provider("Claude", "top_p = 0.9");
+//! assert_eq!(claude.effective_params(&none), GenParams { top_p: Some(0.9), max_tokens: Some(4096), ..none });
+//!
+//! // A request-level value wins over both; what it leaves unset falls through.
+//! let request = GenParams { temperature: Some(1.0), max_tokens: Some(200), ..none };
+//! assert_eq!(
+//!     ollama.effective_params(&request),
+//!     GenParams { temperature: Some(1.0), max_tokens: Some(200), num_ctx: Some(4096), ..none }
+//! );
+//! assert_eq!(claude.effective_params(&request), GenParams { top_p: Some(0.9), ..request });
+//!
+//! // Checked after merging: a bad config value is caught unless a request replaces it.
+//! let hot = provider("OpenAI", "temperature = 3.0");
+//! assert!(hot.effective_params(&none).validate().is_err());
+//! assert!(hot.effective_params(&request).validate().is_ok());
+//! ```
+
+use super::{ConfigError, ProviderConfig, ProviderKind};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    /// Longest reply, in tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Context window, in tokens (Ollama).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+}
+
+impl GenParams {
+    pub fn is_empty(&self) -> bool {
+        *self == GenParams::default()
+    }
+
+    /// `self`, with unset fields taken from `under`.
+    pub fn over(self, under: GenParams) -> GenParams {
+        GenParams {
+            temperature: self.temperature.or(under.temperature),
+            top_p: self.top_p.or(under.top_p),
+            max_tokens: self.max_tokens.or(under.max_tokens),
+            num_ctx: self.num_ctx.or(under.num_ctx),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let out_of_range = |name: &'static str, value: String, range: &'static str| {
+            Err(ConfigError::ParamOutOfRange { name, value, range })
+        };
+        if let Some(t) = self.temperature.filter(|t| !(0.0..=2.0).contains(t)) {
+            return out_of_range("temperature", t.to_string(), "0 to 2");
+        }
+        if let Some(p) = self.top_p.filter(|p| !(*p > 0.0 && *p <= 1.0)) {
+            return out_of_range("top_p", p.to_string(), "above 0, up to 1");
+        }
+        if let Some(0) = self.max_tokens {
+            return out_of_range("max_tokens", "0".to_string(), "at least 1");
+        }
+        if let Some(0) = self.num_ctx {
+            return out_of_range("num_ctx", "0".to_string(), "at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// The parameters a request is sent with: `request` over `config` over `defaults`.
+pub fn merge(request: &GenParams, config: &GenParams, defaults: &GenParams) -> GenParams {
+    request.over(*config).over(*defaults)
+}
+
+impl ProviderKind {
+    /// Parameters sent when neither the request nor the config sets them.
+    pub fn default_params(&self) -> GenParams {
+        match self {
+            ProviderKind::Ollama => GenParams { num_ctx: Some(8192), ..GenParams::default() },
+            ProviderKind::Claude => GenParams { max_tokens: Some(4096), ..GenParams::default() },
+            ProviderKind::OpenAI | ProviderKind::OpenRouter => GenParams::default(),
+        }
+    }
+}
+
+impl ProviderConfig {
+    /// `params` and the provider's defaults under the request-level `request`.
+    pub fn effective_params(&self, request: &GenParams) -> GenParams {
+        merge(request, &self.params, &self.kind.default_params())
+    }
+}
@@ -0,0 +1,153 @@
+//! Reply tone and format presets (`[style]`) and the system prompt they add to.
+//!
+//! Notes:
+//! - `tone` (`concise`, `detailed`, `eli5`) and `format` (`markdown`, `plain`, `json`)
+//!   each add one instruction to the system prompt, after the reply-language one. The
+//!   English wording in `TONES` / `FORMATS` is the fallback of the locale keys
+//!   `style.tone.<name>` / `style.format.<name>`.
+//! - Layers, highest first: `--tone` / `--format` for the run (`pin`, process-wide like
+//!   `dry_run`), the session's `/tone`, then the config. `effective` applies the pinned
+//!   layer over a config's `style`, which the session edits in place.
+//! - `format = "json"` also switches on the provider's own JSON mode where it has one
+//!   (OpenAI's `response_format`, Ollama's `format`); Claude gets the instruction only.
+//! - `compose_system` is pure: the caller supplies the snippet lookup, so the prompt for
+//!   a given style is always the same.
+//!
+//! ```
+//! use aion_core::config::style::{compose_system, StyleConfig};
+//!
+//! let english = |_key: &str, fallback: &str| fallback.to_string();
+//! let style = |tone: Option<&str>, format: Option<&str>| StyleConfig {
+//!     tone: tone.map(String::from),
+//!     format: format.map(String::from),
+//! };
+//!
+//! assert_eq!(compose_system(None, &style(None, None), english), None);
+//! assert_eq!(
+//!     compose_system(None, &style(Some("concise"), None), english).as_deref(),
+//!     Some("Be concise: answer in a few sentences and leave out background unless asked.")
+//! );
+//! assert_eq!(
+//!     compose_system(Some("Reply in Arabic."), &style(Some("eli5"), Some("json")), english).as_deref(),
+//!     Some(
+//!         "Reply in Arabic.\n\n\
+//!          Explain as you would to a curious ten-year-old: plain words, short sentences, one everyday example.\n\n\
+//!          Reply with a single valid JSON value and nothing else: no prose, no code fences."
+//!     )
+//! );
+//! // The session's tone over the config's; the config's format still applies.
+//! let merged = style(Some("detailed"), None).over(&style(Some("concise"), Some("plain")));
+//! assert_eq!(merged, style(Some("detailed"), Some("plain")));
+//! assert!(style(None, Some("json")).json_mode());
+//! assert!(style(Some("pirate"), None).validate().is_err());
+//! ```
+
+use super::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Tones and their English instruction.
+pub const TONES: [(&str, &str); 3] = [
+    ("concise", "Be concise: answer in a few sentences and leave out background unless asked."),
+    ("detailed", "Be thorough: explain your reasoning, cover edge cases, and give examples."),
+    (
+        "eli5",
+        "Explain as you would to a curious ten-year-old: plain words, short sentences, one everyday example.",
+    ),
+];
+
+/// Formats and their English instruction.
+pub const FORMATS: [(&str, &str); 3] = [
+    ("markdown", "Format replies as Markdown, with headings, lists, and fenced code blocks where they help."),
+    ("plain", "Reply in plain text without any Markdown: no headings, bullets, emphasis, or code fences."),
+    ("json", "Reply with a single valid JSON value and nothing else: no prose, no code fences."),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleConfig {
+    /// One of `TONES`; unset adds nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+    /// One of `FORMATS`; unset adds nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl StyleConfig {
+    /// `self`, with unset fields taken from `under`.
+    pub fn over(&self, under: &StyleConfig) -> StyleConfig {
+        StyleConfig {
+            tone: self.tone.clone().or_else(|| under.tone.clone()),
+            format: self.format.clone().or_else(|| under.format.clone()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check("tone", self.tone.as_deref(), &TONES)?;
+        check("format", self.format.as_deref(), &FORMATS)
+    }
+
+    /// Whether replies should be JSON, so providers with a JSON mode use it.
+    pub fn json_mode(&self) -> bool {
+        self.format.as_deref() == Some("json")
+    }
+}
+
+static PINNED: OnceLock<StyleConfig> = OnceLock::new();
+
+/// Pin `--tone` / `--format` for the rest of the process. Only the first call counts.
+pub fn pin(flags: StyleConfig) {
+    let _ = PINNED.set(flags);
+}
+
+/// The style pinned by `pin`; empty when nothing was.
+pub fn pinned() -> StyleConfig {
+    PINNED.get().cloned().unwrap_or_default()
+}
+
+/// `style` (the config's, as the session edited it) under the pinned flags.
+pub fn effective(style: &StyleConfig) -> StyleConfig {
+    pinned().over(style)
+}
+
+/// Names of `table`, e.g. for a usage message.
+pub fn names(table: &[(&'static str, &str)]) -> Vec<&'static str> {
+    table.iter().map(|(name, _)| *name).collect()
+}
+
+fn check(field: &'static str, value: Option<&str>, table: &[(&'static str, &str)]) -> Result<(), ConfigError> {
+    match value {
+        Some(v) if !table.iter().any(|(name, _)| *name == v) => {
+            Err(ConfigError::InvalidStyle { field, value: v.to_string(), expected: names(table).join(", ") })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The system prompt: `base` (the reply-language instruction), then the tone's and the
+/// format's instruction, a blank line apart. `snippet(key, english)` gives the wording
+/// for a locale key such as `style.tone.concise`. Unknown names add nothing.
+pub fn compose_system(
+    base: Option<&str>,
+    style: &StyleConfig,
+    snippet: impl Fn(&str, &str) -> String,
+) -> Option<String> {
+    let lookup = |kind: &str, name: Option<&str>, table: &[(&str, &str)]| {
+        let name = name?;
+        let (_, english) = table.iter().find(|(n, _)| *n == name)?;
+        Some(snippet(&format!("style.{}.{}", kind, name), english))
+    };
+    let parts: Vec<String> = [
+        base.map(String::from),
+        lookup("tone", style.tone.as_deref(), &TONES),
+        lookup("format", style.format.as_deref(), &FORMATS),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    match parts.is_empty() {
+        true => None,
+        false => Some(parts.join("\n\n")),
+    }
+}
@@ -0,0 +1,188 @@
+//! The effective config of a session as a tree, each value marked with where it came
+//! from (`/config` in the chat front ends).
+//!
+//! Notes:
+//! - Sources, first match wins: `Unsaved` (set with `/config set`, see `edit::Edits`),
+//!   `Session` (differs from the config file: `/temp`, `/model` without `--save`,
+//!   command-line flags), `Expanded` (`${VAR}` / `~` in the file, see `expand`), `File`,
+//!   `Included` (from a file the config file includes, see `include`), and `Default`
+//!   (in none of them; the built-in value).
+//! - Tables are branches; everything else, arrays included, is a leaf holding its TOML
+//!   rendering. As in a TOML file, each level lists its leaves before its tables. Unset
+//!   optional keys do not appear.
+//! - Values are not redacted here; the front ends redact what they show.
+//!
+//! ```
+//! use aion_core::config::expand::Expanded;
+//! use aion_core::config::include::Included;
+//! use aion_core::config::tree::{build, Layers, Source};
+//! use aion_core::config::{diff, AppConfig};
+//!
+//! let mut file_cfg = AppConfig::new_default();
+//! file_cfg.cache.dir = Some("/home/ada/cache".into());
+//! let mut file = diff::to_table(&file_cfg).unwrap();
+//! file.remove("updates");
+//! file.remove("history");
+//! let mut included = Included::default();
+//! included.origins.insert("history.max_entries".into(), "/srv/team/aion.toml".into());
+//! let mut effective = file_cfg.clone();
+//! effective.provider.model = "llama3".into();
+//! effective.ui.ascii_only = true;
+//! let expanded = [Expanded { field: "cache.dir".into(), template: "~/cache".into(), value: "/home/ada/cache".into() }];
+//!
+//! let layers = Layers { file: Some(&file), saved: &file_cfg, expanded: &expanded, included: &included, unsaved: &["ui.ascii_only"] };
+//! let tree = build(&effective, &layers).unwrap();
+//! let find = |path: &str| tree.iter().flat_map(|n| n.walk()).find(|n| n.path == path).unwrap().clone();
+//!
+//! assert_eq!(find("provider.model").source, Some(Source::Session));
+//! assert_eq!(find("provider.model").value.as_deref(), Some("\"llama3\""));
+//! assert_eq!(find("ui.ascii_only").source, Some(Source::Unsaved));
+//! assert_eq!(find("cache.dir").source, Some(Source::Expanded("~/cache".into())));
+//! assert_eq!(find("language").source, Some(Source::File));
+//! assert_eq!(find("updates.check_on_start").source, Some(Source::Default));
+//! assert_eq!(find("history.max_entries").source, Some(Source::Included("/srv/team/aion.toml".into())));
+//! assert_eq!(find("provider").source, None);
+//! assert!(find("provider").children.iter().any(|n| n.name == "model"));
+//! assert!(tree.iter().position(|n| n.name == "version") < tree.iter().position(|n| n.name == "cache"));
+//! ```
+
+use super::expand::Expanded;
+use super::include::Included;
+use super::io::{expanded_fields, included_fields, load_config, load_raw_config};
+use super::{diff, edit, AppConfig};
+use anyhow::Result;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Where a value came from; see the module notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    File,
+    Default,
+    /// Expanded from this template in the file.
+    Expanded(String),
+    /// From this included file.
+    Included(PathBuf),
+    Session,
+    Unsaved,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File => write!(f, "file"),
+            Source::Default => write!(f, "default"),
+            Source::Expanded(template) => write!(f, "from {}", template),
+            Source::Included(path) => write!(f, "included from {}", path.display()),
+            Source::Session => write!(f, "session"),
+            Source::Unsaved => write!(f, "unsaved"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: String,
+    /// Dotted key, as `aion config get` and `/config set` take it.
+    pub path: String,
+    /// The TOML rendering of a leaf; `None` for a table.
+    pub value: Option<String>,
+    /// `None` for a table.
+    pub source: Option<Source>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// This node and everything under it, depth first.
+    pub fn walk(&self) -> Vec<&Node> {
+        std::iter::once(self).chain(self.children.iter().flat_map(Node::walk)).collect()
+    }
+}
+
+/// What the effective config is compared against.
+#[derive(Debug, Clone, Copy)]
+pub struct Layers<'a> {
+    /// The config file as written; `None` when there is none.
+    pub file: Option<&'a toml::Table>,
+    /// The config file as loaded (migrated, expanded), or the defaults.
+    pub saved: &'a AppConfig,
+    pub expanded: &'a [Expanded],
+    /// What the config file's includes set.
+    pub included: &'a Included,
+    /// Keys set with `/config set` and not saved.
+    pub unsaved: &'a [&'a str],
+}
+
+/// `effective` as a tree, marked against `layers`.
+pub fn build(effective: &AppConfig, layers: &Layers<'_>) -> Result<Vec<Node>> {
+    let saved = toml::Value::Table(diff::to_table(layers.saved)?);
+    let file = layers.file.cloned().map(toml::Value::Table);
+    let ctx = Context { layers, saved: &saved, file: file.as_ref() };
+    Ok(nodes(&ctx, "", &diff::to_table(effective)?))
+}
+
+/// `build` against the config file as it is now.
+pub fn current(effective: &AppConfig, unsaved: &[&str]) -> Result<Vec<Node>> {
+    let file = load_raw_config().ok();
+    let saved = load_config().unwrap_or_else(|_| AppConfig::new_default());
+    let (expanded, included) = match file {
+        Some(_) => (expanded_fields(), included_fields()),
+        None => (Vec::new(), Included::default()),
+    };
+    build(effective, &Layers { file: file.as_ref(), saved: &saved, expanded: &expanded, included: &included, unsaved })
+}
+
+struct Context<'a> {
+    layers: &'a Layers<'a>,
+    saved: &'a toml::Value,
+    file: Option<&'a toml::Value>,
+}
+
+fn nodes(ctx: &Context<'_>, prefix: &str, table: &toml::Table) -> Vec<Node> {
+    let (tables, leaves): (Vec<_>, Vec<_>) = table.iter().partition(|(_, value)| value.is_table());
+    leaves
+        .into_iter()
+        .chain(tables)
+        .map(|(name, value)| {
+            let path = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+            match value {
+                toml::Value::Table(inner) => Node {
+                    name: name.clone(),
+                    children: nodes(ctx, &path, inner),
+                    path,
+                    value: None,
+                    source: None,
+                },
+                leaf => Node {
+                    name: name.clone(),
+                    value: Some(leaf.to_string()),
+                    source: Some(source(ctx, &path, leaf)),
+                    path,
+                    children: Vec::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+fn source(ctx: &Context<'_>, path: &str, value: &toml::Value) -> Source {
+    let under = |key: &str| path == key || path.starts_with(&format!("{}.", key));
+    if ctx.layers.unsaved.iter().any(|key| under(key)) {
+        return Source::Unsaved;
+    }
+    if edit::get(ctx.saved, path) != Some(value) {
+        return Source::Session;
+    }
+    // `provider.fallbacks` is expanded per entry (`provider.fallbacks[1].base_url`).
+    let expanded = ctx.layers.expanded.iter().find(|e| e.field == path || e.field.starts_with(&format!("{}[", path)));
+    if let Some(e) = expanded {
+        return Source::Expanded(e.template.clone());
+    }
+    if ctx.file.and_then(|file| edit::get(file, path)).is_some() {
+        return Source::File;
+    }
+    match ctx.layers.included.origin(path) {
+        Some(file) => Source::Included(file.to_path_buf()),
+        None => Source::Default,
+    }
+}
@@ -0,0 +1,164 @@
+//! The conversation sent to the provider: completed exchanges, oldest first.
+//!
+//! Notes:
+//! - Only completed exchanges are kept. The prompt in flight is added by `with_prompt`
+//!   and becomes an exchange once its reply arrives, so a failed or cancelled request
+//!   leaves nothing behind.
+//! - `/regen` takes the last exchange off (`pop`), resends its prompt, and puts it back
+//!   (`push_exchange`) if the new request does not complete. `/undo` only pops it.
+//! - This is what the provider sees next. The saved `Session` keeps everything, with
+//!   replaced and undone messages marked `superseded`.
+//! - `/continue` sends `CONTINUE_PROMPT` after a reply cut off at the token limit; what
+//!   comes back is added to that reply (`extend_last`), not kept as an exchange of its own.
+//! - A request holds at most `sessions.max_context_tokens` (estimated, `estimate_tokens`):
+//!   the oldest exchanges are left out, whole, until the rest fits. The prompt is always
+//!   sent, even alone over the budget. Left out is not forgotten: they stay here and in
+//!   the session, and `/clear` is still what drops them.
+//!
+//! ```
+//! use aion_core::conversation::{estimate_tokens, Conversation, Exchange};
+//!
+//! let texts = |messages: Vec<aion_core::providers::Message>| messages.into_iter().map(|m| m.content).collect::<Vec<_>>();
+//!
+//! // Exchanges come and go at the tail.
+//! let mut c = Conversation::new();
+//! assert!(!c.extend_last("nothing to extend"));
+//! c.push("one", "1");
+//! c.push("two", "2, cut");
+//! assert!(c.extend_last(" off"));
+//! assert_eq!(c.last().unwrap().reply, "2, cut off");
+//! let regen = c.pop().unwrap();
+//! assert_eq!(texts(c.with_prompt(&regen.prompt, 0)), ["one", "1", "two"]);
+//! c.push_exchange(regen);
+//! assert_eq!(c.len(), 2);
+//!
+//! // Four characters a token, rounded up.
+//! assert_eq!(estimate_tokens(""), 0);
+//! assert_eq!(estimate_tokens("abcd"), 1);
+//! assert_eq!(estimate_tokens("abcde"), 2);
+//! assert_eq!(estimate_tokens("مرحبا"), 2);
+//!
+//! // Each exchange below is 2 + 2 tokens, the prompt 1.
+//! let mut c = Conversation::new();
+//! for n in 1..=3 {
+//!     c.push(format!("prompt {n}"), format!("reply {n}"));
+//! }
+//! let all = ["prompt 1", "reply 1", "prompt 2", "reply 2", "prompt 3", "reply 3", "next"];
+//! assert_eq!(texts(c.with_prompt("next", 0)), all);
+//! assert_eq!(texts(c.with_prompt("next", 13)), all);
+//! // One token short: the oldest exchange goes, whole.
+//! assert_eq!(texts(c.with_prompt("next", 12)), all[2..]);
+//! assert_eq!(texts(c.with_prompt("next", 5)), all[4..]);
+//! // The prompt is sent even when it alone is over the budget.
+//! assert_eq!(texts(c.with_prompt("next", 4)), ["next"]);
+//! assert_eq!(texts(c.with_prompt(&"x".repeat(400), 1)).len(), 1);
+//! // Nothing is dropped from the conversation itself.
+//! assert_eq!(c.len(), 3);
+//! assert_eq!(c.messages().len(), 6);
+//! assert_eq!(c.last(), Some(&Exchange { prompt: "prompt 3".into(), reply: "reply 3".into() }));
+//! ```
+
+use crate::providers::Message;
+
+/// What `/continue` asks for.
+pub const CONTINUE_PROMPT: &str = "Continue from where you stopped, without repeating anything.";
+
+/// Characters per token for `estimate_tokens`: about right for English with the common
+/// tokenizers, generous for most other text.
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// Roughly how many tokens `text` is; no tokenizer is at hand for every provider.
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    pub prompt: String,
+    pub reply: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Conversation {
+    exchanges: Vec<Exchange>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.exchanges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exchanges.is_empty()
+    }
+
+    pub fn push(&mut self, prompt: impl Into<String>, reply: impl Into<String>) {
+        self.push_exchange(Exchange { prompt: prompt.into(), reply: reply.into() });
+    }
+
+    pub fn push_exchange(&mut self, exchange: Exchange) {
+        self.exchanges.push(exchange);
+    }
+
+    /// Remove and return the last exchange.
+    pub fn pop(&mut self) -> Option<Exchange> {
+        self.exchanges.pop()
+    }
+
+    pub fn last(&self) -> Option<&Exchange> {
+        self.exchanges.last()
+    }
+
+    /// Add `more` to the last reply (`/continue`). `false` when there is none.
+    pub fn extend_last(&mut self, more: &str) -> bool {
+        match self.exchanges.last_mut() {
+            Some(last) => {
+                last.reply.push_str(more);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.exchanges.clear();
+    }
+
+    /// Every exchange as a user turn and an assistant turn.
+    pub fn messages(&self) -> Vec<Message> {
+        self.exchanges
+            .iter()
+            .flat_map(|e| [Message::user(e.prompt.clone()), Message::assistant(e.reply.clone())])
+            .collect()
+    }
+
+    /// `messages`, then `prompt` as the next user turn, leaving out the oldest exchanges
+    /// while the whole is over `max_tokens` (0: no limit).
+    pub fn with_prompt(&self, prompt: &str, max_tokens: u64) -> Vec<Message> {
+        let mut messages: Vec<Message> = self.exchanges[self.first_kept(prompt, max_tokens)..]
+            .iter()
+            .flat_map(|e| [Message::user(e.prompt.clone()), Message::assistant(e.reply.clone())])
+            .collect();
+        messages.push(Message::user(prompt));
+        messages
+    }
+
+    /// Index of the oldest exchange `with_prompt` sends.
+    fn first_kept(&self, prompt: &str, max_tokens: u64) -> usize {
+        if max_tokens == 0 {
+            return 0;
+        }
+        let mut total = estimate_tokens(prompt);
+        for (i, e) in self.exchanges.iter().enumerate().rev() {
+            total += estimate_tokens(&e.prompt) + estimate_tokens(&e.reply);
+            if total > max_tokens {
+                return i + 1;
+            }
+        }
+        0
+    }
+}
@@ -0,0 +1,603 @@
+//! What requests to cloud providers cost, kept in an append-only ledger, and the spending
+//! limits on it (`budget.per_session_usd`, `budget.per_month_usd`).
+//!
+//! Notes:
+//! - Each reply with usage from a metered provider is one `Record`, appended to
+//!   `usage/<YYYY-MM>.jsonl` in the state dir for the month (UTC) it was made in. A new
+//!   month starts a new file; that is the rollover.
+//! - Metered: anything but Ollama and endpoints on this machine or network
+//!   (`ProviderConfig::is_local`, e.g. LM Studio). Those are never recorded or refused.
+//! - A record is one line written with one append. Lines that do not parse (a crash
+//!   mid-write) are skipped, so the totals survive a crash with at most that request lost.
+//! - Cost is `bench::Price` per million tokens: `budget.prices` by model name, else the
+//!   built-in `PRICES` (the longest matching prefix). A model with neither is recorded
+//!   with its tokens and no cost, and counts nothing toward a budget.
+//! - `build_client` wraps each metered provider's client in a `MeteredClient`. Before a
+//!   request it is refused with `BudgetExceeded` once either budget is spent; after, the
+//!   reply's usage is recorded.
+//! - The session budget counts the records of the current chat session (`set_session`;
+//!   else this process) in this month's and last month's files; the month budget counts
+//!   this month's.
+//! - At `WARN_AT` of a budget a `BudgetWarning` is queued, once per session or month.
+//!   The front ends show `take_warnings` under the reply, so a warning never lands in
+//!   the middle of one.
+//! - Like `audit`, the tracker is process-wide: `init` once the config is loaded. An
+//!   ephemeral process (see `crate::persistence`) keeps its records in memory, on top of
+//!   what the ledger already holds.
+//! - Time comes from a `Clock`: `FixedClock` makes months and rollover testable.
+//!
+//! ```
+//! use aion_core::config::{BudgetConfig, ProviderKind};
+//! use aion_core::costs::{month_of, summarize, FixedClock, Ledger, Limit, Tracker};
+//! use aion_core::providers::Usage;
+//! use std::sync::Arc;
+//!
+//! let dir = std::env::temp_dir().join(format!("aion-costs-{}", std::process::id()));
+//! let _ = std::fs::remove_dir_all(&dir);
+//!
+//! // 2026-10-31 23:00 UTC, an hour before the month ends.
+//! let clock = Arc::new(FixedClock::new(1_793_487_600));
+//! assert_eq!(month_of(clock.secs()), "2026-10");
+//! let budget = BudgetConfig { per_session_usd: Some(1.0), per_month_usd: Some(2.0), ..Default::default() };
+//! let tracker = Tracker::new(Ledger::open(&dir, true), &budget, clock.clone());
+//! tracker.set_session("a");
+//!
+//! // gpt-4o-mini: $0.15 in, $0.60 out per million tokens.
+//! let usage = Usage { input_tokens: 2_000_000, output_tokens: 500_000 };
+//! let (record, warnings) = tracker.record(&ProviderKind::OpenAI, "gpt-4o-mini", usage).unwrap();
+//! assert!((record.cost_usd.unwrap() - 0.6).abs() < 1e-9);
+//! assert!(warnings.is_empty() && tracker.check().unwrap().is_empty());
+//!
+//! // $0.90 of the session's $1.00: one warning, then no more.
+//! let usage = Usage { input_tokens: 2_000_000, output_tokens: 0 };
+//! let (_, warnings) = tracker.record(&ProviderKind::OpenAI, "gpt-4o-mini", usage).unwrap();
+//! assert_eq!(warnings.iter().map(|w| w.limit).collect::<Vec<_>>(), [Limit::Session]);
+//! assert!(tracker.check().unwrap().is_empty());
+//!
+//! // Over it: refused, naming the limit and how to raise it.
+//! tracker.record(&ProviderKind::OpenAI, "gpt-4o-mini", usage).unwrap();
+//! let refused = tracker.check().unwrap_err();
+//! assert_eq!(refused.limit, Limit::Session);
+//! assert!(refused.to_string().contains("aion config set budget.per_session_usd"));
+//!
+//! // A new session may spend again, until the month's $2.00 is gone.
+//! tracker.set_session("b");
+//! assert!(tracker.check().is_ok());
+//! for _ in 0..3 {
+//!     tracker.record(&ProviderKind::OpenAI, "gpt-4o-mini", usage).unwrap();
+//! }
+//! assert_eq!(tracker.check().unwrap_err().limit, Limit::Month);
+//!
+//! // Models without a price are counted in tokens only.
+//! let (record, _) = tracker.record(&ProviderKind::OpenAI, "my-finetune", usage).unwrap();
+//! assert_eq!(record.cost_usd, None);
+//!
+//! // A torn last line (a crash mid-write) is skipped; the rest is still there.
+//! let file = dir.join("2026-10.jsonl");
+//! let mut text = std::fs::read_to_string(&file).unwrap();
+//! text.push_str("{\"at\":1793487600,\"provi");
+//! std::fs::write(&file, text).unwrap();
+//! let ledger = Ledger::open(&dir, true);
+//! let lines = summarize(&ledger.month("2026-10"));
+//! assert_eq!((lines.len(), lines[0].totals.requests, lines[1].totals.requests), (2, 6, 1));
+//! assert!((lines[0].totals.cost_usd - 2.1).abs() < 1e-9);
+//!
+//! // Two hours later it is November: the month starts over, the session does not.
+//! clock.advance(2 * 3_600);
+//! let tracker = Tracker::new(Ledger::open(&dir, true), &budget, clock.clone());
+//! tracker.set_session("a");
+//! assert_eq!(tracker.check().unwrap_err().limit, Limit::Session);
+//! tracker.set_session("c");
+//! assert!(tracker.check().is_ok());
+//! assert_eq!(Ledger::open(&dir, true).months(), ["2026-10"]);
+//! std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use crate::bench::Price;
+use crate::cancel::CancelToken;
+use crate::config::{AppConfig, BudgetConfig, ProviderConfig, ProviderKind};
+use crate::paths;
+use crate::persistence;
+use crate::providers::{ChatClient, ChatResponse, Message, ModelInfo, OnChunk, Usage};
+use crate::sessions::format_utc;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LEDGER_DIR_NAME: &str = "usage";
+
+/// The share of a budget at which it is warned about.
+pub const WARN_AT: f64 = 0.8;
+
+/// Built-in prices, in dollars per million input and output tokens, by model name prefix.
+pub const PRICES: &[(ProviderKind, &str, Price)] = &[
+    (ProviderKind::OpenAI, "gpt-4o", Price { input: 2.5, output: 10.0 }),
+    (ProviderKind::OpenAI, "gpt-4o-mini", Price { input: 0.15, output: 0.6 }),
+    (ProviderKind::OpenAI, "gpt-4.1", Price { input: 2.0, output: 8.0 }),
+    (ProviderKind::OpenAI, "gpt-4.1-mini", Price { input: 0.4, output: 1.6 }),
+    (ProviderKind::OpenAI, "gpt-4.1-nano", Price { input: 0.1, output: 0.4 }),
+    (ProviderKind::OpenAI, "gpt-4-turbo", Price { input: 10.0, output: 30.0 }),
+    (ProviderKind::OpenAI, "gpt-3.5-turbo", Price { input: 0.5, output: 1.5 }),
+    (ProviderKind::OpenAI, "o1", Price { input: 15.0, output: 60.0 }),
+    (ProviderKind::OpenAI, "o1-mini", Price { input: 1.1, output: 4.4 }),
+    (ProviderKind::OpenAI, "o3", Price { input: 2.0, output: 8.0 }),
+    (ProviderKind::OpenAI, "o3-mini", Price { input: 1.1, output: 4.4 }),
+    (ProviderKind::OpenAI, "o4-mini", Price { input: 1.1, output: 4.4 }),
+    (ProviderKind::Claude, "claude-3-5-sonnet", Price { input: 3.0, output: 15.0 }),
+    (ProviderKind::Claude, "claude-3-7-sonnet", Price { input: 3.0, output: 15.0 }),
+    (ProviderKind::Claude, "claude-sonnet-4", Price { input: 3.0, output: 15.0 }),
+    (ProviderKind::Claude, "claude-3-5-haiku", Price { input: 0.8, output: 4.0 }),
+    (ProviderKind::Claude, "claude-3-haiku", Price { input: 0.25, output: 1.25 }),
+    (ProviderKind::Claude, "claude-3-opus", Price { input: 15.0, output: 75.0 }),
+    (ProviderKind::Claude, "claude-opus-4", Price { input: 15.0, output: 75.0 }),
+    (ProviderKind::OpenRouter, "openai/gpt-4o", Price { input: 2.5, output: 10.0 }),
+    (ProviderKind::OpenRouter, "openai/gpt-4o-mini", Price { input: 0.15, output: 0.6 }),
+    (ProviderKind::OpenRouter, "openai/gpt-4.1-mini", Price { input: 0.4, output: 1.6 }),
+    (ProviderKind::OpenRouter, "anthropic/claude-3.5-sonnet", Price { input: 3.0, output: 15.0 }),
+    (ProviderKind::OpenRouter, "anthropic/claude-3.5-haiku", Price { input: 0.8, output: 4.0 }),
+];
+
+static GLOBAL: RwLock<Option<Arc<Tracker>>> = RwLock::new(None);
+
+/// Warnings not shown yet.
+static PENDING: Mutex<Vec<BudgetWarning>> = Mutex::new(Vec::new());
+
+static WARNED_WRITE: AtomicBool = AtomicBool::new(false);
+
+/* ---------------------------
+   Clock
+---------------------------- */
+
+/// Wall-clock time in Unix seconds.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+    }
+}
+
+/// A clock that only moves when told to.
+pub struct FixedClock {
+    secs: AtomicU64,
+}
+
+impl FixedClock {
+    pub fn new(secs: u64) -> Self {
+        Self { secs: AtomicU64::new(secs) }
+    }
+
+    pub fn secs(&self) -> u64 {
+        self.secs.load(Ordering::Relaxed)
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.secs.fetch_add(secs, Ordering::Relaxed);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.secs()
+    }
+}
+
+/// `YYYY-MM` (UTC) of Unix seconds `secs`.
+pub fn month_of(secs: u64) -> String {
+    format_utc(secs)[..7].to_string()
+}
+
+/// The month before `YYYY-MM` `month`.
+pub fn previous_month(month: &str) -> String {
+    let (year, number) = month.split_once('-').unwrap_or_default();
+    let (year, number): (i64, u32) = (year.parse().unwrap_or(1970), number.parse().unwrap_or(1));
+    match number {
+        0 | 1 => format!("{:04}-12", year - 1),
+        n => format!("{:04}-{:02}", year, n - 1),
+    }
+}
+
+/* ---------------------------
+   Ledger
+---------------------------- */
+
+/// One metered request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix seconds.
+    pub at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    pub provider: ProviderKind,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// `None` when the model has no known price.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// Where the ledger's monthly files are kept.
+pub fn ledger_dir() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(LEDGER_DIR_NAME))
+}
+
+/// The records, one file per month. With `persist` off, new records are kept in memory.
+pub struct Ledger {
+    dir: PathBuf,
+    persist: AtomicBool,
+    memory: Mutex<Vec<Record>>,
+}
+
+impl Ledger {
+    pub fn open(dir: &Path, persist: bool) -> Self {
+        Self { dir: dir.to_path_buf(), persist: AtomicBool::new(persist), memory: Mutex::new(Vec::new()) }
+    }
+
+    pub fn set_persist(&self, persist: bool) {
+        self.persist.store(persist, Ordering::Relaxed);
+    }
+
+    fn file(&self, month: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", month))
+    }
+
+    pub fn append(&self, record: &Record) -> Result<()> {
+        if !self.persist.load(Ordering::Relaxed) {
+            self.memory.lock().unwrap_or_else(|e| e.into_inner()).push(record.clone());
+            return Ok(());
+        }
+        let path = self.file(&month_of(record.at));
+        let mut line = serde_json::to_string(record).context("failed to serialize usage record")?;
+        line.push('\n');
+        paths::ensure(&self.dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.write_all(line.as_bytes()).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// The records of `YYYY-MM` `month`, oldest first.
+    pub fn month(&self, month: &str) -> Vec<Record> {
+        let text = fs::read_to_string(self.file(month)).unwrap_or_default();
+        let mut records: Vec<Record> = text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+        let memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        records.extend(memory.iter().filter(|r| month_of(r.at) == month).cloned());
+        records
+    }
+
+    /// The months with a file, oldest first.
+    pub fn months(&self) -> Vec<String> {
+        let mut months: Vec<String> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".jsonl").map(str::to_string))
+            .collect();
+        months.sort();
+        months
+    }
+}
+
+/// Tokens and cost added up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Totals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Requests with no known price, so not in `cost_usd`.
+    pub unpriced: u64,
+}
+
+impl Totals {
+    pub fn add(&mut self, record: &Record) {
+        self.requests += 1;
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        match record.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced += 1,
+        }
+    }
+}
+
+/// One provider and model's totals (`aion usage`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Line {
+    pub provider: ProviderKind,
+    pub model: String,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+/// Totals per provider and model, by provider and then model name.
+pub fn summarize(records: &[Record]) -> Vec<Line> {
+    let mut by_model: BTreeMap<(&str, &str), Line> = BTreeMap::new();
+    for record in records {
+        by_model
+            .entry((record.provider.id(), &record.model))
+            .or_insert_with(|| Line { provider: record.provider.clone(), model: record.model.clone(), totals: Totals::default() })
+            .totals
+            .add(record);
+    }
+    by_model.into_values().collect()
+}
+
+/* ---------------------------
+   Budgets
+---------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Limit {
+    Session,
+    Month,
+}
+
+impl Limit {
+    /// The setting it comes from.
+    pub fn key(self) -> &'static str {
+        match self {
+            Limit::Session => "budget.per_session_usd",
+            Limit::Month => "budget.per_month_usd",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Limit::Session => "session",
+            Limit::Month => "monthly",
+        }
+    }
+}
+
+/// A budget was spent; requests to metered providers are refused until it is raised.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "the {} budget of ${:.2} is spent (${:.2} so far); raise it with `aion config set {} <amount>`",
+    .limit.describe(), .budget, .spent, .limit.key()
+)]
+pub struct BudgetExceeded {
+    pub limit: Limit,
+    pub budget: f64,
+    pub spent: f64,
+}
+
+/// Spending reached `WARN_AT` of a budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetWarning {
+    pub limit: Limit,
+    pub budget: f64,
+    pub spent: f64,
+}
+
+impl fmt::Display for BudgetWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "budget: ${:.2} of the {} budget of ${:.2} spent ({:.0}%)",
+            self.spent,
+            self.limit.describe(),
+            self.budget,
+            self.spent / self.budget * 100.0
+        )
+    }
+}
+
+/// Whether requests to `cfg` are recorded and can be refused.
+pub fn is_metered(cfg: &ProviderConfig) -> bool {
+    cfg.kind != ProviderKind::Ollama && !cfg.is_local()
+}
+
+/// Records requests and holds them to the budgets.
+pub struct Tracker {
+    ledger: Ledger,
+    budget: RwLock<BudgetConfig>,
+    clock: Arc<dyn Clock>,
+    session: RwLock<String>,
+    /// `session:<id>` and `month:<YYYY-MM>` warned about.
+    warned: Mutex<BTreeSet<String>>,
+}
+
+impl Tracker {
+    /// A tracker for this process as its session, until `set_session`.
+    pub fn new(ledger: Ledger, budget: &BudgetConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            ledger,
+            budget: RwLock::new(budget.clone()),
+            clock,
+            session: RwLock::new(format!("run-{}", uuid::Uuid::new_v4())),
+            warned: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    pub fn set_budget(&self, budget: &BudgetConfig) {
+        *self.budget.write().unwrap_or_else(|e| e.into_inner()) = budget.clone();
+    }
+
+    pub fn set_session(&self, id: &str) {
+        *self.session.write().unwrap_or_else(|e| e.into_inner()) = id.to_string();
+    }
+
+    /// `model`'s price: `budget.prices`, else the longest prefix in `PRICES`.
+    pub fn price(&self, kind: &ProviderKind, model: &str) -> Option<Price> {
+        let budget = self.budget.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(price) = budget.prices.get(model).and_then(|p| p.parse().ok()) {
+            return Some(price);
+        }
+        PRICES
+            .iter()
+            .filter(|(k, prefix, _)| k == kind && model.starts_with(prefix))
+            .max_by_key(|(_, prefix, _)| prefix.len())
+            .map(|(_, _, price)| *price)
+    }
+
+    /// Dollars spent in the current session, and in the current month.
+    pub fn spent(&self) -> (f64, f64) {
+        let month = month_of(self.clock.now());
+        let session = self.session.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let this_month = self.ledger.month(&month);
+        let cost = |records: &[Record], only_session: bool| -> f64 {
+            records
+                .iter()
+                .filter(|r| !only_session || r.session.as_deref() == Some(session.as_str()))
+                .filter_map(|r| r.cost_usd)
+                .sum()
+        };
+        let by_session = cost(&self.ledger.month(&previous_month(&month)), true) + cost(&this_month, true);
+        (by_session, cost(&this_month, false))
+    }
+
+    /// Before a request: refused once a budget is spent, with any warnings due.
+    pub fn check(&self) -> Result<Vec<BudgetWarning>, BudgetExceeded> {
+        let (session, month) = self.spent();
+        let budget = self.budget.read().unwrap_or_else(|e| e.into_inner()).clone();
+        for (limit, budget, spent) in [(Limit::Session, budget.per_session_usd, session), (Limit::Month, budget.per_month_usd, month)] {
+            if let Some(budget) = budget.filter(|b| spent >= *b) {
+                return Err(BudgetExceeded { limit, budget, spent });
+            }
+        }
+        Ok(self.warnings(session, month))
+    }
+
+    /// After a request: append what it used, with any warnings due.
+    pub fn record(&self, kind: &ProviderKind, model: &str, usage: Usage) -> Result<(Record, Vec<BudgetWarning>)> {
+        let record = Record {
+            at: self.clock.now(),
+            session: Some(self.session.read().unwrap_or_else(|e| e.into_inner()).clone()),
+            provider: kind.clone(),
+            model: model.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd: self.price(kind, model).map(|p| p.cost(usage)),
+        };
+        self.ledger.append(&record)?;
+        let (session, month) = self.spent();
+        Ok((record, self.warnings(session, month)))
+    }
+
+    /// Budgets at `WARN_AT` or over, not warned about yet.
+    fn warnings(&self, session: f64, month: f64) -> Vec<BudgetWarning> {
+        let budget = self.budget.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let id = self.session.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut warned = self.warned.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = Vec::new();
+        let limits = [
+            (Limit::Session, budget.per_session_usd, session, format!("session:{}", id)),
+            (Limit::Month, budget.per_month_usd, month, format!("month:{}", month_of(self.clock.now()))),
+        ];
+        for (limit, budget, spent, tag) in limits {
+            if let Some(budget) = budget.filter(|b| spent >= b * WARN_AT) {
+                if warned.insert(tag) {
+                    out.push(BudgetWarning { limit, budget, spent });
+                }
+            }
+        }
+        out
+    }
+}
+
+/* ---------------------------
+   Process-wide tracker
+---------------------------- */
+
+/// Start tracking with `cfg`'s budgets, or take them (and the persistence policy) over
+/// when already tracking. The session is kept.
+pub fn init(cfg: &AppConfig) {
+    let persist = persistence::policy().keeps_history();
+    let mut global = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    match global.as_ref() {
+        Some(tracker) => {
+            tracker.set_budget(&cfg.budget);
+            tracker.ledger.set_persist(persist);
+        }
+        None => {
+            if let Ok(dir) = ledger_dir() {
+                let ledger = Ledger::open(&dir, persist);
+                *global = Some(Arc::new(Tracker::new(ledger, &cfg.budget, Arc::new(SystemClock))));
+            }
+        }
+    }
+}
+
+/// Count later requests toward chat session `id`.
+pub fn set_session(id: &str) {
+    if let Some(tracker) = tracker() {
+        tracker.set_session(id);
+    }
+}
+
+/// The process's tracker, once `init` ran.
+pub fn tracker() -> Option<Arc<Tracker>> {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The warnings given since the last call.
+pub fn take_warnings() -> Vec<BudgetWarning> {
+    std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn queue(warnings: Vec<BudgetWarning>) {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).extend(warnings);
+}
+
+/* ---------------------------
+   Client
+---------------------------- */
+
+/// `client` metered when `cfg` is (`is_metered`), or as is.
+pub fn wrap(cfg: &ProviderConfig, client: Box<dyn ChatClient>) -> Box<dyn ChatClient> {
+    match is_metered(cfg) {
+        true => Box::new(MeteredClient { inner: client, kind: cfg.kind.clone(), model: cfg.model.clone() }),
+        false => client,
+    }
+}
+
+pub struct MeteredClient {
+    inner: Box<dyn ChatClient>,
+    kind: ProviderKind,
+    model: String,
+}
+
+#[async_trait]
+impl ChatClient for MeteredClient {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let Some(tracker) = tracker() else { return self.inner.chat(messages, cancel, on_chunk).await };
+        queue(tracker.check()?);
+        let response = self.inner.chat(messages, cancel, on_chunk).await?;
+        if let Some(usage) = response.usage {
+            match tracker.record(&self.kind, &self.model, usage) {
+                Ok((_, warnings)) => queue(warnings),
+                Err(e) if !WARNED_WRITE.swap(true, Ordering::Relaxed) => {
+                    eprintln!("Warning: usage not recorded: {:#}", e);
+                }
+                Err(_) => {}
+            }
+        }
+        Ok(response)
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        self.inner.list_models().await
+    }
+}
@@ -0,0 +1,102 @@
+//! When a full-screen view redraws (the setup wizard, the chat view): only after
+//! something changed, and on animation ticks.
+//!
+//! Notes:
+//! - A view marks what changes it (`changed`): a key press, a resize, a chunk of a reply,
+//!   a background task. Events that change nothing (key releases, focus, mouse moves)
+//!   wake the loop but are not drawn.
+//! - With animation off there is no deadline: the loop sleeps until input arrives, so an
+//!   idle view costs nothing.
+//! - With animation on, the loop sleeps until the next tick exactly (`deadline`) instead
+//!   of polling at a rate of its own. A late wake-up skips the ticks it missed.
+//! - Time is passed in, like `providers::limit::TokenBucket`, so the pacing can be
+//!   checked without a terminal or a clock.
+//!
+//! ```
+//! use aion_core::frames::Frames;
+//! use std::time::{Duration, Instant};
+//!
+//! // Five seconds without input: how many frames are drawn.
+//! fn idle(mut frames: Frames, start: Instant) -> u32 {
+//!     let end = start + Duration::from_secs(5);
+//!     let mut draws = 0;
+//!     loop {
+//!         if frames.take() {
+//!             draws += 1;
+//!         }
+//!         match frames.deadline() {
+//!             Some(at) if at <= end => assert!(frames.tick(at)),
+//!             _ => return draws,
+//!         }
+//!     }
+//! }
+//! let start = Instant::now();
+//! let tick = Duration::from_millis(90);
+//!
+//! // Animation off: the first frame only. On: one per 90 ms tick, where polling every
+//! // 60 ms drew 5000 / 60 = 83 frames.
+//! assert_eq!(idle(Frames::new(None, start), start), 1);
+//! assert_eq!(idle(Frames::new(Some(tick), start), start), 56);
+//!
+//! let mut frames = Frames::new(Some(tick), start);
+//! assert!(frames.take() && !frames.take());
+//! assert!(!frames.tick(start + Duration::from_millis(50)) && !frames.take());
+//! // Woken late: one frame, and the next tick is the next one still ahead.
+//! assert!(frames.tick(start + Duration::from_millis(200)) && frames.take());
+//! assert_eq!(frames.deadline(), Some(start + Duration::from_millis(270)));
+//! frames.set_tick(None, start);
+//! assert_eq!(frames.deadline(), None);
+//! frames.changed();
+//! assert!(frames.take());
+//! ```
+
+use std::time::{Duration, Instant};
+
+/// Whether the next frame is due, and when the next animation tick is.
+#[derive(Debug, Clone)]
+pub struct Frames {
+    tick: Option<Duration>,
+    next: Option<Instant>,
+    dirty: bool,
+}
+
+impl Frames {
+    /// Ticking every `tick` from `now` (`None`: no animation), with the first frame due.
+    pub fn new(tick: Option<Duration>, now: Instant) -> Self {
+        Self { tick, next: tick.map(|t| now + t), dirty: true }
+    }
+
+    /// Turn the animation on or off, from `now`. A change is drawn.
+    pub fn set_tick(&mut self, tick: Option<Duration>, now: Instant) {
+        if tick != self.tick {
+            *self = Self::new(tick, now);
+        }
+    }
+
+    /// Something on screen changed.
+    pub fn changed(&mut self) {
+        self.dirty = true;
+    }
+
+    /// When the next tick is; `None`: wait for input.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.next
+    }
+
+    /// Woken at `now`: whether a tick was due. One that was is drawn.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let (Some(tick), Some(next)) = (self.tick, self.next) else { return false };
+        if now < next {
+            return false;
+        }
+        let missed = (now - next).as_nanos() / tick.as_nanos().max(1);
+        self.next = Some(next + tick * (missed as u32 + 1));
+        self.dirty = true;
+        true
+    }
+
+    /// Whether to draw now; asking clears it.
+    pub fn take(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
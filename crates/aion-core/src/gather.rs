@@ -0,0 +1,336 @@
+//! Files gathered by pattern for one prompt (`aion chat --file 'src/**/*.rs'`).
+//!
+//! Notes:
+//! - Patterns use the `guard` syntax: `*` and `?` within one component, `**` for any
+//!   number of components. The part before the first wildcard is walked; a pattern
+//!   without one names a file, or a directory whose files are all taken. Directory
+//!   symlinks are not followed.
+//! - Each match is read through `guard`, so `caps.read_files` and its path lists apply.
+//!   A file that is refused, too large, or not UTF-8 goes to `skipped`; it does not fail
+//!   the gather. A pattern that matches nothing does.
+//! - Reading runs on up to `workers` threads. A file's share of the count and size
+//!   budgets is reserved atomically before it is read, so racing workers never take the
+//!   totals past them. Which files are left out once the budget runs short can differ
+//!   between runs; how many fit cannot exceed it.
+//! - `files` is sorted by path whatever order the reads finish in.
+//!
+//! ```
+//! use aion_core::config::AppConfig;
+//! use aion_core::gather::{self, Budget};
+//! use std::fs;
+//!
+//! let root = std::env::temp_dir().join(format!("aion-gather-{}", std::process::id()));
+//! for dir in 0..30 {
+//!     let dir = root.join(format!("d{:02}", dir));
+//!     fs::create_dir_all(&dir).unwrap();
+//!     for file in 0..100 {
+//!         fs::write(dir.join(format!("f{:03}.rs", file)), "fn main() {}\n").unwrap();
+//!     }
+//!     fs::write(dir.join("notes.txt"), "not matched\n").unwrap();
+//! }
+//! let pattern = format!("{}/**/*.rs", root.display());
+//! let caps = AppConfig::new_default().caps;
+//! let ample = Budget { max_files: 10_000, max_bytes: 1 << 20, max_file_bytes: 1024 };
+//!
+//! let all = gather::gather(&caps, &[pattern.clone()], ample, 8, &|_| {}).unwrap();
+//! assert_eq!(all.files.len(), 3000);
+//! assert_eq!(all.over_budget, 0);
+//! assert!(all.files.windows(2).all(|w| w[0].path < w[1].path));
+//! assert!(all.files.iter().all(|f| f.path.extension().unwrap() == "rs"));
+//!
+//! // 13 bytes a file: 100 fit the count, 50 the size; racing workers never take more.
+//! let by_count = Budget { max_files: 100, ..ample };
+//! let by_size = Budget { max_bytes: 50 * 13 + 12, ..ample };
+//! for (budget, fit) in [(by_count, 100), (by_size, 50)] {
+//!     let some = gather::gather(&caps, &[pattern.clone()], budget, 8, &|_| {}).unwrap();
+//!     assert_eq!(some.files.len(), fit);
+//!     assert!(some.bytes() <= budget.max_bytes);
+//!     assert_eq!(some.files.len() + some.over_budget, 3000);
+//!     assert!(some.files.windows(2).all(|w| w[0].path < w[1].path));
+//! }
+//!
+//! let missing = format!("{}/**/*.py", root.display());
+//! assert!(gather::gather(&caps, &[missing], ample, 8, &|_| {}).is_err());
+//! fs::remove_dir_all(&root).unwrap();
+//! ```
+
+use crate::config::{Capabilities, CapabilityDenied};
+use crate::guard::{self, Guard};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
+/// Most reader threads `default_workers` picks.
+pub const MAX_WORKERS: usize = 8;
+
+/// Limits on what one gather attaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub max_files: usize,
+    /// All files together.
+    pub max_bytes: u64,
+    /// Any one file; a larger one is skipped.
+    pub max_file_bytes: u64,
+}
+
+/// Counts so far, passed to the progress callback after each file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Matched files looked at.
+    pub scanned: usize,
+    pub attached: usize,
+    /// Size of the attached files.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GatheredFile {
+    /// As matched (relative when the pattern was), for the fence label.
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// A matched file that was not attached, and why.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Gathered {
+    /// Sorted by path.
+    pub files: Vec<GatheredFile>,
+    /// Sorted by path.
+    pub skipped: Vec<Skipped>,
+    /// Matched files left out because the budget was spent.
+    pub over_budget: usize,
+}
+
+impl Gathered {
+    pub fn bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.text.len() as u64).sum()
+    }
+}
+
+/// Reader threads for this machine: one per core, at most `MAX_WORKERS`.
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(4, |n| n.get()).min(MAX_WORKERS)
+}
+
+/// Every file matched by `patterns`, read on up to `workers` threads within `budget`.
+pub fn gather(
+    caps: &Capabilities,
+    patterns: &[String],
+    budget: Budget,
+    workers: usize,
+    on_progress: &(dyn Fn(Progress) + Sync),
+) -> Result<Gathered> {
+    if !caps.read_files {
+        return Err(CapabilityDenied { cap: "read_files" }.into());
+    }
+    let mut candidates = Vec::new();
+    for pattern in patterns {
+        let before = candidates.len();
+        expand(pattern, &mut candidates)?;
+        if candidates.len() == before {
+            bail!("no files match {}", pattern);
+        }
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    let shared = Shared {
+        budget,
+        next: AtomicUsize::new(0),
+        scanned: AtomicUsize::new(0),
+        files: AtomicUsize::new(0),
+        bytes: AtomicU64::new(0),
+    };
+    let guard = Guard::new(caps);
+    let mut outcomes: Vec<(usize, Outcome)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.clamp(1, candidates.len().max(1)))
+            .map(|_| scope.spawn(|| shared.work(&guard, &candidates, on_progress)))
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect()
+    });
+    outcomes.sort_by_key(|(i, _)| *i);
+
+    let mut gathered = Gathered::default();
+    for (i, outcome) in outcomes {
+        let path = candidates[i].clone();
+        match outcome {
+            Outcome::Read(text) => gathered.files.push(GatheredFile { path, text }),
+            Outcome::Skipped(reason) => gathered.skipped.push(Skipped { path, reason }),
+            Outcome::OverBudget => gathered.over_budget += 1,
+        }
+    }
+    Ok(gathered)
+}
+
+/* --- reading --- */
+
+enum Outcome {
+    Read(String),
+    Skipped(String),
+    OverBudget,
+}
+
+/// State the reader threads share: the next candidate to take and the budget spent.
+struct Shared {
+    budget: Budget,
+    next: AtomicUsize,
+    scanned: AtomicUsize,
+    files: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl Shared {
+    /// Take candidates in order until none are left; `(index, outcome)` for each taken.
+    fn work(
+        &self,
+        guard: &Guard,
+        candidates: &[PathBuf],
+        on_progress: &(dyn Fn(Progress) + Sync),
+    ) -> Vec<(usize, Outcome)> {
+        let mut done = Vec::new();
+        loop {
+            let i = self.next.fetch_add(1, Ordering::Relaxed);
+            let Some(path) = candidates.get(i) else { break };
+            done.push((i, self.read(guard, path)));
+            self.scanned.fetch_add(1, Ordering::Relaxed);
+            on_progress(Progress {
+                scanned: self.scanned.load(Ordering::Relaxed),
+                attached: self.files.load(Ordering::Relaxed),
+                bytes: self.bytes.load(Ordering::Relaxed),
+            });
+        }
+        done
+    }
+
+    fn read(&self, guard: &Guard, path: &Path) -> Outcome {
+        // Every file counts against `max_files`, so once it is spent there is nothing to check.
+        if self.files.load(Ordering::Acquire) >= self.budget.max_files {
+            return Outcome::OverBudget;
+        }
+        let resolved = match guard.check_read(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Outcome::Skipped(format!("{:#}", e)),
+        };
+        let size = match fs::metadata(&resolved) {
+            Ok(meta) => meta.len(),
+            Err(e) => return Outcome::Skipped(e.to_string()),
+        };
+        if size > self.budget.max_file_bytes {
+            return Outcome::Skipped(format!("larger than {} bytes", self.budget.max_file_bytes));
+        }
+        if !self.reserve(size) {
+            return Outcome::OverBudget;
+        }
+        let text = match fs::read(&resolved).map(String::from_utf8) {
+            Ok(Ok(text)) => text,
+            Ok(Err(_)) => {
+                self.release(1, size);
+                return Outcome::Skipped("not a text file".to_string());
+            }
+            Err(e) => {
+                self.release(1, size);
+                return Outcome::Skipped(e.to_string());
+            }
+        };
+        let read = text.len() as u64;
+        if read > size {
+            // Grew since the metadata was read; what was reserved no longer covers it.
+            self.release(1, size);
+            return Outcome::Skipped("changed while being read".to_string());
+        }
+        self.release(0, size - read);
+        Outcome::Read(text)
+    }
+
+    /// Take one file and `size` bytes from the budget, or nothing if either would overrun it.
+    fn reserve(&self, size: u64) -> bool {
+        let max_files = self.budget.max_files;
+        if self.files.fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < max_files).then_some(n + 1)).is_err() {
+            return false;
+        }
+        let max_bytes = self.budget.max_bytes;
+        let fits = |b: u64| b.checked_add(size).filter(|&total| total <= max_bytes);
+        if self.bytes.fetch_update(Ordering::AcqRel, Ordering::Acquire, fits).is_err() {
+            self.files.fetch_sub(1, Ordering::AcqRel);
+            return false;
+        }
+        true
+    }
+
+    fn release(&self, files: usize, bytes: u64) {
+        self.files.fetch_sub(files, Ordering::AcqRel);
+        self.bytes.fetch_sub(bytes, Ordering::AcqRel);
+    }
+}
+
+/* --- matching --- */
+
+/// Add the files `pattern` matches to `out`.
+fn expand(pattern: &str, out: &mut Vec<PathBuf>) -> Result<()> {
+    let pattern = Path::new(pattern.trim());
+    let wildcards = parts(pattern);
+    let literal = wildcards.iter().take_while(|p| !p.contains(['*', '?'])).count();
+    let base: PathBuf = pattern.components().filter(|c| !matches!(c, Component::CurDir)).take(literal).collect();
+    if literal == wildcards.len() {
+        let meta = fs::metadata(&base).with_context(|| format!("failed to read {}", base.display()))?;
+        if !meta.is_dir() {
+            out.push(base);
+            return Ok(());
+        }
+    }
+    let mut walked = Vec::new();
+    match walk(&base, &mut walked) {
+        // Nothing to match; the caller reports the pattern.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        result => result.with_context(|| format!("failed to read {}", base.display()))?,
+    }
+    let all = ["**".to_string()];
+    let wanted = if literal == wildcards.len() { &all[..] } else { &wildcards[literal..] };
+    let skip = parts(&base).len();
+    out.extend(walked.into_iter().filter(|path| matches(wanted, &parts(path)[skip..])));
+    Ok(())
+}
+
+/// Every file below `dir`, without following directory symlinks.
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let read = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    for entry in fs::read_dir(read)? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            // A subdirectory that cannot be listed is left out, not a failed gather.
+            let _ = walk(&path, out);
+        } else if kind.is_file() || (kind.is_symlink() && path.is_file()) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn parts(path: &Path) -> Vec<String> {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Whether `path` is matched by `pattern` as a whole (unlike `guard`, not what lies below).
+fn matches(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((p, rest)) if p == "**" => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+        Some((p, rest)) => match path.split_first() {
+            Some((c, tail)) => guard::matches_component(p, c) && matches(rest, tail),
+            None => false,
+        },
+    }
+}
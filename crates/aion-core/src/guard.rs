@@ -0,0 +1,506 @@
+//! Access checks: `caps.read_files`/`write_files` and their path lists, and
+//! `caps.network` and its host lists.
+//!
+//! Notes:
+//! - The switch comes first: with `caps.write_files = false` nothing is written, whatever
+//!   `write_allow` says.
+//! - An empty allow list allows every path; otherwise the path must match one of its
+//!   patterns. A matching deny pattern refuses the path even when an allow pattern matches.
+//! - Paths are made absolute and resolved (symlinks, `..`) before matching, so
+//!   `../../.ssh/id_rsa` and a link pointing into `~/.ssh` are both caught. Components
+//!   that do not exist yet (a file about to be written) are taken as written.
+//! - Patterns: `*` and `?` within one component, `**` for any number of components, `~`
+//!   for the home directory. A pattern starting with `/` or `~` is anchored; any other
+//!   matches at any depth (`.env` is `**/.env`). A pattern that matches a directory
+//!   covers everything in it.
+//! - Callers use the returned resolved path, so what was checked is what gets opened.
+//! - A working directory (`crate::workdir`) is held to the read lists but not to
+//!   `caps.read_files`: whether commands run at all is `caps.run_commands`'s call.
+//! - Hosts (`network_allow`/`network_deny`) work the same way, deny first. A pattern is
+//!   a host (`api.openai.com`, `127.0.0.1`, `::1`), `*.example.com` for any subdomain
+//!   (not `example.com` itself), or a CIDR range (`10.0.0.0/8`, `fd00::/8`). A host may
+//!   take a port (`localhost:11434`, `[::1]:8080`); without one every port matches.
+//! - What is matched is the host written in the URL, never what it resolves to: a name
+//!   is not caught by a CIDR range, and a name re-pointed by DNS stays the same name.
+//! - `Guard::for_app` lets the configured providers' hosts (and ports) through an allow
+//!   list, so chatting keeps working; a deny pattern still refuses them. Provider
+//!   requests use `check_host`, since `caps.network` is about AION's other traffic.
+//!
+//! ```
+//! use aion_core::config::{AppConfig, Capabilities};
+//! use aion_core::guard::Guard;
+//!
+//! let mut app = AppConfig::new_default();
+//! app.caps.network_allow = vec!["*.example.com".into(), "localhost:8080".into(), "10.0.0.0/8".into(), "[::1]".into()];
+//! app.caps.network_deny = vec!["admin.example.com".into(), "10.0.0.1".into()];
+//! let guard = Guard::new(&app.caps);
+//! let allowed = |url: &str| guard.check_network(url).is_ok();
+//!
+//! // Wildcards cover subdomains only; deny wins over allow.
+//! assert!(allowed("https://api.example.com/v1") && allowed("https://a.b.EXAMPLE.com."));
+//! assert!(!allowed("https://example.com") && !allowed("https://evil-example.com"));
+//! let denied = guard.check_network("https://admin.example.com/x").unwrap_err();
+//! assert_eq!(denied.to_string(), "admin.example.com is blocked by caps.network_deny \"admin.example.com\"");
+//!
+//! // Ports, CIDR ranges, and IPv6 literals.
+//! assert!(allowed("http://localhost:8080") && !allowed("http://localhost:8081") && !allowed("http://localhost"));
+//! assert!(allowed("http://10.2.3.4:9000") && !allowed("http://10.0.0.1") && !allowed("http://11.0.0.1"));
+//! assert!(allowed("http://[::1]:11434/api") && !allowed("http://[::2]/"));
+//! let missed = guard.check_network("http://[fe80::1]:8080/").unwrap_err();
+//! assert_eq!(missed.to_string(), "[fe80::1]:8080 is not matched by caps.network_allow");
+//!
+//! // The configured provider is let through the allow list, but not the deny list.
+//! let for_app = Guard::for_app(&app);
+//! assert!(for_app.check_network(&app.provider.effective_base_url()).is_ok());
+//! app.caps.network_deny.push("localhost".into());
+//! assert!(Guard::for_app(&app).check_host(&app.provider.effective_base_url()).is_err());
+//!
+//! // The switch comes first.
+//! let off = Capabilities { network: false, ..app.caps.clone() };
+//! assert!(Guard::new(&off).check_network("http://localhost:8080").is_err());
+//! ```
+//!
+//! Paths, in a scratch tree with a link from the project to a directory outside it:
+//!
+//! ```
+//! # #[cfg(unix)]
+//! # {
+//! use aion_core::config::{AppConfig, CapabilityDenied};
+//! use aion_core::guard::{Guard, PathDenied};
+//! use std::fs;
+//!
+//! let root = std::env::temp_dir().join(format!("aion-guard-{}", std::process::id()));
+//! for dir in ["project/src", "project/a/b/out", "outside", "home/.ssh"] {
+//!     fs::create_dir_all(root.join(dir)).unwrap();
+//! }
+//! let root = root.canonicalize().unwrap();
+//! for file in ["project/src/main.rs", "project/.env", "outside/secret.txt", "home/.ssh/id_rsa", "home/notes.txt"] {
+//!     fs::write(root.join(file), "x").unwrap();
+//! }
+//! let _ = std::os::unix::fs::symlink(root.join("outside"), root.join("project/link"));
+//! std::env::set_var("HOME", root.join("home"));
+//!
+//! let mut app = AppConfig::new_default();
+//! app.caps.read_files = true;
+//! app.caps.read_allow = vec![root.join("project").display().to_string()];
+//! app.caps.read_deny = vec![".env".into()];
+//! let guard = Guard::new(&app.caps);
+//! let denied = |result: anyhow::Result<_>| -> PathDenied { result.unwrap_err().downcast::<PathDenied>().unwrap() };
+//!
+//! let main = root.join("project/src/main.rs");
+//! assert_eq!(guard.check_read(&main).unwrap(), main);
+//! assert_eq!(guard.check_read(&root.join("project/src/../src/./main.rs")).unwrap(), main);
+//!
+//! // `..` out of the allowed root, and a link out of it, are checked where they lead.
+//! let escaped = denied(guard.check_read(&root.join("project/src/../../outside/secret.txt")));
+//! assert_eq!((escaped.path, escaped.list, escaped.rule), (root.join("outside/secret.txt"), "read_allow", None));
+//! let linked = denied(guard.check_read(&root.join("project/link/secret.txt")));
+//! assert_eq!((linked.path, linked.list), (root.join("outside/secret.txt"), "read_allow"));
+//!
+//! // Deny wins over an allow pattern that also matches.
+//! let env = denied(guard.check_read(&root.join("project/.env")));
+//! assert_eq!(env.to_string(), format!("{} is blocked by caps.read_deny \".env\"", root.join("project/.env").display()));
+//!
+//! // `~` is the home directory; `**` is any number of components.
+//! app.caps.read_allow = Vec::new();
+//! app.caps.read_deny = vec!["~/.ssh".into()];
+//! app.caps.write_files = true;
+//! app.caps.write_allow = vec![format!("{}/project/**/out", root.display())];
+//! let guard = Guard::new(&app.caps);
+//! assert_eq!(denied(guard.check_read(&root.join("home/.ssh/id_rsa"))).rule.as_deref(), Some("~/.ssh"));
+//! assert!(guard.check_read(&root.join("home/notes.txt")).is_ok());
+//! assert!(guard.check_write(&root.join("project/a/b/out/new.txt")).is_ok(), "a file that does not exist yet");
+//! assert!(guard.check_write(&root.join("project/out/new.txt")).is_ok());
+//! assert_eq!(denied(guard.check_write(&root.join("project/a/new.txt"))).list, "write_allow");
+//!
+//! // The switches come first, except for the directory commands run in.
+//! app.caps.read_files = false;
+//! app.caps.write_files = false;
+//! let guard = Guard::new(&app.caps);
+//! assert!(guard.check_write(&root.join("project/out/new.txt")).unwrap_err().is::<CapabilityDenied>());
+//! assert!(guard.check_read(&main).unwrap_err().is::<CapabilityDenied>());
+//! assert_eq!(guard.check_dir(&root.join("project")).unwrap(), root.join("project"));
+//! assert_eq!(denied(guard.check_dir(&root.join("home/.ssh"))).list, "read_deny");
+//! fs::remove_dir_all(&root).unwrap();
+//! # }
+//! ```
+
+use crate::config::{AppConfig, Capabilities, CapabilityDenied, ConfigError};
+use anyhow::{anyhow, Context, Result};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Component, Path, PathBuf};
+use url::{Host, Url};
+
+/// Symlinks followed while resolving one path, as in most kernels.
+const MAX_LINK_HOPS: u32 = 40;
+
+/// A path refused by one of the `caps` path lists.
+#[derive(Debug, Clone)]
+pub struct PathDenied {
+    pub path: PathBuf,
+    /// The list that refused it, e.g. `read_deny`.
+    pub list: &'static str,
+    /// The deny pattern that matched; `None` when no allow pattern did.
+    pub rule: Option<String>,
+}
+
+impl fmt::Display for PathDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.rule {
+            Some(rule) => write!(f, "{} is blocked by caps.{} \"{}\"", self.path.display(), self.list, rule),
+            None => write!(f, "{} is not matched by caps.{}", self.path.display(), self.list),
+        }
+    }
+}
+
+impl std::error::Error for PathDenied {}
+
+/// A URL refused by `caps.network_allow` or `network_deny`.
+#[derive(Debug, Clone)]
+pub struct HostDenied {
+    /// The host the URL names, with the port when the URL gives one.
+    pub host: String,
+    pub list: &'static str,
+    /// The deny pattern that matched; `None` when no allow pattern did.
+    pub rule: Option<String>,
+}
+
+impl fmt::Display for HostDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.rule {
+            Some(rule) => write!(f, "{} is blocked by caps.{} \"{}\"", self.host, self.list, rule),
+            None => write!(f, "{} is not matched by caps.{}", self.host, self.list),
+        }
+    }
+}
+
+impl std::error::Error for HostDenied {}
+
+/// Checks paths and URLs against one config's `caps`.
+pub struct Guard<'a> {
+    caps: &'a Capabilities,
+    /// Hosts and ports `network_allow` lets through regardless (the providers').
+    trusted: Vec<(Host, Option<u16>)>,
+}
+
+impl<'a> Guard<'a> {
+    pub fn new(caps: &'a Capabilities) -> Self {
+        Self { caps, trusted: Vec::new() }
+    }
+
+    /// A guard for `app`'s `caps`, trusting the hosts of its provider and fallbacks.
+    pub fn for_app(app: &'a AppConfig) -> Self {
+        let trusted = app
+            .provider
+            .chain()
+            .filter_map(|p| Url::parse(&p.effective_base_url()).ok())
+            .filter_map(|url| Some((normalize(url.host()?.to_owned()), url.port_or_known_default())))
+            .collect();
+        Self { caps: &app.caps, trusted }
+    }
+
+    /// `url` parsed, if `caps.network` and the host lists allow it.
+    pub fn check_network(&self, url: &str) -> Result<Url> {
+        if !self.caps.network {
+            return Err(CapabilityDenied { cap: "network" }.into());
+        }
+        self.check_host(url)
+    }
+
+    /// `url` parsed, if the host lists allow it, whatever `caps.network` says.
+    pub fn check_host(&self, url: &str) -> Result<Url> {
+        let parsed = Url::parse(url).with_context(|| format!("{} is not a valid URL", url))?;
+        let host = normalize(parsed.host().ok_or_else(|| anyhow!("{} has no host", url))?.to_owned());
+        let port = parsed.port_or_known_default();
+        let shown = match parsed.port() {
+            Some(explicit) => format!("{}:{}", host, explicit),
+            None => host.to_string(),
+        };
+        let c = self.caps;
+        for rule in &c.network_deny {
+            if HostPattern::parse(rule).map_err(|e| invalid("network_deny", rule, e))?.matches(&host, port) {
+                return Err(HostDenied { host: shown, list: "network_deny", rule: Some(rule.clone()) }.into());
+            }
+        }
+        if c.network_allow.is_empty() || self.trusted.iter().any(|t| t.0 == host && t.1 == port) {
+            return Ok(parsed);
+        }
+        for rule in &c.network_allow {
+            if HostPattern::parse(rule).map_err(|e| invalid("network_allow", rule, e))?.matches(&host, port) {
+                return Ok(parsed);
+            }
+        }
+        Err(HostDenied { host: shown, list: "network_allow", rule: None }.into())
+    }
+
+    /// `path` resolved, if it may be read.
+    pub fn check_read(&self, path: &Path) -> Result<PathBuf> {
+        let c = self.caps;
+        check(path, c.read_files, "read_files", (&c.read_allow, "read_allow"), (&c.read_deny, "read_deny"))
+    }
+
+    /// `path` resolved, if the read lists let commands run in it.
+    pub fn check_dir(&self, path: &Path) -> Result<PathBuf> {
+        let c = self.caps;
+        check(path, true, "read_files", (&c.read_allow, "read_allow"), (&c.read_deny, "read_deny"))
+    }
+
+    /// `path` resolved, if it may be written.
+    pub fn check_write(&self, path: &Path) -> Result<PathBuf> {
+        let c = self.caps;
+        check(path, c.write_files, "write_files", (&c.write_allow, "write_allow"), (&c.write_deny, "write_deny"))
+    }
+}
+
+fn check(
+    path: &Path,
+    on: bool,
+    cap: &'static str,
+    (allow, allow_name): (&[String], &'static str),
+    (deny, deny_name): (&[String], &'static str),
+) -> Result<PathBuf> {
+    if !on {
+        return Err(CapabilityDenied { cap }.into());
+    }
+    let resolved = resolve(path, 0).with_context(|| format!("failed to resolve {}", path.display()))?;
+    let target = parts(&resolved);
+    if let Some(rule) = deny.iter().find(|p| Pattern::new(p).matches(&target)) {
+        return Err(PathDenied { path: resolved, list: deny_name, rule: Some(rule.clone()) }.into());
+    }
+    if !allow.is_empty() && !allow.iter().any(|p| Pattern::new(p).matches(&target)) {
+        return Err(PathDenied { path: resolved, list: allow_name, rule: None }.into());
+    }
+    Ok(resolved)
+}
+
+/* --- resolving paths --- */
+
+/// `path` made absolute with every existing symlink and `..` resolved. Missing
+/// components are kept as they are; a dangling final link is followed to its target.
+fn resolve(path: &Path, hops: u32) -> io::Result<PathBuf> {
+    let abs = if path.is_absolute() { path.to_path_buf() } else { std::env::current_dir()?.join(path) };
+    let mut real = PathBuf::new();
+    for component in abs.components() {
+        match component {
+            Component::CurDir => {}
+            // After the resolution below, `real` has no links left, so this is the real parent.
+            Component::ParentDir => {
+                real.pop();
+            }
+            other => {
+                real.push(other);
+                match real.canonicalize() {
+                    Ok(canonical) => real = canonical,
+                    Err(_) if fs::symlink_metadata(&real).is_ok_and(|m| m.file_type().is_symlink()) => {
+                        if hops >= MAX_LINK_HOPS {
+                            return Err(io::Error::other("too many levels of symbolic links"));
+                        }
+                        let target = fs::read_link(&real)?;
+                        real.pop();
+                        real = resolve(&real.join(target), hops + 1)?;
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+    Ok(real)
+}
+
+/// Path components as strings; the root (and a Windows prefix) is the first one.
+fn parts(path: &Path) -> Vec<String> {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
+
+/* --- patterns --- */
+
+struct Pattern {
+    parts: Vec<String>,
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> Self {
+        let pattern = pattern.trim();
+        let expanded = match pattern.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+                dirs::home_dir().map(|home| home.join(rest.trim_start_matches(['/', '\\'])))
+            }
+            _ => None,
+        };
+        let path = expanded.unwrap_or_else(|| PathBuf::from(pattern));
+        if !path.has_root() {
+            let mut parts = vec!["**".to_string()];
+            parts.extend(self::parts(&path));
+            return Self { parts };
+        }
+        Self { parts: anchored(&path) }
+    }
+
+    fn matches(&self, path: &[String]) -> bool {
+        matches_parts(&self.parts, path)
+    }
+}
+
+/// Components of an anchored pattern, with the part before the first wildcard resolved
+/// like the paths it is matched against (e.g. `/tmp` to `/private/tmp` on macOS).
+fn anchored(pattern: &Path) -> Vec<String> {
+    let all = parts(pattern);
+    let literal = all.iter().take_while(|p| !p.contains(['*', '?'])).count();
+    let prefix: PathBuf = all[..literal].iter().collect();
+    match resolve(&prefix, 0) {
+        Ok(real) => parts(&real).into_iter().chain(all[literal..].iter().cloned()).collect(),
+        Err(_) => all,
+    }
+}
+
+/// Whether `path` is matched by `pattern`, or lies below something it matches.
+fn matches_parts(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((p, rest)) if p == "**" => (0..=path.len()).any(|i| matches_parts(rest, &path[i..])),
+        Some((p, rest)) => match path.split_first() {
+            Some((c, tail)) => matches_component(p, c) && matches_parts(rest, tail),
+            None => false,
+        },
+    }
+}
+
+/// `*` and `?` wildcards within one component.
+pub(crate) fn matches_component(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // Where the last `*` was, and how much of `name` it has taken so far.
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        match p.get(pi) {
+            Some('*') => {
+                star = Some((pi, ni));
+                pi += 1;
+            }
+            Some(&c) if c == '?' || c == n[ni] => {
+                pi += 1;
+                ni += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    pi = sp + 1;
+                    ni = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/* --- host patterns --- */
+
+/// One `network_allow`/`network_deny` entry; see the module notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HostPattern {
+    rule: HostRule,
+    port: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostRule {
+    Exact(Host),
+    /// `*.example.com`, as `example.com`.
+    Subdomains(String),
+    /// Address and prefix length.
+    Cidr(IpAddr, u8),
+}
+
+impl HostPattern {
+    /// The pattern, or what is wrong with it.
+    pub(crate) fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err("the pattern is empty".to_string());
+        }
+        if let Some((addr, bits)) = pattern.split_once('/') {
+            let addr = addr.trim_start_matches('[').trim_end_matches(']');
+            let addr: IpAddr = addr.parse().map_err(|_| format!("{} is not an IP address", addr))?;
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            let bits = bits.parse().ok().filter(|b| *b <= max);
+            let bits = bits.ok_or_else(|| format!("a prefix length is 0 to {}", max))?;
+            return Ok(Self { rule: HostRule::Cidr(addr, bits), port: None });
+        }
+        let (host, port) = split_port(pattern)?;
+        let rule = match host.strip_prefix("*.") {
+            Some(domain) if !domain.contains('*') => match Host::parse(domain) {
+                Ok(Host::Domain(domain)) => HostRule::Subdomains(domain.trim_end_matches('.').to_string()),
+                Ok(_) => return Err("`*.` goes before a domain name, not an address".to_string()),
+                Err(e) => return Err(e.to_string()),
+            },
+            _ if host.contains('*') => return Err("`*` is only allowed as a leading `*.`".to_string()),
+            _ => HostRule::Exact(normalize(Host::parse(&host).map_err(|e| e.to_string())?)),
+        };
+        Ok(Self { rule, port })
+    }
+
+    fn matches(&self, host: &Host, port: Option<u16>) -> bool {
+        if self.port.is_some() && self.port != port {
+            return false;
+        }
+        match (&self.rule, host) {
+            (HostRule::Exact(exact), host) => exact == host,
+            (HostRule::Subdomains(domain), Host::Domain(name)) => {
+                name.strip_suffix(domain.as_str()).is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'))
+            }
+            (HostRule::Cidr(IpAddr::V4(net), bits), Host::Ipv4(ip)) => {
+                prefix(u128::from(u32::from(*net)), u128::from(u32::from(*ip)), *bits, 32)
+            }
+            (HostRule::Cidr(IpAddr::V6(net), bits), Host::Ipv6(ip)) => {
+                prefix(u128::from(*net), u128::from(*ip), *bits, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `host` and its port: `name:443`, `[::1]:8080`; a bare IPv6 address has none.
+fn split_port(pattern: &str) -> Result<(String, Option<u16>), String> {
+    let (host, port) = match pattern.strip_prefix('[') {
+        Some(rest) => {
+            let (addr, after) = rest.split_once(']').ok_or("an IPv6 address is missing its `]`")?;
+            (format!("[{}]", addr), (!after.is_empty()).then_some(after.strip_prefix(':').unwrap_or(after)))
+        }
+        None if pattern.matches(':').count() > 1 => (format!("[{}]", pattern), None),
+        None => match pattern.split_once(':') {
+            Some((host, port)) => (host.to_string(), Some(port)),
+            None => (pattern.to_string(), None),
+        },
+    };
+    let port = match port {
+        Some(port) => Some(port.parse().map_err(|_| format!("{:?} is not a port", port))?),
+        None => None,
+    };
+    Ok((host, port))
+}
+
+/// Domains compare without a trailing dot.
+fn normalize(host: Host) -> Host {
+    match host {
+        Host::Domain(name) => Host::Domain(name.trim_end_matches('.').to_string()),
+        other => other,
+    }
+}
+
+/// Whether the first `bits` of `a` and `b`, both `width` bits wide, are the same.
+fn prefix(a: u128, b: u128, bits: u8, width: u32) -> bool {
+    let shift = width - u32::from(bits);
+    shift >= width || (a >> shift) == (b >> shift)
+}
+
+fn invalid(list: &'static str, pattern: &str, reason: String) -> ConfigError {
+    ConfigError::InvalidHostPattern { list, pattern: pattern.to_string(), reason }
+}
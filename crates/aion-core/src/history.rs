@@ -0,0 +1,254 @@
+//! What was typed at the chat prompt, for ↑/↓, Ctrl+R, `/history`, and `!<n>`
+//! (`input_history.json` in the state dir, `crate::paths`). Shared by the REPL and the
+//! full-screen chat.
+//!
+//! Notes:
+//! - An entry is what was submitted, multi-line entries whole. Blank entries, entries
+//!   starting with a space (like bash's `ignorespace`), and an entry equal to the one
+//!   before it are not kept.
+//! - At most `history.max_entries`, oldest dropped first; the file is cut to the same
+//!   number when saved. 0 keeps nothing.
+//! - `save` reads the file again and adds what this process pushed since, so two chats
+//!   open at once both keep their entries. Entries are redacted on disk. Nothing is
+//!   written in read-only mode or for an ephemeral chat; what is in memory still works.
+//! - Numbers (`/history`, `!<n>`) count from 1, oldest first, as listed.
+//! - `search` is Ctrl+R: the newest entry containing the query (case-sensitive) before
+//!   the current match, skipping entries that read the same as a newer one (found
+//!   already). `Walk` is ↑/↓, and brings back what was being typed after the newest.
+//! - A missing or unreadable file reads as empty; history never fails the caller.
+//!
+//! ```
+//! use aion_core::history::{Found, InputHistory, Walk};
+//!
+//! let mut history = InputHistory::new(4);
+//! for entry in ["ls", "ls", " secret", "", "git status", "cargo test", "git stash", "git status"] {
+//!     history.push(entry);
+//! }
+//! // Repeats in a row, a leading space, and blank entries are dropped; the cap keeps the newest.
+//! assert_eq!(history.entries(), ["git status", "cargo test", "git stash", "git status"]);
+//! assert_eq!(history.recent(2).collect::<Vec<_>>(), [(3, "git stash"), (4, "git status")]);
+//!
+//! // `!<n>` by number; other input is not a recall.
+//! assert_eq!(history.recall("!2"), Ok(Some("cargo test")));
+//! assert_eq!(history.recall("!9").unwrap_err().to_string(), "!9: no entry 9 in the history (1 to 4)");
+//! assert_eq!(history.recall("!important"), Ok(None));
+//!
+//! // Ctrl+R: newest first, with where the match is; again skips what reads the same.
+//! assert_eq!(history.search("st", None), Some(Found { index: 3, at: 4 }));
+//! assert_eq!(history.search("st", Some(3)), Some(Found { index: 2, at: 4 }));
+//! assert_eq!(history.search("st", Some(2)), Some(Found { index: 1, at: 8 }));
+//! assert_eq!(history.search("st", Some(1)), None);
+//! assert_eq!(history.search("", None), None);
+//!
+//! // ↑/↓ walk the entries and come back to the draft.
+//! let mut walk = Walk::default();
+//! assert_eq!(walk.older(&history, "half typed").as_deref(), Some("git status"));
+//! assert_eq!(walk.older(&history, "").as_deref(), Some("git stash"));
+//! assert_eq!(walk.newer(&history).as_deref(), Some("git status"));
+//! assert_eq!(walk.newer(&history).as_deref(), Some("half typed"));
+//! assert_eq!(walk.newer(&history), None);
+//!
+//! // Saved and read back under a smaller cap.
+//! let read = InputHistory::from_json(&history.to_json().unwrap(), 2);
+//! assert_eq!(read.entries(), ["git stash", "git status"]);
+//! assert!(!InputHistory::new(0).push("kept?"));
+//! ```
+
+use crate::audit;
+use crate::paths;
+use crate::persistence;
+use crate::read_only;
+use crate::redact;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+const HISTORY_FILE_NAME: &str = "input_history.json";
+
+/// `!<n>` names no entry.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("!{number}: no entry {number} in the history{}", range(*.len))]
+pub struct NoSuchEntry {
+    pub number: usize,
+    pub len: usize,
+}
+
+fn range(len: usize) -> String {
+    match len {
+        0 => " (it is empty)".to_string(),
+        len => format!(" (1 to {})", len),
+    }
+}
+
+/// Where `search` found the query: the entry's index (from 0) and the byte offset in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Found {
+    pub index: usize,
+    pub at: usize,
+}
+
+/// Submitted entries, oldest first; see the module notes.
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    max: usize,
+    /// Pushed since the last `save`.
+    added: Vec<String>,
+}
+
+pub fn history_file_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(HISTORY_FILE_NAME))
+}
+
+impl InputHistory {
+    /// An empty history keeping at most `max` entries.
+    pub fn new(max: usize) -> Self {
+        Self { entries: Vec::new(), max, added: Vec::new() }
+    }
+
+    /// The saved entries, up to `max`.
+    pub fn load(max: usize) -> Self {
+        history_file_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| Self::from_json(&text, max))
+            .unwrap_or_else(|| Self::new(max))
+    }
+
+    /// Entries from a saved file; what `push` would not keep is dropped.
+    pub fn from_json(text: &str, max: usize) -> Self {
+        let mut history = Self::new(max);
+        for entry in serde_json::from_str::<Vec<String>>(text).unwrap_or_default() {
+            history.push(&entry);
+        }
+        history.added.clear();
+        history
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.entries).context("failed to serialize the input history")? + "\n")
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Add `entry`, unless the notes say it is not kept. `true` when it was.
+    pub fn push(&mut self, entry: &str) -> bool {
+        if self.max == 0
+            || entry.trim().is_empty()
+            || entry.starts_with(' ')
+            || self.entries.last().is_some_and(|last| last == entry)
+        {
+            return false;
+        }
+        self.entries.push(entry.to_string());
+        self.added.push(entry.to_string());
+        let over = self.entries.len().saturating_sub(self.max);
+        self.entries.drain(..over);
+        true
+    }
+
+    /// The last `n` entries with their numbers, oldest first.
+    pub fn recent(&self, n: usize) -> impl Iterator<Item = (usize, &str)> {
+        let skip = self.entries.len().saturating_sub(n);
+        self.entries.iter().enumerate().skip(skip).map(|(i, e)| (i + 1, e.as_str()))
+    }
+
+    /// The entry `input` recalls when it is `!<n>`; `Ok(None)` when it is something else.
+    pub fn recall(&self, input: &str) -> Result<Option<&str>, NoSuchEntry> {
+        let digits = input.trim().strip_prefix('!').unwrap_or_default();
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let number = digits.parse().unwrap_or(usize::MAX);
+        match number.checked_sub(1).and_then(|i| self.entries.get(i)) {
+            Some(entry) => Ok(Some(entry)),
+            None => Err(NoSuchEntry { number, len: self.entries.len() }),
+        }
+    }
+
+    /// The newest entry before index `before` (all of them when `None`) containing `query`.
+    /// An entry equal to a newer one is skipped: that one matched first.
+    pub fn search(&self, query: &str, before: Option<usize>) -> Option<Found> {
+        if query.is_empty() {
+            return None;
+        }
+        let end = before.unwrap_or(self.entries.len()).min(self.entries.len());
+        (0..end).rev().find_map(|index| {
+            let entry = &self.entries[index];
+            if self.entries[index + 1..].contains(entry) {
+                return None;
+            }
+            entry.find(query).map(|at| Found { index, at })
+        })
+    }
+
+    /// What `save` would add, leaving nothing to save here; for saving on another thread.
+    pub fn take_unsaved(&mut self) -> Self {
+        Self { entries: Vec::new(), max: self.max, added: std::mem::take(&mut self.added) }
+    }
+
+    /// Add what was pushed since the last save to the file, and cut it to `max`.
+    pub fn save(&mut self) -> Result<()> {
+        if self.added.is_empty() || read_only::active().is_some() || !persistence::policy().keeps_history() {
+            return Ok(());
+        }
+        let path = history_file_path()?;
+        let mut saved = fs::read_to_string(&path).map(|text| Self::from_json(&text, self.max)).unwrap_or_default();
+        saved.max = self.max;
+        for entry in self.added.drain(..) {
+            saved.push(&redact::redact(&entry));
+        }
+        paths::ensure(&paths::state_dir()?)?;
+        fs::write(&path, saved.to_json()?).with_context(|| format!("failed to write {}", path.display()))?;
+        audit::file_write(&path);
+        Ok(())
+    }
+}
+
+/// Where ↑/↓ are in the history while an entry is being typed.
+#[derive(Debug, Clone, Default)]
+pub struct Walk {
+    /// The entry shown; `None` while on the draft.
+    at: Option<usize>,
+    draft: String,
+}
+
+impl Walk {
+    /// The entry before the one shown (the newest, from the draft, which is `current`).
+    /// `None` when there is no older one.
+    pub fn older(&mut self, history: &InputHistory, current: &str) -> Option<String> {
+        let next = match self.at {
+            None => {
+                let newest = history.entries.len().checked_sub(1)?;
+                self.draft = current.to_string();
+                newest
+            }
+            Some(at) => at.checked_sub(1)?,
+        };
+        self.at = Some(next);
+        history.entries.get(next).cloned()
+    }
+
+    /// The entry after the one shown, or the draft after the newest. `None` on the draft.
+    pub fn newer(&mut self, history: &InputHistory) -> Option<String> {
+        let at = self.at?;
+        match history.entries.get(at + 1) {
+            Some(entry) => {
+                self.at = Some(at + 1);
+                Some(entry.clone())
+            }
+            None => {
+                self.at = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+        }
+    }
+
+    /// Back on the draft, e.g. after submitting.
+    pub fn reset(&mut self) {
+        self.at = None;
+        self.draft.clear();
+    }
+}
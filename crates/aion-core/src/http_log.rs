@@ -0,0 +1,252 @@
+//! Provider HTTP traffic, for debugging requests a provider rejects (`http.log` in the
+//! state dir, `crate::paths`, and `tracing` events).
+//!
+//! Notes:
+//! - The file is on with `--debug-http` (`force`) or `logging.http_debug`; the latter is
+//!   ignored in an ephemeral process (see `crate::persistence`). Process-wide like
+//!   `audit`: `init` once the config is loaded; `providers::stream` calls `request`,
+//!   `response`, and `StreamTrace` without passing anything around.
+//! - Every line is also a DEBUG event with target `aion::http`, whenever a subscriber
+//!   wants it, file or not. The `aion` binary installs no subscriber (its output is
+//!   the terminal UI); the events are for other front ends built on this crate.
+//! - Requests are logged with method, URL, headers, and body. Credential headers
+//!   (`Authorization`, `x-api-key`, ...) are replaced before anything else, and every
+//!   line then passes through `redact`, even with `logging.redaction = false`.
+//! - Responses: status, plus a body excerpt for errors. Streamed replies log the chunk
+//!   count and the first and last chunks when the stream ends, not every chunk.
+//! - The file is rotated at `MAX_FILE_BYTES`: the old one is kept as `http.log.1`.
+//! - Logging never fails the request: the first write error is printed as a warning and
+//!   later ones are ignored.
+//!
+//! ```
+//! use aion_core::http_log;
+//! use std::sync::{Arc, Mutex};
+//! use tracing::field::{Field, Visit};
+//! use tracing::span::{Attributes, Id, Record};
+//! use tracing::{Event, Metadata, Subscriber};
+//!
+//! /// Collects the events `aion::http` sends.
+//! struct Sink(Arc<Mutex<Vec<String>>>);
+//! impl Visit for Sink {
+//!     fn record_debug(&mut self, _: &Field, value: &dyn std::fmt::Debug) {
+//!         self.0.lock().unwrap().push(format!("{:?}", value));
+//!     }
+//! }
+//! impl Subscriber for Sink {
+//!     fn enabled(&self, meta: &Metadata<'_>) -> bool { meta.target() == "aion::http" }
+//!     fn new_span(&self, _: &Attributes<'_>) -> Id { Id::from_u64(1) }
+//!     fn record(&self, _: &Id, _: &Record<'_>) {}
+//!     fn record_follows_from(&self, _: &Id, _: &Id) {}
+//!     fn event(&self, event: &Event<'_>) { event.record(&mut Sink(self.0.clone())) }
+//!     fn enter(&self, _: &Id) {}
+//!     fn exit(&self, _: &Id) {}
+//! }
+//!
+//! let state = std::env::temp_dir().join(format!("aion-http-log-{}", std::process::id()));
+//! std::env::set_var("AION_STATE_DIR", &state);
+//! let client = reqwest::Client::new();
+//! let request = client
+//!     .post("https://api.openai.com/v1/chat/completions")
+//!     .bearer_auth("sk-fixture0123456789abcdef")
+//!     .header("x-api-key", "fixture-anthropic-key")
+//!     .header("Cookie", "session=fixture-cookie")
+//!     .header("Content-Type", "application/json")
+//!     .body(r#"{"model":"gpt-4o-mini"}"#)
+//!     .build()
+//!     .unwrap();
+//!
+//! let events = Arc::new(Mutex::new(Vec::new()));
+//! http_log::force();
+//! tracing::subscriber::with_default(Sink(events.clone()), || http_log::request(&request));
+//!
+//! let file = std::fs::read_to_string(http_log::http_log_file_path().unwrap()).unwrap();
+//! let events = events.lock().unwrap().join("\n");
+//! for sink in [&file, &events] {
+//!     assert!(sink.contains("--> POST https://api.openai.com/v1/chat/completions"), "{}", sink);
+//!     assert!(sink.contains("authorization: ***REDACTED***") && sink.contains("x-api-key: ***REDACTED***"), "{}", sink);
+//!     assert!(sink.contains("content-type: application/json") && sink.contains(r#"{"model":"gpt-4o-mini"}"#), "{}", sink);
+//!     for secret in ["sk-fixture", "Bearer", "fixture-anthropic-key", "fixture-cookie"] {
+//!         assert!(!sink.contains(secret), "{} in {}", secret, sink);
+//!     }
+//! }
+//! std::fs::remove_dir_all(&state).unwrap();
+//! ```
+
+use crate::config::AppConfig;
+use crate::paths;
+use crate::persistence;
+use crate::redact::{self, Redactor, REDACTED};
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HTTP_LOG_FILE_NAME: &str = "http.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Longest body or chunk text written for one entry.
+const MAX_EXCERPT_CHARS: usize = 2000;
+/// Header values never written.
+const SECRET_HEADERS: &[&str] = &["authorization", "proxy-authorization", "x-api-key", "api-key", "cookie"];
+/// The `tracing` target of the events.
+const TARGET: &str = "aion::http";
+
+static FORCED: AtomicBool = AtomicBool::new(false);
+static GLOBAL: RwLock<Option<PathBuf>> = RwLock::new(None);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Where the HTTP log is kept.
+pub fn http_log_file_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(HTTP_LOG_FILE_NAME))
+}
+
+/// Log for the rest of the process, whatever the config says (`--debug-http`), unless
+/// in read-only mode.
+pub fn force() {
+    if crate::read_only::active().is_some() {
+        return;
+    }
+    FORCED.store(true, Ordering::Relaxed);
+    let mut global = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    *global = http_log_file_path().ok();
+}
+
+/// Start (or stop) logging according to `cfg`, unless `force` was called.
+pub fn init(cfg: &AppConfig) {
+    let mut global = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    let on = FORCED.load(Ordering::Relaxed) || (cfg.logging.http_debug && persistence::policy().keeps_history());
+    *global = if on { http_log_file_path().ok() } else { None };
+}
+
+/// Whether anything is logged: the file is on, or a subscriber wants the events.
+pub fn enabled() -> bool {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).is_some() || tracing::enabled!(target: TARGET, tracing::Level::DEBUG)
+}
+
+/// An outgoing request, before it is sent.
+pub fn request(req: &reqwest::Request) {
+    if !enabled() {
+        return;
+    }
+    let mut lines = vec![format!("--> {} {}", req.method(), req.url())];
+    for (name, value) in req.headers() {
+        let value = match SECRET_HEADERS.contains(&name.as_str()) {
+            true => REDACTED.to_string(),
+            false => String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        };
+        lines.push(format!("    {}: {}", name, value));
+    }
+    if let Some(body) = req.body().and_then(|b| b.as_bytes()) {
+        lines.push(format!("    {}", excerpt(&String::from_utf8_lossy(body))));
+    }
+    write(&lines);
+}
+
+/// The status of a reply, and `body` (an excerpt) when it was read, i.e. for errors.
+pub fn response(url: &str, status: u16, body: Option<&str>) {
+    if !enabled() {
+        return;
+    }
+    let mut lines = vec![format!("<-- {} {}", status, url)];
+    if let Some(body) = body {
+        lines.push(format!("    {}", excerpt(body)));
+    }
+    write(&lines);
+}
+
+/// Counts the chunks of a streamed reply and logs a summary when dropped, however the
+/// stream ended.
+pub struct StreamTrace {
+    url: String,
+    count: usize,
+    first: Option<String>,
+    last: Option<String>,
+}
+
+impl StreamTrace {
+    /// `None` when logging is off, so a stream costs nothing then.
+    pub fn start(url: &str) -> Option<Self> {
+        enabled().then(|| Self { url: url.to_string(), count: 0, first: None, last: None })
+    }
+
+    pub fn chunk(&mut self, bytes: &[u8]) {
+        let text = String::from_utf8_lossy(bytes).into_owned();
+        if self.first.is_none() {
+            self.first = Some(text);
+        } else {
+            self.last = Some(text);
+        }
+        self.count += 1;
+    }
+}
+
+impl Drop for StreamTrace {
+    fn drop(&mut self) {
+        let mut lines = vec![format!("<-- stream {}: {} chunks", self.url, self.count)];
+        if let Some(first) = &self.first {
+            lines.push(format!("    first: {}", excerpt(first)));
+        }
+        if let Some(last) = &self.last {
+            lines.push(format!("    last: {}", excerpt(last)));
+        }
+        write(&lines);
+    }
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}... ({} bytes)", &text[..end], text.len()),
+        None => text.to_string(),
+    }
+}
+
+fn write(lines: &[String]) {
+    let lines: Vec<String> = lines.iter().map(|line| scrub(line)).collect();
+    for line in &lines {
+        tracing::debug!(target: TARGET, "{}", line);
+    }
+    let global = GLOBAL.read().unwrap_or_else(|e| e.into_inner());
+    let Some(path) = global.as_ref() else { return };
+    if let Err(e) = append(path, &lines) {
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            eprintln!("Warning: HTTP debug log not written: {:#}", e);
+        }
+    }
+}
+
+fn append(path: &Path, lines: &[String]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    if fs::metadata(path).map(|m| m.len() >= MAX_FILE_BYTES).unwrap_or(false) {
+        let mut old = path.as_os_str().to_owned();
+        old.push(".1");
+        fs::rename(path, &old).with_context(|| format!("failed to rotate {}", path.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let ts = now();
+    for line in lines {
+        writeln!(file, "{} {}", ts, line).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// `redact` with the process-wide redactor, always on: this log exists to be shared.
+fn scrub(line: &str) -> String {
+    let line = redact::redact(line);
+    Redactor::default().redact(&line)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,187 @@
+//! Lines that mix directions, like an Arabic language name in an English line or a model
+//! name in an Arabic one, composed so terminals that reorder bidi text keep them in order.
+//!
+//! Notes:
+//! - A line is written in one locale, and that locale's `[meta] direction` is the line's
+//!   direction (`ltr` when the locale is not installed). Each run of text in the other
+//!   direction is wrapped in a directional isolate: RLI … PDI for right-to-left text in a
+//!   left-to-right line, LRI … PDI the other way round.
+//! - A run covers whole words: punctuation and digits touching it go in with it, so
+//!   `(ar)`, a path, or `mistral:7b` stays in one piece, except sentence punctuation at
+//!   its end (`?` after an Arabic word in an English question) and a closing bracket
+//!   opened before it. Words next to each other
+//!   share an isolate; a word in the line's own direction ends it.
+//! - `line` leaves a line alone when nothing in it is in its own direction (the terminal
+//!   gets it right as it is) or when it has isolates already.
+//! - `part` is for a value put into a line, like a localized description: one that
+//!   starts in the other direction is isolated whole, so the Latin names inside an
+//!   Arabic phrase stay where the phrase puts them.
+//! - `ui.bidi_isolates = false` turns this off, process-wide like `format` (`init`), for
+//!   terminals that draw the isolates as boxes. The full-screen views draw cell by cell
+//!   and drop zero-width characters, so only printed output goes through here.
+//!
+//! ```
+//! use aion_core::config::AppConfig;
+//! use aion_core::i18n::bidi::{self, isolate, Direction};
+//!
+//! // An Arabic name in an English line.
+//! assert_eq!(isolate(Direction::Ltr, "Language: العربية (ar)"), "Language: \u{2067}العربية\u{2069} (ar)");
+//! assert_eq!(isolate(Direction::Ltr, "Enable فحص النظام? [Y/n]"), "Enable \u{2067}فحص النظام\u{2069}? [Y/n]");
+//! assert_eq!(isolate(Direction::Ltr, "Model: mistral"), "Model: mistral");
+//!
+//! // Latin in an Arabic line; punctuation and digits stay with their word.
+//! assert_eq!(isolate(Direction::Rtl, "اللغة: العربية (ar)"), "اللغة: العربية \u{2066}(ar)\u{2069}");
+//! assert_eq!(isolate(Direction::Rtl, "النموذج: mistral:7b"), "النموذج: \u{2066}mistral:7b\u{2069}");
+//! assert_eq!(
+//!     isolate(Direction::Rtl, "الإعدادات: ~/.config/aion/config.toml"),
+//!     "الإعدادات: \u{2066}~/.config/aion/config.toml\u{2069}"
+//! );
+//! assert_eq!(
+//!     isolate(Direction::Rtl, "الميزات: Web search, Retrieval و Safe execute"),
+//!     "الميزات: \u{2066}Web search, Retrieval\u{2069} و \u{2066}Safe execute\u{2069}"
+//! );
+//! assert_eq!(isolate(Direction::Rtl, "[الإعداد المسبق safe]"), "[الإعداد المسبق \u{2066}safe\u{2069}]");
+//!
+//! // English lines without a locale file; a line all in the other direction is left alone.
+//! assert_eq!(bidi::line("en", "Provider: أولاما"), "Provider: \u{2067}أولاما\u{2069}");
+//! assert_eq!(bidi::line("en", "أولاما"), "أولاما");
+//! // A value is isolated whole by the direction it starts in.
+//! let description = "نماذج Claude عبر واجهة Anthropic";
+//! assert_eq!(bidi::part("en", description), format!("\u{2067}{}\u{2069}", description));
+//! assert_eq!(bidi::part("en", "العربية (ar)"), "\u{2067}العربية (ar)\u{2069}");
+//! assert_eq!(bidi::part("en", "Claude (نماذج)"), "Claude \u{2067}(نماذج)\u{2069}");
+//! assert_eq!(bidi::line("en", &bidi::line("en", "Provider: أولاما")), "Provider: \u{2067}أولاما\u{2069}");
+//!
+//! let mut cfg = AppConfig::new_default();
+//! cfg.ui.bidi_isolates = false;
+//! bidi::init(&cfg);
+//! assert_eq!(bidi::line("en", "Provider: أولاما"), "Provider: أولاما");
+//! ```
+
+use crate::config::AppConfig;
+use std::sync::atomic::{AtomicBool, Ordering};
+use unicode_bidi::{bidi_class, BidiClass};
+
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+/// Punctuation left outside the end of a run: it belongs to the line.
+const SENTENCE_END: [char; 9] = ['.', ',', ':', ';', '!', '?', '،', '؛', '؟'];
+
+static ISOLATES: AtomicBool = AtomicBool::new(true);
+
+/// Use (or stop using) isolates according to `cfg.ui.bidi_isolates`.
+pub fn init(cfg: &AppConfig) {
+    ISOLATES.store(cfg.ui.bidi_isolates, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// `locale`'s direction from its `[meta]`; left to right when it is not installed.
+    pub fn of(locale: &str) -> Self {
+        match super::shared().and_then(|m| m.meta(locale)) {
+            Some(meta) if meta.direction.eq_ignore_ascii_case("rtl") => Direction::Rtl,
+            _ => Direction::Ltr,
+        }
+    }
+
+    /// The direction `c` is strongly in; `None` for digits, punctuation, and spaces.
+    fn of_char(c: char) -> Option<Self> {
+        match bidi_class(c) {
+            BidiClass::L => Some(Direction::Ltr),
+            BidiClass::R | BidiClass::AL => Some(Direction::Rtl),
+            _ => None,
+        }
+    }
+}
+
+/// A whole line written in `locale`, with isolates as the module notes say.
+pub fn line(locale: &str, text: &str) -> String {
+    let base = Direction::of(locale);
+    match ISOLATES.load(Ordering::Relaxed) && text.chars().any(|c| Direction::of_char(c) == Some(base)) {
+        true => isolate(base, text),
+        false => text.to_string(),
+    }
+}
+
+/// A value to put into a line written in `locale`; see the module notes.
+pub fn part(locale: &str, text: &str) -> String {
+    let base = Direction::of(locale);
+    if !ISOLATES.load(Ordering::Relaxed) || text.contains([LRI, RLI, FSI, PDI]) {
+        return text.to_string();
+    }
+    match text.chars().find_map(Direction::of_char) {
+        Some(Direction::Rtl) if base == Direction::Ltr => format!("{}{}{}", RLI, text, PDI),
+        Some(Direction::Ltr) if base == Direction::Rtl => format!("{}{}{}", LRI, text, PDI),
+        _ => isolate(base, text),
+    }
+}
+
+/// `text` with each run in the other direction than `base` isolated, whatever
+/// `ui.bidi_isolates` says. Text that has isolates already is returned as it is.
+pub fn isolate(base: Direction, text: &str) -> String {
+    if text.contains([LRI, RLI, FSI, PDI]) {
+        return text.to_string();
+    }
+    let (open, other) = match base {
+        Direction::Ltr => (RLI, Direction::Rtl),
+        Direction::Rtl => (LRI, Direction::Ltr),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let joined = |c: char| !c.is_whitespace() && Direction::of_char(c).is_none();
+
+    // (first, last) character of each run.
+    let mut runs = Vec::new();
+    let mut from = 0;
+    while let Some(first) = (from..chars.len()).find(|&i| Direction::of_char(chars[i]) == Some(other)) {
+        let mut last = first;
+        for (i, &c) in chars.iter().enumerate().skip(first) {
+            match Direction::of_char(c) {
+                Some(d) if d == base => break,
+                Some(_) => last = i,
+                None => {}
+            }
+        }
+        let mut start = first;
+        while start > from && joined(chars[start - 1]) {
+            start -= 1;
+        }
+        // A closing bracket goes in only when its opening one is in the run.
+        let brackets = |range: &[char], set: &str| range.iter().filter(|c| set.contains(**c)).count();
+        let mut unclosed = brackets(&chars[start..=last], "([{").saturating_sub(brackets(&chars[start..=last], ")]}"));
+        while last + 1 < chars.len() && joined(chars[last + 1]) {
+            if ")]}".contains(chars[last + 1]) {
+                if unclosed == 0 {
+                    break;
+                }
+                unclosed -= 1;
+            }
+            last += 1;
+        }
+        while last > first && SENTENCE_END.contains(&chars[last]) {
+            last -= 1;
+        }
+        runs.push((start, last));
+        from = last + 1;
+    }
+
+    let mut out = String::with_capacity(text.len() + runs.len() * 6);
+    let mut runs = runs.into_iter().peekable();
+    for (i, &c) in chars.iter().enumerate() {
+        if runs.peek().is_some_and(|&(start, _)| start == i) {
+            out.push(open);
+        }
+        out.push(c);
+        if runs.peek().is_some_and(|&(_, last)| last == i) {
+            out.push(PDI);
+            runs.next();
+        }
+    }
+    out
+}
@@ -0,0 +1,143 @@
+//! Locale-aware presentation of sizes and paths, shared by the CLI and TUI.
+//!
+//! Notes:
+//! - Sizes use binary units (1 KiB = 1024 bytes) with one decimal; unit names come from
+//!   the `[format]` section of the locale, English when it has none.
+//! - Locales with their own digits (Arabic) use them only with `ui.native_digits`,
+//!   process-wide like `redact` (`init` once the config is loaded). Their decimal
+//!   separator changes with them.
+//! - Paths are shown with the home directory as `~`. `shorten_path` then elides the
+//!   middle to fit a display width, cutting only between grapheme clusters and counting
+//!   wide characters as two columns.
+//!
+//! ```
+//! use aion_core::config::AppConfig;
+//! use aion_core::i18n::format::{format_bytes, localize_number, shorten_path};
+//! use std::path::Path;
+//!
+//! assert_eq!(format_bytes("en", 512), "512 B");
+//! assert_eq!(format_bytes("en", 1_503_238_553), "1.4 GiB");
+//! assert_eq!(shorten_path(Path::new("/srv/projects/very-long-name/src/main.rs"), 24), "/srv/projec…/src/main.rs");
+//! // The damma on "مُ" stays with its letter.
+//! assert_eq!(shorten_path(Path::new("/srv/مُشاريع/تقرير-نهائي.md"), 16), "/srv/مُش…نهائي.md");
+//!
+//! let mut cfg = AppConfig::new_default();
+//! cfg.ui.native_digits = true;
+//! aion_core::i18n::format::init(&cfg);
+//! assert_eq!(localize_number("ar", "1.4"), "١٫٤");
+//! assert_eq!(localize_number("en", "1.4"), "1.4");
+//! ```
+
+use super::text;
+use crate::config::AppConfig;
+use std::path::{Path, MAIN_SEPARATOR};
+use std::sync::atomic::{AtomicBool, Ordering};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const UNITS: [(&str, &str); 5] = [
+    ("format.bytes", "{n} B"),
+    ("format.kib", "{n} KiB"),
+    ("format.mib", "{n} MiB"),
+    ("format.gib", "{n} GiB"),
+    ("format.tib", "{n} TiB"),
+];
+const ELLIPSIS: &str = "…";
+
+static NATIVE_DIGITS: AtomicBool = AtomicBool::new(false);
+
+/// Use (or stop using) native digits according to `cfg.ui.native_digits`.
+pub fn init(cfg: &AppConfig) {
+    NATIVE_DIGITS.store(cfg.ui.native_digits, Ordering::Relaxed);
+}
+
+/// `bytes` in the largest unit that keeps the number at or above 1, e.g. "1.4 GiB".
+pub fn format_bytes(locale: &str, bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    // Up a unit before rounding could print "1024.0 KiB".
+    while value >= 1023.95 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let number = if unit == 0 { bytes.to_string() } else { format!("{:.1}", value) };
+    let (key, fallback) = UNITS[unit];
+    text(locale, key, fallback, &[("n", localize_number(locale, &number))])
+}
+
+/// `number` (ASCII digits and `.`) in `locale`'s own digits, with `ui.native_digits`.
+pub fn localize_number(locale: &str, number: &str) -> String {
+    let native = NATIVE_DIGITS.load(Ordering::Relaxed);
+    let Some((zero, decimal)) = native_digits(locale).filter(|_| native) else {
+        return number.to_string();
+    };
+    number
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => char::from_u32(zero as u32 + (c as u32 - '0' as u32)).unwrap_or(c),
+            '.' => decimal,
+            c => c,
+        })
+        .collect()
+}
+
+/// The zero digit and decimal separator of locales that have their own digits.
+fn native_digits(locale: &str) -> Option<(char, char)> {
+    match locale.split(['-', '_']).next().unwrap_or(locale) {
+        // Arabic-Indic digits and the Arabic decimal separator.
+        "ar" => Some(('\u{0660}', '\u{066B}')),
+        _ => None,
+    }
+}
+
+/// `path` with the home directory shown as `~`.
+pub fn tilde(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => Path::new("~").join(rest).display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+/// `text` with every path under the home directory starting with `~` instead.
+pub fn tilde_in(text: &str) -> String {
+    match dirs::home_dir() {
+        Some(home) => text.replace(&format!("{}{}", home.display(), MAIN_SEPARATOR), &format!("~{}", MAIN_SEPARATOR)),
+        None => text.to_string(),
+    }
+}
+
+/// `tilde(path)`, with the middle replaced by `…` when it is wider than `width` columns.
+/// The end (the file name) keeps the larger share.
+pub fn shorten_path(path: &Path, width: usize) -> String {
+    let full = tilde(path);
+    if full.width() <= width {
+        return full;
+    }
+    let room = width.saturating_sub(ELLIPSIS.width());
+    if room == 0 {
+        return if width == 0 { String::new() } else { ELLIPSIS.to_string() };
+    }
+    let graphemes: Vec<&str> = full.graphemes(true).collect();
+
+    let mut head = String::new();
+    let mut head_width = 0;
+    for g in &graphemes {
+        if head_width + g.width() > room / 2 {
+            break;
+        }
+        head_width += g.width();
+        head.push_str(g);
+    }
+    let mut tail = Vec::new();
+    let mut tail_width = 0;
+    for g in graphemes.iter().rev() {
+        if head_width + tail_width + g.width() > room {
+            break;
+        }
+        tail_width += g.width();
+        tail.push(*g);
+    }
+    tail.reverse();
+    format!("{}{}{}", head, ELLIPSIS, tail.concat())
+}
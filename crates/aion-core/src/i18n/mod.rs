@@ -0,0 +1,672 @@
+pub mod bidi;
+pub mod format;
+pub mod scan;
+
+use crate::config::I18nConfig;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// Locale metadata section
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocaleMeta {
+    pub code: String,
+    pub name: String,
+    pub native: String,
+    pub direction: String,
+    pub status: String,
+}
+
+/// Full locale structure
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocaleFile {
+    pub meta: LocaleMeta,
+
+    #[serde(flatten)]
+    pub sections: HashMap<String, toml::Value>,
+}
+
+/// Deepest nesting of tables a locale file may have (`[cli.command]` is 2).
+pub const MAX_DEPTH: usize = 6;
+/// Most values a locale file may define.
+pub const MAX_KEYS: usize = 10_000;
+
+/// Locale files built into the binary, by code. `LocaleManager` falls back to them for
+/// any locale no directory has; `aion init --install-locales` copies them out.
+pub const BUNDLED_LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../../locales/en.toml")),
+    ("ar", include_str!("../../locales/ar.toml")),
+    ("no", include_str!("../../locales/no.toml")),
+    ("zh", include_str!("../../locales/zh.toml")),
+];
+
+/// Where a locale is read from.
+#[derive(Debug, Clone)]
+enum Origin {
+    /// A file found on disk.
+    File { path: PathBuf, max_bytes: u64 },
+    /// One of `BUNDLED_LOCALES`.
+    Bundled { code: &'static str, text: &'static str },
+}
+
+/// A locale, parsed on first use.
+#[derive(Debug, Clone)]
+struct LocaleSlot {
+    origin: Origin,
+    /// The parsed file, or why it could not be read.
+    file: OnceLock<Result<LocaleFile, String>>,
+}
+
+impl LocaleSlot {
+    fn parsed(&self) -> &Result<LocaleFile, String> {
+        self.file.get_or_init(|| {
+            match &self.origin {
+                Origin::File { path, max_bytes } => LocaleManager::load_file(path, *max_bytes),
+                Origin::Bundled { code, text } => LocaleManager::parse(text, code, &format!("{}.toml (bundled)", code)),
+            }
+            .map_err(|e| format!("{:#}", e))
+        })
+    }
+
+    /// The file on disk; `None` for a bundled locale.
+    fn path(&self) -> Option<&Path> {
+        match &self.origin {
+            Origin::File { path, .. } => Some(path),
+            Origin::Bundled { .. } => None,
+        }
+    }
+}
+
+/// A locale file that was left out, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Runtime locale manager
+///
+/// Loading only lists the locale directories; a file is read the first time one of its
+/// strings is needed. The file name (`ar.toml`) is the locale code, and its `meta.code`
+/// must say the same; `meta.direction` must be `ltr` or `rtl`.
+///
+/// A file that cannot be used is left out and the rest load as usual: its locale falls
+/// back to `en`, and `skipped` (or `load_report`, which reads every file first) says
+/// why. A directory that cannot be listed is left out the same way. Locales no directory
+/// has come from `BUNDLED_LOCALES`, so a fresh install needs no files. An `en` file on
+/// disk replaces the bundled one, and must be usable: without it, loading fails and the
+/// front ends use their built-in English strings.
+///
+/// Locale files can come from a directory someone else controls (with
+/// `i18n.load_cwd_locales`, the current one), so each is limited: one larger than
+/// `i18n.max_file_bytes` is skipped without being read, and one nested deeper than
+/// `MAX_DEPTH` or defining more than `MAX_KEYS` values is rejected when read. An
+/// oversized file leaves its locale as earlier directories had it; a rejected one falls
+/// back to `en` like any unreadable file. `skipped` lists both.
+///
+/// ```
+/// use aion_core::config::I18nConfig;
+/// use aion_core::i18n::LocaleManager;
+///
+/// let dir = std::env::temp_dir().join(format!("aion-locales-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let meta = |code: &str| format!("[meta]\ncode = \"{code}\"\nname = \"{code}\"\nnative = \"{code}\"\ndirection = \"ltr\"\nstatus = \"ok\"\n");
+/// std::fs::write(dir.join("en.toml"), meta("en") + "[chat]\nhello = \"Hello\"\n").unwrap();
+/// // Oversized: well past the 4 KiB allowed below.
+/// std::fs::write(dir.join("big.toml"), meta("big") + &format!("[x]\npad = \"{}\"\n", "a".repeat(8192))).unwrap();
+/// // Deeply nested: a table 40 levels down.
+/// let path = vec!["t"; 40].join(".");
+/// std::fs::write(dir.join("deep.toml"), meta("deep") + &format!("[{path}]\nkey = \"v\"\n")).unwrap();
+///
+/// let settings = I18nConfig { load_cwd_locales: false, max_file_bytes: 4096 };
+/// let manager = LocaleManager::load_from(&[dir.clone()], &settings).unwrap();
+/// assert_eq!(manager.t("en", "chat.hello"), "Hello");
+/// assert_eq!(manager.available_locales(), ["ar", "deep", "en", "no", "zh"]);
+/// assert!(manager.meta("deep").is_none());
+/// assert_eq!(manager.t("deep", "chat.hello"), "Hello");
+///
+/// let skipped = manager.skipped();
+/// let names: Vec<_> = skipped.iter().map(|s| s.path.file_name().unwrap().to_str().unwrap()).collect();
+/// assert_eq!(names, ["big.toml", "deep.toml"]);
+/// assert!(skipped[0].reason.contains("larger than"));
+/// assert!(skipped[1].reason.contains("nested"));
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocaleManager {
+    locales: HashMap<String, LocaleSlot>,
+    fallback: String,
+    /// Files and directories left out when the directories were listed (too large,
+    /// unreadable).
+    unlisted: Vec<Skipped>,
+}
+
+impl LocaleManager {
+    /// Find the installed locales and read the fallback (`en`), with the config file's
+    /// `[i18n]` settings.
+    pub fn load() -> Result<Self> {
+        let settings = I18nConfig::from_config_file();
+        Self::load_from(&Self::search_paths(&settings), &settings)
+    }
+
+    /// `load` from `dirs` (later ones override earlier ones, and all of them the bundled
+    /// locales) with `settings`' limits.
+    ///
+    /// ```
+    /// use aion_core::config::I18nConfig;
+    /// use aion_core::i18n::LocaleManager;
+    ///
+    /// // Nothing installed: the bundled locales, in every language.
+    /// let missing = std::env::temp_dir().join(format!("aion-no-locales-{}", std::process::id()));
+    /// let manager = LocaleManager::load_from(&[missing], &I18nConfig::default()).unwrap();
+    /// assert_eq!(manager.available_locales(), ["ar", "en", "no", "zh"]);
+    /// assert_eq!(manager.meta("ar").unwrap().direction, "rtl");
+    /// assert_ne!(manager.t("ar", "cli.help"), manager.t("en", "cli.help"));
+    /// assert_eq!(manager.source("en"), None);
+    /// assert!(manager.load_report().is_empty());
+    /// ```
+    pub fn load_from(dirs: &[PathBuf], settings: &I18nConfig) -> Result<Self> {
+        let mut manager = Self {
+            locales: HashMap::new(),
+            fallback: "en".to_string(),
+            unlisted: Vec::new(),
+        };
+        for (code, text) in BUNDLED_LOCALES {
+            let origin = Origin::Bundled { code, text };
+            manager.locales.insert(code.to_string(), LocaleSlot { origin, file: OnceLock::new() });
+        }
+
+        for dir in dirs {
+            if dir.exists() {
+                manager.index_dir(dir, settings.max_file_bytes);
+            }
+        }
+
+        manager.preload("en").context("Fallback locale 'en' cannot be used")?;
+
+        Ok(manager)
+    }
+
+    /// `load`, and read `code` now as well. A broken file for `code` is in `skipped`
+    /// rather than an error, and `code` falls back to `en`.
+    pub fn load_for(code: &str) -> Result<Self> {
+        let manager = Self::load()?;
+        let _ = manager.preload(code);
+        Ok(manager)
+    }
+
+    /// Read `code` now rather than on first lookup; the error if its file is broken.
+    pub fn preload(&self, code: &str) -> Result<()> {
+        match self.locales.get(code).map(LocaleSlot::parsed) {
+            Some(Err(e)) => Err(anyhow::anyhow!("{}", e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The parsed file for `code`; `None` when not installed or unreadable.
+    fn file(&self, code: &str) -> Option<&LocaleFile> {
+        self.locales.get(code)?.parsed().as_ref().ok()
+    }
+
+    /// Files left out: too large or unreadable (found when listing), or read and rejected
+    /// (too deep, too many keys, not valid TOML, wrong `[meta]`). Only files read so far
+    /// are checked; see `load_report`. Sorted by path.
+    pub fn skipped(&self) -> Vec<Skipped> {
+        let mut out = self.unlisted.clone();
+        out.extend(self.locales.values().filter_map(|slot| match slot.file.get() {
+            Some(Err(reason)) => Some(Skipped {
+                path: slot.path().map_or_else(|| PathBuf::from("<bundled>"), Path::to_path_buf),
+                reason: reason.clone(),
+            }),
+            _ => None,
+        }));
+        out.sort_by(|a, b| a.path.cmp(&b.path));
+        out
+    }
+
+    /// Read every installed locale, then `skipped`: every file left out and why.
+    ///
+    /// ```
+    /// use aion_core::config::I18nConfig;
+    /// use aion_core::i18n::LocaleManager;
+    ///
+    /// let fixtures = |name: &str| std::path::PathBuf::from(format!("{}/fixtures/locales/{}", env!("CARGO_MANIFEST_DIR"), name));
+    /// let settings = I18nConfig::default();
+    ///
+    /// // Valid and broken files side by side: nothing is read until it is needed.
+    /// let manager = LocaleManager::load_from(&[fixtures("mixed")], &settings).unwrap();
+    /// assert_eq!(manager.available_locales(), ["ar", "en", "fa", "no", "tr", "zh"]);
+    /// assert!(manager.skipped().is_empty());
+    /// assert_eq!(manager.t("ar", "chat.hello"), "مرحبا");
+    /// assert_eq!(manager.t("tr", "chat.hello"), "Hello");
+    /// assert_eq!(manager.skipped().len(), 1); // tr.toml, read by the lookup above
+    ///
+    /// let report = manager.load_report();
+    /// let found: Vec<(&str, &str)> = report
+    ///     .iter()
+    ///     .map(|s| (s.path.file_name().unwrap().to_str().unwrap(), s.reason.split(' ').next().unwrap()))
+    ///     .collect();
+    /// assert_eq!(found, [("fa.toml", "meta.code"), ("no.toml", "meta.direction"), ("tr.toml", "Failed")]);
+    /// assert_eq!(manager.t("fa", "chat.hello"), "Hello");
+    ///
+    /// // Only a broken `en` stops loading, even when the next directory has good files.
+    /// assert!(LocaleManager::load_from(&[fixtures("mixed"), fixtures("broken-en")], &settings).is_err());
+    /// ```
+    pub fn load_report(&self) -> Vec<Skipped> {
+        for slot in self.locales.values() {
+            slot.parsed();
+        }
+        self.skipped()
+    }
+
+    /// Get translated string
+    pub fn t(&self, locale: &str, key: &str) -> String {
+        self.lookup(locale, key)
+            .or_else(|| self.lookup(&self.fallback, key))
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Installed and bundled locale codes, sorted, so the order never depends on the directory listing.
+    pub fn available_locales(&self) -> Vec<String> {
+        let mut list: Vec<String> = self.locales.keys().cloned().collect();
+        list.sort();
+        list
+    }
+
+    /// The file `code` is read from (the last one found on the search path); `None` when
+    /// it is bundled or not available.
+    pub fn source(&self, code: &str) -> Option<&Path> {
+        self.locales.get(code)?.path()
+    }
+
+    /// Get locale metadata
+    pub fn meta(&self, code: &str) -> Option<&LocaleMeta> {
+        self.file(code).map(|l| &l.meta)
+    }
+
+    /// Share of the fallback locale's keys that `code` translates, from 0.0 to 1.0.
+    /// `None` when `code` is not loaded.
+    pub fn coverage(&self, code: &str) -> Option<f64> {
+        self.file(code)?;
+        let fallback = self.file(&self.fallback)?;
+
+        let mut keys = Vec::new();
+        for (name, value) in &fallback.sections {
+            collect_keys(name, value, &mut keys);
+        }
+        if keys.is_empty() {
+            return Some(1.0);
+        }
+        let translated = keys.iter().filter(|k| self.lookup(code, k).is_some()).count();
+        Some(translated as f64 / keys.len() as f64)
+    }
+
+    /// Every string `code` defines itself (no fallback), as `(dotted key, text)` sorted by key.
+    /// `None` when `code` is not loaded.
+    pub fn strings(&self, code: &str) -> Option<Vec<(String, String)>> {
+        let locale = self.file(code)?;
+        let mut keys = Vec::new();
+        for (name, value) in &locale.sections {
+            collect_keys(name, value, &mut keys);
+        }
+        let mut strings: Vec<(String, String)> = keys
+            .into_iter()
+            .filter_map(|k| self.lookup(code, &k).map(|text| (k, text)))
+            .collect();
+        strings.sort();
+        Some(strings)
+    }
+
+    /// Keys the fallback locale has and `code` does not, sorted.
+    pub fn missing_keys(&self, code: &str) -> Vec<String> {
+        self.strings(&self.fallback)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, _)| k)
+            .filter(|k| self.lookup(code, k).is_none())
+            .collect()
+    }
+
+    /// Internal lookup
+    fn lookup(&self, locale: &str, key: &str) -> Option<String> {
+        let locale_file = self.file(locale)?;
+
+        let parts: Vec<&str> = key.split('.').collect();
+
+        let mut current: Option<&toml::Value> = None;
+
+        for (i, part) in parts.iter().enumerate() {
+            if i == 0 {
+                current = locale_file.sections.get(*part);
+            } else {
+                current = current?.get(*part);
+            }
+        }
+
+        current?.as_str().map(|s| s.to_string())
+    }
+
+    /// Add the `*.toml` files in `dir` up to `max_bytes`; later directories override
+    /// earlier ones. What cannot be listed is recorded in `unlisted`.
+    fn index_dir(&mut self, dir: &Path, max_bytes: u64) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                let reason = format!("cannot list the directory: {}", e);
+                self.unlisted.push(Skipped { path: dir.to_path_buf(), reason });
+                return;
+            }
+        };
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    let reason = format!("cannot list the directory: {}", e);
+                    self.unlisted.push(Skipped { path: dir.to_path_buf(), reason });
+                    continue;
+                }
+            };
+
+            if path.extension().and_then(|s| s.to_str()) != Some("toml") {
+                continue;
+            }
+            let Some(code) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+                continue;
+            };
+
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if size > max_bytes {
+                let reason = format!("{} bytes, larger than i18n.max_file_bytes ({})", size, max_bytes);
+                self.unlisted.push(Skipped { path, reason });
+                continue;
+            }
+            self.locales.insert(code, LocaleSlot { origin: Origin::File { path, max_bytes }, file: OnceLock::new() });
+        }
+    }
+
+    /// Load a single locale file, within `max_bytes`, and `parse` it as the locale its
+    /// name says.
+    fn load_file(path: &Path, max_bytes: u64) -> Result<LocaleFile> {
+        let read_error = || format!("Failed to read locale file {}", path.display());
+        // The size was checked when listing; the file may have grown since.
+        let mut content = String::new();
+        File::open(path)
+            .and_then(|f| f.take(max_bytes + 1).read_to_string(&mut content))
+            .with_context(read_error)?;
+        if content.len() as u64 > max_bytes {
+            anyhow::bail!("larger than i18n.max_file_bytes ({})", max_bytes);
+        }
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        Self::parse(&content, stem, &path.display().to_string())
+    }
+
+    /// Parse `content` as the locale `code`, within `MAX_DEPTH` and `MAX_KEYS`; `name`
+    /// says where it came from in errors.
+    fn parse(content: &str, code: &str, name: &str) -> Result<LocaleFile> {
+        // Checked before parsing: a long dotted header nests as deep as it is long.
+        if key_path_depth(content) > MAX_DEPTH {
+            anyhow::bail!("tables nested deeper than {} levels", MAX_DEPTH);
+        }
+
+        let table: toml::Table =
+            toml::from_str(content).with_context(|| format!("Failed to parse locale file {}", name))?;
+        let (depth, keys) = table.values().map(shape).fold((0, 0), |(d, k), (vd, vk)| (d.max(vd), k + vk));
+        if depth > MAX_DEPTH {
+            anyhow::bail!("tables nested deeper than {} levels", MAX_DEPTH);
+        }
+        if keys > MAX_KEYS {
+            anyhow::bail!("more than {} keys", MAX_KEYS);
+        }
+
+        let file: LocaleFile = toml::Value::Table(table)
+            .try_into()
+            .with_context(|| format!("Failed to parse locale file {}", name))?;
+        if file.meta.code != code {
+            anyhow::bail!("meta.code is {:?}, but the file name says {:?}", file.meta.code, code);
+        }
+        if !["ltr", "rtl"].contains(&file.meta.direction.to_ascii_lowercase().as_str()) {
+            anyhow::bail!("meta.direction is {:?}, expected \"ltr\" or \"rtl\"", file.meta.direction);
+        }
+        Ok(file)
+    }
+
+    /// The directories locales are read from, with the config file's `[i18n]` settings.
+    pub fn locale_search_paths() -> Result<Vec<PathBuf>> {
+        Ok(Self::search_paths(&I18nConfig::from_config_file()))
+    }
+
+    /// Determine search paths
+    fn search_paths(settings: &I18nConfig) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        // ./locales, only when asked for
+        if settings.load_cwd_locales {
+            if let Ok(current) = std::env::current_dir() {
+                paths.push(current.join("locales"));
+            }
+        }
+
+        // executable_dir/locales
+        if let Ok(exe) = std::env::current_exe() {
+            if let Some(dir) = exe.parent() {
+                paths.push(dir.join("locales"));
+            }
+        }
+
+        // %APPDATA%/aion/locales
+        if let Ok(dir) = user_locale_dir() {
+            paths.push(dir);
+        }
+
+        paths
+    }
+}
+
+/// `locales/` next to config.toml, where `aion init --install-locales` copies the
+/// bundled locale files.
+pub fn user_locale_dir() -> Result<PathBuf> {
+    Ok(crate::paths::config_dir()?.join("locales"))
+}
+
+/// How deep `value` nests (a string is 1) and how many values it holds.
+fn shape(value: &toml::Value) -> (usize, usize) {
+    match value {
+        toml::Value::Table(table) => {
+            let (depth, keys) = table.values().map(shape).fold((0, 0), |(d, k), (vd, vk)| (d.max(vd), k + vk));
+            (depth + 1, keys)
+        }
+        toml::Value::Array(items) => {
+            let (depth, keys) = items.iter().map(shape).fold((0, 0), |(d, k), (vd, vk)| (d.max(vd), k + vk));
+            (depth + 1, keys.max(1))
+        }
+        _ => (1, 1),
+    }
+}
+
+/// The most segments in a dotted key or table header of `text`, counting the dots outside
+/// quotes; over-counts rather than under-counts, which is all a limit needs.
+fn key_path_depth(text: &str) -> usize {
+    text.lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let key = match line.strip_prefix('[') {
+                Some(header) => header.split(']').next().unwrap_or(""),
+                None => line.split('=').next().unwrap_or(""),
+            };
+            let mut quote = None;
+            let mut segments = 1;
+            for c in key.chars() {
+                match (quote, c) {
+                    (None, '"' | '\'') => quote = Some(c),
+                    (Some(q), c) if c == q => quote = None,
+                    (None, '.') => segments += 1,
+                    _ => {}
+                }
+            }
+            segments
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Dotted keys of every string under `value`.
+fn collect_keys(prefix: &str, value: &toml::Value, out: &mut Vec<String>) {
+    match value {
+        toml::Value::String(_) => out.push(prefix.to_string()),
+        toml::Value::Table(table) => {
+            for (name, child) in table {
+                collect_keys(&format!("{}.{}", prefix, name), child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Missing placeholder value during interpolation
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("missing value for variable '{0}'")]
+pub struct MissingVariable(pub String);
+
+/// Replace `{name}` placeholders with values from `vars`.
+///
+/// `{{` and `}}` produce literal braces. A placeholder without a value is an error
+/// naming the variable, so callers can report exactly what is missing.
+pub fn interpolate(template: &str, vars: &HashMap<String, String>) -> Result<String, MissingVariable> {
+    let mut out = String::with_capacity(template.len());
+    for part in parts(template) {
+        match part {
+            Part::Text(text) => out.push_str(&text),
+            Part::Placeholder(name) => match vars.get(&name) {
+                Some(value) => out.push_str(value),
+                None => return Err(MissingVariable(name)),
+            },
+        }
+    }
+    Ok(out)
+}
+
+/// The placeholder names in `template`, as `interpolate` reads them.
+///
+/// ```
+/// use aion_core::i18n::placeholders;
+///
+/// assert_eq!(placeholders("{path} ({reason}), again {path}"), ["path", "reason"].map(String::from).into());
+/// assert!(placeholders("{{literal}} and {\"json\": 1}").is_empty());
+/// ```
+pub fn placeholders(template: &str) -> BTreeSet<String> {
+    parts(template)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Placeholder(name) => Some(name),
+            Part::Text(_) => None,
+        })
+        .collect()
+}
+
+enum Part {
+    Text(String),
+    Placeholder(String),
+}
+
+/// `template` split into text and `{name}` placeholders.
+fn parts(template: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for n in chars.by_ref() {
+                    if n == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(n);
+                }
+                let valid = closed
+                    && !name.is_empty()
+                    && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '-');
+                if !valid {
+                    // Not a placeholder (e.g. a JSON snippet in a prompt): keep it verbatim.
+                    text.push('{');
+                    text.push_str(&name);
+                    if closed {
+                        text.push('}');
+                    }
+                    continue;
+                }
+                parts.push(Part::Text(std::mem::take(&mut text)));
+                parts.push(Part::Placeholder(name));
+            }
+            _ => text.push(c),
+        }
+    }
+
+    parts.push(Part::Text(text));
+    parts
+}
+
+/// Global locale instance
+static GLOBAL_LOCALE: RwLock<Option<LocaleManager>> = RwLock::new(None);
+
+/// Initialize locale system
+pub fn init() -> Result<()> {
+    let manager = LocaleManager::load()?;
+
+    *GLOBAL_LOCALE.write().unwrap_or_else(|e| e.into_inner()) = Some(manager);
+
+    Ok(())
+}
+
+/// Get translated string from global locale
+pub fn t(locale: &str, key: &str) -> String {
+    GLOBAL_LOCALE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|m| m.t(locale, key))
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Locales found on first use and kept for the process (each file is read when first
+/// needed); `None` when no locale files are found.
+pub fn shared() -> Option<&'static LocaleManager> {
+    static SHARED: OnceLock<Option<LocaleManager>> = OnceLock::new();
+    SHARED.get_or_init(|| LocaleManager::load().ok()).as_ref()
+}
+
+/// `key` from the shared locales in `locale`, filled with `vars`. `fallback` (filled the
+/// same way) is used when no locale file has the key, e.g. when none are installed.
+pub fn text(locale: &str, key: &str, fallback: &str, vars: &[(&str, String)]) -> String {
+    let template = shared()
+        .map(|m| m.t(locale, key))
+        .filter(|t| t != key)
+        .unwrap_or_else(|| fallback.to_string());
+    let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+    interpolate(&template, &vars).unwrap_or(template)
+}
+
+/// Get available locales
+pub fn available_locales() -> Vec<String> {
+    GLOBAL_LOCALE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|m| m.available_locales())
+        .unwrap_or_default()
+}
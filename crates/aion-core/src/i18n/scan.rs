@@ -0,0 +1,380 @@
+//! Which locale keys the code looks up: a scan of Rust sources for `t(`, `text(`,
+//! `t_list(`, and `tr!(` calls, compared with the keys a locale defines
+//! (`aion locales stats`).
+//!
+//! Notes:
+//! - A light lexer rather than a parser: comments, strings, and char literals are
+//!   skipped, so a call in a doc comment or a message does not count. Calls may span
+//!   lines.
+//! - The key is the argument after the locale (`text(lang, "summary.title", ..)`), or the
+//!   first for `tr!`. A string literal is a key; so is a `const` of the same file holding
+//!   one. Anything else is a dynamic key: counted on its own, with the literal start of a
+//!   `format!` (`"summary.cap.{}"` gives `summary.cap.`) kept as its prefix.
+//! - Keys a dynamic prefix covers are neither used nor unused: `maybe_used` lists them.
+//! - `.text(` method calls are not lookups, and `fn t(` is a definition. A file that
+//!   binds a closure named `t` or `text` (`let t = |key| ..`) wraps the lookup: its own
+//!   calls to that name are skipped, and the wrapped call is what counts.
+//!
+//! ```
+//! use aion_core::i18n::scan::{compare, scan_dir, scan_source, Key};
+//! use std::fs;
+//!
+//! let lookups = scan_source(r#"
+//! const KEY: &str = "chat.reply_instruction";
+//! fn f(lang: &str, m: &LocaleManager, q: &Prompt) {
+//!     let a = i18n::text(lang, "summary.title", "Config loaded", &[]);
+//!     let b = i18n::text(
+//!         lang,
+//!         "summary.warning.missing_api_key",
+//!         "{var} is not set",
+//!         &[("var", var.clone())],
+//!     );
+//!     let c = m.t(lang, KEY);
+//!     let d = i18n::text(lang, &format!("summary.cap.{}", cap), cap, &[]);
+//!     let e = tr!("wizard.hint.next");
+//!     let f = t_list(lang, "help.examples");
+//!     // i18n::text(lang, "commented.out", "", &[])
+//!     let s = "i18n::t(lang, \"in.a.string\")";
+//!     let model = q.text("Model", &current);
+//! }
+//! "#);
+//! let keys: Vec<&Key> = lookups.iter().map(|l| &l.key).collect();
+//! assert_eq!(keys.len(), 6);
+//! assert_eq!((lookups[1].line, keys[1]), (5, &Key::Literal("summary.warning.missing_api_key".into())));
+//! assert_eq!(keys[2], &Key::Literal("chat.reply_instruction".into()));
+//! assert!(matches!(keys[3], Key::Dynamic { prefix: Some(p), .. } if p == "summary.cap."));
+//!
+//! // A directory of fixtures, one with a wrapping closure.
+//! let dir = std::env::temp_dir().join(format!("aion-scan-{}", std::process::id()));
+//! fs::create_dir_all(dir.join("ui")).unwrap();
+//! fs::write(dir.join("a.rs"), "fn a(lang: &str) { i18n::t(lang, \"summary.title\"); i18n::t(lang, \"not.in.en\"); }").unwrap();
+//! fs::write(dir.join("ui/b.rs"), "fn b(lang: &str) {\n    let t = |key: &str| i18n::text(lang, &format!(\"summary.{}\", key), key, &[]);\n    t(\"none\");\n}\n").unwrap();
+//! let found = scan_dir(&dir).unwrap();
+//! fs::remove_dir_all(&dir).unwrap();
+//!
+//! let defined: Vec<String> = ["summary.title", "summary.none", "old.unused"].map(String::from).to_vec();
+//! let report = compare(&found, &defined);
+//! assert_eq!((report.files, report.used), (2, 1));
+//! assert_eq!(report.unused, ["old.unused"]);
+//! assert_eq!(report.missing.iter().map(|m| m.key.as_str()).collect::<Vec<_>>(), ["not.in.en"]);
+//! assert_eq!(report.maybe_used, ["summary.none"]);
+//! assert_eq!(report.dynamic.len(), 1);
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lookup functions, and which argument holds the key.
+const LOOKUPS: &[(&str, usize)] = &[("t", 1), ("text", 1), ("t_list", 1), ("tr!", 0)];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    Literal(String),
+    /// Built at runtime; `expr` is the argument as written, on one line.
+    Dynamic { prefix: Option<String>, expr: String },
+}
+
+/// One lookup call, by its line (from 1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookup {
+    pub line: usize,
+    pub key: Key,
+}
+
+/// A lookup in a scanned file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Found {
+    pub file: PathBuf,
+    pub lookup: Lookup,
+}
+
+/// The lookup calls in `source`, in order.
+pub fn scan_source(source: &str) -> Vec<Lookup> {
+    let bytes = source.as_bytes();
+    let consts = string_consts(source);
+    let wrappers: Vec<&str> = ["t", "text"]
+        .into_iter()
+        .filter(|name| source.contains(&format!("let {} =", name)) || source.contains(&format!("let {}:", name)))
+        .collect();
+
+    let mut lookups = Vec::new();
+    let mut previous = "";
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(source, i) {
+            i = end;
+            continue;
+        }
+        if !is_ident_start(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && is_ident(bytes[i]) {
+            i += 1;
+        }
+        let mut name = &source[start..i];
+        if bytes.get(i) == Some(&b'!') {
+            name = &source[start..=i];
+        }
+        let ident = &source[start..i];
+        let before = source[..start].trim_end();
+        let method = before.ends_with('.');
+        let free = !method && !before.ends_with("::");
+        let skip = previous == "fn" || (method && name == "text") || (free && wrappers.contains(&name));
+        previous = ident;
+        let Some(&(_, position)) = LOOKUPS.iter().find(|(n, _)| *n == name).filter(|_| !skip) else { continue };
+
+        let open = source[start + name.len()..].find(|c: char| !c.is_whitespace()).map(|o| start + name.len() + o);
+        let Some(open) = open.filter(|&o| bytes[o] == b'(') else { continue };
+        let Some(args) = arguments(source, open) else { continue };
+        if let Some(arg) = args.get(position) {
+            let line = source[..start].matches('\n').count() + 1;
+            lookups.push(Lookup { line, key: key(arg, &consts) });
+        }
+        // Carry on inside the arguments: a lookup can hold another.
+        i = open + 1;
+    }
+    lookups
+}
+
+/// Every `.rs` file under `dir`, scanned, in path order.
+pub fn scan_dir(dir: &Path) -> Result<Vec<Found>> {
+    let mut files = Vec::new();
+    rust_files(dir, &mut files)?;
+    files.sort();
+    let mut found = Vec::new();
+    for file in files {
+        let source = fs::read_to_string(&file).with_context(|| format!("failed to read {}", file.display()))?;
+        found.extend(scan_source(&source).into_iter().map(|lookup| Found { file: file.clone(), lookup }));
+    }
+    Ok(found)
+}
+
+fn rust_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry.with_context(|| format!("failed to read {}", dir.display()))?.path();
+        if path.is_dir() {
+            rust_files(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/* ---------------------------
+   Comparing
+---------------------------- */
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Site {
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// A literal key the locale does not define, where it is first used.
+#[derive(Debug, Clone, Serialize)]
+pub struct Missing {
+    pub key: String,
+    #[serde(flatten)]
+    pub site: Site,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dynamic {
+    pub prefix: Option<String>,
+    pub expr: String,
+    #[serde(flatten)]
+    pub site: Site,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// Files with at least one lookup.
+    pub files: usize,
+    /// Defined keys looked up by a literal.
+    pub used: usize,
+    /// Defined keys nothing looks up, sorted.
+    pub unused: Vec<String>,
+    /// Defined keys only a dynamic key's prefix reaches, sorted.
+    pub maybe_used: Vec<String>,
+    pub missing: Vec<Missing>,
+    pub dynamic: Vec<Dynamic>,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.unused.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// `found` against the `defined` keys of a locale (dotted, as `LocaleManager::strings`).
+pub fn compare(found: &[Found], defined: &[String]) -> Report {
+    let defined: BTreeSet<&str> = defined.iter().map(String::as_str).collect();
+    let mut literal: BTreeMap<&str, Site> = BTreeMap::new();
+    let mut dynamic = Vec::new();
+    for f in found {
+        let site = || Site { file: f.file.clone(), line: f.lookup.line };
+        match &f.lookup.key {
+            Key::Literal(key) => {
+                literal.entry(key.as_str()).or_insert_with(site);
+            }
+            Key::Dynamic { prefix, expr } => dynamic.push(Dynamic { prefix: prefix.clone(), expr: expr.clone(), site: site() }),
+        }
+    }
+    let prefixes: Vec<&str> = dynamic.iter().filter_map(|d| d.prefix.as_deref()).collect();
+
+    let (mut unused, mut maybe_used) = (Vec::new(), Vec::new());
+    for key in defined.iter().filter(|k| !literal.contains_key(*k)) {
+        match prefixes.iter().any(|p| key.starts_with(p)) {
+            true => maybe_used.push(key.to_string()),
+            false => unused.push(key.to_string()),
+        }
+    }
+    let missing = literal
+        .iter()
+        .filter(|(key, _)| !defined.contains(*key))
+        .map(|(key, site)| Missing { key: key.to_string(), site: site.clone() })
+        .collect();
+    let files: BTreeSet<&Path> = found.iter().map(|f| f.file.as_path()).collect();
+
+    Report {
+        files: files.len(),
+        used: literal.keys().filter(|k| defined.contains(*k)).count(),
+        unused,
+        maybe_used,
+        missing,
+        dynamic,
+    }
+}
+
+/* ---------------------------
+   Lexing
+---------------------------- */
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// The end of the comment, string, or char literal starting at `i`, if one does.
+fn skip_literal(source: &str, i: usize) -> Option<usize> {
+    if !source.is_char_boundary(i) {
+        return None;
+    }
+    let bytes = source.as_bytes();
+    let rest = &source[i..];
+    if rest.starts_with("//") {
+        return Some(rest.find('\n').map_or(source.len(), |n| i + n));
+    }
+    if let Some(comment) = rest.strip_prefix("/*") {
+        return Some(comment.find("*/").map_or(source.len(), |n| i + 2 + n + 2));
+    }
+    // A raw string: r"..", r#".."#, also after b. Not when `r` ends an identifier.
+    let after_b = if rest.starts_with("br") { 1 } else { 0 };
+    if rest[after_b..].starts_with('r') && (i == 0 || !is_ident(bytes[i - 1])) {
+        let hashes = rest[after_b + 1..].bytes().take_while(|b| *b == b'#').count();
+        if rest[after_b + 1 + hashes..].starts_with('"') {
+            let body = i + after_b + 2 + hashes;
+            let close = format!("\"{}", "#".repeat(hashes));
+            return Some(source[body..].find(&close).map_or(source.len(), |n| body + n + close.len()));
+        }
+    }
+    match bytes[i] {
+        b'"' => {
+            let mut j = i + 1;
+            while j < bytes.len() {
+                match bytes[j] {
+                    b'\\' => j += 2,
+                    b'"' => return Some(j + 1),
+                    _ => j += 1,
+                }
+            }
+            Some(bytes.len())
+        }
+        // A char literal ('a', '\n', 'é'); a lifetime ('a) is left alone.
+        b'\'' => {
+            let mut chars = rest[1..].char_indices();
+            match chars.next() {
+                Some((_, '\\')) => rest.get(3..)?.find('\'').map(|n| i + 3 + n + 1),
+                Some((_, c)) => rest[1 + c.len_utf8()..].starts_with('\'').then(|| i + 1 + c.len_utf8() + 1),
+                None => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The top-level arguments of the call whose `(` is at `open`, trimmed.
+fn arguments(source: &str, open: usize) -> Option<Vec<&str>> {
+    let bytes = source.as_bytes();
+    let (mut depth, mut start, mut i) = (0usize, open + 1, open + 1);
+    let mut args = Vec::new();
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(source, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth > 0 => depth -= 1,
+            b')' => {
+                let last = source[start..i].trim();
+                if !last.is_empty() {
+                    args.push(last);
+                }
+                return Some(args);
+            }
+            b',' if depth == 0 => {
+                args.push(source[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The text of `arg` when it is a plain string literal (`"…"`, `r"…"`).
+fn literal(arg: &str) -> Option<&str> {
+    let arg = arg.trim_start_matches('&').trim();
+    let inner = arg.strip_prefix('"').and_then(|a| a.strip_suffix('"'));
+    let raw = arg.strip_prefix("r\"").and_then(|a| a.strip_suffix('"'));
+    inner.filter(|s| !s.contains('"')).or(raw)
+}
+
+fn key(arg: &str, consts: &HashMap<&str, &str>) -> Key {
+    if let Some(text) = literal(arg) {
+        return Key::Literal(text.to_string());
+    }
+    if let Some(text) = consts.get(arg.trim_start_matches('&')) {
+        return Key::Literal(text.to_string());
+    }
+    let prefix = arg.find("format!(").and_then(|at| {
+        let args = arguments(arg, at + "format!".len())?;
+        let template = literal(args.first()?)?;
+        Some(template[..template.find('{').unwrap_or(template.len())].to_string()).filter(|p| !p.is_empty())
+    });
+    Key::Dynamic { prefix, expr: arg.split_whitespace().collect::<Vec<_>>().join(" ") }
+}
+
+/// `const NAME: &str = "…";` in `source`, by name.
+fn string_consts(source: &str) -> HashMap<&str, &str> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().trim_start_matches("pub ").trim_start_matches("pub(crate) ");
+            let (name, value) = rest.strip_prefix("const ")?.split_once(": &str =")?;
+            Some((name.trim(), literal(value.trim().strip_suffix(';')?)?))
+        })
+        .collect()
+}
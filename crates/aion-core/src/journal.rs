@@ -0,0 +1,463 @@
+//! Session files as append-only journals (`sessions/<id>.jsonl`), and the one place
+//! session files are read (`read`).
+//!
+//! Notes:
+//! - One event per line. An exchange is a `user` line and a `reply` line (model, provider,
+//!   usage); `/regen` and `/undo` add `superseded` lines, `/continue` an `extended` line
+//!   with the text and usage it added, a title a `title` line. A change none of these
+//!   describe is written as a `snapshot` of the whole session. Replaying the lines gives
+//!   the `Session` back.
+//! - A save appends only what changed since the last one (`changes`) and syncs the file
+//!   before returning, so a crash can only cut off the save in progress.
+//! - Recovery: a last line cut off mid-write is dropped, and with it a prompt left without
+//!   its reply. The next save first cuts the file back to the last whole line. Damage
+//!   anywhere else is an error naming the line.
+//! - A process writing a session holds an advisory lock on its file until it exits
+//!   (`open`); another one gets `InUse`. Reading takes no lock.
+//! - Session files from before the journal (`<id>.json`, one JSON document) are still
+//!   read; `sessions` moves them to a journal the first time they are written.
+//!
+//! ```
+//! use aion_core::config::ProviderKind;
+//! use aion_core::journal::{self, InUse};
+//! use aion_core::providers::Usage;
+//! use aion_core::sessions::Session;
+//! use std::fs;
+//!
+//! let dir = std::env::temp_dir().join(format!("aion-journal-{}", std::process::id()));
+//! fs::create_dir_all(&dir).unwrap();
+//! let path = dir.join("chat.jsonl");
+//! let usage = Some(Usage { input_tokens: 3, output_tokens: 5 });
+//!
+//! // A chat, saved after every change: the file length and the session at each save.
+//! let mut session = Session::new();
+//! let mut saves = Vec::new();
+//! let mut save = |session: &Session| {
+//!     journal::append(&path, session).unwrap();
+//!     saves.push((fs::metadata(&path).unwrap().len() as usize, session.clone()));
+//! };
+//! for i in 0..5 {
+//!     session.record(&format!("question {}", i), &format!("answer {}", i), &ProviderKind::Ollama, "llama3", usage);
+//!     save(&session);
+//! }
+//! session.extend_reply(", continued", usage);
+//! save(&session);
+//! session.regenerate("another answer", &ProviderKind::OpenAI, "gpt-4o", usage);
+//! save(&session);
+//! session.undo();
+//! save(&session);
+//! session.title = Some("Five questions".into());
+//! save(&session);
+//! let loaded = journal::read(&path).unwrap();
+//! assert_eq!((loaded.session, loaded.recovered), (session.clone(), false));
+//!
+//! // Cut the file anywhere: everything the last finished save wrote is still there.
+//! let bytes = fs::read(&path).unwrap();
+//! let cut = dir.join("cut.jsonl");
+//! let mut seed = 0x2545_f491_4f6c_dd1du64;
+//! let boundaries = saves.iter().map(|(len, _)| *len);
+//! let offsets = (0..300).map(|_| {
+//!     seed ^= seed << 13;
+//!     seed ^= seed >> 7;
+//!     seed ^= seed << 17;
+//!     (seed % bytes.len() as u64) as usize
+//! });
+//! for at in offsets.chain(boundaries) {
+//!     fs::write(&cut, &bytes[..at]).unwrap();
+//!     let recovered = journal::read(&cut);
+//!     let Some((len, saved)) = saves.iter().rev().find(|(len, _)| *len <= at) else {
+//!         // Cut inside the first save: at most an empty session.
+//!         assert!(recovered.map_or(true, |r| r.session.messages.is_empty()), "cut at {}", at);
+//!         continue;
+//!     };
+//!     let recovered = recovered.unwrap_or_else(|e| panic!("cut at {}: {:#}", at, e));
+//!     if *len == at {
+//!         assert_eq!(&recovered.session, saved, "cut at {}", at);
+//!     }
+//!     let messages = &recovered.session.messages;
+//!     assert!(messages.len() >= saved.messages.len(), "cut at {}", at);
+//!     for (got, want) in messages.iter().zip(&saved.messages) {
+//!         assert!(got.role == want.role && got.content.starts_with(&want.content), "cut at {}", at);
+//!     }
+//!     // No prompt is left without its reply.
+//!     assert!(messages.last().is_none_or(|m| m.role != aion_core::providers::Role::User));
+//! }
+//!
+//! // A torn last line is cut off before the next save, which then reads back whole.
+//! fs::write(&cut, &bytes[..saves[2].0 + 10]).unwrap();
+//! let mut resumed = journal::open(&cut).unwrap().unwrap();
+//! assert_eq!(resumed, saves[2].1);
+//! resumed.record("question 3 again", "answer", &ProviderKind::Ollama, "llama3", None);
+//! journal::append(&cut, &resumed).unwrap();
+//! assert_eq!(journal::read(&cut).unwrap().session, resumed);
+//!
+//! // A damaged line before the end is not skipped over.
+//! let mut damaged = bytes.clone();
+//! damaged[saves[0].0 + 2] = b'#';
+//! fs::write(dir.join("damaged.jsonl"), &damaged).unwrap();
+//! let err = journal::read(&dir.join("damaged.jsonl")).unwrap_err();
+//! assert!(format!("{:#}", err).contains("line 4"), "{:#}", err);
+//!
+//! // Another process writing the same session: refused, by name, until it lets go.
+//! let busy = dir.join("busy.jsonl");
+//! fs::copy(&path, &busy).unwrap();
+//! let other_process = fs::File::open(&busy).unwrap();
+//! other_process.lock().unwrap();
+//! let err = journal::open(&busy).unwrap_err();
+//! assert!(err.downcast_ref::<InUse>().is_some());
+//! assert_eq!(err.to_string(), format!("session {} is in use by another AION process", session.id));
+//! drop(other_process);
+//! assert_eq!(journal::open(&busy).unwrap(), Some(session));
+//! fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use crate::config::ProviderKind;
+use crate::providers::{Role, Usage};
+use crate::redact;
+use crate::sessions::{Session, SessionMessage};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// One line of a session journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Start {
+        id: String,
+        created_at: u64,
+    },
+    User {
+        at: u64,
+        content: String,
+    },
+    Reply {
+        at: u64,
+        content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<ProviderKind>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
+    /// Message `index` left the conversation (`/regen`, `/undo`).
+    Superseded {
+        at: u64,
+        index: usize,
+    },
+    /// `more` was added to message `index` (`/continue`), using `usage` on top of its own.
+    Extended {
+        at: u64,
+        index: usize,
+        more: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
+    Title {
+        title: Option<String>,
+    },
+    /// The whole session, replacing what came before.
+    Snapshot {
+        session: Session,
+    },
+}
+
+/// Another process is writing the session.
+#[derive(Debug, thiserror::Error)]
+#[error("session {id} is in use by another AION process")]
+pub struct InUse {
+    pub id: String,
+}
+
+/// A session read back from its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loaded {
+    pub session: Session,
+    /// A save cut off mid-write was dropped.
+    pub recovered: bool,
+    /// Bytes of the file that hold whole events; the rest is what was dropped.
+    pub valid_len: u64,
+}
+
+/// The session in the file at `path`: a journal, or a session file from before them
+/// (`.json`).
+pub fn read(path: &Path) -> Result<Loaded> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read session file: {}", path.display()))?;
+    if path.extension().is_some_and(|e| e == "json") {
+        let session = serde_json::from_slice(&bytes)
+            .with_context(|| format!("failed to parse session file: {}", path.display()))?;
+        return Ok(Loaded { session, recovered: false, valid_len: bytes.len() as u64 });
+    }
+    replay(&bytes).with_context(|| format!("failed to read session file: {}", path.display()))
+}
+
+fn replay(bytes: &[u8]) -> Result<Loaded> {
+    let mut session: Option<Session> = None;
+    let mut valid_len = 0;
+    let mut recovered = false;
+    // Where the line of the last live prompt starts, while it has no reply.
+    let mut unanswered = None;
+    let mut start = 0;
+    for (number, line) in bytes.split_inclusive(|b| *b == b'\n').enumerate() {
+        let end = start + line.len();
+        let text = std::str::from_utf8(line).ok().map(str::trim).filter(|t| !t.is_empty());
+        let applied = text.map(|t| {
+            serde_json::from_str::<Event>(t)
+                .map_err(anyhow::Error::from)
+                .and_then(|event| apply(&mut session, event))
+        });
+        match applied {
+            Some(Ok(prompt)) => {
+                unanswered = if prompt { Some(start) } else { unanswered.filter(|_| !is_reply(&session)) };
+                valid_len = end;
+            }
+            None if std::str::from_utf8(line).is_ok() => valid_len = end,
+            // The last line, cut off mid-write.
+            _ if end == bytes.len() && !line.ends_with(b"\n") => recovered = true,
+            Some(Err(e)) => return Err(e.context(format!("line {} is damaged", number + 1))),
+            None => bail!("line {} is damaged", number + 1),
+        }
+        start = end;
+    }
+    let Some(mut session) = session else { bail!("the file holds no session") };
+    if let Some(at) = unanswered {
+        session.messages.pop();
+        valid_len = at;
+        recovered = true;
+    }
+    Ok(Loaded { session, recovered, valid_len: valid_len as u64 })
+}
+
+/// Whether the last message is a live reply.
+fn is_reply(session: &Option<Session>) -> bool {
+    session.as_ref().and_then(|s| s.messages.last()).is_some_and(|m| m.role == Role::Assistant && !m.superseded)
+}
+
+/// Apply `event` to the session replayed so far; `true` when it was a prompt.
+fn apply(session: &mut Option<Session>, event: Event) -> Result<bool> {
+    match (event, session.as_mut()) {
+        (Event::Start { id, created_at }, None) => *session = Some(started(id, created_at)),
+        (Event::Snapshot { session: snapshot }, _) => *session = Some(snapshot),
+        (Event::Start { .. }, Some(_)) => bail!("a second start"),
+        (_, None) => bail!("an event before the start"),
+        (event, Some(s)) => return apply_to(s, event),
+    }
+    Ok(false)
+}
+
+/// The session a `start` line opens.
+fn started(id: String, created_at: u64) -> Session {
+    Session { id, created_at, updated_at: created_at, title: None, provider: None, messages: Vec::new() }
+}
+
+fn apply_to(s: &mut Session, event: Event) -> Result<bool> {
+    match event {
+        Event::User { at, content } => {
+            s.messages.push(SessionMessage { role: Role::User, content, at, model: None, usage: None, superseded: false });
+            s.updated_at = at;
+            return Ok(true);
+        }
+        Event::Reply { at, content, model, provider, usage } => {
+            s.messages.push(SessionMessage { role: Role::Assistant, content, at, model, usage, superseded: false });
+            if provider.is_some() {
+                s.provider = provider;
+            }
+            s.updated_at = at;
+        }
+        Event::Superseded { at, index } => {
+            message(s, index)?.superseded = true;
+            s.updated_at = at;
+        }
+        Event::Extended { at, index, more, usage } => {
+            let m = message(s, index)?;
+            m.content.push_str(&more);
+            if let Some(u) = usage {
+                let total = m.usage.get_or_insert_with(Usage::default);
+                total.input_tokens += u.input_tokens;
+                total.output_tokens += u.output_tokens;
+            }
+            s.updated_at = at;
+        }
+        Event::Title { title } => s.title = title,
+        Event::Start { .. } | Event::Snapshot { .. } => unreachable!("handled by apply"),
+    }
+    Ok(false)
+}
+
+fn message(s: &mut Session, index: usize) -> Result<&mut SessionMessage> {
+    let len = s.messages.len();
+    s.messages.get_mut(index).with_context(|| format!("no message {} (the session has {})", index, len))
+}
+
+/// The events that turn `before` (what the file holds; `None`: nothing yet) into `after`.
+pub fn changes(before: Option<&Session>, after: &Session) -> Vec<Event> {
+    let snapshot = || vec![Event::Snapshot { session: after.clone() }];
+    let Some(before) = before else {
+        let mut events = vec![Event::Start { id: after.id.clone(), created_at: after.created_at }];
+        match changes(Some(&started(after.id.clone(), after.created_at)), after).as_slice() {
+            [Event::Snapshot { .. }] => return snapshot(),
+            rest => events.extend_from_slice(rest),
+        }
+        return events;
+    };
+    if before.id != after.id || before.created_at != after.created_at || after.messages.len() < before.messages.len() {
+        return snapshot();
+    }
+
+    let mut events = Vec::new();
+    for (index, (old, new)) in before.messages.iter().zip(&after.messages).enumerate() {
+        if old == new {
+            continue;
+        }
+        let Some(more) = new.content.strip_prefix(old.content.as_str()) else { return snapshot() };
+        if new.role != old.role || new.at != old.at || new.model != old.model || (old.superseded && !new.superseded) {
+            return snapshot();
+        }
+        if !more.is_empty() || new.usage != old.usage {
+            let Some(usage) = added(old.usage, new.usage) else { return snapshot() };
+            events.push(Event::Extended { at: after.updated_at, index, more: more.to_string(), usage });
+        }
+        if new.superseded && !old.superseded {
+            events.push(Event::Superseded { at: after.updated_at, index });
+        }
+    }
+    let first_new = before.messages.len();
+    for (offset, m) in after.messages[first_new..].iter().enumerate() {
+        events.push(match m.role {
+            Role::Assistant => Event::Reply {
+                at: m.at,
+                content: m.content.clone(),
+                model: m.model.clone(),
+                provider: after.provider.clone(),
+                usage: m.usage,
+            },
+            _ => Event::User { at: m.at, content: m.content.clone() },
+        });
+        if m.superseded {
+            events.push(Event::Superseded { at: after.updated_at, index: first_new + offset });
+        }
+    }
+    if after.title != before.title {
+        events.push(Event::Title { title: after.title.clone() });
+    }
+
+    // Anything the events do not reproduce exactly (a system message, a provider
+    // changed on its own) is written whole.
+    let mut replayed = before.clone();
+    for event in &events {
+        if apply_to(&mut replayed, event.clone()).is_err() {
+            return snapshot();
+        }
+    }
+    match replayed == *after {
+        true => events,
+        false => snapshot(),
+    }
+}
+
+/// The usage `new` adds to `old`; `None` inside when neither has any, `None` outside when
+/// it went down.
+fn added(old: Option<Usage>, new: Option<Usage>) -> Option<Option<Usage>> {
+    match (old, new) {
+        (None, new) => Some(new),
+        (Some(_), None) => None,
+        (Some(a), Some(b)) if b.input_tokens >= a.input_tokens && b.output_tokens >= a.output_tokens => {
+            Some(Some(Usage { input_tokens: b.input_tokens - a.input_tokens, output_tokens: b.output_tokens - a.output_tokens }))
+        }
+        _ => None,
+    }
+}
+
+/* ---------------------------
+   Writing
+---------------------------- */
+
+/// A journal this process writes: its file, locked, and the session as the file has it.
+struct Writer {
+    file: File,
+    written: Option<Session>,
+}
+
+static OPEN: Mutex<Option<HashMap<PathBuf, Writer>>> = Mutex::new(None);
+
+/// Take the journal at `path` for this process (created when missing), cutting off a
+/// torn last line. What it holds; `None` when nothing yet. Held until the process exits.
+pub fn open(path: &Path) -> Result<Option<Session>> {
+    let mut open = OPEN.lock().unwrap_or_else(|e| e.into_inner());
+    let writers = open.get_or_insert_with(HashMap::new);
+    if let Some(writer) = writers.get(path) {
+        return Ok(writer.written.clone());
+    }
+    let writer = lock(path)?;
+    let written = writer.written.clone();
+    writers.insert(path.to_path_buf(), writer);
+    Ok(written)
+}
+
+fn lock(path: &Path) -> Result<Writer> {
+    let file = File::options()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .with_context(|| format!("failed to open session file: {}", path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let id = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            let id = read(path).map(|l| l.session.id).unwrap_or(id);
+            return Err(InUse { id }.into());
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(e).with_context(|| format!("failed to lock session file: {}", path.display()))
+        }
+    }
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len == 0 {
+        return Ok(Writer { file, written: None });
+    }
+    let loaded = read(path)?;
+    if loaded.valid_len < len {
+        file.set_len(loaded.valid_len)
+            .with_context(|| format!("failed to repair session file: {}", path.display()))?;
+    }
+    // A last line that is whole but for its newline gets one, so the next starts apart.
+    let mut file = file;
+    if fs::read(path).ok().and_then(|b| b.last().copied()) != Some(b'\n') {
+        file.write_all(b"\n").with_context(|| format!("failed to repair session file: {}", path.display()))?;
+    }
+    Ok(Writer { file, written: Some(loaded.session) })
+}
+
+/// Bring the journal at `path` up to `session`: append what changed since the last
+/// append, then sync. Takes the file first (`open`) when this process has not yet.
+pub fn append(path: &Path, session: &Session) -> Result<()> {
+    let mut open = OPEN.lock().unwrap_or_else(|e| e.into_inner());
+    let writers = open.get_or_insert_with(HashMap::new);
+    if !writers.contains_key(path) {
+        writers.insert(path.to_path_buf(), lock(path)?);
+    }
+    let writer = writers.get_mut(path).expect("inserted above");
+    let events = changes(writer.written.as_ref(), session);
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut text = String::new();
+    for event in &events {
+        let line = serde_json::to_string(event).context("failed to serialize session")?;
+        text.push_str(&redact::redact(&line));
+        text.push('\n');
+    }
+    writer
+        .file
+        .write_all(text.as_bytes())
+        .and_then(|()| writer.file.sync_data())
+        .with_context(|| format!("failed to write session file: {}", path.display()))?;
+    writer.written = Some(session.clone());
+    Ok(())
+}
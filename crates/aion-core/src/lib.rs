@@ -0,0 +1,57 @@
+//! AION core: configuration, localization, saved sessions, and provider clients.
+//!
+//! Notes:
+//! - Shared by the `aion` CLI/TUI and anything embedding AION; nothing here touches the
+//!   terminal.
+//! - The HTTP provider clients (`providers::build_client` and the per-backend modules)
+//!   sit behind the `providers` feature (on by default). Without it, `providers` only
+//!   holds the message types and the `ChatClient` trait, so the config and session types
+//!   can be used without pulling in an HTTP stack.
+//! - `http_log` (provider request/response logging) also needs `providers`.
+//! - The `clap` feature derives `clap::ValueEnum` for enums the CLI takes as arguments.
+//!
+//! ```
+//! use aion_core::config::AppConfig;
+//! use std::collections::HashMap;
+//!
+//! let cfg = AppConfig::new_default();
+//! assert!(cfg.validate().is_ok());
+//!
+//! let vars = HashMap::from([("model".to_string(), cfg.provider.model.clone())]);
+//! let line = aion_core::i18n::interpolate("Using {model}", &vars).unwrap();
+//! assert_eq!(line, format!("Using {}", cfg.provider.model));
+//! ```
+
+pub mod audit;
+pub mod backup;
+pub mod bench;
+pub mod cache;
+pub mod cancel;
+pub mod catalog;
+pub mod commands;
+pub mod config;
+pub mod conversation;
+pub mod costs;
+pub mod frames;
+pub mod gather;
+pub mod guard;
+pub mod history;
+#[cfg(feature = "providers")]
+pub mod http_log;
+pub mod i18n;
+pub mod journal;
+pub mod notify;
+pub mod paths;
+pub mod persistence;
+pub mod pipeline;
+pub mod project_context;
+pub mod providers;
+pub mod read_only;
+pub mod recent;
+pub mod redact;
+pub mod retrieval;
+pub mod sessions;
+pub mod shell_history;
+pub mod structured;
+pub mod workdir;
+pub mod wrap;
@@ -0,0 +1,242 @@
+//! Notices when a long request finishes (`ui.notify_after_secs`, `--notify`): a terminal
+//! bell and, from the CLI, a desktop notification.
+//!
+//! Notes:
+//! - Timed: provider requests (one-shot chats, the REPL, the chat view, `aion ask`) and
+//!   the command `aion exec` runs. A reply from the cache is not a request.
+//! - `Policy::due`: after at least `ui.notify_after_secs`, or every time with `--notify`.
+//!   Unset and without the flag, never.
+//! - The text is the locale's (`notify.*`) and goes through `redact`: a command line can
+//!   hold a token.
+//! - Every `Notifier` gets every notice; one that cannot show it drops it silently.
+//!   `Bell` rings on stderr when that is a terminal. The desktop one is the CLI's.
+//! - Process-wide like `http_log`: `force` for `--notify`, then `init` once the config is
+//!   loaded; `start` and `finish` do nothing before. The CLI adds its desktop backend
+//!   with `set_desktop`.
+//!
+//! ```
+//! use aion_core::config::UiConfig;
+//! use aion_core::notify::{Clock, NoOp, Notifications, Policy, Task};
+//! use std::sync::{Arc, Mutex};
+//! use std::time::{Duration, Instant};
+//!
+//! #[derive(Clone)]
+//! struct FakeClock(Arc<Mutex<Instant>>);
+//! impl Clock for FakeClock {
+//!     fn now(&self) -> Instant {
+//!         *self.0.lock().unwrap()
+//!     }
+//! }
+//! let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+//! let advance = |secs| *clock.0.lock().unwrap() += Duration::from_secs(secs);
+//!
+//! let ui = UiConfig { notify_after_secs: Some(60), ..Default::default() };
+//! let notes = Notifications::new(Policy::new(&ui, false), "en", vec![Box::new(NoOp)]).with_clock(clock.clone());
+//! let started = notes.start();
+//! advance(59);
+//! assert_eq!(notes.finish(started, Task::Reply { model: "llama3" }), None);
+//! advance(1);
+//! let notice = notes.finish(started, Task::Reply { model: "llama3" }).unwrap();
+//! assert_eq!(notice.body, "The reply from llama3 is ready (60s)");
+//!
+//! let notice = notes.finish(started, Task::Command { command: "curl https://x.test/?token=abcdef123456" }).unwrap();
+//! assert!(notice.body.contains("***REDACTED***") && !notice.body.contains("abcdef"));
+//!
+//! // Unset: never. --notify: every time.
+//! assert!(!Policy::new(&UiConfig::default(), false).due(Duration::from_secs(3600)));
+//! assert!(Policy::new(&UiConfig::default(), true).due(Duration::ZERO));
+//! ```
+
+use crate::config::{AppConfig, UiConfig};
+use crate::{i18n, redact};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// When a finished task is announced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Policy {
+    /// `ui.notify_after_secs`.
+    pub after: Option<Duration>,
+    /// `--notify`: whatever the duration.
+    pub forced: bool,
+}
+
+impl Policy {
+    pub fn new(ui: &UiConfig, forced: bool) -> Self {
+        Self { after: ui.notify_after_secs.map(Duration::from_secs), forced }
+    }
+
+    /// Whether a task that took `elapsed` is announced.
+    pub fn due(&self, elapsed: Duration) -> bool {
+        self.forced || self.after.is_some_and(|after| elapsed >= after)
+    }
+
+    fn is_off(&self) -> bool {
+        !self.forced && self.after.is_none()
+    }
+}
+
+/// Where the time comes from; tests use their own.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// What a backend shows, in the user's language and redacted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice {
+    pub title: String,
+    pub body: String,
+}
+
+/// A way of telling the user.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, notice: &Notice);
+}
+
+/// Tells no one.
+pub struct NoOp;
+
+impl Notifier for NoOp {
+    fn notify(&self, _: &Notice) {}
+}
+
+/// The terminal bell, on stderr so piped output stays clean.
+pub struct Bell;
+
+impl Notifier for Bell {
+    fn notify(&self, _: &Notice) {
+        let mut err = std::io::stderr();
+        if err.is_terminal() {
+            let _ = err.write_all(b"\x07");
+            let _ = err.flush();
+        }
+    }
+}
+
+/// What finished.
+#[derive(Debug, Clone, Copy)]
+pub enum Task<'a> {
+    Reply { model: &'a str },
+    Command { command: &'a str },
+}
+
+/// When a timed task started (`Notifications::start`).
+#[derive(Debug, Clone, Copy)]
+pub struct Started(Instant);
+
+pub struct Notifications {
+    policy: Policy,
+    lang: String,
+    clock: Box<dyn Clock>,
+    backends: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifications {
+    /// Notices in `lang` through `backends`, timed by the system clock.
+    pub fn new(policy: Policy, lang: &str, backends: Vec<Box<dyn Notifier>>) -> Self {
+        Self { policy, lang: lang.to_string(), clock: Box::new(SystemClock), backends }
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    pub fn start(&self) -> Started {
+        Started(self.clock.now())
+    }
+
+    /// Announce `task` if it took long enough; the notice, when there was one.
+    pub fn finish(&self, started: Started, task: Task<'_>) -> Option<Notice> {
+        let elapsed = self.clock.now().saturating_duration_since(started.0);
+        if !self.policy.due(elapsed) {
+            return None;
+        }
+        let notice = self.notice(task, elapsed);
+        for backend in &self.backends {
+            backend.notify(&notice);
+        }
+        Some(notice)
+    }
+
+    fn notice(&self, task: Task<'_>, elapsed: Duration) -> Notice {
+        let lang = self.lang.as_str();
+        let seconds = i18n::format::localize_number(lang, &elapsed.as_secs().to_string());
+        let body = match task {
+            Task::Reply { model } => i18n::text(
+                lang,
+                "notify.reply",
+                "The reply from {model} is ready ({seconds}s)",
+                &[("model", model.to_string()), ("seconds", seconds)],
+            ),
+            Task::Command { command } => i18n::text(
+                lang,
+                "notify.command",
+                "{command} finished ({seconds}s)",
+                &[("command", command.to_string()), ("seconds", seconds)],
+            ),
+        };
+        Notice { title: redact::redact(&i18n::text(lang, "notify.title", "AION", &[])), body: redact::redact(&body) }
+    }
+}
+
+static GLOBAL: RwLock<Option<Arc<Notifications>>> = RwLock::new(None);
+static FORCED: AtomicBool = AtomicBool::new(false);
+static DESKTOP: Mutex<Option<fn(&Notice)>> = Mutex::new(None);
+
+/// Announce every timed task for the rest of the process, however short (`--notify`).
+pub fn force() {
+    FORCED.store(true, Ordering::Relaxed);
+}
+
+/// Show notices with `show` too, from the next `init` on (the CLI's desktop
+/// notifications); `None` stops. Returns the one replaced.
+pub fn set_desktop(show: Option<fn(&Notice)>) -> Option<fn(&Notice)> {
+    std::mem::replace(&mut *DESKTOP.lock().unwrap_or_else(|e| e.into_inner()), show)
+}
+
+/// Announce tasks according to `cfg` (and `force`), in its language.
+pub fn init(cfg: &AppConfig) {
+    let mut backends: Vec<Box<dyn Notifier>> = vec![Box::new(Bell)];
+    if let Some(show) = *DESKTOP.lock().unwrap_or_else(|e| e.into_inner()) {
+        backends.push(Box::new(Hook(show)));
+    }
+    let policy = Policy::new(&cfg.ui, FORCED.load(Ordering::Relaxed));
+    let notes = Notifications::new(policy, &cfg.language, backends);
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(notes));
+}
+
+/// A backend set with `set_desktop`.
+struct Hook(fn(&Notice));
+
+impl Notifier for Hook {
+    fn notify(&self, notice: &Notice) {
+        (self.0)(notice)
+    }
+}
+
+fn global() -> Option<Arc<Notifications>> {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Start timing a task; `None` when nothing would be announced.
+pub fn start() -> Option<Started> {
+    global().filter(|notes| !notes.policy.is_off()).map(|notes| notes.start())
+}
+
+/// The task timed since `started` is done (see `Notifications::finish`).
+pub fn finish(started: Option<Started>, task: Task<'_>) {
+    if let (Some(notes), Some(started)) = (global(), started) {
+        notes.finish(started, task);
+    }
+}
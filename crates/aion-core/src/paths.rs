@@ -0,0 +1,267 @@
+//! Where AION keeps its files: config (config.toml, templates, locales), state (sessions,
+//! the audit and HTTP logs, recent models, input history, update checks, the usage
+//! ledger), and cache
+//! (cached replies, provider health, the model catalog).
+//!
+//! Notes:
+//! - Config is `dirs::config_dir()/aion` (`~/.config/aion` on Linux). State is
+//!   `AION_STATE_DIR`, else `dirs::state_dir()/aion` (`~/.local/state/aion`), else the
+//!   platform's local data dir where it has no state dir (macOS, Windows). Cache is
+//!   `AION_CACHE_DIR`, else `dirs::cache_dir()/aion`. Both fall back to the config dir on
+//!   a platform with neither. `Roots::resolve` is the rule.
+//! - Every store asks here for its directory; nothing else calls `dirs` for AION's own
+//!   files. `cache.dir` in the config still moves the cached replies on their own.
+//! - Earlier versions kept everything in the config dir. `migrate` moves what it finds
+//!   there (`MOVED`) to its new place, once: after that there is nothing left to move. A
+//!   file already at the new place is never replaced; the old one is kept and reported.
+//! - No portable mode exists yet: `AION_STATE_DIR` and `AION_CACHE_DIR` are how to keep
+//!   state and cache next to the config, or anywhere else.
+//!
+//! ```
+//! use aion_core::paths::{migrate, Platform, Roots};
+//! use std::path::PathBuf;
+//!
+//! let linux = Platform {
+//!     config: Some("/home/me/.config".into()),
+//!     state: Some("/home/me/.local/state".into()),
+//!     data_local: Some("/home/me/.local/share".into()),
+//!     cache: Some("/home/me/.cache".into()),
+//! };
+//! let no_env = |_: &str| None;
+//! let roots = Roots::resolve(&linux, no_env).unwrap();
+//! assert_eq!(roots.config, PathBuf::from("/home/me/.config/aion"));
+//! assert_eq!(roots.state, PathBuf::from("/home/me/.local/state/aion"));
+//! assert_eq!(roots.cache, PathBuf::from("/home/me/.cache/aion"));
+//!
+//! // The variables win; an empty one counts as unset.
+//! let env = |name: &str| match name {
+//!     "AION_STATE_DIR" => Some("/srv/aion/state".into()),
+//!     "AION_CACHE_DIR" => Some("".into()),
+//!     _ => None,
+//! };
+//! let roots = Roots::resolve(&linux, env).unwrap();
+//! assert_eq!(roots.state, PathBuf::from("/srv/aion/state"));
+//! assert_eq!(roots.cache, PathBuf::from("/home/me/.cache/aion"));
+//!
+//! // No state dir (macOS, Windows): local data. Neither: the config dir.
+//! let macos = Platform { state: None, data_local: Some("/Users/me/Library/Application Support".into()), ..linux.clone() };
+//! assert_eq!(Roots::resolve(&macos, no_env).unwrap().state, PathBuf::from("/Users/me/Library/Application Support/aion"));
+//! let bare = Platform { config: linux.config.clone(), ..Platform::default() };
+//! let roots = Roots::resolve(&bare, no_env).unwrap();
+//! assert_eq!((&roots.state, &roots.cache), (&roots.config, &roots.config));
+//! assert!(Roots::resolve(&Platform::default(), no_env).is_err());
+//!
+//! // Files from before the split move once; one already at the new place stays put.
+//! let home = std::env::temp_dir().join(format!("aion-paths-{}", std::process::id()));
+//! let roots = Roots { config: home.join("config"), state: home.join("state"), cache: home.join("cache") };
+//! std::fs::create_dir_all(roots.config.join("sessions")).unwrap();
+//! std::fs::create_dir_all(roots.config.join("cache")).unwrap();
+//! std::fs::create_dir_all(&roots.state).unwrap();
+//! std::fs::write(roots.config.join("config.toml"), "").unwrap();
+//! std::fs::write(roots.config.join("sessions/abc.jsonl"), "{}\n").unwrap();
+//! std::fs::write(roots.config.join("cache/key.json"), "{}").unwrap();
+//! std::fs::write(roots.config.join("audit.log"), "old\n").unwrap();
+//! std::fs::write(roots.state.join("audit.log"), "new\n").unwrap();
+//!
+//! let first = migrate(&roots).unwrap();
+//! let moved: Vec<_> = first.moved.iter().map(|m| m.to.strip_prefix(&home).unwrap().to_path_buf()).collect();
+//! assert_eq!(moved, [PathBuf::from("state/sessions"), PathBuf::from("cache/responses")]);
+//! assert_eq!(first.kept, [roots.config.join("audit.log")]);
+//! assert!(roots.state.join("sessions/abc.jsonl").is_file() && !roots.config.join("sessions").exists());
+//! assert_eq!(std::fs::read_to_string(roots.state.join("audit.log")).unwrap(), "new\n");
+//! assert!(roots.config.join("config.toml").is_file());
+//!
+//! let second = migrate(&roots).unwrap();
+//! assert!(second.moved.is_empty());
+//! std::fs::remove_dir_all(&home).unwrap();
+//! ```
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const APP_DIR_NAME: &str = "aion";
+pub const STATE_DIR_VAR: &str = "AION_STATE_DIR";
+pub const CACHE_DIR_VAR: &str = "AION_CACHE_DIR";
+
+/// Which of the roots a file belongs under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Root {
+    State,
+    Cache,
+}
+
+/// What earlier versions kept in the config dir, where it belongs now, and its name there.
+pub const MOVED: &[(&str, Root, &str)] = &[
+    ("sessions", Root::State, "sessions"),
+    ("audit.log", Root::State, "audit.log"),
+    ("http.log", Root::State, "http.log"),
+    ("http.log.1", Root::State, "http.log.1"),
+    ("recent_models.json", Root::State, "recent_models.json"),
+    ("update_check.json", Root::State, "update_check.json"),
+    ("cache", Root::Cache, "responses"),
+    ("provider_health.json", Root::Cache, "provider_health.json"),
+    ("model_catalog.json", Root::Cache, "model_catalog.json"),
+];
+
+/// The platform's base directories, as `dirs` reports them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Platform {
+    pub config: Option<PathBuf>,
+    pub state: Option<PathBuf>,
+    pub data_local: Option<PathBuf>,
+    pub cache: Option<PathBuf>,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        Self {
+            config: dirs::config_dir(),
+            state: dirs::state_dir(),
+            data_local: dirs::data_local_dir(),
+            cache: dirs::cache_dir(),
+        }
+    }
+}
+
+/// AION's three directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roots {
+    pub config: PathBuf,
+    pub state: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl Roots {
+    /// The roots for `platform`, with `var` reading the environment (see the module notes).
+    pub fn resolve(platform: &Platform, var: impl Fn(&str) -> Option<OsString>) -> Result<Self> {
+        let Some(base) = &platform.config else { bail!("failed to locate system config directory") };
+        let config = base.join(APP_DIR_NAME);
+        let set = |name: &str| var(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+        let app = |dir: &Option<PathBuf>| dir.as_ref().map(|d| d.join(APP_DIR_NAME));
+        let state = set(STATE_DIR_VAR)
+            .or_else(|| app(&platform.state))
+            .or_else(|| app(&platform.data_local))
+            .unwrap_or_else(|| config.clone());
+        let cache = set(CACHE_DIR_VAR).or_else(|| app(&platform.cache)).unwrap_or_else(|| config.clone());
+        Ok(Self { config, state, cache })
+    }
+
+    /// This process's.
+    pub fn current() -> Result<Self> {
+        Self::resolve(&Platform::current(), |name| std::env::var_os(name))
+    }
+
+    pub fn dir(&self, root: Root) -> &Path {
+        match root {
+            Root::State => &self.state,
+            Root::Cache => &self.cache,
+        }
+    }
+}
+
+/// Config: config.toml (unless `--config` says otherwise), templates, locales.
+pub fn config_dir() -> Result<PathBuf> {
+    Ok(Roots::current()?.config)
+}
+
+/// State: sessions, the audit and HTTP logs, recent models, update checks, the usage ledger.
+pub fn state_dir() -> Result<PathBuf> {
+    Ok(Roots::current()?.state)
+}
+
+/// Cache: cached replies, provider health, the model catalog.
+pub fn cache_dir() -> Result<PathBuf> {
+    Ok(Roots::current()?.cache)
+}
+
+/// Create `dir` (and its parents) when missing.
+pub fn ensure(dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create directory: {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/* ---------------------------
+   Migration
+---------------------------- */
+
+/// One file or directory `migrate` moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moved {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// What `migrate` did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Migration {
+    pub moved: Vec<Moved>,
+    /// Old files left where they were, since the new place already has one.
+    pub kept: Vec<PathBuf>,
+}
+
+/// Move what earlier versions kept in `roots.config` to the state and cache roots
+/// (`MOVED`). A directory whose new place exists already is merged entry by entry.
+pub fn migrate(roots: &Roots) -> Result<Migration> {
+    let mut migration = Migration::default();
+    for (old, root, new) in MOVED {
+        let from = roots.config.join(old);
+        let to = roots.dir(*root).join(new);
+        if from == to || fs::symlink_metadata(&from).is_err() {
+            continue;
+        }
+        move_into(&from, &to, &mut migration)?;
+    }
+    Ok(migration)
+}
+
+fn move_into(from: &Path, to: &Path, migration: &mut Migration) -> Result<()> {
+    if fs::symlink_metadata(to).is_err() {
+        if let Some(parent) = to.parent() {
+            ensure(parent)?;
+        }
+        move_path(from, to)?;
+        migration.moved.push(Moved { from: from.to_path_buf(), to: to.to_path_buf() });
+        return Ok(());
+    }
+    if !(from.is_dir() && to.is_dir()) {
+        migration.kept.push(from.to_path_buf());
+        return Ok(());
+    }
+    for entry in fs::read_dir(from).with_context(|| format!("failed to read {}", from.display()))? {
+        let name = entry?.file_name();
+        move_into(&from.join(&name), &to.join(&name), migration)?;
+    }
+    // Gone unless something was kept in it.
+    let _ = fs::remove_dir(from);
+    Ok(())
+}
+
+/// Rename `from` to `to`; across file systems, copy and then remove.
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_all(from, to).with_context(|| format!("failed to move {} to {}", from.display(), to.display()))?;
+    match from.is_dir() {
+        true => fs::remove_dir_all(from),
+        false => fs::remove_file(from),
+    }
+    .with_context(|| format!("failed to remove {} after copying it", from.display()))
+}
+
+fn copy_all(from: &Path, to: &Path) -> Result<()> {
+    if !from.is_dir() {
+        fs::copy(from, to)?;
+        return Ok(());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let name = entry?.file_name();
+        copy_all(&from.join(&name), &to.join(&name))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! What a chat leaves on disk on its own: session files, cached replies, provider health,
+//! and the audit and HTTP logs.
+//!
+//! Notes:
+//! - Like `redact`, the policy is process-wide: `init` once the config and flags are
+//!   known, then every store asks `policy()` before it writes. Before `init` everything
+//!   is kept.
+//! - Ephemeral (`aion chat --ephemeral`, or `history.default_ephemeral`): none of the
+//!   above is written, and the config dir is not created. Cached replies and provider
+//!   health are kept in memory for the process, as without `caps.write_files`.
+//! - Writes the user asks for by name still happen (`/config save`, `/save <file>`,
+//!   `sessions export --out`); only the automatic ones stop.
+//! - `history.retention_days` is applied by `sessions::prune`, not here.
+//!
+//! ```
+//! use aion_core::cache::{self, ResponseCache};
+//! use aion_core::config::{AppConfig, ProviderKind};
+//! use aion_core::paths::config_dir;
+//! use aion_core::persistence::{self, PersistencePolicy};
+//! use aion_core::providers::{ChatResponse, Message};
+//! use aion_core::sessions::Session;
+//!
+//! let home = std::env::temp_dir().join(format!("aion-ephemeral-{}", std::process::id()));
+//! std::env::set_var("XDG_CONFIG_HOME", &home);
+//! std::env::set_var("HOME", &home);
+//!
+//! let mut cfg = AppConfig::new_default();
+//! cfg.caps.write_files = true;
+//! cfg.cache.enabled = true;
+//! cfg.history.default_ephemeral = true;
+//! let policy = PersistencePolicy::new(&cfg, false);
+//! assert!(policy.ephemeral);
+//! persistence::init(policy);
+//!
+//! let mut session = Session::new();
+//! session.record("hi", "hello", &ProviderKind::Ollama, "llama3", None);
+//! session.save().unwrap();
+//!
+//! let cache = ResponseCache::open(&cfg).unwrap().unwrap();
+//! let key = cache::key(&cfg.provider, &[Message::user("hi")]);
+//! let reply = ChatResponse { content: "hello".into(), ..Default::default() };
+//! cache.put(&key, &reply).unwrap();
+//! assert_eq!(cache.get(&key).unwrap().content, "hello");
+//!
+//! // Nothing was written: not even the config dir exists.
+//! assert!(!config_dir().unwrap().exists());
+//! assert!(!home.exists());
+//! ```
+
+use crate::config::AppConfig;
+use std::sync::RwLock;
+
+/// What this process may write without being asked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PersistencePolicy {
+    /// Keep sessions, cached replies, provider health, and logs in memory only.
+    pub ephemeral: bool,
+}
+
+impl PersistencePolicy {
+    /// `--ephemeral` (`flag`) or `history.default_ephemeral`.
+    pub fn new(cfg: &AppConfig, flag: bool) -> Self {
+        Self { ephemeral: flag || cfg.history.default_ephemeral }
+    }
+
+    /// Whether session files, cache entries, provider health, and the logs may be written.
+    pub fn keeps_history(&self) -> bool {
+        !self.ephemeral
+    }
+}
+
+static GLOBAL: RwLock<PersistencePolicy> = RwLock::new(PersistencePolicy { ephemeral: false });
+
+/// Use `policy` for the rest of the process (or until the next `init`).
+pub fn init(policy: PersistencePolicy) {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+pub fn policy() -> PersistencePolicy {
+    *GLOBAL.read().unwrap_or_else(|e| e.into_inner())
+}
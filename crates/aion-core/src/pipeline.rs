@@ -0,0 +1,252 @@
+//! Questions about a document too long for one request (`aion ask`): split it into
+//! overlapping chunks, ask each one, then ask once more over the partial answers.
+//!
+//! Notes:
+//! - `split` cuts windows of at most `Budget::chunk_chars` characters, each starting
+//!   `overlap_chars` before the previous one ended, so a passage cut at one boundary is
+//!   whole in the next chunk. A window ends after the last line break (else space) in
+//!   its final quarter when there is one, so chunks rarely end mid-word.
+//! - A document that fits in one chunk is asked about directly: one request.
+//! - A chunk with nothing on the question is asked to reply `NOTHING_RELEVANT`; it does
+//!   not contribute and is left out of the synthesis. When no chunk contributes, that
+//!   is the answer, without another request.
+//! - Only the final request (the synthesis, or the single one) streams through
+//!   `on_chunk`; partial answers are collected silently and `on_part` reports progress.
+//! - The prompts are English; the system prompt (reply language, style) is the caller's.
+//!
+//! ```
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::pipeline::{ask, split, Budget, Job};
+//! use aion_core::providers::mock::ScriptedClient;
+//!
+//! // A synthetic document: numbered sentences, about 6,000 characters.
+//! let doc: String = (0..200).map(|i| format!("Sentence {:03} is here.\n", i)).collect();
+//! let budget = Budget { chunk_chars: 1_000, overlap_chars: 100 };
+//! let chunks = split(&doc, budget);
+//!
+//! // Every character is in a chunk, chunks overlap, and none is over budget.
+//! assert_eq!(chunks.first().unwrap().start, 0);
+//! assert_eq!(chunks.last().unwrap().end, doc.chars().count());
+//! for pair in chunks.windows(2) {
+//!     assert!(pair[1].start < pair[0].end && pair[1].start > pair[0].start);
+//! }
+//! assert!(chunks.iter().all(|c| c.end - c.start <= 1_000 && c.text.ends_with('\n')));
+//! assert_eq!(split("short", budget).len(), 1);
+//! assert!(split("", budget).is_empty());
+//!
+//! // One reply per chunk, then the synthesis; the second chunk has nothing relevant.
+//! let mut steps: Vec<String> = (0..chunks.len()).map(|i| format!(r#"{{ "content": "from {}" }}"#, i)).collect();
+//! steps[1] = r#"{ "content": "NOTHING_RELEVANT." }"#.to_string();
+//! steps.push(r#"{ "chunks": ["All ", "parts"] }"#.to_string());
+//! let script = std::env::temp_dir().join(format!("aion-pipeline-{}.json", std::process::id()));
+//! std::fs::write(&script, format!(r#"{{ "steps": [{}] }}"#, steps.join(","))).unwrap();
+//! let client = ScriptedClient::load(&script).unwrap();
+//!
+//! let job = Job { name: "doc.txt", document: &doc, question: "What is here?", system: None, budget };
+//! let (mut parts, mut streamed) = (0, String::new());
+//! let outcome = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(ask(
+//!     &client,
+//!     &job,
+//!     &CancelToken::new(),
+//!     &mut |_, _| parts += 1,
+//!     &mut |piece| streamed.push_str(piece),
+//! ));
+//! let outcome = outcome.unwrap();
+//! std::fs::remove_file(&script).unwrap();
+//!
+//! assert_eq!((outcome.content.as_str(), streamed.as_str()), ("All parts", "All parts"));
+//! assert_eq!(parts, chunks.len());
+//! assert_eq!(outcome.chunks.len(), chunks.len());
+//! assert!(!outcome.chunks[1].contributed && outcome.chunks[0].contributed);
+//! assert_eq!(outcome.requests, chunks.len() + 1);
+//! ```
+
+use crate::cancel::CancelToken;
+use crate::providers::{ChatClient, ChatResponse, Fallback, Message, OnChunk, Usage};
+use anyhow::Result;
+use serde::Serialize;
+
+/// The reply that marks a chunk as having nothing on the question.
+pub const NOTHING_RELEVANT: &str = "NOTHING_RELEVANT";
+
+/// How much of the document goes into one request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub chunk_chars: usize,
+    /// Characters each chunk repeats from the end of the one before; at most half a chunk.
+    pub overlap_chars: usize,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self { chunk_chars: 12_000, overlap_chars: 400 }
+    }
+}
+
+/// A window of the document. `start` / `end` count characters, `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Split `text` into overlapping windows as `budget` allows (see the module notes).
+pub fn split(text: &str, budget: Budget) -> Vec<Chunk> {
+    let chars: Vec<char> = text.chars().collect();
+    let size = budget.chunk_chars.max(1);
+    let overlap = budget.overlap_chars.min(size / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            let window = &chars[start + size * 3 / 4..end];
+            let cut = window.iter().rposition(|&c| c == '\n').or_else(|| window.iter().rposition(|c| c.is_whitespace()));
+            if let Some(at) = cut {
+                end = start + size * 3 / 4 + at + 1;
+            }
+        }
+        chunks.push(Chunk { index: chunks.len(), start, end, text: chars[start..end].iter().collect() });
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    chunks
+}
+
+/// A question about a document.
+#[derive(Debug, Clone, Copy)]
+pub struct Job<'a> {
+    /// How the prompts refer to the document, e.g. its path.
+    pub name: &'a str,
+    pub document: &'a str,
+    pub question: &'a str,
+    pub system: Option<&'a str>,
+    pub budget: Budget,
+}
+
+/// What one chunk gave.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChunkAnswer {
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    /// Whether the answer went into the synthesis.
+    pub contributed: bool,
+    pub answer: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Outcome {
+    pub content: String,
+    /// Empty when the document fit in one request.
+    pub chunks: Vec<ChunkAnswer>,
+    /// Summed over the requests that reported it.
+    pub usage: Option<Usage>,
+    pub requests: usize,
+    /// The fallback that answered the final request, if one did.
+    pub fallback: Option<Fallback>,
+}
+
+/// Answer `job` with `client`. `on_part(done, total)` is called after each chunk's
+/// request; `on_chunk` receives the final answer as it streams.
+pub async fn ask(
+    client: &dyn ChatClient,
+    job: &Job<'_>,
+    cancel: &CancelToken,
+    on_part: &mut (dyn FnMut(usize, usize) + Send),
+    on_chunk: &mut OnChunk<'_>,
+) -> Result<Outcome> {
+    let chunks = split(job.document, job.budget);
+    let mut outcome = Outcome::default();
+    if chunks.len() <= 1 {
+        let prompt = format!("{}\n\nThe document {}:\n\n{}", job.question, job.name, fenced(job.document));
+        let resp = client.chat(&messages(job, prompt), cancel, on_chunk).await?;
+        outcome.record(&resp);
+        outcome.content = resp.content;
+        outcome.fallback = resp.fallback;
+        return Ok(outcome);
+    }
+
+    for chunk in &chunks {
+        let prompt = format!(
+            "Below is part {} of {} of the document {} (characters {}-{}). Answer the question using only \
+             this part. If it has nothing relevant to the question, reply with exactly {}.\n\n\
+             Question: {}\n\n{}",
+            chunk.index + 1,
+            chunks.len(),
+            job.name,
+            chunk.start,
+            chunk.end,
+            NOTHING_RELEVANT,
+            job.question,
+            fenced(&chunk.text)
+        );
+        let resp = client.chat(&messages(job, prompt), cancel, &mut |_| {}).await?;
+        outcome.record(&resp);
+        let answer = resp.content.trim().to_string();
+        outcome.chunks.push(ChunkAnswer {
+            index: chunk.index,
+            start: chunk.start,
+            end: chunk.end,
+            contributed: !is_nothing(&answer),
+            answer,
+        });
+        on_part(chunk.index + 1, chunks.len());
+    }
+
+    let partial: Vec<String> = outcome
+        .chunks
+        .iter()
+        .filter(|c| c.contributed)
+        .map(|c| format!("From part {}:\n{}", c.index + 1, c.answer))
+        .collect();
+    if partial.is_empty() {
+        outcome.content = format!("The document {} has nothing relevant to the question.", job.name);
+        on_chunk(&outcome.content);
+        return Ok(outcome);
+    }
+    let prompt = format!(
+        "These are answers to the question below, each drawn from one part of the document {}. Combine \
+         them into one answer to the question: merge points made more than once, keep the specifics, and \
+         say where the parts disagree. Do not mention the parts.\n\n\
+         Question: {}\n\n{}",
+        job.name,
+        job.question,
+        partial.join("\n\n")
+    );
+    let resp = client.chat(&messages(job, prompt), cancel, on_chunk).await?;
+    outcome.record(&resp);
+    outcome.content = resp.content;
+    outcome.fallback = resp.fallback;
+    Ok(outcome)
+}
+
+impl Outcome {
+    fn record(&mut self, resp: &ChatResponse) {
+        self.requests += 1;
+        if let Some(u) = resp.usage {
+            let total = self.usage.get_or_insert_with(Usage::default);
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+        }
+    }
+}
+
+fn messages(job: &Job<'_>, prompt: String) -> Vec<Message> {
+    job.system.map(Message::system).into_iter().chain([Message::user(prompt)]).collect()
+}
+
+fn is_nothing(answer: &str) -> bool {
+    answer.trim_matches(|c: char| c == '.' || c == '`' || c.is_whitespace()).eq_ignore_ascii_case(NOTHING_RELEVANT)
+}
+
+/// `text` in a Markdown fence longer than any fence inside it.
+fn fenced(text: &str) -> String {
+    let longest = text.lines().map(|l| l.trim_start().chars().take_while(|&c| c == '`').count()).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}\n{}\n{}", fence, text.trim_end_matches('\n'), fence)
+}
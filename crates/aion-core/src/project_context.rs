@@ -0,0 +1,128 @@
+//! The project context file (`.aion/context.md` by default): standing notes about a
+//! project that chats started inside it send with the system prompt.
+//!
+//! Notes:
+//! - `find_upward` looks in the starting directory, then each parent up to the root; the
+//!   nearest file wins, so a subdirectory of a project finds the project's file. An
+//!   absolute `context.file` is used as is.
+//! - Reading goes through `guard` (`caps.read_files` and the read path lists). A file the
+//!   caps refuse is an error for the caller to report; no file is `Ok(None)`.
+//! - A file over `context.max_bytes` is cut at the last line break before the cap (else at
+//!   a character boundary) and only that much is read. `prompt` then ends with a notice,
+//!   so the model knows it has part of the file.
+//! - Invalid UTF-8 is replaced rather than refused; the file is the user's own.
+//!
+//! ```
+//! use aion_core::config::{AppConfig, Capabilities, ContextConfig};
+//! use aion_core::project_context::{find_upward, load};
+//! use std::fs;
+//! use std::path::Path;
+//!
+//! let root = std::env::temp_dir().join(format!("aion-context-{}", std::process::id()));
+//! let nested = root.join("src/deep/er");
+//! fs::create_dir_all(root.join(".aion")).unwrap();
+//! fs::create_dir_all(&nested).unwrap();
+//! fs::write(root.join(".aion/context.md"), "Rust workspace.\nTests are doctests.\n").unwrap();
+//! let settings = ContextConfig::default();
+//! let caps = Capabilities { read_files: true, ..AppConfig::new_default().caps };
+//!
+//! // Found from a nested subdirectory.
+//! let found = find_upward(&nested, Path::new(".aion/context.md")).unwrap();
+//! assert_eq!(found, root.join(".aion/context.md"));
+//! let context = load(&settings, &caps, &nested).unwrap().unwrap();
+//! assert_eq!(context.text, "Rust workspace.\nTests are doctests.\n");
+//! assert!(!context.truncated && context.prompt().contains("Tests are doctests."));
+//!
+//! // Cut at the last line break under the cap, with a notice.
+//! let small = ContextConfig { max_bytes: 20, ..ContextConfig::default() };
+//! let cut = load(&small, &caps, &nested).unwrap().unwrap();
+//! assert_eq!((cut.text.as_str(), cut.truncated, cut.size), ("Rust workspace.\n", true, 36));
+//! assert!(cut.prompt().contains("first 16 of 36 bytes"));
+//!
+//! // Refused without caps.read_files, or when the read lists deny it.
+//! let closed = Capabilities { read_files: false, ..caps.clone() };
+//! assert!(load(&settings, &closed, &nested).is_err());
+//! let denied = Capabilities { read_deny: vec![".aion".into()], ..caps.clone() };
+//! assert!(load(&settings, &denied, &nested).is_err());
+//!
+//! // Switched off, or nothing there.
+//! let off = ContextConfig { enabled: false, ..ContextConfig::default() };
+//! assert!(load(&off, &caps, &nested).unwrap().is_none());
+//! fs::remove_dir_all(&root).unwrap();
+//! assert!(load(&settings, &caps, &nested).unwrap().is_none());
+//! ```
+
+use crate::config::{Capabilities, ContextConfig};
+use crate::guard::Guard;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectContext {
+    pub path: PathBuf,
+    /// What is sent: the whole file, or its start when `truncated`.
+    pub text: String,
+    /// The file's size in bytes.
+    pub size: u64,
+    pub truncated: bool,
+}
+
+impl ProjectContext {
+    /// The system prompt section: the file's text under a heading naming it.
+    pub fn prompt(&self) -> String {
+        let mut out = format!(
+            "Project context from {} (notes the user keeps for this project):\n\n{}",
+            self.path.display(),
+            self.text.trim_end()
+        );
+        if self.truncated {
+            out.push_str(&format!(
+                "\n\n[Truncated: this is the first {} of {} bytes of the file.]",
+                self.text.len(),
+                self.size
+            ));
+        }
+        out
+    }
+}
+
+/// `name` in `start` or the nearest of its parents that has it.
+pub fn find_upward(start: &Path, name: &Path) -> Option<PathBuf> {
+    start.ancestors().map(|dir| dir.join(name)).find(|path| path.is_file())
+}
+
+/// The context file for a chat started in `cwd`, if there is one and it is switched on.
+pub fn discover(settings: &ContextConfig, cwd: &Path) -> Option<PathBuf> {
+    if !settings.enabled {
+        return None;
+    }
+    match settings.file.is_absolute() {
+        true => Some(settings.file.clone()).filter(|path| path.is_file()),
+        false => find_upward(cwd, &settings.file),
+    }
+}
+
+/// The context file for `cwd`, read as `caps` allow and cut to `settings.max_bytes`.
+pub fn load(settings: &ContextConfig, caps: &Capabilities, cwd: &Path) -> Result<Option<ProjectContext>> {
+    let Some(path) = discover(settings, cwd) else {
+        return Ok(None);
+    };
+    let resolved = Guard::new(caps).check_read(&path)?;
+    let read_error = || format!("failed to read {}", path.display());
+    let file = File::open(&resolved).with_context(read_error)?;
+    let size = file.metadata().with_context(read_error)?.len();
+    let mut bytes = Vec::new();
+    file.take(settings.max_bytes).read_to_end(&mut bytes).with_context(read_error)?;
+
+    let truncated = size > settings.max_bytes;
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    if truncated {
+        // A cut inside a character shows up as a replacement character at the end.
+        let whole = text.trim_end_matches('\u{FFFD}').len();
+        let end = text[..whole].rfind('\n').map_or(whole, |i| i + 1);
+        text.truncate(end);
+    }
+    Ok(Some(ProjectContext { path, text, size, truncated }))
+}
@@ -0,0 +1,262 @@
+//! Anthropic Claude client (`/messages`, SSE streaming).
+//!
+//! Notes:
+//! - `provider.extra_options` go at the top level of the request (`top_k`, `metadata`).
+//! - `stop_reason` `max_tokens` and `refusal` become `ChatResponse::stop`; `parse_stream`
+//!   decodes a recorded stream the way `chat` does.
+//!
+//! ```
+//! use aion_core::providers::{claude::ClaudeClient, Message};
+//! use serde_json::json;
+//!
+//! let extra = json!({ "top_k": 40, "metadata": { "user_id": "u1" } }).as_object().unwrap().clone();
+//! let client = ClaudeClient::new("https://api.anthropic.com/v1".into(), "claude-3-5-haiku-latest".into(), "key".into())
+//!     .unwrap()
+//!     .with_extra_options(extra);
+//! let body = client.request_body(&[Message::system("be brief"), Message::user("hi")]).unwrap();
+//! assert_eq!(body["top_k"], 40);
+//! assert_eq!(body["metadata"]["user_id"], "u1");
+//! assert_eq!(body["system"], "be brief");
+//! assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+//!
+//! let clash = json!({ "system": "x" }).as_object().unwrap().clone();
+//! let client = ClaudeClient::new("https://api.anthropic.com/v1".into(), "claude-3-5-haiku-latest".into(), "key".into())
+//!     .unwrap()
+//!     .with_extra_options(clash);
+//! assert!(client.request_body(&[Message::system("be brief")]).is_err());
+//!
+//! use aion_core::providers::{claude::parse_stream, StopReason};
+//! let stream = |stop: &str| format!(
+//!     "event: message_start\ndata: {{\"type\":\"message_start\",\"message\":{{\"usage\":{{\"input_tokens\":12}}}}}}\n\n\
+//!      data: {{\"type\":\"content_block_delta\",\"delta\":{{\"type\":\"text_delta\",\"text\":\"The first\"}}}}\n\n\
+//!      data: {{\"type\":\"message_delta\",\"delta\":{{\"stop_reason\":\"{}\"}},\"usage\":{{\"output_tokens\":2}}}}\n\n\
+//!      data: {{\"type\":\"message_stop\"}}\n", stop);
+//! let reply = parse_stream(&stream("max_tokens")).unwrap();
+//! assert_eq!((reply.content.as_str(), reply.stop), ("The first", Some(StopReason::MaxTokens)));
+//! assert_eq!(reply.usage.unwrap().output_tokens, 2);
+//! assert_eq!(parse_stream(&stream("refusal")).unwrap().stop, Some(StopReason::Refusal));
+//! assert_eq!(parse_stream(&stream("end_turn")).unwrap().stop, None);
+//! ```
+
+use super::stream::{for_each_line, send, sse_data};
+use super::{
+    merge_extra, ChatClient, ChatResponse, Message, OnChunk, ProviderError, Role, StopReason, Usage, CONNECT_TIMEOUT,
+};
+use crate::cancel::CancelToken;
+use crate::config::GenParams;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Claude requires `max_tokens` on every request; used when the params leave it unset.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+pub struct ClaudeClient {
+    http: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: String,
+    params: GenParams,
+    /// `provider.extra_options`, added at the top level.
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart { message: StartMessage },
+    ContentBlockDelta { delta: TextDelta },
+    MessageDelta {
+        #[serde(default)]
+        delta: Option<StopDelta>,
+        #[serde(default)]
+        usage: Option<OutputUsage>,
+    },
+    MessageStop,
+    Error { error: ErrorBody },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartMessage {
+    #[serde(default)]
+    usage: Option<InputUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InputUsage {
+    #[serde(default)]
+    input_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct StopDelta {
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputUsage {
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TextDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl ErrorBody {
+    /// Map an in-stream `error` event; `kind` values follow Anthropic's error types.
+    fn into_provider_error(self) -> ProviderError {
+        match self.kind.as_str() {
+            "authentication_error" | "permission_error" => ProviderError::Auth,
+            "rate_limit_error" => ProviderError::RateLimited { retry_after: None },
+            "invalid_request_error" | "not_found_error" | "request_too_large" => {
+                ProviderError::InvalidRequest { message: self.message }
+            }
+            "overloaded_error" => ProviderError::ServerError { status: 529, body_excerpt: self.message },
+            _ => ProviderError::ServerError { status: 500, body_excerpt: self.message },
+        }
+    }
+}
+
+impl ClaudeClient {
+    pub fn new(base_url: String, model: String, api_key: String) -> Result<Self> {
+        let http = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build()?;
+        Ok(Self { http, base_url, model, api_key, params: GenParams::default(), extra: serde_json::Map::new() })
+    }
+
+    /// Send `max_tokens`, `temperature`, and `top_p` when set.
+    pub fn with_params(mut self, params: GenParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Add `extra` to every request body (`provider.extra_options`).
+    pub fn with_extra_options(mut self, extra: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// The `/messages` body for `messages`. Fails when an extra option names a field AION
+    /// sets.
+    pub fn request_body(&self, messages: &[Message]) -> Result<serde_json::Value> {
+        // Claude takes the system prompt as a top-level field, not as a message.
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        let turns: Vec<&Message> = messages.iter().filter(|m| m.role != Role::System).collect();
+
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": turns,
+            "stream": true,
+        });
+        if let Some(t) = self.params.temperature {
+            body["temperature"] = json!(t);
+        }
+        if let Some(p) = self.params.top_p {
+            body["top_p"] = json!(p);
+        }
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n\n"));
+        }
+        if let Some(fields) = body.as_object_mut() {
+            merge_extra(fields, &self.extra)?;
+        }
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl ChatClient for ClaudeClient {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let body = self.request_body(messages)?;
+        let req = self
+            .http
+            .post(format!("{}/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body);
+
+        let resp = send(req, cancel).await?;
+        let mut decoder = Decoder::default();
+        for_each_line(resp, cancel, |line| decoder.line(line, on_chunk)).await?;
+        Ok(decoder.finish())
+    }
+}
+
+/// A recorded `/messages` stream, decoded as `chat` decodes it.
+pub fn parse_stream(body: &str) -> Result<ChatResponse> {
+    let mut decoder = Decoder::default();
+    for line in body.lines() {
+        if !decoder.line(line, &mut |_| {})? {
+            break;
+        }
+    }
+    Ok(decoder.finish())
+}
+
+/// The reply so far, one SSE line at a time.
+#[derive(Default)]
+struct Decoder {
+    out: ChatResponse,
+    usage: Usage,
+}
+
+impl Decoder {
+    /// Take in `line`; `false` once the message is complete.
+    fn line(&mut self, line: &str, on_chunk: &mut OnChunk<'_>) -> Result<bool> {
+        let Some(data) = sse_data(line) else { return Ok(true) };
+        match serde_json::from_str::<StreamEvent>(data)? {
+            StreamEvent::MessageStart { message } => {
+                self.usage.input_tokens = message.usage.map(|u| u.input_tokens).unwrap_or(0);
+            }
+            StreamEvent::ContentBlockDelta { delta } => {
+                if let Some(text) = delta.text {
+                    on_chunk(&text);
+                    self.out.content.push_str(&text);
+                }
+            }
+            StreamEvent::MessageDelta { delta, usage } => {
+                if let Some(reason) = delta.and_then(|d| d.stop_reason) {
+                    self.out.stop = StopReason::from_wire(&reason);
+                }
+                if let Some(u) = usage {
+                    self.usage.output_tokens = u.output_tokens;
+                }
+            }
+            StreamEvent::MessageStop => return Ok(false),
+            StreamEvent::Error { error } => return Err(error.into_provider_error().into()),
+            StreamEvent::Other => {}
+        }
+        Ok(true)
+    }
+
+    fn finish(mut self) -> ChatResponse {
+        self.out.usage = Some(self.usage);
+        self.out
+    }
+}
@@ -0,0 +1,281 @@
+//! Provider failures and how they are shown to the user.
+//!
+//! Notes:
+//! - Clients map HTTP statuses, transport errors, and in-stream error events into
+//!   `ProviderError`; callers branch on the variant, never on message text.
+//! - `describe` turns a variant into a user-facing message (the CLI's error renderer
+//!   calls it for any error chain). Strings come from the `provider_error.*` locale keys, with
+//!   English built in for when no locale files are installed.
+//! - `retry_after` is only read from a `Retry-After` header given in seconds.
+//!
+//! Each backend against recorded replies (`fixtures/http`), served from a local socket:
+//!
+//! ```
+//! # #[cfg(feature = "providers")]
+//! # {
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::providers::{claude::ClaudeClient, ollama::OllamaClient, openai::OpenAiClient};
+//! use aion_core::providers::{ChatClient, Message, ProviderError};
+//! use std::io::{Read, Write};
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! /// Answer every request with the recorded reply `name`; the URL and a count of requests.
+//! fn serve(name: &str) -> (String, Arc<AtomicUsize>) {
+//!     let reply = std::fs::read(format!("{}/fixtures/http/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap();
+//!     let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+//!     let url = format!("http://{}", listener.local_addr().unwrap());
+//!     let hits = Arc::new(AtomicUsize::new(0));
+//!     let counted = hits.clone();
+//!     std::thread::spawn(move || {
+//!         for mut conn in listener.incoming().flatten() {
+//!             // Read the whole request first, so the client is never cut off mid-send.
+//!             let mut request = Vec::new();
+//!             let mut buf = [0; 4096];
+//!             while let Ok(n @ 1..) = conn.read(&mut buf) {
+//!                 request.extend_from_slice(&buf[..n]);
+//!                 let text = String::from_utf8_lossy(&request);
+//!                 let Some(end) = text.find("\r\n\r\n") else { continue };
+//!                 let length = text[..end].lines().find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:")
+//!                     .map(|v| v.trim().parse::<usize>().unwrap()));
+//!                 if request.len() >= end + 4 + length.unwrap_or(0) {
+//!                     break;
+//!                 }
+//!             }
+//!             counted.fetch_add(1, Ordering::SeqCst);
+//!             let _ = conn.write_all(&reply);
+//!         }
+//!     });
+//!     (url, hits)
+//! }
+//!
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+//! let error = |client: &dyn ChatClient| -> ProviderError {
+//!     let mut streamed = String::new();
+//!     let result = rt.block_on(client.chat(&[Message::user("hi")], &CancelToken::new(), &mut |c| streamed.push_str(c)));
+//!     let err = result.expect_err("the reply is an error");
+//!     match err.downcast::<ProviderError>() {
+//!         Ok(err) => err,
+//!         Err(other) => panic!("not a ProviderError: {:#}", other),
+//!     }
+//! };
+//! let openai = |name| {
+//!     let (url, hits) = serve(name);
+//!     (OpenAiClient::new(url, "gpt-4o-mini".into(), "sk-test".into()).unwrap(), hits)
+//! };
+//! let claude = |name| ClaudeClient::new(serve(name).0, "claude-3-5-haiku-latest".into(), "key".into()).unwrap();
+//! let ollama = |name| OllamaClient::new(serve(name).0, "llama9".into()).unwrap().with_preflight(false);
+//!
+//! // OpenAI-compatible: statuses, with the message taken from the JSON body.
+//! let (client, hits) = openai("openai-401.http");
+//! assert!(matches!(error(&client), ProviderError::Auth));
+//! assert_eq!(hits.load(Ordering::SeqCst), 1);
+//! let (client, hits) = openai("openai-429.http");
+//! let err = error(&client);
+//! assert!(matches!(err, ProviderError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(120)));
+//! assert_eq!((err.exit_code(), hits.load(Ordering::SeqCst)), (4, 1), "a long Retry-After is not waited out");
+//! match error(&openai("openai-400.http").0) {
+//!     ProviderError::InvalidRequest { message } => {
+//!         assert_eq!(message, "This model's maximum context length is 128000 tokens.")
+//!     }
+//!     other => panic!("{:?}", other),
+//! }
+//! let (client, hits) = openai("openai-503.http");
+//! match error(&client) {
+//!     ProviderError::ServerError { status, body_excerpt } => {
+//!         assert_eq!(status, 503);
+//!         assert!(body_excerpt.starts_with("<html>"), "{}", body_excerpt);
+//!     }
+//!     other => panic!("{:?}", other),
+//! }
+//! assert_eq!(hits.load(Ordering::SeqCst), 3, "a 5xx is retried");
+//!
+//! // Claude: statuses, and an `error` event after the stream has started.
+//! assert!(matches!(error(&claude("claude-401.http")), ProviderError::Auth));
+//! assert!(matches!(error(&claude("claude-404.http")),
+//!     ProviderError::InvalidRequest { message } if message == "model: claude-9-opus"));
+//! let err = error(&claude("claude-overloaded.http"));
+//! assert!(matches!(&err, ProviderError::ServerError { status: 529, body_excerpt } if body_excerpt == "Overloaded"));
+//! assert!(err.fails_over());
+//!
+//! // Ollama: a model that is not pulled, and an error line after the stream has started.
+//! assert!(matches!(error(&ollama("ollama-404.http")),
+//!     ProviderError::InvalidRequest { message } if message == "model \"llama9\" not found, try pulling it first"));
+//! assert!(matches!(error(&ollama("ollama-stream-error.http")),
+//!     ProviderError::ServerError { status: 500, body_excerpt }
+//!         if body_excerpt == "an unknown error was encountered while running the model"));
+//! # }
+//! ```
+
+use super::ollama::OllamaUnreachable;
+use crate::config::AppConfig;
+use std::time::Duration;
+
+/// Longest provider body kept in `ServerError` and `InvalidRequest`.
+const EXCERPT_CHARS: usize = 300;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProviderError {
+    #[error("authentication failed")]
+    Auth,
+    #[error("rate limited")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("network error: {0}")]
+    Network(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("request timed out")]
+    Timeout,
+    #[error("invalid request: {message}")]
+    InvalidRequest { message: String },
+    #[error("provider returned HTTP {status}: {body_excerpt}")]
+    ServerError { status: u16, body_excerpt: String },
+    #[error("request cancelled")]
+    Cancelled,
+}
+
+impl ProviderError {
+    /// The provider error in `e`'s chain, if there is one.
+    pub fn find(e: &anyhow::Error) -> Option<&ProviderError> {
+        e.chain().find_map(|c| c.downcast_ref::<ProviderError>())
+    }
+
+    /// Map a non-success HTTP response.
+    pub fn from_status(status: u16, retry_after: Option<&str>, body: &str) -> Self {
+        match status {
+            401 | 403 => ProviderError::Auth,
+            429 => ProviderError::RateLimited {
+                retry_after: retry_after
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs),
+            },
+            408 | 504 => ProviderError::Timeout,
+            400..=499 => ProviderError::InvalidRequest {
+                message: error_message(body),
+            },
+            _ => ProviderError::ServerError {
+                status,
+                body_excerpt: excerpt(body),
+            },
+        }
+    }
+
+    /// Map a transport error from reqwest.
+    pub fn from_reqwest(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ProviderError::Timeout
+        } else {
+            ProviderError::Network(Box::new(e))
+        }
+    }
+
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ProviderError::RateLimited { .. } | ProviderError::Timeout => true,
+            ProviderError::ServerError { status, .. } => *status >= 500,
+            ProviderError::Network(source) => !source.is::<OllamaUnreachable>(),
+            ProviderError::Auth | ProviderError::InvalidRequest { .. } | ProviderError::Cancelled => false,
+        }
+    }
+
+    /// Whether another provider may answer where this one failed: anything worth
+    /// retrying, and a server that cannot be reached at all.
+    pub fn fails_over(&self) -> bool {
+        self.is_retryable() || matches!(self, ProviderError::Network(_))
+    }
+
+    /// Process exit code for a one-shot command that failed with this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ProviderError::Auth => 3,
+            ProviderError::RateLimited { .. } => 4,
+            ProviderError::Network(_) | ProviderError::Timeout => 5,
+            ProviderError::InvalidRequest { .. } => 6,
+            ProviderError::ServerError { .. } => 7,
+            ProviderError::Cancelled => 130,
+        }
+    }
+}
+
+impl From<OllamaUnreachable> for ProviderError {
+    fn from(e: OllamaUnreachable) -> Self {
+        ProviderError::Network(Box::new(e))
+    }
+}
+
+/// The `error.message` (or `error`, or `message`) field of a JSON error body, else an
+/// excerpt of the raw body.
+fn error_message(body: &str) -> String {
+    let parsed: Option<serde_json::Value> = serde_json::from_str(body).ok();
+    let field = parsed.as_ref().and_then(|v| {
+        v.pointer("/error/message")
+            .or_else(|| v.get("error"))
+            .or_else(|| v.get("message"))
+            .and_then(|m| m.as_str())
+    });
+    match field {
+        Some(m) => excerpt(m),
+        None => excerpt(body),
+    }
+}
+
+fn excerpt(body: &str) -> String {
+    body.trim().chars().take(EXCERPT_CHARS).collect()
+}
+
+/* ---------------------------
+   Rendering
+---------------------------- */
+
+/// A localized, actionable message for `err`. Not redacted; callers pass it through
+/// `redact` before showing it.
+pub fn describe(err: &ProviderError, cfg: &AppConfig) -> String {
+    let lang = cfg.language.as_str();
+
+    match err {
+        ProviderError::Auth => {
+            let env = cfg.provider.api_key_env.as_deref().unwrap_or("provider.api_key_env");
+            message(lang, "auth", &[("provider", cfg.provider.kind.to_string()), ("env", env.to_string())])
+        }
+        ProviderError::RateLimited { retry_after: Some(d) } => {
+            message(lang, "rate_limited_after", &[("seconds", d.as_secs().to_string())])
+        }
+        ProviderError::RateLimited { retry_after: None } => message(lang, "rate_limited", &[]),
+        ProviderError::Network(source) => match source.downcast_ref::<OllamaUnreachable>() {
+            Some(u) => message(lang, "ollama_unreachable", &[("url", u.base_url.clone())]),
+            None => message(lang, "network", &[("detail", source.to_string())]),
+        },
+        ProviderError::Timeout => message(lang, "timeout", &[]),
+        ProviderError::InvalidRequest { message: m } => {
+            message(lang, "invalid_request", &[("message", m.clone()), ("model", cfg.provider.model.clone())])
+        }
+        ProviderError::ServerError { status, body_excerpt } => message(
+            lang,
+            "server_error",
+            &[("status", status.to_string()), ("body", body_excerpt.clone())],
+        ),
+        ProviderError::Cancelled => message(lang, "cancelled", &[]),
+    }
+}
+
+fn message(lang: &str, name: &str, vars: &[(&str, String)]) -> String {
+    crate::i18n::text(lang, &format!("provider_error.{}", name), fallback(name), vars)
+}
+
+/// English text used when no locale files are installed.
+fn fallback(name: &str) -> &'static str {
+    match name {
+        "auth" => "Authentication with {provider} failed. Check that {env} holds a valid API key.",
+        "rate_limited" => "The provider is rate limiting requests. Wait a moment, then retry (/retry in chat).",
+        "rate_limited_after" => "The provider is rate limiting requests. Retry in {seconds}s (/retry in chat).",
+        "ollama_unreachable" => {
+            "Ollama is not reachable at {url}. Start the server with `ollama serve`, then retry (/retry in chat)."
+        }
+        "network" => "Could not reach the provider: {detail}. Check your connection and provider.base_url.",
+        "timeout" => "The provider did not respond in time. Retry (/retry in chat).",
+        "invalid_request" => "The provider rejected the request: {message}. Check that model '{model}' exists.",
+        "server_error" => "The provider failed (HTTP {status}): {body}. Retry later (/retry in chat).",
+        "cancelled" => "[cancelled]",
+        _ => "Provider error.",
+    }
+}
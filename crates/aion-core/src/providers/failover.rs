@@ -0,0 +1,239 @@
+//! Trying the configured provider's fallbacks when it fails (`provider.fallbacks`).
+//!
+//! Notes:
+//! - `build_client` returns a `FailoverClient` when fallbacks are configured. It tries
+//!   the provider, then each fallback in order, moving on when a request fails in a way
+//!   another provider may not (`ProviderError::fails_over`: timeouts, rate limits, 5xx,
+//!   unreachable servers). Auth failures, rejected requests, and cancellation end it.
+//! - A provider other than the last that has not sent its first chunk within
+//!   `network.timeout_secs` counts as timed out. The last one gets as long as it needs.
+//! - Once a reply has started streaming it is never abandoned: a failure mid-reply is
+//!   returned as is, so the output is not mixed from two providers.
+//! - Each client still retries on its own first (`stream::retry_delay`).
+//! - Providers cooling down after a failure (`health`) are passed over; when all of
+//!   them are, they are tried in order anyway.
+//! - A fallback that cannot be built (its key variable is unset, ...) is passed over
+//!   like one that failed, but does not count against its health.
+//! - A reply from a fallback carries `ChatResponse::fallback`, naming who answered and
+//!   why the providers before it did not.
+//!
+//! Two providers driven by one script (`mock`), each request taking the next steps:
+//!
+//! ```
+//! # #[cfg(all(feature = "providers", debug_assertions))]
+//! # {
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::config::{AppConfig, ProviderKind};
+//! use aion_core::providers::{build_client, health, Message, ProviderError};
+//!
+//! let dir = std::env::temp_dir().join(format!("aion-failover-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir).unwrap();
+//! std::env::set_var("AION_CACHE_DIR", dir.join("cache"));
+//! // A 429 asking for a long wait fails at once, without the client retrying.
+//! let limited = r#"{ "status": 429, "retry_after": "600", "body": "slow down" }"#;
+//! let script = format!(
+//!     r#"{{ "steps": [{0}, {{ "content": "from the fallback" }}, {{ "content": "again" }}, {0}, {0}, {0}] }}"#,
+//!     limited
+//! );
+//! std::fs::write(dir.join("script.json"), script).unwrap();
+//! std::env::set_var("AION_MOCK_PROVIDER", dir.join("script.json"));
+//!
+//! let mut app = AppConfig::new_default();
+//! app.caps.write_files = true;
+//! let mut backup = app.provider.clone();
+//! backup.kind = ProviderKind::OpenAI;
+//! backup.model = "gpt-4o-mini".into();
+//! app.provider.fallbacks = vec![backup.clone()];
+//! let (primary, client) = (app.provider.clone(), build_client(&app).unwrap());
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! let send = || rt.block_on(client.chat(&[Message::user("hi")], &CancelToken::new(), &mut |_| {}));
+//!
+//! // The provider fails, and the fallback answers.
+//! let reply = send().unwrap();
+//! let fallback = reply.fallback.unwrap();
+//! assert_eq!((reply.content.as_str(), fallback.kind, fallback.model.as_str()), ("from the fallback", ProviderKind::OpenAI, "gpt-4o-mini"));
+//! assert_eq!(fallback.passed_over, ["Ollama · mistral @ localhost:11434: rate limited"]);
+//! assert!(health::get(&primary).cooldown_left().is_some());
+//! assert_eq!(health::get(&backup).failures, 0);
+//!
+//! // While it cools down the provider is not tried: the fallback takes the next step.
+//! let reply = send().unwrap();
+//! assert_eq!(reply.content, "again");
+//! assert_eq!(reply.fallback.unwrap().passed_over, ["Ollama · mistral @ localhost:11434: cooling down after rate limited"]);
+//! assert_eq!(health::get(&primary).failures, 1);
+//!
+//! // The fallback fails too; with both cooling down, both are tried, and the last error is returned.
+//! assert!(matches!(ProviderError::find(&send().unwrap_err()), Some(ProviderError::RateLimited { .. })));
+//! let err = send().unwrap_err();
+//! assert!(matches!(ProviderError::find(&err), Some(ProviderError::RateLimited { .. })), "{:#}", err);
+//! assert_eq!((health::get(&primary).failures, health::get(&backup).failures), (2, 2));
+//!
+//! // The records were saved for the next run.
+//! let saved = std::fs::read_to_string(health::health_file_path().unwrap()).unwrap();
+//! assert!(saved.contains("\"ollama mistral @ http://localhost:11434\"") && saved.contains("\"failures\": 2"), "{}", saved);
+//! std::fs::remove_dir_all(&dir).unwrap();
+//! # }
+//! ```
+
+use super::{build_single, health, ChatClient, ChatResponse, Fallback, Message, ModelInfo, OnChunk, ProviderError};
+use crate::cancel::CancelToken;
+use crate::config::{AppConfig, GenParams, ProviderConfig};
+use crate::persistence;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Longest failure reason kept for a notice or a health record.
+const REASON_CHARS: usize = 120;
+
+struct Link {
+    cfg: ProviderConfig,
+    /// The build error, as text, when the client could not be built.
+    client: Result<Box<dyn ChatClient>, String>,
+}
+
+pub struct FailoverClient {
+    links: Vec<Link>,
+    /// Time to the first chunk for every provider but the last.
+    first_chunk_timeout: Duration,
+    /// Save health records (`caps.write_files`, unless ephemeral).
+    persist: bool,
+    /// What the links were built from, to build one again for its typed error.
+    app: AppConfig,
+    request: GenParams,
+}
+
+impl FailoverClient {
+    /// Clients for `app.provider` and its fallbacks. Fails only when the provider itself
+    /// cannot be built.
+    pub fn new(app: &AppConfig, request: &GenParams) -> Result<Self> {
+        let mut links = vec![Link { cfg: app.provider.clone(), client: Ok(build_single(app, &app.provider, request)?) }];
+        for cfg in &app.provider.fallbacks {
+            let client = build_single(app, cfg, request).map_err(|e| format!("{:#}", e));
+            links.push(Link { cfg: cfg.clone(), client });
+        }
+        Ok(Self {
+            links,
+            first_chunk_timeout: app.network.timeout(),
+            persist: app.caps.write_files && persistence::policy().keeps_history(),
+            app: app.clone(),
+            request: *request,
+        })
+    }
+
+    /// Indexes of the links to try, in order: the ones not cooling down, or all.
+    fn order(&self) -> Vec<usize> {
+        let ready: Vec<usize> =
+            (0..self.links.len()).filter(|&i| health::get(&self.links[i].cfg).cooldown_left().is_none()).collect();
+        match ready.is_empty() {
+            true => (0..self.links.len()).collect(),
+            false => ready,
+        }
+    }
+}
+
+#[async_trait]
+impl ChatClient for FailoverClient {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let order = self.order();
+        let mut passed_over = Vec::new();
+        for (i, link) in self.links.iter().enumerate() {
+            if !order.contains(&i) {
+                let reason = health::get(&link.cfg).error.unwrap_or_default();
+                passed_over.push(format!("{}: cooling down after {}", label(&link.cfg), reason));
+                continue;
+            }
+            let last = order.last() == Some(&i);
+            let client = match &link.client {
+                Ok(client) => client,
+                // Built again: the stored error is text, and the caller needs its type
+                // (`ProviderError`, `ConfigError`, ...) to localize it and pick the exit code.
+                Err(e) if last => {
+                    return Err(build_single(&self.app, &link.cfg, &self.request).err().unwrap_or_else(|| anyhow!("{}", e)))
+                }
+                Err(e) => {
+                    passed_over.push(format!("{}: {}", label(&link.cfg), one_line(e)));
+                    continue;
+                }
+            };
+
+            let streamed = AtomicBool::new(false);
+            let mut relay = |chunk: &str| {
+                streamed.store(true, Ordering::Relaxed);
+                on_chunk(chunk);
+            };
+            let work = client.chat(messages, cancel, &mut relay);
+            let result = match last {
+                true => work.await,
+                false => {
+                    tokio::pin!(work);
+                    tokio::select! {
+                        result = &mut work => result,
+                        _ = tokio::time::sleep(self.first_chunk_timeout) => match streamed.load(Ordering::Relaxed) {
+                            true => work.await,
+                            false => Err(ProviderError::Timeout.into()),
+                        },
+                    }
+                }
+            };
+
+            match result {
+                Ok(mut response) => {
+                    health::record_ok(&link.cfg, self.persist);
+                    if i > 0 {
+                        response.fallback = Some(Fallback {
+                            kind: link.cfg.kind.clone(),
+                            model: link.cfg.model.clone(),
+                            passed_over,
+                        });
+                    }
+                    return Ok(response);
+                }
+                Err(e) => {
+                    let Some(err) = ProviderError::find(&e).filter(|err| err.fails_over()) else {
+                        return Err(e);
+                    };
+                    let reason = one_line(&err.to_string());
+                    health::record_failure(&link.cfg, &reason, self.persist);
+                    if last || streamed.load(Ordering::Relaxed) {
+                        return Err(e);
+                    }
+                    passed_over.push(format!("{}: {}", label(&link.cfg), reason));
+                }
+            }
+        }
+        unreachable!("the chain always holds the configured provider")
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        match &self.links[0].client {
+            Ok(client) => client.list_models().await,
+            Err(e) => Err(anyhow!("{}", e)),
+        }
+    }
+}
+
+/// `<provider> · <model> @ <host>`, as the notices show it.
+pub fn label(cfg: &ProviderConfig) -> String {
+    let url = cfg.effective_base_url();
+    let host = url::Url::parse(&url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| u.port().map_or(h.to_string(), |p| format!("{}:{}", h, p))))
+        .unwrap_or(url);
+    format!("{} · {} @ {}", cfg.kind, cfg.model, host)
+}
+
+/// `reason` on one line and cut short; error bodies can be long HTML pages.
+fn one_line(reason: &str) -> String {
+    let line = reason.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(REASON_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line,
+    }
+}
@@ -0,0 +1,178 @@
+//! How the providers of a failover chain have been doing (`provider_health.json` in the
+//! cache dir, `crate::paths`).
+//!
+//! Notes:
+//! - Written by `failover::FailoverClient` only; a provider without fallbacks has no
+//!   record. Entries are keyed by provider, model, and endpoint (`key`).
+//! - A failure the chain moved on from starts a `COOLDOWN`: until it ends, requests start
+//!   at the next provider. A reply clears it.
+//! - Like `audit`, the records are process-wide, so a client rebuilt after `/model` still
+//!   knows a provider is down. They are seeded from the file, so separate runs (and
+//!   `aion status`) see the same health.
+//! - Saved only when the client was built with `caps.write_files`; otherwise kept for the
+//!   process. A missing or unreadable file reads as empty and a failed save is ignored:
+//!   health only decides where a request starts.
+//! - Error texts pass through `redact` before they are kept.
+//!
+//! A cooldown read from the file, and the provider tried again once it has run out:
+//!
+//! ```
+//! # #[cfg(all(feature = "providers", debug_assertions))]
+//! # {
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::config::AppConfig;
+//! use aion_core::providers::{build_client, health, Message};
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//!
+//! let dir = std::env::temp_dir().join(format!("aion-health-{}", std::process::id()));
+//! std::fs::create_dir_all(dir.join("cache")).unwrap();
+//! std::env::set_var("AION_CACHE_DIR", dir.join("cache"));
+//! std::fs::write(dir.join("script.json"), r#"{ "steps": [{ "content": "fallback" }, { "content": "primary" }] }"#).unwrap();
+//! std::env::set_var("AION_MOCK_PROVIDER", dir.join("script.json"));
+//!
+//! let mut app = AppConfig::new_default();
+//! let mut backup = app.provider.clone();
+//! backup.model = "llama3".into();
+//! app.provider.fallbacks = vec![backup];
+//! let primary = app.provider.clone();
+//!
+//! // An earlier run left the provider cooling down for two more seconds.
+//! let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+//! let record = format!(r#"{{ "{}": {{ "failures": 3, "error": "timed out", "cooldown_until": {} }} }}"#, health::key(&primary), now + 2);
+//! std::fs::write(health::health_file_path().unwrap(), record).unwrap();
+//! assert_eq!(health::get(&primary).failures, 3);
+//!
+//! let client = build_client(&app).unwrap();
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! let send = || rt.block_on(client.chat(&[Message::user("hi")], &CancelToken::new(), &mut |_| {})).unwrap();
+//! assert!(send().fallback.is_some(), "passed over while cooling down");
+//!
+//! std::thread::sleep(Duration::from_secs(3));
+//! assert_eq!(health::get(&primary).cooldown_left(), None);
+//! let reply = send();
+//! assert_eq!((reply.content.as_str(), reply.fallback), ("primary", None));
+//! let health = health::get(&primary);
+//! assert_eq!((health.failures, health.cooldown_until, health.last_ok.is_some()), (0, None, true));
+//! std::fs::remove_dir_all(&dir).unwrap();
+//! # }
+//! ```
+
+use crate::audit;
+use crate::config::ProviderConfig;
+use crate::paths;
+use crate::redact;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HEALTH_FILE_NAME: &str = "provider_health.json";
+
+/// How long a provider that failed is passed over.
+pub const COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Health by `key`; `None` until first read from the file.
+static RECORDS: Mutex<Option<BTreeMap<String, Health>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Health {
+    /// Unix seconds of the last reply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_ok: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure: Option<u64>,
+    /// What the last failure was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Failures since the last reply.
+    #[serde(default)]
+    pub failures: u32,
+    /// Unix seconds until which the provider is passed over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_until: Option<u64>,
+}
+
+impl Health {
+    /// Seconds left of the cooldown, if one is running.
+    pub fn cooldown_left(&self) -> Option<u64> {
+        self.cooldown_until.and_then(|until| until.checked_sub(now())).filter(|left| *left > 0)
+    }
+}
+
+pub fn health_file_path() -> Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(HEALTH_FILE_NAME))
+}
+
+/// What the records are keyed by: `<provider> <model> @ <endpoint>`.
+pub fn key(cfg: &ProviderConfig) -> String {
+    format!("{} {} @ {}", cfg.kind.id(), cfg.model, cfg.effective_base_url())
+}
+
+/// The record for `cfg`; default (no record) when it was never used in a chain.
+pub fn get(cfg: &ProviderConfig) -> Health {
+    with_records(|records| records.get(&key(cfg)).cloned().unwrap_or_default())
+}
+
+/// `cfg` answered.
+pub fn record_ok(cfg: &ProviderConfig, persist: bool) {
+    update(cfg, persist, |h| {
+        h.last_ok = Some(now());
+        h.failures = 0;
+        h.cooldown_until = None;
+    });
+}
+
+/// `cfg` failed with `error` and is passed over for `COOLDOWN`.
+pub fn record_failure(cfg: &ProviderConfig, error: &str, persist: bool) {
+    update(cfg, persist, |h| {
+        let at = now();
+        h.last_failure = Some(at);
+        h.error = Some(redact::redact(error));
+        h.failures = h.failures.saturating_add(1);
+        h.cooldown_until = Some(at + COOLDOWN.as_secs());
+    });
+}
+
+fn update(cfg: &ProviderConfig, persist: bool, change: impl FnOnce(&mut Health)) {
+    let key = key(cfg);
+    let health = with_records(|records| {
+        let health = records.entry(key.clone()).or_default();
+        change(health);
+        health.clone()
+    });
+    if persist {
+        // Re-read the file so records another process wrote meanwhile are kept.
+        let mut on_disk = load();
+        on_disk.insert(key, health);
+        let _ = save(&on_disk);
+    }
+}
+
+fn with_records<T>(f: impl FnOnce(&mut BTreeMap<String, Health>) -> T) -> T {
+    let mut records = RECORDS.lock().unwrap_or_else(|e| e.into_inner());
+    f(records.get_or_insert_with(load))
+}
+
+fn load() -> BTreeMap<String, Health> {
+    health_file_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save(records: &BTreeMap<String, Health>) -> Result<()> {
+    paths::ensure(&paths::cache_dir()?)?;
+    let path = health_file_path()?;
+    let text = serde_json::to_string_pretty(records).context("failed to serialize provider health")? + "\n";
+    fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))?;
+    audit::file_write(&path);
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,351 @@
+//! Client-side limits on provider requests (`network.max_concurrent_requests`,
+//! `network.requests_per_minute`), so batch work does not run a provider into 429s.
+//!
+//! Notes:
+//! - `build_client` wraps each provider's client in a `LimitedClient` when either limit
+//!   is set (0 is no limit). Limiters are process-wide, one per provider instance (kind
+//!   and endpoint, `key`): the models of `aion bench`, a fallback chain, and a client
+//!   rebuilt after `/model` all draw from the same limits.
+//! - Concurrency is a semaphore; a request holds its slot until its reply has ended.
+//! - Requests per minute is a token bucket holding a minute's worth, refilled evenly. It
+//!   is kept as the time the next token is due (GCRA), so waits are exact. A request
+//!   takes its token once it has a slot.
+//! - Waiting is cancellable: a cancelled token ends it with `ProviderError::Cancelled`.
+//! - Waits are not silent. Each one is passed to the reporter (`set_reporter`; the
+//!   line-based front ends print it) as it starts, and `waiting` says what is being
+//!   waited for right now (the chat view's status line).
+//! - Time comes from a `Clock`; with `ManualClock` waits take no time and are exact.
+//! - A wait counts toward a fallback chain's first-chunk timeout (see `failover`).
+//! - New limits (`/config set`) give the provider a new limiter; requests already
+//!   waiting finish on the old one.
+//!
+//! ```
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::providers::limit::{Clock, Limits, ManualClock, RateLimiter, TokenBucket, Wait};
+//! use std::sync::Arc;
+//! use std::time::{Duration, Instant};
+//!
+//! // Two a minute: both at once, then one every 30 s.
+//! let secs = Duration::from_secs;
+//! let start = Instant::now();
+//! let mut bucket = TokenBucket::new(2, start);
+//! assert_eq!((bucket.take(start), bucket.take(start)), (None, None));
+//! assert_eq!(bucket.take(start), Some(secs(30)));
+//! assert_eq!(bucket.take(start + secs(10)), Some(secs(20)));
+//! assert_eq!(bucket.take(start + secs(30)), None);
+//!
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! rt.block_on(async {
+//!     let clock = Arc::new(ManualClock::new());
+//!     let begun = clock.now();
+//!     let limiter = RateLimiter::with_clock(Limits { max_concurrent: 1, per_minute: 2 }, clock.clone());
+//!     let token = CancelToken::new();
+//!     let mut waits = Vec::new();
+//!     for _ in 0..3 {
+//!         limiter.acquire(&token, &mut |w| waits.push(w)).await.unwrap();
+//!     }
+//!     // The third request waited for its token, on the clock.
+//!     assert_eq!(waits, [Wait::Time(secs(30))]);
+//!     assert_eq!(clock.now() - begun, secs(30));
+//!
+//!     // The only slot is taken: the wait for it is reported and cancelling ends it.
+//!     let _held = limiter.acquire(&token, &mut |_| {}).await.unwrap();
+//!     let cancelled = CancelToken::new();
+//!     cancelled.cancel();
+//!     waits.clear();
+//!     assert!(limiter.acquire(&cancelled, &mut |w| waits.push(w)).await.is_err());
+//!     assert_eq!(waits, [Wait::Slot { max: 1 }]);
+//!     assert_eq!(Wait::Time(Duration::from_millis(2_080)).to_string(), "rate limit: waiting 2.1s");
+//! });
+//! ```
+
+use super::{ChatClient, ChatResponse, Message, ModelInfo, OnChunk, ProviderError};
+use crate::cancel::CancelToken;
+use crate::config::{NetworkConfig, ProviderConfig};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limiters by provider instance (`key`).
+static LIMITERS: Mutex<BTreeMap<String, Arc<RateLimiter>>> = Mutex::new(BTreeMap::new());
+
+/// Told about each wait as it starts.
+static REPORTER: Mutex<Option<fn(&Wait)>> = Mutex::new(None);
+
+/// The wait in progress, and when it started.
+static WAITING: Mutex<Option<(Wait, Instant)>> = Mutex::new(None);
+
+/// `0` is no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_concurrent: u32,
+    pub per_minute: u32,
+}
+
+impl Limits {
+    pub fn is_off(&self) -> bool {
+        self.max_concurrent == 0 && self.per_minute == 0
+    }
+}
+
+impl From<&NetworkConfig> for Limits {
+    fn from(network: &NetworkConfig) -> Self {
+        Self { max_concurrent: network.max_concurrent_requests, per_minute: network.requests_per_minute }
+    }
+}
+
+/// What a request is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wait {
+    /// All `max` slots are taken.
+    Slot { max: u32 },
+    /// The next token, this long from now.
+    Time(Duration),
+}
+
+impl fmt::Display for Wait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Wait::Slot { max } => write!(f, "rate limit: waiting for one of {} requests to finish", max),
+            Wait::Time(left) => write!(f, "rate limit: waiting {:.1}s", left.as_secs_f64()),
+        }
+    }
+}
+
+/* ---------------------------
+   Clock
+---------------------------- */
+
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Real time, sleeping on the tokio timer.
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// A clock that only moves when slept on: `sleep` returns at once, `duration` later.
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { now: Mutex::new(Instant::now()) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/* ---------------------------
+   Limiter
+---------------------------- */
+
+/// `per_minute` tokens, refilled evenly; full at the start.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    /// Time per token.
+    interval: Duration,
+    /// How far ahead of now `due` may run: the bucket's size less one token.
+    burst: Duration,
+    /// When the bucket would next have a token if no more were taken early.
+    due: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(per_minute: u32, now: Instant) -> Self {
+        let interval = Duration::from_secs(60) / per_minute.max(1);
+        Self { interval, burst: interval * (per_minute.max(1) - 1), due: now }
+    }
+
+    /// Take a token at `now`: `None` when taken, else how long until one is there.
+    pub fn take(&mut self, now: Instant) -> Option<Duration> {
+        let limit = now + self.burst;
+        if self.due > limit {
+            return Some(self.due - limit);
+        }
+        self.due = self.due.max(now) + self.interval;
+        None
+    }
+}
+
+/// A request's slot, held until it is dropped.
+pub struct Permit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+pub struct RateLimiter {
+    limits: Limits,
+    slots: Option<Arc<Semaphore>>,
+    bucket: Option<Mutex<TokenBucket>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: Limits) -> Self {
+        Self::with_clock(limits, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(limits: Limits, clock: Arc<dyn Clock>) -> Self {
+        let slots = (limits.max_concurrent > 0).then(|| Arc::new(Semaphore::new(limits.max_concurrent as usize)));
+        let bucket = (limits.per_minute > 0).then(|| Mutex::new(TokenBucket::new(limits.per_minute, clock.now())));
+        Self { limits, slots, bucket, clock }
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Wait for a slot, then a token, telling `report` about each wait.
+    pub async fn acquire(
+        &self,
+        cancel: &CancelToken,
+        report: &mut (dyn FnMut(Wait) + Send),
+    ) -> Result<Permit, ProviderError> {
+        let slot = match &self.slots {
+            None => None,
+            Some(slots) => match Arc::clone(slots).try_acquire_owned() {
+                Ok(slot) => Some(slot),
+                Err(_) => {
+                    report(Wait::Slot { max: self.limits.max_concurrent });
+                    tokio::select! {
+                        biased;
+                        _ = cancel.cancelled() => return Err(ProviderError::Cancelled),
+                        slot = Arc::clone(slots).acquire_owned() => Some(slot.expect("the semaphore is never closed")),
+                    }
+                }
+            },
+        };
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = bucket.lock().unwrap_or_else(|e| e.into_inner()).take(self.clock.now());
+                let Some(wait) = wait else { break };
+                report(Wait::Time(wait));
+                tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => return Err(ProviderError::Cancelled),
+                    _ = self.clock.sleep(wait) => {}
+                }
+            }
+        }
+        Ok(Permit { _slot: slot })
+    }
+
+    /// `acquire`, with the waits reported to the reporter and `waiting`.
+    pub async fn turn(&self, cancel: &CancelToken) -> Result<Permit, ProviderError> {
+        let reporter = *REPORTER.lock().unwrap_or_else(|e| e.into_inner());
+        let permit = self
+            .acquire(cancel, &mut |wait| {
+                *WAITING.lock().unwrap_or_else(|e| e.into_inner()) = Some((wait, Instant::now()));
+                if let Some(report) = reporter {
+                    report(&wait);
+                }
+            })
+            .await;
+        *WAITING.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        permit
+    }
+}
+
+/// The provider instance a limiter belongs to.
+pub fn key(cfg: &ProviderConfig) -> String {
+    format!("{}@{}", cfg.kind.id(), cfg.effective_base_url())
+}
+
+/// The process-wide limiter for `cfg`; `None` when `limits` are off.
+pub fn shared(cfg: &ProviderConfig, limits: Limits) -> Option<Arc<RateLimiter>> {
+    if limits.is_off() {
+        return None;
+    }
+    let mut limiters = LIMITERS.lock().unwrap_or_else(|e| e.into_inner());
+    let limiter = limiters.entry(key(cfg)).or_insert_with(|| Arc::new(RateLimiter::new(limits)));
+    if limiter.limits() != limits {
+        *limiter = Arc::new(RateLimiter::new(limits));
+    }
+    Some(Arc::clone(limiter))
+}
+
+/// `client` under `network`'s limits for `cfg`, or as is when there are none.
+pub fn wrap(network: &NetworkConfig, cfg: &ProviderConfig, client: Box<dyn ChatClient>) -> Box<dyn ChatClient> {
+    match shared(cfg, Limits::from(network)) {
+        Some(limiter) => Box::new(LimitedClient { inner: client, limiter }),
+        None => client,
+    }
+}
+
+/// Call `report` for each wait from now on; `None` stops reporting. Returns the one
+/// replaced.
+pub fn set_reporter(report: Option<fn(&Wait)>) -> Option<fn(&Wait)> {
+    std::mem::replace(&mut *REPORTER.lock().unwrap_or_else(|e| e.into_inner()), report)
+}
+
+/// What a request is waiting for now; a `Time` wait with the time left.
+pub fn waiting() -> Option<Wait> {
+    let waiting = *WAITING.lock().unwrap_or_else(|e| e.into_inner());
+    waiting.map(|(wait, since)| match wait {
+        Wait::Time(total) => Wait::Time(total.saturating_sub(since.elapsed())),
+        slot => slot,
+    })
+}
+
+/* ---------------------------
+   Client
+---------------------------- */
+
+pub struct LimitedClient {
+    inner: Box<dyn ChatClient>,
+    limiter: Arc<RateLimiter>,
+}
+
+#[async_trait]
+impl ChatClient for LimitedClient {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let _permit = self.limiter.turn(cancel).await?;
+        self.inner.chat(messages, cancel, on_chunk).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let _permit = self.limiter.turn(&CancelToken::new()).await?;
+        self.inner.list_models().await
+    }
+}
@@ -0,0 +1,153 @@
+//! Scripted provider, for driving the chat front-ends without a network (debug builds).
+//!
+//! Notes:
+//! - `AION_MOCK_PROVIDER=<script.json>` makes `build_client` return a `ScriptedClient`
+//!   whatever the config says; the configured provider and model are still shown and
+//!   recorded. With fallbacks, every provider of the chain is scripted, so a failing
+//!   step followed by a reply exercises the failover. Release builds ignore the variable.
+//!   The CLI's `--provider mock` (debug builds) insists that the variable is set.
+//! - Each request takes the next step of the script; after the last step, the last one
+//!   repeats. The position is process-wide, so a client rebuilt after `/model` carries on.
+//! - A step is a reply (`content`, or `chunks` with `delay_ms` before each, plus
+//!   `usage`, and `stop` for a reply that ended early: `"max_tokens"`, ...), an error (`status`, `body`, `retry_after`, mapped like an HTTP reply), or
+//!   both: the chunks, then the error mid-stream.
+//! - An error without chunks goes through the HTTP clients' retry policy
+//!   (`stream::retry_delay`), each attempt taking the next step: a 429 step followed by
+//!   a reply is retried and succeeds.
+//! - `models` answers `list_models`.
+//!
+//! ```json
+//! {
+//!   "steps": [
+//!     { "status": 429, "retry_after": "1" },
+//!     { "chunks": ["Hel", "lo"], "delay_ms": 50, "usage": { "input_tokens": 3, "output_tokens": 2 } },
+//!     { "chunks": ["cut "], "status": 500, "body": "stream reset" }
+//!   ],
+//!   "models": ["mock-small", "mock-large"]
+//! }
+//! ```
+
+use super::stream::{pause, retry_delay, MAX_ATTEMPTS};
+use super::{
+    ChatClient, ChatResponse, ListingUnsupported, Message, ModelInfo, OnChunk, ProviderError, StopReason, Usage,
+};
+use crate::cancel::CancelToken;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+pub const SCRIPT_ENV: &str = "AION_MOCK_PROVIDER";
+
+/// Index of the next step, shared by every `ScriptedClient` in the process.
+static NEXT_STEP: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    steps: Vec<Step>,
+    #[serde(default)]
+    models: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Step {
+    content: Option<String>,
+    chunks: Vec<String>,
+    delay_ms: u64,
+    usage: Option<Usage>,
+    stop: Option<StopReason>,
+    status: Option<u16>,
+    body: String,
+    retry_after: Option<String>,
+}
+
+impl Step {
+    fn error(&self) -> Option<ProviderError> {
+        self.status
+            .map(|status| ProviderError::from_status(status, self.retry_after.as_deref(), &self.body))
+    }
+}
+
+pub struct ScriptedClient {
+    script: Script,
+}
+
+/// The client for the script named by `AION_MOCK_PROVIDER`, when it is set.
+pub fn from_env() -> Option<Result<ScriptedClient>> {
+    let path = std::env::var_os(SCRIPT_ENV)?;
+    Some(ScriptedClient::load(&path))
+}
+
+impl ScriptedClient {
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let script: Script =
+            serde_json::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))?;
+        if script.steps.is_empty() {
+            bail!("{} has no steps", path.display());
+        }
+        Ok(Self { script })
+    }
+
+    fn next_step(&self) -> Step {
+        let i = NEXT_STEP.fetch_add(1, Ordering::Relaxed);
+        self.script.steps[i.min(self.script.steps.len() - 1)].clone()
+    }
+}
+
+#[async_trait]
+impl ChatClient for ScriptedClient {
+    async fn chat(
+        &self,
+        _messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let mut attempt = 1;
+        let step = loop {
+            let step = self.next_step();
+            match step.error() {
+                Some(err) if step.chunks.is_empty() && step.content.is_none() => {
+                    let Some(delay) = retry_delay(&err, attempt).filter(|_| attempt < MAX_ATTEMPTS) else {
+                        return Err(err.into());
+                    };
+                    pause(delay, cancel).await?;
+                    attempt += 1;
+                }
+                _ => break step,
+            }
+        };
+
+        let chunks = match &step.content {
+            Some(content) if step.chunks.is_empty() => vec![content.clone()],
+            _ => step.chunks.clone(),
+        };
+        let mut out = ChatResponse { usage: step.usage, stop: step.stop, ..Default::default() };
+        for chunk in chunks {
+            pause(Duration::from_millis(step.delay_ms), cancel).await?;
+            on_chunk(&chunk);
+            out.content.push_str(&chunk);
+        }
+        match step.error() {
+            Some(err) => Err(err.into()),
+            None => Ok(out),
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        if self.script.models.is_empty() {
+            return Err(ListingUnsupported.into());
+        }
+        Ok(self
+            .script
+            .models
+            .iter()
+            .map(|id| ModelInfo { id: id.clone(), context_length: None })
+            .collect())
+    }
+}
@@ -0,0 +1,365 @@
+//! Provider clients.
+//!
+//! Notes:
+//! - Every backend implements `ChatClient`; callers never branch on `ProviderKind`.
+//! - Replies are streamed chunk by chunk through a callback and also returned whole.
+//! - All clients honor a `CancelToken`: cancelling drops the HTTP stream and returns
+//!   `Err(ProviderError::Cancelled)`.
+//! - Failures are `ProviderError`s (see `error`).
+//! - Model listing is optional; backends without it return `Err(ListingUnsupported)`.
+//! - Clients are async and run on the caller's tokio runtime; nothing here starts its
+//!   own runtime or threads.
+//! - The clients, `ProviderError`, and `build_client` need the `providers` feature; the
+//!   message types and `ChatClient` are always available.
+//! - Interactive sessions hold a `LazyClient`, so the HTTP client (and its TLS setup) is
+//!   only built when the first request is sent.
+//! - With `provider.fallbacks`, `build_client` wraps the provider and its fallbacks in a
+//!   `failover::FailoverClient`; `health` keeps how each of them has been doing.
+//! - With `network.max_concurrent_requests` or `network.requests_per_minute`, each
+//!   provider's client waits its turn in a `limit::LimitedClient`.
+//! - Cloud providers' clients are wrapped in a `costs::MeteredClient`, which records what
+//!   each request cost and refuses requests once a budget is spent.
+//! - Debug builds replace every client with `mock::ScriptedClient` when
+//!   `AION_MOCK_PROVIDER` names a script (see `mock`).
+//! - Embeddings (`EmbeddingClient`, for `crate::retrieval`) are Ollama's only;
+//!   `build_embedding_client` refuses the others with `EmbeddingsUnsupported`.
+//! - A client is not built for a base_url `caps.network_deny` matches (`Guard::check_host`).
+
+#[cfg(feature = "providers")]
+pub mod claude;
+#[cfg(feature = "providers")]
+pub mod error;
+#[cfg(feature = "providers")]
+pub mod failover;
+#[cfg(feature = "providers")]
+pub mod health;
+#[cfg(feature = "providers")]
+pub mod limit;
+#[cfg(all(feature = "providers", debug_assertions))]
+pub mod mock;
+#[cfg(feature = "providers")]
+pub mod ollama;
+#[cfg(feature = "providers")]
+pub mod openai;
+#[cfg(feature = "providers")]
+mod stream;
+
+#[cfg(feature = "providers")]
+pub use error::ProviderError;
+
+use crate::cancel::CancelToken;
+use crate::config::{ProviderConfig, ProviderKind};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "providers")]
+use crate::config::{style, AppConfig, GenParams};
+#[cfg(feature = "providers")]
+use crate::guard::Guard;
+#[cfg(feature = "providers")]
+use anyhow::anyhow;
+#[cfg(feature = "providers")]
+use std::sync::{Arc, OnceLock};
+#[cfg(feature = "providers")]
+use std::time::Duration;
+
+/// Connect timeout for provider requests. Reading a streamed reply has no overall timeout.
+#[cfg(feature = "providers")]
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self { role: Role::System, content: content.into() }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: Role::User, content: content.into() }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self { role: Role::Assistant, content: content.into() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub content: String,
+    pub usage: Option<Usage>,
+    /// Set when a fallback answered instead of the configured provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Fallback>,
+    /// Why the reply ended, when it did not simply finish.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<StopReason>,
+}
+
+/// A reply that ended early, as the provider reported it (Claude's `stop_reason`,
+/// OpenAI's `finish_reason`, Ollama's `done_reason`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Cut off at the token limit (`max_tokens`, `length`); `/continue` picks it up.
+    MaxTokens,
+    /// Stopped by the provider's content filter (`content_filter`).
+    ContentFilter,
+    /// The model declined to answer (`refusal`).
+    Refusal,
+}
+
+impl StopReason {
+    /// The reason behind a provider's stop value; `None` for a normal end.
+    pub fn from_wire(reason: &str) -> Option<Self> {
+        match reason {
+            "max_tokens" | "length" => Some(Self::MaxTokens),
+            "content_filter" => Some(Self::ContentFilter),
+            "refusal" => Some(Self::Refusal),
+            _ => None,
+        }
+    }
+
+    /// The notice the chat front ends show under the reply, in `lang`.
+    pub fn notice(self, lang: &str) -> String {
+        let (key, fallback) = match self {
+            Self::MaxTokens => (
+                "stop.max_tokens",
+                "Response truncated at the token limit: /continue picks it up, or increase provider.params.max_tokens",
+            ),
+            Self::ContentFilter => ("stop.content_filter", "The provider's content filter stopped this reply"),
+            Self::Refusal => ("stop.refusal", "The provider declined to answer"),
+        };
+        format!("[{}]", crate::i18n::text(lang, key, fallback, &[]))
+    }
+}
+
+/// The fallback that answered, and why the providers before it did not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fallback {
+    pub kind: ProviderKind,
+    pub model: String,
+    /// One line per provider passed over: `<provider> · <model> @ <host>: <reason>`.
+    pub passed_over: Vec<String>,
+}
+
+impl ChatResponse {
+    /// The provider and model that answered: the fallback, or else `cfg`.
+    pub fn answered_by<'a>(&'a self, cfg: &'a ProviderConfig) -> (&'a ProviderKind, &'a str) {
+        match &self.fallback {
+            Some(f) => (&f.kind, &f.model),
+            None => (&cfg.kind, &cfg.model),
+        }
+    }
+}
+
+impl Fallback {
+    /// The line the chat front ends show under a reply from a fallback.
+    pub fn notice(&self) -> String {
+        format!("[answered by fallback {} · {}; {}]", self.kind, self.model, self.passed_over.join("; "))
+    }
+}
+
+/// A model offered by the provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModelInfo {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+}
+
+impl ModelInfo {
+    /// Whether this is the configured `model`. Ollama lists untagged models as `name:latest`.
+    pub fn matches(&self, model: &str) -> bool {
+        self.id == model || self.id.strip_suffix(":latest") == Some(model)
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("listing not supported for this provider")]
+pub struct ListingUnsupported;
+
+/// Receives each piece of a streamed reply.
+pub type OnChunk<'a> = dyn FnMut(&str) + Send + 'a;
+
+#[async_trait]
+pub trait ChatClient: Send + Sync {
+    /// Send the conversation and stream the reply through `on_chunk`.
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse>;
+
+    /// Models available from the provider.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        Err(ListingUnsupported.into())
+    }
+}
+
+/// Turns text into vectors, for `crate::retrieval`.
+#[async_trait]
+pub trait EmbeddingClient: Send + Sync {
+    /// One vector per text, in order.
+    async fn embed(&self, texts: &[String], cancel: &CancelToken) -> Result<Vec<Vec<f32>>>;
+}
+
+/// The provider has no embeddings AION can use.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("retrieval requires an embedding-capable provider; {kind} is not one here (use ollama)")]
+pub struct EmbeddingsUnsupported {
+    pub kind: ProviderKind,
+}
+
+/// Build the client for the configured provider.
+#[cfg(feature = "providers")]
+pub fn build_client(app: &AppConfig) -> Result<Box<dyn ChatClient>> {
+    build_client_with_params(app, &GenParams::default())
+}
+
+/// Build the client, with `request` overriding the configured generation parameters.
+#[cfg(feature = "providers")]
+pub fn build_client_with_params(app: &AppConfig, request: &GenParams) -> Result<Box<dyn ChatClient>> {
+    match app.provider.fallbacks.is_empty() {
+        true => build_single(app, &app.provider, request),
+        false => Ok(Box::new(failover::FailoverClient::new(app, request)?)),
+    }
+}
+
+/// The client for `cfg`, one provider of `app`'s chain.
+#[cfg(feature = "providers")]
+fn build_single(app: &AppConfig, cfg: &ProviderConfig, request: &GenParams) -> Result<Box<dyn ChatClient>> {
+    #[cfg(debug_assertions)]
+    if let Some(client) = mock::from_env() {
+        return Ok(crate::costs::wrap(cfg, limit::wrap(&app.network, cfg, Box::new(client?))));
+    }
+    let base_url = cfg.effective_base_url();
+    Guard::for_app(app).check_host(&base_url)?;
+    let api_key = resolve_api_key(cfg)?;
+    let params = cfg.effective_params(request);
+    params.validate()?;
+    let schema = crate::structured::pinned().cloned();
+    let json = style::effective(&app.style).json_mode() || schema.is_some();
+    let extra = crate::config::extra::to_json(&cfg.extra_options);
+
+    let client: Box<dyn ChatClient> = match cfg.kind {
+        ProviderKind::Ollama => Box::new(
+            ollama::OllamaClient::new(base_url, cfg.model.clone())?
+                .with_preflight(app.caps.network)
+                .with_params(params)
+                .with_json(json)
+                .with_extra_options(extra),
+        ),
+        ProviderKind::OpenAI | ProviderKind::OpenRouter => Box::new(
+            openai::OpenAiClient::new(base_url, cfg.model.clone(), api_key.unwrap_or_default())?
+                .with_params(params)
+                .with_headers(cfg.scope_headers())
+                .with_json(json)
+                .with_schema(schema)
+                .with_extra_options(extra),
+        ),
+        ProviderKind::Claude => Box::new(
+            claude::ClaudeClient::new(base_url, cfg.model.clone(), api_key.unwrap_or_default())?
+                .with_params(params)
+                .with_extra_options(extra),
+        ),
+    };
+    Ok(crate::costs::wrap(cfg, limit::wrap(&app.network, cfg, client)))
+}
+
+/// The embedding client for the configured provider (`provider.embedding_model`). Fallbacks
+/// are not used: vectors from different models cannot be compared.
+#[cfg(feature = "providers")]
+pub fn build_embedding_client(app: &AppConfig) -> Result<Arc<dyn EmbeddingClient>> {
+    let cfg = &app.provider;
+    Guard::for_app(app).check_host(&cfg.effective_base_url())?;
+    match cfg.kind {
+        ProviderKind::Ollama => Ok(Arc::new(
+            ollama::OllamaClient::new(cfg.effective_base_url(), cfg.embedding_model().to_string())?
+                .with_preflight(app.caps.network),
+        )),
+        _ => Err(EmbeddingsUnsupported { kind: cfg.kind.clone() }.into()),
+    }
+}
+
+/// Add `extra` (`provider.extra_options`) to `fields`. A name AION already set is an
+/// error, not an override; `config::extra::validate` catches most of them earlier.
+#[cfg(feature = "providers")]
+fn merge_extra(
+    fields: &mut serde_json::Map<String, serde_json::Value>,
+    extra: &serde_json::Map<String, serde_json::Value>,
+) -> Result<()> {
+    for (name, value) in extra {
+        if fields.contains_key(name) {
+            return Err(anyhow!("provider.extra_options.{} conflicts with a field AION sends", name));
+        }
+        fields.insert(name.clone(), value.clone());
+    }
+    Ok(())
+}
+
+/// The client for a config, built on first use and then reused.
+#[cfg(feature = "providers")]
+pub struct LazyClient {
+    app: AppConfig,
+    params: GenParams,
+    client: OnceLock<Arc<dyn ChatClient>>,
+}
+
+#[cfg(feature = "providers")]
+impl LazyClient {
+    pub fn new(app: &AppConfig) -> Self {
+        Self::with_params(app, &GenParams::default())
+    }
+
+    /// Like `new`, with request-level parameter overrides (see `config::params`).
+    pub fn with_params(app: &AppConfig, request: &GenParams) -> Self {
+        Self { app: app.clone(), params: *request, client: OnceLock::new() }
+    }
+
+    /// The client, building it now if this is the first use. A failed build is not
+    /// remembered; the next call tries again.
+    pub fn get(&self) -> Result<Arc<dyn ChatClient>> {
+        if let Some(client) = self.client.get() {
+            return Ok(Arc::clone(client));
+        }
+        let client: Arc<dyn ChatClient> = Arc::from(build_client_with_params(&self.app, &self.params)?);
+        Ok(Arc::clone(self.client.get_or_init(|| client)))
+    }
+}
+
+/// Read the API key from the environment variable named by `api_key_env`.
+#[cfg(feature = "providers")]
+pub fn resolve_api_key(cfg: &ProviderConfig) -> Result<Option<String>> {
+    if !cfg.kind.requires_api_key() {
+        return Ok(None);
+    }
+    let var = cfg
+        .api_key_env
+        .as_deref()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| anyhow!("provider.api_key_env is not configured"))?;
+    match std::env::var(var) {
+        Ok(key) if !key.trim().is_empty() => Ok(Some(key)),
+        _ => Err(anyhow!("environment variable {} is not set", var)),
+    }
+}
@@ -0,0 +1,355 @@
+//! Ollama client (`/api/chat`, NDJSON streaming).
+//!
+//! Notes:
+//! - Before the first request a TCP preflight (bounded by `PREFLIGHT_TIMEOUT`) checks that
+//!   the server is listening, so a stopped `ollama serve` fails fast with `OllamaUnreachable`
+//!   (wrapped in `ProviderError::Network`) instead of a raw connect error.
+//! - A successful preflight is cached for the client's lifetime; failures are not, so a
+//!   retry after starting the server works without restarting AION.
+//! - `provider.extra_options` go inside `options`, next to the params.
+//! - `done_reason: "length"` (`num_predict` reached) becomes `ChatResponse::stop`;
+//!   `parse_stream` decodes a recorded stream the way `chat` does.
+//!
+//! ```
+//! use aion_core::config::GenParams;
+//! use aion_core::providers::{ollama::OllamaClient, Message};
+//! use serde_json::json;
+//!
+//! let extra = json!({ "mirostat": 2, "stop": ["###"] }).as_object().unwrap().clone();
+//! let client = OllamaClient::new("http://localhost:11434".into(), "llama3".into())
+//!     .unwrap()
+//!     .with_params(GenParams { temperature: Some(0.5), ..Default::default() })
+//!     .with_extra_options(extra);
+//! let body = client.request_body(&[Message::user("hi")]).unwrap();
+//! assert_eq!(body["options"], json!({ "temperature": 0.5, "mirostat": 2, "stop": ["###"] }));
+//! assert_eq!(body["messages"][0]["content"], "hi");
+//!
+//! let clash = json!({ "temperature": 1 }).as_object().unwrap().clone();
+//! let client = OllamaClient::new("http://localhost:11434".into(), "llama3".into())
+//!     .unwrap()
+//!     .with_params(GenParams { temperature: Some(0.5), ..Default::default() })
+//!     .with_extra_options(clash);
+//! assert!(client.request_body(&[]).is_err());
+//!
+//! use aion_core::providers::{ollama::parse_stream, StopReason};
+//! let stream = |reason: &str| format!(
+//!     "{{\"message\":{{\"role\":\"assistant\",\"content\":\"Step one\"}},\"done\":false}}\n\
+//!      {{\"message\":{{\"role\":\"assistant\",\"content\":\"\"}},\"done\":true,\"done_reason\":\"{}\",\"eval_count\":2}}\n",
+//!     reason
+//! );
+//! let reply = parse_stream(&stream("length")).unwrap();
+//! assert_eq!((reply.content.as_str(), reply.stop), ("Step one", Some(StopReason::MaxTokens)));
+//! assert_eq!(parse_stream(&stream("stop")).unwrap().stop, None);
+//! ```
+
+use super::stream::{for_each_line, send};
+use super::{
+    merge_extra, ChatClient, ChatResponse, EmbeddingClient, Message, OnChunk, ModelInfo, ProviderError, StopReason, Usage,
+    CONNECT_TIMEOUT,
+};
+use crate::cancel::CancelToken;
+use crate::config::GenParams;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+pub const PREFLIGHT_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// The Ollama server did not accept a connection.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Ollama is not reachable at {base_url} — is `ollama serve` running?")]
+pub struct OllamaUnreachable {
+    pub base_url: String,
+}
+
+pub struct OllamaClient {
+    http: reqwest::Client,
+    base_url: String,
+    model: String,
+    preflight: bool,
+    reachable: AtomicBool,
+    params: GenParams,
+    /// Constrain replies to JSON (`format: "json"`).
+    json: bool,
+    /// `provider.extra_options`, added to `options`.
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    message: Option<ChunkMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkMessage {
+    #[serde(default)]
+    content: String,
+}
+
+/// One progress line from `/api/pull`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl PullProgress {
+    /// Completed fraction of the current layer, when Ollama reports sizes.
+    pub fn ratio(&self) -> Option<f64> {
+        match (self.completed, self.total) {
+            (Some(done), Some(total)) if total > 0 => Some((done as f64 / total as f64).min(1.0)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<TagEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagEntry {
+    name: String,
+}
+
+impl OllamaClient {
+    pub fn new(base_url: String, model: String) -> Result<Self> {
+        let http = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build()?;
+        Ok(Self {
+            http,
+            base_url,
+            model,
+            preflight: true,
+            reachable: AtomicBool::new(false),
+            params: GenParams::default(),
+            json: false,
+            extra: serde_json::Map::new(),
+        })
+    }
+
+    /// Send the params as `options` (`max_tokens` becomes `num_predict`).
+    pub fn with_params(mut self, params: GenParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Send `format: "json"` (`style.format = "json"`).
+    pub fn with_json(mut self, enabled: bool) -> Self {
+        self.json = enabled;
+        self
+    }
+
+    /// Add `extra` to `options` (`provider.extra_options`).
+    pub fn with_extra_options(mut self, extra: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// The `/api/chat` body for `messages`. Fails when an extra option names one AION sets.
+    pub fn request_body(&self, messages: &[Message]) -> Result<serde_json::Value> {
+        let p = &self.params;
+        let mut options = serde_json::Map::new();
+        for (name, value) in [
+            ("temperature", p.temperature.map(|v| json!(v))),
+            ("top_p", p.top_p.map(|v| json!(v))),
+            ("num_predict", p.max_tokens.map(|v| json!(v))),
+            ("num_ctx", p.num_ctx.map(|v| json!(v))),
+        ] {
+            if let Some(value) = value {
+                options.insert(name.to_string(), value);
+            }
+        }
+        merge_extra(&mut options, &self.extra)?;
+        let mut body = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+        });
+        if !options.is_empty() {
+            body["options"] = options.into();
+        }
+        if self.json {
+            body["format"] = json!("json");
+        }
+        Ok(body)
+    }
+
+    /// Enable or disable the connection preflight (disabled when network access is off).
+    pub fn with_preflight(mut self, enabled: bool) -> Self {
+        self.preflight = enabled;
+        self
+    }
+
+    /// Download `model` through `/api/pull`, reporting each progress line.
+    pub async fn pull(
+        &self,
+        model: &str,
+        cancel: &CancelToken,
+        on_progress: &mut (dyn FnMut(&PullProgress) + Send),
+    ) -> Result<()> {
+        self.ensure_reachable().await?;
+
+        // Older servers read `name`, newer ones `model`.
+        let body = json!({ "model": model, "name": model, "stream": true });
+        let req = self.http.post(format!("{}/api/pull", self.base_url)).json(&body);
+
+        let resp = send(req, cancel).await?;
+        let mut succeeded = false;
+        for_each_line(resp, cancel, |line| {
+            let progress: PullProgress = serde_json::from_str(line)?;
+            if let Some(err) = &progress.error {
+                return Err(ProviderError::InvalidRequest { message: err.clone() }.into());
+            }
+            on_progress(&progress);
+            succeeded = progress.status == "success";
+            Ok(!succeeded)
+        })
+        .await?;
+
+        if !succeeded {
+            return Err(anyhow!("ollama: pull of '{}' ended without success", model));
+        }
+        Ok(())
+    }
+
+    async fn ensure_reachable(&self) -> Result<()> {
+        if !self.preflight || self.reachable.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        if tcp_reachable(&self.base_url, PREFLIGHT_TIMEOUT).await {
+            self.reachable.store(true, Ordering::Relaxed);
+            Ok(())
+        } else {
+            Err(ProviderError::from(OllamaUnreachable {
+                base_url: self.base_url.clone(),
+            })
+            .into())
+        }
+    }
+}
+
+/// Try a TCP connection to the URL's host and port within `budget`, across all resolved
+/// addresses. Unparseable URLs are treated as reachable so the real request reports them.
+pub async fn tcp_reachable(base_url: &str, budget: Duration) -> bool {
+    let Ok(url) = reqwest::Url::parse(base_url) else {
+        return true;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return true;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    // `connect` tries every resolved address in turn; the budget covers all of them.
+    matches!(
+        tokio::time::timeout(budget, TcpStream::connect((host, port))).await,
+        Ok(Ok(_))
+    )
+}
+
+/// `/api/embeddings`, one request per text.
+#[async_trait]
+impl EmbeddingClient for OllamaClient {
+    async fn embed(&self, texts: &[String], cancel: &CancelToken) -> Result<Vec<Vec<f32>>> {
+        self.ensure_reachable().await?;
+        let mut out = Vec::with_capacity(texts.len());
+        for text in texts {
+            let body = json!({ "model": self.model, "prompt": text });
+            let req = self.http.post(format!("{}/api/embeddings", self.base_url)).json(&body);
+            let resp: EmbeddingResponse = send(req, cancel).await?.json().await?;
+            out.push(resp.embedding);
+        }
+        Ok(out)
+    }
+}
+
+/// A recorded `/api/chat` stream, decoded as `chat` decodes it.
+pub fn parse_stream(body: &str) -> Result<ChatResponse> {
+    let mut out = ChatResponse::default();
+    for line in body.lines().filter(|l| !l.trim().is_empty()) {
+        if !decode_line(line, &mut out, &mut |_| {})? {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// Add one NDJSON line to `out`; `false` once the reply is done.
+fn decode_line(line: &str, out: &mut ChatResponse, on_chunk: &mut OnChunk<'_>) -> Result<bool> {
+    let chunk: ChatChunk = serde_json::from_str(line)?;
+    // Errors after the stream has started arrive after HTTP 200 was sent.
+    if let Some(err) = chunk.error {
+        return Err(ProviderError::ServerError { status: 500, body_excerpt: err }.into());
+    }
+    if let Some(msg) = chunk.message {
+        on_chunk(&msg.content);
+        out.content.push_str(&msg.content);
+    }
+    if chunk.done {
+        out.usage = Some(Usage {
+            input_tokens: chunk.prompt_eval_count.unwrap_or(0),
+            output_tokens: chunk.eval_count.unwrap_or(0),
+        });
+        out.stop = chunk.done_reason.as_deref().and_then(StopReason::from_wire);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[async_trait]
+impl ChatClient for OllamaClient {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let body = self.request_body(messages)?;
+        self.ensure_reachable().await?;
+
+        let req = self.http.post(format!("{}/api/chat", self.base_url)).json(&body);
+
+        let resp = send(req, cancel).await?;
+        let mut out = ChatResponse::default();
+        for_each_line(resp, cancel, |line| decode_line(line, &mut out, on_chunk)).await?;
+        Ok(out)
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        self.ensure_reachable().await?;
+
+        let req = self.http.get(format!("{}/api/tags", self.base_url));
+        let tags: TagsResponse = send(req, &CancelToken::new()).await?.json().await?;
+        Ok(tags
+            .models
+            .into_iter()
+            .map(|m| ModelInfo { id: m.name, context_length: None })
+            .collect())
+    }
+}
@@ -0,0 +1,323 @@
+//! OpenAI-compatible client (`/chat/completions`, SSE streaming).
+//! Used for OpenAI and OpenRouter.
+//!
+//! Notes:
+//! - `provider.extra_options` go at the top level of the request.
+//! - A schema (`with_schema`, from `aion chat --schema`) is sent as a `json_schema`
+//!   response format, in place of the plain JSON object mode.
+//! - `finish_reason` `length` and `content_filter`, and a `refusal` in place of content,
+//!   become `ChatResponse::stop`; the refusal's text is the reply. `parse_stream` decodes
+//!   a recorded stream the way `chat` does.
+//!
+//! ```
+//! use aion_core::config::GenParams;
+//! use aion_core::providers::{openai::OpenAiClient, Message};
+//! use serde_json::json;
+//!
+//! let extra = json!({ "seed": 42, "logit_bias": { "50256": -100 } }).as_object().unwrap().clone();
+//! let client = OpenAiClient::new("https://api.openai.com/v1".into(), "gpt-4o-mini".into(), "key".into())
+//!     .unwrap()
+//!     .with_params(GenParams { max_tokens: Some(256), ..Default::default() })
+//!     .with_extra_options(extra);
+//! let body = client.request_body(&[Message::user("hi")]).unwrap();
+//! assert_eq!(body["seed"], 42);
+//! assert_eq!(body["logit_bias"], json!({ "50256": -100 }));
+//! assert_eq!(body["max_tokens"], 256);
+//! assert_eq!(body["stream"], true);
+//!
+//! let clash = json!({ "max_tokens": 10 }).as_object().unwrap().clone();
+//! let client = OpenAiClient::new("https://api.openai.com/v1".into(), "gpt-4o-mini".into(), "key".into())
+//!     .unwrap()
+//!     .with_params(GenParams { max_tokens: Some(256), ..Default::default() })
+//!     .with_extra_options(clash);
+//! assert!(client.request_body(&[]).is_err());
+//!
+//! let schema = json!({ "type": "object", "required": ["version"] });
+//! let client = OpenAiClient::new("https://api.openai.com/v1".into(), "gpt-4o-mini".into(), "key".into())
+//!     .unwrap()
+//!     .with_json(true)
+//!     .with_schema(Some(schema.clone()));
+//! let body = client.request_body(&[]).unwrap();
+//! assert_eq!(body["response_format"]["type"], "json_schema");
+//! assert_eq!(body["response_format"]["json_schema"]["schema"], schema);
+//!
+//! use aion_core::providers::{openai::parse_stream, StopReason};
+//! let stream = |last: &str| format!(
+//!     "data: {{\"choices\":[{{\"delta\":{{\"content\":\"Once upon\"}},\"finish_reason\":null}}]}}\n\n\
+//!      data: {{\"choices\":[{{\"delta\":{{}},\"finish_reason\":\"{}\"}}]}}\n\n\
+//!      data: [DONE]\n", last);
+//! let reply = parse_stream(&stream("length")).unwrap();
+//! assert_eq!((reply.content.as_str(), reply.stop), ("Once upon", Some(StopReason::MaxTokens)));
+//! assert_eq!(parse_stream(&stream("content_filter")).unwrap().stop, Some(StopReason::ContentFilter));
+//! assert_eq!(parse_stream(&stream("stop")).unwrap().stop, None);
+//!
+//! let refused = parse_stream(
+//!     "data: {\"choices\":[{\"delta\":{\"refusal\":\"I can't help with that.\"}}]}\n\n\
+//!      data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n",
+//! )
+//! .unwrap();
+//! assert_eq!((refused.content.as_str(), refused.stop), ("I can't help with that.", Some(StopReason::Refusal)));
+//! ```
+//!
+//! `provider.organization` and `provider.project` become headers on every OpenAI
+//! request, and must be valid header values to pass validation:
+//!
+//! ```
+//! use aion_core::config::{AppConfig, ConfigError, ProviderKind};
+//! use aion_core::providers::{openai::OpenAiClient, Message};
+//!
+//! let mut cfg = AppConfig::new_default().provider;
+//! cfg.kind = ProviderKind::OpenAI;
+//! cfg.base_url = None;
+//! cfg.model = "gpt-4o-mini".into();
+//! cfg.api_key_env = Some("OPENAI_API_KEY".into());
+//! cfg.organization = Some("org-fixture123".into());
+//! cfg.project = Some("proj_fixture456".into());
+//! cfg.validate().unwrap();
+//!
+//! let client = OpenAiClient::new(cfg.effective_base_url(), cfg.model.clone(), "sk-key".into())
+//!     .unwrap()
+//!     .with_headers(cfg.scope_headers());
+//! let request = client.request(&[Message::user("hi")]).unwrap();
+//! assert_eq!(request.url().as_str(), "https://api.openai.com/v1/chat/completions");
+//! let headers = request.headers();
+//! assert_eq!(headers["OpenAI-Organization"], "org-fixture123");
+//! assert_eq!(headers["OpenAI-Project"], "proj_fixture456");
+//! assert_eq!(headers["Authorization"], "Bearer sk-key");
+//!
+//! // Only OpenAI reads them; unset ones are not sent.
+//! cfg.project = None;
+//! assert_eq!(cfg.scope_headers(), [("OpenAI-Organization", "org-fixture123".to_string())]);
+//! cfg.kind = ProviderKind::OpenRouter;
+//! assert!(cfg.scope_headers().is_empty());
+//!
+//! cfg.kind = ProviderKind::OpenAI;
+//! for bad in ["", "org fixture", "org-\r\nX-Injected: 1", "org-\u{e9}", "org\t1"] {
+//!     cfg.organization = Some(bad.into());
+//!     let err = cfg.validate().unwrap_err();
+//!     assert!(matches!(&err, ConfigError::InvalidScopeId { field: "organization", value } if value == bad), "{:?}", err);
+//! }
+//! cfg.organization = None;
+//! cfg.project = Some("proj\n".into());
+//! assert!(matches!(cfg.validate(), Err(ConfigError::InvalidScopeId { field: "project", .. })));
+//! ```
+
+use super::stream::{for_each_line, send, sse_data};
+use super::{merge_extra, ChatClient, ChatResponse, Message, OnChunk, ModelInfo, StopReason, Usage, CONNECT_TIMEOUT};
+use crate::cancel::CancelToken;
+use crate::config::GenParams;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: String,
+    params: GenParams,
+    /// Sent with every request (`ProviderConfig::scope_headers`).
+    headers: Vec<(&'static str, String)>,
+    /// Ask for a JSON object (`response_format`).
+    json: bool,
+    /// Ask for JSON matching this schema (`response_format` of type `json_schema`).
+    schema: Option<serde_json::Value>,
+    /// `provider.extra_options`, added at the top level.
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<StreamUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Delta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+    /// Sent instead of `content` when the model declines.
+    #[serde(default)]
+    refusal: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+/// A recorded `/chat/completions` stream, decoded as `chat` decodes it.
+pub fn parse_stream(body: &str) -> Result<ChatResponse> {
+    let mut out = ChatResponse::default();
+    for line in body.lines() {
+        if !decode_line(line, &mut out, &mut |_| {})? {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// Add one SSE line to `out`; `false` at `[DONE]`.
+fn decode_line(line: &str, out: &mut ChatResponse, on_chunk: &mut OnChunk<'_>) -> Result<bool> {
+    let Some(data) = sse_data(line) else { return Ok(true) };
+    if data == "[DONE]" {
+        return Ok(false);
+    }
+    let chunk: StreamChunk = serde_json::from_str(data)?;
+    for choice in chunk.choices {
+        if let Some(text) = choice.delta.content {
+            on_chunk(&text);
+            out.content.push_str(&text);
+        }
+        if let Some(text) = choice.delta.refusal {
+            on_chunk(&text);
+            out.content.push_str(&text);
+            out.stop = Some(StopReason::Refusal);
+        }
+        if let Some(reason) = choice.finish_reason.as_deref().and_then(StopReason::from_wire) {
+            out.stop = Some(reason);
+        }
+    }
+    if let Some(u) = chunk.usage {
+        out.usage = Some(Usage {
+            input_tokens: u.prompt_tokens,
+            output_tokens: u.completion_tokens,
+        });
+    }
+    Ok(true)
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    #[serde(default)]
+    data: Vec<ModelEntry>,
+}
+
+/// `context_length` is an OpenRouter extension; OpenAI omits it.
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+    #[serde(default)]
+    context_length: Option<u64>,
+}
+
+impl OpenAiClient {
+    pub fn new(base_url: String, model: String, api_key: String) -> Result<Self> {
+        let http = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build()?;
+        Ok(Self { http, base_url, model, api_key, params: GenParams::default(), headers: Vec::new(), json: false, schema: None, extra: serde_json::Map::new() })
+    }
+
+    /// Send `temperature`, `top_p`, and `max_tokens` when set.
+    pub fn with_params(mut self, params: GenParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Add `headers` to every request, e.g. `OpenAI-Organization`.
+    pub fn with_headers(mut self, headers: Vec<(&'static str, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Send `response_format: {"type": "json_object"}` (`style.format = "json"`).
+    pub fn with_json(mut self, enabled: bool) -> Self {
+        self.json = enabled;
+        self
+    }
+
+    /// Send `response_format: {"type": "json_schema", ...}` with `schema`; it takes the
+    /// place of `with_json`.
+    pub fn with_schema(mut self, schema: Option<serde_json::Value>) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Add `extra` to every request body (`provider.extra_options`).
+    pub fn with_extra_options(mut self, extra: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// The `/chat/completions` body for `messages`. Fails when an extra option names a
+    /// field AION sets.
+    pub fn request_body(&self, messages: &[Message]) -> Result<serde_json::Value> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+            "stream_options": { "include_usage": true },
+        });
+        let p = &self.params;
+        for (name, value) in [("temperature", p.temperature.map(|v| json!(v))), ("top_p", p.top_p.map(|v| json!(v))), ("max_tokens", p.max_tokens.map(|v| json!(v)))] {
+            if let Some(value) = value {
+                body[name] = value;
+            }
+        }
+        if let Some(schema) = &self.schema {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": { "name": "reply", "schema": schema, "strict": false },
+            });
+        } else if self.json {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+        if let Some(fields) = body.as_object_mut() {
+            merge_extra(fields, &self.extra)?;
+        }
+        Ok(body)
+    }
+
+    /// The `/chat/completions` request for `messages`, with the API key and the extra
+    /// headers.
+    pub fn request(&self, messages: &[Message]) -> Result<reqwest::Request> {
+        let body = self.request_body(messages)?;
+        Ok(self.authorized(self.http.post(format!("{}/chat/completions", self.base_url))).json(&body).build()?)
+    }
+
+    /// `req` with the API key and the extra headers.
+    fn authorized(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        self.headers.iter().fold(req.bearer_auth(&self.api_key), |req, (name, value)| req.header(*name, value))
+    }
+}
+
+#[async_trait]
+impl ChatClient for OpenAiClient {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        cancel: &CancelToken,
+        on_chunk: &mut OnChunk<'_>,
+    ) -> Result<ChatResponse> {
+        let req = reqwest::RequestBuilder::from_parts(self.http.clone(), self.request(messages)?);
+        let resp = send(req, cancel).await?;
+        let mut out = ChatResponse::default();
+        for_each_line(resp, cancel, |line| decode_line(line, &mut out, on_chunk)).await?;
+        Ok(out)
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let req = self.authorized(self.http.get(format!("{}/models", self.base_url)));
+        let list: ModelsResponse = send(req, &CancelToken::new()).await?.json().await?;
+        let mut models: Vec<ModelInfo> = list
+            .data
+            .into_iter()
+            .map(|m| ModelInfo { id: m.id, context_length: m.context_length })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(models)
+    }
+}
@@ -0,0 +1,148 @@
+//! Shared plumbing for streamed HTTP replies (NDJSON and server-sent events).
+//!
+//! Notes:
+//! - `send` retries retryable `ProviderError`s (rate limits, timeouts, 5xx, dropped
+//!   connections) up to `MAX_ATTEMPTS` times, but only before any of the reply has been
+//!   read, so nothing streamed is ever repeated. `retry_delay` is the policy, shared
+//!   with the scripted client (`mock`).
+//! - Every attempt and reply is logged here when `http_log` is on; clients need not.
+
+use super::ProviderError;
+use crate::cancel::CancelToken;
+use crate::http_log::{self, StreamTrace};
+use anyhow::Result;
+use std::time::Duration;
+
+pub const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Longest `Retry-After` honored; anything longer is left to the user.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Send a request, racing it against cancellation, and map failures to `ProviderError`.
+pub async fn send(req: reqwest::RequestBuilder, cancel: &CancelToken) -> Result<reqwest::Response> {
+    let mut attempt = 1;
+    let mut req = req;
+    loop {
+        let retry = if attempt < MAX_ATTEMPTS { req.try_clone() } else { None };
+        let err = match send_once(req, cancel).await {
+            Ok(resp) => return Ok(resp),
+            Err(e) => e,
+        };
+        let (Some(next), Some(delay)) = (retry, retry_delay(&err, attempt)) else { return Err(err.into()) };
+        pause(delay, cancel).await?;
+        req = next;
+        attempt += 1;
+    }
+}
+
+/// How long to wait after attempt number `attempt` failed with `err` before sending
+/// again; `None` when `err` is final.
+pub fn retry_delay(err: &ProviderError, attempt: u32) -> Option<Duration> {
+    match err {
+        ProviderError::RateLimited { retry_after: Some(d) } if *d > MAX_RETRY_DELAY => None,
+        ProviderError::RateLimited { retry_after: Some(d) } => Some(*d),
+        e if e.is_retryable() => Some(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)),
+        _ => None,
+    }
+}
+
+/// Sleep for `delay` unless cancelled first.
+pub async fn pause(delay: Duration, cancel: &CancelToken) -> Result<(), ProviderError> {
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => Ok(()),
+        _ = cancel.cancelled() => Err(ProviderError::Cancelled),
+    }
+}
+
+async fn send_once(req: reqwest::RequestBuilder, cancel: &CancelToken) -> Result<reqwest::Response, ProviderError> {
+    if http_log::enabled() {
+        if let Some(built) = req.try_clone().and_then(|r| r.build().ok()) {
+            http_log::request(&built);
+        }
+    }
+    let resp = tokio::select! {
+        r = req.send() => r.map_err(ProviderError::from_reqwest)?,
+        _ = cancel.cancelled() => return Err(ProviderError::Cancelled),
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let url = resp.url().to_string();
+        let body = resp.text().await.unwrap_or_default();
+        http_log::response(&url, status.as_u16(), Some(&body));
+        return Err(ProviderError::from_status(status.as_u16(), retry_after.as_deref(), &body));
+    }
+    http_log::response(resp.url().as_str(), status.as_u16(), None);
+    Ok(resp)
+}
+
+/// Feed the response body line by line to `on_line` until the stream ends or is cancelled.
+/// `on_line` returns `false` to stop early (e.g. on a terminal `[DONE]` event).
+pub async fn for_each_line(
+    mut resp: reqwest::Response,
+    cancel: &CancelToken,
+    mut on_line: impl FnMut(&str) -> Result<bool>,
+) -> Result<()> {
+    let mut buf = LineBuffer::default();
+    let mut trace = StreamTrace::start(resp.url().as_str());
+    loop {
+        let chunk = tokio::select! {
+            c = resp.chunk() => c.map_err(ProviderError::from_reqwest)?,
+            _ = cancel.cancelled() => return Err(ProviderError::Cancelled.into()),
+        };
+        let Some(chunk) = chunk else { break };
+        if let Some(trace) = trace.as_mut() {
+            trace.chunk(&chunk);
+        }
+
+        for line in buf.push(&chunk) {
+            if !on_line(&line)? {
+                return Ok(());
+            }
+        }
+    }
+
+    if let Some(rest) = buf.finish() {
+        on_line(&rest)?;
+    }
+    Ok(())
+}
+
+/// Splits a byte stream into lines, keeping partial lines (and partial UTF-8) buffered.
+#[derive(Debug, Default)]
+pub struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+
+        let mut lines = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\r', '\n']);
+            if !text.is_empty() {
+                lines.push(text.to_string());
+            }
+        }
+        lines
+    }
+
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = String::from_utf8_lossy(&self.pending).trim().to_string();
+        self.pending.clear();
+        (!rest.is_empty()).then_some(rest)
+    }
+}
+
+/// Payload of a server-sent event `data:` line, if this line is one.
+pub fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim_start)
+}
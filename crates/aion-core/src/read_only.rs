@@ -0,0 +1,175 @@
+//! Read-only mode (`aion --read-only`, or `AION_READ_ONLY=1`): the process changes no files,
+//! whatever the config says. For demos and shared machines.
+//!
+//! Notes:
+//! - Process-wide like `persistence`: `init` once the flags are parsed, before the config
+//!   is loaded. It also makes the process ephemeral.
+//! - `config::io` passes every config it loads through `apply`, which turns off
+//!   `caps.write_files`, `caps.run_commands` (and `features.safe_execute` with it), the
+//!   cache, and the audit and HTTP logs, and makes history ephemeral. Everything that asks
+//!   the config or `persistence` before writing follows without a check of its own.
+//! - A cap turned off here is refused as read-only mode: `CapabilityDenied` names the flag
+//!   rather than the `caps` switch.
+//! - Writes that never asked the caps call `check`: saving the config, the setup wizard,
+//!   templates, `aion init`, `aion import`, and removing sessions, cached replies, or the audit log.
+//!
+//! ```
+//! use aion_core::cache::ResponseCache;
+//! use aion_core::config::io::{load_config, save_config};
+//! use aion_core::paths::config_dir;
+//! use aion_core::config::{AppConfig, ProviderKind};
+//! use aion_core::{audit, persistence, read_only, sessions};
+//! use std::path::{Path, PathBuf};
+//!
+//! let home = std::env::temp_dir().join(format!("aion-read-only-{}", std::process::id()));
+//! std::env::set_var("XDG_CONFIG_HOME", &home);
+//! std::env::set_var("HOME", &home);
+//!
+//! let mut cfg = AppConfig::new_default();
+//! cfg.caps.write_files = true;
+//! cfg.caps.run_commands = true;
+//! cfg.cache.enabled = true;
+//! cfg.logging.audit = Some(true);
+//! save_config(&cfg).unwrap();
+//!
+//! fn files(dir: &Path, out: &mut Vec<(PathBuf, std::time::SystemTime)>) {
+//!     for entry in std::fs::read_dir(dir).unwrap().flatten() {
+//!         let meta = entry.metadata().unwrap();
+//!         out.push((entry.path(), meta.modified().unwrap()));
+//!         if meta.is_dir() {
+//!             files(&entry.path(), out);
+//!         }
+//!     }
+//! }
+//! let mut before = Vec::new();
+//! files(&home, &mut before);
+//!
+//! read_only::init(true);
+//! let cfg = load_config().unwrap();
+//! assert!(!cfg.caps.write_files && !cfg.caps.run_commands && !cfg.cache.enabled);
+//! assert!(persistence::policy().ephemeral);
+//!
+//! // A chat: the reply is recorded and saved, written nowhere.
+//! audit::init(&cfg);
+//! let mut session = sessions::Session::new();
+//! session.record("hi", "hello", &ProviderKind::Ollama, "llama3", None);
+//! session.save().unwrap();
+//! assert!(ResponseCache::open(&cfg).unwrap().is_none());
+//!
+//! // What the user asks for is refused, naming the mode.
+//! let target = home.join("reply.md");
+//! let saved = sessions::save_transcript(&cfg, &session, target.to_str().unwrap(), false).unwrap_err();
+//! assert_eq!(saved.to_string(), "read-only mode (--read-only): writing files is off");
+//! assert!(save_config(&cfg).unwrap_err().to_string().starts_with("read-only mode (--read-only)"));
+//! assert!(sessions::prune(1).is_err());
+//!
+//! let mut after = Vec::new();
+//! files(&home, &mut after);
+//! assert_eq!(before, after);
+//! assert!(config_dir().unwrap().join("config.toml").exists());
+//! std::fs::remove_dir_all(&home).unwrap();
+//! ```
+
+use crate::config::AppConfig;
+use crate::persistence::{self, PersistencePolicy};
+use std::sync::RwLock;
+
+/// Turns read-only mode on when set to anything but empty, `0`, or `false`.
+pub const ENV_VAR: &str = "AION_READ_ONLY";
+
+/// The `caps` this mode turns off.
+pub const DENIED_CAPS: [&str; 2] = ["write_files", "run_commands"];
+
+/// What turned read-only mode on, as named in its messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Flag,
+    Env,
+}
+
+impl Trigger {
+    pub fn name(self) -> &'static str {
+        match self {
+            Trigger::Flag => "--read-only",
+            Trigger::Env => "AION_READ_ONLY=1",
+        }
+    }
+}
+
+/// An action read-only mode refuses. `action` is a `DENIED_CAPS` name or one passed to
+/// `check`.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("read-only mode ({}): {}", .trigger.name(), describe(.action))]
+pub struct ReadOnlyMode {
+    pub action: &'static str,
+    pub trigger: Trigger,
+}
+
+static GLOBAL: RwLock<Option<Trigger>> = RwLock::new(None);
+
+/// Turn read-only mode on for the rest of the process with `--read-only` (`flag`) or
+/// `AION_READ_ONLY`; otherwise leave it off.
+pub fn init(flag: bool) {
+    let trigger = match flag {
+        true => Some(Trigger::Flag),
+        false => env_set().then_some(Trigger::Env),
+    };
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = trigger;
+    if trigger.is_some() {
+        persistence::init(PersistencePolicy { ephemeral: true });
+    }
+}
+
+fn env_set() -> bool {
+    std::env::var(ENV_VAR).is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false"))
+}
+
+/// How read-only mode was turned on, when it is.
+pub fn active() -> Option<Trigger> {
+    *GLOBAL.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// `cfg` as read-only mode lets it act; unchanged when the mode is off.
+pub fn apply(cfg: &mut AppConfig) {
+    if active().is_none() {
+        return;
+    }
+    cfg.caps.write_files = false;
+    cfg.caps.run_commands = false;
+    // Off with the commands it guards, so the config does not warn about the pair.
+    cfg.features.safe_execute = false;
+    cfg.cache.enabled = false;
+    cfg.logging.audit = Some(false);
+    cfg.logging.http_debug = false;
+    cfg.history.default_ephemeral = true;
+}
+
+/// Refuse `action` (see `describe`) when read-only mode is on.
+pub fn check(action: &'static str) -> Result<(), ReadOnlyMode> {
+    match active() {
+        Some(trigger) => Err(ReadOnlyMode { action, trigger }),
+        None => Ok(()),
+    }
+}
+
+/// The refusal for `cap`, when it is off because of this mode rather than the config.
+pub fn denied_cap(cap: &'static str) -> Option<ReadOnlyMode> {
+    check(cap).err().filter(|_| DENIED_CAPS.contains(&cap))
+}
+
+/// What is refused, in English.
+pub fn describe(action: &str) -> &'static str {
+    match action {
+        "write_files" => "writing files is off",
+        "run_commands" => "running commands is off",
+        "save_config" => "the config cannot be saved",
+        "setup" => "the setup wizard cannot run",
+        "templates" => "templates cannot be saved or removed",
+        "init" => "aion init cannot write files",
+        "import" => "aion import cannot write files",
+        "prune_sessions" => "sessions cannot be pruned",
+        "clear_cache" => "the cache cannot be cleared",
+        "clear_audit" => "the audit log cannot be cleared",
+        _ => "this action is not allowed",
+    }
+}
@@ -0,0 +1,132 @@
+//! Recently used model names per provider (`recent_models.json` in the state dir,
+//! `crate::paths`).
+//!
+//! Notes:
+//! - History, not settings, so it is kept out of config.toml. `save_config` records the
+//!   saved provider/model pair; the wizard's model step and the REPL's `/model`
+//!   completion read it.
+//! - Most recent first, at most `MAX_PER_PROVIDER` names per provider. Names are
+//!   compared case-sensitively (`Llama3` and `llama3` are different models).
+//! - A missing or unreadable file reads as empty; the list is a convenience and never
+//!   fails the caller.
+//!
+//! ```
+//! use aion_core::config::ProviderKind;
+//! use aion_core::recent::{RecentModels, MAX_PER_PROVIDER};
+//!
+//! let mut recent = RecentModels::default();
+//! recent.push(&ProviderKind::Ollama, "llama3");
+//! recent.push(&ProviderKind::Ollama, " mistral ");
+//! recent.push(&ProviderKind::OpenAI, "gpt-4o");
+//! recent.push(&ProviderKind::Ollama, "llama3");
+//! recent.push(&ProviderKind::Ollama, "  ");
+//! assert_eq!(recent.models(&ProviderKind::Ollama), ["llama3", "mistral"]);
+//!
+//! // Saved and read back unchanged, in a stable layout keyed by provider id.
+//! let json = recent.to_json().unwrap();
+//! assert_eq!(json, "{\n  \"ollama\": [\n    \"llama3\",\n    \"mistral\"\n  ],\n  \"openai\": [\n    \"gpt-4o\"\n  ]\n}\n");
+//! assert_eq!(RecentModels::from_json(&json).unwrap(), recent);
+//! assert!(RecentModels::from_json("{}").unwrap().models(&ProviderKind::Claude).is_empty());
+//!
+//! // A hand-edited file is read back with its duplicates, blanks and overflow dropped,
+//! // and then round-trips as it is.
+//! let names: Vec<String> = (0..12).map(|n| format!("\"m{}\"", n % 10)).collect();
+//! let edited = format!("{{\"ollama\": [\"\", \"Llama3\", \"llama3\", \"Llama3\", {}]}}", names.join(", "));
+//! let read = RecentModels::from_json(&edited).unwrap();
+//! let kept = read.models(&ProviderKind::Ollama);
+//! assert_eq!(kept.len(), MAX_PER_PROVIDER);
+//! assert_eq!(kept[..4], ["Llama3", "llama3", "m0", "m1"]);
+//! assert_eq!(RecentModels::from_json(&read.to_json().unwrap()).unwrap(), read);
+//!
+//! assert!(RecentModels::from_json("[\"llama3\"]").is_err());
+//! ```
+
+use crate::audit;
+use crate::config::ProviderKind;
+use crate::paths;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const RECENT_FILE_NAME: &str = "recent_models.json";
+
+/// Names kept per provider.
+pub const MAX_PER_PROVIDER: usize = 8;
+
+/// Model names by provider id (`ollama`, `openai`, ...), most recent first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RecentModels {
+    by_provider: BTreeMap<String, Vec<String>>,
+}
+
+pub fn recent_file_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(RECENT_FILE_NAME))
+}
+
+impl RecentModels {
+    pub fn load() -> Self {
+        recent_file_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| Self::from_json(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        let mut recent: Self = serde_json::from_str(text).context("failed to parse recent models")?;
+        // Hand-edited files may break the invariants; restore them.
+        for names in recent.by_provider.values_mut() {
+            let mut kept: Vec<String> = Vec::new();
+            for name in names.drain(..) {
+                if !name.trim().is_empty() && !kept.contains(&name) {
+                    kept.push(name);
+                }
+            }
+            kept.truncate(MAX_PER_PROVIDER);
+            *names = kept;
+        }
+        Ok(recent)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self).context("failed to serialize recent models")? + "\n")
+    }
+
+    /// Names used with `kind`, most recent first.
+    pub fn models(&self, kind: &ProviderKind) -> &[String] {
+        self.by_provider.get(kind.id()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Move `model` to the front of `kind`'s list, dropping the oldest beyond the cap.
+    pub fn push(&mut self, kind: &ProviderKind, model: &str) {
+        let model = model.trim();
+        if model.is_empty() {
+            return;
+        }
+        let names = self.by_provider.entry(kind.id().to_string()).or_default();
+        names.retain(|n| n != model);
+        names.insert(0, model.to_string());
+        names.truncate(MAX_PER_PROVIDER);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        paths::ensure(&paths::state_dir()?)?;
+        let path = recent_file_path()?;
+        fs::write(&path, self.to_json()?).with_context(|| format!("failed to write {}", path.display()))?;
+        audit::file_write(&path);
+        Ok(())
+    }
+}
+
+/// Record that `model` was saved as `kind`'s model.
+pub fn record(kind: &ProviderKind, model: &str) -> Result<()> {
+    let mut recent = RecentModels::load();
+    if recent.models(kind).first().map(String::as_str) == Some(model.trim()) {
+        return Ok(());
+    }
+    recent.push(kind, model);
+    recent.save()
+}
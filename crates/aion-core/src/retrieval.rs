@@ -0,0 +1,213 @@
+//! Retrieval over attachments (`features.retrieval`, `aion chat --retrieval`): each
+//! message carries the parts of the attached files most like it, not the whole files.
+//!
+//! Notes:
+//! - Files are cut into overlapping passages (`pipeline::split` with `PASSAGE`) and
+//!   embedded with an `EmbeddingClient`; only Ollama has one
+//!   (`providers::build_embedding_client`).
+//! - The index lives in memory for the session. Passages are embedded when a message first
+//!   needs them, so attaching is instant and a session that never asks embeds nothing.
+//! - Per message, the message is embedded and the `top_k` passages by cosine similarity
+//!   go in front of it the way `--file` puts whole files, labelled with their file and
+//!   character range, in the order they appear in the files.
+//! - Vectors of different lengths (another embedding model) never match: similarity 0.
+//!
+//! ```
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::providers::EmbeddingClient;
+//! use aion_core::retrieval::{cosine, top_k, Index, Retriever};
+//! use std::sync::Arc;
+//!
+//! assert_eq!(cosine(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+//! assert_eq!(cosine(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+//! assert_eq!(cosine(&[1.0, 0.0], &[1.0, 0.0, 0.0]), 0.0);
+//! let vectors = [vec![0.0, 1.0], vec![1.0, 0.1], vec![1.0, 1.0]];
+//! assert_eq!(top_k(&[1.0, 0.0], &vectors, 2), [1, 2]);
+//!
+//! // Synthetic embeddings: one dimension per topic word.
+//! struct Topics;
+//! #[async_trait::async_trait]
+//! impl EmbeddingClient for Topics {
+//!     async fn embed(&self, texts: &[String], _: &CancelToken) -> anyhow::Result<Vec<Vec<f32>>> {
+//!         let count = |t: &str, w: &str| t.matches(w).count() as f32;
+//!         Ok(texts.iter().map(|t| vec![count(t, "tokio"), count(t, "serde"), count(t, "clap")]).collect())
+//!     }
+//! }
+//!
+//! let mut index = Index::default();
+//! assert_eq!(index.add("notes.md", "tokio runs the tasks"), 1);
+//! assert_eq!(index.pending().len(), 1);
+//!
+//! let retriever = Retriever::new(Arc::new(Topics), 1);
+//! retriever.add("a.rs", "use serde; serde derives");
+//! retriever.add("b.rs", "use tokio; tokio::spawn");
+//! retriever.add("c.rs", "use clap;");
+//! let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let picked = rt.block_on(retriever.select("why does tokio::spawn need Send?", &CancelToken::new())).unwrap();
+//! assert_eq!((picked.len(), picked[0].source.as_str()), (1, "b.rs"));
+//! let prompt = rt.block_on(retriever.prompt("and serde?", &CancelToken::new())).unwrap();
+//! assert!(prompt.contains("a.rs (characters 0-24)") && prompt.ends_with("and serde?") && !prompt.contains("b.rs"));
+//! ```
+
+use crate::cancel::CancelToken;
+use crate::pipeline::{self, Budget};
+use crate::providers::EmbeddingClient;
+use anyhow::{anyhow, Result};
+use std::sync::{Arc, Mutex};
+
+/// How attachments are cut up: passages small enough that several fit in a message.
+pub const PASSAGE: Budget = Budget { chunk_chars: 1_500, overlap_chars: 200 };
+
+/// Passages sent with each message.
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// Part of an attached file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passage {
+    /// The file, as the user named it.
+    pub source: String,
+    /// Character offsets in the file.
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Passage {
+    /// The passage fenced like an attached file (`aion chat --file`), labelled with the file
+    /// and range, ending in a newline.
+    pub fn fenced(&self) -> String {
+        let longest =
+            self.text.lines().map(|l| l.trim_start().chars().take_while(|&c| c == '`').count()).max().unwrap_or(0);
+        let fence = "`".repeat(longest.max(2) + 1);
+        let text = self.text.trim_end_matches('\n');
+        format!("{}{} (characters {}-{})\n{}\n{}\n", fence, self.source, self.start, self.end, text, fence)
+    }
+}
+
+/// Cosine similarity of `a` and `b`; 0 when their lengths differ or either is all zeros.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    match norm(a) * norm(b) {
+        n if n > 0.0 => dot / n,
+        _ => 0.0,
+    }
+}
+
+/// Indices of the `k` vectors most like `query`, most alike first.
+pub fn top_k(query: &[f32], vectors: &[Vec<f32>], k: usize) -> Vec<usize> {
+    let mut scored: Vec<(usize, f32)> = vectors.iter().enumerate().map(|(i, v)| (i, cosine(query, v))).collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.into_iter().take(k).map(|(i, _)| i).collect()
+}
+
+/// Passages and, once embedded, their vectors.
+#[derive(Debug, Default)]
+pub struct Index {
+    passages: Vec<Passage>,
+    vectors: Vec<Option<Vec<f32>>>,
+}
+
+impl Index {
+    /// Cut `text` into passages from `source`; how many.
+    pub fn add(&mut self, source: &str, text: &str) -> usize {
+        let chunks = pipeline::split(text, PASSAGE);
+        let added = chunks.len();
+        for chunk in chunks {
+            self.passages.push(Passage { source: source.to_string(), start: chunk.start, end: chunk.end, text: chunk.text });
+            self.vectors.push(None);
+        }
+        added
+    }
+
+    pub fn len(&self) -> usize {
+        self.passages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passages.is_empty()
+    }
+
+    /// Passages not embedded yet, with their positions.
+    pub fn pending(&self) -> Vec<(usize, String)> {
+        let missing = self.vectors.iter().enumerate().filter(|(_, v)| v.is_none());
+        missing.map(|(i, _)| (i, self.passages[i].text.clone())).collect()
+    }
+
+    /// Record the vector for the passage at `position`.
+    pub fn store(&mut self, position: usize, vector: Vec<f32>) {
+        if let Some(slot) = self.vectors.get_mut(position) {
+            *slot = Some(vector);
+        }
+    }
+
+    /// The `k` embedded passages most like `query`, in file order.
+    pub fn nearest(&self, query: &[f32], k: usize) -> Vec<Passage> {
+        let embedded: Vec<(usize, &Vec<f32>)> =
+            self.vectors.iter().enumerate().filter_map(|(i, v)| v.as_ref().map(|v| (i, v))).collect();
+        let vectors: Vec<Vec<f32>> = embedded.iter().map(|(_, v)| (*v).clone()).collect();
+        let mut picked: Vec<usize> = top_k(query, &vectors, k).into_iter().map(|i| embedded[i].0).collect();
+        picked.sort_unstable();
+        picked.into_iter().map(|i| self.passages[i].clone()).collect()
+    }
+}
+
+/// A session's attachments and the client that embeds them.
+pub struct Retriever {
+    client: Arc<dyn EmbeddingClient>,
+    index: Mutex<Index>,
+    top_k: usize,
+}
+
+impl Retriever {
+    pub fn new(client: Arc<dyn EmbeddingClient>, top_k: usize) -> Self {
+        Self { client, index: Mutex::new(Index::default()), top_k: top_k.max(1) }
+    }
+
+    /// Attach `text` from `source`; how many passages it made. Nothing is embedded yet.
+    pub fn add(&self, source: &str, text: &str) -> usize {
+        self.lock().add(source, text)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// The passages most like `query`, embedding whatever is new first.
+    pub async fn select(&self, query: &str, cancel: &CancelToken) -> Result<Vec<Passage>> {
+        let pending = self.lock().pending();
+        if !pending.is_empty() {
+            let texts: Vec<String> = pending.iter().map(|(_, text)| text.clone()).collect();
+            let vectors = self.client.embed(&texts, cancel).await?;
+            if vectors.len() != texts.len() {
+                return Err(anyhow!("the provider returned {} embeddings for {} passages", vectors.len(), texts.len()));
+            }
+            let mut index = self.lock();
+            for ((position, _), vector) in pending.into_iter().zip(vectors) {
+                index.store(position, vector);
+            }
+        }
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = self.client.embed(&[query.to_string()], cancel).await?;
+        let query = query.into_iter().next().ok_or_else(|| anyhow!("the provider returned no embedding"))?;
+        Ok(self.lock().nearest(&query, self.top_k))
+    }
+
+    /// `query` with the passages most like it in front; unchanged when nothing is attached.
+    pub async fn prompt(&self, query: &str, cancel: &CancelToken) -> Result<String> {
+        let passages = self.select(query, cancel).await?;
+        if passages.is_empty() {
+            return Ok(query.to_string());
+        }
+        Ok(passages.iter().map(Passage::fenced).chain([query.to_string()]).collect::<Vec<_>>().join("\n"))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
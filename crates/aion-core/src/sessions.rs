@@ -0,0 +1,678 @@
+//! Saved chat sessions and their export formats.
+//!
+//! Notes:
+//! - Each session is one journal under `sessions/` in the state dir (`crate::paths`),
+//!   appended to after every completed exchange (`crate::journal`); reading one goes
+//!   through `journal::read`.
+//!   Failed and cancelled turns are never recorded.
+//! - `--resume` continues a saved session (`resume`). The process that does holds its file
+//!   until it exits, so a second `--resume` of the same session is refused.
+//! - Timestamps are Unix seconds; exports render them as UTC RFC 3339.
+//! - `to_markdown` is shared by `aion sessions export` and the REPL's `/save --all`.
+//! - Both formats (and therefore the session files) pass through `redact`.
+//! - `sessions/index.json` holds one summary row per session (title, counts, provider,
+//!   timestamps) so listing and searching do not read every session file. `save` keeps
+//!   it current; `index` repairs it in memory from the files when it is missing or stale.
+//! - `/regen` and `/undo` never delete: the replaced reply, or the undone exchange, is
+//!   kept and marked `superseded`. Its usage still counts towards the totals, since those
+//!   tokens were spent.
+//! - `/continue` adds to the last reply (`extend_reply`) rather than recording an
+//!   exchange; the prompt that asked for it is not kept.
+//! - `history.retention_days` deletes sessions by file time (`prune`), at startup or with
+//!   `aion sessions prune`. Nothing is saved in an ephemeral process (`crate::persistence`).
+//! - Titles are generated once, after the second exchange, when `sessions.auto_title`
+//!   is on. A failed title request is retried after the next exchange.
+//!
+//! Both exports of a saved session, against their golden files:
+//!
+//! ```
+//! use aion_core::sessions::{to_json, to_markdown, Session};
+//!
+//! let fixture = |name: &str| std::fs::read_to_string(format!("{}/fixtures/sessions/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap();
+//! let session: Session = serde_json::from_str(&fixture("session.json")).unwrap();
+//!
+//! assert_eq!(to_markdown(&session), fixture("export.md"));
+//! assert_eq!(to_json(&session).unwrap(), fixture("export.json"));
+//! let back: Session = serde_json::from_str(&fixture("export.json")).unwrap();
+//! assert_eq!(back, session);
+//! ```
+
+use crate::audit;
+use crate::cancel::CancelToken;
+use crate::config::{AppConfig, CapabilityDenied, ProviderKind};
+use crate::conversation::Conversation;
+use crate::guard::Guard;
+use crate::journal;
+use crate::paths;
+use crate::persistence;
+use crate::providers::{ChatClient, Message, Role, Usage};
+use crate::redact;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SESSIONS_DIR_NAME: &str = "sessions";
+const INDEX_FILE_NAME: &str = "index.json";
+const JOURNAL_EXTENSION: &str = "jsonl";
+/// Session files from before the journal: one JSON document each.
+const LEGACY_EXTENSION: &str = "json";
+
+/// Exchanges after which a title is requested.
+const TITLE_AFTER_EXCHANGES: usize = 2;
+/// Longest title kept, in words.
+const TITLE_MAX_WORDS: usize = 8;
+/// Characters of each message sent with the title request.
+const TITLE_EXCERPT_CHARS: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMessage {
+    pub role: Role,
+    pub content: String,
+    pub at: u64,
+    /// Model that produced an assistant message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Token usage reported for an assistant message, when the provider sent it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    /// Replaced by `/regen` or dropped by `/undo`; no longer part of the conversation.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub superseded: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Short generated title, once there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Provider of the latest reply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderKind>,
+    #[serde(default)]
+    pub messages: Vec<SessionMessage>,
+}
+
+/// Output format for `aion sessions export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ExportFormat {
+    Md,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Md => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn render(self, session: &Session) -> Result<String> {
+        Ok(match self {
+            ExportFormat::Md => to_markdown(session),
+            ExportFormat::Json => to_json(session)?,
+        })
+    }
+}
+
+pub fn sessions_dir() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(SESSIONS_DIR_NAME))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        let at = now();
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            created_at: at,
+            updated_at: at,
+            title: None,
+            provider: None,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Record a completed exchange: the user turn and the reply from `kind`'s `model`.
+    pub fn record(&mut self, user: &str, reply: &str, kind: &ProviderKind, model: &str, usage: Option<Usage>) {
+        let at = now();
+        self.messages.push(SessionMessage {
+            role: Role::User,
+            content: user.to_string(),
+            at,
+            model: None,
+            usage: None,
+            superseded: false,
+        });
+        self.push_reply(reply, kind, model, usage, at);
+    }
+
+    /// Record a new reply to the last prompt (`/regen`); the reply it replaces is kept,
+    /// marked superseded.
+    pub fn regenerate(&mut self, reply: &str, kind: &ProviderKind, model: &str, usage: Option<Usage>) {
+        if let Some(old) = self.live_mut().rev().find(|m| m.role == Role::Assistant) {
+            old.superseded = true;
+        }
+        self.push_reply(reply, kind, model, usage, now());
+    }
+
+    /// Mark the last live exchange superseded (`/undo`). `false` when there is none.
+    pub fn undo(&mut self) -> bool {
+        let Some(start) = self.messages.iter().rposition(|m| m.role == Role::User && !m.superseded) else {
+            return false;
+        };
+        for m in &mut self.messages[start..] {
+            m.superseded = true;
+        }
+        self.updated_at = now();
+        true
+    }
+
+    /// Add `more` to the last live reply (`/continue`), with its usage. `false` when there
+    /// is none.
+    pub fn extend_reply(&mut self, more: &str, usage: Option<Usage>) -> bool {
+        let Some(last) = self.live_mut().rev().find(|m| m.role == Role::Assistant) else {
+            return false;
+        };
+        last.content.push_str(more);
+        if let Some(u) = usage {
+            let total = last.usage.get_or_insert_with(Usage::default);
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+        }
+        self.updated_at = now();
+        true
+    }
+
+    fn push_reply(&mut self, reply: &str, kind: &ProviderKind, model: &str, usage: Option<Usage>, at: u64) {
+        self.messages.push(SessionMessage {
+            role: Role::Assistant,
+            content: reply.to_string(),
+            at,
+            model: Some(model.to_string()),
+            usage,
+            superseded: false,
+        });
+        self.provider = Some(kind.clone());
+        self.updated_at = at;
+    }
+
+    /// Messages still in the conversation, oldest first.
+    pub fn live(&self) -> impl DoubleEndedIterator<Item = &SessionMessage> {
+        self.messages.iter().filter(|m| !m.superseded)
+    }
+
+    fn live_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut SessionMessage> {
+        self.messages.iter_mut().filter(|m| !m.superseded)
+    }
+
+    /// Whether a title should be requested now (see `generate_title`).
+    pub fn wants_title(&self, cfg: &AppConfig) -> bool {
+        cfg.sessions.auto_title && self.title.is_none() && self.exchanges() >= TITLE_AFTER_EXCHANGES
+    }
+
+    fn exchanges(&self) -> usize {
+        self.live().filter(|m| m.role == Role::User).count()
+    }
+
+    /// The live exchanges, as the provider is sent them: for a resumed session.
+    pub fn conversation(&self) -> Conversation {
+        let mut conversation = Conversation::new();
+        let mut prompt = None;
+        for m in self.live() {
+            match m.role {
+                Role::User => prompt = Some(&m.content),
+                Role::Assistant => {
+                    if let Some(prompt) = prompt.take() {
+                        conversation.push(prompt.clone(), m.content.clone());
+                    }
+                }
+                Role::System => {}
+            }
+        }
+        conversation
+    }
+
+    /// Model of the latest reply.
+    pub fn model(&self) -> Option<&str> {
+        self.messages.iter().rev().find_map(|m| m.model.as_deref())
+    }
+
+    /// Tokens used across the session's replies, superseded ones included (replies
+    /// without usage count as zero).
+    pub fn usage(&self) -> Usage {
+        self.messages.iter().filter_map(|m| m.usage).fold(Usage::default(), |acc, u| Usage {
+            input_tokens: acc.input_tokens + u.input_tokens,
+            output_tokens: acc.output_tokens + u.output_tokens,
+        })
+    }
+
+    pub fn path(&self) -> Result<PathBuf> {
+        Ok(sessions_dir()?.join(format!("{}.{}", self.id, JOURNAL_EXTENSION)))
+    }
+
+    /// Append what changed to the session's journal. Empty sessions are not saved, nor
+    /// any in an ephemeral process (see `crate::persistence`). Fails with
+    /// `journal::InUse` when another process is writing the session.
+    pub fn save(&self) -> Result<()> {
+        if self.messages.is_empty() || !persistence::policy().keeps_history() {
+            return Ok(());
+        }
+        let dir = sessions_dir()?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create sessions directory: {}", dir.display()))?;
+        let path = self.path()?;
+        journal::append(&path, self)?;
+        audit::file_write(&path);
+        // The journal now holds everything a file from before it did.
+        let legacy = path.with_extension(LEGACY_EXTENSION);
+        if legacy.exists() {
+            fs::remove_file(&legacy).with_context(|| format!("failed to remove {}", legacy.display()))?;
+        }
+        update_index(&dir, self)
+    }
+}
+
+fn read_session(path: &Path) -> Result<Session> {
+    Ok(journal::read(path)?.session)
+}
+
+/// The session files in `dir`. A file from before the journal is left out once there is
+/// a journal for the same session.
+fn session_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let journaled = path.extension().is_some_and(|e| e == LEGACY_EXTENSION)
+            && path.with_extension(JOURNAL_EXTENSION).exists();
+        if is_session_file(&path) && !journaled {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// The file of the session `id` in `dir`: its journal, else a file from before them.
+fn session_file(dir: &Path, id: &str) -> PathBuf {
+    let journal = dir.join(format!("{}.{}", id, JOURNAL_EXTENSION));
+    match journal.exists() {
+        true => journal,
+        false => dir.join(format!("{}.{}", id, LEGACY_EXTENSION)),
+    }
+}
+
+/// All saved sessions, most recently updated first.
+pub fn list() -> Result<Vec<Session>> {
+    let dir = sessions_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for path in session_files(&dir)? {
+        sessions.push(read_session(&path)?);
+    }
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(sessions)
+}
+
+/// Load a session by id or unique id prefix.
+pub fn load(id: &str) -> Result<Session> {
+    let mut matches: Vec<Session> = list()?.into_iter().filter(|s| s.id.starts_with(id)).collect();
+    match matches.len() {
+        0 => Err(anyhow!("no session with id '{}' (see `aion sessions list`)", id)),
+        1 => Ok(matches.remove(0)),
+        n => bail!("session id '{}' is ambiguous ({} matches)", id, n),
+    }
+}
+
+/// Continue the saved session `id` (or a unique prefix) in this process (`--resume`).
+/// With `claim`, unless the process is ephemeral, its file is taken for writing
+/// (`journal::open`): another process resuming it gets `journal::InUse`, and a file from
+/// before the journal becomes one.
+pub fn resume(id: &str, claim: bool) -> Result<Session> {
+    let session = load(id)?;
+    if !claim || !persistence::policy().keeps_history() {
+        return Ok(session);
+    }
+    match journal::open(&session.path()?)? {
+        Some(current) => Ok(current),
+        None => {
+            session.save()?;
+            Ok(session)
+        }
+    }
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == JOURNAL_EXTENSION || e == LEGACY_EXTENSION)
+        && path.file_name().is_some_and(|n| n != INDEX_FILE_NAME)
+}
+
+/* ---------------------------
+   Index and search
+---------------------------- */
+
+/// One session as summarized in `index.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub messages: usize,
+    /// Input plus output tokens reported for the session's replies.
+    pub tokens: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl IndexEntry {
+    fn of(session: &Session) -> Self {
+        let usage = session.usage();
+        Self {
+            id: session.id.clone(),
+            title: session.title.clone(),
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            messages: session.messages.len(),
+            tokens: usage.input_tokens + usage.output_tokens,
+            provider: session.provider.clone(),
+            model: session.model().map(str::to_string),
+        }
+    }
+}
+
+/// A session found by `search`, with the matching line when the title did not match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub entry: IndexEntry,
+    pub excerpt: Option<String>,
+}
+
+fn read_index(dir: &Path) -> Vec<IndexEntry> {
+    fs::read_to_string(dir.join(INDEX_FILE_NAME))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_index(dir: &Path, entries: &[IndexEntry]) -> Result<()> {
+    let path = dir.join(INDEX_FILE_NAME);
+    let text = serde_json::to_string_pretty(entries).context("failed to serialize session index")?;
+    fs::write(&path, redact::redact(&text) + "\n")
+        .with_context(|| format!("failed to write session index: {}", path.display()))?;
+    audit::file_write(&path);
+    Ok(())
+}
+
+fn update_index(dir: &Path, session: &Session) -> Result<()> {
+    let mut entries = read_index(dir);
+    entries.retain(|e| e.id != session.id);
+    entries.push(IndexEntry::of(session));
+    sort_entries(&mut entries);
+    write_index(dir, &entries)
+}
+
+fn sort_entries(entries: &mut [IndexEntry]) {
+    entries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.id.cmp(&b.id)));
+}
+
+/// Every saved session's summary, most recently updated first. Sessions missing from
+/// `index.json`, or whose file was written after it, are read from their files; rows
+/// whose file is gone are dropped. The repaired index is written back on the next `save`.
+pub fn index() -> Result<Vec<IndexEntry>> {
+    let dir = sessions_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut indexed = read_index(&dir);
+    let indexed_at = modified(&dir.join(INDEX_FILE_NAME));
+    let mut entries = Vec::new();
+    for path in session_files(&dir)? {
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        match indexed.iter().position(|e| e.id == id) {
+            Some(i) if modified(&path) <= indexed_at => entries.push(indexed.swap_remove(i)),
+            _ => entries.push(IndexEntry::of(&read_session(&path)?)),
+        }
+    }
+    sort_entries(&mut entries);
+    Ok(entries)
+}
+
+/// When `path` was last written; `None` (which sorts first) when unknown.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Session files last written more than `days` days ago, oldest first. Only the file
+/// times are read, so this is cheap enough to run at every startup.
+pub fn expired(days: u32) -> Result<Vec<PathBuf>> {
+    let dir = sessions_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let cutoff = SystemTime::now() - std::time::Duration::from_secs(u64::from(days) * 24 * 60 * 60);
+    let mut found = Vec::new();
+    for path in session_files(&dir)? {
+        match modified(&path) {
+            Some(at) if at < cutoff => found.push((at, path)),
+            _ => {}
+        }
+    }
+    found.sort();
+    Ok(found.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Delete the `expired(days)` session files and their index rows. The number deleted.
+pub fn prune(days: u32) -> Result<usize> {
+    crate::read_only::check("prune_sessions")?;
+    let files = expired(days)?;
+    if files.is_empty() {
+        return Ok(0);
+    }
+    for path in &files {
+        fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    let dir = sessions_dir()?;
+    let mut entries = read_index(&dir);
+    entries.retain(|e| !files.iter().any(|f| f.file_stem().is_some_and(|s| *s == *e.id)));
+    write_index(&dir, &entries)?;
+    Ok(files.len())
+}
+
+/// Sessions matching `query`, title matches first. Every word of the query must appear
+/// (case-insensitively, in any order) in the title or in a single message; message
+/// bodies are only read for sessions whose title does not match.
+pub fn search(query: &str) -> Result<Vec<SearchHit>> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        bail!("the search text is empty");
+    }
+    let dir = sessions_dir()?;
+
+    let mut by_title = Vec::new();
+    let mut by_content = Vec::new();
+    for entry in index()? {
+        if entry.title.as_deref().is_some_and(|t| matches_all(t, &words)) {
+            by_title.push(SearchHit { entry, excerpt: None });
+            continue;
+        }
+        let session = read_session(&session_file(&dir, &entry.id))?;
+        let excerpt = session
+            .messages
+            .iter()
+            .find(|m| matches_all(&m.content, &words))
+            .map(|m| excerpt(&m.content, &words));
+        if excerpt.is_some() {
+            by_content.push(SearchHit { entry, excerpt });
+        }
+    }
+    by_title.extend(by_content);
+    Ok(by_title)
+}
+
+fn matches_all(text: &str, words: &[String]) -> bool {
+    let text = text.to_lowercase();
+    words.iter().all(|w| text.contains(w.as_str()))
+}
+
+/// The first line of `content` containing a query word, shortened to one line.
+fn excerpt(content: &str, words: &[String]) -> String {
+    let line = content
+        .lines()
+        .find(|l| {
+            let l = l.to_lowercase();
+            words.iter().any(|w| l.contains(w.as_str()))
+        })
+        .unwrap_or(content);
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    const MAX_CHARS: usize = 100;
+    if line.chars().count() > MAX_CHARS {
+        line.chars().take(MAX_CHARS - 1).collect::<String>() + "…"
+    } else {
+        line
+    }
+}
+
+/* ---------------------------
+   Titles
+---------------------------- */
+
+/// Ask `client` for a 5–8 word title for `session`. Only the opening exchanges are
+/// sent, each shortened, so the request stays small.
+pub async fn generate_title(client: &dyn ChatClient, session: &Session, cancel: &CancelToken) -> Result<String> {
+    let mut transcript = String::new();
+    for m in session.live().take(TITLE_AFTER_EXCHANGES * 2) {
+        let who = if m.role == Role::User { "User" } else { "Assistant" };
+        let text: String = m.content.chars().take(TITLE_EXCERPT_CHARS).collect();
+        transcript.push_str(&format!("{}: {}\n\n", who, text.trim()));
+    }
+    let messages = [
+        Message::system(
+            "Write a title of 5 to 8 words for this conversation, in the language it is written in. \
+             Reply with the title only, without quotes or punctuation at the end.",
+        ),
+        Message::user(transcript),
+    ];
+    let reply = client.chat(&messages, cancel, &mut |_| {}).await?;
+    clean_title(&reply.content).ok_or_else(|| anyhow!("the provider returned an empty title"))
+}
+
+/// The first non-empty line of `reply`, without quotes, a `Title:` label, or a trailing
+/// period, cut to `TITLE_MAX_WORDS` words.
+fn clean_title(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line.strip_prefix("Title:").unwrap_or(line);
+    let line = line.trim().trim_matches(|c| matches!(c, '"' | '\'' | '*' | '#' | '“' | '”'));
+    let line = line.trim_end_matches('.').trim();
+    let words: Vec<&str> = line.split_whitespace().take(TITLE_MAX_WORDS).collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/* ---------------------------
+   Export formats
+---------------------------- */
+
+pub fn to_json(session: &Session) -> Result<String> {
+    let text = serde_json::to_string_pretty(session).context("failed to serialize session")?;
+    Ok(redact::redact(&text) + "\n")
+}
+
+/// Readable transcript. Message bodies are copied verbatim, so fenced code survives.
+pub fn to_markdown(session: &Session) -> String {
+    let mut out = format!("# AION session {}\n\n", session.id);
+    if let Some(title) = &session.title {
+        out.push_str(&format!("- Title: {}\n", title));
+    }
+    out.push_str(&format!("- Started: {}\n", format_utc(session.created_at)));
+    out.push_str(&format!("- Updated: {}\n", format_utc(session.updated_at)));
+    out.push_str(&format!("- Messages: {}\n", session.messages.len()));
+
+    for m in &session.messages {
+        let who = match (m.role, &m.model) {
+            (Role::User, _) => "User".to_string(),
+            (Role::System, _) => "System".to_string(),
+            (Role::Assistant, Some(model)) => format!("Assistant ({})", model),
+            (Role::Assistant, None) => "Assistant".to_string(),
+        };
+        let note = if m.superseded { " · superseded" } else { "" };
+        out.push_str(&format!("\n## {} · {}{}\n\n", who, format_utc(m.at), note));
+        out.push_str(m.content.trim_end_matches('\n'));
+        out.push('\n');
+    }
+    redact::redact(&out)
+}
+
+/// What `/save` writes: the last reply, or with `all` the whole transcript as Markdown.
+/// Fails when `caps.write_files` is off.
+pub fn transcript(cfg: &AppConfig, session: &Session, all: bool) -> Result<String> {
+    if !cfg.caps.write_files {
+        return Err(CapabilityDenied { cap: "write_files" }.into());
+    }
+    if all {
+        return Ok(to_markdown(session));
+    }
+    match session.live().rev().find(|m| m.role == Role::Assistant) {
+        Some(m) => Ok(format!("{}\n", m.content.trim_end_matches('\n'))),
+        None => bail!("there is no reply to save yet"),
+    }
+}
+
+/// `/save`: write `transcript(session, all)` to `path`.
+pub fn save_transcript(cfg: &AppConfig, session: &Session, path: &str, all: bool) -> Result<()> {
+    let text = transcript(cfg, session, all)?;
+    let target = Guard::new(&cfg.caps).check_write(Path::new(path))?;
+    fs::write(&target, text).with_context(|| format!("failed to write {}", path))?;
+    audit::file_write(&target);
+    Ok(())
+}
+
+/// Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`.
+pub fn format_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,216 @@
+//! Structured output (`aion chat --schema <file>`): a reply that is one JSON value
+//! matching a JSON Schema.
+//!
+//! Notes:
+//! - `fill` adds the schema to the system prompt, sends the conversation, and checks the
+//!   reply. One that is not JSON or does not match is shown back to the model with what
+//!   was wrong, up to `max_attempts` requests in all; then `Exhausted` holds the last one.
+//!   It only needs a `ChatClient`, so every provider gets the same loop.
+//! - Where a provider has a JSON mode of its own, `build_client` turns it on for the
+//!   schema `pin`ned for the run: OpenAI's `json_schema` response format (which also
+//!   covers OpenRouter), Ollama's `format: "json"`. Claude relies on the prompt and the
+//!   loop.
+//! - A reply in a code fence is accepted; models add one despite being told not to.
+//! - Schemas are checked with `jsonschema`, offline: a `$ref` to a URL or file is not
+//!   fetched.
+//!
+//! ```
+//! use aion_core::cancel::CancelToken;
+//! use aion_core::providers::{mock::ScriptedClient, Message};
+//! use aion_core::structured::{fill, Exhausted, Schema};
+//! use serde_json::json;
+//!
+//! let schema = Schema::new(json!({
+//!     "type": "object",
+//!     "properties": { "version": { "type": "string" }, "license": { "type": "string" } },
+//!     "required": ["version", "license"]
+//! }))
+//! .unwrap();
+//! assert!(Schema::new(json!({ "type": 12 })).is_err());
+//! assert_eq!(schema.check("```json\n{\"version\": \"1.2\", \"license\": \"MIT\"}\n```").unwrap()["license"], "MIT");
+//! assert!(schema.check("Sure! Here it is.").unwrap_err()[0].starts_with("not JSON"));
+//!
+//! // Prose, then a wrong type, then a match: three requests.
+//! let script = std::env::temp_dir().join(format!("aion-structured-{}.json", std::process::id()));
+//! std::fs::write(&script, r#"{ "steps": [
+//!     { "content": "The version is 1.2." },
+//!     { "content": "{\"version\": 1.2, \"license\": \"MIT\"}" },
+//!     { "content": "{\"version\": \"1.2\", \"license\": \"MIT\"}" },
+//!     { "content": "not again" }
+//! ] }"#).unwrap();
+//! let client = ScriptedClient::load(&script).unwrap();
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! let messages = [Message::user("extract the version and license")];
+//!
+//! let filled = rt.block_on(fill(&client, &messages, &schema, 3, &CancelToken::new())).unwrap();
+//! assert_eq!(filled.value, json!({ "version": "1.2", "license": "MIT" }));
+//! assert_eq!(filled.attempts, 3);
+//!
+//! // The script now only says "not again": two attempts, then the last one is kept.
+//! let err = rt.block_on(fill(&client, &messages, &schema, 2, &CancelToken::new())).unwrap_err();
+//! let exhausted = err.downcast_ref::<Exhausted>().unwrap();
+//! assert_eq!((exhausted.attempts, exhausted.last.as_str()), (2, "not again"));
+//! std::fs::remove_file(&script).unwrap();
+//! ```
+
+use crate::cancel::CancelToken;
+use crate::providers::{ChatClient, Message, Role, Usage};
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Requests `fill` makes when the caller does not say.
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// Validation errors shown to the model (and the user) per attempt; the rest are counted.
+const MAX_ERRORS: usize = 10;
+
+/// A JSON Schema, compiled.
+pub struct Schema {
+    raw: Value,
+    validator: jsonschema::Validator,
+}
+
+/// A reply that matched.
+#[derive(Debug, Clone)]
+pub struct Filled {
+    pub value: Value,
+    /// Requests it took, counting the one that matched.
+    pub attempts: u32,
+    /// Summed over the attempts that reported it.
+    pub usage: Option<Usage>,
+}
+
+/// No reply matched within the attempts allowed.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("no reply matched the schema after {attempts} attempt(s): {}", .errors.join("; "))]
+pub struct Exhausted {
+    pub attempts: u32,
+    /// The last reply, as received.
+    pub last: String,
+    /// What was wrong with it.
+    pub errors: Vec<String>,
+}
+
+impl Schema {
+    pub fn new(raw: Value) -> Result<Self> {
+        let validator = jsonschema::validator_for(&raw).map_err(|e| anyhow!("invalid JSON Schema: {}", e))?;
+        Ok(Self { raw, validator })
+    }
+
+    /// The schema in the JSON file at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let raw = serde_json::from_str(&text).with_context(|| format!("{} is not JSON", path.display()))?;
+        Self::new(raw).with_context(|| format!("failed to load the schema in {}", path.display()))
+    }
+
+    /// The schema as written.
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+
+    /// `reply` as the JSON value it holds, or what is wrong with it.
+    pub fn check(&self, reply: &str) -> Result<Value, Vec<String>> {
+        let value: Value = serde_json::from_str(unfence(reply)).map_err(|e| vec![format!("not JSON: {}", e)])?;
+        let mut errors: Vec<String> = self
+            .validator
+            .iter_errors(&value)
+            .map(|e| match e.instance_path.as_str() {
+                "" => e.to_string(),
+                path => format!("{}: {}", path, e),
+            })
+            .collect();
+        if errors.len() > MAX_ERRORS {
+            let more = errors.len() - MAX_ERRORS;
+            errors.truncate(MAX_ERRORS);
+            errors.push(format!("and {} more", more));
+        }
+        match errors.is_empty() {
+            true => Ok(value),
+            false => Err(errors),
+        }
+    }
+
+    /// What the system prompt asks for.
+    pub fn instruction(&self) -> String {
+        format!(
+            "Reply with a single JSON value that matches this JSON Schema, and nothing else: no prose, no code fences.\n{}",
+            self.raw
+        )
+    }
+}
+
+static PINNED: OnceLock<Value> = OnceLock::new();
+
+/// Pin `--schema` for the rest of the process, for the providers' JSON modes. Only the
+/// first call counts.
+pub fn pin(schema: &Schema) {
+    let _ = PINNED.set(schema.raw.clone());
+}
+
+/// The schema pinned by `pin`, if any.
+pub fn pinned() -> Option<&'static Value> {
+    PINNED.get()
+}
+
+/// Ask `client` until a reply matches `schema`, at most `max_attempts` times (at least
+/// once). `messages` is the conversation as composed for the provider; the schema goes
+/// into its system prompt. Fails with `Exhausted` when no reply matched.
+pub async fn fill(
+    client: &dyn ChatClient,
+    messages: &[Message],
+    schema: &Schema,
+    max_attempts: u32,
+    cancel: &CancelToken,
+) -> Result<Filled> {
+    let mut messages = with_instruction(messages, &schema.instruction());
+    let mut usage: Option<Usage> = None;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let resp = client.chat(&messages, cancel, &mut |_| {}).await?;
+        if let Some(u) = resp.usage {
+            let total = usage.get_or_insert_with(Usage::default);
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+        }
+        let errors = match schema.check(&resp.content) {
+            Ok(value) => return Ok(Filled { value, attempts: attempt, usage }),
+            Err(errors) => errors,
+        };
+        if attempt >= max_attempts {
+            return Err(Exhausted { attempts: attempt, last: resp.content, errors }.into());
+        }
+        let feedback = format!(
+            "That reply does not match the JSON Schema:\n- {}\nReply again with only the corrected JSON value.",
+            errors.join("\n- ")
+        );
+        messages.extend([Message::assistant(resp.content), Message::user(feedback)]);
+    }
+}
+
+/// `messages` with `instruction` at the end of the system prompt, or as one.
+fn with_instruction(messages: &[Message], instruction: &str) -> Vec<Message> {
+    let mut out = messages.to_vec();
+    match out.first_mut() {
+        Some(first) if first.role == Role::System => {
+            first.content = format!("{}\n\n{}", first.content, instruction);
+        }
+        _ => out.insert(0, Message::system(instruction)),
+    }
+    out
+}
+
+/// `reply` without a surrounding code fence (```` ```json ... ``` ````).
+fn unfence(reply: &str) -> &str {
+    let reply = reply.trim();
+    let Some(inner) = reply.strip_prefix("```").and_then(|r| r.strip_suffix("```")) else {
+        return reply;
+    };
+    match inner.split_once('\n') {
+        Some((lang, body)) if !lang.trim_start().starts_with(['{', '[']) => body.trim(),
+        _ => inner.trim(),
+    }
+}
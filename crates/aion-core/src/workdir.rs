@@ -0,0 +1,179 @@
+//! The directory commands run in (`aion exec`) and relative attachment paths are read
+//! from in a chat session.
+//!
+//! Notes:
+//! - Precedence: `--cwd` (`pin`), then the session's `/cd` (`change`), then
+//!   `exec.default_cwd`, then AION's own directory. `Layers::effective` is the rule;
+//!   `current` applies it to this process.
+//! - A directory is checked when it is given (`check`): `~` expanded, made absolute
+//!   against the directory in effect, resolved, and it must exist, be a directory, and
+//!   pass the `caps.read_allow`/`read_deny` lists (`Guard::check_dir`).
+//!   `exec.default_cwd` is checked each time it is used, since the config can change.
+//! - Process-wide like `structured::pin`: a chat process is one session.
+//!
+//! ```
+//! use aion_core::config::AppConfig;
+//! use aion_core::workdir::{check, Layers, Source};
+//! use std::path::{Path, PathBuf};
+//!
+//! // Flag over session over config; none of them: AION's own directory.
+//! let mut layers = Layers { config: Some("/srv/app".into()), ..Default::default() };
+//! assert_eq!(layers.effective(), Some((PathBuf::from("/srv/app"), Source::Config)));
+//! layers.session = Some("/tmp/work".into());
+//! assert_eq!(layers.effective(), Some((PathBuf::from("/tmp/work"), Source::Session)));
+//! layers.flag = Some("/opt/build".into());
+//! assert_eq!(layers.effective(), Some((PathBuf::from("/opt/build"), Source::Flag)));
+//! assert_eq!(Layers::default().effective(), None);
+//!
+//! let root = std::env::temp_dir().join(format!("aion-workdir-{}", std::process::id()));
+//! std::fs::create_dir_all(root.join("secrets")).unwrap();
+//! std::fs::create_dir_all(root.join("project")).unwrap();
+//! std::fs::write(root.join("notes.txt"), "").unwrap();
+//! let root = root.canonicalize().unwrap();
+//! let mut caps = AppConfig::new_default().caps;
+//! caps.read_deny = vec!["secrets".into()];
+//!
+//! assert_eq!(check(&caps, Path::new("project"), &root).unwrap(), root.join("project"));
+//! assert_eq!(check(&caps, Path::new(".."), &root.join("project")).unwrap(), root);
+//! let denied = check(&caps, Path::new("secrets"), &root).unwrap_err();
+//! assert!(format!("{:#}", denied).contains("caps.read_deny \"secrets\""));
+//! // Through `..` too, and whatever the read switch says.
+//! caps.read_files = false;
+//! assert!(check(&caps, Path::new("project/../secrets"), &root).is_err());
+//! assert!(check(&caps, Path::new("project"), &root).is_ok());
+//! assert!(check(&caps, Path::new("missing"), &root).unwrap_err().to_string().contains("does not exist"));
+//! assert!(check(&caps, Path::new("notes.txt"), &root).unwrap_err().to_string().contains("not a directory"));
+//! std::fs::remove_dir_all(&root).unwrap();
+//! ```
+
+use crate::config::{AppConfig, Capabilities};
+use crate::guard::Guard;
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::path::{is_separator, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Where the directory in effect came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// `--cwd`.
+    Flag,
+    /// `/cd`.
+    Session,
+    /// `exec.default_cwd`.
+    Config,
+    /// AION's own working directory.
+    Process,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Flag => "--cwd",
+            Source::Session => "/cd",
+            Source::Config => "exec.default_cwd",
+            Source::Process => "AION's directory",
+        })
+    }
+}
+
+/// The directories that can be in effect, highest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layers {
+    pub flag: Option<PathBuf>,
+    pub session: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+}
+
+impl Layers {
+    /// The directory that wins, and which layer it is; `None` leaves AION's own.
+    pub fn effective(&self) -> Option<(PathBuf, Source)> {
+        let layers = [(&self.flag, Source::Flag), (&self.session, Source::Session), (&self.config, Source::Config)];
+        layers.into_iter().find_map(|(dir, source)| dir.clone().map(|dir| (dir, source)))
+    }
+}
+
+/// The directory in effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkDir {
+    pub path: PathBuf,
+    pub source: Source,
+}
+
+impl WorkDir {
+    /// `path` when it is relative, resolved against this directory.
+    pub fn join(&self, path: &Path) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl fmt::Display for WorkDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::i18n::format::tilde(&self.path))
+    }
+}
+
+static FLAG: OnceLock<PathBuf> = OnceLock::new();
+static SESSION: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// `dir` checked (see the module notes), with a relative one taken from `base`.
+pub fn check(caps: &Capabilities, dir: &Path, base: &Path) -> Result<PathBuf> {
+    let dir = expand_tilde(dir);
+    let abs = base.join(&dir);
+    if !abs.exists() {
+        bail!("{} does not exist", dir.display());
+    }
+    let resolved = Guard::new(caps).check_dir(&abs)?;
+    if !resolved.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    Ok(resolved)
+}
+
+/// Run commands in `dir` for the rest of the process (`--cwd`); relative to AION's own
+/// directory. Only the first call counts.
+pub fn pin(caps: &Capabilities, dir: &Path) -> Result<PathBuf> {
+    let resolved = check(caps, dir, &process()?).context("invalid --cwd")?;
+    Ok(FLAG.get_or_init(|| resolved).clone())
+}
+
+/// Make `dir` the session's directory (`/cd`); relative to the one in effect. Returns
+/// what is in effect afterwards, which is still `--cwd` when that was given.
+pub fn change(cfg: &AppConfig, dir: &Path) -> Result<WorkDir> {
+    let resolved = check(&cfg.caps, dir, &current(cfg)?.path)?;
+    *SESSION.lock().unwrap_or_else(|e| e.into_inner()) = Some(resolved);
+    current(cfg)
+}
+
+/// The directory in effect for `cfg`. A bad `exec.default_cwd` is an error rather than
+/// a silent fallback, so a command never runs somewhere unexpected.
+pub fn current(cfg: &AppConfig) -> Result<WorkDir> {
+    let layers = Layers {
+        flag: FLAG.get().cloned(),
+        session: SESSION.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        config: cfg.exec.default_cwd.clone(),
+    };
+    match layers.effective() {
+        Some((dir, Source::Config)) => {
+            let path = check(&cfg.caps, &dir, &process()?).context("invalid exec.default_cwd")?;
+            Ok(WorkDir { path, source: Source::Config })
+        }
+        Some((path, source)) => Ok(WorkDir { path, source }),
+        None => Ok(WorkDir { path: process()?, source: Source::Process }),
+    }
+}
+
+fn process() -> Result<PathBuf> {
+    std::env::current_dir().context("failed to read the current directory")
+}
+
+/// `dir` with a leading `~` as the home directory.
+fn expand_tilde(dir: &Path) -> PathBuf {
+    let text = dir.to_string_lossy();
+    match (text.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.chars().next().is_none_or(is_separator) => {
+            home.join(rest.trim_start_matches(is_separator))
+        }
+        _ => dir.to_path_buf(),
+    }
+}
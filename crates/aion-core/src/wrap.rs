@@ -0,0 +1,248 @@
+//! Word wrapping for replies printed to a terminal (CLI mode), as they stream in.
+//!
+//! Notes:
+//! - Widths are terminal columns (`unicode-width`): a CJK character takes two, Arabic
+//!   harakat and other combining marks none. Text without spaces between wide characters
+//!   (Chinese, Japanese) may break between any two of them.
+//! - A line starting with a list marker (`-`, `*`, `+`, `1.`, `2)`) continues under the
+//!   text after the marker (a hanging indent); any other indented line continues at its
+//!   own indentation.
+//! - Fenced code (between ```` ``` ```` lines) is left as it is, so snippets copy intact.
+//!   A word wider than the whole line is cut between grapheme clusters.
+//! - `Wrapper` takes the reply in chunks and holds back only the word being typed;
+//!   `wrap` is the same thing for a whole text. Spaces at the end of a line are dropped.
+//!
+//! ```
+//! use aion_core::wrap::{wrap, Wrapper};
+//!
+//! assert_eq!(wrap("the quick brown fox jumps over the lazy dog", 15), "the quick brown\nfox jumps over\nthe lazy dog");
+//!
+//! // Hanging indents for list items; an indented paragraph keeps its indent.
+//! let list = "- first item that is long enough\n  12. numbered item";
+//! assert_eq!(wrap(list, 14), "- first item\n  that is long\n  enough\n  12. numbered\n      item");
+//! assert_eq!(wrap("    indented words go on", 15), "    indented\n    words go on");
+//!
+//! // Columns, not characters: CJK breaks anywhere, Arabic with harakat by words.
+//! assert_eq!(wrap("中文的文本没有空格", 8), "中文的文\n本没有空\n格");
+//! let arabic = "مَرْحَبًا بِكُمْ فِي الْعَالَمِ";
+//! assert_eq!(wrap(arabic, 10), "مَرْحَبًا بِكُمْ\nفِي الْعَالَمِ");
+//!
+//! // Code is left alone; a word wider than the line is cut.
+//! let code = "``` rust\nlet long_line = this_is_not_wrapped();\n```";
+//! assert_eq!(wrap(code, 10), code);
+//! assert_eq!(wrap("see https://example.com/a/very/long/path", 12), "see\nhttps://exam\nple.com/a/ve\nry/long/path");
+//!
+//! // Streamed in any pieces, the result is the same.
+//! let text = "- one two three\n\n中文 mixed with words, and ```inline``` ticks\n```\ncode here\n```\nend";
+//! let whole = wrap(text, 11);
+//! for size in 1..6 {
+//!     let chars: Vec<char> = text.chars().collect();
+//!     let mut wrapper = Wrapper::new(11);
+//!     let mut out = String::new();
+//!     for piece in chars.chunks(size) {
+//!         out.push_str(&wrapper.push(&piece.iter().collect::<String>()));
+//!     }
+//!     out.push_str(&wrapper.finish());
+//!     assert_eq!(out, whole, "pieces of {}", size);
+//! }
+//! ```
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Wraps text to `width` columns as it arrives; see the module notes.
+#[derive(Debug, Clone)]
+pub struct Wrapper {
+    width: usize,
+    /// Column the output is at.
+    column: usize,
+    /// Where continuation lines of the current line start.
+    indent: usize,
+    /// Nothing but whitespace seen on the current line yet.
+    line_start: bool,
+    /// That leading whitespace.
+    lead: String,
+    /// Whitespace after the last word written.
+    space: String,
+    /// The word being read.
+    word: String,
+    /// The rest of this line is copied as it is (fenced code).
+    verbatim: bool,
+    /// Between fence lines.
+    in_fence: bool,
+    /// The current verbatim line, to see whether it opens or closes a fence.
+    line: String,
+}
+
+impl Wrapper {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width: width.max(1),
+            column: 0,
+            indent: 0,
+            line_start: true,
+            lead: String::new(),
+            space: String::new(),
+            word: String::new(),
+            verbatim: false,
+            in_fence: false,
+            line: String::new(),
+        }
+    }
+
+    /// The output for `chunk`: everything up to the word still being read.
+    pub fn push(&mut self, chunk: &str) -> String {
+        let mut out = String::new();
+        for c in chunk.chars() {
+            if self.verbatim {
+                out.push(c);
+                if c == '\n' {
+                    if self.line.trim_start().starts_with("```") {
+                        self.in_fence = !self.in_fence;
+                    }
+                    self.new_line();
+                } else {
+                    self.line.push(c);
+                }
+                continue;
+            }
+            match c {
+                '\n' => {
+                    self.flush(&mut out);
+                    if self.verbatim {
+                        // A fence line alone on its line.
+                        self.verbatim = false;
+                        if self.line.trim_start().starts_with("```") {
+                            self.in_fence = !self.in_fence;
+                        }
+                    }
+                    out.push('\n');
+                    self.new_line();
+                }
+                c if c.is_whitespace() => {
+                    if self.line_start && self.word.is_empty() {
+                        self.lead.push(c);
+                    } else {
+                        self.flush(&mut out);
+                        // The word opened a fence line: the rest of it is copied.
+                        if self.verbatim {
+                            out.push(c);
+                            self.line.push(c);
+                        } else {
+                            self.space.push(c);
+                        }
+                    }
+                }
+                // A wide character is a word of its own, so a line may break after it.
+                c if c.width() == Some(2) && !self.in_fence && !self.word.starts_with("```") => {
+                    self.flush(&mut out);
+                    self.word.push(c);
+                    self.flush(&mut out);
+                }
+                c => self.word.push(c),
+            }
+        }
+        out
+    }
+
+    /// The output still held back, at the end of the text.
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        self.flush(&mut out);
+        self.new_line();
+        self.in_fence = false;
+        out
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+        self.indent = 0;
+        self.line_start = true;
+        self.lead.clear();
+        self.space.clear();
+        self.verbatim = false;
+        self.line.clear();
+    }
+
+    /// Write the word read so far, on a new line if it does not fit on this one.
+    fn flush(&mut self, out: &mut String) {
+        if self.word.is_empty() {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        if self.line_start {
+            self.line_start = false;
+            if self.in_fence || word.starts_with("```") {
+                self.verbatim = true;
+                self.line = format!("{}{}", self.lead, word);
+                out.push_str(&self.line);
+                return;
+            }
+            let lead = width(&self.lead);
+            self.indent = lead + if is_marker(&word) { width(&word) + 1 } else { 0 };
+            // Past half the line a hanging indent leaves too little room to be worth it.
+            if self.indent > self.width / 2 {
+                self.indent = lead.min(self.width / 2);
+            }
+            out.push_str(&self.lead);
+            self.column = lead;
+            self.put(out, &word);
+            return;
+        }
+
+        let space = std::mem::take(&mut self.space);
+        let gap = space.chars().count();
+        if self.column + gap + width(&word) <= self.width {
+            out.push_str(&space);
+            self.column += gap;
+        } else {
+            self.break_line(out);
+        }
+        self.put(out, &word);
+    }
+
+    /// Write `word` at the current column, cutting it when it is wider than a line.
+    fn put(&mut self, out: &mut String, word: &str) {
+        if self.column + width(word) <= self.width {
+            out.push_str(word);
+            self.column += width(word);
+            return;
+        }
+        for grapheme in word.graphemes(true) {
+            let w = width(grapheme);
+            if self.column + w > self.width && self.column > self.indent {
+                self.break_line(out);
+            }
+            out.push_str(grapheme);
+            self.column += w;
+        }
+    }
+
+    fn break_line(&mut self, out: &mut String) {
+        out.push('\n');
+        out.push_str(&" ".repeat(self.indent));
+        self.column = self.indent;
+    }
+}
+
+/// `text` wrapped to `width` columns.
+pub fn wrap(text: &str, width: usize) -> String {
+    let mut wrapper = Wrapper::new(width);
+    let mut out = wrapper.push(text);
+    out.push_str(&wrapper.finish());
+    out
+}
+
+/// `-`, `*`, `+`, or a number followed by `.` or `)`.
+fn is_marker(word: &str) -> bool {
+    match word {
+        "-" | "*" | "+" => true,
+        _ => word
+            .strip_suffix(['.', ')'])
+            .is_some_and(|n| !n.is_empty() && n.len() <= 3 && n.chars().all(|c| c.is_ascii_digit())),
+    }
+}
+
+fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
@@ -0,0 +1,157 @@
+//! `aion ask <file> "<question>"`: answer a question about a text file, or summarize it.
+//!
+//! Notes:
+//! - The file is read through `guard` (`caps.read_files` and its path lists), up to
+//!   `--max-bytes`. PDFs and other binary files are refused with a message rather than
+//!   sent as garbage; the file must be UTF-8 text.
+//! - A file longer than `--chunk-chars` is asked in overlapping parts and the answers
+//!   combined (`pipeline`); progress is shown on stderr when it is a terminal. Only the
+//!   final answer is streamed to stdout.
+//! - `--json` (or `--output json`) prints the answer with the parts it drew on instead.
+//! - The system prompt (reply language, `[style]`) applies to every request.
+//! - The file is checked for secrets before anything is sent (`secrets`).
+
+use crate::cancel;
+use crate::cli::AskArgs;
+use crate::config::io::load_config;
+use crate::guard::Guard;
+use crate::i18n::format::format_bytes;
+use crate::persona;
+use crate::pipeline::{self, Budget, Job};
+use crate::providers;
+use crate::redact;
+use crate::secrets;
+use crate::ui::console::Console;
+use crate::{audit, costs, http_log, i18n, notify};
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::tty::IsTty;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Default `--max-bytes`.
+pub const DEFAULT_MAX_BYTES: u64 = 4 * 1024 * 1024;
+/// Smallest `--chunk-chars`.
+pub const MIN_CHUNK_CHARS: usize = 1_000;
+
+const SUMMARIZE: &str = "Summarize this document: open with its purpose in one or two sentences, then list \
+                         the main points in order, and end with any conclusions or open questions it states.";
+
+pub async fn run(console: &Console, args: &AskArgs, lang: Option<&str>, strict: bool) -> Result<ExitCode> {
+    let mut cfg = load_config().context("failed to load config")?;
+    if let Some(lang) = lang {
+        cfg.language = lang.to_string();
+    }
+    let cfg = args.overrides.apply(&cfg)?;
+    crate::validate_strict(&cfg, strict)?;
+    redact::init(&cfg);
+    audit::init(&cfg);
+    costs::init(&cfg);
+    http_log::init(&cfg);
+    i18n::format::init(&cfg);
+    i18n::bidi::init(&cfg);
+    notify::init(&cfg);
+
+    let document = read_document(&cfg.caps, &args.file, args.max_bytes)?;
+    secrets::confirm(&cfg, [(args.file.display().to_string(), document.as_str())])?;
+    let question = match &args.question {
+        Some(q) if !q.trim().is_empty() => q.as_str(),
+        Some(_) => bail!("the question is empty"),
+        None => SUMMARIZE,
+    };
+    let system = persona::system_prompt(&cfg);
+    let name = args.file.display().to_string();
+    let job = Job {
+        name: &name,
+        document: &document,
+        question,
+        system: system.as_deref(),
+        budget: Budget { chunk_chars: args.chunk_chars, ..Budget::default() },
+    };
+
+    let json = args.json || console.is_json();
+    let progress = !json && io::stderr().is_tty();
+    let client = providers::build_client(&cfg).context("failed to create provider client")?;
+    cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+    let token = cancel::begin();
+    let started = notify::start();
+    let mut stdout = io::stdout();
+    let result = pipeline::ask(
+        client.as_ref(),
+        &job,
+        &token,
+        &mut |done, total| {
+            if progress {
+                let end = if done == total { "\r\x1b[2K" } else { "" };
+                eprint!("\rReading {}: part {} of {}{}", name, done, total, end);
+            }
+        },
+        &mut |chunk| {
+            if !json {
+                print!("{}", chunk);
+                let _ = stdout.flush();
+            }
+        },
+    )
+    .await;
+    cancel::end();
+    if !token.is_cancelled() {
+        notify::finish(started, notify::Task::Reply { model: &cfg.provider.model });
+    }
+    let outcome = result?;
+
+    if let Some(fallback) = &outcome.fallback {
+        eprintln!("{}", redact::redact(&fallback.notice()));
+    }
+    for warning in costs::take_warnings() {
+        eprintln!("{}", warning);
+    }
+    if !json {
+        println!();
+        return Ok(ExitCode::SUCCESS);
+    }
+    let (kind, model) = match &outcome.fallback {
+        Some(f) => (&f.kind, f.model.as_str()),
+        None => (&cfg.provider.kind, cfg.provider.model.as_str()),
+    };
+    console.print_json(&serde_json::json!({
+        "provider": kind.id(),
+        "model": model,
+        "file": name,
+        "question": args.question,
+        "summarize": args.summarize,
+        "content": outcome.content,
+        "requests": outcome.requests,
+        "chunks": outcome.chunks,
+        "usage": outcome.usage,
+        "fallback": outcome.fallback,
+    }))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The text of `path`, as `caps` allow: at most `max_bytes`, and not binary.
+fn read_document(caps: &crate::config::Capabilities, path: &Path, max_bytes: u64) -> Result<String> {
+    let resolved = Guard::new(caps).check_read(path)?;
+    let size = fs::metadata(&resolved).with_context(|| format!("failed to read {}", path.display()))?.len();
+    if size > max_bytes {
+        bail!(
+            "{} is {}; files up to {} can be read (raise it with --max-bytes)",
+            path.display(),
+            format_bytes("en", size),
+            format_bytes("en", max_bytes)
+        );
+    }
+    let bytes = fs::read(&resolved).with_context(|| format!("failed to read {}", path.display()))?;
+    if bytes.starts_with(b"%PDF-") {
+        bail!("{} is a PDF; aion ask reads text files (convert it first, e.g. `pdftotext file.pdf`)", path.display());
+    }
+    if bytes.contains(&0) {
+        bail!("{} is a binary file; aion ask reads text files", path.display());
+    }
+    let text = String::from_utf8(bytes).map_err(|_| anyhow!("{} is not UTF-8 text", path.display()))?;
+    if text.trim().is_empty() {
+        bail!("{} is empty", path.display());
+    }
+    Ok(text)
+}
@@ -0,0 +1,135 @@
+//! Files attached to a prompt (Ctrl+O in the chat view, `aion chat --file <PATTERN>`).
+//!
+//! Notes:
+//! - Read through `guard`, so `caps.read_files` and its path lists apply, and the
+//!   resolved path is what gets opened.
+//! - Text only, up to `MAX_BYTES`. A larger file, or one that is not UTF-8, is refused
+//!   rather than cut short, so a reply is never based on part of a file unknowingly.
+//! - `fenced` puts the contents in a Markdown fence labelled with the path, longer than
+//!   any fence inside the file so the file cannot close it.
+//! - `gather` expands `--file` patterns (`aion_core::gather`) within `MAX_FILES` and
+//!   `MAX_TOTAL_BYTES`. Its progress line is drawn on stderr only when asked, i.e. when
+//!   stderr is a terminal.
+//! - Callers check the contents for secrets before sending them (`secrets`).
+//! - With `features.retrieval`, only the parts of the files most like the prompt are sent
+//!   (`retrieve`, `aion_core::retrieval`); the chat view keeps a `Retriever` per session.
+
+use crate::cancel;
+use crate::config::{AppConfig, Capabilities};
+use crate::gather::{self, Budget, Progress};
+use crate::guard::Guard;
+use crate::i18n::format::format_bytes;
+use crate::providers;
+use crate::retrieval::{Retriever, DEFAULT_TOP_K};
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Largest file that can be attached.
+pub const MAX_BYTES: u64 = 256 * 1024;
+/// Most files one `--file` gather attaches.
+pub const MAX_FILES: usize = 100;
+/// Most bytes one `--file` gather attaches, all files together.
+pub const MAX_TOTAL_BYTES: u64 = 1024 * 1024;
+/// Skipped files listed by name before the rest are only counted.
+const SKIPPED_SHOWN: usize = 5;
+/// Least time between redraws of the progress line.
+const PROGRESS_EVERY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    /// As given, for the fence label.
+    pub path: PathBuf,
+    pub text: String,
+}
+
+impl Attachment {
+    /// Read `path`, as `caps` allow.
+    pub fn read(caps: &Capabilities, path: &Path) -> Result<Self> {
+        let resolved = Guard::new(caps).check_read(path)?;
+        let size = fs::metadata(&resolved).with_context(|| format!("failed to read {}", path.display()))?.len();
+        if size > MAX_BYTES {
+            bail!(
+                "{} is {}; files up to {} can be attached",
+                path.display(),
+                format_bytes("en", size),
+                format_bytes("en", MAX_BYTES)
+            );
+        }
+        let bytes = fs::read(&resolved).with_context(|| format!("failed to read {}", path.display()))?;
+        let text = String::from_utf8(bytes).map_err(|_| anyhow!("{} is not a text file", path.display()))?;
+        Ok(Self { path: path.to_path_buf(), text })
+    }
+
+    /// The contents fenced and labelled with the path, ending in a newline.
+    pub fn fenced(&self) -> String {
+        let longest = self
+            .text
+            .lines()
+            .map(|l| l.trim_start().chars().take_while(|&c| c == '`').count())
+            .max()
+            .unwrap_or(0);
+        let fence = "`".repeat(longest.max(2) + 1);
+        format!("{}{}\n{}\n{}\n", fence, self.path.display(), self.text.trim_end_matches('\n'), fence)
+    }
+}
+
+/// `prompt` with the parts of `files` most like it in front, embedded with the provider's
+/// embedding model. Ctrl+C stops the embedding.
+pub async fn retrieve(cfg: &AppConfig, files: &[Attachment], prompt: &str) -> Result<String> {
+    let retriever = Retriever::new(providers::build_embedding_client(cfg)?, DEFAULT_TOP_K);
+    for file in files {
+        retriever.add(&file.path.display().to_string(), &file.text);
+    }
+    cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+    let token = cancel::begin();
+    let result = retriever.prompt(prompt, &token).await;
+    cancel::end();
+    result
+}
+
+/// The files matching `patterns`, sorted by path, with what was left out noted on stderr.
+/// `progress` draws a line on stderr that updates in place while files are read.
+pub fn gather(caps: &Capabilities, patterns: &[String], lang: &str, progress: bool) -> Result<Vec<Attachment>> {
+    let budget = Budget { max_files: MAX_FILES, max_bytes: MAX_TOTAL_BYTES, max_file_bytes: MAX_BYTES };
+    let last = Mutex::new(Instant::now());
+    let show = |p: Progress| {
+        // A worker that finds the line being drawn moves on; the next file redraws it.
+        let Ok(mut last) = last.try_lock() else { return };
+        if progress && last.elapsed() >= PROGRESS_EVERY {
+            eprint!("\r\x1b[2K{}", progress_line(lang, p));
+            *last = Instant::now();
+        }
+    };
+    let result = gather::gather(caps, patterns, budget, gather::default_workers(), &show);
+    if progress {
+        eprint!("\r\x1b[2K");
+    }
+    let gathered = result?;
+
+    for skipped in gathered.skipped.iter().take(SKIPPED_SHOWN) {
+        eprintln!("Warning: {} not attached: {}", skipped.path.display(), skipped.reason);
+    }
+    if gathered.skipped.len() > SKIPPED_SHOWN {
+        eprintln!("Warning: {} more files not attached", gathered.skipped.len() - SKIPPED_SHOWN);
+    }
+    if gathered.over_budget > 0 {
+        eprintln!(
+            "Warning: {} matching files left out; up to {} files and {} are attached",
+            gathered.over_budget,
+            MAX_FILES,
+            format_bytes(lang, MAX_TOTAL_BYTES)
+        );
+    }
+    if gathered.files.is_empty() {
+        bail!("none of the matching files could be attached");
+    }
+    Ok(gathered.files.into_iter().map(|f| Attachment { path: f.path, text: f.text }).collect())
+}
+
+/// e.g. "scanned 412 files, attached 37, 1.2 MiB".
+fn progress_line(lang: &str, p: Progress) -> String {
+    format!("scanned {} files, attached {}, {}", p.scanned, p.attached, format_bytes(lang, p.bytes))
+}
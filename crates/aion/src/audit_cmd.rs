@@ -0,0 +1,43 @@
+//! `aion audit`: the latest entries of the audit log (`aion_core::audit`), and clearing it.
+
+use crate::cli::AuditAction;
+use crate::ui::console::Console;
+use crate::{audit, dry_run, sessions};
+use anyhow::Result;
+use std::process::ExitCode;
+
+pub fn run(console: &Console, action: &AuditAction) -> Result<ExitCode> {
+    match action {
+        AuditAction::Tail(a) => {
+            let entries = audit::tail(a.n)?;
+            if console.is_json() {
+                console.print_json(&entries)?;
+            } else {
+                for e in &entries {
+                    let session = e.session.as_deref().unwrap_or("-");
+                    println!("{}  {}  {}", sessions::format_utc(e.ts), session, describe_audit_event(&e.event));
+                }
+            }
+        }
+        AuditAction::Clear if dry_run::enabled() => {
+            println!("{}", dry_run::note_remove(&audit::audit_file_path()?));
+        }
+        AuditAction::Clear => match audit::clear()? {
+            true => println!("Audit log cleared"),
+            false => println!("No audit log"),
+        },
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn describe_audit_event(event: &audit::AuditEvent) -> String {
+    match event {
+        audit::AuditEvent::Command { command, cwd, exit_code } => {
+            let cwd = cwd.as_ref().map(|dir| format!(" in {}", dir.display())).unwrap_or_default();
+            let code = exit_code.map(|code| format!(" (exit {})", code)).unwrap_or_default();
+            format!("command  {}{}{}", command, cwd, code)
+        }
+        audit::AuditEvent::FileWrite { path } => format!("write    {}", path.display()),
+        audit::AuditEvent::Network { url } => format!("network  {}", url),
+    }
+}
@@ -0,0 +1,282 @@
+//! `aion bench`: send a small set of prompts to one or more models on the configured
+//! provider and compare how quickly they answer.
+//!
+//! Notes:
+//! - Needs `caps.network`, a local Ollama included. `--prompts` is read through `guard`.
+//! - Models: `--models`, else every installed model on Ollama, else the configured one.
+//!   Each is asked directly; `provider.fallbacks` are left out so a fallback never
+//!   answers for a model under test.
+//! - Requests run one at a time, model by model: every prompt, `--rounds` times. One that
+//!   takes longer than `--timeout` counts as failed and the next starts.
+//! - Ctrl+C stops the run; what finished is still summarized, and the exit code is 130.
+//! - Only the prompt is sent (no system prompt, `[style]`, or cache), so every model gets
+//!   the same request. The configured generation parameters apply.
+//! - The first request to an Ollama model includes loading it. It shows in p95; with
+//!   `--rounds` it stays out of the median.
+//! - `network.max_concurrent_requests` / `requests_per_minute` apply; the wait for a turn
+//!   comes before a request's clock starts, so it is not counted in its times.
+//! - The statistics are `aion_core::bench`. Cost needs a `--price` for the model and
+//!   usage from the provider.
+
+use crate::bench::{summarize, Run, Stats};
+use crate::cancel::{self, CancelToken};
+use crate::cli::BenchArgs;
+use crate::config::io::load_config;
+use crate::config::{AppConfig, Capabilities, ProviderKind};
+use crate::guard::Guard;
+use crate::providers::limit::{self, Limits, RateLimiter};
+use crate::providers::{self, ChatClient, Message};
+use crate::redact;
+use crate::ui::console::Console;
+use crate::ui::width::width;
+use crate::{audit, costs, http_log, i18n};
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::tty::IsTty;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+/// Default `--timeout`, in seconds.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// Sent when no `--prompts` file is given: a one-word reply, two short explanations,
+/// and a little code.
+const PROMPTS: &[&str] = &[
+    "Reply with the single word: ready",
+    "Explain in three sentences what a hash table is and when to use one.",
+    "Summarize the water cycle as a bulleted list of at most five items.",
+    "Write a Python function that returns the n-th Fibonacci number without recursion.",
+];
+
+#[derive(Debug, Serialize)]
+struct ModelResult {
+    model: String,
+    #[serde(flatten)]
+    stats: Stats,
+    /// Why runs failed, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+pub async fn run(console: &Console, args: &BenchArgs, strict: bool) -> Result<ExitCode> {
+    let cfg = load_config().context("failed to load config")?;
+    let mut cfg = cfg.with_overrides(args.provider.as_ref(), None);
+    if args.provider.is_some() {
+        cfg.validate().context("invalid --provider override")?;
+    }
+    crate::validate_strict(&cfg, strict)?;
+    redact::init(&cfg);
+    audit::init(&cfg);
+    costs::init(&cfg);
+    http_log::init(&cfg);
+    i18n::format::init(&cfg);
+    i18n::bidi::init(&cfg);
+    Guard::for_app(&cfg).check_network(&cfg.provider.effective_base_url())?;
+    cfg.provider.fallbacks.clear();
+    // Turns are taken here, outside the timings, rather than by the client.
+    let limiter = limit::shared(&cfg.provider, Limits::from(&cfg.network));
+    cfg.network.max_concurrent_requests = 0;
+    cfg.network.requests_per_minute = 0;
+
+    let prompts = match &args.prompts {
+        Some(path) => read_prompts(&cfg.caps, path)?,
+        None => PROMPTS.iter().map(|p| p.to_string()).collect(),
+    };
+    let models = models(&cfg, &args.models).await?;
+    let json = args.json || console.is_json();
+    let progress = !json && io::stderr().is_tty();
+    let timeout = Duration::from_secs(args.timeout);
+    let total = models.len() * prompts.len() * args.rounds as usize;
+
+    cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+    let token = cancel::begin();
+    let mut results = Vec::new();
+    let mut done = 0;
+    for model in &models {
+        let mut model_cfg = cfg.clone();
+        model_cfg.provider.model = model.clone();
+        let price = args.price.iter().find(|(name, _)| matches(model, name)).map(|(_, price)| *price);
+        let (mut runs, mut errors) = (Vec::new(), Vec::new());
+        match providers::build_client(&model_cfg) {
+            Ok(client) => {
+                'rounds: for _ in 0..args.rounds {
+                    for prompt in &prompts {
+                        done += 1;
+                        if progress {
+                            eprint!("\r\x1b[2KBenchmarking {}: request {} of {}", model, done, total);
+                        }
+                        match timed(client.as_ref(), limiter.as_deref(), prompt, &token, timeout).await {
+                            Ok(run) => runs.push(run),
+                            Err(_) if token.is_cancelled() => break 'rounds,
+                            Err(e) => errors.push(redact::redact(&format!("{:#}", e))),
+                        }
+                    }
+                }
+            }
+            Err(e) => errors.push(redact::redact(&format!("{:#}", e))),
+        }
+        if !runs.is_empty() || !errors.is_empty() {
+            let stats = summarize(&runs, errors.len(), price);
+            results.push(ModelResult { model: model.clone(), stats, errors });
+        }
+        if token.is_cancelled() {
+            break;
+        }
+    }
+    cancel::end();
+    if progress {
+        eprint!("\r\x1b[2K");
+    }
+
+    let interrupted = token.is_cancelled();
+    if json {
+        console.print_json(&serde_json::json!({
+            "provider": cfg.provider.kind.id(),
+            "prompts": prompts.len(),
+            "rounds": args.rounds,
+            "timeout_secs": args.timeout,
+            "interrupted": interrupted,
+            "models": results,
+        }))?;
+    } else {
+        if interrupted {
+            eprintln!("Interrupted at request {} of {}; results so far:", done, total);
+        }
+        print_table(&results);
+    }
+
+    Ok(if interrupted {
+        ExitCode::from(cancel::INTERRUPTED_EXIT_CODE as u8)
+    } else if results.iter().all(|r| r.stats.runs == 0) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// One request, timed from sending it (after its turn under `limiter`) to the end of the reply.
+async fn timed(
+    client: &dyn ChatClient,
+    limiter: Option<&RateLimiter>,
+    prompt: &str,
+    cancel: &CancelToken,
+    limit: Duration,
+) -> Result<Run> {
+    let messages = [Message::user(prompt)];
+    let _turn = match limiter {
+        Some(limiter) => Some(limiter.turn(cancel).await?),
+        None => None,
+    };
+    let start = Instant::now();
+    let mut first_token = None;
+    let reply = tokio::time::timeout(
+        limit,
+        client.chat(&messages, cancel, &mut |chunk| {
+            if !chunk.is_empty() && first_token.is_none() {
+                first_token = Some(start.elapsed());
+            }
+        }),
+    )
+    .await;
+    let total = start.elapsed();
+    let response = reply.map_err(|_| anyhow!("no reply within {}s", limit.as_secs()))??;
+    Ok(Run { first_token, total, usage: response.usage })
+}
+
+/// `requested`, or what the provider has: Ollama's installed models, else the configured one.
+async fn models(cfg: &AppConfig, requested: &[String]) -> Result<Vec<String>> {
+    let requested: Vec<String> = requested.iter().map(|m| m.trim()).filter(|m| !m.is_empty()).map(String::from).collect();
+    if !requested.is_empty() {
+        return Ok(requested);
+    }
+    if cfg.provider.kind != ProviderKind::Ollama {
+        return Ok(vec![cfg.provider.model.clone()]);
+    }
+    let client = providers::build_client(cfg).context("failed to create provider client")?;
+    let installed = client.list_models().await.context("failed to list the installed Ollama models")?;
+    if installed.is_empty() {
+        bail!("no Ollama models are installed (pull one with `ollama pull <model>`, or pass --models)");
+    }
+    Ok(installed.into_iter().map(|m| m.id).collect())
+}
+
+/// Whether `--price` for `name` applies to `model`; Ollama lists untagged models as `name:latest`.
+fn matches(model: &str, name: &str) -> bool {
+    let untagged = |m: &str| m.strip_suffix(":latest").unwrap_or(m).to_string();
+    untagged(model) == untagged(name)
+}
+
+/// The non-empty lines of `path` that are not `#` comments, as `caps` allow.
+fn read_prompts(caps: &Capabilities, path: &Path) -> Result<Vec<String>> {
+    let resolved = Guard::new(caps).check_read(path)?;
+    let text = fs::read_to_string(&resolved).with_context(|| format!("failed to read {}", path.display()))?;
+    let prompts: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if prompts.is_empty() {
+        bail!("{} has no prompts (one per line; lines starting with # are skipped)", path.display());
+    }
+    Ok(prompts)
+}
+
+fn print_table(results: &[ModelResult]) {
+    let header = ["MODEL", "OK", "FAILED", "TTFT p50", "TTFT p95", "TOTAL p50", "TOTAL p95", "TOK/S p50", "COST/RUN"];
+    let rows: Vec<[String; 9]> = results
+        .iter()
+        .map(|r| {
+            let s = &r.stats;
+            let ms = |v: Option<f64>| v.map_or("-".to_string(), format_ms);
+            [
+                r.model.clone(),
+                s.runs.to_string(),
+                s.failed.to_string(),
+                ms(s.first_token_ms.map(|t| t.median)),
+                ms(s.first_token_ms.map(|t| t.p95)),
+                ms(s.total_ms.map(|t| t.median)),
+                ms(s.total_ms.map(|t| t.p95)),
+                s.tokens_per_sec.map_or("-".to_string(), |t| format!("{:.1}", t.median)),
+                s.cost_per_run.map_or("-".to_string(), |c| format!("${:.6}", c)),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(width);
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(width(cell));
+        }
+    }
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(i, (cell, w))| match i {
+                0 => format!("{}{}", cell, " ".repeat(w - width(cell))),
+                _ => format!("{}{}", " ".repeat(w - width(cell)), cell),
+            })
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    println!("{}", line(&header.map(String::from)));
+    for row in &rows {
+        println!("{}", line(row));
+    }
+    for r in results.iter().filter(|r| !r.errors.is_empty()) {
+        println!("{}: {} failed; first: {}", r.model, r.errors.len(), r.errors[0]);
+    }
+}
+
+/// `850ms`, or `1.25s` from a second up.
+fn format_ms(ms: f64) -> String {
+    match ms < 1_000.0 {
+        true => format!("{:.0}ms", ms),
+        false => format!("{:.2}s", ms / 1_000.0),
+    }
+}
@@ -0,0 +1,35 @@
+//! `aion cache`: the response cache's size, and clearing it (`aion_core::cache`).
+
+use crate::cli::CacheAction;
+use crate::config::io::load_config;
+use crate::ui::console::Console;
+use crate::{cache, dry_run, i18n};
+use anyhow::{Context, Result};
+use std::process::ExitCode;
+
+pub fn run(console: &Console, action: &CacheAction, lang: &str) -> Result<ExitCode> {
+    let cfg = load_config().context("failed to load config")?;
+    match action {
+        CacheAction::Stats => {
+            let stats = cache::stats(&cfg)?;
+            if console.is_json() {
+                console.print_json(&stats)?;
+            } else {
+                println!("Directory: {}", i18n::format::tilde(&stats.dir));
+                println!("Enabled:   {} (ttl {}s)", cfg.cache.enabled, cfg.cache.ttl_secs);
+                println!("Entries:   {} ({} expired)", stats.entries, stats.expired);
+                println!("Size:      {}", i18n::format::format_bytes(lang, stats.bytes));
+            }
+        }
+        CacheAction::Clear if dry_run::enabled() => {
+            for path in cache::entries(&cfg)? {
+                println!("{}", dry_run::note_remove(&path));
+            }
+        }
+        CacheAction::Clear => {
+            let removed = cache::clear(&cfg)?;
+            println!("Removed {} cached replies", removed);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,95 @@
+//! Ctrl+C handling.
+//!
+//! Notes:
+//! - `CancelToken` comes from `aion_core::cancel`; this module connects tokens to Ctrl+C.
+//! - The Ctrl+C handler cancels the token of the operation in flight. A second Ctrl+C
+//!   within `DOUBLE_PRESS_WINDOW` exits the process.
+//! - In raw mode (wizard, TUI) the terminal delivers Ctrl+C as a key event instead of a
+//!   signal, so those loops handle it themselves.
+
+pub use aion_core::cancel::CancelToken;
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DOUBLE_PRESS_WINDOW: Duration = Duration::from_secs(2);
+
+/// Exit code used when the user exits with a double Ctrl+C (128 + SIGINT), and by
+/// commands that stop early on Ctrl+C.
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/* ---------------------------
+   Ctrl+C handling
+---------------------------- */
+
+#[derive(Default)]
+struct SignalState {
+    current: Option<CancelToken>,
+    last_press: Option<Instant>,
+}
+
+static SIGNAL_STATE: Mutex<Option<SignalState>> = Mutex::new(None);
+
+/// Install the process-wide Ctrl+C handler. Safe to call more than once.
+pub fn install_ctrlc_handler() -> anyhow::Result<()> {
+    let mut state = SIGNAL_STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if state.is_some() {
+        return Ok(());
+    }
+    *state = Some(SignalState::default());
+    drop(state);
+
+    ctrlc::set_handler(on_ctrlc)?;
+    Ok(())
+}
+
+fn on_ctrlc() {
+    if register_press() {
+        eprintln!();
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+
+    let guard = SIGNAL_STATE.lock().unwrap_or_else(|e| e.into_inner());
+    match guard.as_ref().and_then(|s| s.current.as_ref()) {
+        Some(token) if !token.is_cancelled() => token.cancel(),
+        _ => eprintln!("\n(Press Ctrl+C again to exit)"),
+    }
+}
+
+/// Record a Ctrl+C press and report whether it completes a double press.
+pub fn register_press() -> bool {
+    let mut guard = SIGNAL_STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.get_or_insert_with(SignalState::default);
+
+    let now = Instant::now();
+    let double_press = state
+        .last_press
+        .is_some_and(|t| now.duration_since(t) <= DOUBLE_PRESS_WINDOW);
+    state.last_press = Some(now);
+    double_press
+}
+
+/// Ctrl+C received as a key event while idle (raw-mode input). Exits on a double press,
+/// otherwise prints the exit hint.
+pub fn idle_interrupt() {
+    if register_press() {
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+    println!("(Press Ctrl+C again to exit)");
+}
+
+/// Start a cancellable operation: returns a fresh token that Ctrl+C will cancel.
+pub fn begin() -> CancelToken {
+    let token = CancelToken::new();
+    let mut guard = SIGNAL_STATE.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(SignalState::default).current = Some(token.clone());
+    token
+}
+
+/// Mark the in-flight operation as finished so Ctrl+C goes back to the idle behavior.
+pub fn end() {
+    let mut guard = SIGNAL_STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(state) = guard.as_mut() {
+        state.current = None;
+    }
+}
@@ -0,0 +1,267 @@
+//! One-shot (non-interactive) chat requests used by scripting commands, and `aion chat`.
+//!
+//! Notes:
+//! - With `use_cache` the reply may come from the response cache, and a fresh reply is
+//!   printed once complete instead of streamed, so a hit and a miss look the same.
+//! - A reply that cannot be cached is a warning, never a failed request.
+//! - With `echo` the reply goes through `ui::reply`: wrapped on a terminal, and paged
+//!   when long (`ui.pager`).
+//! - Without `echo` nothing is printed; the caller shows the returned reply (the JSON
+//!   envelope of `chat --output json`).
+//! - A reply from a fallback provider is noted on stderr and never cached, since the
+//!   cache key names the configured provider.
+//! - A reply that stopped early (token limit, content filter, refusal) says so on stderr,
+//!   and in the JSON envelope as `stop`. So does a budget nearly spent (`costs`).
+//! - `send_structured` (`chat --schema`) neither streams nor uses the cache: a reply is
+//!   only shown once it matched.
+//! - `aion chat` sends one prompt (an argument or stdin) with `send_once`, or, in a
+//!   terminal without one, starts an interactive chat (`interactive`): the full-screen
+//!   view when `ui_mode` asks for it and the terminal can show it, else the REPL. `aion`
+//!   without a command ends there too.
+
+use crate::cache::{self, ResponseCache};
+use crate::cancel;
+use crate::cli::ChatArgs;
+use crate::config::io::load_config;
+use crate::config::{AppConfig, GenParams, UiMode};
+use crate::costs;
+use crate::dry_run;
+use crate::notify::{self, Task};
+use crate::persona;
+use crate::providers::{self, ChatResponse, Message};
+use crate::redact;
+use crate::structured::{self, Filled, Schema};
+use crate::ui::console::Console;
+use crate::ui::reply::ReplyPrinter;
+use crate::{attach, persistence, repl, secrets, sessions, summary, tui};
+use anyhow::{Context, Result};
+use crossterm::tty::IsTty;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Send a single prompt with `cfg`'s provider, streaming the reply to stdout if `echo`.
+pub async fn send_once(cfg: &AppConfig, prompt: &str, use_cache: bool, echo: bool) -> Result<ChatResponse> {
+    cfg.validate().context("config validation failed")?;
+    let messages = persona::compose(cfg, &[Message::user(prompt)]);
+
+    let cache = if use_cache { ResponseCache::open(cfg)? } else { None };
+    let key = cache::key(&cfg.provider, &messages);
+    let mut printer = echo.then(|| ReplyPrinter::new(&cfg.ui));
+    if let Some(hit) = cache.as_ref().and_then(|c| c.get(&key)) {
+        if let Some(printer) = &mut printer {
+            printer.chunk(&hit.content);
+            printer.finish();
+            printer.page();
+        }
+        return Ok(hit);
+    }
+
+    let client = providers::build_client(cfg).context("failed to create provider client")?;
+    cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+    let token = cancel::begin();
+    let started = notify::start();
+    let result = client
+        .chat(&messages, &token, &mut |chunk| {
+            if let (Some(printer), None) = (&mut printer, &cache) {
+                printer.chunk(chunk);
+            }
+        })
+        .await;
+    cancel::end();
+    if !token.is_cancelled() {
+        notify::finish(started, Task::Reply { model: &cfg.provider.model });
+    }
+
+    if let (Some(cache), Ok(resp)) = (&cache, &result) {
+        if let Some(printer) = &mut printer {
+            printer.chunk(&resp.content);
+        }
+        // Reads are fine under --dry-run; storing is a write.
+        let stored = if dry_run::enabled() || resp.fallback.is_some() { Ok(()) } else { cache.put(&key, resp) };
+        if let Err(e) = stored {
+            eprintln!("Warning: reply not cached: {:#}", e);
+        }
+    }
+    if let Some(printer) = &mut printer {
+        printer.finish();
+        if result.is_ok() {
+            printer.page();
+        }
+    }
+    if let Some(fallback) = result.as_ref().ok().and_then(|r| r.fallback.as_ref()) {
+        eprintln!("{}", redact::redact(&fallback.notice()));
+    }
+    if let Some(stop) = result.as_ref().ok().and_then(|r| r.stop) {
+        eprintln!("{}", stop.notice(&cfg.language));
+    }
+    for warning in costs::take_warnings() {
+        eprintln!("{}", warning);
+    }
+
+    result
+}
+
+/// Ask for a reply matching `schema`, at most `max_attempts` times (see `structured::fill`).
+pub async fn send_structured(cfg: &AppConfig, prompt: &str, schema: &Schema, max_attempts: u32) -> Result<Filled> {
+    cfg.validate().context("config validation failed")?;
+    let messages = persona::compose(cfg, &[Message::user(prompt)]);
+    structured::pin(schema);
+
+    let client = providers::build_client(cfg).context("failed to create provider client")?;
+    cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+    let token = cancel::begin();
+    let started = notify::start();
+    let result = structured::fill(client.as_ref(), &messages, schema, max_attempts, &token).await;
+    cancel::end();
+    if !token.is_cancelled() {
+        notify::finish(started, Task::Reply { model: &cfg.provider.model });
+    }
+    for warning in costs::take_warnings() {
+        eprintln!("{}", warning);
+    }
+    result
+}
+
+/* ---------------------------
+   aion chat
+---------------------------- */
+
+pub async fn run(console: &Console, args: &ChatArgs, lang: Option<&str>, strict: bool) -> Result<ExitCode> {
+    let mut cfg = load_config().context("failed to load config")?;
+    if let Some(lang) = lang {
+        cfg.language = lang.to_string();
+    }
+    let mut cfg = args.overrides.apply(&cfg)?;
+    cfg.features.retrieval |= args.retrieval;
+    crate::validate_strict(&cfg, strict)?;
+    persistence::init(persistence::PersistencePolicy::new(&cfg, args.ephemeral));
+    crate::init_globals(&cfg);
+    warn_context(&cfg);
+
+    if args.resume.is_some() && (args.prompt.is_some() || !io::stdin().is_tty()) {
+        anyhow::bail!("--resume continues a chat in the terminal; it takes no prompt");
+    }
+    let prompt = match &args.prompt {
+        Some(text) => text.clone(),
+        None if io::stdin().is_tty() && !args.files.is_empty() => {
+            anyhow::bail!("--file needs a prompt; in the chat view, attach files with Ctrl+O")
+        }
+        None if io::stdin().is_tty() && args.schema.is_some() => anyhow::bail!("--schema needs a prompt"),
+        None if io::stdin().is_tty() => {
+            interactive(&cfg, None, args.resume.as_deref()).await?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text).context("failed to read stdin")?;
+            text
+        }
+    };
+    if prompt.trim().is_empty() {
+        anyhow::bail!("no prompt given (pass it as an argument or on stdin)");
+    }
+    let prompt = match args.files.is_empty() {
+        true => prompt,
+        false => {
+            let progress = !console.is_json() && io::stderr().is_tty();
+            let files = attach::gather(&cfg.caps, &args.files, &cfg.language, progress)?;
+            secrets::confirm(&cfg, files.iter().map(|f| (f.path.display().to_string(), f.text.as_str())))?;
+            match cfg.features.retrieval {
+                true => attach::retrieve(&cfg, &files, &prompt).await?,
+                false => files.iter().map(|f| f.fenced()).chain([prompt]).collect::<Vec<_>>().join("\n"),
+            }
+        }
+    };
+    if let Some(path) = &args.schema {
+        return run_structured(&cfg, &prompt, path, args.max_attempts).await;
+    }
+    let use_cache = args.cache.use_cache(&cfg)?;
+    if !console.is_json() {
+        send_once(&cfg, &prompt, use_cache, true).await?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let resp = send_once(&cfg, &prompt, use_cache, false).await?;
+    let (kind, model) = resp.answered_by(&cfg.provider);
+    console.print_json(&serde_json::json!({
+        "provider": kind.id(),
+        "model": model,
+        "params": cfg.provider.effective_params(&GenParams::default()),
+        "content": resp.content,
+        "usage": resp.usage,
+        "fallback": resp.fallback,
+        "stop": resp.stop,
+    }))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `chat --schema`: print the validated JSON alone, or on stderr what was wrong with the
+/// last reply, and that reply, and fail.
+async fn run_structured(cfg: &AppConfig, prompt: &str, path: &Path, max_attempts: u32) -> Result<ExitCode> {
+    let schema = structured::Schema::load(path)?;
+    let err = match send_structured(cfg, prompt, &schema, max_attempts).await {
+        Ok(filled) => {
+            println!("{}", serde_json::to_string_pretty(&filled.value)?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Err(e) => e,
+    };
+    let Some(exhausted) = err.downcast_ref::<structured::Exhausted>() else {
+        return Err(err);
+    };
+    eprintln!("Error: no reply matched {} after {} attempt(s):", path.display(), exhausted.attempts);
+    for error in &exhausted.errors {
+        eprintln!("  - {}", error);
+    }
+    eprintln!("Last reply:\n{}", exhausted.last);
+    Ok(ExitCode::FAILURE)
+}
+
+/// Load the project context for a chat without a startup summary; a file that cannot be
+/// read is a warning, not a reason to refuse the chat.
+pub fn warn_context(cfg: &AppConfig) {
+    if let Err(e) = persona::load_context(cfg) {
+        eprintln!("Warning: the project context was not loaded: {}", redact::redact(&format!("{:#}", e)));
+    }
+}
+
+/// Interactive chat: the full-screen view when configured and supported, else the REPL.
+/// `splash` is shown before the full-screen view opens; the REPL ignores it. `resume`
+/// continues a saved session (`--resume`) instead of starting one.
+pub async fn interactive(cfg: &AppConfig, splash: Option<&summary::Summary>, resume: Option<&str>) -> Result<()> {
+    let resumed = resume.map(|id| sessions::resume(id, !dry_run::enabled())).transpose()?;
+    // A resumed session can be older than `history.retention_days`; pruning waits for the
+    // next chat rather than removing it from under this one.
+    if resumed.is_none() {
+        prune_sessions(cfg);
+    }
+    if uses_tui(cfg) {
+        tui::run_chat(cfg, splash, resumed).await
+    } else {
+        repl::Repl::new(cfg, resumed)?.run().await
+    }
+}
+
+/// Apply `history.retention_days` before a chat starts (not in an ephemeral one, which
+/// leaves the sessions directory alone). A failure is a warning.
+fn prune_sessions(cfg: &AppConfig) {
+    let days = cfg.history.retention_days;
+    if days == 0 || !persistence::policy().keeps_history() {
+        return;
+    }
+    if dry_run::enabled() {
+        for path in sessions::expired(days).unwrap_or_default() {
+            eprintln!("{}", dry_run::note_remove(&path));
+        }
+        return;
+    }
+    match sessions::prune(days) {
+        Ok(0) => {}
+        Ok(n) => eprintln!("Removed {} session(s) older than {} days (history.retention_days)", n, days),
+        Err(e) => eprintln!("Warning: old sessions were not removed: {:#}", e),
+    }
+}
+
+pub fn uses_tui(cfg: &AppConfig) -> bool {
+    cfg.ui_mode == UiMode::Tui && tui::chat::terminal_capable()
+}
@@ -0,0 +1,989 @@
+//! Command-line interface definition.
+//!
+//! Notes:
+//! - Flags that existed before subcommands (e.g. `--setup`) stay top-level for compatibility.
+//! - Each subcommand gets its own args struct so main.rs only dispatches.
+//! - Help is localized at runtime (`command`): the about text, headings, top-level flags,
+//!   and subcommand summaries come from the `cli.*` locale keys. Untranslated strings,
+//!   nested subcommand help, and clap's own error messages stay English.
+//! - The help language is known before parsing: `--lang` is read from the raw arguments,
+//!   then the config's `language`, then English (`ui_language`).
+
+use crate::backup::Part;
+use crate::bench::Price;
+use crate::config::io::load_config;
+use crate::config::style::{self, StyleConfig};
+use crate::config::{allowed_languages, AppConfig, CapsPreset, ProviderKind, UiMode, UnknownName};
+use crate::pipeline::Budget;
+use crate::sessions::ExportFormat;
+use crate::setup::Step;
+use crate::ui::console::OutputFormat;
+use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
+use crossterm::tty::IsTty;
+use clap::{Arg, ArgAction, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(name = "aion", version, about = "AION - AI Operating Node")]
+pub struct Cli {
+    /// Run the interactive setup wizard
+    #[arg(long)]
+    pub setup: bool,
+
+    /// Open the wizard at this step; confirming it goes straight to the summary
+    #[arg(long, value_enum, value_name = "STEP", requires = "setup")]
+    pub step: Option<Step>,
+
+    /// Save the wizard's changes without asking
+    #[arg(long, short, requires = "setup")]
+    pub yes: bool,
+
+    /// Ask the wizard's questions line by line (screen readers, piped answers)
+    #[arg(long, requires = "setup")]
+    pub plain: bool,
+
+    /// Take the wizard's answers from flags instead of asking, save, and report the outcome (scripts)
+    #[arg(long, requires = "setup", conflicts_with_all = ["plain", "step"])]
+    pub non_interactive: bool,
+
+    /// Endpoint to save with --non-interactive; empty for the provider's default
+    #[arg(long, value_name = "URL", requires = "non_interactive")]
+    pub base_url: Option<String>,
+
+    /// Variable holding the API key to save with --non-interactive
+    #[arg(long, value_name = "VAR", requires = "non_interactive")]
+    pub api_key_env: Option<String>,
+
+    /// Draw the wizard with ASCII symbols only (for terminals that show boxes or question marks)
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Show every reply in $PAGER once complete (CLI mode; `ui.pager` decides otherwise)
+    #[arg(long, global = true)]
+    pub pager: bool,
+
+    /// Suppress everything except errors and command output
+    #[arg(long, short, global = true)]
+    pub quiet: bool,
+
+    /// Skip the startup banner and environment info this run
+    #[arg(long)]
+    pub no_banner: bool,
+
+    /// Keep this chat in memory only: no session file, cache entries, or logs
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// Continue a saved chat session (its id, or the start of it; see `aion sessions list`)
+    #[arg(long, value_name = "ID")]
+    pub resume: Option<String>,
+
+    /// Output format for command results
+    #[arg(long, value_enum, global = true, default_value = "text")]
+    pub output: OutputFormat,
+
+    /// Language for help, messages, and this session (the config file is unchanged)
+    #[arg(long, global = true, value_name = "CODE")]
+    pub lang: Option<String>,
+
+    /// Show what would be written or run, without changing anything
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Treat conflicting feature/capability settings as errors instead of warnings
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Log provider requests and replies to http.log in the config directory
+    #[arg(long, global = true)]
+    pub debug_http: bool,
+
+    /// Change no files this run: no saved config, sessions, cache, or logs, and no commands (also AION_READ_ONLY=1)
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Ring the bell and send a desktop notification when each request finishes, however quick
+    #[arg(long, global = true)]
+    pub notify: bool,
+
+    /// Send files and command output without asking when they look like they contain secrets
+    #[arg(long, global = true)]
+    pub allow_secrets: bool,
+
+    /// Config file to read and write for this run (default: config.toml in the config directory)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub overrides: ProviderOverride,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Commands whose output is never translated (`config get`), so startup leaves the
+    /// locale files unread. Errors are still shown in the UI language.
+    pub fn untranslated(&self) -> bool {
+        matches!(&self.command, Some(Command::Config { action: ConfigAction::Get(_) }))
+    }
+}
+
+/// `--provider` / `--model` / `--tone` / `--format` for one invocation. Never written to
+/// the config.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ProviderOverride {
+    /// Provider for this run only (ollama, openai, claude, openrouter)
+    #[arg(long, value_name = "NAME", value_parser = provider_choice_arg)]
+    pub provider: Option<ProviderChoice>,
+
+    /// Model for this run only
+    #[arg(long, value_name = "NAME")]
+    pub model: Option<String>,
+
+    /// Reply tone for this run only (concise, detailed, eli5)
+    #[arg(long, value_name = "NAME", value_parser = PossibleValuesParser::new(style::names(&style::TONES)))]
+    pub tone: Option<String>,
+
+    /// Reply format for this run only (markdown, plain, json)
+    #[arg(long, value_name = "NAME", value_parser = PossibleValuesParser::new(style::names(&style::FORMATS)))]
+    pub format: Option<String>,
+}
+
+/// `--provider`'s value. Debug builds also take `mock`: the scripted provider replaying
+/// `AION_MOCK_PROVIDER` (`aion_core::providers::mock`) in place of the configured one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderChoice {
+    Kind(ProviderKind),
+    Mock,
+}
+
+impl ProviderOverride {
+    /// The provider given with `--provider`, unless it is `mock`.
+    pub fn kind(&self) -> Option<&ProviderKind> {
+        match &self.provider {
+            Some(ProviderChoice::Kind(kind)) => Some(kind),
+            _ => None,
+        }
+    }
+
+    /// `cfg` with the overrides layered on top, validated when anything was overridden.
+    /// `--tone` / `--format` are pinned for the process (`style::pin`), so they also win
+    /// over `/tone` and survive config reloads.
+    pub fn apply(&self, cfg: &AppConfig) -> Result<AppConfig> {
+        if self.provider == Some(ProviderChoice::Mock) && std::env::var_os(crate::providers::mock::SCRIPT_ENV).is_none() {
+            anyhow::bail!("--provider mock replays a script; set {} to its path", crate::providers::mock::SCRIPT_ENV);
+        }
+        let out = cfg.with_overrides(self.kind(), self.model.as_deref());
+        if self.provider.is_some() || self.model.is_some() {
+            out.validate().context("invalid --provider/--model override")?;
+        }
+        style::pin(StyleConfig { tone: self.tone.clone(), format: self.format.clone() });
+        Ok(out)
+    }
+}
+
+/// `--cache` / `--no-cache` for one-shot requests.
+#[derive(Debug, Clone, Default, Args)]
+pub struct CacheArgs {
+    /// Use the response cache even when stdout is a terminal (the reply is not streamed)
+    #[arg(long, conflicts_with = "no_cache")]
+    pub cache: bool,
+
+    /// Neither read nor store cached replies
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+impl CacheArgs {
+    /// Whether the request may use the cache: `--cache`, or output is not a terminal
+    /// (so streaming is not visible anyway), and never with `--no-cache`. Also needs
+    /// `cache.enabled`, which `--cache` reports when it is off.
+    pub fn use_cache(&self, cfg: &AppConfig) -> Result<bool> {
+        if self.cache && !cfg.cache.enabled {
+            anyhow::bail!("the response cache is off; set cache.enabled = true in config.toml");
+        }
+        Ok(!self.no_cache && (self.cache || !std::io::stdout().is_tty()))
+    }
+}
+
+fn provider_arg(name: &str) -> Result<ProviderKind, String> {
+    name.parse().map_err(|e: UnknownName| e.to_string())
+}
+
+fn provider_choice_arg(name: &str) -> Result<ProviderChoice, String> {
+    match name.trim().eq_ignore_ascii_case("mock") && cfg!(debug_assertions) {
+        true => Ok(ProviderChoice::Mock),
+        false => provider_arg(name).map(ProviderChoice::Kind),
+    }
+}
+
+/// `YYYY-MM`, or empty for this month.
+fn month_arg(value: &str) -> Result<String, String> {
+    let valid = value.len() == 7
+        && value.as_bytes()[4] == b'-'
+        && value.split('-').all(|part| part.bytes().all(|b| b.is_ascii_digit()))
+        && matches!(value[5..].parse::<u32>(), Ok(1..=12));
+    match value.is_empty() || valid {
+        true => Ok(value.to_string()),
+        false => Err("expected YYYY-MM, e.g. 2026-10".to_string()),
+    }
+}
+
+fn chunk_chars_arg(value: &str) -> Result<usize, String> {
+    let n: usize = value.parse().map_err(|e: std::num::ParseIntError| e.to_string())?;
+    match n >= crate::ask::MIN_CHUNK_CHARS {
+        true => Ok(n),
+        false => Err(format!("must be at least {}", crate::ask::MIN_CHUNK_CHARS)),
+    }
+}
+
+fn price_arg(value: &str) -> Result<(String, Price), String> {
+    let (model, price) = value.split_once('=').ok_or("expected MODEL=IN,OUT")?;
+    Ok((model.trim().to_string(), price.parse()?))
+}
+
+fn ui_mode_arg(name: &str) -> Result<UiMode, String> {
+    name.parse().map_err(|e: UnknownName| e.to_string())
+}
+
+fn language_arg(code: &str) -> Result<String, String> {
+    let allowed = allowed_languages();
+    if allowed.contains(code) {
+        Ok(code.to_string())
+    } else {
+        Err(format!("unsupported language '{}' (expected one of: {})", code, allowed.into_iter().collect::<Vec<_>>().join(", ")))
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run health checks on the config, provider, locales, and terminal
+    Doctor(DoctorArgs),
+
+    /// Show config, provider, session, locale, and endpoint status
+    Status(StatusArgs),
+
+    /// Answer one prompt (argument or stdin), or start a chat session
+    Chat(ChatArgs),
+
+    /// Send a prompt template (input from the argument or stdin)
+    Run(RunArgs),
+
+    /// Run a command and explain its output when it fails
+    Exec(ExecArgs),
+
+    /// Ask a question about a text file, or summarize it
+    Ask(AskArgs),
+
+    /// Compare how quickly models answer a small set of prompts
+    Bench(BenchArgs),
+
+    /// Manage prompt templates
+    Templates {
+        #[command(subcommand)]
+        action: TemplatesAction,
+    },
+
+    /// List or check the configured provider's models
+    Models(ModelsArgs),
+
+    /// List and export saved chat sessions
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+
+    /// Write a config from flags, without the wizard (safe to rerun)
+    Init(InitArgs),
+
+    /// Maintain the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Check the installed translations
+    Locales {
+        #[command(subcommand)]
+        action: LocalesAction,
+    },
+
+    /// Inspect or clear the response cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Show the tokens and cost recorded for cloud providers, per month or for one month
+    Usage(UsageArgs),
+
+    /// Show or clear the log of commands run and files written
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+
+    /// Bundle config, checks, and logs (secrets masked) for a bug report
+    Report(ReportArgs),
+
+    /// Bundle config, templates, recent models, and locales (secrets masked) for another machine
+    Export(ExportArgs),
+
+    /// Restore what `aion export` bundled into the config dir
+    Import(ImportArgs),
+
+    /// Check for a newer release and replace this executable with it
+    SelfUpdate(SelfUpdateArgs),
+
+    /// Check that this build works, offline and without a terminal (for packaging and CI)
+    SelfTest(SelfTestArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Print the check results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SelfTestArgs {
+    /// Print the check results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ModelsArgs {
+    /// Print the result as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub action: ModelsAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ModelsAction {
+    /// List models offered by the configured provider (`*` marks the configured one)
+    List,
+
+    /// Exit non-zero unless the configured `provider.model` is offered
+    Check,
+}
+
+#[derive(Debug, Args)]
+pub struct ChatArgs {
+    /// Prompt to answer; read from stdin when omitted and stdin is piped
+    pub prompt: Option<String>,
+
+    #[command(flatten)]
+    pub overrides: ProviderOverride,
+
+    #[command(flatten)]
+    pub cache: CacheArgs,
+
+    /// Keep this chat in memory only: no session file, cache entries, or logs
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// Continue a saved chat session (its id, or the start of it; see `aion sessions list`)
+    #[arg(long, value_name = "ID")]
+    pub resume: Option<String>,
+
+    /// Attach the files matching a pattern (e.g. 'src/**/*.rs') to the prompt, repeatable
+    #[arg(long = "file", value_name = "PATTERN")]
+    pub files: Vec<String>,
+
+    /// Send only the parts of attached files most like the prompt (features.retrieval for this
+    /// run; needs an embedding-capable provider)
+    #[arg(long)]
+    pub retrieval: bool,
+
+    /// Reply with JSON matching this JSON Schema file; only the validated JSON is printed
+    #[arg(long, value_name = "FILE", conflicts_with = "cache")]
+    pub schema: Option<PathBuf>,
+
+    /// Requests made with --schema before giving up; each retry tells the model what was wrong
+    #[arg(long, value_name = "N", requires = "schema", default_value_t = crate::structured::DEFAULT_ATTEMPTS,
+          value_parser = clap::value_parser!(u32).range(1..=10))]
+    pub max_attempts: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// Template name
+    pub template: String,
+
+    /// Template variable, repeatable
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    pub vars: Vec<String>,
+
+    /// Input bound to {input}; read from stdin when omitted and stdin is piped
+    pub input: Option<String>,
+
+    // Layered over the template's own provider/model.
+    #[command(flatten)]
+    pub overrides: ProviderOverride,
+
+    #[command(flatten)]
+    pub cache: CacheArgs,
+}
+
+/// `aion exec -- <command>`. Exits with the command's exit code.
+#[derive(Debug, Args)]
+pub struct ExecArgs {
+    /// Analyze the output even when the command succeeds
+    #[arg(long)]
+    pub always_analyze: bool,
+
+    /// Run nothing; analyze output piped on stdin
+    #[arg(long)]
+    pub no_run: bool,
+
+    /// Directory to run the command in (over exec.default_cwd); checked against caps.read_allow/read_deny
+    #[arg(long, value_name = "DIR", conflicts_with = "no_run")]
+    pub cwd: Option<PathBuf>,
+
+    /// Bytes of output sent for analysis; longer output keeps its start and end
+    #[arg(long, value_name = "BYTES", default_value_t = crate::exec::DEFAULT_MAX_OUTPUT)]
+    pub max_output: usize,
+
+    #[command(flatten)]
+    pub overrides: ProviderOverride,
+
+    /// The command and its arguments, after `--`
+    #[arg(last = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+/// `aion ask <file> "<question>"`. Documents over `--chunk-chars` are asked in parts.
+#[derive(Debug, Args)]
+pub struct AskArgs {
+    /// Text file to read
+    pub file: PathBuf,
+
+    /// What to ask about the file
+    #[arg(required_unless_present = "summarize")]
+    pub question: Option<String>,
+
+    /// Summarize the file instead of answering a question
+    #[arg(long, conflicts_with = "question")]
+    pub summarize: bool,
+
+    /// Largest file read, in bytes
+    #[arg(long, value_name = "BYTES", default_value_t = crate::ask::DEFAULT_MAX_BYTES)]
+    pub max_bytes: u64,
+
+    /// Characters of the file per request; a longer file is asked in overlapping parts
+    #[arg(long, value_name = "CHARS", default_value_t = Budget::default().chunk_chars, value_parser = chunk_chars_arg)]
+    pub chunk_chars: usize,
+
+    /// Print the answer, and which parts of the file it drew on, as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    #[command(flatten)]
+    pub overrides: ProviderOverride,
+}
+
+/// `aion bench`. Requests run one at a time; Ctrl+C stops and summarizes what finished.
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Prompts to send, one per line (lines starting with # are skipped); a built-in set when omitted
+    #[arg(long, value_name = "FILE")]
+    pub prompts: Option<PathBuf>,
+
+    /// Models to compare, comma-separated (default: every installed model on Ollama, else the configured one)
+    #[arg(long, value_name = "MODELS", value_delimiter = ',')]
+    pub models: Vec<String>,
+
+    /// Times each prompt is sent to each model
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub rounds: u32,
+
+    /// Seconds a request may take before it counts as failed
+    #[arg(long, value_name = "SECS", default_value_t = crate::benchmark::DEFAULT_TIMEOUT_SECS,
+          value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: u64,
+
+    /// A model's price in dollars per million input and output tokens, for the cost column (repeatable)
+    #[arg(long, value_name = "MODEL=IN,OUT", value_parser = price_arg)]
+    pub price: Vec<(String, Price)>,
+
+    /// Provider for this run only (ollama, openai, claude, openrouter)
+    #[arg(long, value_name = "NAME", value_parser = provider_arg)]
+    pub provider: Option<ProviderKind>,
+
+    /// Print the results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// `aion report`. Writes `aion-report-<time>.zip` in the current directory by default.
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// Where to write the bundle
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Write a directory of plain files instead of a zip
+    #[arg(long)]
+    pub no_zip: bool,
+
+    /// Add the most recently updated chat session
+    #[arg(long)]
+    pub include_session: bool,
+
+    /// Lines from the end of http.log to include
+    #[arg(long, value_name = "N", default_value_t = crate::report::DEFAULT_LOG_LINES)]
+    pub log_lines: usize,
+}
+
+/// `aion export`. Writes `aion-backup-<time>.tar.gz` in the current directory by default.
+/// See `crate::transfer`.
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Where to write the archive
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Add the saved chat sessions
+    #[arg(long)]
+    pub include_sessions: bool,
+}
+
+/// `aion import`. See `crate::transfer`.
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// The archive `aion export` wrote
+    #[arg(value_name = "ARCHIVE")]
+    pub archive: PathBuf,
+
+    /// Replace files that exist already
+    #[arg(long)]
+    pub force: bool,
+
+    /// Import only these parts
+    #[arg(long, value_name = "PARTS", value_delimiter = ',')]
+    pub only: Vec<Part>,
+}
+
+/// `aion self-update`. See `crate::update`.
+#[derive(Debug, Args)]
+pub struct SelfUpdateArgs {
+    /// Only report whether a newer release exists, with its changelog
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplatesAction {
+    /// List built-in and user templates
+    List,
+
+    /// Show a template's body and overrides
+    Show { name: String },
+
+    /// Add or replace a user template
+    Add(TemplateAddArgs),
+
+    /// Remove a user template
+    Remove { name: String },
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateAddArgs {
+    pub name: String,
+
+    /// Template body with {input} and named {placeholders}
+    #[arg(long)]
+    pub body: String,
+
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Provider override (ollama, openai, claude, openrouter)
+    #[arg(long)]
+    pub provider: Option<String>,
+
+    /// Model override
+    #[arg(long)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionsAction {
+    /// List saved sessions, newest first
+    List,
+
+    /// Export a session (or all with --all) as Markdown or JSON
+    Export(SessionExportArgs),
+
+    /// Find sessions whose title or messages contain every word of TEXT
+    Search(SessionSearchArgs),
+
+    /// Delete sessions not written for longer than history.retention_days
+    Prune(SessionPruneArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SessionPruneArgs {
+    /// Age in days instead of history.retention_days
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub days: Option<u32>,
+}
+
+#[derive(Debug, Args)]
+pub struct SessionSearchArgs {
+    /// Words to look for (any order, case-insensitive)
+    #[arg(required = true, num_args = 1..)]
+    pub text: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct SessionExportArgs {
+    /// Session id or unique id prefix
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    pub id: Option<String>,
+
+    /// Export every session into the --out directory
+    #[arg(long, requires = "out")]
+    pub all: bool,
+
+    #[arg(long, value_enum, default_value = "md")]
+    pub format: ExportFormat,
+
+    /// Output file (directory with --all); stdout when omitted
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Migrate the config file to the current version, showing the changes first
+    Upgrade(ConfigUpgradeArgs),
+
+    /// Print one setting by its dotted key (e.g. `provider.model`)
+    Get(ConfigGetArgs),
+
+    /// Change one setting by its dotted key (e.g. `caps.read_deny '["~/.ssh/**"]'`)
+    Set(ConfigSetArgs),
+
+    /// Check the config file; errors point at their line with a fix (--strict: warnings too)
+    Validate,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigUpgradeArgs {
+    /// Write the upgraded file without asking
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigGetArgs {
+    /// Dotted key; `provider.effective_params` shows the parameters requests are sent with
+    pub key: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigSetArgs {
+    /// Dotted key
+    pub key: String,
+
+    /// A TOML value (`true`, `42`, `["a", "b"]`); anything else is taken as a string
+    pub value: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheAction {
+    /// Show the cache directory, entry count, and size
+    Stats,
+
+    /// Delete every cached reply
+    Clear,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditAction {
+    /// Print the most recent entries
+    Tail(AuditTailArgs),
+
+    /// Delete the audit log
+    Clear,
+}
+
+/// `aion init`. Unset flags take the same defaults as a fresh config.
+#[derive(Debug, Args)]
+pub struct InitArgs {
+    /// Provider (ollama, openai, claude, openrouter)
+    #[arg(long, value_name = "NAME", value_parser = provider_arg)]
+    pub provider: Option<ProviderKind>,
+
+    /// Model; the provider's default when omitted
+    #[arg(long, value_name = "NAME")]
+    pub model: Option<String>,
+
+    /// Endpoint; the provider's default when omitted
+    #[arg(long, value_name = "URL")]
+    pub base_url: Option<String>,
+
+    /// Environment variable holding the API key; the provider's usual one when omitted
+    #[arg(long, value_name = "VAR")]
+    pub api_key_env: Option<String>,
+
+    #[arg(long, value_name = "CODE", value_parser = language_arg)]
+    pub language: Option<String>,
+
+    /// Interface (tui, cli)
+    #[arg(long, value_name = "MODE", value_parser = ui_mode_arg)]
+    pub ui_mode: Option<UiMode>,
+
+    /// Capabilities preset
+    #[arg(long, value_enum, value_name = "PRESET", default_value = "safe")]
+    pub caps: CapsPreset,
+
+    /// Comma-separated features to enable (the rest are disabled); all when omitted
+    #[arg(long, value_name = "LIST", value_delimiter = ',', num_args = 0..)]
+    pub features: Option<Vec<String>>,
+
+    /// Also copy the bundled locale files into the locales directory
+    #[arg(long)]
+    pub install_locales: bool,
+
+    /// Replace an existing config (and locale files) that differ
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct UsageArgs {
+    /// One month per provider and model (YYYY-MM; this month without a value)
+    #[arg(long, value_name = "YYYY-MM", num_args = 0..=1, default_missing_value = "", value_parser = month_arg)]
+    pub month: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct AuditTailArgs {
+    /// Number of entries to show
+    #[arg(long = "n", value_name = "N", default_value_t = 50)]
+    pub n: usize,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LocalesAction {
+    /// Report missing keys per locale; with --widths, flag strings too wide for the wizard
+    Check(LocalesCheckArgs),
+    /// Compare the keys the code looks up with `en`: unused keys, and keys `en` lacks
+    Stats(LocalesStatsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct LocalesStatsArgs {
+    /// Rust sources to scan; repeat for more (e.g. also crates/aion-core/src)
+    #[arg(long, value_name = "DIR", default_value = "crates/aion/src")]
+    pub src: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct LocalesCheckArgs {
+    /// Locale code to check; all installed locales when omitted
+    pub code: Option<String>,
+
+    /// Also check wizard strings against the column budgets below
+    #[arg(long)]
+    pub widths: bool,
+
+    /// Column budget for titles (`*.title`, `wizard.step.*`)
+    #[arg(long, value_name = "COLS", default_value_t = 30, requires = "widths")]
+    pub title_width: usize,
+
+    /// Column budget for status-line text (`status.*`, `wizard.hint.*`)
+    #[arg(long, value_name = "COLS", default_value_t = 76, requires = "widths")]
+    pub status_width: usize,
+
+    /// Column budget for other wizard labels
+    #[arg(long, value_name = "COLS", default_value_t = 48, requires = "widths")]
+    pub label_width: usize,
+}
+
+/* ---------------------------
+   Localized help
+---------------------------- */
+
+/// Top-level flags whose help text is localized, with their locale key.
+const LOCALIZED_FLAGS: &[(&str, &str)] = &[
+    ("setup", "cli.setup"),
+    ("step", "cli.step"),
+    ("yes", "cli.yes"),
+    ("plain", "cli.plain"),
+    ("non_interactive", "cli.non_interactive"),
+    ("base_url", "cli.base_url"),
+    ("api_key_env", "cli.api_key_env"),
+    ("ascii", "cli.ascii"),
+    ("pager", "cli.pager"),
+    ("quiet", "cli.quiet"),
+    ("no_banner", "cli.no_banner"),
+    ("ephemeral", "cli.ephemeral"),
+    ("resume", "cli.resume"),
+    ("output", "cli.output"),
+    ("lang", "cli.lang"),
+    ("dry_run", "cli.dry_run"),
+    ("strict", "cli.strict"),
+    ("debug_http", "cli.debug_http"),
+    ("read_only", "cli.read_only"),
+    ("notify", "cli.notify"),
+    ("allow_secrets", "cli.allow_secrets"),
+    ("config", "cli.config"),
+    ("provider", "cli.provider"),
+    ("model", "cli.model"),
+    ("tone", "cli.tone"),
+    ("format", "cli.format"),
+];
+
+const LOCALIZED_COMMANDS: &[&str] = &["doctor", "status", "chat", "run", "exec", "ask", "bench", "templates", "models", "sessions", "init", "config", "locales", "cache", "usage", "audit", "report", "export", "import", "self-update", "self-test"];
+
+/// The interface language for this run: `--lang`, else the config's, else English.
+pub fn ui_language() -> String {
+    lang_arg(std::env::args_os())
+        .or_else(|| load_config().ok().map(|c| c.language))
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// The value of `--lang CODE` / `--lang=CODE`, read before clap parses anything.
+fn lang_arg(args: impl IntoIterator<Item = OsString>) -> Option<String> {
+    flag_value(args, "--lang")
+}
+
+/// `--config FILE`, read before clap parses anything: `ui_language` already loads the
+/// config.
+pub fn config_arg() -> Option<PathBuf> {
+    flag_value(std::env::args_os(), "--config").map(PathBuf::from)
+}
+
+/// The value of `flag VALUE` / `flag=VALUE` before any `--`.
+fn flag_value(args: impl IntoIterator<Item = OsString>, flag: &str) -> Option<String> {
+    let mut args = args.into_iter().skip(1).map(|a| a.to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+        if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// `key` in `lang`. English is what the definitions above already say, so `en` never
+/// reads the locale files.
+fn localized(lang: &str, key: &str) -> Option<String> {
+    if lang == "en" {
+        return None;
+    }
+    crate::i18n::shared()
+        .map(|m| m.t(lang, key))
+        .filter(|text| text != key)
+}
+
+/// The CLI definition with help text in `lang`.
+pub fn command(lang: &str) -> clap::Command {
+    let tr = |key: &str, default: &str| localized(lang, key).unwrap_or_else(|| default.to_string());
+
+    let mut cmd = Cli::command()
+        .disable_help_flag(true)
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("help")
+                .short('h')
+                .long("help")
+                .action(ArgAction::Help)
+                .global(true)
+                .help(tr("cli.help", "Print help")),
+        )
+        .arg(
+            Arg::new("version")
+                .short('V')
+                .long("version")
+                .action(ArgAction::Version)
+                .help(tr("cli.version", "Print version")),
+        )
+        .mut_arg("lang", |a| a.value_parser(PossibleValuesParser::new(allowed_languages())));
+
+    if let Some(about) = localized(lang, "cli.about") {
+        cmd = cmd.about(about);
+    }
+    for (id, key) in LOCALIZED_FLAGS {
+        if let Some(help) = localized(lang, key) {
+            cmd = cmd.mut_arg(*id, |a| a.help(help));
+        }
+    }
+    for name in LOCALIZED_COMMANDS {
+        if let Some(about) = localized(lang, &format!("cli.command.{}", name)) {
+            cmd = cmd.mut_subcommand(*name, |c| c.about(about));
+        }
+    }
+
+    let labels = HelpLabels {
+        usage: tr("cli.usage", "Usage"),
+        commands: tr("cli.commands", "Commands"),
+        options: tr("cli.options", "Options"),
+    };
+    labels.apply(cmd)
+}
+
+/// Section labels used in every help page.
+struct HelpLabels {
+    usage: String,
+    commands: String,
+    options: String,
+}
+
+impl HelpLabels {
+    /// Label `cmd` and, recursively, its subcommands.
+    fn apply(&self, mut cmd: clap::Command) -> clap::Command {
+        cmd = cmd
+            .help_template(format!(
+                "{{about-with-newline}}\n{}: {{usage}}\n\n{{all-args}}{{after-help}}",
+                self.usage
+            ))
+            .subcommand_help_heading(self.commands.clone());
+
+        let ids: Vec<String> = cmd.get_arguments().map(|a| a.get_id().to_string()).collect();
+        for id in ids {
+            cmd = cmd.mut_arg(id, |a| a.help_heading(self.options.clone()));
+        }
+        let names: Vec<String> = cmd.get_subcommands().map(|c| c.get_name().to_string()).collect();
+        for name in names {
+            cmd = cmd.mut_subcommand(name, |c| self.apply(c));
+        }
+        cmd
+    }
+}
+
+/// Parse the process arguments with help in `lang`. Exits on `--help` and usage errors.
+/// Nothing else is translated, so the English definitions are tried first and the
+/// locale files are read only for help or an error.
+pub fn parse(lang: &str) -> Cli {
+    let matches = match command("en").try_get_matches() {
+        Ok(matches) => matches,
+        // The version line has nothing to translate.
+        Err(e) if lang == "en" || e.kind() == clap::error::ErrorKind::DisplayVersion => e.exit(),
+        Err(_) => command(lang).get_matches(),
+    };
+    Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+}
@@ -0,0 +1,70 @@
+//! Reading the system clipboard, for the chat view's Ctrl+V and `/paste`.
+//!
+//! Notes:
+//! - No clipboard library: the platform's own tool is run (`pbpaste`, PowerShell's
+//!   `Get-Clipboard`, `wl-paste`, `xclip`, `xsel`), the first one that is installed.
+//! - A headless session (no `WAYLAND_DISPLAY` or `DISPLAY` outside macOS and Windows),
+//!   no installed tool, or a tool that does not answer within `TIMEOUT` is an error; the
+//!   chat view reports it in its status line and carries on.
+//! - The tools are fixed and take no input from the user, so `caps.run_commands` does
+//!   not apply and nothing is audited.
+//! - `\r\n` and `\r` become `\n`; the text is otherwise unchanged.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::io;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The clipboard's text.
+pub async fn read() -> Result<String> {
+    let tools = tools()?;
+    for tool in &tools {
+        let output = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+        let output = match tokio::time::timeout(TIMEOUT, output).await {
+            Err(_) => bail!("{} did not answer within {}s", tool[0], TIMEOUT.as_secs()),
+            Ok(Err(e)) if e.kind() == io::ErrorKind::NotFound => continue,
+            Ok(result) => result.with_context(|| format!("failed to run {}", tool[0]))?,
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().next().unwrap_or("").trim();
+            bail!("{} failed: {}", tool[0], if reason.is_empty() { "no text in the clipboard" } else { reason });
+        }
+        let text = String::from_utf8(output.stdout).map_err(|_| anyhow!("the clipboard does not hold text"))?;
+        return Ok(text.replace("\r\n", "\n").replace('\r', "\n"));
+    }
+    let names: Vec<&str> = tools.iter().map(|t| t[0]).collect();
+    bail!("no clipboard tool found (install {})", names.join(" or "))
+}
+
+/// Commands that print the clipboard, in the order they are tried.
+fn tools() -> Result<Vec<&'static [&'static str]>> {
+    if cfg!(target_os = "macos") {
+        return Ok(vec![&["pbpaste"]]);
+    }
+    if cfg!(windows) {
+        return Ok(vec![&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]);
+    }
+    let set = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+    let mut tools: Vec<&'static [&'static str]> = Vec::new();
+    if set("WAYLAND_DISPLAY") {
+        tools.push(&["wl-paste", "--no-newline"]);
+    }
+    if set("DISPLAY") {
+        tools.push(&["xclip", "-selection", "clipboard", "-out"]);
+        tools.push(&["xsel", "--clipboard", "--output"]);
+    }
+    if tools.is_empty() {
+        bail!("no clipboard in this session (no display)");
+    }
+    Ok(tools)
+}
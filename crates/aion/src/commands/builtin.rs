@@ -0,0 +1,601 @@
+//! The commands `registry` lists, one unit struct each.
+
+use super::{ContextAction, SessionContext, HISTORY_LISTED};
+use crate::catalog::Catalog;
+use crate::config::style::{self, TONES};
+use crate::config::{GenParams, ProviderKind};
+use crate::dry_run;
+use crate::i18n::{self, bidi};
+use crate::persona;
+use crate::redact;
+use crate::sessions::{save_transcript, transcript};
+use crate::suggest;
+use crate::workdir;
+use aion_core::commands::{split_word, ArgSpec, ArgValues, Args, Command, CommandError};
+use async_trait::async_trait;
+use std::path::Path;
+
+type Ctx = dyn SessionContext;
+
+fn invalid(value: &str, expected: impl Into<String>) -> CommandError {
+    CommandError::InvalidValue { value: value.to_string(), expected: expected.into() }
+}
+
+/// The argument; parsing has checked it is there unless the spec makes it optional.
+fn arg(args: &Args) -> &str {
+    args.arg.as_deref().unwrap_or_default()
+}
+
+pub struct Help;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Help {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["?"]
+    }
+
+    fn summary(&self) -> &'static str {
+        "List available commands"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let lang = ctx.cfg().language.clone();
+        let mut text = i18n::text(&lang, "commands.heading", "Commands:", &[]);
+        for line in super::registry().help_lines(&lang) {
+            text.push('\n');
+            text.push_str(&line);
+        }
+        ctx.notice(text);
+        Ok(())
+    }
+}
+
+pub struct Model;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Model {
+    fn name(&self) -> &'static str {
+        "model"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Switch the model for this session"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<name>", values: ArgValues::Models, flags: &["--save"], ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let name = arg(&args).to_string();
+        if let Some(notice) = Catalog::load().notice(&ctx.cfg().provider.kind, &name) {
+            ctx.notice(format!("Note: {}", notice));
+        }
+        let mut next = ctx.cfg().clone();
+        next.provider.model = name;
+        ctx.apply_config(next, args.flag("--save"));
+        Ok(())
+    }
+}
+
+pub struct Provider;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Provider {
+    fn name(&self) -> &'static str {
+        "provider"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Switch the provider (resets model and endpoint to its defaults)"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<kind>", values: ArgValues::Providers, flags: &["--save"], ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let kind = arg(&args).parse::<ProviderKind>().map_err(|e| invalid(&e.value, e.expected.join(", ")))?;
+        ctx.switch_provider(kind, args.flag("--save"));
+        Ok(())
+    }
+}
+
+pub struct Lang;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Lang {
+    fn name(&self) -> &'static str {
+        "lang"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Change the interface language"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<code>", values: ArgValues::Locales, flags: &["--save"], ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let mut next = ctx.cfg().clone();
+        next.language = arg(&args).to_string();
+        ctx.apply_config(next, args.flag("--save"));
+        Ok(())
+    }
+}
+
+pub struct Temp;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Temp {
+    fn name(&self) -> &'static str {
+        "temp"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Set the temperature for this session (off: back to the config's)"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<value|off>", values: ArgValues::Free, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let value = arg(&args);
+        let temperature = match value.eq_ignore_ascii_case("off") {
+            true => None,
+            false => {
+                let t: f64 = value.parse().map_err(|_| invalid(value, "a number from 0 to 2, off"))?;
+                let params = GenParams { temperature: Some(t), ..GenParams::default() };
+                params.validate().map_err(|_| invalid(value, "a number from 0 to 2, off"))?;
+                Some(t)
+            }
+        };
+        let params = GenParams { temperature, ..*ctx.params() };
+        ctx.set_params(params);
+        let text = match ctx.cfg().provider.effective_params(ctx.params()).temperature {
+            Some(t) if temperature.is_some() => format!("Temperature {} for this session.", t),
+            Some(t) => format!("Temperature back to the config's ({}).", t),
+            None => "Temperature back to the provider's default.".to_string(),
+        };
+        ctx.notice(text);
+        Ok(())
+    }
+}
+
+pub struct Retry;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Retry {
+    fn name(&self) -> &'static str {
+        "retry"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Resend the last prompt that failed"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        match ctx.state_mut().last_failed.take() {
+            Some(prompt) => ctx.send(prompt).await,
+            None => ctx.notice("Nothing to retry.".to_string()),
+        }
+        Ok(())
+    }
+}
+
+pub struct Regen;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Regen {
+    fn name(&self) -> &'static str {
+        "regen"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Replace the last reply with a new one (optionally from another model)"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { options: &[("--model", "<name>")], ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        ctx.regen(args.option("--model").map(String::from)).await;
+        Ok(())
+    }
+}
+
+pub struct Continue;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Continue {
+    fn name(&self) -> &'static str {
+        "continue"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Pick up a reply that was cut off at the token limit"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        ctx.resume().await;
+        Ok(())
+    }
+}
+
+pub struct Undo;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Undo {
+    fn name(&self) -> &'static str {
+        "undo"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Drop the last exchange from the conversation"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        super::undo(ctx);
+        Ok(())
+    }
+}
+
+pub struct History;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for History {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn summary(&self) -> &'static str {
+        "List the last n entries typed (20 by default); !<n> sends one again"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "[n]", values: ArgValues::Count, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let count = match args.arg.as_deref().map(|value| (value, value.parse::<usize>())) {
+            None => HISTORY_LISTED,
+            Some((_, Ok(n))) if n > 0 => n,
+            Some((value, _)) => return Err(invalid(value, "a number of entries")),
+        };
+        let lines: Vec<String> = ctx
+            .history()
+            .recent(count)
+            .map(|(number, entry)| format!("{:>5}  {}", number, entry.replace('\n', "\n       ")))
+            .collect();
+        ctx.notice(if lines.is_empty() { "No history yet.".to_string() } else { lines.join("\n") });
+        Ok(())
+    }
+}
+
+pub struct LangReply;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for LangReply {
+    fn name(&self) -> &'static str {
+        "lang-reply"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Ask for replies in the configured language (this session)"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "on|off", values: ArgValues::OnOff, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let enabled = match arg(&args).to_ascii_lowercase().as_str() {
+            "on" => true,
+            "off" => false,
+            _ => return Err(invalid(arg(&args), "on, off")),
+        };
+        ctx.cfg_mut().provider.respond_in_config_language = enabled;
+        match (enabled, persona::language_instruction(ctx.cfg())) {
+            (false, _) => ctx.notice("Replies follow the conversation language.".to_string()),
+            (true, Some(text)) => ctx.notice(format!("Reply instruction: {}", bidi::part("en", &text))),
+            (true, None) => {
+                let text = format!("No reply instruction is available for '{}'.", ctx.cfg().language);
+                ctx.error(text)
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Tone;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Tone {
+    fn name(&self) -> &'static str {
+        "tone"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Set the reply tone: concise, detailed, eli5 (this session)"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<name>|off", values: ArgValues::Tones, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let value = arg(&args).to_ascii_lowercase();
+        let name = match value.as_str() {
+            "off" => None,
+            _ if TONES.iter().any(|(name, _)| *name == value) => Some(value),
+            _ => return Err(invalid(arg(&args), format!("{}, off", style::names(&TONES).join(", ")))),
+        };
+        if let Some(pinned) = style::pinned().tone {
+            ctx.error(format!("The tone is set to {} by --tone for this run.", pinned));
+            return Ok(());
+        }
+        ctx.notice(match &name {
+            Some(name) => format!("Tone: {} (this session).", name),
+            None => "Tone off (this session).".to_string(),
+        });
+        ctx.cfg_mut().style.tone = name;
+        Ok(())
+    }
+}
+
+pub struct Clear;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Clear {
+    fn name(&self) -> &'static str {
+        "clear"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Reset the conversation"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        ctx.clear();
+        ctx.notice("Conversation cleared.".to_string());
+        Ok(())
+    }
+}
+
+pub struct Config;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Config {
+    fn name(&self) -> &'static str {
+        "config"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Show the session config and where values come from; set a key, or save what was set"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec {
+            arg: "[set <key> <value> | save]",
+            values: ArgValues::Actions(&["set", "save"]),
+            rest: true,
+            ..ArgSpec::NONE
+        }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        match (args.arg.as_deref(), args.rest.as_deref()) {
+            (None, _) => ctx.show_config(),
+            (Some("set"), Some(rest)) => match split_word(rest) {
+                (key, value) if !value.trim().is_empty() => {
+                    let (key, value) = (key.to_string(), value.trim().to_string());
+                    let cfg = ctx.cfg().clone();
+                    match ctx.state_mut().edits.set(&cfg, &key, &value) {
+                        Ok(next) => {
+                            if ctx.switch_to(next) {
+                                ctx.notice(format!("{} = {} (this session; /config save keeps it)", key, redact::redact(&value)));
+                            }
+                        }
+                        Err(e) => ctx.fail(&e),
+                    }
+                }
+                _ => return Err(CommandError::Usage(self.usage())),
+            },
+            (Some("save"), None) => ctx.save_edits(),
+            (Some("set" | "save"), _) => return Err(CommandError::Usage(self.usage())),
+            (Some(other), _) => return Err(invalid(other, "set, save")),
+        }
+        Ok(())
+    }
+}
+
+pub struct Context;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Context {
+    fn name(&self) -> &'static str {
+        "context"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Show the project context sent with each message; reload it, or stop sending it"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "[show | reload | off]", values: ArgValues::Actions(&["show", "reload", "off"]), ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let action = match args.arg.as_deref().unwrap_or("show").to_ascii_lowercase().as_str() {
+            "show" => ContextAction::Show,
+            "reload" => ContextAction::Reload,
+            "off" => ContextAction::Off,
+            _ => return Err(invalid(arg(&args), "show, reload, off")),
+        };
+        match persona::context_command(ctx.cfg(), action) {
+            Ok(text) => ctx.notice(text),
+            Err(e) => ctx.fail(&e),
+        }
+        Ok(())
+    }
+}
+
+pub struct Tpl;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Tpl {
+    fn name(&self) -> &'static str {
+        "tpl"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Send a prompt template, with the rest of the line as {input}"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<name> [input]", values: ArgValues::Templates, rest: true, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let vars = args.rest.clone().map(|input| ("input".to_string(), input)).into_iter().collect();
+        if let Err(e) = ctx.send_template(arg(&args), vars).await {
+            ctx.fail(&e);
+        }
+        Ok(())
+    }
+}
+
+pub struct Suggest;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Suggest {
+    fn name(&self) -> &'static str {
+        "suggest"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Suggest a shell command (--with-history: with your recent commands)"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<request>", values: ArgValues::Free, flags: &["--with-history"], rest: true, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let sent = match suggest::prepare(ctx.cfg(), &args.text, args.flag("--with-history")) {
+            Ok(suggestion) => {
+                if let Some(notice) = &suggestion.notice {
+                    ctx.notice(notice.clone());
+                }
+                ctx.send_suggestion(suggestion).await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            ctx.fail(&e);
+        }
+        Ok(())
+    }
+}
+
+pub struct Cd;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Cd {
+    fn name(&self) -> &'static str {
+        "cd"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Change the directory commands run in and relative attachments are read from"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<path>", values: ArgValues::Free, rest: true, ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        match workdir::change(ctx.cfg(), Path::new(&args.text)) {
+            Ok(dir) => ctx.notice(format!("Working directory: {} ({})", dir, dir.source)),
+            Err(e) => ctx.fail(&e),
+        }
+        Ok(())
+    }
+}
+
+pub struct Save;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Save {
+    fn name(&self) -> &'static str {
+        "save"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Write the last reply (or with --all, the transcript) to a file"
+    }
+
+    fn spec(&self) -> ArgSpec {
+        ArgSpec { arg: "<path>", values: ArgValues::Free, flags: &["--all"], ..ArgSpec::NONE }
+    }
+
+    async fn execute(&self, args: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        let (path, all) = (arg(&args), args.flag("--all"));
+        let result = match dry_run::enabled() {
+            true => transcript(ctx.cfg(), ctx.session(), all).map(|_| dry_run::note_write(Path::new(path))),
+            false => save_transcript(ctx.cfg(), ctx.session(), path, all).map(|()| format!("Saved to {}", path)),
+        };
+        match result {
+            Ok(text) => ctx.notice(text),
+            Err(e) => ctx.fail(&e),
+        }
+        Ok(())
+    }
+}
+
+pub struct Paste;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Paste {
+    fn name(&self) -> &'static str {
+        "paste"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Insert the clipboard's text into the input (full-screen chat)"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        ctx.read_clipboard();
+        Ok(())
+    }
+}
+
+pub struct Quit;
+
+#[async_trait(?Send)]
+impl Command<Ctx> for Quit {
+    fn name(&self) -> &'static str {
+        "quit"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["exit"]
+    }
+
+    fn summary(&self) -> &'static str {
+        "Exit AION"
+    }
+
+    async fn execute(&self, _: Args, ctx: &mut Ctx) -> Result<(), CommandError> {
+        ctx.quit();
+        Ok(())
+    }
+}
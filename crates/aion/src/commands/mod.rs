@@ -0,0 +1,340 @@
+//! The chat's slash commands, shared by the REPL and the full-screen chat.
+//!
+//! Notes:
+//! - Each command is a unit struct in `builtin` implementing
+//!   `Command<dyn SessionContext>` (see `aion_core::commands`), registered by name in
+//!   `registry`; the order there is the `/help` order.
+//! - A command acts only through `SessionContext`, which both front ends implement, so a
+//!   command behaves the same in each and can be run against a stand-in. What differs
+//!   between them (asking for a missing provider setting or showing a form, printing the
+//!   config or opening its tree, reading the clipboard) is a context method; the checks
+//!   and messages live in the command.
+//! - So does what several commands share: switching config (`switch_to`, `apply_config`),
+//!   `/config save`, templates and `/regen` are provided methods over a few primitives
+//!   (`install`, `write_config`, `send_with`, `resend`), with the state they need in
+//!   `CommandState`.
+//! - Output goes through `notice` and `error`: printed by the REPL, added to the
+//!   conversation by the chat view.
+
+mod builtin;
+#[cfg(test)]
+mod tests;
+
+use crate::audit;
+use crate::config::edit::Edits;
+use crate::config::fields;
+use crate::config::{AppConfig, GenParams, ProviderConfig, ProviderKind};
+use crate::conversation::{Conversation, Exchange};
+use crate::costs;
+use crate::dry_run;
+use crate::history::InputHistory;
+use crate::http_log;
+use crate::i18n;
+use crate::notify;
+use crate::providers::{self, ChatClient};
+use crate::redact;
+use crate::sessions::Session;
+use crate::suggest::Suggestion;
+use crate::templates::TemplateStore;
+use crate::ui::errors;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+pub use aion_core::commands::{is_command, CompletionData, Registry};
+
+/// Entries `/history` lists without a number.
+pub const HISTORY_LISTED: usize = 20;
+
+/// What `/context` does; `Show` without an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextAction {
+    Show,
+    Reload,
+    Off,
+}
+
+/// What a session keeps for the commands, alike in both front ends.
+pub struct CommandState {
+    /// Request-level parameter overrides (`/temp`).
+    pub params: GenParams,
+    /// Keys set with `/config set`, for `/config save`.
+    pub edits: Edits,
+    /// The last prompt whose request failed, for `/retry`.
+    pub last_failed: Option<String>,
+    /// The last reply was cut off at the token limit, for `/continue`.
+    pub truncated: bool,
+    /// The provider the session started with, for `/provider` to switch back to.
+    pub started: ProviderConfig,
+}
+
+impl CommandState {
+    pub fn new(cfg: &AppConfig) -> Self {
+        Self {
+            params: GenParams::default(),
+            edits: Edits::default(),
+            last_failed: None,
+            truncated: false,
+            started: cfg.provider.clone(),
+        }
+    }
+}
+
+/// A client for one request, with the config it was built from.
+pub type Override = (Arc<dyn ChatClient>, AppConfig);
+
+/// A chat session as the commands see it.
+#[async_trait(?Send)]
+pub trait SessionContext {
+    fn cfg(&self) -> &AppConfig;
+
+    /// For settings no client is built from (reply language, tone), changed for this
+    /// session in place; anything else goes through `switch_to` or `apply_config`.
+    fn cfg_mut(&mut self) -> &mut AppConfig;
+
+    fn state(&self) -> &CommandState;
+
+    fn state_mut(&mut self) -> &mut CommandState;
+
+    fn session(&self) -> &Session;
+
+    fn history(&self) -> &InputHistory;
+
+    fn conversation_mut(&mut self) -> &mut Conversation;
+
+    fn notice(&mut self, text: String);
+
+    fn error(&mut self, text: String);
+
+    fn fail(&mut self, e: &anyhow::Error) {
+        let text = errors::render(e, self.cfg());
+        self.error(text);
+    }
+
+    /// A config about to be saved, under `--dry-run`.
+    fn preview(&mut self, text: String) {
+        self.notice(text);
+    }
+
+    /// Use `next` from now on, with a client built from it and the session's parameters.
+    /// Nothing is checked; see `switch_to`.
+    fn install(&mut self, next: AppConfig);
+
+    /// Write `cfg` to the config file and say `done`, or show why it failed.
+    fn write_config(&mut self, cfg: AppConfig, done: String);
+
+    /// Move to `kind`, asking for what it needs and the config lacks (see `draft_for`).
+    fn switch_provider(&mut self, kind: ProviderKind, save: bool);
+
+    fn show_config(&mut self);
+
+    /// Drop the last exchange; `false` when there is none.
+    fn drop_last(&mut self) -> bool;
+
+    /// Start a new conversation and session.
+    fn clear(&mut self);
+
+    fn read_clipboard(&mut self);
+
+    fn quit(&mut self);
+
+    /// Send `prompt` as the next turn; `with` replaces the session's client for it.
+    async fn send_with(&mut self, prompt: String, with: Option<Override>);
+
+    /// Send a prepared `/suggest` request, once any secrets in it were confirmed.
+    async fn send_suggestion(&mut self, suggestion: Suggestion) -> Result<()>;
+
+    /// Send `last`'s prompt again for a new reply in place of its own; `with` replaces
+    /// the session's client for it. `last` goes back when the request does not complete.
+    async fn resend(&mut self, last: Exchange, with: Option<Override>);
+
+    /// Ask for the rest of the last reply and add it to that reply.
+    async fn continue_reply(&mut self);
+
+    /// Request parameters set for this session (`/temp`).
+    fn params(&self) -> &GenParams {
+        &self.state().params
+    }
+
+    /// Replace the session's parameters; the client is rebuilt with them.
+    fn set_params(&mut self, params: GenParams) {
+        self.state_mut().params = params;
+        let cfg = self.cfg().clone();
+        self.install(cfg);
+    }
+
+    /// Switch to `next` for this session; `false`, with the error shown, when it is not
+    /// usable. Its client is built on the next request.
+    fn switch_to(&mut self, next: AppConfig) -> bool {
+        if let Err(e) = next.validate() {
+            self.fail(&e.into());
+            return false;
+        }
+        if let Err(e) = providers::resolve_api_key(&next.provider) {
+            self.fail(&e);
+            return false;
+        }
+        self.install(next);
+        let cfg = self.cfg();
+        redact::init(cfg);
+        audit::init(cfg);
+        costs::init(cfg);
+        http_log::init(cfg);
+        i18n::format::init(cfg);
+        i18n::bidi::init(cfg);
+        notify::init(cfg);
+        true
+    }
+
+    /// `switch_to`, then say what is in use, and with `save` write it to the config.
+    fn apply_config(&mut self, next: AppConfig, save: bool) {
+        if !self.switch_to(next) {
+            return;
+        }
+        let cfg = self.cfg();
+        let text = format!("Using {} / {} (language: {})", cfg.provider.kind, cfg.provider.model, cfg.language);
+        self.notice(text);
+        if save && dry_run::enabled() {
+            match dry_run::preview_config(self.cfg()) {
+                Ok(preview) => self.preview(preview),
+                Err(e) => self.fail(&e),
+            }
+        } else if save {
+            let cfg = self.cfg().clone();
+            self.write_config(cfg, "Saved to config.".to_string());
+        }
+    }
+
+    /// What `/provider <kind>` starts from: the session's provider, or one of that kind it
+    /// knows (a fallback, the one it started with), as `fields::switch` picks.
+    fn draft_for(&self, kind: &ProviderKind) -> ProviderConfig {
+        let cfg = self.cfg();
+        let known: Vec<&ProviderConfig> = cfg.provider.fallbacks.iter().chain([&self.state().started]).collect();
+        fields::switch(&cfg.provider, kind, &known)
+    }
+
+    /// Write the keys set with `/config set` over the config file as it is now.
+    fn save_edits(&mut self) {
+        if self.state().edits.is_empty() {
+            return self.notice("Nothing to save; /config set changes a key first.".to_string());
+        }
+        let (updated, changes) = match self.state().edits.apply_to_file() {
+            Ok(applied) => applied,
+            Err(e) => return self.fail(&e),
+        };
+        let mut done = "Saved to config.".to_string();
+        for line in &changes {
+            done.push_str(&format!("\n  {}", redact::redact(&line.to_string())));
+        }
+        if dry_run::enabled() {
+            match dry_run::preview_config(&updated) {
+                Ok(preview) => self.preview(preview),
+                Err(e) => self.fail(&e),
+            }
+            return;
+        }
+        // Cleared now: a failed save says so, and the keys can be set again.
+        self.state_mut().edits.clear();
+        self.write_config(updated, done);
+    }
+
+    /// A client for `cfg` when its provider settings differ from the session's; `None`
+    /// when the session's own client serves.
+    fn client_for(&self, cfg: &AppConfig) -> Result<Option<Override>> {
+        if cfg.provider == self.cfg().provider {
+            return Ok(None);
+        }
+        cfg.validate()?;
+        let client = providers::build_client_with_params(cfg, self.params())?;
+        Ok(Some((Arc::from(client), cfg.clone())))
+    }
+
+    async fn send(&mut self, prompt: String) {
+        self.send_with(prompt, None).await
+    }
+
+    /// Render template `name` with `vars` and send it. Provider/model overrides apply to
+    /// this one request only.
+    async fn send_template(&mut self, name: &str, vars: HashMap<String, String>) -> Result<()> {
+        let store = TemplateStore::load()?;
+        let (template, _) = store
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("no template named '{}'", name))?;
+        let prompt = template.render(&vars)?;
+        let with = self.client_for(&template.apply_overrides(self.cfg()))?;
+        self.send_with(prompt, with).await;
+        Ok(())
+    }
+
+    /// Replace the last reply; `model` applies to this request only.
+    async fn regen(&mut self, model: Option<String>) {
+        let Some(last) = self.conversation_mut().pop() else {
+            return self.notice("Nothing to regenerate.".to_string());
+        };
+        let mut cfg = self.cfg().clone();
+        if let Some(model) = model {
+            cfg.provider.model = model;
+        }
+        match self.client_for(&cfg) {
+            Ok(with) => self.resend(last, with).await,
+            Err(e) => {
+                self.conversation_mut().push_exchange(last);
+                self.fail(&e.context("failed to create provider client"));
+            }
+        }
+    }
+
+    /// Ask for the rest of a reply cut off at the token limit.
+    async fn resume(&mut self) {
+        if !self.state().truncated {
+            return self.notice("Nothing to continue: the last reply was not cut off.".to_string());
+        }
+        self.continue_reply().await
+    }
+}
+
+/// Every command, in `/help` order.
+pub fn registry() -> &'static Registry<dyn SessionContext> {
+    static REGISTRY: LazyLock<Registry<dyn SessionContext>> = LazyLock::new(|| {
+        use builtin::*;
+        Registry::new()
+            .register(Help)
+            .register(Model)
+            .register(Provider)
+            .register(Lang)
+            .register(Temp)
+            .register(Retry)
+            .register(Regen)
+            .register(Continue)
+            .register(Undo)
+            .register(History)
+            .register(LangReply)
+            .register(Tone)
+            .register(Clear)
+            .register(Config)
+            .register(Context)
+            .register(Tpl)
+            .register(Suggest)
+            .register(Cd)
+            .register(Save)
+            .register(Paste)
+            .register(Quit)
+    });
+    &REGISTRY
+}
+
+/// Run `input` as a command against `ctx`; a parse error is shown like any other.
+pub async fn run(input: &str, ctx: &mut (dyn SessionContext + 'static)) {
+    if let Err(e) = registry().dispatch(input, ctx).await {
+        ctx.error(e.to_string());
+    }
+}
+
+/// `/undo`, also on Ctrl+Z in the chat view.
+pub fn undo(ctx: &mut dyn SessionContext) {
+    match ctx.drop_last() {
+        true => ctx.notice("Dropped the last exchange.".to_string()),
+        false => ctx.notice("Nothing to undo.".to_string()),
+    }
+}
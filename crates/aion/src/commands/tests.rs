@@ -0,0 +1,442 @@
+//! Each command run against `Stub`, a `SessionContext` that only records what it is asked.
+
+use super::*;
+use crate::config::style;
+use crate::persona;
+use crate::providers::Message;
+use crate::suggest::Suggestion;
+use tempfile::TempDir;
+
+/// A session with no front end: what it shows goes to `out` (errors marked `!`), and the
+/// front-end primitives only add a line to `calls`.
+struct Stub {
+    cfg: AppConfig,
+    state: CommandState,
+    session: Session,
+    history: InputHistory,
+    conversation: Conversation,
+    out: Vec<String>,
+    calls: Vec<String>,
+    quit: bool,
+}
+
+impl Stub {
+    fn new() -> Self {
+        let cfg = AppConfig::new_default();
+        Self {
+            state: CommandState::new(&cfg),
+            cfg,
+            session: Session::new(),
+            history: InputHistory::new(10),
+            conversation: Conversation::new(),
+            out: Vec::new(),
+            calls: Vec::new(),
+            quit: false,
+        }
+    }
+
+    /// A stub with one exchange in its conversation and session.
+    fn with_exchange() -> Self {
+        let mut stub = Self::new();
+        let kind = stub.cfg.provider.kind.clone();
+        stub.session.record("hi", "hello", &kind, "llama3", None);
+        stub.conversation.push("hi", "hello");
+        stub
+    }
+
+    async fn run(&mut self, input: &str) -> &mut Self {
+        self.out.clear();
+        self.calls.clear();
+        run(input, self).await;
+        self
+    }
+
+    fn shown(&self) -> String {
+        self.out.join("\n")
+    }
+
+    fn errors(&self) -> Vec<&str> {
+        self.out.iter().filter_map(|line| line.strip_prefix("! ")).collect()
+    }
+}
+
+/// The model a request was sent with: the override's, or the session's.
+fn via(with: &Option<Override>) -> String {
+    with.as_ref().map_or("session".to_string(), |(_, cfg)| cfg.provider.model.clone())
+}
+
+#[async_trait(?Send)]
+impl SessionContext for Stub {
+    fn cfg(&self) -> &AppConfig {
+        &self.cfg
+    }
+
+    fn cfg_mut(&mut self) -> &mut AppConfig {
+        &mut self.cfg
+    }
+
+    fn state(&self) -> &CommandState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut CommandState {
+        &mut self.state
+    }
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn history(&self) -> &InputHistory {
+        &self.history
+    }
+
+    fn conversation_mut(&mut self) -> &mut Conversation {
+        &mut self.conversation
+    }
+
+    fn notice(&mut self, text: String) {
+        self.out.push(text);
+    }
+
+    fn error(&mut self, text: String) {
+        self.out.push(format!("! {}", text));
+    }
+
+    fn install(&mut self, next: AppConfig) {
+        self.calls.push(format!("install {}", next.provider.model));
+        self.cfg = next;
+    }
+
+    fn write_config(&mut self, cfg: AppConfig, done: String) {
+        self.calls.push(format!("write_config {}", cfg.provider.model));
+        self.out.push(done);
+    }
+
+    fn switch_provider(&mut self, kind: ProviderKind, save: bool) {
+        self.calls.push(format!("switch_provider {} {}", kind, save));
+    }
+
+    fn show_config(&mut self) {
+        self.calls.push("show_config".to_string());
+    }
+
+    fn drop_last(&mut self) -> bool {
+        self.conversation.pop().is_some()
+    }
+
+    fn clear(&mut self) {
+        self.calls.push("clear".to_string());
+        self.conversation.clear();
+    }
+
+    fn read_clipboard(&mut self) {
+        self.calls.push("read_clipboard".to_string());
+    }
+
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    async fn send_with(&mut self, prompt: String, with: Option<Override>) {
+        self.calls.push(format!("send {} via {}", prompt, via(&with)));
+    }
+
+    async fn send_suggestion(&mut self, suggestion: Suggestion) -> Result<()> {
+        self.calls.push(format!("suggest {}", suggestion.vars["input"]));
+        Ok(())
+    }
+
+    async fn resend(&mut self, last: Exchange, with: Option<Override>) {
+        self.calls.push(format!("resend {} via {}", last.prompt, via(&with)));
+        self.conversation.push(last.prompt, "again");
+    }
+
+    async fn continue_reply(&mut self) {
+        self.calls.push("continue".to_string());
+    }
+}
+
+#[test]
+fn every_name_and_alias_finds_its_command() {
+    for command in registry().iter() {
+        for name in std::iter::once(command.name()).chain(command.aliases().iter().copied()) {
+            assert_eq!(registry().find(name).map(|c| c.name()), Some(command.name()));
+        }
+    }
+    assert_eq!(registry().find("?").map(|c| c.name()), Some("help"));
+    assert_eq!(registry().find("exit").map(|c| c.name()), Some("quit"));
+    assert!(registry().find("mod").is_none());
+    assert_eq!(registry().suggest("modle"), Some("model"));
+}
+
+#[tokio::test]
+async fn help_lists_every_command() {
+    let mut stub = Stub::new();
+    let text = stub.run("/help").await.shown();
+    for command in registry().iter() {
+        assert!(text.contains(&format!("/{}", command.name())), "/{} missing from:\n{}", command.name(), text);
+    }
+    assert_eq!(stub.run("/?").await.shown(), text);
+}
+
+#[tokio::test]
+async fn model_switches_and_saves_with_the_flag() {
+    let mut stub = Stub::new();
+    stub.run("/model qwen2.5").await;
+    assert_eq!(stub.cfg.provider.model, "qwen2.5");
+    assert_eq!(stub.calls, ["install qwen2.5"]);
+    assert_eq!(stub.out.last().unwrap(), "Using Ollama / qwen2.5 (language: en)");
+
+    stub.run("/model llama3 --save").await;
+    assert_eq!(stub.calls, ["install llama3", "write_config llama3"]);
+    assert_eq!(stub.out.last().unwrap(), "Saved to config.");
+
+    stub.run("/model").await;
+    assert_eq!(stub.errors().len(), 1, "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn provider_parses_the_kind_before_switching() {
+    let mut stub = Stub::new();
+    stub.run("/provider openai --save").await;
+    assert_eq!(stub.calls, ["switch_provider OpenAI true"]);
+
+    stub.run("/provider nowhere").await;
+    assert!(stub.calls.is_empty());
+    assert!(stub.errors()[0].contains("nowhere"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn lang_switches_the_interface_language() {
+    let mut stub = Stub::new();
+    stub.run("/lang ar").await;
+    assert_eq!(stub.cfg.language, "ar");
+    assert_eq!(stub.out.last().unwrap(), "Using Ollama / mistral (language: ar)");
+}
+
+#[tokio::test]
+async fn temp_sets_checks_and_resets_the_temperature() {
+    let mut stub = Stub::new();
+    stub.run("/temp 0.5").await;
+    assert_eq!(stub.state.params.temperature, Some(0.5));
+    assert_eq!(stub.shown(), "Temperature 0.5 for this session.");
+    assert_eq!(stub.calls, ["install mistral"], "the client is rebuilt with the parameters");
+
+    stub.run("/temp 3").await;
+    assert_eq!(stub.state.params.temperature, Some(0.5));
+    assert!(stub.errors()[0].contains("a number from 0 to 2, off"), "{:?}", stub.out);
+
+    stub.run("/temp off").await;
+    assert_eq!(stub.state.params.temperature, None);
+}
+
+#[tokio::test]
+async fn retry_resends_the_failed_prompt_once() {
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/retry").await.shown(), "Nothing to retry.");
+
+    stub.state.last_failed = Some("hi".to_string());
+    assert_eq!(stub.run("/retry").await.calls, ["send hi via session"]);
+    assert_eq!(stub.state.last_failed, None);
+}
+
+#[tokio::test]
+async fn regen_resends_the_last_prompt() {
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/regen").await.shown(), "Nothing to regenerate.");
+
+    let mut stub = Stub::with_exchange();
+    assert_eq!(stub.run("/regen").await.calls, ["resend hi via session"]);
+    assert_eq!(stub.run("/regen --model qwen2.5").await.calls, ["resend hi via qwen2.5"]);
+    assert_eq!(stub.cfg.provider.model, "mistral", "the model applies to that request only");
+    assert_eq!(stub.conversation.len(), 1);
+}
+
+#[tokio::test]
+async fn regen_keeps_the_reply_when_no_client_can_be_built() {
+    let mut stub = Stub::with_exchange();
+    stub.cfg.provider.base_url = Some("not a url".to_string());
+    stub.run("/regen --model qwen2.5").await;
+    assert!(stub.calls.is_empty());
+    assert_eq!(stub.errors().len(), 1, "{:?}", stub.out);
+    assert_eq!(stub.conversation.last().unwrap().reply, "hello");
+}
+
+#[tokio::test]
+async fn continue_needs_a_reply_cut_off() {
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/continue").await.shown(), "Nothing to continue: the last reply was not cut off.");
+
+    stub.state.truncated = true;
+    assert_eq!(stub.run("/continue").await.calls, ["continue"]);
+}
+
+#[tokio::test]
+async fn undo_drops_the_last_exchange() {
+    let mut stub = Stub::with_exchange();
+    assert_eq!(stub.run("/undo").await.shown(), "Dropped the last exchange.");
+    assert_eq!(stub.run("/undo").await.shown(), "Nothing to undo.");
+}
+
+#[tokio::test]
+async fn history_lists_the_last_entries() {
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/history").await.shown(), "No history yet.");
+
+    for entry in ["one", "two", "three"] {
+        stub.history.push(entry);
+    }
+    assert_eq!(stub.run("/history 2").await.shown(), "    2  two\n    3  three");
+    assert_eq!(stub.run("/history").await.out[0].lines().count(), 3);
+    assert!(stub.run("/history 0").await.errors()[0].contains("a number of entries"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn lang_reply_turns_the_instruction_on_and_off() {
+    let mut stub = Stub::new();
+    assert_eq!(persona::system_prompt(&stub.cfg), None);
+    stub.run("/lang-reply on").await;
+    assert!(stub.cfg.provider.respond_in_config_language);
+    let en = "Always reply in English unless the user explicitly asks for another language.";
+    assert_eq!(stub.shown(), format!("Reply instruction: {}", en));
+    assert_eq!(persona::system_prompt(&stub.cfg).as_deref(), Some(en));
+
+    // Phrased by the Arabic locale, with its own name for the language.
+    stub.cfg.language = "ar".to_string();
+    let ar = "أجب دائمًا باللغة العربية ما لم يطلب المستخدم صراحةً لغة أخرى.";
+    assert_eq!(persona::system_prompt(&stub.cfg).as_deref(), Some(ar));
+    let composed = persona::compose(&stub.cfg, &[Message::user("مرحبا")]);
+    assert_eq!(composed[0], Message::system(ar));
+    assert_eq!(composed[1], Message::user("مرحبا"));
+
+    stub.run("/lang-reply off").await;
+    assert!(!stub.cfg.provider.respond_in_config_language);
+    assert_eq!(stub.shown(), "Replies follow the conversation language.");
+    assert_eq!(persona::system_prompt(&stub.cfg), None);
+
+    assert!(stub.run("/lang-reply maybe").await.errors()[0].contains("on, off"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn tone_sets_a_known_tone_for_the_session() {
+    let mut stub = Stub::new();
+    assert!(style::pinned().tone.is_none());
+    stub.run("/tone concise").await;
+    assert_eq!(stub.cfg.style.tone.as_deref(), Some("concise"));
+    assert_eq!(stub.shown(), "Tone: concise (this session).");
+
+    stub.run("/tone off").await;
+    assert_eq!(stub.cfg.style.tone, None);
+
+    assert!(stub.run("/tone loud").await.errors()[0].contains("concise"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn clear_starts_over() {
+    let mut stub = Stub::with_exchange();
+    stub.run("/clear").await;
+    assert_eq!(stub.calls, ["clear"]);
+    assert_eq!(stub.shown(), "Conversation cleared.");
+    assert_eq!(stub.conversation.len(), 0);
+}
+
+#[tokio::test]
+async fn config_shows_sets_and_saves() {
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/config").await.calls, ["show_config"]);
+
+    stub.run("/config set language ar").await;
+    assert_eq!(stub.cfg.language, "ar");
+    assert_eq!(stub.state.edits.keys().collect::<Vec<_>>(), ["language"]);
+    assert_eq!(stub.shown(), "language = ar (this session; /config save keeps it)");
+
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/config save").await.shown(), "Nothing to save; /config set changes a key first.");
+    assert!(stub.calls.is_empty());
+
+    assert!(stub.run("/config set language").await.errors()[0].contains("/config"), "{:?}", stub.out);
+    assert!(stub.run("/config unset").await.errors()[0].contains("set, save"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn config_set_keeps_the_config_when_the_value_is_invalid() {
+    let mut stub = Stub::new();
+    stub.run("/config set provider.params.temperature 9").await;
+    assert_eq!(stub.errors().len(), 1, "{:?}", stub.out);
+    assert!(stub.calls.is_empty());
+    assert_eq!(stub.cfg.provider.params.temperature, AppConfig::new_default().provider.params.temperature);
+}
+
+#[tokio::test]
+async fn context_takes_an_action() {
+    let mut stub = Stub::new();
+    stub.run("/context off").await;
+    assert_eq!(stub.out.len(), 1, "{:?}", stub.out);
+    assert!(stub.run("/context later").await.errors()[0].contains("show, reload, off"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn tpl_names_a_missing_template() {
+    let mut stub = Stub::new();
+    stub.run("/tpl no-such-template some input").await;
+    assert!(stub.calls.is_empty());
+    assert!(stub.errors()[0].contains("no-such-template"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn suggest_sends_the_request_as_input() {
+    let mut stub = Stub::new();
+    stub.cfg.features.command_suggestions = true;
+    assert_eq!(stub.run("/suggest list big files").await.calls, ["suggest list big files"]);
+
+    stub.cfg.features.command_suggestions = false;
+    stub.run("/suggest list big files").await;
+    assert!(stub.errors()[0].contains("features.command_suggestions"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn cd_says_where_commands_now_run() {
+    let dir = TempDir::new().unwrap();
+    let mut stub = Stub::new();
+    stub.run(&format!("/cd {}", dir.path().display())).await;
+    assert!(stub.shown().starts_with("Working directory: "), "{:?}", stub.out);
+    assert!(stub.run("/cd /no/such/dir").await.errors().len() == 1, "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn save_writes_the_last_reply() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("reply.md");
+    let mut stub = Stub::with_exchange();
+    stub.cfg.caps.write_files = true;
+    stub.run(&format!("/save {}", path.display())).await;
+    assert_eq!(stub.shown(), format!("Saved to {}", path.display()));
+    assert!(std::fs::read_to_string(&path).unwrap().contains("hello"));
+
+    stub.cfg.caps.write_files = false;
+    stub.run(&format!("/save {} --all", path.display())).await;
+    assert!(stub.errors()[0].contains("write_files"), "{:?}", stub.out);
+}
+
+#[tokio::test]
+async fn paste_reads_the_clipboard() {
+    let mut stub = Stub::new();
+    assert_eq!(stub.run("/paste").await.calls, ["read_clipboard"]);
+}
+
+#[tokio::test]
+async fn quit_and_its_alias_end_the_session() {
+    for input in ["/quit", "/exit"] {
+        let mut stub = Stub::new();
+        stub.run(input).await;
+        assert!(stub.quit, "{}", input);
+    }
+}
+
+#[tokio::test]
+async fn an_unknown_command_is_an_error() {
+    let mut stub = Stub::new();
+    stub.run("/frobnicate").await;
+    assert!(stub.errors()[0].contains("frobnicate"), "{:?}", stub.out);
+}
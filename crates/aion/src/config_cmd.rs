@@ -0,0 +1,171 @@
+//! `aion config`: validate, upgrade, get, and set the config file.
+//!
+//! Notes:
+//! - `upgrade` shows each migration and the resulting changes, and asks before writing
+//!   (`--yes` skips the question; without a terminal it is required).
+//! - `set` loads the config unvalidated, so a value that fails validation can be fixed.
+//! - Printed values go through `redact`.
+
+use crate::cli::{self, ConfigAction};
+use crate::config::io::{config_file_path, load_config, load_raw_config, save_config};
+use crate::ui::console::Console;
+use crate::{catalog, config, dry_run, i18n, redact, ui};
+use anyhow::{Context, Result};
+use crossterm::tty::IsTty;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+pub fn run(console: &Console, action: &ConfigAction, strict: bool) -> Result<ExitCode> {
+    match action {
+        ConfigAction::Validate => return validate_config(console, strict),
+        ConfigAction::Upgrade(a) => {
+            let path = config_file_path()?;
+            let before = load_raw_config()?;
+            let from = config::migrate::file_version(&before);
+
+            let mut table = before.clone();
+            let applied = config::migrate::upgrade(&mut table)?;
+            let upgraded: config::AppConfig = toml::Value::Table(table)
+                .try_into()
+                .context("upgraded config does not match the current format")?;
+            // Validated as loaded, with `${VAR}` / `~` expanded; written with the templates.
+            let mut check = upgraded.clone();
+            config::expand::apply(&mut check).context("upgraded config failed validation")?;
+            check.validate().context("upgraded config failed validation")?;
+
+            // Diff against what `save_config` will actually write.
+            let changes = config::diff::diff(&before, &config::diff::to_table(&upgraded)?);
+            if changes.is_empty() {
+                println!("{} is up to date (version {}).", path.display(), from);
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            println!(
+                "Upgrading {} from version {} to {}:",
+                path.display(),
+                from,
+                config::AppConfig::CURRENT_VERSION
+            );
+            for summary in &applied {
+                println!("  * {}", summary);
+            }
+            println!();
+            for line in &changes {
+                println!("{}", redact::redact(&line.to_string()));
+            }
+            println!();
+
+            if dry_run::enabled() {
+                println!("{}", dry_run::preview_config(&upgraded)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            if !a.yes {
+                if !io::stdin().is_tty() {
+                    anyhow::bail!("stdin is not a terminal; rerun with --yes to write the changes");
+                }
+                print!("Write these changes? [y/N] ");
+                io::stdout().flush()?;
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                    println!("Nothing written.");
+                    return Ok(ExitCode::SUCCESS);
+                }
+            }
+
+            save_config(&upgraded).context("failed to save config")?;
+            println!("Saved {}", path.display());
+        }
+        ConfigAction::Get(a) => {
+            let cfg = load_config().context("failed to load config")?;
+            let mut table = config::diff::to_table(&cfg)?;
+            // Not stored; the merge of `provider.params` with the provider's defaults.
+            let effective = toml::Value::try_from(cfg.provider.effective_params(&config::GenParams::default()))?;
+            if let Some(toml::Value::Table(provider)) = table.get_mut("provider") {
+                provider.insert("effective_params".to_string(), effective);
+            }
+            let root = toml::Value::Table(table);
+            let value = config::edit::get(&root, &a.key).ok_or_else(|| anyhow::anyhow!("no config key named '{}'", a.key))?;
+            if console.is_json() {
+                console.print_json(value)?;
+            } else {
+                match value {
+                    toml::Value::String(s) => println!("{}", redact::redact(s)),
+                    toml::Value::Table(t) => print!("{}", redact::redact(&toml::to_string_pretty(t)?)),
+                    other => println!("{}", other),
+                }
+            }
+        }
+        ConfigAction::Set(a) => {
+            // Unvalidated, so a value that fails validation can be replaced.
+            let cfg = config::io::load_config_unvalidated().context("failed to load config")?;
+            let updated = config::edit::set(&cfg, &a.key, &a.value)?;
+            let changes = config::diff::config_changes(&cfg, &updated)?;
+            if changes.is_empty() {
+                println!("{} is unchanged.", a.key);
+                return Ok(ExitCode::SUCCESS);
+            }
+            for line in &changes {
+                println!("{}", redact::redact(&line.to_string()));
+            }
+            if updated.provider.model != cfg.provider.model {
+                if let Some(notice) = catalog::Catalog::load().notice(&updated.provider.kind, &updated.provider.model) {
+                    eprintln!("Warning: {}", notice);
+                }
+            }
+            if dry_run::enabled() {
+                println!("{}", dry_run::preview_config(&updated)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            save_config(&updated).context("failed to save config")?;
+            println!("Saved {}", config_file_path()?.display());
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `aion config validate`: the first error, at its line, or the consistency warnings
+/// (errors with `--strict`). With `--output json` a failure is reported as an object.
+fn validate_config(console: &Console, strict: bool) -> Result<ExitCode> {
+    let path = config_file_path()?;
+    let checked = load_config().and_then(|cfg| {
+        match cfg.consistency_warnings().first() {
+            Some(&w) if strict => {
+                Err(config::ConfigError::Inconsistent(w)).context(config::locate::InvalidConfigFile { path: path.clone() })
+            }
+            _ => Ok(cfg),
+        }
+    });
+    let cfg = match checked {
+        Ok(cfg) => cfg,
+        Err(e) if console.is_json() => {
+            // As the error path in `main` renders it: the defaults, in the UI language.
+            let mut shown = config::AppConfig::new_default();
+            shown.language = cli::ui_language();
+            let diagnostic = ui::errors::config_diagnostic(&e);
+            let at = diagnostic.as_ref().and_then(|d| d.location.as_ref()).filter(|l| l.found);
+            console.print_json(&serde_json::json!({
+                "path": path,
+                "valid": false,
+                "error": ui::errors::render(&e, &shown),
+                "key": diagnostic.as_ref().and_then(|d| d.key.clone()),
+                "line": at.map(|l| l.line),
+                "value": at.and_then(|l| l.value.as_deref().map(redact::redact)),
+                "fix": diagnostic.as_ref().and_then(|d| d.hint(&shown.language)).map(|h| redact::redact(&h)),
+            }))?;
+            return Ok(ExitCode::FAILURE);
+        }
+        Err(e) => return Err(e),
+    };
+    let warnings = cfg.consistency_warnings();
+    if console.is_json() {
+        let ids: Vec<_> = warnings.iter().map(|w| w.id()).collect();
+        console.print_json(&serde_json::json!({ "path": path, "valid": true, "warnings": ids }))?;
+    } else {
+        println!("{} is valid.", i18n::format::tilde(&path));
+        for w in &warnings {
+            println!("Warning: {}", w);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
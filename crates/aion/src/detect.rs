@@ -0,0 +1,227 @@
+//! Provider settings found outside AION, offered by the wizard's first screen.
+//!
+//! Notes:
+//! - Every probe is an independent function returning what it found; a probe that
+//!   cannot read something finds nothing rather than failing the scan.
+//! - Everything is local (environment variables, files under the home and config
+//!   directories) except short TCP connects to the default Ollama and LM Studio addresses
+//!   (`local_servers`), made only when `caps.network` and its host lists allow them and
+//!   all within 150ms.
+//! - Findings for the same provider and endpoint are merged, so each is offered once with
+//!   every source that pointed at it. LM Studio is offered as OpenAI at its own endpoint.
+//! - Outside the wizard, `local_tip` probes only when `provider.autodetect` is on and the
+//!   configured provider cannot be used (`prerequisites_unmet`); it then suggests the
+//!   server found, at the ready prompt and in `aion doctor`.
+
+use crate::config::{AppConfig, ProviderKind};
+use crate::guard::Guard;
+use crate::i18n;
+use crate::providers::ollama::tcp_reachable;
+use crate::providers::resolve_api_key;
+use futures_util::future::join_all;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Budget for the local server probes, all of them together.
+const LOCAL_PROBE_BUDGET: Duration = Duration::from_millis(150);
+
+/// Provider settings that can be adopted in one step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detected {
+    pub kind: ProviderKind,
+    /// Where it was found, e.g. `OPENAI_API_KEY` or `aichat config`.
+    pub sources: Vec<String>,
+    /// Variable holding the API key, when one is set.
+    pub api_key_env: Option<String>,
+    /// Model named by another tool's config.
+    pub model: Option<String>,
+    /// Endpoint of a local server speaking this provider's API.
+    pub base_url: Option<String>,
+}
+
+impl Detected {
+    fn new(kind: ProviderKind, source: impl Into<String>) -> Self {
+        Self { kind, sources: vec![source.into()], api_key_env: None, model: None, base_url: None }
+    }
+}
+
+/// Everything found, one entry per provider and endpoint, in `ProviderKind::ALL` order.
+pub async fn scan(cfg: &AppConfig) -> Vec<Detected> {
+    let mut found = Vec::new();
+    found.extend(api_key_vars());
+    found.extend(ollama_home());
+    found.extend(local_servers(cfg).await.into_iter().map(LocalServer::detected));
+    found.extend(aichat_config());
+    found.extend(llm_default_model());
+    merge(found)
+}
+
+fn merge(found: Vec<Detected>) -> Vec<Detected> {
+    let mut merged: Vec<Detected> = Vec::new();
+    for d in found {
+        match merged.iter_mut().find(|m| m.kind == d.kind && m.base_url == d.base_url) {
+            Some(m) => {
+                m.sources.extend(d.sources);
+                m.api_key_env = m.api_key_env.take().or(d.api_key_env);
+                m.model = m.model.take().or(d.model);
+            }
+            None => merged.push(d),
+        }
+    }
+    merged.sort_by_key(|d| ProviderKind::ALL.iter().position(|k| *k == d.kind));
+    merged
+}
+
+/* ---------------------------
+   Probes
+---------------------------- */
+
+/// Providers whose usual API key variable is set.
+fn api_key_vars() -> Vec<Detected> {
+    ProviderKind::ALL
+        .iter()
+        .filter_map(|kind| {
+            let var = kind.default_api_key_env()?;
+            let set = std::env::var(var).is_ok_and(|v| !v.trim().is_empty());
+            set.then(|| Detected { api_key_env: Some(var.to_string()), ..Detected::new(kind.clone(), var) })
+        })
+        .collect()
+}
+
+/// `~/.ollama`, created by any Ollama install.
+fn ollama_home() -> Option<Detected> {
+    let dir = dirs::home_dir()?.join(".ollama");
+    dir.is_dir().then(|| Detected::new(ProviderKind::Ollama, "~/.ollama"))
+}
+
+/// aichat's `model: <client>:<model>` setting.
+fn aichat_config() -> Option<Detected> {
+    let text = read_config_file(["aichat", "config.yaml"])?;
+    let value = text.lines().find_map(|l| l.strip_prefix("model:"))?;
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    let (client, model) = value.split_once(':')?;
+    let kind = match client {
+        "openai" => ProviderKind::OpenAI,
+        "claude" => ProviderKind::Claude,
+        "ollama" => ProviderKind::Ollama,
+        "openrouter" => ProviderKind::OpenRouter,
+        _ => return None,
+    };
+    Some(Detected { model: Some(model.to_string()), ..Detected::new(kind, "aichat config") })
+}
+
+/// llm's `default_model.txt`; only model names that identify their provider are used.
+fn llm_default_model() -> Option<Detected> {
+    let text = read_config_file(["io.datasette.llm", "default_model.txt"])?;
+    let model = text.trim();
+    let kind = if model.starts_with("claude") {
+        ProviderKind::Claude
+    } else if model.starts_with("gpt-") || model.starts_with("o1") || model.starts_with("o3") {
+        ProviderKind::OpenAI
+    } else {
+        return None;
+    };
+    Some(Detected { model: Some(model.to_string()), ..Detected::new(kind, "llm config") })
+}
+
+/// A file under the platform config directory, or `~/.config` where that differs.
+fn read_config_file(parts: [&str; 2]) -> Option<String> {
+    let relative: PathBuf = parts.iter().collect();
+    let candidates = [dirs::config_dir(), dirs::home_dir().map(|h| h.join(".config"))];
+    candidates
+        .into_iter()
+        .flatten()
+        .find_map(|dir| fs::read_to_string(dir.join(&relative)).ok())
+}
+
+/* ---------------------------
+   Local servers
+---------------------------- */
+
+/// A model server with a well-known local address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalServer {
+    Ollama,
+    LmStudio,
+}
+
+impl LocalServer {
+    pub const ALL: [LocalServer; 2] = [LocalServer::Ollama, LocalServer::LmStudio];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LocalServer::Ollama => "Ollama",
+            LocalServer::LmStudio => "LM Studio",
+        }
+    }
+
+    /// Where it listens unless told otherwise.
+    pub fn address(self) -> &'static str {
+        match self {
+            LocalServer::Ollama => ProviderKind::Ollama.public_base_url(),
+            LocalServer::LmStudio => "http://localhost:1234",
+        }
+    }
+
+    /// The settings that use it: Ollama's own provider, or OpenAI at LM Studio's endpoint.
+    fn detected(self) -> Detected {
+        let source = format!("{} at {}", self.name(), self.address());
+        match self {
+            LocalServer::Ollama => Detected::new(ProviderKind::Ollama, source),
+            LocalServer::LmStudio => Detected {
+                base_url: Some(format!("{}/v1", self.address())),
+                ..Detected::new(ProviderKind::OpenAI, source)
+            },
+        }
+    }
+
+    /// "Tip: an Ollama server was detected — switch with ...", in `lang`.
+    pub fn tip(self, lang: &str) -> String {
+        let how = match self {
+            LocalServer::Ollama => "`aion config set provider.kind ollama` or `/provider ollama`".to_string(),
+            LocalServer::LmStudio => format!(
+                "`aion config set provider.kind openai` and `aion config set provider.base_url {}/v1` (any API key works)",
+                self.address()
+            ),
+        };
+        i18n::text(
+            lang,
+            "detect.local_tip",
+            "Tip: an {name} server was detected — switch with {how}",
+            &[("name", self.name().to_string()), ("how", how)],
+        )
+    }
+}
+
+/// Whether the configured provider cannot be used as it is: it needs an API key and none
+/// is set.
+pub fn prerequisites_unmet(cfg: &AppConfig) -> bool {
+    resolve_api_key(&cfg.provider).is_err()
+}
+
+/// Local servers listening at their default address. Addresses `caps.network` or its
+/// host lists rule out are not tried; the others are tried at once, within
+/// `LOCAL_PROBE_BUDGET`.
+pub async fn local_servers(cfg: &AppConfig) -> Vec<LocalServer> {
+    let guard = Guard::for_app(cfg);
+    let probes = LocalServer::ALL
+        .into_iter()
+        .filter(|s| guard.check_network(s.address()).is_ok())
+        .map(|s| async move { tcp_reachable(s.address(), LOCAL_PROBE_BUDGET).await.then_some(s) });
+    join_all(probes).await.into_iter().flatten().collect()
+}
+
+/// `local_servers`, when `provider.autodetect` is on and `prerequisites_unmet`; otherwise
+/// nothing is probed.
+pub async fn local_fallbacks(cfg: &AppConfig) -> Vec<LocalServer> {
+    if !cfg.provider.autodetect || !prerequisites_unmet(cfg) {
+        return Vec::new();
+    }
+    local_servers(cfg).await
+}
+
+/// The ready prompt's tip for the first of `local_fallbacks`.
+pub async fn local_tip(cfg: &AppConfig) -> Option<String> {
+    local_fallbacks(cfg).await.first().map(|s| s.tip(&cfg.language))
+}
@@ -0,0 +1,403 @@
+//! `aion doctor`: end-to-end health checks.
+//!
+//! Notes:
+//! - Every check is an independent function returning a `CheckResult`.
+//! - Adding a check means writing one function and listing it in `CHECKS`.
+//! - Checks must never modify state (no config creation, no saves).
+//! - Checks are synchronous. Network probes run while the context is built
+//!   (`DoctorContext::from_env`) and checks read their results. Local servers are
+//!   probed only when the configured provider cannot be used (`detect::local_fallbacks`).
+
+use crate::cli::DoctorArgs;
+use crate::config::AppConfig;
+use crate::config::io::{config_exists, config_file_path};
+use crate::detect::{self, LocalServer};
+use crate::guard::Guard;
+use crate::i18n::format::tilde_in;
+use crate::paths::config_dir;
+use crate::{read_only, redact};
+use crate::ui::console::Console;
+use crossterm::style::Stylize;
+use crossterm::tty::IsTty;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, detail: detail.into() }
+    }
+
+    pub fn warn(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Warn, detail: detail.into() }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Fail, detail: detail.into() }
+    }
+}
+
+/// Inputs shared by all checks. Resolved once so checks stay cheap and side-effect free.
+pub struct DoctorContext {
+    pub config_dir: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+    /// The provider endpoint and its HTTP status (or transport error). `None` when the
+    /// probe was skipped.
+    pub endpoint: Option<(String, Result<u16, String>)>,
+    /// Local servers that could stand in for the configured provider.
+    pub local: Vec<LocalServer>,
+    /// `--strict`: consistency warnings fail instead of warn.
+    pub strict: bool,
+}
+
+impl DoctorContext {
+    pub async fn from_env(strict: bool) -> Self {
+        let mut ctx = Self {
+            config_dir: config_dir().ok(),
+            config_path: config_file_path().ok(),
+            endpoint: None,
+            local: Vec::new(),
+            strict,
+        };
+        if let Some(cfg) = ctx.parsed_config().filter(|c| c.caps.network) {
+            let url = cfg.provider.effective_base_url();
+            let result = match Guard::for_app(&cfg).check_network(&url) {
+                Ok(_) => probe_url(&url).await.map_err(|e| e.to_string()),
+                Err(e) => Err(format!("{:#}", e)),
+            };
+            ctx.endpoint = Some((url, result));
+        }
+        if let Some(cfg) = ctx.parsed_config() {
+            ctx.local = detect::local_fallbacks(&cfg).await;
+        }
+        ctx
+    }
+
+    fn read_config_text(&self) -> Option<String> {
+        fs::read_to_string(self.config_path.as_ref()?).ok()
+    }
+
+    /// Parsed config, if the file exists and parses (validation not required).
+    fn parsed_config(&self) -> Option<AppConfig> {
+        toml::from_str(&self.read_config_text()?).ok()
+    }
+}
+
+type Check = fn(&DoctorContext) -> CheckResult;
+
+const CHECKS: &[Check] = &[
+    check_config_exists,
+    check_config_parses,
+    check_config_validates,
+    check_config_consistency,
+    check_config_dir_writable,
+    check_api_key_env,
+    check_base_url_reachable,
+    check_local_servers,
+    check_locales,
+    check_terminal,
+    check_version,
+];
+
+pub async fn run(console: &Console, args: &DoctorArgs, strict: bool) -> anyhow::Result<ExitCode> {
+    let ctx = DoctorContext::from_env(strict).await;
+    let results = run_checks(&ctx);
+
+    if args.json || console.is_json() {
+        print_json(&results)?;
+    } else {
+        print_human("AION doctor", &results);
+    }
+
+    Ok(if has_failures(&results) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+pub fn run_checks(ctx: &DoctorContext) -> Vec<CheckResult> {
+    CHECKS.iter().map(|check| check(ctx)).collect()
+}
+
+pub fn has_failures(results: &[CheckResult]) -> bool {
+    results.iter().any(|r| r.status == CheckStatus::Fail)
+}
+
+/* ---------------------------
+   Checks
+---------------------------- */
+
+fn check_config_exists(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "config file exists";
+    let Some(path) = &ctx.config_path else {
+        return CheckResult::fail(NAME, "could not locate the system config directory");
+    };
+    match config_exists() {
+        Ok(true) => CheckResult::pass(NAME, path.display().to_string()),
+        Ok(false) => CheckResult::fail(
+            NAME,
+            format!("{} not found (run `aion --setup`)", path.display()),
+        ),
+        Err(e) => CheckResult::fail(NAME, format!("{:#}", e)),
+    }
+}
+
+fn check_config_parses(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "config file parses";
+    let Some(text) = ctx.read_config_text() else {
+        return CheckResult::fail(NAME, "config file could not be read");
+    };
+    match toml::from_str::<AppConfig>(&text) {
+        Ok(_) => CheckResult::pass(NAME, "valid TOML"),
+        Err(e) => CheckResult::fail(NAME, e.message().to_string()),
+    }
+}
+
+fn check_config_validates(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "config file validates";
+    let Some(cfg) = ctx.parsed_config() else {
+        return CheckResult::fail(NAME, "skipped: config could not be parsed");
+    };
+    match cfg.validate() {
+        Ok(()) => CheckResult::pass(NAME, "all fields valid"),
+        Err(e) => CheckResult::fail(NAME, e.to_string()),
+    }
+}
+
+fn check_config_consistency(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "features match caps";
+    let Some(cfg) = ctx.parsed_config() else {
+        return CheckResult::warn(NAME, "skipped: config could not be parsed");
+    };
+    let warnings = cfg.consistency_warnings();
+    if warnings.is_empty() {
+        return CheckResult::pass(NAME, "no conflicts");
+    }
+    let detail = warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>().join("; ");
+    if ctx.strict {
+        CheckResult::fail(NAME, detail)
+    } else {
+        CheckResult::warn(NAME, detail)
+    }
+}
+
+fn check_config_dir_writable(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "config directory writable";
+    let Some(dir) = &ctx.config_dir else {
+        return CheckResult::fail(NAME, "could not locate the system config directory");
+    };
+    if !dir.exists() {
+        return CheckResult::warn(
+            NAME,
+            format!("{} does not exist yet (created on first save)", dir.display()),
+        );
+    }
+    if let Some(trigger) = read_only::active() {
+        return CheckResult::warn(NAME, format!("not checked in read-only mode ({})", trigger.name()));
+    }
+
+    let probe = dir.join(".doctor-probe");
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            CheckResult::pass(NAME, dir.display().to_string())
+        }
+        Err(e) => CheckResult::fail(NAME, format!("{}: {}", dir.display(), e)),
+    }
+}
+
+fn check_api_key_env(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "provider api key";
+    let Some(cfg) = ctx.parsed_config() else {
+        return CheckResult::warn(NAME, "skipped: config could not be parsed");
+    };
+    if !cfg.provider.kind.requires_api_key() {
+        return CheckResult::pass(NAME, format!("not required for {}", cfg.provider.kind));
+    }
+
+    let Some(var) = cfg.provider.api_key_env.as_deref().filter(|v| !v.trim().is_empty()) else {
+        return CheckResult::fail(NAME, "provider.api_key_env is not configured");
+    };
+    match std::env::var(var) {
+        Ok(value) if !value.trim().is_empty() => CheckResult::pass(NAME, format!("{var} is set")),
+        _ => CheckResult::fail(NAME, format!("environment variable {var} is not set")),
+    }
+}
+
+fn check_base_url_reachable(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "provider endpoint reachable";
+    let Some(cfg) = ctx.parsed_config() else {
+        return CheckResult::warn(NAME, "skipped: config could not be parsed");
+    };
+    if !cfg.caps.network {
+        return CheckResult::warn(NAME, "skipped: caps.network is disabled");
+    }
+
+    match &ctx.endpoint {
+        Some((url, Ok(status))) => CheckResult::pass(NAME, format!("{url} (HTTP {status})")),
+        Some((url, Err(e))) => CheckResult::fail(NAME, format!("{url}: {e}")),
+        None => CheckResult::warn(NAME, "skipped: endpoint was not probed"),
+    }
+}
+
+fn check_local_servers(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "local providers";
+    let Some(cfg) = ctx.parsed_config() else {
+        return CheckResult::warn(NAME, "skipped: config could not be parsed");
+    };
+    if !detect::prerequisites_unmet(&cfg) {
+        return CheckResult::pass(NAME, "not needed: the configured provider has what it needs");
+    }
+    if !cfg.provider.autodetect {
+        return CheckResult::pass(NAME, "not looked for: provider.autodetect is off");
+    }
+    match ctx.local.first() {
+        Some(server) => CheckResult::warn(NAME, server.tip(&cfg.language)),
+        None => CheckResult::pass(NAME, "none found (Ollama, LM Studio)"),
+    }
+}
+
+fn check_locales(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "locales";
+    let language = ctx
+        .parsed_config()
+        .map(|c| c.language)
+        .unwrap_or_else(|| "en".to_string());
+
+    let found = crate::i18n::LocaleManager::locale_search_paths()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|p| p.is_dir());
+    let Some(dir) = found else {
+        return CheckResult::warn(NAME, "no locales directory found; using built-in English strings");
+    };
+
+    let manager = match crate::i18n::LocaleManager::load_for(&language) {
+        Ok(manager) => manager,
+        Err(e) => return CheckResult::fail(NAME, format!("{}: {:#}", dir.display(), e)),
+    };
+    let report = manager.load_report();
+    if !report.is_empty() {
+        let skipped: Vec<String> = report.iter().map(|s| format!("{} ({})", s.path.display(), s.reason)).collect();
+        return CheckResult::warn(NAME, format!("{}: skipped {}", dir.display(), skipped.join("; ")));
+    }
+    match manager.meta(&language) {
+        Some(_) => CheckResult::pass(NAME, format!("{} ({} loaded)", dir.display(), language)),
+        None => CheckResult::warn(
+            NAME,
+            format!("{}: locale '{}' not found, falling back to English", dir.display(), language),
+        ),
+    }
+}
+
+fn check_terminal(_ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "terminal";
+    let tty = io::stdout().is_tty();
+    let color = color_enabled();
+    let size = crossterm::terminal::size()
+        .map(|(w, h)| format!("{w}x{h}"))
+        .unwrap_or_else(|_| "unknown size".to_string());
+
+    let detail = format!(
+        "tty: {}, color: {}, size: {}",
+        if tty { "yes" } else { "no" },
+        if color { "yes" } else { "no" },
+        size
+    );
+    if tty {
+        CheckResult::pass(NAME, detail)
+    } else {
+        CheckResult::warn(NAME, format!("{detail} (setup wizard needs a TTY)"))
+    }
+}
+
+fn check_version(ctx: &DoctorContext) -> CheckResult {
+    const NAME: &str = "version";
+    let detail = match ctx.parsed_config() {
+        Some(cfg) => format!(
+            "aion {} (config version {}, supported {})",
+            env!("CARGO_PKG_VERSION"),
+            cfg.version,
+            AppConfig::CURRENT_VERSION
+        ),
+        None => format!("aion {}", env!("CARGO_PKG_VERSION")),
+    };
+    CheckResult::pass(NAME, detail)
+}
+
+/* ---------------------------
+   Helpers
+---------------------------- */
+
+/// Any HTTP response counts as reachable; only transport errors fail.
+pub async fn probe_url(url: &str) -> anyhow::Result<u16> {
+    let client = reqwest::Client::builder().timeout(REACHABILITY_TIMEOUT).build()?;
+    let resp = client.get(url).send().await?;
+    Ok(resp.status().as_u16())
+}
+
+fn color_enabled() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && io::stdout().is_tty()
+}
+
+/* ---------------------------
+   Rendering
+---------------------------- */
+
+/// The results under `title` ("AION doctor"), one line each, and the counts.
+pub fn print_human(title: &str, results: &[CheckResult]) {
+    let color = color_enabled();
+    println!("{}", title);
+    println!();
+    for r in results {
+        let marker = match (r.status, color) {
+            (CheckStatus::Pass, true) => format!("{}", "[ OK ]".green()),
+            (CheckStatus::Warn, true) => format!("{}", "[WARN]".yellow()),
+            (CheckStatus::Fail, true) => format!("{}", "[FAIL]".red()),
+            (CheckStatus::Pass, false) => "[ OK ]".to_string(),
+            (CheckStatus::Warn, false) => "[WARN]".to_string(),
+            (CheckStatus::Fail, false) => "[FAIL]".to_string(),
+        };
+        println!("{} {:<28} {}", marker, r.name, redact::redact(&tilde_in(&r.detail)));
+    }
+    println!();
+
+    let failed = results.iter().filter(|r| r.status == CheckStatus::Fail).count();
+    let warned = results.iter().filter(|r| r.status == CheckStatus::Warn).count();
+    println!("{} checks, {} failed, {} warnings", results.len(), failed, warned);
+}
+
+pub fn print_json(results: &[CheckResult]) -> anyhow::Result<()> {
+    println!("{}", redact::redact(&to_json(results)?));
+    Ok(())
+}
+
+/// The results as `--json` prints them, before redaction.
+pub fn to_json(results: &[CheckResult]) -> anyhow::Result<String> {
+    let out = serde_json::json!({
+        "ok": !has_failures(results),
+        "checks": results,
+    });
+    Ok(serde_json::to_string_pretty(&out)?)
+}
@@ -0,0 +1,67 @@
+//! `--dry-run`: operations that change files report what they would do instead.
+//!
+//! Notes:
+//! - Turned on once from the CLI flag (`enable`) and read by every write path. Like
+//!   `redact`, it is process-wide so deep call sites need no extra parameter.
+//! - Config and template writes show a unified diff against the current file; session
+//!   files, exports, and removed cache entries only name the path. Previews are redacted.
+//! - Command runs (the wizard's model pull) show what would run and stop there.
+
+use crate::config::diff::unified;
+use crate::config::io::{config_file_path, render_config};
+use crate::config::AppConfig;
+use crate::redact;
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// What writing `text` to `path` would change.
+pub fn preview_write(path: &Path, text: &str) -> String {
+    let current = fs::read_to_string(path).unwrap_or_default();
+    let verb = if path.exists() { "write" } else { "create" };
+    let label = path.display().to_string();
+
+    let mut out = format!("[dry-run] would {} {}", verb, label);
+    let diff = unified(&current, text, &label, &label);
+    if diff.is_empty() {
+        out.push_str(" (no changes)");
+    } else {
+        out.push('\n');
+        out.push_str(diff.trim_end());
+    }
+    redact::redact(&out)
+}
+
+/// What `save_config(cfg)` would change.
+pub fn preview_config(cfg: &AppConfig) -> Result<String> {
+    Ok(preview_write(&config_file_path()?, &render_config(cfg)?))
+}
+
+/// A write reported by path only.
+pub fn note_write(path: &Path) -> String {
+    format!("[dry-run] would write {}", path.display())
+}
+
+/// A deletion reported by path.
+pub fn note_remove(path: &Path) -> String {
+    format!("[dry-run] would remove {}", path.display())
+}
+
+/// A command reported instead of run, with the directory it would run in when known.
+pub fn note_run(command: &str, cwd: Option<&Path>) -> String {
+    match cwd {
+        Some(dir) => format!("[dry-run] would run in {}: {}", dir.display(), command),
+        None => format!("[dry-run] would run: {}", command),
+    }
+}
@@ -0,0 +1,278 @@
+//! `aion exec`: run a command and, when it fails, ask the provider what went wrong.
+//!
+//! Notes:
+//! - Needs `caps.run_commands`. The command runs directly, without a shell. Its output is
+//!   shown as it arrives and sampled for the prompt; since it goes through a pipe, tools
+//!   that only color a terminal print plain text.
+//! - The sample holds stdout and stderr together in arrival order, cut to the first and
+//!   last `max_output / 2` bytes with a marker where output was left out, and redacted.
+//!   What looks like a secret after that is shown masked and asked about (`secrets`).
+//! - The prompt is the `explain-command` template, so a user template of that name
+//!   replaces it, along with its provider and model overrides.
+//! - The exit code is the child's (128 + signal when killed), whatever happens to the
+//!   analysis. A command stopped with Ctrl+C is not analyzed.
+//! - `--no-run` analyzes output piped on stdin instead; a command after `--` only labels it.
+//! - The command runs in `--cwd`, else `exec.default_cwd`, else AION's own directory
+//!   (`workdir`). Any but the last is announced on stderr before the command starts, and
+//!   the directory goes into the dry-run note and the audit log.
+
+use crate::audit;
+use crate::cancel;
+use crate::chat;
+use crate::cli::ExecArgs;
+use crate::config::io::load_config;
+use crate::config::CapabilityDenied;
+use crate::dry_run;
+use crate::notify;
+use crate::redact;
+use crate::secrets;
+use crate::templates::TemplateStore;
+use crate::ui::errors;
+use crate::workdir::{self, Source};
+use anyhow::{bail, Context, Result};
+use crossterm::tty::IsTty;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Command, ExitCode, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+pub const DEFAULT_MAX_OUTPUT: usize = 16 * 1024;
+const TEMPLATE: &str = "explain-command";
+
+pub async fn run(args: &ExecArgs, strict: bool) -> Result<ExitCode> {
+    let cfg = load_config().context("failed to load config")?;
+    let store = TemplateStore::load()?;
+    let (template, _) = store
+        .get(TEMPLATE)
+        .ok_or_else(|| anyhow::anyhow!("no template named '{}' (see `aion templates list`)", TEMPLATE))?;
+    let cfg = args.overrides.apply(&template.apply_overrides(&cfg))?;
+    crate::validate_strict(&cfg, strict)?;
+    let line = command_line(&args.command);
+
+    let (status, sample) = if args.no_run {
+        (None, read_stdin(args.max_output)?)
+    } else {
+        if args.command.is_empty() {
+            bail!("no command given (aion exec -- <command> [args...])");
+        }
+        if !cfg.caps.run_commands {
+            return Err(CapabilityDenied { cap: "run_commands" }.into());
+        }
+        if let Some(dir) = &args.cwd {
+            workdir::pin(&cfg.caps, dir)?;
+        }
+        let dir = workdir::current(&cfg)?;
+        if dry_run::enabled() {
+            println!("{}", dry_run::note_run(&line, Some(&dir.path)));
+            return Ok(ExitCode::SUCCESS);
+        }
+        if dir.source != Source::Process {
+            eprintln!("--- aion: running in {} ({}) ---", dir, dir.source);
+        }
+        cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+        // Ctrl+C reaches the child too; the token only records that it happened.
+        let token = cancel::begin();
+        let started = notify::start();
+        let result = run_captured(&args.command, &dir.path, args.max_output);
+        cancel::end();
+        let (status, sample) = result?;
+        audit::command(&line, Some(&dir.path), status.code());
+        if token.is_cancelled() {
+            return Ok(exit_code(status));
+        }
+        notify::finish(started, notify::Task::Command { command: &line });
+        (Some(status), sample)
+    };
+
+    let code = status.map_or(ExitCode::SUCCESS, exit_code);
+    let failed = status.is_some_and(|s| !s.success());
+    if !(failed || args.always_analyze || args.no_run) {
+        return Ok(code);
+    }
+
+    let outcome = match status {
+        None => "produced this output".to_string(),
+        Some(s) if s.success() => "succeeded".to_string(),
+        Some(s) => format!("failed ({})", describe(s)),
+    };
+    let output = redact::redact(&sample.text());
+    if let Err(e) = secrets::confirm(&cfg, [("the output".to_string(), output.as_str())]) {
+        if args.no_run {
+            return Err(e);
+        }
+        eprintln!("Error: no analysis: {}", errors::render(&e, &cfg));
+        return Ok(code);
+    }
+    let vars = HashMap::from([
+        ("command".to_string(), if line.is_empty() { "a command".to_string() } else { line }),
+        ("outcome".to_string(), outcome),
+        ("input".to_string(), output),
+    ]);
+    let prompt = template.render(&vars)?;
+
+    println!();
+    println!("--- aion: analysis ---");
+    match chat::send_once(&cfg, &prompt, false, true).await {
+        Ok(_) => {}
+        Err(e) if args.no_run => return Err(e),
+        Err(e) => eprintln!("Error: analysis failed: {}", errors::render(&e, &cfg)),
+    }
+    Ok(code)
+}
+
+/// Run `command` in `dir` with its output shown and sampled; stdin is passed through.
+fn run_captured(command: &[String], dir: &Path, max_output: usize) -> Result<(ExitStatus, Sample)> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(dir)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {}", command[0]))?;
+
+    let sample = Arc::new(Mutex::new(Sample::new(max_output)));
+    let readers = [
+        child.stdout.take().map(|from| tee(from, io::stdout(), Arc::clone(&sample))),
+        child.stderr.take().map(|from| tee(from, io::stderr(), Arc::clone(&sample))),
+    ];
+    let status = child.wait().with_context(|| format!("failed to wait for {}", command[0]))?;
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+    let sample = std::mem::replace(&mut *sample.lock().unwrap_or_else(|e| e.into_inner()), Sample::new(0));
+    Ok((status, sample))
+}
+
+/// Copy `from` to `to` as it arrives, adding everything to `sample`.
+fn tee<R, W>(mut from: R, mut to: W, sample: Arc<Mutex<Sample>>) -> JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        while let Ok(n) = from.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let _ = to.write_all(&buf[..n]);
+            let _ = to.flush();
+            sample.lock().unwrap_or_else(|e| e.into_inner()).push(&buf[..n]);
+        }
+    })
+}
+
+fn read_stdin(max_output: usize) -> Result<Sample> {
+    let mut stdin = io::stdin();
+    if stdin.is_tty() {
+        bail!("--no-run analyzes output piped on stdin (e.g. `make 2>&1 | aion exec --no-run`)");
+    }
+    let mut sample = Sample::new(max_output);
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = stdin.read(&mut buf).context("failed to read stdin")?;
+        if n == 0 {
+            break;
+        }
+        sample.push(&buf[..n]);
+    }
+    if sample.total == 0 {
+        bail!("nothing on stdin to analyze");
+    }
+    Ok(sample)
+}
+
+/* ---------------------------
+   Output sample
+---------------------------- */
+
+/// The first and last bytes of a stream, without holding the middle.
+struct Sample {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    half: usize,
+    total: usize,
+}
+
+impl Sample {
+    fn new(max: usize) -> Self {
+        Self { head: Vec::new(), tail: VecDeque::new(), half: max / 2, total: 0 }
+    }
+
+    fn push(&mut self, mut bytes: &[u8]) {
+        self.total += bytes.len();
+        let room = self.half.saturating_sub(self.head.len());
+        let (first, rest) = bytes.split_at(room.min(bytes.len()));
+        self.head.extend_from_slice(first);
+        bytes = rest;
+        if bytes.len() >= self.half {
+            self.tail.clear();
+            bytes = &bytes[bytes.len() - self.half..];
+        }
+        let overflow = (self.tail.len() + bytes.len()).saturating_sub(self.half);
+        self.tail.drain(..overflow);
+        self.tail.extend(bytes);
+    }
+
+    fn text(&self) -> String {
+        let kept = self.head.len() + self.tail.len();
+        let tail: Vec<u8> = self.tail.iter().copied().collect();
+        let mut out = String::from_utf8_lossy(&self.head).into_owned();
+        if self.total > kept {
+            out.push_str(&format!("\n[... {} bytes left out ...]\n", self.total - kept));
+        }
+        out.push_str(&String::from_utf8_lossy(&tail));
+        out
+    }
+}
+
+/* ---------------------------
+   Exit status
+---------------------------- */
+
+/// The command as it would be typed, quoting arguments that need it.
+fn command_line(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+%".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `status` as this process's exit code: the child's, or 128 + the signal that killed it.
+fn exit_code(status: ExitStatus) -> ExitCode {
+    match status.code() {
+        Some(code) => ExitCode::from(code as u8),
+        None => ExitCode::from(signal(status).map_or(1, |s| 128u8.wrapping_add(s as u8))),
+    }
+}
+
+fn describe(status: ExitStatus) -> String {
+    match (status.code(), signal(status)) {
+        (Some(code), _) => format!("exit status {}", code),
+        (None, Some(sig)) => format!("killed by signal {}", sig),
+        (None, None) => "no exit status".to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_: ExitStatus) -> Option<i32> {
+    None
+}
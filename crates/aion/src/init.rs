@@ -0,0 +1,143 @@
+//! `aion init`: write a config from flags, for provisioning and dotfile scripts.
+//!
+//! Notes:
+//! - Never opens the wizard and never reads stdin, so it is safe in scripts.
+//! - Idempotent: an existing config with the same settings is left alone ("already
+//!   configured"); one that differs is an error showing the differences, unless
+//!   `--force` replaces it.
+//! - Existing configs are compared as `save_config` would write them, so formatting,
+//!   comments, and sections added by newer versions do not count as differences.
+//! - `--install-locales` copies the locale files built into the binary into
+//!   `locales/` next to config.toml; files that differ are only replaced with `--force`.
+
+use crate::cli::InitArgs;
+use crate::config::diff::config_changes;
+use crate::config::io::{config_file_path, ensure_config_dir_exists, save_config};
+use crate::paths::config_dir;
+use crate::config::{AppConfig, Features};
+use crate::dry_run;
+use crate::{audit, i18n, read_only, redact};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+pub fn run(args: &InitArgs) -> Result<ExitCode> {
+    read_only::check("init")?;
+    let cfg = build(args)?;
+    let path = config_file_path()?;
+
+    let write = match existing(&path)? {
+        None => true,
+        Some(Ok(current)) => {
+            let changes = config_changes(&current, &cfg)?;
+            if changes.is_empty() {
+                println!("{} is already configured.", path.display());
+                false
+            } else if args.force {
+                true
+            } else {
+                let mut msg = format!("{} exists with different settings:", path.display());
+                for line in &changes {
+                    msg.push_str(&format!("\n  {}", redact::redact(&line.to_string())));
+                }
+                msg.push_str("\nrerun with --force to replace it");
+                bail!(msg);
+            }
+        }
+        Some(Err(e)) if args.force => {
+            eprintln!("Warning: replacing unreadable config: {:#}", e);
+            true
+        }
+        Some(Err(e)) => return Err(e.context("the existing config cannot be read; rerun with --force to replace it")),
+    };
+
+    let locale_dir = i18n::user_locale_dir()?;
+    if dry_run::enabled() {
+        if !config_dir()?.exists() {
+            println!("{}", dry_run::note_write(&config_dir()?));
+        }
+        if write {
+            println!("{}", dry_run::preview_config(&cfg)?);
+        }
+        if !locale_dir.exists() {
+            println!("{}", dry_run::note_write(&locale_dir));
+        }
+    } else {
+        ensure_config_dir_exists()?;
+        if write {
+            audit::init(&cfg);
+            save_config(&cfg).context("failed to save config")?;
+            println!("Wrote {}", path.display());
+        }
+        fs::create_dir_all(&locale_dir)
+            .with_context(|| format!("failed to create {}", locale_dir.display()))?;
+    }
+
+    if args.install_locales {
+        install_locales(&locale_dir, args.force)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The config the flags describe, validated. Unset flags keep `new_default`'s values.
+fn build(args: &InitArgs) -> Result<AppConfig> {
+    let mut cfg = AppConfig::new_default();
+    if let Some(kind) = &args.provider {
+        cfg.set_provider_kind(kind.clone());
+    }
+    if let Some(model) = &args.model {
+        cfg.provider.model = model.clone();
+    }
+    if let Some(url) = &args.base_url {
+        cfg.provider.base_url = Some(url.clone());
+    }
+    if let Some(var) = &args.api_key_env {
+        cfg.provider.api_key_env = Some(var.clone());
+    }
+    if let Some(lang) = &args.language {
+        cfg.language = lang.clone();
+    }
+    if let Some(mode) = &args.ui_mode {
+        cfg.ui_mode = mode.clone();
+    }
+    cfg.caps = args.caps.caps();
+    if let Some(names) = &args.features {
+        cfg.features = Features::only(names)?;
+    }
+    cfg.validate().context("the flags do not make a valid config")?;
+    Ok(cfg)
+}
+
+/// The config at `path`: `None` when there is none, `Some(Err)` when it does not parse.
+fn existing(path: &Path) -> Result<Option<Result<AppConfig>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(Some(
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display())),
+    ))
+}
+
+fn install_locales(dir: &Path, force: bool) -> Result<()> {
+    for (code, text) in i18n::BUNDLED_LOCALES {
+        let path = dir.join(format!("{}.toml", code));
+        let current = fs::read_to_string(&path).ok();
+        if current.as_deref() == Some(*text) {
+            continue;
+        }
+        if current.is_some() && !force {
+            println!("Kept {} (differs from the bundled file; --force replaces it)", path.display());
+            continue;
+        }
+        if dry_run::enabled() {
+            println!("{}", dry_run::note_write(&path));
+            continue;
+        }
+        fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))?;
+        audit::file_write(&path);
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
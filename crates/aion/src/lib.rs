@@ -1 +0,0 @@
-pub mod i18n;
\ No newline at end of file
@@ -0,0 +1,331 @@
+//! `aion locales check`: translation coverage and strings too wide for the wizard.
+//! `aion locales stats`: locale keys the code does not use, and keys it uses that `en`
+//! does not define.
+//!
+//! Notes:
+//! - Coverage is measured against `en`, the fallback locale. Missing keys fall back to
+//!   English at runtime, so they are reported but never fail the check.
+//! - `--widths` checks the keys the wizard draws (`wizard.*`, `status.*`) against a
+//!   column budget per category: titles (`*.title`, `*_title`, `wizard.step.*`),
+//!   status-line text (`status.*`, `wizard.hint.*`), and labels (the rest). Any string
+//!   over its budget fails the check.
+//! - Widths are terminal columns, measured like the wizard measures them (`ui::width`).
+//!   Step title templates are measured filled in, with the locale's widest numbered step
+//!   name and "100" for the percentage.
+//! - Every `ProviderKind` needs `providers.<id>.name` and `.description` in `en` and in
+//!   each locale marked `status = "full"`; a missing one fails the check, so a new
+//!   provider cannot ship without its labels.
+//! - A translated string must use the same `{placeholders}` as its `en` text: one the
+//!   code does not fill fails at runtime, and one left out loses what it would show.
+//!   A mismatch fails the check.
+//! - `stats` scans sources with `i18n::scan` and compares against the `en` that would be
+//!   loaded (its file is shown). Keys only a dynamic key's prefix reaches are counted
+//!   apart from the unused ones. Unused and missing keys fail it only with `--strict`.
+
+use crate::cli::{LocalesAction, LocalesCheckArgs, LocalesStatsArgs};
+use crate::config::ProviderKind;
+use crate::i18n::scan::{self, Report};
+use crate::i18n::{self, LocaleManager};
+use crate::ui::console::Console;
+use crate::ui::width;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Title,
+    Status,
+    Label,
+}
+
+impl Category {
+    fn name(self) -> &'static str {
+        match self {
+            Category::Title => "title",
+            Category::Status => "status",
+            Category::Label => "label",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TooWide {
+    pub key: String,
+    pub category: Category,
+    pub width: usize,
+    pub budget: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlaceholderMismatch {
+    pub key: String,
+    /// What the `en` text uses.
+    pub expected: Vec<String>,
+    pub found: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocaleReport {
+    pub code: String,
+    pub name: String,
+    pub coverage: f64,
+    pub missing: Vec<String>,
+    /// Required `providers.*` keys this locale lacks.
+    pub missing_required: Vec<String>,
+    pub placeholders: Vec<PlaceholderMismatch>,
+    /// `None` unless `--widths` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub too_wide: Option<Vec<TooWide>>,
+}
+
+/// Reports for `args.code`, or for every installed locale.
+pub fn run(console: &Console, action: &LocalesAction, strict: bool) -> Result<ExitCode> {
+    match action {
+        LocalesAction::Check(a) => {
+            let reports = check(a)?;
+            if console.is_json() {
+                console.print_json(&reports)?;
+            } else {
+                print_human(&reports, a.code.is_some());
+            }
+            Ok(if has_failures(&reports) {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            })
+        }
+        LocalesAction::Stats(a) => {
+            let stats = stats(a)?;
+            if console.is_json() {
+                console.print_json(&stats)?;
+            } else {
+                print_stats(&stats);
+            }
+            Ok(if strict && !stats.report.is_clean() { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+        }
+    }
+}
+
+pub fn check(args: &LocalesCheckArgs) -> Result<Vec<LocaleReport>> {
+    let manager = LocaleManager::load()?;
+    let codes = match &args.code {
+        Some(code) if !manager.available_locales().contains(code) => {
+            bail!("locale '{}' is not installed (found: {})", code, manager.available_locales().join(", "))
+        }
+        Some(code) => vec![code.clone()],
+        None => manager.available_locales(),
+    };
+    for code in &codes {
+        manager.preload(code)?;
+    }
+
+    Ok(codes
+        .into_iter()
+        .map(|code| LocaleReport {
+            name: manager.meta(&code).map(|m| m.name.clone()).unwrap_or_default(),
+            coverage: manager.coverage(&code).unwrap_or(0.0),
+            missing: manager.missing_keys(&code),
+            missing_required: missing_required(&manager, &code),
+            placeholders: placeholder_mismatches(&manager, &code),
+            too_wide: args.widths.then(|| too_wide(&manager, &code, args)),
+            code,
+        })
+        .collect())
+}
+
+pub fn has_failures(reports: &[LocaleReport]) -> bool {
+    reports.iter().any(|r| {
+        !r.missing_required.is_empty()
+            || !r.placeholders.is_empty()
+            || r.too_wide.as_ref().is_some_and(|w| !w.is_empty())
+    })
+}
+
+/// Strings of `code` whose placeholders differ from the `en` text's.
+fn placeholder_mismatches(manager: &LocaleManager, code: &str) -> Vec<PlaceholderMismatch> {
+    let english: HashMap<String, String> = manager.strings("en").unwrap_or_default().into_iter().collect();
+    manager
+        .strings(code)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(key, text)| {
+            let expected = i18n::placeholders(english.get(&key)?);
+            let found = i18n::placeholders(&text);
+            (expected != found).then(|| PlaceholderMismatch {
+                key,
+                expected: expected.into_iter().collect(),
+                found: found.into_iter().collect(),
+            })
+        })
+        .collect()
+}
+
+/// Provider keys `code` must define itself: all of them for `en` and full locales.
+fn missing_required(manager: &LocaleManager, code: &str) -> Vec<String> {
+    let full = code == "en" || manager.meta(code).is_some_and(|m| m.status == "full");
+    if !full {
+        return Vec::new();
+    }
+    let defined: Vec<String> = manager.strings(code).unwrap_or_default().into_iter().map(|(k, _)| k).collect();
+    ProviderKind::ALL
+        .iter()
+        .flat_map(|p| ["name", "description"].map(|field| format!("providers.{}.{}", p.id(), field)))
+        .filter(|key| !defined.contains(key))
+        .collect()
+}
+
+/// The wizard category of `key`, or `None` for strings the wizard does not draw.
+fn category(key: &str) -> Option<Category> {
+    if !key.starts_with("wizard.") && !key.starts_with("status.") {
+        return None;
+    }
+    let last = key.rsplit('.').next().unwrap_or(key);
+    Some(if last == "title" || last.ends_with("_title") || key.starts_with("wizard.step.") {
+        Category::Title
+    } else if key.starts_with("status.") || key.starts_with("wizard.hint.") {
+        Category::Status
+    } else {
+        Category::Label
+    })
+}
+
+fn too_wide(manager: &LocaleManager, code: &str, args: &LocalesCheckArgs) -> Vec<TooWide> {
+    let strings = manager.strings(code).unwrap_or_default();
+    let widest_step = strings
+        .iter()
+        .filter(|(key, _)| key.starts_with("wizard.step.") && !key.starts_with("wizard.step.title"))
+        .filter(|(key, _)| key != "wizard.step.import")
+        .map(|(_, text)| text.as_str())
+        .max_by_key(|text| width::width(text))
+        .unwrap_or_default()
+        .to_string();
+    strings
+        .into_iter()
+        .filter_map(|(key, text)| {
+            let category = category(&key)?;
+            let budget = match category {
+                Category::Title => args.title_width,
+                Category::Status => args.status_width,
+                Category::Label => args.label_width,
+            };
+            let width = match key.starts_with("wizard.step.title") {
+                true => width::width(&step_title_sample(&text, &widest_step)),
+                false => width::width(&text),
+            };
+            (width > budget).then_some(TooWide { key, category, width, budget, text })
+        })
+        .collect()
+}
+
+/// A step title template as the wizard could draw it, with `name` as the step's name.
+fn step_title_sample(template: &str, name: &str) -> String {
+    template.replace("{i}", "9").replace("{n}", "9").replace("{percent}", "100").replace("{name}", name)
+}
+
+/// Missing keys are listed only for a single locale (`list_missing`); otherwise counted.
+pub fn print_human(reports: &[LocaleReport], list_missing: bool) {
+    for r in reports {
+        println!(
+            "{} ({}): {:.0}% translated, {} missing",
+            r.code,
+            r.name,
+            r.coverage * 100.0,
+            r.missing.len()
+        );
+        for key in r.missing.iter().filter(|_| list_missing) {
+            println!("  missing   {}", key);
+        }
+        for key in &r.missing_required {
+            println!("  required  {} (no translation)", key);
+        }
+        for m in &r.placeholders {
+            println!("  mismatch  {} (placeholders {} in en, {} here)", m.key, braced(&m.expected), braced(&m.found));
+        }
+        for w in r.too_wide.iter().flatten() {
+            println!(
+                "  too wide  {} ({}, {} > {} columns): {}",
+                w.key, w.category.name(), w.width, w.budget, w.text
+            );
+        }
+    }
+    if reports.iter().any(|r| r.too_wide.is_some()) {
+        let count: usize = reports.iter().filter_map(|r| r.too_wide.as_ref()).map(Vec::len).sum();
+        println!();
+        println!("{} strings over their width budget", count);
+    }
+}
+
+/// `names` as they are written in a locale file, or "none".
+fn braced(names: &[String]) -> String {
+    match names.is_empty() {
+        true => "none".to_string(),
+        false => names.iter().map(|n| format!("{{{}}}", n)).collect::<Vec<_>>().join(" "),
+    }
+}
+
+/* ---------------------------
+   Key usage
+---------------------------- */
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    /// The `en` file compared against.
+    pub locale: Option<PathBuf>,
+    pub src: Vec<PathBuf>,
+    /// Keys `en` defines.
+    pub defined: usize,
+    #[serde(flatten)]
+    pub report: Report,
+}
+
+pub fn stats(args: &LocalesStatsArgs) -> Result<Stats> {
+    let manager = LocaleManager::load()?;
+    manager.preload("en")?;
+    let Some(strings) = manager.strings("en") else { bail!("the en locale is not installed") };
+    let defined: Vec<String> = strings.into_iter().map(|(key, _)| key).collect();
+    let mut found = Vec::new();
+    for dir in &args.src {
+        found.extend(scan::scan_dir(dir)?);
+    }
+    Ok(Stats {
+        locale: manager.source("en").map(PathBuf::from),
+        src: args.src.clone(),
+        defined: defined.len(),
+        report: scan::compare(&found, &defined),
+    })
+}
+
+pub fn print_stats(stats: &Stats) {
+    let r = &stats.report;
+    if let Some(path) = &stats.locale {
+        println!("en: {}", path.display());
+    }
+    println!(
+        "{} keys: {} used, {} unused, {} reached only by dynamic keys",
+        stats.defined,
+        r.used,
+        r.unused.len(),
+        r.maybe_used.len()
+    );
+    println!(
+        "{} files with lookups; {} keys used but not in en; {} dynamic lookups",
+        r.files,
+        r.missing.len(),
+        r.dynamic.len()
+    );
+    for key in &r.unused {
+        println!("  unused    {}", key);
+    }
+    for m in &r.missing {
+        println!("  missing   {} ({}:{})", m.key, m.site.file.display(), m.site.line);
+    }
+    for d in &r.dynamic {
+        let prefix = d.prefix.as_deref().map_or(String::new(), |p| format!(" [{}*]", p));
+        println!("  dynamic   {}{} ({}:{})", d.expr, prefix, d.site.file.display(), d.site.line);
+    }
+}
@@ -7,89 +7,268 @@
 //!   https://doc.rust-lang.org/book/ch07-02-defining-modules-to-control-scope-and-privacy.html
 
 use anyhow::{Context, Result};
-use std::io::{self, Write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use crossterm::tty::IsTty;
+use std::io;
+use std::process::ExitCode;
 
-mod config;
+mod ask;
+mod attach;
+mod audit_cmd;
+mod benchmark;
+mod cache_cmd;
+mod cancel;
+mod chat;
+mod cli;
+mod clipboard;
+mod commands;
+mod config_cmd;
+mod detect;
+mod doctor;
+mod dry_run;
+mod exec;
+mod init;
+mod locales;
+mod models;
+mod persona;
+mod report;
+mod repl;
+mod secrets;
+mod self_test;
+mod sessions_cmd;
+mod setup;
+mod status;
+mod suggest;
+mod summary;
+mod templates;
+mod transfer;
 mod tui;
+mod ui;
+mod update;
+mod usage;
+
+// Core modules keep their `crate::` paths.
+use aion_core::{
+    audit, backup, bench, cache, catalog, config, conversation, costs, frames, gather, guard, history, http_log, i18n, notify, paths, persistence, pipeline,
+    project_context, providers, read_only, recent, redact, retrieval, sessions, shell_history, structured, workdir, wrap,
+};
 
 // Optional i18n module. If you currently have an i18n module with init() -> Result<()>,
 // you can enable it by uncommenting the two lines below.
 // mod i18n;
 // use crate::i18n as _i18n;
 
-use crate::config::io::{load_or_create_config, save_config};
+use crate::config::io::{config_file_path, load_config, load_or_create_config, save_config};
+use crate::ui::console::{BootOptions, Console};
 
-fn print_banner() {
-    println!();
-    println!("==============================================================");
-    println!("                      AION CORE INITIALIZED                    ");
-    println!("==============================================================");
-    println!();
+/// With `--strict`, fail on the config's consistency warnings (`validate` covers the rest).
+fn validate_strict(cfg: &config::AppConfig, strict: bool) -> Result<()> {
+    if strict {
+        cfg.validate_strict().context("config validation failed")?;
+    }
+    Ok(())
 }
 
-fn print_environment_info() {
-    let os: &str = std::env::consts::OS;
-    let arch: &str = std::env::consts::ARCH;
-
-    println!("System Information:");
-    println!("  OS Architecture : {}", arch);
-    println!("  Operating System: {}", os);
-    println!();
+/// Point the process-wide settings at `cfg`: redaction, the audit log, the cost ledger,
+/// the HTTP log, number and text-direction formatting, and notifications.
+fn init_globals(cfg: &config::AppConfig) {
+    redact::init(cfg);
+    audit::init(cfg);
+    costs::init(cfg);
+    http_log::init(cfg);
+    i18n::format::init(cfg);
+    i18n::bidi::init(cfg);
+    notify::init(cfg);
 }
 
-fn print_timestamp() {
-    match SystemTime::now().duration_since(UNIX_EPOCH) {
-        Ok(d) => println!("Startup Timestamp: {}", d.as_secs()),
-        Err(_) => println!("Startup Timestamp: unavailable"),
+fn main() -> ExitCode {
+    // One runtime for the whole process. Current-thread: one-shot commands never start
+    // worker threads, and the TUI multiplexes input and requests with `select!`.
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("Error: failed to start async runtime: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(path) = cli::config_arg() {
+        config::io::set_config_file(path);
+    }
+    let lang = cli::ui_language();
+    match runtime.block_on(run(&lang)) {
+        Ok(code) => code,
+        // Typed errors are localized; the rest print as their redacted context chain.
+        Err(e) => {
+            let mut cfg = load_config().unwrap_or_else(|_| config::AppConfig::new_default());
+            cfg.language = lang;
+            eprintln!("Error: {}", ui::errors::render(&e, &cfg));
+            if let Some(details) = ui::errors::config_file_details(&e, &cfg.language) {
+                eprintln!("{}", details);
+            }
+            match providers::ProviderError::find(&e) {
+                Some(err) => ExitCode::from(err.exit_code()),
+                None => ExitCode::FAILURE,
+            }
+        }
     }
-    println!();
 }
 
-fn print_config_summary(cfg: &config::AppConfig) {
-    println!("Config loaded successfully");
-    println!("Language: {}", cfg.language);
-    println!("Provider: {:?}", cfg.provider.kind);
-    println!("Model: {}", cfg.provider.model);
-    println!();
+/// Name the locale files left out, once per run: directories that could not be listed,
+/// files too large, and the files read so far (`en`, and `lang`, read now so a problem
+/// with it shows here rather than as English text later). Other locales stay unread;
+/// `aion doctor` and `aion locales check` read them all. Not called for commands that
+/// print nothing translated (`Cli::untranslated`).
+fn warn_skipped_locales(lang: &str) {
+    let Some(locales) = i18n::shared() else { return };
+    let _ = locales.preload(lang);
+    for skipped in locales.skipped() {
+        eprintln!("Warning: locale file {} skipped: {}", skipped.path.display(), skipped.reason);
+    }
 }
 
-fn prompt_ready() {
-    print!("AION is ready > ");
-    let _ = io::stdout().flush();
+/// Move what an earlier version kept in the config dir to the state and cache dirs
+/// (`paths::migrate`), a line for each. Read-only mode and dry runs leave it for the next
+/// ordinary run. A failure is a warning.
+fn move_old_files(quiet: bool) {
+    if read_only::active().is_some() || dry_run::enabled() {
+        return;
+    }
+    let migration = match paths::Roots::current().and_then(|roots| paths::migrate(&roots)) {
+        Ok(migration) => migration,
+        Err(e) => {
+            eprintln!("Warning: files from an earlier version were not moved: {:#}", e);
+            return;
+        }
+    };
+    for moved in migration.moved.iter().filter(|_| !quiet) {
+        eprintln!("Moved {} to {}", i18n::format::tilde(&moved.from), i18n::format::tilde(&moved.to));
+    }
+    for kept in &migration.kept {
+        eprintln!("Warning: left {} where it was: the new location already has one", i18n::format::tilde(kept));
+    }
 }
 
-fn has_flag(name: &str) -> bool {
-    std::env::args().any(|a| a == name)
+/// A rate limit wait (`network.requests_per_minute`, ...), on its own line of stderr.
+fn report_wait(wait: &providers::limit::Wait) {
+    match io::stderr().is_tty() {
+        true => eprintln!("\r\x1b[2K{}", wait),
+        false => eprintln!("{}", wait),
+    }
 }
 
-fn main() -> Result<()> {
+async fn run(lang: &str) -> Result<ExitCode> {
+    let args = cli::parse(lang);
+    read_only::init(args.read_only);
+    if args.setup {
+        read_only::check("setup")?;
+    }
+    if args.dry_run {
+        dry_run::enable();
+    }
+    // The self-test leaves the user's files alone.
+    if !matches!(args.command, Some(cli::Command::SelfTest(_))) {
+        move_old_files(args.quiet);
+    }
+    if args.debug_http {
+        http_log::force();
+    }
+    if args.notify {
+        notify::force();
+    }
+    ui::notify::install();
+    if args.ascii {
+        tui::glyphs::force_ascii();
+    }
+    if args.pager {
+        ui::reply::force_pager();
+    }
+    if args.allow_secrets {
+        secrets::allow();
+    }
+    let console = Console::new(args.output, args.quiet);
+    if !args.quiet {
+        if !args.untranslated() {
+            warn_skipped_locales(lang);
+        }
+        providers::limit::set_reporter(Some(report_wait));
+    }
+    if let Ok(cfg) = load_config() {
+        init_globals(&cfg);
+    }
+
+    // Subcommands return before `load_or_create_config`, so none creates a config as a side
+    // effect. Several do write on purpose (`init`, `config set`, `cache clear`, ...).
+    if let Some(command) = &args.command {
+        return match command {
+            cli::Command::Doctor(a) => doctor::run(&console, a, args.strict).await,
+            cli::Command::Status(args) => status::run(&console, args, lang).await,
+            cli::Command::Chat(a) => chat::run(&console, a, args.lang.as_deref(), args.strict).await,
+            cli::Command::Run(a) => templates::run(a, args.strict).await,
+            cli::Command::Exec(a) => exec::run(a, args.strict).await,
+            cli::Command::Ask(a) => ask::run(&console, a, args.lang.as_deref(), args.strict).await,
+            cli::Command::Bench(a) => benchmark::run(&console, a, args.strict).await,
+            cli::Command::Templates { action } => templates::manage(action),
+            cli::Command::Models(a) => models::run(&console, a).await,
+            cli::Command::Sessions { action } => sessions_cmd::run(&console, action),
+            cli::Command::Init(a) => init::run(a),
+            cli::Command::Config { action } => config_cmd::run(&console, action, args.strict),
+            cli::Command::Locales { action } => locales::run(&console, action, args.strict),
+            cli::Command::Cache { action } => cache_cmd::run(&console, action, lang),
+            cli::Command::Usage(a) => usage::run(&console, a),
+            cli::Command::Audit { action } => audit_cmd::run(&console, action),
+            cli::Command::Report(a) => report::run(a, args.strict).await,
+            cli::Command::Export(a) => transfer::run_export(a),
+            cli::Command::Import(a) => transfer::run_import(a),
+            cli::Command::SelfUpdate(a) => update::run(a).await,
+            cli::Command::SelfTest(a) => self_test::run(&console, a),
+        };
+    }
+
     // 1) Load (or create) config
-    let mut cfg: config::AppConfig = load_or_create_config().context("failed to load or create config")?;
+    let first_run = !config_file_path()?.exists();
+    let mut cfg: config::AppConfig = match load_config() {
+        Err(_) if dry_run::enabled() => {
+            let cfg = config::AppConfig::new_default();
+            println!("{}", dry_run::preview_config(&cfg)?);
+            cfg
+        }
+        _ => load_or_create_config().context("failed to load or create config")?,
+    };
+    // As on disk, before this run's `--lang`; the wizard's result is compared to it.
+    let saved = cfg.clone();
+    if let Some(lang) = &args.lang {
+        cfg.language = lang.clone();
+    }
 
     // 2) Initialize localization (optional)
     // If you have i18n::init() implemented, you can enable this.
     // _i18n::init().context("failed to initialize locale")?;
 
-    // 3) Print boot info
-    print_banner();
-    print_environment_info();
-    print_timestamp();
-
-    println!("Core Status: OK");
-    println!("Runtime Status: OK");
-    println!("Initialization Complete");
-    println!();
+    // 3) Print boot info (decorative; suppressed when quiet, JSON, or not a TTY)
+    console.print_boot(&cfg, BootOptions { first_run, no_banner: args.no_banner });
 
     // 4) If user requests setup wizard
-    if has_flag("--setup") {
+    if args.setup && args.non_interactive {
+        return setup::unattended::run(&console, &args, &saved);
+    }
+    if args.setup {
         // The wizard is expected to return an updated config.
-        // This assumes you have: pub fn run(existing: &AppConfig) -> Result<AppConfig>
-        let updated: config::AppConfig =
-            tui::wizard::run(&cfg).context("setup wizard failed")?;
+        let updated: config::AppConfig = if args.plain {
+            setup::plain::run(&cfg, args.step).await
+        } else {
+            tui::run_wizard(&cfg, args.step).await
+        }
+        .context("setup wizard failed")?;
 
         updated.validate().context("config validation failed")?;
-        save_config(&updated).context("failed to save config")?;
+        validate_strict(&updated, args.strict)?;
+        if dry_run::enabled() {
+            println!("{}", dry_run::preview_config(&updated)?);
+        } else if setup::confirm_save(&saved, &updated, args.yes || args.plain)? {
+            save_config(&updated).context("failed to save config")?;
+        } else {
+            println!("No changes applied.");
+            return Ok(ExitCode::SUCCESS);
+        }
 
         cfg = updated;
 
@@ -97,9 +276,38 @@ fn main() -> Result<()> {
         // _i18n::init().context("failed to re-initialize locale")?;
     }
 
-    // 5) Show current config summary + ready prompt
-    print_config_summary(&cfg);
-    prompt_ready();
+    // `--provider` / `--model` apply to this session only, after anything was saved.
+    let cfg = args.overrides.apply(&cfg)?;
+    validate_strict(&cfg, args.strict)?;
+    persistence::init(persistence::PersistencePolicy::new(&cfg, args.ephemeral));
+    init_globals(&cfg);
 
-    Ok(())
-}
\ No newline at end of file
+    update::check_in_background(&cfg);
+
+    // 5) Show current config summary, then chat interactively when attached to a terminal.
+    // The full-screen view shows the summary as its splash instead.
+    let summary = summary::Summary::new(&cfg, config_file_path().ok().as_deref(), summary::key_set)
+        .with_context(&persona::load_context(&cfg));
+    let interactive = console.decorations() && io::stdin().is_tty();
+    if args.resume.is_some() && !interactive {
+        anyhow::bail!("--resume continues a chat in the terminal");
+    }
+    if !(interactive && chat::uses_tui(&cfg)) {
+        console.print_startup_summary(&summary, &cfg.language)?;
+    }
+    if interactive {
+        chat::interactive(&cfg, Some(&summary), args.resume.as_deref()).await?;
+    } else {
+        if let Some(notice) = update::take_notice(&cfg.language).filter(|_| console.decorations()) {
+            println!("{}", notice);
+        }
+        if console.decorations() {
+            if let Some(tip) = detect::local_tip(&cfg).await {
+                println!("{}", tip);
+            }
+        }
+        console.prompt_ready();
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
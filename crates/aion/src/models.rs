@@ -0,0 +1,78 @@
+//! `aion models`: the models the configured provider offers.
+//!
+//! Notes:
+//! - `list` marks the configured model with `*`; `check` fails when it is not offered.
+//! - A provider that cannot list its models is reported, not treated as a failure.
+//! - Each listing refreshes the model catalog (`aion_core::catalog`), unless dry-run.
+
+use crate::catalog;
+use crate::cli::{ModelsAction, ModelsArgs};
+use crate::config::io::load_config;
+use crate::dry_run;
+use crate::providers;
+use crate::ui::console::Console;
+use anyhow::{Context, Result};
+use std::process::ExitCode;
+
+pub async fn run(console: &Console, args: &ModelsArgs) -> Result<ExitCode> {
+    let cfg = load_config().context("failed to load config")?;
+    let json = args.json || console.is_json();
+    let client = providers::build_client(&cfg).context("failed to create provider client")?;
+    let current = cfg.provider.model.as_str();
+
+    let models = match client.list_models().await {
+        Ok(models) => models,
+        Err(e) if e.is::<providers::ListingUnsupported>() => {
+            // Nothing to compare against; report it without failing bootstrap scripts.
+            if json {
+                console.print_json(&serde_json::json!({ "supported": false, "model": current }))?;
+            } else {
+                eprintln!("{}: {}", cfg.provider.kind, e);
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Err(e) => return Err(e.context("failed to list models")),
+    };
+    let found = models.iter().any(|m| m.matches(current));
+    if !dry_run::enabled() {
+        let ids: Vec<String> = models.iter().map(|m| m.id.clone()).collect();
+        if let Err(e) = catalog::store(&cfg.provider.kind, &ids) {
+            eprintln!("Warning: model catalog not updated: {:#}", e);
+        }
+    }
+
+    match args.action {
+        ModelsAction::List => {
+            if json {
+                let rows: Vec<_> = models
+                    .iter()
+                    .map(|m| serde_json::json!({
+                        "id": m.id,
+                        "context_length": m.context_length,
+                        "current": m.matches(current),
+                    }))
+                    .collect();
+                console.print_json(&rows)?;
+            } else {
+                for m in &models {
+                    let marker = if m.matches(current) { '*' } else { ' ' };
+                    let ctx = m.context_length.map(|n| n.to_string()).unwrap_or_default();
+                    println!("{}", format!("{} {:<48} {}", marker, m.id, ctx).trim_end());
+                }
+            }
+        }
+        ModelsAction::Check => {
+            if json {
+                console.print_json(&serde_json::json!({ "model": current, "found": found }))?;
+            } else if found {
+                println!("{} is available", current);
+            } else {
+                eprintln!("{} is not offered by {} (see `aion models list`)", current, cfg.provider.kind);
+            }
+            if !found {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,116 @@
+//! System prompt composition.
+//!
+//! Notes:
+//! - With `provider.respond_in_config_language`, the system prompt asks the model to reply
+//!   in the configured language. The wording is the locale's `chat.reply_instruction`,
+//!   filled with its `meta.native` name, so each language phrases it naturally.
+//! - The effective `[style]` (see `config::style`) adds its tone and format
+//!   instructions after it, worded by the locale's `style.tone.*` / `style.format.*`.
+//! - Locales are loaded once per process. Without them no reply-language instruction is
+//!   added, and the style instructions are in English.
+//! - The project context (see `project_context`) goes first in the messages `compose`
+//!   builds, ahead of the instructions above. Like the locales it is process-wide: chats
+//!   load it at start (`load_context`), and `/context` shows, reloads, or drops it.
+
+use crate::config::{style, AppConfig};
+use crate::i18n::format::{format_bytes, tilde};
+use crate::i18n::{self, interpolate, LocaleManager};
+use crate::project_context::{self, ProjectContext};
+use crate::providers::Message;
+use crate::redact;
+use crate::commands::ContextAction;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+static CONTEXT: Mutex<Option<ProjectContext>> = Mutex::new(None);
+
+const REPLY_INSTRUCTION_KEY: &str = "chat.reply_instruction";
+
+/// The system prompt for `cfg`, if any.
+pub fn system_prompt(cfg: &AppConfig) -> Option<String> {
+    let base = language_instruction(cfg);
+    style::compose_system(base.as_deref(), &style::effective(&cfg.style), |key, english| {
+        i18n::text(&cfg.language, key, english, &[])
+    })
+}
+
+/// The reply-language instruction, when `provider.respond_in_config_language` is on.
+pub fn language_instruction(cfg: &AppConfig) -> Option<String> {
+    if !cfg.provider.respond_in_config_language {
+        return None;
+    }
+    reply_instruction(i18n::shared()?, &cfg.language)
+}
+
+/// The reply-language instruction for `language`, phrased by that locale.
+pub fn reply_instruction(locales: &LocaleManager, language: &str) -> Option<String> {
+    let native = locales.meta(language)?.native.clone();
+    let template = locales.t(language, REPLY_INSTRUCTION_KEY);
+    if template == REPLY_INSTRUCTION_KEY {
+        return None;
+    }
+    let vars = HashMap::from([("language".to_string(), native)]);
+    interpolate(&template, &vars).ok()
+}
+
+/// The messages to send: the project context and the system prompt for `cfg` (if any)
+/// followed by `history`.
+pub fn compose(cfg: &AppConfig, history: &[Message]) -> Vec<Message> {
+    let mut out = Vec::with_capacity(history.len() + 1);
+    let system: Vec<String> = context().map(|c| c.prompt()).into_iter().chain(system_prompt(cfg)).collect();
+    if !system.is_empty() {
+        out.push(Message::system(system.join("\n\n")));
+    }
+    out.extend_from_slice(history);
+    out
+}
+
+/* ---------------------------
+   Project context
+---------------------------- */
+
+/// Find and read the project context for the current directory, in place of what was
+/// loaded before. `Ok(None)` when there is none; an error (the caps refused it) leaves
+/// none loaded.
+pub fn load_context(cfg: &AppConfig) -> Result<Option<ProjectContext>> {
+    let cwd = std::env::current_dir().context("failed to read the current directory")?;
+    let loaded = project_context::load(&cfg.context, &cfg.caps, &cwd);
+    *CONTEXT.lock().unwrap_or_else(|e| e.into_inner()) = loaded.as_ref().ok().cloned().flatten();
+    loaded
+}
+
+/// The project context sent with chats, if any.
+pub fn context() -> Option<ProjectContext> {
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// `path (size)`, or `path (first n of size)` when cut.
+pub fn describe_context(c: &ProjectContext, lang: &str) -> String {
+    let size = format_bytes(lang, c.size);
+    match c.truncated {
+        true => format!("{} (first {} of {})", tilde(&c.path), format_bytes(lang, c.text.len() as u64), size),
+        false => format!("{} ({})", tilde(&c.path), size),
+    }
+}
+
+/// `/context`, in the REPL and the chat view: the text to show.
+pub fn context_command(cfg: &AppConfig, action: ContextAction) -> Result<String> {
+    Ok(match action {
+        ContextAction::Show => match context() {
+            Some(c) => format!("Project context: {}\n\n{}", describe_context(&c, &cfg.language), redact::redact(c.text.trim_end())),
+            None => "No project context is sent (/context reload looks for one).".to_string(),
+        },
+        ContextAction::Reload if !cfg.context.enabled => {
+            "The project context is switched off (context.enabled = false).".to_string()
+        }
+        ContextAction::Reload => match load_context(cfg)? {
+            Some(c) => format!("Project context: {}", describe_context(&c, &cfg.language)),
+            None => format!("No {} here or in a parent directory.", cfg.context.file.display()),
+        },
+        ContextAction::Off => {
+            *CONTEXT.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            "Project context off for this session (/context reload sends it again).".to_string()
+        }
+    })
+}
@@ -0,0 +1,142 @@
+//! Multi-line entry assembly for the REPL.
+//!
+//! Notes:
+//! - Pure state machine: fed one submitted line at a time, independent of terminal I/O.
+//! - A trailing backslash continues the entry on the next line.
+//! - A line consisting of `"""` opens a block that runs until the next `"""` line.
+//! - Pasted text arrives inside a single submitted line (newlines kept literally), so it
+//!   never completes an entry on its own.
+
+pub const FENCE: &str = "\"\"\"";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feed {
+    /// The entry is complete and ready to send.
+    Complete(String),
+    /// More lines are needed; show the continuation prompt.
+    Continue,
+}
+
+#[derive(Debug, Default)]
+pub struct LineAssembler {
+    lines: Vec<String>,
+    in_fence: bool,
+}
+
+impl LineAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True while an entry is partially assembled (continuation prompt should be shown).
+    pub fn is_pending(&self) -> bool {
+        self.in_fence || !self.lines.is_empty()
+    }
+
+    /// Drop the partially assembled entry (Ctrl+C).
+    pub fn reset(&mut self) {
+        self.lines.clear();
+        self.in_fence = false;
+    }
+
+    pub fn feed(&mut self, line: &str) -> Feed {
+        if self.in_fence {
+            if line.trim() == FENCE {
+                self.in_fence = false;
+                return Feed::Complete(self.take());
+            }
+            self.lines.push(line.to_string());
+            return Feed::Continue;
+        }
+
+        if line.trim() == FENCE {
+            self.in_fence = true;
+            return Feed::Continue;
+        }
+
+        if let Some(stripped) = line.strip_suffix('\\') {
+            self.lines.push(stripped.to_string());
+            return Feed::Continue;
+        }
+
+        self.lines.push(line.to_string());
+        Feed::Complete(self.take())
+    }
+
+    fn take(&mut self) -> String {
+        std::mem::take(&mut self.lines).join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `lines` fed in order: what each returned, and whether an entry was still pending after it.
+    fn feed_all(asm: &mut LineAssembler, lines: &[&str]) -> Vec<(Feed, bool)> {
+        lines.iter().map(|l| (asm.feed(l), asm.is_pending())).collect()
+    }
+
+    fn done(text: &str) -> (Feed, bool) {
+        (Feed::Complete(text.to_string()), false)
+    }
+
+    const MORE: (Feed, bool) = (Feed::Continue, true);
+
+    #[test]
+    fn a_plain_line_completes_at_once() {
+        let mut asm = LineAssembler::new();
+        assert!(!asm.is_pending());
+        assert_eq!(feed_all(&mut asm, &["hello", ""]), [done("hello"), done("")]);
+    }
+
+    #[test]
+    fn a_trailing_backslash_continues_until_a_line_without_one() {
+        let mut asm = LineAssembler::new();
+        let fed = feed_all(&mut asm, &["first \\", "second\\", "third", "next"]);
+        assert_eq!(fed, [MORE, MORE, done("first \nsecond\nthird"), done("next")]);
+
+        // An empty continued line is kept as an empty line.
+        assert_eq!(feed_all(&mut asm, &["a\\", "\\", "b"]), [MORE, MORE, done("a\n\nb")]);
+    }
+
+    #[test]
+    fn a_fence_holds_lines_verbatim_until_the_closing_fence() {
+        let mut asm = LineAssembler::new();
+        let fed = feed_all(&mut asm, &["\"\"\"", "fn main() {", "    x \\", "", "}", "  \"\"\"  "]);
+        assert_eq!(fed, [MORE, MORE, MORE, MORE, MORE, done("fn main() {\n    x \\\n\n}")]);
+
+        // An empty block is an empty entry.
+        assert_eq!(feed_all(&mut asm, &[FENCE, FENCE]), [MORE, done("")]);
+    }
+
+    #[test]
+    fn a_fence_after_a_continued_line_opens_a_block_inside_the_entry() {
+        let mut asm = LineAssembler::new();
+        let fed = feed_all(&mut asm, &["explain this:\\", FENCE, "let x = 1;", FENCE]);
+        assert_eq!(fed, [MORE, MORE, MORE, done("explain this:\nlet x = 1;")]);
+    }
+
+    #[test]
+    fn a_pasted_line_keeps_its_newlines_and_completes_once() {
+        let mut asm = LineAssembler::new();
+        let pasted = "Traceback (most recent call last):\n  File \"a.py\", line 1\nNameError: x";
+        assert_eq!(feed_all(&mut asm, &[pasted]), [done(pasted)]);
+        assert_eq!(feed_all(&mut asm, &["see:\\", pasted]), [MORE, done(&format!("see:\n{}", pasted))]);
+    }
+
+    #[test]
+    fn reset_drops_the_pending_entry() {
+        let mut asm = LineAssembler::new();
+        feed_all(&mut asm, &["half\\"]);
+        asm.reset();
+        assert!(!asm.is_pending());
+        assert_eq!(asm.feed("new"), Feed::Complete("new".to_string()));
+
+        feed_all(&mut asm, &[FENCE, "inside"]);
+        asm.reset();
+        assert!(!asm.is_pending());
+        // Out of the block again: a fence opens a new one rather than closing the old.
+        assert_eq!(feed_all(&mut asm, &[FENCE, "after", FENCE]), [MORE, MORE, done("after")]);
+    }
+}
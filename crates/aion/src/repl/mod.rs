@@ -0,0 +1,567 @@
+//! Interactive chat REPL (CLI mode).
+//!
+//! Notes:
+//! - Each request runs under a fresh `CancelToken`; Ctrl+C cancels it and returns
+//!   to the prompt. A second Ctrl+C within two seconds exits.
+//! - A cancelled exchange is dropped from the conversation so it isn't resent.
+//! - Input may span several lines (trailing `\`, `"""` fences, bracketed paste); see
+//!   `input::LineAssembler`. History (`crate::history`) stores each complete entry as one
+//!   item: ↑/↓ and Ctrl+R in the reader, `/history [n]` to list it, `!<n>` to send entry
+//!   n again (printed first, and kept as what it recalled).
+//! - Lines starting with `/` are commands (see `crate::commands`), run against the REPL
+//!   as their `SessionContext`. Changes made by commands apply to this session only
+//!   unless `--save` is given.
+//! - A prompt that fails (not one that is cancelled) is kept so `/retry` can resend it,
+//!   e.g. after starting `ollama serve`.
+//! - Reading input blocks the runtime thread. Nothing else runs between requests, so the
+//!   REPL does not need an async line reader.
+//! - `/regen` resends the last prompt in place of its reply, `/undo` drops the last
+//!   exchange (see `conversation`). If a regeneration fails or is cancelled, the old
+//!   reply stays.
+//! - Replies are wrapped to the terminal and, when taller than it, shown again in
+//!   `$PAGER` once complete (`ui::reply`).
+//! - A reply that stopped early says why (`StopReason::notice`). One cut off at the token
+//!   limit can be picked up with `/continue`, which adds the rest to that reply.
+//! - Completed exchanges are recorded in a `Session` saved after each reply; `/clear`
+//!   starts a new one. The title request (see `sessions::generate_title`) runs before
+//!   the next prompt, also under Ctrl+C, and its failures are not reported.
+//! - Ephemeral (see `persistence`): the session is neither saved nor titled, and the
+//!   prompt says so. The input history is then kept in memory only, as with `--dry-run`.
+//! - `/config` prints the session's config with where each value came from (see
+//!   `config::tree`). `/config set` changes a key for the session; `/config save` writes
+//!   the keys set that way, and only those, to the config file.
+//! - `/context` shows the project context sent with each message (see `persona`),
+//!   `/context reload` reads the file again, `/context off` stops sending it.
+//! - `/provider <kind>` keeps the model, endpoint, and key variable of a provider of that
+//!   kind the session knows (a fallback, the one it started with) and asks inline only for
+//!   what the kind still needs (`fields::missing`), until the key variable is set. After
+//!   answers were typed, and without `--save`, it offers to save them.
+//! - `/suggest --with-history` asks before sending history that looks like it holds a
+//!   secret (`secrets`).
+
+mod input;
+mod reader;
+
+use crate::audit;
+use crate::costs;
+use crate::commands::{self, CommandState, CompletionData, Override, SessionContext};
+use crate::notify;
+use crate::cancel;
+use crate::config::io::{config_file_path, save_config};
+use crate::config::tree::{self, Node, Source};
+use crate::config::fields::{self, Check, Required};
+use crate::config::{AppConfig, ProviderConfig, ProviderKind};
+use crate::conversation::{Conversation, Exchange, CONTINUE_PROMPT};
+use crate::detect;
+use crate::dry_run;
+use crate::history::InputHistory;
+use crate::persistence;
+use crate::read_only;
+use crate::providers::{self, ChatClient, ChatResponse, LazyClient, ProviderError, StopReason};
+use crate::persona;
+use crate::recent::RecentModels;
+use crate::redact;
+use crate::secrets::Findings;
+use crate::ui::errors;
+use crate::ui::reply::ReplyPrinter;
+use crate::sessions::{self, Session};
+use crate::suggest::{self, Suggestion};
+use crate::templates::TemplateStore;
+use crate::update;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use input::{Feed, LineAssembler};
+use reader::{LineReader, ReadResult};
+use std::sync::Arc;
+
+const PROMPT: &str = "AION > ";
+const EPHEMERAL_PROMPT: &str = "AION (ephemeral) > ";
+const READ_ONLY_PROMPT: &str = "AION (read-only) > ";
+const CONTINUATION_PROMPT: &str = "... > ";
+
+pub struct Repl {
+    cfg: AppConfig,
+    client: LazyClient,
+    conversation: Conversation,
+    /// What was typed, for ↑/↓, Ctrl+R, `/history`, and `!<n>`.
+    history: InputHistory,
+    /// Installed locale codes, template names, and recent models, for Tab completion.
+    completion: CompletionData,
+    session: Session,
+    state: CommandState,
+    reader: LineReader,
+    /// `/quit` was run.
+    quit: bool,
+}
+
+impl Repl {
+    /// A new session, or the `resumed` one, continuing its conversation.
+    pub fn new(cfg: &AppConfig, resumed: Option<Session>) -> Result<Self> {
+        let completion = CompletionData {
+            locales: crate::i18n::LocaleManager::load()
+                .map(|m| m.available_locales())
+                .unwrap_or_default(),
+            templates: TemplateStore::load().map(|s| s.names()).unwrap_or_default(),
+            models: RecentModels::load().models(&cfg.provider.kind).to_vec(),
+        };
+        if let Some(session) = &resumed {
+            println!("Resumed session {} ({} exchange(s))", session.id, session.conversation().len());
+        }
+        let session = resumed.unwrap_or_default();
+        audit::set_session(&session.id);
+        costs::set_session(&session.id);
+        Ok(Self {
+            cfg: cfg.clone(),
+            client: LazyClient::new(cfg),
+            conversation: session.conversation(),
+            history: InputHistory::load(cfg.history.max_entries),
+            completion,
+            session,
+            state: CommandState::new(cfg),
+            reader: LineReader::new(),
+            quit: false,
+        })
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        cancel::install_ctrlc_handler().context("failed to install Ctrl+C handler")?;
+
+        if let Some(tip) = detect::local_tip(&self.cfg).await {
+            println!("{}", tip);
+        }
+        loop {
+            if let Some(notice) = update::take_notice(&self.cfg.language) {
+                println!("{}", notice);
+            }
+            let Some(entry) = self.read_entry()? else {
+                break;
+            };
+            let entry = match self.history.recall(&entry) {
+                Ok(Some(recalled)) => {
+                    println!("{}", recalled);
+                    recalled.to_string()
+                }
+                Ok(None) => entry,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+            let input = entry.trim();
+            if input.is_empty() {
+                continue;
+            }
+            self.remember(&entry);
+
+            if commands::is_command(input) {
+                commands::run(input, self).await;
+            } else {
+                self.send(input.to_string()).await;
+            }
+            if self.quit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read one complete (possibly multi-line) entry. `None` on end of input.
+    fn read_entry(&mut self) -> Result<Option<String>> {
+        let data = self.completion.clone();
+        let complete = move |line: &str| commands::registry().complete(line, &data);
+
+        let mut assembler = LineAssembler::new();
+        loop {
+            let prompt = match assembler.is_pending() {
+                true => CONTINUATION_PROMPT,
+                false if read_only::active().is_some() => READ_ONLY_PROMPT,
+                false if persistence::policy().ephemeral => EPHEMERAL_PROMPT,
+                false => PROMPT,
+            };
+            match self.reader.read_line(prompt, &complete, Some(&self.history))? {
+                ReadResult::Line(line) => {
+                    if let Feed::Complete(entry) = assembler.feed(&line) {
+                        return Ok(Some(entry));
+                    }
+                }
+                ReadResult::Interrupted if assembler.is_pending() => assembler.reset(),
+                ReadResult::Interrupted => cancel::idle_interrupt(),
+                ReadResult::Eof => return Ok(None),
+            }
+        }
+    }
+
+    /// `/provider <kind>`: ask for what the kind needs and the config lacks, then switch.
+    fn switch_provider(&mut self, kind: ProviderKind, save: bool) -> Result<()> {
+        let mut draft = self.draft_for(&kind);
+        let mut asked = false;
+        for field in fields::missing(&draft) {
+            asked = true;
+            if !ask_field(&mut self.reader, &mut draft, field)? {
+                println!("Provider unchanged.");
+                return Ok(());
+            }
+        }
+        while let Err(e) = providers::resolve_api_key(&draft) {
+            eprintln!("{}", e);
+            asked = true;
+            if !ask_field(&mut self.reader, &mut draft, Required::ApiKeyEnv)? {
+                println!("Provider unchanged.");
+                return Ok(());
+            }
+        }
+        let save = save || (asked && confirm(&mut self.reader, "Save this provider to the config? [y/N] ")?);
+        let mut next = self.cfg.clone();
+        next.provider = draft;
+        self.apply_config(next, save);
+        Ok(())
+    }
+
+    fn show_config(&self) {
+        let keys: Vec<&str> = self.state.edits.keys().collect();
+        let nodes = match tree::current(&self.cfg, &keys) {
+            Ok(nodes) => nodes,
+            Err(e) => return eprintln!("Error: {}", errors::render(&e, &self.cfg)),
+        };
+        if let Ok(path) = config_file_path() {
+            println!("# {}", path.display());
+        }
+        if !keys.is_empty() {
+            println!("# {} unsaved: /config save writes them", keys.len());
+        }
+        for node in &nodes {
+            print_node(node, 0);
+        }
+    }
+
+    /// The client for a request and the config it goes with: `with`, or the session's.
+    /// `None`, with the error shown, when the session's client cannot be built.
+    fn request_client(&mut self, with: Option<Override>) -> (Option<Arc<dyn ChatClient>>, AppConfig) {
+        if let Some((client, cfg)) = with {
+            return (Some(client), cfg);
+        }
+        match self.client.get() {
+            Ok(client) => (Some(client), self.cfg.clone()),
+            Err(e) => {
+                self.fail(&e.context("failed to create provider client"));
+                (None, self.cfg.clone())
+            }
+        }
+    }
+
+    async fn record(&mut self, outcome: Outcome, prompt: String, provider: &ProviderConfig) {
+        self.state.last_failed = match outcome {
+            Outcome::Failed => Some(prompt),
+            Outcome::Cancelled => None,
+            Outcome::Done(resp) => {
+                self.state.truncated = resp.stop == Some(StopReason::MaxTokens);
+                let (kind, model) = resp.answered_by(provider);
+                self.session.record(&prompt, &resp.content, kind, model, resp.usage);
+                self.conversation.push(prompt, resp.content);
+                if self.persist() {
+                    self.add_title().await;
+                }
+                None
+            }
+        };
+    }
+
+    /// Add `entry` to the input history, and to its file unless this is a dry run.
+    fn remember(&mut self, entry: &str) {
+        if self.history.push(entry) && !dry_run::enabled() {
+            // History is a convenience: a file that cannot be written is not worth a warning.
+            let _ = self.history.save();
+        }
+    }
+
+    /// Write the session file (or, with `--dry-run`, say where it would go). `true` when
+    /// it was written; never when ephemeral.
+    fn persist(&self) -> bool {
+        if !persistence::policy().keeps_history() {
+            return false;
+        }
+        if dry_run::enabled() {
+            if let Ok(path) = self.session.path() {
+                println!("{}", dry_run::note_write(&path));
+            }
+            return false;
+        }
+        match self.session.save() {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Warning: session not saved: {:#}", e);
+                false
+            }
+        }
+    }
+
+    /// Give the saved session a title once it has enough exchanges. A failed request
+    /// leaves it untitled until the next exchange.
+    async fn add_title(&mut self) {
+        if !self.session.wants_title(&self.cfg) {
+            return;
+        }
+        let Ok(client) = self.client.get() else { return };
+        let token = cancel::begin();
+        let title = sessions::generate_title(client.as_ref(), &self.session, &token).await;
+        cancel::end();
+        if let Ok(title) = title {
+            self.session.title = Some(title);
+            if let Err(e) = self.session.save() {
+                eprintln!("Warning: session not saved: {:#}", e);
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionContext for Repl {
+    fn cfg(&self) -> &AppConfig {
+        &self.cfg
+    }
+
+    fn cfg_mut(&mut self) -> &mut AppConfig {
+        &mut self.cfg
+    }
+
+    fn state(&self) -> &CommandState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut CommandState {
+        &mut self.state
+    }
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn history(&self) -> &InputHistory {
+        &self.history
+    }
+
+    fn conversation_mut(&mut self) -> &mut Conversation {
+        &mut self.conversation
+    }
+
+    fn notice(&mut self, text: String) {
+        println!("{}", text);
+    }
+
+    fn error(&mut self, text: String) {
+        eprintln!("{}", text);
+    }
+
+    fn fail(&mut self, e: &anyhow::Error) {
+        eprintln!("Error: {}", errors::render(e, &self.cfg));
+    }
+
+    fn install(&mut self, next: AppConfig) {
+        self.client = LazyClient::with_params(&next, &self.state.params);
+        self.completion.models = RecentModels::load().models(&next.provider.kind).to_vec();
+        self.cfg = next;
+    }
+
+    fn write_config(&mut self, cfg: AppConfig, done: String) {
+        match save_config(&cfg) {
+            Ok(()) => println!("{}", done),
+            Err(e) => self.fail(&e),
+        }
+    }
+
+    fn switch_provider(&mut self, kind: ProviderKind, save: bool) {
+        if let Err(e) = Repl::switch_provider(self, kind, save) {
+            self.fail(&e);
+        }
+    }
+
+    fn show_config(&mut self) {
+        Repl::show_config(self)
+    }
+
+    fn drop_last(&mut self) -> bool {
+        if self.conversation.pop().is_none() {
+            return false;
+        }
+        self.session.undo();
+        self.state.truncated = false;
+        self.persist();
+        true
+    }
+
+    fn clear(&mut self) {
+        self.conversation.clear();
+        self.state.truncated = false;
+        self.session = Session::new();
+        audit::set_session(&self.session.id);
+        costs::set_session(&self.session.id);
+    }
+
+    fn read_clipboard(&mut self) {
+        println!("/paste is for the full-screen chat (ui_mode = \"tui\"); paste with your terminal here.")
+    }
+
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    async fn send_with(&mut self, prompt: String, with: Option<Override>) {
+        let (client, cfg) = self.request_client(with);
+        let outcome = match client {
+            Some(client) => exchange(client.as_ref(), &cfg, &self.conversation, &prompt).await,
+            None => Outcome::Failed,
+        };
+        self.record(outcome, prompt, &cfg.provider).await;
+    }
+
+    async fn send_suggestion(&mut self, suggestion: Suggestion) -> Result<()> {
+        if !confirm_secrets(&suggestion.secrets, &mut self.reader)? {
+            println!("Not sent.");
+            return Ok(());
+        }
+        self.send_template(suggest::TEMPLATE, suggestion.vars).await
+    }
+
+    async fn resend(&mut self, last: Exchange, with: Option<Override>) {
+        let (client, cfg) = self.request_client(with);
+        let outcome = match client {
+            Some(client) => exchange(client.as_ref(), &cfg, &self.conversation, &last.prompt).await,
+            None => Outcome::Failed,
+        };
+        match outcome {
+            Outcome::Done(resp) => {
+                self.state.truncated = resp.stop == Some(StopReason::MaxTokens);
+                let (kind, model) = resp.answered_by(&cfg.provider);
+                self.session.regenerate(&resp.content, kind, model, resp.usage);
+                self.conversation.push(last.prompt, resp.content);
+                self.persist();
+            }
+            Outcome::Failed | Outcome::Cancelled => self.conversation.push_exchange(last),
+        }
+    }
+
+    /// Failing or cancelled, the reply stays as it was and `/continue` can be tried again.
+    async fn continue_reply(&mut self) {
+        let (client, cfg) = self.request_client(None);
+        let Some(client) = client else { return };
+        if let Outcome::Done(resp) = exchange(client.as_ref(), &cfg, &self.conversation, CONTINUE_PROMPT).await {
+            self.state.truncated = resp.stop == Some(StopReason::MaxTokens);
+            self.session.extend_reply(&resp.content, resp.usage);
+            self.conversation.extend_last(&resp.content);
+            self.persist();
+        }
+    }
+}
+
+/// `/config` lines for `node` and what is under it; values are redacted.
+/// Ask for `field` of `draft` until it passes its check, offering its value or the kind's
+/// default. `false` when cancelled (Ctrl+C, Ctrl+D).
+fn ask_field(reader: &mut LineReader, draft: &mut ProviderConfig, field: Required) -> Result<bool> {
+    let default = field.value(draft).filter(|v| !v.trim().is_empty()).or(field.default_for(&draft.kind));
+    let default = default.unwrap_or_default().to_string();
+    let prompt = format!("{} [{}]: ", field.label(), default);
+    loop {
+        let value = match reader.read_line(&prompt, &|_| Vec::new(), None)? {
+            ReadResult::Line(line) if line.trim().is_empty() => default.clone(),
+            ReadResult::Line(line) => line,
+            ReadResult::Interrupted | ReadResult::Eof => return Ok(false),
+        };
+        // Warnings pass: an unset key variable is reported by the caller's own check.
+        match field.check(&draft.kind, &value) {
+            check @ Check::Error(_) => eprintln!("{}", check.message()),
+            _ => {
+                field.set(draft, &value);
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// When `findings` has any, list them and ask whether to send anyway.
+fn confirm_secrets(findings: &Findings, reader: &mut LineReader) -> Result<bool> {
+    if findings.is_empty() {
+        return Ok(true);
+    }
+    eprintln!("Possible secrets in what is about to be sent:");
+    for line in findings.report() {
+        eprintln!("  {}", line);
+    }
+    confirm(reader, "Send anyway? [y/N] ")
+}
+
+/// A y/N question; anything but y/yes (or a cancel) is no.
+fn confirm(reader: &mut LineReader, prompt: &str) -> Result<bool> {
+    Ok(match reader.read_line(prompt, &|_| Vec::new(), None)? {
+        ReadResult::Line(answer) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        _ => false,
+    })
+}
+
+fn print_node(node: &Node, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match (&node.value, &node.source) {
+        (Some(value), Some(source)) => {
+            let tag = match source {
+                Source::File => String::new(),
+                other => format!("  [{}]", redact::redact(&other.to_string())),
+            };
+            println!("{}{} = {}{}", indent, node.name, redact::redact(value), tag);
+        }
+        _ => {
+            println!("{}[{}]", indent, node.path);
+            for child in &node.children {
+                print_node(child, depth + 1);
+            }
+        }
+    }
+}
+
+/// How an exchange ended.
+enum Outcome {
+    Done(ChatResponse),
+    Cancelled,
+    Failed,
+}
+
+/// Send `input` after `conversation` and stream the reply. The caller adds a completed
+/// exchange to the conversation; a failed or cancelled one is simply not added.
+async fn exchange(client: &dyn ChatClient, cfg: &AppConfig, conversation: &Conversation, input: &str) -> Outcome {
+    let messages = persona::compose(cfg, &conversation.with_prompt(input, cfg.sessions.max_context_tokens));
+
+    let token = cancel::begin();
+    let started = notify::start();
+    let mut printer = ReplyPrinter::new(&cfg.ui);
+    let result = client.chat(&messages, &token, &mut |chunk| printer.chunk(chunk)).await;
+    cancel::end();
+    printer.finish();
+    if !token.is_cancelled() {
+        notify::finish(started, notify::Task::Reply { model: &cfg.provider.model });
+    }
+
+    let outcome = match result {
+        Ok(resp) => {
+            printer.page();
+            if let Some(fallback) = &resp.fallback {
+                println!("{}", redact::redact(&fallback.notice()));
+            }
+            if let Some(stop) = resp.stop {
+                println!("{}", stop.notice(&cfg.language));
+            }
+            for warning in costs::take_warnings() {
+                println!("{}", warning);
+            }
+            Outcome::Done(resp)
+        }
+        Err(e) => {
+            if matches!(ProviderError::find(&e), Some(ProviderError::Cancelled)) {
+                println!("{}", errors::render(&e, cfg));
+                Outcome::Cancelled
+            } else {
+                eprintln!("Error: {}", errors::render(&e, cfg));
+                Outcome::Failed
+            }
+        }
+    };
+    println!();
+    outcome
+}
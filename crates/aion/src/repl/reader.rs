@@ -0,0 +1,384 @@
+//! Raw-mode line reader for the REPL.
+//!
+//! Notes:
+//! - Raw mode is only held while reading, so replies stream in cooked mode and Ctrl+C
+//!   during a request is a real SIGINT handled by `cancel`.
+//! - Bracketed paste is enabled so pasted newlines stay literal in the line buffer.
+//! - The whole buffer is redrawn on every change; pasted text can span several rows.
+//! - Tab asks the caller's completer for full-line candidates.
+//! - ←/→, Home/End, and Delete move and edit by grapheme cluster (`crate::ui::input`);
+//!   the terminal cursor is placed by display width, so CJK and emoji line up.
+//! - Given a history (`crate::history`), ↑ on the first row and ↓ on the last walk it,
+//!   and Ctrl+R searches it backwards as you type, the match shown in reverse video.
+//!   Ctrl+R again finds an older match; Enter sends the entry, ←/→ (or Home/End, ↑/↓)
+//!   keep it for editing, Esc, Ctrl+G, or Ctrl+C go back to what was typed before.
+
+use anyhow::Result;
+use crossterm::{
+    cursor::{MoveToColumn, MoveUp},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Print, Stylize},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+use crate::history::{Found, InputHistory, Walk};
+use crate::ui::input::TextInput;
+use crate::ui::width::width;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadResult {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnableBracketedPaste)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), DisableBracketedPaste);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// A Ctrl+R search in progress.
+struct Search {
+    query: String,
+    found: Option<Found>,
+    /// The last Ctrl+R found nothing older.
+    failed: bool,
+    /// What was typed before the search, for cancelling it.
+    typed: String,
+}
+
+/// What a key does to a search.
+enum SearchStep {
+    Searching,
+    /// Keep the match (or what was typed) in the buffer and go on editing.
+    Done,
+    /// Keep the match and send it.
+    Send,
+}
+
+#[derive(Default)]
+pub struct LineReader {
+    buffer: TextInput,
+    /// Terminal rows occupied by the last render, so the next one can clear them.
+    rows_drawn: u16,
+    /// Row of the last render the terminal cursor was left on.
+    cursor_row: u16,
+}
+
+impl LineReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read one line. `history` enables ↑/↓ and Ctrl+R; the REPL's own questions pass `None`.
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        complete: &dyn Fn(&str) -> Vec<String>,
+        history: Option<&InputHistory>,
+    ) -> Result<ReadResult> {
+        let _guard = RawModeGuard::enter()?;
+        self.buffer.clear();
+        self.rows_drawn = 0;
+        self.cursor_row = 0;
+        self.render(prompt)?;
+        let mut walk = Walk::default();
+        let mut search: Option<Search> = None;
+
+        loop {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    if let (Some(history), Some(active)) = (history, search.as_mut()) {
+                        let step = search_key(active, history, key);
+                        if let SearchStep::Searching = step {
+                            self.render_search(active, history)?;
+                            continue;
+                        }
+                        self.buffer.set(match active.found {
+                            Some(found) => history.entries()[found.index].clone(),
+                            None => std::mem::take(&mut active.typed),
+                        });
+                        search = None;
+                        walk.reset();
+                        if let SearchStep::Send = step {
+                            self.finish_line(prompt, "")?;
+                            return Ok(ReadResult::Line(self.buffer.take()));
+                        }
+                        self.render(prompt)?;
+                        continue;
+                    }
+                    match key.code {
+                        KeyCode::Char('r') if ctrl => {
+                            if let Some(history) = history {
+                                let typed = self.buffer.as_str().to_string();
+                                let active = search.insert(Search { query: String::new(), found: None, failed: false, typed });
+                                self.render_search(active, history)?;
+                            }
+                            continue;
+                        }
+                        KeyCode::Up if self.buffer.cursor_row() == 0 => {
+                            match history.and_then(|h| walk.older(h, self.buffer.as_str())) {
+                                Some(entry) => self.buffer.set(entry),
+                                None => continue,
+                            }
+                        }
+                        KeyCode::Down if self.buffer.on_last_row() => {
+                            match history.and_then(|h| walk.newer(h)) {
+                                Some(entry) => self.buffer.set(entry),
+                                None => continue,
+                            }
+                        }
+                        KeyCode::Char('c') if ctrl => {
+                            self.finish_line(prompt, "^C")?;
+                            return Ok(ReadResult::Interrupted);
+                        }
+                        KeyCode::Char('d') if ctrl && self.buffer.is_empty() => {
+                            self.finish_line(prompt, "")?;
+                            return Ok(ReadResult::Eof);
+                        }
+                        KeyCode::Char('u') if ctrl => self.buffer.clear(),
+                        KeyCode::Enter => {
+                            self.finish_line(prompt, "")?;
+                            return Ok(ReadResult::Line(self.buffer.take()));
+                        }
+                        KeyCode::Backspace => {
+                            self.buffer.backspace();
+                        }
+                        KeyCode::Delete => {
+                            self.buffer.delete();
+                        }
+                        KeyCode::Left => {
+                            self.buffer.left();
+                        }
+                        KeyCode::Right => {
+                            self.buffer.right();
+                        }
+                        KeyCode::Home => self.buffer.home(),
+                        KeyCode::End => self.buffer.end(),
+                        KeyCode::Tab => self.complete(prompt, complete(self.buffer.as_str()))?,
+                        KeyCode::Char(c) if !ctrl => self.buffer.insert(c),
+                        _ => continue,
+                    }
+                }
+                Event::Paste(text) => match (history, search.as_mut()) {
+                    (Some(history), Some(active)) => {
+                        active.query.push_str(&text.replace(['\r', '\n'], " "));
+                        find(active, history);
+                    }
+                    _ => self.buffer.insert_str(&text.replace("\r\n", "\n").replace('\r', "\n")),
+                },
+                Event::Resize(_, _) => {}
+                _ => continue,
+            }
+            match (history, &search) {
+                (Some(history), Some(active)) => self.render_search(active, history)?,
+                _ => self.render(prompt)?,
+            }
+        }
+    }
+
+    /// The search line in place of the prompt: the query, and the match in the entry
+    /// found, on one row.
+    fn render_search(&mut self, active: &Search, history: &InputHistory) -> Result<()> {
+        let mut out = io::stdout();
+        if self.cursor_row > 0 {
+            queue!(out, MoveUp(self.cursor_row))?;
+        }
+        queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+
+        let label = format!("({}reverse-i-search)'{}': ", if active.failed { "failing " } else { "" }, active.query);
+        let (entry, at) = match active.found {
+            Some(found) => (history.entries()[found.index].as_str(), found.at),
+            None => ("", 0),
+        };
+        let (before, rest) = entry.split_at(at);
+        let (matched, after) = rest.split_at(if rest.starts_with(&active.query) { active.query.len() } else { 0 });
+        let cols = match terminal::size() {
+            Ok((w, _)) if w > 0 => w as usize,
+            _ => 80,
+        };
+        let mut room = cols - 1;
+        queue!(out, Print(clip(&label, &mut room)), Print(clip(before, &mut room)))?;
+        queue!(out, Print(clip(matched, &mut room).reverse()), Print(clip(after, &mut room)))?;
+
+        self.rows_drawn = 1;
+        self.cursor_row = 0;
+        queue!(out, MoveToColumn(width(&label).saturating_sub(3).min(cols - 1) as u16))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn render(&mut self, prompt: &str) -> Result<()> {
+        let mut out = io::stdout();
+        if self.cursor_row > 0 {
+            queue!(out, MoveUp(self.cursor_row))?;
+        }
+        queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+
+        let cols = match terminal::size() {
+            Ok((w, _)) if w > 0 => w as usize,
+            _ => 80,
+        };
+        let (cursor_line, cursor_column) = (self.buffer.cursor_row(), self.buffer.cursor_column());
+        let mut rows = 0usize;
+        let mut cursor = (0, 0);
+        for (i, line) in self.buffer.as_str().split('\n').enumerate() {
+            let lead = if i == 0 { prompt } else { "" };
+            if i > 0 {
+                queue!(out, crossterm::style::Print("\r\n"))?;
+            }
+            queue!(out, crossterm::style::Print(lead), crossterm::style::Print(line))?;
+            let line_rows = (width(lead) + width(line)).max(1).div_ceil(cols);
+            if i == cursor_line {
+                // At the very end of a full row the terminal has not wrapped yet.
+                let column = width(lead) + cursor_column;
+                cursor = match column / cols < line_rows {
+                    true => (rows + column / cols, column % cols),
+                    false => (rows + line_rows - 1, cols - 1),
+                };
+            }
+            rows += line_rows;
+        }
+
+        self.rows_drawn = rows.max(1) as u16;
+        self.cursor_row = cursor.0 as u16;
+        let up = self.rows_drawn - 1 - self.cursor_row;
+        if up > 0 {
+            queue!(out, MoveUp(up))?;
+        }
+        queue!(out, MoveToColumn(cursor.1 as u16))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Put the cursor after the text, where output can continue below it.
+    fn move_to_end(&mut self, prompt: &str) -> Result<()> {
+        self.buffer.end_of_text();
+        self.render(prompt)
+    }
+
+    fn complete(&mut self, prompt: &str, candidates: Vec<String>) -> Result<()> {
+        match candidates.as_slice() {
+            [] => {}
+            [only] => self.buffer.set(only.clone()),
+            many => {
+                let prefix = common_prefix(many);
+                if prefix.len() > self.buffer.as_str().len() {
+                    self.buffer.set(prefix);
+                } else {
+                    // Show the choices below the input, then redraw the prompt fresh.
+                    self.move_to_end(prompt)?;
+                    let mut out = io::stdout();
+                    write!(out, "\r\n{}\r\n", many.join("  "))?;
+                    self.rows_drawn = 0;
+                    self.cursor_row = 0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish_line(&mut self, prompt: &str, suffix: &str) -> Result<()> {
+        self.move_to_end(prompt)?;
+        let mut out = io::stdout();
+        write!(out, "{}\r\n", suffix)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Apply `key` to the search `active`; see the module notes.
+fn search_key(active: &mut Search, history: &InputHistory, key: KeyEvent) -> SearchStep {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('r') if ctrl => match history.search(&active.query, active.found.map(|f| f.index)) {
+            Some(found) => active.found = Some(found),
+            None => active.failed = !active.query.is_empty(),
+        },
+        KeyCode::Char('c' | 'g') if ctrl => {
+            active.found = None;
+            return SearchStep::Done;
+        }
+        KeyCode::Esc => {
+            active.found = None;
+            return SearchStep::Done;
+        }
+        KeyCode::Enter => return SearchStep::Send,
+        KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Up | KeyCode::Down => {
+            return SearchStep::Done
+        }
+        KeyCode::Backspace => {
+            active.query.pop();
+            find(active, history);
+        }
+        KeyCode::Char(c) if !ctrl => {
+            active.query.push(c);
+            find(active, history);
+        }
+        _ => {}
+    }
+    SearchStep::Searching
+}
+
+/// Search again from the newest entry, after the query changed. With nothing found the
+/// last match stays on screen, as failing.
+fn find(active: &mut Search, history: &InputHistory) {
+    match history.search(&active.query, None) {
+        Some(found) => {
+            active.found = Some(found);
+            active.failed = false;
+        }
+        None if active.query.is_empty() => {
+            active.found = None;
+            active.failed = false;
+        }
+        None => active.failed = true,
+    }
+}
+
+/// As much of `text` as fits in `room` columns, on one row; `room` is what is left after.
+fn clip(text: &str, room: &mut usize) -> String {
+    let mut out = String::new();
+    for c in text.chars().map(|c| if c == '\n' { ' ' } else { c }) {
+        let w = width(c.encode_utf8(&mut [0; 4]));
+        if w > *room {
+            *room = 0;
+            break;
+        }
+        *room -= w;
+        out.push(c);
+    }
+    out
+}
+
+fn common_prefix(items: &[String]) -> String {
+    let Some(first) = items.first() else {
+        return String::new();
+    };
+    let mut end = first.len();
+    for item in &items[1..] {
+        end = first
+            .char_indices()
+            .zip(item.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(end);
+    }
+    first[..end].to_string()
+}
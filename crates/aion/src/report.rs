@@ -0,0 +1,211 @@
+//! `aion report`: one bundle with what is needed to look into a problem.
+//!
+//! Notes:
+//! - Collected: config.toml as it is on disk, the doctor results, version and platform,
+//!   the end of http.log, the installed locales with the file each is read from, and with
+//!   `--include-session` the most recently updated session.
+//! - Every file goes through the redactor, even with `logging.redaction = false`: the
+//!   value of `provider.api_key_env` and anything that looks like a key, token, or URL
+//!   password is masked. Nothing else is collected (no environment dump).
+//! - A config that does not validate is still read for its `caps` and key variable, so
+//!   a broken setup can be reported.
+//! - Written as a zip, or a directory with `--no-zip`; in the current directory unless
+//!   `--out` says where. Needs `caps.write_files` and passes the path lists
+//!   (`crate::guard`). An existing file or directory is never replaced.
+
+use crate::audit;
+use crate::cli::ReportArgs;
+use crate::config::io::{config_file_path, load_config, load_raw_config};
+use crate::config::AppConfig;
+use crate::doctor;
+use crate::dry_run;
+use crate::guard::Guard;
+use crate::http_log;
+use crate::i18n::format::tilde;
+use crate::i18n::LocaleManager;
+use crate::redact::Redactor;
+use crate::sessions;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub const DEFAULT_LOG_LINES: usize = 200;
+
+/// One file of the bundle.
+struct Entry {
+    name: &'static str,
+    /// What it holds, for the summary printed at the end.
+    about: String,
+    text: String,
+}
+
+pub async fn run(args: &ReportArgs, strict: bool) -> Result<ExitCode> {
+    let cfg = report_config();
+    let mut redaction = cfg.clone();
+    redaction.logging.redaction = true;
+    let redactor = Redactor::from_config(&redaction);
+
+    let stamp = sessions::format_utc(now()).replace(['-', ':'], "");
+    let default_name = match args.no_zip {
+        true => format!("aion-report-{}", stamp),
+        false => format!("aion-report-{}.zip", stamp),
+    };
+    let target = args.out.clone().unwrap_or_else(|| PathBuf::from(default_name));
+    let target = Guard::new(&cfg.caps).check_write(&target).context("cannot write the report")?;
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+
+    let mut entries = collect(args, strict).await;
+    for entry in &mut entries {
+        entry.text = redactor.redact(&entry.text);
+    }
+
+    if dry_run::enabled() {
+        println!("{}", dry_run::note_write(&target));
+    } else if args.no_zip {
+        write_dir(&target, &entries)?;
+        audit::file_write(&target);
+    } else {
+        write_zip(&target, &entries)?;
+        audit::file_write(&target);
+    }
+
+    println!("Collected (secrets masked):");
+    for entry in &entries {
+        println!("  {:<14} {}", entry.name, entry.about);
+    }
+    if !args.include_session {
+        println!("  (no session; --include-session adds the latest one)");
+    }
+    if !dry_run::enabled() {
+        println!("Wrote {}", target.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The config for `caps` and the key variable: the valid one, else whatever parses.
+fn report_config() -> AppConfig {
+    load_config()
+        .ok()
+        .or_else(|| load_raw_config().ok().and_then(|t| toml::Value::Table(t).try_into().ok()))
+        .unwrap_or_else(AppConfig::new_default)
+}
+
+async fn collect(args: &ReportArgs, strict: bool) -> Vec<Entry> {
+    let mut entries =
+        vec![config_entry(), doctor_entry(strict).await, version_entry(), log_entry(args.log_lines), locales_entry()];
+    if args.include_session {
+        entries.push(session_entry());
+    }
+    entries
+}
+
+fn config_entry() -> Entry {
+    let (about, text) = match config_file_path() {
+        Ok(path) => match fs::read_to_string(&path) {
+            Ok(text) => (format!("config file {}", tilde(&path)), text),
+            Err(e) => (format!("no config ({})", e), format!("# {}: {}\n", path.display(), e)),
+        },
+        Err(e) => (format!("no config ({})", e), format!("# {}\n", e)),
+    };
+    Entry { name: "config.toml", about, text }
+}
+
+async fn doctor_entry(strict: bool) -> Entry {
+    let ctx = doctor::DoctorContext::from_env(strict).await;
+    let results = doctor::run_checks(&ctx);
+    let text = doctor::to_json(&results).unwrap_or_else(|e| format!("{{\"error\": {:?}}}", e.to_string()));
+    Entry { name: "doctor.json", about: format!("aion doctor --json ({} checks)", results.len()), text }
+}
+
+fn version_entry() -> Entry {
+    let text = format!(
+        "aion {}\nconfig version {}\nbuild {}\nos {} ({})\narch {}\nterm {}\n",
+        env!("CARGO_PKG_VERSION"),
+        AppConfig::CURRENT_VERSION,
+        if cfg!(debug_assertions) { "debug" } else { "release" },
+        std::env::consts::OS,
+        std::env::consts::FAMILY,
+        std::env::consts::ARCH,
+        std::env::var("TERM").unwrap_or_else(|_| "-".to_string()),
+    );
+    Entry { name: "version.txt", about: "AION version and build, OS, architecture, TERM".to_string(), text }
+}
+
+fn log_entry(lines: usize) -> Entry {
+    let path = http_log::http_log_file_path();
+    let text = path.as_ref().ok().and_then(|p| fs::read_to_string(p).ok());
+    let (about, text) = match (path, text) {
+        (Ok(path), Some(text)) => {
+            let all: Vec<&str> = text.lines().collect();
+            let tail = &all[all.len().saturating_sub(lines)..];
+            (format!("last {} of {} lines of {}", tail.len(), all.len(), tilde(&path)), tail.join("\n") + "\n")
+        }
+        _ => ("no HTTP log (aion --debug-http writes one)".to_string(), String::new()),
+    };
+    Entry { name: "http.log", about, text }
+}
+
+fn locales_entry() -> Entry {
+    let (about, text) = match LocaleManager::load() {
+        Ok(manager) => {
+            let codes = manager.available_locales();
+            let mut text = String::new();
+            for code in &codes {
+                let source = manager.source(code).map_or_else(|| "bundled".to_string(), |p| p.display().to_string());
+                let detail = match (manager.meta(code), manager.coverage(code)) {
+                    (Some(meta), Some(coverage)) => format!("{}, {}, {:.0}%", meta.name, meta.status, coverage * 100.0),
+                    _ => "unreadable".to_string(),
+                };
+                text.push_str(&format!("{}\t{}\t{}\n", code, detail, source));
+            }
+            (format!("{} locales and their files", codes.len()), text)
+        }
+        Err(e) => ("no locales".to_string(), format!("{:#}\n", e)),
+    };
+    Entry { name: "locales.txt", about, text }
+}
+
+fn session_entry() -> Entry {
+    let latest = sessions::index().and_then(|index| index.first().map(|e| sessions::load(&e.id)).transpose());
+    let (about, text) = match latest {
+        Ok(Some(session)) => match sessions::to_json(&session) {
+            Ok(json) => (format!("session {}", session.id), json),
+            Err(e) => (format!("session {} not readable", session.id), format!("{:#}\n", e)),
+        },
+        Ok(None) => ("no saved sessions".to_string(), String::new()),
+        Err(e) => ("sessions not readable".to_string(), format!("{:#}\n", e)),
+    };
+    Entry { name: "session.json", about, text }
+}
+
+fn write_zip(path: &Path, entries: &[Entry]) -> Result<()> {
+    let file = fs::File::create_new(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for entry in entries {
+        zip.start_file(entry.name, options)?;
+        zip.write_all(entry.text.as_bytes())?;
+    }
+    zip.finish().with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn write_dir(path: &Path, entries: &[Entry]) -> Result<()> {
+    fs::create_dir(path).with_context(|| format!("failed to create {}", path.display()))?;
+    for entry in entries {
+        let file = path.join(entry.name);
+        fs::write(&file, &entry.text).with_context(|| format!("failed to write {}", file.display()))?;
+    }
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
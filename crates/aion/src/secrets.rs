@@ -0,0 +1,104 @@
+//! The check before file contents, shell history, or command output go to a provider
+//! (`redact::SecretScanner`).
+//!
+//! Notes:
+//! - Run on `--file` attachments, `aion ask`'s file, `aion exec`'s output, files attached
+//!   with Ctrl+O, and `/suggest --with-history`. What the user types is not checked.
+//! - Lines that look like they hold a secret are listed with the secrets masked, and the
+//!   user is asked before anything is sent. In CLI mode without a terminal to ask on,
+//!   nothing is sent (`SecretsFound`); `--allow-secrets` sends without asking, for scripts.
+//! - `privacy.trust_local` skips the check when every provider the request may reach,
+//!   fallbacks included, is local (`ProviderConfig::is_local`).
+
+use crate::config::AppConfig;
+use crate::redact::{SecretHit, SecretScanner};
+use anyhow::{anyhow, Result};
+use crossterm::tty::IsTty;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Lines listed before the rest are only counted.
+const SHOWN: usize = 10;
+
+static ALLOWED: AtomicBool = AtomicBool::new(false);
+
+/// `--allow-secrets`: no check for the rest of the process.
+pub fn allow() {
+    ALLOWED.store(true, Ordering::Relaxed);
+}
+
+/// Secrets were found and sending them was not confirmed.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{lines} line(s) look like they contain secrets, so nothing was sent (remove them, or pass --allow-secrets)")]
+pub struct SecretsFound {
+    pub lines: usize,
+}
+
+/// What the check found, with where each line came from (a path, "the output").
+#[derive(Debug, Clone, Default)]
+pub struct Findings {
+    hits: Vec<(String, SecretHit)>,
+}
+
+impl Findings {
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.hits.len()
+    }
+
+    /// `source:line (kind): preview` for the first `SHOWN` lines, then how many more.
+    pub fn report(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .hits
+            .iter()
+            .take(SHOWN)
+            .map(|(source, hit)| format!("{}:{} ({}): {}", source, hit.line, hit.kind, hit.preview))
+            .collect();
+        if self.hits.len() > SHOWN {
+            lines.push(format!("... and {} more", self.hits.len() - SHOWN));
+        }
+        lines
+    }
+}
+
+/// The secrets in `texts` (source, text), or none when `cfg` or `--allow-secrets` say
+/// not to check.
+pub fn scan<'a>(cfg: &AppConfig, texts: impl IntoIterator<Item = (String, &'a str)>) -> Result<Findings> {
+    if ALLOWED.load(Ordering::Relaxed) || (cfg.privacy.trust_local && cfg.provider.chain().all(|p| p.is_local())) {
+        return Ok(Findings::default());
+    }
+    let scanner = SecretScanner::new(&cfg.privacy.patterns).map_err(|reason| anyhow!("privacy.patterns: {}", reason))?;
+    let hits = texts
+        .into_iter()
+        .flat_map(|(source, text)| scanner.scan(text).into_iter().map(move |hit| (source.clone(), hit)))
+        .collect();
+    Ok(Findings { hits })
+}
+
+/// `scan`, and on findings list them on stderr and ask on the terminal (CLI mode).
+/// `SecretsFound` when the answer is no, or when there is no terminal to ask on.
+pub fn confirm<'a>(cfg: &AppConfig, texts: impl IntoIterator<Item = (String, &'a str)>) -> Result<()> {
+    let findings = scan(cfg, texts)?;
+    if findings.is_empty() {
+        return Ok(());
+    }
+    eprintln!("Possible secrets in what is about to be sent:");
+    for line in findings.report() {
+        eprintln!("  {}", line);
+    }
+    let refused = SecretsFound { lines: findings.len() };
+    if !io::stdin().is_tty() {
+        return Err(refused.into());
+    }
+    eprint!("Send anyway? [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(refused.into()),
+    }
+}
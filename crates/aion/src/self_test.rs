@@ -0,0 +1,228 @@
+//! `aion self-test`: checks that the binary itself works, for packagers and CI.
+//!
+//! Notes:
+//! - Offline and non-interactive: no network, no terminal, no config needed. Everything
+//!   written goes to a temporary directory that is removed afterwards; the user's config,
+//!   state, and cache are not touched (not even by the move of old files at startup).
+//! - Unlike `aion doctor`, which checks this machine's setup, these check what was built:
+//!   the bundled locales, the default config, directory resolution, every provider's
+//!   defaults, the Markdown renderer, the text input, and the command line definition.
+//! - Adding a check means writing one function and listing it in `CHECKS`. A check
+//!   returns what it verified, or why it failed.
+//! - Results print like `aion doctor`'s (`--json` too); any failure exits non-zero.
+
+use crate::cli;
+use crate::config::{AppConfig, I18nConfig, ProviderKind};
+use crate::config::diff::config_changes;
+use crate::config::io::render_config;
+use crate::doctor::{self, CheckResult};
+use crate::i18n::{LocaleManager, BUNDLED_LOCALES};
+use crate::paths::{self, Platform, Roots};
+use crate::tui::markdown;
+use crate::ui::console::Console;
+use crate::ui::input::TextInput;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+/// A check, with a scratch directory of its own.
+type Check = fn(&Path) -> Result<String>;
+
+const CHECKS: &[(&str, Check)] = &[
+    ("bundled locales load", check_locales),
+    ("default config", check_default_config),
+    ("directories resolve", check_directories),
+    ("provider defaults", check_provider_defaults),
+    ("markdown renderer", check_markdown),
+    ("text input", check_text_input),
+    ("command line", check_command_line),
+];
+
+pub fn run(console: &Console, args: &cli::SelfTestArgs) -> Result<ExitCode> {
+    let results = run_checks();
+    if args.json || console.is_json() {
+        doctor::print_json(&results)?;
+    } else {
+        doctor::print_human("AION self-test", &results);
+    }
+    Ok(if doctor::has_failures(&results) { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+pub fn run_checks() -> Vec<CheckResult> {
+    let scratch = std::env::temp_dir().join(format!("aion-self-test-{}", std::process::id()));
+    let results = CHECKS
+        .iter()
+        .map(|(name, check)| {
+            let dir = scratch.join(name.replace(' ', "-"));
+            let result = fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))
+                .and_then(|_| check(&dir));
+            match result {
+                Ok(detail) => CheckResult::pass(name, detail),
+                Err(e) => CheckResult::fail(name, format!("{:#}", e)),
+            }
+        })
+        .collect();
+    let _ = fs::remove_dir_all(&scratch);
+    results
+}
+
+/* ---------------------------
+   Checks
+---------------------------- */
+
+/// The locale files built into the binary (`aion init --install-locales`) read back.
+fn check_locales(dir: &Path) -> Result<String> {
+    for (code, text) in BUNDLED_LOCALES {
+        fs::write(dir.join(format!("{}.toml", code)), text)?;
+    }
+    let manager = LocaleManager::load_from(&[dir.to_path_buf()], &I18nConfig::default())?;
+    for (code, _) in BUNDLED_LOCALES {
+        // Bundled locales stand in for missing files; these must come from `dir`.
+        ensure!(manager.source(code) == Some(dir.join(format!("{}.toml", code)).as_path()), "{}.toml was not read", code);
+        manager.preload(code)?;
+        let meta = manager.meta(code).ok_or_else(|| anyhow!("{}: no [meta]", code))?;
+        ensure!(meta.code == *code, "{}.toml says it is {}", code, meta.code);
+    }
+    ensure!(manager.t("en", "cli.help") != "cli.help", "en lacks cli.help");
+    let codes: Vec<&str> = BUNDLED_LOCALES.iter().map(|(code, _)| *code).collect();
+    Ok(codes.join(", "))
+}
+
+/// `AppConfig::new_default` validates, and survives being written and read back.
+fn check_default_config(_: &Path) -> Result<String> {
+    let cfg = AppConfig::new_default();
+    cfg.validate()?;
+    let text = render_config(&cfg)?;
+    let parsed: AppConfig = toml::from_str(&text).context("the written config does not parse")?;
+    parsed.validate().context("the written config does not validate")?;
+    if let Some(line) = config_changes(&cfg, &parsed)?.first() {
+        bail!("changed on the way back: {}", line);
+    }
+    Ok(format!("validates; {} lines of TOML round-trip", text.lines().count()))
+}
+
+/// Config, state, and cache resolve from the platform's directories and the variables
+/// (a temporary home here), and can be created.
+fn check_directories(dir: &Path) -> Result<String> {
+    let platform = Platform { config: Some(dir.join("config")), cache: Some(dir.join("cache")), ..Platform::default() };
+    let state = dir.join("state");
+    let roots = Roots::resolve(&platform, |name| (name == paths::STATE_DIR_VAR).then(|| state.clone().into_os_string()))?;
+    let expected = Roots { config: dir.join("config/aion"), state, cache: dir.join("cache/aion") };
+    ensure!(roots == expected, "resolved {:?}, expected {:?}", roots, expected);
+    for root in [&roots.config, &roots.state, &roots.cache] {
+        paths::ensure(root)?;
+    }
+    ensure!(Roots::resolve(&Platform::default(), |_| None).is_err(), "resolved without a config dir");
+    Ok("config, state, and cache".to_string())
+}
+
+/// Each provider's defaults (model, endpoint, key variable) pass `validate`.
+fn check_provider_defaults(_: &Path) -> Result<String> {
+    for kind in ProviderKind::ALL {
+        let mut cfg = AppConfig::new_default();
+        cfg.set_provider_kind(kind.clone());
+        cfg.validate().with_context(|| format!("{} defaults", kind))?;
+    }
+    let ids: Vec<&str> = ProviderKind::ALL.iter().map(|k| k.id()).collect();
+    Ok(ids.join(", "))
+}
+
+fn check_markdown(_: &Path) -> Result<String> {
+    let text = "# Title\n- item with `code` and **bold**\n```\nlet x = **1**;\n```\nplain *text*";
+    let lines: Vec<String> = markdown::render(text)
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+    let expected = ["Title", "• item with code and bold", "```", "let x = **1**;", "```", "plain *text*"];
+    ensure!(lines == expected, "rendered {:?}", lines);
+    // An unterminated fence styles the rest as code.
+    let open = markdown::render("```\n# not a heading");
+    ensure!(open.len() == 2 && open[1].spans[0].content == "# not a heading", "unterminated fence: {:?}", open);
+    Ok("headings, lists, code, bold, open fences".to_string())
+}
+
+fn check_text_input(_: &Path) -> Result<String> {
+    let mut input = TextInput::with_text("héllo");
+    ensure!(input.at_end() && input.cursor_column() == 5, "cursor not at the end");
+    input.left();
+    ensure!(input.backspace() && input.as_str() == "hélo", "backspace: {:?}", input.as_str());
+    input.insert('L');
+    input.home();
+    ensure!(input.at_start() && !input.backspace(), "home");
+    ensure!(input.delete() && input.as_str() == "élLo", "delete: {:?}", input.as_str());
+
+    // One cluster each: a ZWJ family, a flag, a letter with a combining mark.
+    for cluster in ["\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}", "\u{1F1F3}\u{1F1F4}", "e\u{301}"] {
+        let mut input = TextInput::with_text(format!("a{}", cluster));
+        ensure!(input.backspace() && input.as_str() == "a", "{:?} is not one character", cluster);
+    }
+    // Wide characters take two columns; rows start after a newline.
+    let mut input = TextInput::with_text("中文\nab");
+    ensure!(input.cursor_row() == 1 && input.cursor_column() == 2, "second row");
+    input.home();
+    input.left();
+    ensure!(input.cursor_row() == 0 && input.cursor_column() == 4, "wide column: {}", input.cursor_column());
+    Ok("editing, clusters, columns".to_string())
+}
+
+/// The command line definition passes clap's own consistency checks.
+fn check_command_line(_: &Path) -> Result<String> {
+    // clap reports a broken definition by panicking; keep the message for the result.
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let checked = std::panic::catch_unwind(|| {
+        let cmd = cli::command("en");
+        let count = subcommands(&cmd);
+        cmd.debug_assert();
+        count
+    });
+    std::panic::set_hook(hook);
+    match checked {
+        Ok(count) => Ok(format!("{} commands", count)),
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "clap rejected the definition".to_string());
+            bail!(message)
+        }
+    }
+}
+
+/// Commands under `cmd`, at every depth.
+fn subcommands(cmd: &clap::Command) -> usize {
+    cmd.get_subcommands().map(|sub| 1 + subcommands(sub)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doctor::CheckStatus;
+    use tempfile::TempDir;
+
+    #[test]
+    fn every_check_passes() {
+        let _hook = crate::tui::PANIC_HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for (name, check) in CHECKS {
+            let dir = TempDir::new().unwrap();
+            if let Err(e) = check(dir.path()) {
+                panic!("{}: {:#}", name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn run_checks_reports_each_check_in_order() {
+        let _hook = crate::tui::PANIC_HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let results = run_checks();
+        let names: Vec<&str> = results.iter().map(|r| r.name).collect();
+        let listed: Vec<&str> = CHECKS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, listed);
+        for result in &results {
+            assert!(matches!(result.status, CheckStatus::Pass), "{}: {}", result.name, result.detail);
+        }
+    }
+}
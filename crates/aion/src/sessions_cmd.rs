@@ -0,0 +1,127 @@
+//! `aion sessions`: list, search, prune, and export saved chats (`aion_core::sessions`).
+//!
+//! Notes:
+//! - `export --out` writes only where `caps` allow; with `--all` every target is checked
+//!   before anything is written.
+//! - `prune` without `--days` uses `history.retention_days`; 0 keeps everything.
+
+use crate::cli::SessionsAction;
+use crate::config::io::load_config;
+use crate::dry_run;
+use crate::ui::console::Console;
+use crate::{audit, guard, sessions};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+pub fn run(console: &Console, action: &SessionsAction) -> Result<ExitCode> {
+    match action {
+        SessionsAction::List => {
+            let all = sessions::index()?;
+            if console.is_json() {
+                console.print_json(&all)?;
+            } else {
+                for s in &all {
+                    println!("{}", session_row(s));
+                }
+            }
+        }
+        SessionsAction::Search(a) => {
+            let hits = sessions::search(&a.text.join(" "))?;
+            if console.is_json() {
+                let rows: Vec<_> = hits
+                    .iter()
+                    .map(|h| serde_json::json!({ "session": h.entry, "excerpt": h.excerpt }))
+                    .collect();
+                console.print_json(&rows)?;
+            } else if hits.is_empty() {
+                println!("No sessions match.");
+            } else {
+                for h in &hits {
+                    println!("{}", session_row(&h.entry));
+                    if let Some(excerpt) = &h.excerpt {
+                        println!("    {}", excerpt);
+                    }
+                }
+            }
+        }
+        SessionsAction::Prune(a) => {
+            let days = match a.days {
+                Some(days) => days,
+                None => load_config().context("failed to load config")?.history.retention_days,
+            };
+            if days == 0 {
+                anyhow::bail!("history.retention_days is 0, so sessions are kept (pass --days N to prune anyway)");
+            }
+            if dry_run::enabled() {
+                for path in sessions::expired(days)? {
+                    println!("{}", dry_run::note_remove(&path));
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+            let removed = sessions::prune(days)?;
+            if console.is_json() {
+                console.print_json(&serde_json::json!({ "removed": removed, "days": days }))?;
+            } else {
+                println!("Removed {} session(s) older than {} days", removed, days);
+            }
+        }
+        SessionsAction::Export(a) => {
+            let cfg = match &a.out {
+                Some(_) => Some(load_config().context("failed to load config")?),
+                None => None,
+            };
+            let guard = cfg.as_ref().map(|c| guard::Guard::new(&c.caps));
+
+            if a.all {
+                let (dir, guard) = a.out.as_deref().zip(guard.as_ref()).context("--all requires --out <dir>")?;
+                let all = sessions::list()?;
+                // Every target is checked before anything is written.
+                let targets = all
+                    .iter()
+                    .map(|s| export_target(guard, &dir.join(format!("{}.{}", s.id, a.format.extension()))))
+                    .collect::<Result<Vec<_>>>()?;
+                if dry_run::enabled() {
+                    for path in &targets {
+                        println!("{}", dry_run::note_write(path));
+                    }
+                    return Ok(ExitCode::SUCCESS);
+                }
+                std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+                for (s, path) in all.iter().zip(&targets) {
+                    std::fs::write(path, a.format.render(s)?)
+                        .with_context(|| format!("failed to write {}", path.display()))?;
+                    audit::file_write(path);
+                }
+                println!("Exported {} session(s) to {}", all.len(), dir.display());
+            } else {
+                let target = match (&a.out, &guard) {
+                    (Some(path), Some(guard)) => Some(export_target(guard, path)?),
+                    _ => None,
+                };
+                let session = sessions::load(a.id.as_deref().unwrap_or_default())?;
+                let text = a.format.render(&session)?;
+                match &target {
+                    Some(path) if dry_run::enabled() => println!("{}", dry_run::note_write(path)),
+                    Some(path) => {
+                        std::fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))?;
+                        audit::file_write(path);
+                    }
+                    None => print!("{}", text),
+                }
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `path`, resolved, if the caps allow `sessions export` to write it.
+fn export_target(guard: &guard::Guard, path: &Path) -> Result<PathBuf> {
+    guard.check_write(path).context("cannot export to --out (omit it to print to stdout)")
+}
+
+/// One line of `sessions list` / `sessions search`.
+fn session_row(s: &sessions::IndexEntry) -> String {
+    let title = s.title.as_deref().unwrap_or("(untitled)");
+    format!("{}  {}  {:>4} messages  {}", s.id, sessions::format_utc(s.updated_at), s.messages, title)
+}
@@ -0,0 +1,479 @@
+//! Setup wizard state shared by the full-screen wizard (`tui::wizard`) and the
+//! line-based one (`plain`, `aion --setup --plain`).
+//!
+//! Notes:
+//! - `WizardModel` owns the draft (`config::draft::WizardDraft`, the answers, not a
+//!   config) and the current step. Front-ends collect answers and show them; every choice
+//!   goes through a setter here, so both apply the same checks, defaults, and step order.
+//! - `unattended` (`--non-interactive`) answers from flags with the same draft.
+//! - Step dots and hints read the draft's fields (`step_done`, `WizardDraft::check`);
+//!   what only a config can show (changes, preview, warnings) reads `config`.
+//! - `finish` commits the draft over the config the wizard started from; its problems
+//!   come back by field, and `field_step` says which step fixes each. Saving (diff,
+//!   confirmation, `--dry-run`) happens in `main`, through `confirm_save`, and is the
+//!   same for both front-ends.
+//! - Languages outside `allowed_languages` are listed but cannot be chosen yet.
+//! - The language and provider lists are built from what is installed, in a fixed order
+//!   (see `language_options`, `provider_options`). Front-ends keep a list's cursor by
+//!   value (code, kind), not by index, so a list rebuilt with other entries still points
+//!   at the same item.
+
+pub mod plain;
+pub mod unattended;
+
+use crate::config::diff::{config_changes, DiffLine};
+use crate::config::draft::{Field, WizardDraft};
+use crate::config::io::config_file_path;
+use crate::config::{allowed_languages, AppConfig, ConfigError, ProviderKind};
+use crate::detect::{self, Detected};
+use crate::i18n::{self, LocaleManager};
+use crate::redact;
+use anyhow::Result;
+use crossterm::tty::IsTty;
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Wizard steps; also the values of `aion --setup --step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Step {
+    /// Settings found elsewhere (`detect`); shown first, and only when there are any.
+    #[value(skip)]
+    Import,
+    Language,
+    Provider,
+    Model,
+    Summary,
+}
+
+/// A numbered step of the wizard.
+#[derive(Debug, Clone, Copy)]
+pub struct StepDef {
+    pub step: Step,
+    /// Locale key of the step's name (`wizard.step.*`).
+    pub title_key: &'static str,
+    /// The name when the locale has none.
+    pub fallback: &'static str,
+    /// Whether `draft` needs the step. Skipped steps are not numbered or counted.
+    pub applies: fn(&WizardDraft) -> bool,
+}
+
+fn always(_: &WizardDraft) -> bool {
+    true
+}
+
+/// The numbered steps, in order. Import comes before them and is not numbered.
+pub fn steps() -> Vec<StepDef> {
+    vec![
+        StepDef { step: Step::Language, title_key: "wizard.step.language", fallback: "Language", applies: always },
+        StepDef { step: Step::Provider, title_key: "wizard.step.provider", fallback: "Provider", applies: always },
+        StepDef { step: Step::Model, title_key: "wizard.step.model", fallback: "Model", applies: always },
+        StepDef { step: Step::Summary, title_key: "wizard.step.summary", fallback: "Summary", applies: always },
+    ]
+}
+
+/// A choice the draft cannot take; the front-end shows it and asks again.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SetupError {
+    #[error("This language is not supported yet")]
+    UnsupportedLanguage,
+
+    #[error("Model cannot be empty")]
+    EmptyModel,
+}
+
+/// The step that asks for `field`; problems with other settings are shown at the summary.
+pub fn field_step(field: Field) -> Step {
+    match field {
+        Field::Language => Step::Language,
+        Field::Provider => Step::Provider,
+        Field::Model | Field::BaseUrl | Field::ApiKeyEnv => Step::Model,
+        Field::Other => Step::Summary,
+    }
+}
+
+pub struct WizardModel {
+    pub draft: WizardDraft,
+    /// The config the wizard started from; `finish` commits the draft over it.
+    pub existing: AppConfig,
+    pub step: Step,
+    /// The numbered steps (`steps`); which of them apply is decided against the draft
+    /// each time.
+    steps: Vec<StepDef>,
+    /// Settings found outside AION, offered at the import step.
+    pub detected: Vec<Detected>,
+    /// Opened at one step (`--step`): confirming it goes straight to the summary.
+    return_to_summary: bool,
+}
+
+impl WizardModel {
+    pub fn new(existing: &AppConfig, start: Option<Step>, detected: Vec<Detected>) -> Self {
+        let step = match start {
+            Some(step) => step,
+            None if !detected.is_empty() => Step::Import,
+            None => Step::Language,
+        };
+        Self {
+            draft: WizardDraft::new(existing),
+            existing: existing.clone(),
+            step,
+            steps: steps(),
+            detected,
+            return_to_summary: start.is_some(),
+        }
+    }
+
+    /// The config the draft would save as it stands (`WizardDraft::preview`).
+    pub fn config(&self) -> AppConfig {
+        self.draft.preview(&self.existing)
+    }
+
+    /// Whether `step`'s answers can be saved; the summary's when every answer can.
+    pub fn step_done(&self, step: Step) -> bool {
+        match step {
+            Step::Import => true,
+            Step::Summary => Field::ASKED.into_iter().all(|f| self.draft.done(f)),
+            step => Field::ASKED.into_iter().filter(|f| field_step(*f) == step).all(|f| self.draft.done(f)),
+        }
+    }
+
+    /// The numbered steps the draft needs, in order.
+    pub fn shown(&self) -> Vec<Step> {
+        self.steps.iter().filter(|d| (d.applies)(&self.draft)).map(|d| d.step).collect()
+    }
+
+    /// The step after the current one that applies; from Import, the first.
+    pub fn next_step(&self) -> Option<Step> {
+        let from = self.steps.iter().position(|d| d.step == self.step).map_or(0, |i| i + 1);
+        self.steps[from..].iter().find(|d| (d.applies)(&self.draft)).map(|d| d.step)
+    }
+
+    /// The step before the current one that applies; `None` from the first (see `back`).
+    pub fn prev_step(&self) -> Option<Step> {
+        let to = self.steps.iter().position(|d| d.step == self.step)?;
+        self.steps[..to].iter().rev().find(|d| (d.applies)(&self.draft)).map(|d| d.step)
+    }
+
+    /// Numbered step `n` as shown now, counting from 1.
+    pub fn nth(&self, n: usize) -> Option<Step> {
+        self.shown().get(n.checked_sub(1)?).copied()
+    }
+
+    /// "Step 2/4: AI Provider" in the draft's language, with "(50%)" when `percent`. A
+    /// step that is not numbered (Import, or one the draft skips) gets its name only.
+    pub fn title(&self, step: Step, percent: bool) -> String {
+        let lang = self.draft.language();
+        let Some(def) = self.steps.iter().find(|d| d.step == step) else {
+            return i18n::text(lang, "wizard.step.import", "Detected existing setup", &[]);
+        };
+        let name = i18n::text(lang, def.title_key, def.fallback, &[]);
+        let shown = self.shown();
+        let Some(i) = shown.iter().position(|s| *s == step) else { return name };
+        let number = |n: usize| i18n::format::localize_number(lang, &n.to_string());
+        let mut vars = vec![("i", number(i + 1)), ("n", number(shown.len())), ("name", name)];
+        if !percent {
+            return i18n::text(lang, "wizard.step.title", "Step {i}/{n}: {name}", &vars);
+        }
+        vars.push(("percent", number((i + 1) * 100 / shown.len())));
+        i18n::text(lang, "wizard.step.title_percent", "Step {i}/{n}: {name} ({percent}%)", &vars)
+    }
+
+    /// Move on from a confirmed step: to the next one, or to the summary when the wizard
+    /// was opened at a single step.
+    pub fn advance(&mut self) {
+        self.step = match self.next_step() {
+            Some(_) if self.return_to_summary => Step::Summary,
+            Some(next) => next,
+            None => Step::Summary,
+        };
+    }
+
+    /// Go to the previous step. `false` at the first step, where going back cancels.
+    pub fn back(&mut self) -> bool {
+        if let Some(prev) = self.prev_step() {
+            self.step = prev;
+        } else if self.step != Step::Import && !self.detected.is_empty() {
+            self.step = Step::Import;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Use settings found by `detect` and go straight to the summary.
+    pub fn adopt(&mut self, found: &Detected) {
+        self.draft.set_provider(found.kind.clone());
+        if let Some(var) = &found.api_key_env {
+            self.draft.set_text(Field::ApiKeyEnv, var);
+        }
+        if let Some(model) = &found.model {
+            self.draft.set_text(Field::Model, model);
+        }
+        if let Some(url) = &found.base_url {
+            self.draft.set_text(Field::BaseUrl, url);
+        }
+        self.step = Step::Summary;
+    }
+
+    pub fn set_language(&mut self, code: &str) -> Result<(), SetupError> {
+        if !allowed_languages().contains(code) {
+            return Err(SetupError::UnsupportedLanguage);
+        }
+        self.draft.set_language(code);
+        self.advance();
+        Ok(())
+    }
+
+    /// Choose the provider. A different one resets model, endpoint, and key variable to
+    /// its defaults (otherwise e.g. OpenAI without a key variable would fail validation),
+    /// drops organization and project, and always goes on to the model step. Returns
+    /// whether it changed.
+    pub fn set_provider(&mut self, kind: ProviderKind) -> bool {
+        let changed = self.draft.set_provider(kind);
+        if changed {
+            self.step = Step::Model;
+        } else {
+            self.advance();
+        }
+        changed
+    }
+
+    pub fn set_model(&mut self, name: &str) -> Result<(), SetupError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(SetupError::EmptyModel);
+        }
+        self.draft.set_text(Field::Model, name);
+        self.advance();
+        Ok(())
+    }
+
+    /// Set the endpoint; an empty value means the provider's default.
+    pub fn set_base_url(&mut self, url: &str) {
+        self.draft.set_text(Field::BaseUrl, url);
+    }
+
+    /// Set the variable holding the API key; an empty value clears it.
+    pub fn set_api_key_env(&mut self, name: &str) {
+        self.draft.set_text(Field::ApiKeyEnv, name);
+    }
+
+    /// The finished config. An invalid draft stays in the wizard so it can be fixed:
+    /// every problem comes back, in the order the steps ask for their fields.
+    pub fn finish(&self) -> Result<AppConfig, Vec<(Field, ConfigError)>> {
+        self.draft.commit(&self.existing)
+    }
+
+    /// After a failed `finish`: go to the step of the first problem.
+    pub fn show_problems(&mut self, errors: &[(Field, ConfigError)]) {
+        if let Some((field, _)) = errors.first() {
+            self.step = field_step(*field);
+        }
+    }
+}
+
+/// Settings found outside AION, for a full run (no `start`). Providers the config
+/// already uses are not offered, unless found at another endpoint (LM Studio for OpenAI).
+pub async fn detect(existing: &AppConfig, start: Option<Step>) -> Vec<Detected> {
+    let current = &existing.provider;
+    match start {
+        None => detect::scan(existing)
+            .await
+            .into_iter()
+            .filter(|d| d.kind != current.kind || d.base_url.as_ref().is_some_and(|u| *u != current.effective_base_url()))
+            .collect(),
+        Some(_) => Vec::new(),
+    }
+}
+
+/* ---------------------------
+   Option lists
+---------------------------- */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LangOption {
+    pub code: String,
+    /// The locale's own name for itself (`meta.native`); the code when unreadable.
+    pub name: String,
+    pub supported: bool,
+}
+
+/// The installed locales and the languages the config accepts, sorted by code, so the
+/// order does not depend on the directory listing or on the current language.
+pub fn language_options() -> Vec<LangOption> {
+    language_options_from(i18n::shared())
+}
+
+/// `language_options` with `locales` as the installed ones.
+pub fn language_options_from(locales: Option<&LocaleManager>) -> Vec<LangOption> {
+    let supported = allowed_languages();
+    let mut codes: BTreeSet<String> = locales.map(|m| m.available_locales()).unwrap_or_default().into_iter().collect();
+    codes.extend(supported.iter().map(|c| c.to_string()));
+    codes
+        .into_iter()
+        .map(|code| LangOption {
+            name: locales.and_then(|m| m.meta(&code)).map(|m| m.native.clone()).unwrap_or_else(|| code.clone()),
+            supported: supported.contains(code.as_str()),
+            code,
+        })
+        .collect()
+}
+
+/// Every provider, sorted by id.
+pub fn provider_options() -> Vec<ProviderKind> {
+    let mut kinds = ProviderKind::ALL.to_vec();
+    kinds.sort_by_key(|k| k.id());
+    kinds
+}
+
+/// Provider name in `lang` (`providers.<id>.name`), falling back to `ProviderKind::name`.
+pub fn provider_label(p: &ProviderKind, lang: &str) -> String {
+    i18n::text(lang, &format!("providers.{}.name", p.id()), p.name(), &[])
+}
+
+/// One-line description in `lang` (`providers.<id>.description`).
+pub fn provider_description(p: &ProviderKind, lang: &str) -> String {
+    let fallback = match p {
+        ProviderKind::Ollama => "Local models, no API key",
+        ProviderKind::OpenAI => "GPT models via the OpenAI API",
+        ProviderKind::Claude => "Claude models via the Anthropic API",
+        ProviderKind::OpenRouter => "Many hosted models behind one API key",
+    };
+    i18n::text(lang, &format!("providers.{}.description", p.id()), fallback, &[])
+}
+
+/// "OpenAI (from OPENAI_API_KEY, aichat config)".
+pub fn import_label(found: &Detected, lang: &str) -> String {
+    format!("{} (from {})", provider_label(&found.kind, lang), found.sources.join(", "))
+}
+
+/// "Organization: org-..." / "Project: proj_..." for the ones set, when the provider
+/// sends them (`ProviderConfig::scope_headers`).
+pub fn scope_lines(draft: &AppConfig) -> Vec<String> {
+    scope_fields(draft).into_iter().map(|(label, _, value)| format!("{}: {}", label, value)).collect()
+}
+
+/// The settings behind `scope_lines`: label, config key, value.
+pub fn scope_fields(draft: &AppConfig) -> Vec<(&'static str, &'static str, String)> {
+    let p = &draft.provider;
+    [("Organization", "provider.organization", &p.organization), ("Project", "provider.project", &p.project)]
+        .into_iter()
+        .filter(|_| !p.scope_headers().is_empty())
+        .filter_map(|(label, key, value)| value.clone().map(|v| (label, key, v)))
+        .collect()
+}
+
+/// Name of the `features` field `field` in `lang` (`features.<field>.name`).
+pub fn feature_label(field: &str, lang: &str) -> String {
+    let fallback = match field {
+        "system_scan" => "System scan",
+        "web_in_terminal" => "Web in terminal",
+        "command_suggestions" => "Command suggestions",
+        "safe_execute" => "Safe execute",
+        "retrieval" => "Retrieval",
+        other => other,
+    };
+    i18n::text(lang, &format!("features.{}.name", field), fallback, &[])
+}
+
+/// Names of the enabled `features` in `lang`.
+pub fn feature_labels(draft: &AppConfig) -> Vec<String> {
+    let f = &draft.features;
+    [
+        (f.system_scan, "system_scan"),
+        (f.web_in_terminal, "web_in_terminal"),
+        (f.command_suggestions, "command_suggestions"),
+        (f.safe_execute, "safe_execute"),
+        (f.retrieval, "retrieval"),
+    ]
+    .into_iter()
+    .filter(|(on, _)| *on)
+    .map(|(_, field)| feature_label(field, &draft.language))
+    .collect()
+}
+
+/* ---------------------------
+   Saving
+---------------------------- */
+
+/// Show what the wizard changed and ask before it is saved. Saves without asking with
+/// `--yes`, when stdin is not a terminal, or when nothing changed.
+pub fn confirm_save(saved: &AppConfig, updated: &AppConfig, yes: bool) -> Result<bool> {
+    let changes = config_changes(saved, updated)?;
+    let ask = !yes && io::stdin().is_tty();
+    confirm_changes(&changes, &config_file_path()?, ask, &mut io::stdin().lock(), &mut io::stdout())
+}
+
+/// `changes` to the file at `path`, then "Apply these changes?" answered on `input`.
+/// True without asking when `ask` is off or nothing changed; end of input declines.
+fn confirm_changes<R: BufRead, W: Write>(
+    changes: &[DiffLine],
+    path: &Path,
+    ask: bool,
+    input: &mut R,
+    out: &mut W,
+) -> Result<bool> {
+    if !ask || changes.is_empty() {
+        return Ok(true);
+    }
+
+    writeln!(out, "Changes to {}:", path.display())?;
+    for line in changes {
+        writeln!(out, "  {}", redact::redact(&line.to_string()))?;
+    }
+    write!(out, "Apply these changes? [Y/n] ")?;
+    out.flush()?;
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        writeln!(out)?;
+        return Ok(false);
+    }
+    Ok(matches!(answer.trim(), "" | "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `confirm_changes` returns and prints for `answer`, with one change to the model.
+    fn confirm(ask: bool, answer: &str) -> (bool, String) {
+        let saved = AppConfig::new_default();
+        let mut updated = saved.clone();
+        updated.provider.model = "llama3".to_string();
+        let changes = config_changes(&saved, &updated).unwrap();
+        let mut out = Vec::new();
+        let apply = confirm_changes(&changes, Path::new("/tmp/aion.toml"), ask, &mut answer.as_bytes(), &mut out).unwrap();
+        (apply, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn confirming_shows_the_changes_and_accepts_yes_or_enter() {
+        for answer in ["\n", "y\n", "yes\n", "Y"] {
+            let (apply, out) = confirm(true, answer);
+            assert!(apply, "{:?}", answer);
+            assert_eq!(
+                out,
+                "Changes to /tmp/aion.toml:\n  ~ provider.model: \"mistral\" -> \"llama3\"\nApply these changes? [Y/n] "
+            );
+        }
+    }
+
+    #[test]
+    fn confirming_declines_anything_else_and_end_of_input() {
+        for answer in ["n\n", "no\n", "nope\n", ""] {
+            assert!(!confirm(true, answer).0, "{:?}", answer);
+        }
+    }
+
+    #[test]
+    fn without_a_terminal_nothing_is_asked() {
+        let (apply, out) = confirm(false, "n\n");
+        assert!(apply);
+        assert_eq!(out, "");
+
+        // Nothing changed: nothing to ask either.
+        let mut out = Vec::new();
+        assert!(confirm_changes(&[], Path::new("/tmp/aion.toml"), true, &mut "n\n".as_bytes(), &mut out).unwrap());
+        assert!(out.is_empty());
+    }
+}
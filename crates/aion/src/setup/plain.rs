@@ -0,0 +1,334 @@
+//! Line-based setup wizard (`aion --setup --plain`), for screen readers and scripts.
+//!
+//! Notes:
+//! - Same steps, checks, and defaults as the full-screen wizard (see `WizardModel`), as
+//!   plain questions on stdout and answers on stdin: no cursor movement, no colors.
+//! - Menus are numbered; other questions show the current value in brackets and keep
+//!   it on an empty answer. `b` goes back a step, `q` quits without saving.
+//! - Works with stdin as a pipe, one answer per line. Piped answers are echoed so the
+//!   output reads as a transcript. An invalid answer repeats the question; input that
+//!   ends before the summary cancels the wizard.
+//! - Also asks what the full-screen wizard leaves to config.toml: the endpoint, the API
+//!   key variable for providers that need one (`fields::required`), and yes/no for each
+//!   feature and capability.
+//! - Questions and summary lines are English; localized names and descriptions in them
+//!   are isolated when they read right to left (`i18n::bidi::part`).
+//! - The Ollama model check and pull are not offered here; `aion models check` covers
+//!   the first.
+
+use super::{import_label, language_options, provider_description, provider_label, provider_options, Step, WizardModel};
+use crate::config::fields::{self, Check, Required};
+use crate::catalog::Catalog;
+use crate::config::draft::Field;
+use crate::config::AppConfig;
+use crate::i18n::{self, bidi};
+use crate::recent::RecentModels;
+use anyhow::{anyhow, Result};
+use crossterm::tty::IsTty;
+use std::io::{self, BufRead, Write};
+
+/// Run the wizard over `existing` on stdin/stdout, from the first step or from `start`.
+pub async fn run(existing: &AppConfig, start: Option<Step>) -> Result<AppConfig> {
+    let detected = super::detect(existing, start).await;
+    let mut wiz = WizardModel::new(existing, start, detected);
+    let echo = !io::stdin().is_tty();
+    drive(&mut wiz, &mut Prompter { input: &mut io::stdin().lock(), out: &mut io::stdout(), echo })
+}
+
+/// What a step asks the driver to do next.
+enum Flow {
+    /// The step's setter moved the wizard on (or kept it in place after a bad choice).
+    Stay,
+    Back,
+    /// The summary was confirmed.
+    Save,
+}
+
+/// An answer: text, or `b` for back.
+enum Reply {
+    Text(String),
+    Back,
+}
+
+/// Ask every step until the summary is confirmed.
+fn drive<R: BufRead, W: Write>(wiz: &mut WizardModel, q: &mut Prompter<R, W>) -> Result<AppConfig> {
+    loop {
+        writeln!(q.out)?;
+        writeln!(q.out, "{}", wiz.title(wiz.step, false))?;
+        let flow = match wiz.step {
+            Step::Import => import_step(q, wiz)?,
+            Step::Language => language_step(q, wiz)?,
+            Step::Provider => provider_step(q, wiz)?,
+            Step::Model => model_step(q, wiz)?,
+            Step::Summary => summary_step(q, wiz)?,
+        };
+        match flow {
+            Flow::Stay => {}
+            Flow::Back if wiz.back() => {}
+            Flow::Back => return Err(cancelled()),
+            // An invalid draft goes back to the step of its first problem so it can be fixed.
+            Flow::Save => match wiz.finish() {
+                Ok(cfg) => return Ok(cfg),
+                Err(errors) => {
+                    for (_, e) in &errors {
+                        writeln!(q.out, "Cannot save: {}", e)?;
+                    }
+                    wiz.show_problems(&errors);
+                }
+            },
+        }
+    }
+}
+
+fn cancelled() -> anyhow::Error {
+    anyhow!("Wizard cancelled by user")
+}
+
+/* ---------------------------
+   Steps
+---------------------------- */
+
+fn import_step<R: BufRead, W: Write>(q: &mut Prompter<R, W>, wiz: &mut WizardModel) -> Result<Flow> {
+    writeln!(q.out, "AION found provider settings from your environment or other tools.")?;
+    let mut items: Vec<String> = wiz.detected.iter().map(|d| import_label(d, wiz.draft.language())).collect();
+    items.push("Set up manually".to_string());
+    let Some(idx) = q.menu(&items, Some(0))? else {
+        return Ok(Flow::Back);
+    };
+    match wiz.detected.get(idx).cloned() {
+        Some(found) => wiz.adopt(&found),
+        None => wiz.advance(),
+    }
+    Ok(Flow::Stay)
+}
+
+fn language_step<R: BufRead, W: Write>(q: &mut Prompter<R, W>, wiz: &mut WizardModel) -> Result<Flow> {
+    let langs = language_options();
+    let items: Vec<String> = langs
+        .iter()
+        .map(|l| match l.supported {
+            true => format!("{} ({})", bidi::part("en", &l.name), l.code),
+            false => format!("{} ({}) - not supported yet", bidi::part("en", &l.name), l.code),
+        })
+        .collect();
+    let current = langs.iter().position(|l| l.code == wiz.draft.language());
+    let Some(idx) = q.menu(&items, current)? else {
+        return Ok(Flow::Back);
+    };
+    if let Err(e) = wiz.set_language(&langs[idx].code) {
+        writeln!(q.out, "{}", e)?;
+    }
+    Ok(Flow::Stay)
+}
+
+fn provider_step<R: BufRead, W: Write>(q: &mut Prompter<R, W>, wiz: &mut WizardModel) -> Result<Flow> {
+    let providers = provider_options();
+    let lang = wiz.draft.language().to_string();
+    let items: Vec<String> = providers
+        .iter()
+        .map(|p| {
+            let (label, description) = (provider_label(p, &lang), provider_description(p, &lang));
+            format!("{} - {}", bidi::part("en", &label), bidi::part("en", &description))
+        })
+        .collect();
+    let current = providers.iter().position(|p| p == wiz.draft.kind());
+    let Some(idx) = q.menu(&items, current)? else {
+        return Ok(Flow::Back);
+    };
+    wiz.set_provider(providers[idx].clone());
+    Ok(Flow::Stay)
+}
+
+fn model_step<R: BufRead, W: Write>(q: &mut Prompter<R, W>, wiz: &mut WizardModel) -> Result<Flow> {
+    let kind = wiz.draft.kind().clone();
+    let recent = RecentModels::load();
+    let recent = recent.models(&kind);
+    if !recent.is_empty() {
+        writeln!(q.out, "Used before: {}", recent.join(", "))?;
+    }
+    let Reply::Text(model) = q.text("Model", wiz.draft.text(Field::Model))? else {
+        return Ok(Flow::Back);
+    };
+    let endpoint = wiz.config().provider.effective_base_url();
+    let Reply::Text(url) = q.text("Endpoint", &endpoint)? else {
+        return Ok(Flow::Back);
+    };
+    if url != endpoint {
+        wiz.set_base_url(&url);
+    }
+    // Asked only of kinds that cannot do without one, as `/provider` does.
+    if fields::required(&kind).contains(&Required::ApiKeyEnv) {
+        let current = wiz.draft.text(Field::ApiKeyEnv).to_string();
+        let Reply::Text(name) = q.text(Required::ApiKeyEnv.label(), &current)? else {
+            return Ok(Flow::Back);
+        };
+        if let check @ (Check::Warning(_) | Check::Error(_)) = fields::api_key_env(&kind, &name) {
+            writeln!(q.out, "API key variable: {}", check.message())?;
+        }
+        wiz.set_api_key_env(&name);
+    }
+    // An empty model is reported by `set_model` below.
+    match fields::model(&model) {
+        check @ Check::Warning(_) => writeln!(q.out, "Model: {}", check.message())?,
+        Check::Ok(_) => {
+            if let Some(notice) = Catalog::load().notice(&kind, &model) {
+                writeln!(q.out, "Model: {}", notice)?;
+            }
+        }
+        Check::Error(_) => {}
+    }
+    if let check @ (Check::Warning(_) | Check::Error(_)) = fields::base_url(&kind, &url) {
+        writeln!(q.out, "Endpoint: {}", check.message())?;
+    }
+    if let Err(e) = wiz.set_model(&model) {
+        writeln!(q.out, "{}", e)?;
+    }
+    Ok(Flow::Stay)
+}
+
+fn summary_step<R: BufRead, W: Write>(q: &mut Prompter<R, W>, wiz: &mut WizardModel) -> Result<Flow> {
+    let lang = wiz.draft.language().to_string();
+    let f = &mut wiz.draft.features;
+    let features = [
+        ("system_scan", "System scan", &mut f.system_scan),
+        ("web_in_terminal", "Web in terminal", &mut f.web_in_terminal),
+        ("command_suggestions", "Command suggestions", &mut f.command_suggestions),
+        ("safe_execute", "Safe execute", &mut f.safe_execute),
+        ("retrieval", "Retrieval", &mut f.retrieval),
+    ];
+    for (field, fallback, flag) in features {
+        let label = i18n::text(&lang, &format!("features.{}.name", field), fallback, &[]);
+        match q.yes_no(&format!("Enable {}?", bidi::part("en", &label)), *flag)? {
+            Some(on) => *flag = on,
+            None => return Ok(Flow::Back),
+        }
+    }
+    let c = &mut wiz.draft.caps;
+    let caps = [
+        ("Allow reading files?", &mut c.read_files),
+        ("Allow writing files?", &mut c.write_files),
+        ("Allow network access?", &mut c.network),
+        ("Allow running commands?", &mut c.run_commands),
+    ];
+    for (question, flag) in caps {
+        match q.yes_no(question, *flag)? {
+            Some(on) => *flag = on,
+            None => return Ok(Flow::Back),
+        }
+    }
+
+    let draft = &wiz.config();
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    writeln!(q.out)?;
+    writeln!(q.out, "Language: {}", draft.language)?;
+    writeln!(q.out, "Provider: {}", bidi::part("en", &provider_label(&draft.provider.kind, &lang)))?;
+    writeln!(q.out, "Model: {}", draft.provider.model)?;
+    writeln!(q.out, "Endpoint: {}", draft.provider.effective_base_url())?;
+    for line in super::scope_lines(draft) {
+        writeln!(q.out, "{}", line)?;
+    }
+    match super::feature_labels(draft) {
+        labels if labels.is_empty() => writeln!(q.out, "Features: none")?,
+        labels => {
+            let labels: Vec<String> = labels.iter().map(|l| bidi::part("en", l)).collect();
+            writeln!(q.out, "Features: {}", labels.join(", "))?
+        }
+    }
+    writeln!(
+        q.out,
+        "Capabilities: read files {}, write files {}, network {}, run commands {}",
+        on_off(draft.caps.read_files),
+        on_off(draft.caps.write_files),
+        on_off(draft.caps.network),
+        on_off(draft.caps.run_commands)
+    )?;
+    for warning in draft.consistency_warnings() {
+        writeln!(q.out, "Warning: {}", warning)?;
+    }
+
+    match q.yes_no("Save these settings?", true)? {
+        None => Ok(Flow::Back),
+        Some(false) => Err(cancelled()),
+        Some(true) => Ok(Flow::Save),
+    }
+}
+
+/* ---------------------------
+   Questions
+---------------------------- */
+
+struct Prompter<'a, R, W> {
+    input: &'a mut R,
+    out: &'a mut W,
+    /// Write each answer after its question (the input is not a terminal).
+    echo: bool,
+}
+
+impl<R: BufRead, W: Write> Prompter<'_, R, W> {
+    /// One trimmed answer to `question`. `q` quits; end of input cancels.
+    fn ask(&mut self, question: &str) -> Result<Reply> {
+        write!(self.out, "{} (b back, q quit): ", question)?;
+        self.out.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            writeln!(self.out)?;
+            return Err(anyhow!("Wizard cancelled: input ended"));
+        }
+        if self.echo {
+            writeln!(self.out, "{}", line.trim_end())?;
+        }
+        match line.trim() {
+            "q" => Err(cancelled()),
+            "b" => Ok(Reply::Back),
+            answer => Ok(Reply::Text(answer.to_string())),
+        }
+    }
+
+    /// A numbered menu; `None` to go back. An empty answer picks `current`.
+    fn menu(&mut self, items: &[String], current: Option<usize>) -> Result<Option<usize>> {
+        for (i, item) in items.iter().enumerate() {
+            writeln!(self.out, "  {}. {}", i + 1, item)?;
+        }
+        let question = match current {
+            Some(i) => format!("Choose 1-{} [{}]", items.len(), i + 1),
+            None => format!("Choose 1-{}", items.len()),
+        };
+        loop {
+            let Reply::Text(answer) = self.ask(&question)? else {
+                return Ok(None);
+            };
+            let choice = match answer.as_str() {
+                "" => current,
+                n => n.parse::<usize>().ok().and_then(|n| n.checked_sub(1)).filter(|i| *i < items.len()),
+            };
+            match choice {
+                Some(i) => return Ok(Some(i)),
+                None => writeln!(self.out, "Please enter a number from 1 to {}.", items.len())?,
+            }
+        }
+    }
+
+    /// Free text; an empty answer keeps `current`.
+    fn text(&mut self, label: &str, current: &str) -> Result<Reply> {
+        Ok(match self.ask(&format!("{} [{}]", label, current))? {
+            Reply::Text(answer) if answer.is_empty() => Reply::Text(current.to_string()),
+            reply => reply,
+        })
+    }
+
+    /// y/n; `None` to go back. An empty answer keeps `current`.
+    fn yes_no(&mut self, question: &str, current: bool) -> Result<Option<bool>> {
+        let hint = if current { "Y/n" } else { "y/N" };
+        loop {
+            let Reply::Text(answer) = self.ask(&format!("{} [{}]", question, hint))? else {
+                return Ok(None);
+            };
+            match answer.to_ascii_lowercase().as_str() {
+                "" => return Ok(Some(current)),
+                "y" | "yes" => return Ok(Some(true)),
+                "n" | "no" => return Ok(Some(false)),
+                _ => writeln!(self.out, "Please answer y or n.")?,
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! `aion --setup --non-interactive`: the wizard's answers from flags, for scripts.
+//!
+//! Notes:
+//! - `--lang`, `--provider`, `--model`, `--base-url`, and `--api-key-env` answer the
+//!   wizard's questions; what is not given keeps the saved value. The answers go through
+//!   the wizard's draft (`WizardDraft`), so a new provider brings its defaults and the
+//!   problems are the ones the wizard reports, by field. `--strict` adds the consistency
+//!   warnings to them.
+//! - Nothing is asked and stdin is not read. Saving needs no confirmation; `--dry-run`
+//!   previews instead and reports `dry_run`.
+//! - The outcome is printed: with `--output json`,
+//!   `{"status":"saved","config_path":"...","changed_fields":["provider.model"]}` or
+//!   `{"status":"error","errors":[{"field":"provider.base_url","code":"MissingBaseUrl","message":"..."}]}`.
+//!   Changed fields are the keys `config_changes` reports; an error's field is the
+//!   wizard's (`Field::of`), or the key of a setting it does not ask for. Errors exit
+//!   with status 1 and change nothing.
+
+use crate::cli::Cli;
+use crate::config::diff::config_changes;
+use crate::config::draft::{Field, WizardDraft};
+use crate::config::io::{config_file_path, save_config};
+use crate::config::{AppConfig, ConfigError};
+use crate::dry_run;
+use crate::redact;
+use crate::ui::console::Console;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::process::ExitCode;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum Outcome {
+    Saved { config_path: String, changed_fields: Vec<String> },
+    DryRun { config_path: String, changed_fields: Vec<String> },
+    Error { errors: Vec<Problem> },
+}
+
+#[derive(Debug, Serialize)]
+struct Problem {
+    /// Dotted config key; `None` for a problem that is not about one setting.
+    field: Option<String>,
+    /// `ConfigError::code`.
+    code: &'static str,
+    message: String,
+}
+
+impl Problem {
+    fn new(field: Field, err: &ConfigError) -> Self {
+        let field = match field {
+            Field::Other => err.key(),
+            field => Some(field.key().to_string()),
+        };
+        Self { field, code: err.code(), message: err.to_string() }
+    }
+}
+
+/// Answer the wizard from `args` over `saved` (the config as on disk), and save.
+pub fn run(console: &Console, args: &Cli, saved: &AppConfig) -> Result<ExitCode> {
+    let mut draft = WizardDraft::new(saved);
+    if let Some(lang) = &args.lang {
+        draft.set_language(lang);
+    }
+    if let Some(kind) = args.overrides.kind() {
+        draft.set_provider(kind.clone());
+    }
+    for (field, value) in [(Field::Model, &args.overrides.model), (Field::BaseUrl, &args.base_url), (Field::ApiKeyEnv, &args.api_key_env)] {
+        if let Some(value) = value {
+            draft.set_text(field, value);
+        }
+    }
+
+    let path = config_file_path()?;
+    let outcome = match draft.commit(saved) {
+        Ok(updated) if args.strict && !updated.consistency_warnings().is_empty() => Outcome::Error {
+            errors: updated
+                .consistency_warnings()
+                .into_iter()
+                .map(|w| Problem::new(Field::Other, &ConfigError::Inconsistent(w)))
+                .collect(),
+        },
+        Ok(updated) => {
+            let changed_fields = config_changes(saved, &updated)?.into_iter().map(|l| l.key).collect();
+            let config_path = path.display().to_string();
+            if dry_run::enabled() {
+                if !console.is_json() {
+                    println!("{}", dry_run::preview_config(&updated)?);
+                }
+                Outcome::DryRun { config_path, changed_fields }
+            } else {
+                save_config(&updated).context("failed to save config")?;
+                Outcome::Saved { config_path, changed_fields }
+            }
+        }
+        Err(errors) => Outcome::Error { errors: errors.iter().map(|(field, e)| Problem::new(*field, e)).collect() },
+    };
+
+    if console.is_json() {
+        console.print_json(&outcome)?;
+    } else {
+        print_text(&outcome);
+    }
+    Ok(match outcome {
+        Outcome::Error { .. } => ExitCode::FAILURE,
+        _ => ExitCode::SUCCESS,
+    })
+}
+
+fn print_text(outcome: &Outcome) {
+    match outcome {
+        Outcome::Saved { config_path, changed_fields } | Outcome::DryRun { config_path, changed_fields } => {
+            let verb = if matches!(outcome, Outcome::Saved { .. }) { "Saved" } else { "Would save" };
+            match changed_fields.is_empty() {
+                true => println!("{} {} (no changes)", verb, config_path),
+                false => println!("{} {}: {}", verb, config_path, changed_fields.join(", ")),
+            }
+        }
+        Outcome::Error { errors } => {
+            for problem in errors {
+                let message = redact::redact(&problem.message);
+                match &problem.field {
+                    Some(field) => eprintln!("Cannot save: {}: {}", field, message),
+                    None => eprintln!("Cannot save: {}", message),
+                }
+            }
+        }
+    }
+}
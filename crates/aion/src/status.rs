@@ -0,0 +1,323 @@
+//! `aion status`: a read-only dashboard of the current setup.
+//!
+//! Notes:
+//! - Each section (config, provider, sessions, locale, endpoint) is gathered on its own;
+//!   a failing section is reported as an error and the rest still print.
+//! - Nothing here writes: the config is loaded (and migrated) in memory only.
+//! - The endpoint is probed only when `caps.network` and its host lists allow it, reusing
+//!   doctor's probe.
+//! - Provider health is what earlier failovers recorded (`providers::health`), shown
+//!   only when `provider.fallbacks` are configured; nothing is probed for it.
+//! - `summary` is the startup summary (`crate::summary`), so features, capabilities,
+//!   and warnings read the same in both.
+//! - Token totals come from the usage stored with each saved reply; replies recorded
+//!   before usage was stored count as zero.
+
+use crate::cli::StatusArgs;
+use crate::config::AppConfig;
+use crate::config::io::{config_file_path, load_config};
+use crate::doctor::probe_url;
+use crate::guard::Guard;
+use crate::i18n::{bidi, LocaleManager};
+use crate::providers::failover::label;
+use crate::providers::health::{self, Health};
+use crate::providers::Usage;
+use crate::redact;
+use crate::sessions::{self, format_utc};
+use crate::summary::{self, Summary, RISKY_CAPS};
+use crate::ui::console::Console;
+use anyhow::Result;
+use serde::Serialize;
+use std::process::ExitCode;
+use std::time::Instant;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Section<T> {
+    Ok(T),
+    Skipped { reason: String },
+    Error { error: String },
+}
+
+impl<T> Section<T> {
+    fn skipped(reason: impl Into<String>) -> Self {
+        Section::Skipped { reason: reason.into() }
+    }
+
+    fn error(e: &anyhow::Error) -> Self {
+        Section::Error { error: redact::error_chain(e) }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigInfo {
+    pub path: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderInfo {
+    pub kind: String,
+    pub model: String,
+    pub base_url: String,
+    /// `None` when the provider needs no key; otherwise whether the env var resolves.
+    pub api_key_env: Option<String>,
+    pub api_key_set: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionStats {
+    pub count: usize,
+    pub last_updated: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocaleInfo {
+    pub language: String,
+    pub name: Option<String>,
+    /// Percentage of English keys this locale translates.
+    pub coverage: Option<u8>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointInfo {
+    pub url: String,
+    pub http_status: u16,
+    pub latency_ms: u128,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthInfo {
+    /// `<provider> · <model> @ <host>`.
+    pub provider: String,
+    #[serde(flatten)]
+    pub health: Health,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub aion_version: &'static str,
+    pub config: Section<ConfigInfo>,
+    pub provider: Section<ProviderInfo>,
+    pub sessions: Section<SessionStats>,
+    pub locale: Section<LocaleInfo>,
+    pub endpoint: Section<EndpointInfo>,
+    pub health: Section<Vec<HealthInfo>>,
+    pub summary: Section<Summary>,
+}
+
+impl StatusReport {
+    /// Gather every section. `language` is the interface language for this run.
+    pub async fn gather(language: &str) -> Self {
+        let cfg = load_config();
+        let config = match (&cfg, &config_file_path()) {
+            (Ok(cfg), Ok(path)) => Section::Ok(ConfigInfo {
+                path: path.display().to_string(),
+                version: cfg.version,
+            }),
+            (Err(e), _) | (_, Err(e)) => Section::error(e),
+        };
+        let cfg = cfg.ok();
+
+        let provider = match &cfg {
+            Some(cfg) => Section::Ok(provider_info(cfg)),
+            None => Section::skipped("config unavailable"),
+        };
+        let health = match &cfg {
+            Some(cfg) if cfg.provider.fallbacks.is_empty() => Section::skipped("no provider.fallbacks configured"),
+            Some(cfg) => Section::Ok(health_info(cfg)),
+            None => Section::skipped("config unavailable"),
+        };
+        let summary = match (&cfg, &config_file_path()) {
+            (Some(cfg), path) => Section::Ok(Summary::new(cfg, path.as_deref().ok(), summary::key_set)),
+            (None, _) => Section::skipped("config unavailable"),
+        };
+        let endpoint = match &cfg {
+            Some(cfg) if cfg.caps.network => endpoint_info(cfg).await,
+            Some(_) => Section::skipped("caps.network is disabled"),
+            None => Section::skipped("config unavailable"),
+        };
+
+        Self {
+            aion_version: env!("CARGO_PKG_VERSION"),
+            config,
+            provider,
+            sessions: session_stats(),
+            locale: locale_info(language),
+            endpoint,
+            health,
+            summary,
+        }
+    }
+
+    /// Whether the config itself could not be loaded.
+    pub fn config_failed(&self) -> bool {
+        matches!(self.config, Section::Error { .. })
+    }
+}
+
+pub async fn run(console: &Console, args: &StatusArgs, lang: &str) -> Result<ExitCode> {
+    let report = StatusReport::gather(lang).await;
+
+    if args.json || console.is_json() {
+        console.print_json(&report)?;
+    } else {
+        print_human(&report);
+    }
+
+    Ok(if report.config_failed() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/* ---------------------------
+   Sections
+---------------------------- */
+
+fn provider_info(cfg: &AppConfig) -> ProviderInfo {
+    let p = &cfg.provider;
+    let api_key_env = p.api_key_env.clone().filter(|_| p.kind.requires_api_key());
+    let api_key_set = match &api_key_env {
+        Some(var) => std::env::var(var).is_ok_and(|v| !v.trim().is_empty()),
+        None => true,
+    };
+    ProviderInfo {
+        kind: p.kind.to_string(),
+        model: p.model.clone(),
+        base_url: p.effective_base_url(),
+        api_key_env,
+        api_key_set,
+    }
+}
+
+fn session_stats() -> Section<SessionStats> {
+    let list = match sessions::list() {
+        Ok(list) => list,
+        Err(e) => return Section::error(&e),
+    };
+    let usage = list.iter().map(|s| s.usage()).fold(Usage::default(), |acc, u| Usage {
+        input_tokens: acc.input_tokens + u.input_tokens,
+        output_tokens: acc.output_tokens + u.output_tokens,
+    });
+    Section::Ok(SessionStats {
+        count: list.len(),
+        // `list` is sorted newest first.
+        last_updated: list.first().map(|s| format_utc(s.updated_at)),
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+    })
+}
+
+fn locale_info(language: &str) -> Section<LocaleInfo> {
+    match LocaleManager::load_for(language) {
+        Ok(manager) => Section::Ok(LocaleInfo {
+            language: language.to_string(),
+            name: manager.meta(language).map(|m| m.native.clone()),
+            coverage: manager.coverage(language).map(|c| (c * 100.0).round() as u8),
+        }),
+        Err(e) => Section::error(&e),
+    }
+}
+
+fn health_info(cfg: &AppConfig) -> Vec<HealthInfo> {
+    cfg.provider.chain().map(|p| HealthInfo { provider: label(p), health: health::get(p) }).collect()
+}
+
+async fn endpoint_info(cfg: &AppConfig) -> Section<EndpointInfo> {
+    let url = cfg.provider.effective_base_url();
+    if let Err(e) = Guard::for_app(cfg).check_network(&url) {
+        return Section::error(&e);
+    }
+    let started = Instant::now();
+    match probe_url(&url).await {
+        Ok(http_status) => Section::Ok(EndpointInfo {
+            url,
+            http_status,
+            latency_ms: started.elapsed().as_millis(),
+        }),
+        Err(e) => Section::error(&e.context(format!("{} is not reachable", url))),
+    }
+}
+
+/* ---------------------------
+   Rendering
+---------------------------- */
+
+pub fn print_human(report: &StatusReport) {
+    println!("AION status ({})", report.aion_version);
+    println!();
+
+    line("Config", &report.config, |c| format!("{} (version {})", c.path, c.version));
+    line("Provider", &report.provider, |p| format!("{} · {} @ {}", p.kind, p.model, p.base_url));
+    line("API key", &report.provider, |p| match (&p.api_key_env, p.api_key_set) {
+        (None, _) => "not required".to_string(),
+        (Some(var), true) => format!("{} is set", var),
+        (Some(var), false) => format!("{} is not set", var),
+    });
+    line("Features", &report.summary, |s| list_or_none(s.features.iter().map(|f| f.to_string()).collect()));
+    line("Caps", &report.summary, |s| {
+        let caps = s.caps.iter().map(|c| match RISKY_CAPS.contains(c) {
+            true => format!("{} (risky)", c),
+            false => c.to_string(),
+        });
+        let preset = s.caps_preset.map(|p| format!(" [{} preset]", p)).unwrap_or_default();
+        list_or_none(caps.collect()) + &preset
+    });
+    line("Warnings", &report.summary, |s| match summary::warning_lines(s, "en") {
+        warnings if warnings.is_empty() => "none".to_string(),
+        warnings => warnings.join(&format!("\n{:<10} ", "")),
+    });
+    line("Sessions", &report.sessions, |s| {
+        let last = s.last_updated.as_deref().unwrap_or("never");
+        format!(
+            "{} saved, last {}, {} in / {} out tokens",
+            s.count, last, s.input_tokens, s.output_tokens
+        )
+    });
+    line("Locale", &report.locale, |l| {
+        let name = l.name.as_deref().map(|n| format!(" ({})", bidi::part("en", n))).unwrap_or_default();
+        match l.coverage {
+            Some(pct) => format!("{}{}, {}% translated", l.language, name, pct),
+            None => format!("{} not installed, using English", l.language),
+        }
+    });
+    line("Endpoint", &report.endpoint, |e| {
+        format!("{} reachable in {} ms (HTTP {})", e.url, e.latency_ms, e.http_status)
+    });
+    line("Health", &report.health, |list| {
+        let lines: Vec<String> = list.iter().map(|h| format!("{}: {}", h.provider, describe_health(&h.health))).collect();
+        lines.join(&format!("\n{:<10} ", ""))
+    });
+}
+
+fn describe_health(h: &Health) -> String {
+    let error = h.error.as_deref().unwrap_or("unknown error");
+    if let Some(left) = h.cooldown_left() {
+        return format!("passed over for {}s more after: {}", left, error);
+    }
+    match (h.failures, h.last_ok) {
+        (0, Some(at)) => format!("ok, last reply {}", format_utc(at)),
+        (0, None) => "no requests yet".to_string(),
+        (n, _) => format!("{} failure(s) since the last reply, latest: {}", n, error),
+    }
+}
+
+fn list_or_none(items: Vec<String>) -> String {
+    match items.is_empty() {
+        true => "none".to_string(),
+        false => items.join(", "),
+    }
+}
+
+fn line<T>(label: &str, section: &Section<T>, describe: impl Fn(&T) -> String) {
+    let text = match section {
+        Section::Ok(value) => describe(value),
+        Section::Skipped { reason } => format!("skipped: {}", reason),
+        Section::Error { error } => format!("error: {}", error),
+    };
+    println!("{:<10} {}", label, redact::redact(&text));
+}
@@ -0,0 +1,77 @@
+//! `/suggest <request>`: ask for a shell command, optionally with the user's recent shell
+//! history as context.
+//!
+//! Notes:
+//! - Needs `features.command_suggestions`. The prompt is the `suggest-command` template
+//!   (a user template of that name replaces it), sent as the next turn of the
+//!   conversation, so earlier turns and follow-ups ("now only for .rs files") count.
+//! - `--with-history` adds the last `HISTORY_COMMANDS` commands of the shell in `$SHELL`
+//!   (`shell_history`). The file is read through `guard`, so `caps.read_files` and its
+//!   path lists apply, and every command is redacted before it goes into the prompt.
+//!   What still looks like a secret is in `Suggestion::secrets`, for the front end to ask
+//!   about (`secrets`). Without the flag no history file is opened.
+
+use crate::config::AppConfig;
+use crate::guard::Guard;
+use crate::secrets::{self, Findings};
+use crate::shell_history::{self, Shell};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+
+pub const TEMPLATE: &str = "suggest-command";
+/// Most history commands included with `--with-history`.
+pub const HISTORY_COMMANDS: usize = 20;
+
+/// What `/suggest` sends: the template's variables, a line telling the user what was
+/// read from their history, if anything, and what in it looks like a secret.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub vars: HashMap<String, String>,
+    pub notice: Option<String>,
+    pub secrets: Findings,
+}
+
+pub fn prepare(cfg: &AppConfig, request: &str, with_history: bool) -> Result<Suggestion> {
+    if !cfg.features.command_suggestions {
+        bail!("command suggestions are off (features.command_suggestions)");
+    }
+    let shell = Shell::detect();
+    let (history, notice, secrets) = match with_history {
+        true => read_history(cfg, shell)?,
+        false => (String::new(), None, Findings::default()),
+    };
+    let vars = HashMap::from([
+        ("shell".to_string(), shell.map_or("shell", Shell::name).to_string()),
+        ("os".to_string(), std::env::consts::OS.to_string()),
+        ("history".to_string(), history),
+        ("input".to_string(), request.to_string()),
+    ]);
+    Ok(Suggestion { vars, notice, secrets })
+}
+
+/// The `{history}` block, the notice for it, and the secrets in it.
+fn read_history(cfg: &AppConfig, shell: Option<Shell>) -> Result<(String, Option<String>, Findings)> {
+    let shell = shell.ok_or_else(|| {
+        anyhow!("cannot tell which shell $SHELL is; history is read for bash, zsh, fish, and PowerShell")
+    })?;
+    let path = shell.history_path().ok_or_else(|| anyhow!("no home directory to find the {} history in", shell.name()))?;
+    let resolved = Guard::new(&cfg.caps).check_read(&path)?;
+    let commands = shell_history::read(shell, &resolved, HISTORY_COMMANDS)
+        .with_context(|| format!("failed to read the {} history at {}", shell.name(), path.display()))?;
+    if commands.is_empty() {
+        let notice = format!("The {} history at {} is empty.", shell.name(), path.display());
+        return Ok((String::new(), Some(notice), Findings::default()));
+    }
+
+    let notice = format!(
+        "Including your last {} {} commands from {} (secrets redacted).",
+        commands.len(),
+        shell.name(),
+        path.display()
+    );
+    let listed = commands.join("\n");
+    let source = format!("the last {} {} commands", commands.len(), shell.name());
+    let secrets = secrets::scan(cfg, [(source, listed.as_str())])?;
+    let block = format!("\nMy most recent {} commands, oldest first:\n```\n{}\n```\n", shell.name(), listed);
+    Ok((block, Some(notice), secrets))
+}
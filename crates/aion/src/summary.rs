@@ -0,0 +1,298 @@
+//! The config summary shown at startup, in `--output json`, and in `aion status`.
+//!
+//! Notes:
+//! - `Summary::new` only reads the config it is given; whether an API key variable is
+//!   set is asked of the caller (`key_set`), so the same config always gives the same
+//!   summary.
+//! - Renderers: `write_text` (an aligned table on a TTY, `Label: value` lines
+//!   otherwise) and `tui::splash`. Both take their rows from `rows` and `warning_lines`,
+//!   localized in the interface language. `write_text` isolates values written the
+//!   other way round from the labels, like model names under Arabic ones (`i18n::bidi`).
+//! - Capabilities that let AION change the machine (`RISKY_CAPS`) are marked.
+//! - Warnings are the config's consistency warnings (`--strict` turns them into errors)
+//!   plus runtime ones: a key variable that is not set, redaction switched off, a
+//!   project context file that could not be read.
+//! - Chats add the project context they loaded (`with_context`); `aion status` does not.
+
+use crate::config::{AppConfig, CapsPreset, ConfigWarning};
+use crate::i18n::{self, bidi, format::format_bytes, format::tilde};
+use crate::project_context::ProjectContext;
+use crate::providers::failover::label;
+use crate::setup::feature_label;
+use crate::ui::width::width;
+use crossterm::style::Stylize;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Capabilities marked as risky: writing files and running commands.
+pub const RISKY_CAPS: [&str; 2] = ["write_files", "run_commands"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub aion_version: &'static str,
+    pub config_version: u32,
+    pub config_path: Option<String>,
+    pub language: String,
+    pub ui_mode: String,
+    pub provider: String,
+    pub model: String,
+    pub base_url: String,
+    /// `<provider> · <model> @ <host>` of each `provider.fallbacks` entry.
+    pub fallbacks: Vec<String>,
+    /// Enabled `features`, by field name.
+    pub features: Vec<&'static str>,
+    /// Enabled `caps`, by field name.
+    pub caps: Vec<&'static str>,
+    /// The `--caps` preset the capabilities match, if any.
+    pub caps_preset: Option<&'static str>,
+    /// The project context this chat sends, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_context: Option<ContextNote>,
+    pub warnings: Vec<ConfigWarning>,
+    pub runtime_warnings: Vec<RuntimeWarning>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextNote {
+    pub path: String,
+    /// The file's size in bytes.
+    pub size: u64,
+    /// How much of it is sent; less than `size` when cut to `context.max_bytes`.
+    pub sent: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "id", rename_all = "snake_case")]
+pub enum RuntimeWarning {
+    /// A provider of the chain needs a key and `var` is not set.
+    MissingApiKey { provider: String, var: String },
+    /// `logging.redaction = false`.
+    RedactionDisabled,
+    /// The project context file was found but could not be read (caps, I/O).
+    ContextNotLoaded { reason: String },
+}
+
+impl Summary {
+    /// Summary of `cfg`, read from `config_path`. `key_set` says whether an API key
+    /// variable holds a value (`key_set` below, for the real environment).
+    pub fn new(cfg: &AppConfig, config_path: Option<&Path>, key_set: impl Fn(&str) -> bool) -> Self {
+        let (f, c) = (&cfg.features, &cfg.caps);
+        let features = [
+            ("system_scan", f.system_scan),
+            ("web_in_terminal", f.web_in_terminal),
+            ("command_suggestions", f.command_suggestions),
+            ("safe_execute", f.safe_execute),
+            ("retrieval", f.retrieval),
+        ];
+        let caps = [
+            ("read_files", c.read_files),
+            ("network", c.network),
+            ("write_files", c.write_files),
+            ("run_commands", c.run_commands),
+        ];
+
+        let mut runtime_warnings: Vec<RuntimeWarning> = cfg
+            .provider
+            .chain()
+            .filter(|p| p.kind.requires_api_key())
+            .filter_map(|p| p.api_key_env.as_ref().map(|var| (p, var)))
+            .filter(|(_, var)| !key_set(var))
+            .map(|(p, var)| RuntimeWarning::MissingApiKey { provider: label(p), var: var.clone() })
+            .collect();
+        if !cfg.logging.redaction {
+            runtime_warnings.push(RuntimeWarning::RedactionDisabled);
+        }
+
+        Self {
+            aion_version: env!("CARGO_PKG_VERSION"),
+            config_version: cfg.version,
+            config_path: config_path.map(|p| p.display().to_string()),
+            language: cfg.language.clone(),
+            ui_mode: cfg.ui_mode.to_string(),
+            provider: cfg.provider.kind.to_string(),
+            model: cfg.provider.model.clone(),
+            base_url: cfg.provider.effective_base_url(),
+            fallbacks: cfg.provider.fallbacks.iter().map(label).collect(),
+            features: features.into_iter().filter(|(_, on)| *on).map(|(name, _)| name).collect(),
+            caps: caps.into_iter().filter(|(_, on)| *on).map(|(name, _)| name).collect(),
+            caps_preset: CapsPreset::matching(c).map(CapsPreset::id),
+            project_context: None,
+            warnings: cfg.consistency_warnings(),
+            runtime_warnings,
+        }
+    }
+
+    /// With the result of loading the project context (`persona::load_context`).
+    pub fn with_context(mut self, loaded: &anyhow::Result<Option<ProjectContext>>) -> Self {
+        match loaded {
+            Ok(Some(c)) => {
+                let path = tilde(&c.path);
+                self.project_context = Some(ContextNote { path, size: c.size, sent: c.text.len() as u64 });
+            }
+            Ok(None) => {}
+            Err(e) => self.runtime_warnings.push(RuntimeWarning::ContextNotLoaded { reason: format!("{:#}", e) }),
+        }
+        self
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty() || !self.runtime_warnings.is_empty()
+    }
+}
+
+/// Whether the environment variable `var` holds a value.
+pub fn key_set(var: &str) -> bool {
+    std::env::var(var).is_ok_and(|v| !v.trim().is_empty())
+}
+
+/* ---------------------------
+   Rows
+---------------------------- */
+
+/// A labelled line; `risky` parts of the value are highlighted.
+pub struct Row {
+    pub label: String,
+    pub parts: Vec<Part>,
+}
+
+pub struct Part {
+    pub text: String,
+    pub risky: bool,
+}
+
+impl Part {
+    fn plain(text: impl Into<String>) -> Self {
+        Self { text: text.into(), risky: false }
+    }
+}
+
+/// "Config loaded successfully", in `lang`.
+pub fn title(lang: &str) -> String {
+    i18n::text(lang, "summary.title", "Config loaded successfully", &[])
+}
+
+/// The summary's rows, in `lang`. Empty lists read "none"; unset paths are left out.
+pub fn rows(s: &Summary, lang: &str) -> Vec<Row> {
+    let t = |key: &str, fallback: &str| i18n::text(lang, &format!("summary.{}", key), fallback, &[]);
+    let none = || vec![Part::plain(t("none", "none"))];
+    let row = |key: &str, fallback: &str, parts: Vec<Part>| Row { label: t(key, fallback), parts };
+
+    let mut rows = vec![
+        row("language", "Language", vec![Part::plain(&s.language)]),
+        row("provider", "Provider", vec![Part::plain(&s.provider)]),
+        row("model", "Model", vec![Part::plain(&s.model)]),
+        row("ui_mode", "UI mode", vec![Part::plain(&s.ui_mode)]),
+    ];
+    if let Some(path) = &s.config_path {
+        rows.push(row("config", "Config", vec![Part::plain(path)]));
+    }
+    if !s.fallbacks.is_empty() {
+        rows.push(row("fallbacks", "Fallbacks", s.fallbacks.iter().map(Part::plain).collect()));
+    }
+
+    let features: Vec<Part> = s.features.iter().map(|f| Part::plain(feature_label(f, lang))).collect();
+    rows.push(row("features", "Features", if features.is_empty() { none() } else { features }));
+
+    let mut caps: Vec<Part> = s
+        .caps
+        .iter()
+        .map(|cap| {
+            let name = t(&format!("cap.{}", cap), &cap.replace('_', " "));
+            match RISKY_CAPS.contains(cap) {
+                true => Part { text: format!("{} ({})", name, t("risky", "risky")), risky: true },
+                false => Part::plain(name),
+            }
+        })
+        .collect();
+    if caps.is_empty() {
+        caps = none();
+    }
+    if let Some(preset) = s.caps_preset {
+        let vars = [("preset", preset.to_string())];
+        let text = i18n::text(lang, "summary.preset", "{preset} preset", &vars);
+        caps.last_mut().expect("caps has a part").text += &format!(" [{}]", text);
+    }
+    rows.push(row("caps", "Capabilities", caps));
+
+    if let Some(c) = &s.project_context {
+        let size = format_bytes(lang, c.size);
+        let text = match c.sent < c.size {
+            true => {
+                let vars = [("sent", format_bytes(lang, c.sent)), ("size", size)];
+                i18n::text(lang, "summary.context_truncated", "first {sent} of {size}", &vars)
+            }
+            false => size,
+        };
+        rows.push(row("context", "Project context", vec![Part::plain(format!("{} ({})", c.path, text))]));
+    }
+    rows
+}
+
+/// Each warning as a sentence, in `lang`.
+pub fn warning_lines(s: &Summary, lang: &str) -> Vec<String> {
+    let consistency = s
+        .warnings
+        .iter()
+        .map(|w| i18n::text(lang, &format!("summary.warning.{}", w.id()), &w.to_string(), &[]));
+    let runtime = s.runtime_warnings.iter().map(|w| match w {
+        RuntimeWarning::MissingApiKey { provider, var } => i18n::text(
+            lang,
+            "summary.warning.missing_api_key",
+            "{var} is not set, so requests to {provider} will fail",
+            &[("var", var.clone()), ("provider", provider.clone())],
+        ),
+        RuntimeWarning::RedactionDisabled => i18n::text(
+            lang,
+            "summary.warning.redaction_disabled",
+            "logging.redaction is off: secrets are not masked in output or logs",
+            &[],
+        ),
+        RuntimeWarning::ContextNotLoaded { reason } => i18n::text(
+            lang,
+            "summary.warning.context_not_loaded",
+            "the project context was not loaded: {reason}",
+            &[("reason", reason.clone())],
+        ),
+    });
+    consistency.chain(runtime).collect()
+}
+
+/* ---------------------------
+   Text
+---------------------------- */
+
+/// The summary as text: an aligned, coloured table when `table`, else `Label: value`
+/// lines. Ends with a blank line.
+pub fn write_text(out: &mut impl Write, s: &Summary, lang: &str, table: bool) -> io::Result<()> {
+    writeln!(out, "{}", title(lang))?;
+    let rows = rows(s, lang);
+    let label_width = rows.iter().map(|r| width(&r.label)).max().unwrap_or(0);
+    for row in &rows {
+        let parts: Vec<String> = row
+            .parts
+            .iter()
+            .map(|p| match (table, p.risky) {
+                (true, true) => bidi::part(lang, &p.text).red().bold().to_string(),
+                _ => bidi::part(lang, &p.text),
+            })
+            .collect();
+        match table {
+            true => {
+                let pad = " ".repeat(label_width - width(&row.label));
+                writeln!(out, "  {}{}  {}", row.label.clone().bold(), pad, parts.join(", "))?
+            }
+            false => writeln!(out, "{}: {}", row.label, parts.join(", "))?,
+        }
+    }
+
+    let label = i18n::text(lang, "summary.warning_label", "Warning", &[]);
+    for warning in warning_lines(s, lang) {
+        let line = bidi::line(lang, &format!("{}: {}", label, warning));
+        match table {
+            true => writeln!(out, "{}", line.yellow())?,
+            false => writeln!(out, "{}", line)?,
+        }
+    }
+    writeln!(out)
+}
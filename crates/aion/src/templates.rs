@@ -0,0 +1,285 @@
+//! Prompt templates.
+//!
+//! Notes:
+//! - Built-in templates are embedded from `templates/builtin.toml`.
+//! - User templates live in `templates.toml` next to config.toml and override built-ins
+//!   with the same name.
+//! - Bodies use `{name}` placeholders filled by `crate::i18n::interpolate`; the piped or
+//!   positional input is bound to `{input}`.
+//! - `run` is `aion run`: one template, rendered and sent once (`chat::send_once`).
+//!   `manage` is `aion templates`.
+
+use crate::audit;
+use crate::chat;
+use crate::cli::{RunArgs, TemplatesAction};
+use crate::config::{AppConfig, ProviderKind};
+use crate::config::io::{ensure_config_dir_exists, load_config};
+use crate::dry_run;
+use crate::paths::config_dir;
+use crate::read_only;
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::tty::IsTty;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const TEMPLATES_FILE_NAME: &str = "templates.toml";
+const BUILTIN_TEMPLATES: &str = include_str!("../templates/builtin.toml");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Template {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TemplateFile {
+    #[serde(default)]
+    templates: BTreeMap<String, Template>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Builtin,
+    User,
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Builtin => "built-in",
+            Source::User => "user",
+        }
+    }
+}
+
+pub struct TemplateStore {
+    builtin: BTreeMap<String, Template>,
+    user: BTreeMap<String, Template>,
+}
+
+pub fn templates_file_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(TEMPLATES_FILE_NAME))
+}
+
+impl TemplateStore {
+    pub fn load() -> Result<Self> {
+        let builtin: TemplateFile =
+            toml::from_str(BUILTIN_TEMPLATES).context("failed to parse built-in templates")?;
+
+        let path = templates_file_path()?;
+        let user = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read templates file: {}", path.display()))?;
+            toml::from_str::<TemplateFile>(&content)
+                .with_context(|| format!("failed to parse templates file: {}", path.display()))?
+                .templates
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            builtin: builtin.templates,
+            user,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<(&Template, Source)> {
+        self.user
+            .get(name)
+            .map(|t| (t, Source::User))
+            .or_else(|| self.builtin.get(name).map(|t| (t, Source::Builtin)))
+    }
+
+    /// All templates by name; user templates shadow built-ins.
+    pub fn list(&self) -> Vec<(&str, &Template, Source)> {
+        let mut names: Vec<&String> = self.builtin.keys().chain(self.user.keys()).collect();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .filter_map(|n| self.get(n).map(|(t, src)| (n.as_str(), t, src)))
+            .collect()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.list().into_iter().map(|(n, _, _)| n.to_string()).collect()
+    }
+
+    pub fn add(&mut self, name: &str, template: Template) -> Result<()> {
+        validate_name(name)?;
+        if template.body.trim().is_empty() {
+            bail!("template body is empty");
+        }
+        self.user.insert(name.to_string(), template);
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        if self.user.remove(name).is_none() {
+            if self.builtin.contains_key(name) {
+                bail!("'{}' is a built-in template and cannot be removed", name);
+            }
+            bail!("no template named '{}'", name);
+        }
+        self.save()
+    }
+
+    /// Write the user templates; under `--dry-run`, print the change instead.
+    fn save(&self) -> Result<()> {
+        read_only::check("templates")?;
+        let path = templates_file_path()?;
+        let file = TemplateFile {
+            templates: self.user.clone(),
+        };
+        let text = toml::to_string_pretty(&file).context("failed to serialize templates")?;
+        if dry_run::enabled() {
+            println!("{}", dry_run::preview_write(&path, &text));
+            return Ok(());
+        }
+        ensure_config_dir_exists()?;
+        fs::write(&path, text)
+            .with_context(|| format!("failed to write templates file: {}", path.display()))?;
+        audit::file_write(&path);
+        Ok(())
+    }
+}
+
+impl Template {
+    /// Fill placeholders. A missing variable is an error naming it.
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String> {
+        crate::i18n::interpolate(&self.body, vars)
+            .map_err(|e| anyhow!("template {} (pass --var {}=...)", e, e.0))
+    }
+
+    /// The config to run this template with: provider/model overrides layered over `cfg`.
+    pub fn apply_overrides(&self, cfg: &AppConfig) -> AppConfig {
+        cfg.with_overrides(self.provider.as_ref(), self.model.as_deref())
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let ok = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !ok {
+        bail!("invalid template name '{}': use letters, digits, '-' and '_'", name);
+    }
+    Ok(())
+}
+
+/// Parse a `--var key=value` argument.
+pub fn parse_var(arg: &str) -> Result<(String, String)> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow!("invalid --var '{}': expected key=value", arg))?;
+    if key.trim().is_empty() {
+        bail!("invalid --var '{}': empty key", arg);
+    }
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+/* ---------------------------
+   aion run, aion templates
+---------------------------- */
+
+pub async fn run(args: &RunArgs, strict: bool) -> Result<ExitCode> {
+    let cfg = load_config().context("failed to load config")?;
+    let store = TemplateStore::load()?;
+    let (template, _) = store
+        .get(&args.template)
+        .ok_or_else(|| anyhow::anyhow!("no template named '{}' (see `aion templates list`)", args.template))?;
+
+    let mut vars: HashMap<String, String> = HashMap::new();
+    for v in &args.vars {
+        let (key, value) = parse_var(v)?;
+        vars.insert(key, value);
+    }
+
+    let input = match &args.input {
+        Some(text) => Some(text.clone()),
+        None if !io::stdin().is_tty() => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text).context("failed to read stdin")?;
+            Some(text)
+        }
+        None => None,
+    };
+    if let Some(input) = input {
+        vars.insert("input".to_string(), input);
+    }
+
+    let prompt = template.render(&vars)?;
+    let cfg = args.overrides.apply(&template.apply_overrides(&cfg))?;
+    crate::validate_strict(&cfg, strict)?;
+    chat::warn_context(&cfg);
+    chat::send_once(&cfg, &prompt, args.cache.use_cache(&cfg)?, true).await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn manage(action: &TemplatesAction) -> Result<ExitCode> {
+    let mut store = TemplateStore::load()?;
+    match action {
+        TemplatesAction::List => {
+            for (name, t, source) in store.list() {
+                println!(
+                    "{:<20} {:<9} {}",
+                    name,
+                    source.label(),
+                    t.description.as_deref().unwrap_or("")
+                );
+            }
+        }
+        TemplatesAction::Show { name } => {
+            let (t, source) = store
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("no template named '{}'", name))?;
+            println!("name: {} ({})", name, source.label());
+            if let Some(d) = &t.description {
+                println!("description: {}", d);
+            }
+            if let Some(p) = &t.provider {
+                println!("provider: {}", p);
+            }
+            if let Some(m) = &t.model {
+                println!("model: {}", m);
+            }
+            println!();
+            println!("{}", t.body.trim_end());
+        }
+        TemplatesAction::Add(a) => {
+            let provider = match &a.provider {
+                Some(p) => Some(p.parse::<ProviderKind>()?),
+                None => None,
+            };
+            store.add(
+                &a.name,
+                Template {
+                    description: a.description.clone(),
+                    body: a.body.clone(),
+                    provider,
+                    model: a.model.clone(),
+                },
+            )?;
+            if !dry_run::enabled() {
+                println!("Template '{}' saved to {}", a.name, templates_file_path()?.display());
+            }
+        }
+        TemplatesAction::Remove { name } => {
+            store.remove(name)?;
+            if !dry_run::enabled() {
+                println!("Template '{}' removed", name);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,218 @@
+//! `aion export` and `aion import`: AION's state in one archive, for moving to another
+//! machine. What is in it, and the checks on the way in, are `crate::backup`'s.
+//!
+//! Notes:
+//! - The archive is a gzipped tar (`.tar.gz`) with a `manifest.json` saying which
+//!   version of AION wrote it and which parts it holds. Written in the current directory
+//!   unless `--out` says where; needs `caps.write_files` and passes the path lists
+//!   (`crate::guard`). An existing file is never replaced.
+//! - Sessions only go in with `--include-sessions`. Secrets are masked on the way out,
+//!   so an import says which files still hold a `***REDACTED***` to fill in.
+//! - `aion import` writes into the config dir (config.toml where `--config` says), and
+//!   nothing at all until every part has been checked; `--only` picks parts. Entries
+//!   that are not AION state are skipped with a warning.
+//! - Refused in read-only mode; under `--dry-run` it checks everything and lists what it
+//!   would write.
+
+use crate::audit;
+use crate::backup::{self, Home, Item, Part};
+use crate::cli::{ExportArgs, ImportArgs};
+use crate::config::io::load_config;
+use crate::config::{AppConfig, Capabilities};
+use crate::dry_run;
+use crate::guard::Guard;
+use crate::i18n::format::tilde;
+use crate::read_only;
+use crate::redact::Redactor;
+use crate::sessions;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MANIFEST: &str = "manifest.json";
+const REDACTED: &str = "***REDACTED***";
+
+/// What wrote the archive, and what is in it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    aion: String,
+    config_version: u32,
+    created_at: u64,
+    parts: Vec<String>,
+}
+
+pub fn run_export(args: &ExportArgs) -> Result<ExitCode> {
+    let cfg = load_config().unwrap_or_else(|_| AppConfig::new_default());
+    let mut redaction = cfg.clone();
+    redaction.logging.redaction = true;
+
+    let stamp = sessions::format_utc(now()).replace(['-', ':'], "");
+    let target = args.out.clone().unwrap_or_else(|| format!("aion-backup-{}.tar.gz", stamp).into());
+    let target = Guard::new(&cfg.caps).check_write(&target).context("cannot write the archive")?;
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+
+    let parts = match args.include_sessions {
+        true => &Part::ALL[..],
+        false => Part::DEFAULT,
+    };
+    let items = backup::collect(&Home::current()?, parts, &Redactor::from_config(&redaction))?;
+    let manifest = Manifest {
+        aion: env!("CARGO_PKG_VERSION").to_string(),
+        config_version: AppConfig::CURRENT_VERSION,
+        created_at: now(),
+        parts: by_part(&items).keys().map(Part::to_string).collect(),
+    };
+
+    if dry_run::enabled() {
+        println!("{}", dry_run::note_write(&target));
+    } else {
+        write_archive(&target, &manifest, &items)?;
+        audit::file_write(&target);
+    }
+
+    println!("Bundled (secrets masked):");
+    for (part, names) in by_part(&items) {
+        println!("  {:<10} {}", part.to_string(), describe(&names));
+    }
+    if !args.include_sessions {
+        println!("  (no sessions; --include-sessions adds them)");
+    }
+    if !dry_run::enabled() {
+        println!("Wrote {}", target.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn run_import(args: &ImportArgs) -> Result<ExitCode> {
+    read_only::check("import")?;
+    let caps = load_config().map(|c| c.caps).unwrap_or_else(|_| AppConfig::new_default().caps);
+    let (manifest, items) = read_archive(&caps, &args.archive)?;
+    if let Some(m) = &manifest {
+        println!("{} was exported by AION {} on {}", args.archive.display(), m.aion, sessions::format_utc(m.created_at));
+    }
+
+    let items: Vec<Item> = items.into_iter().filter(|i| args.only.is_empty() || args.only.contains(&i.part)).collect();
+    for part in args.only.iter().filter(|p| !items.iter().any(|i| i.part == **p)) {
+        eprintln!("Warning: the archive holds no {}", part);
+    }
+    if items.is_empty() {
+        anyhow::bail!("nothing to import from {}", args.archive.display());
+    }
+
+    let home = Home::current()?;
+    let plan = backup::plan(&home, items, args.force).context("nothing was imported")?;
+    if dry_run::enabled() {
+        for file in &plan.files {
+            println!("{}", dry_run::note_write(&file.path));
+        }
+    } else {
+        plan.apply().context("the import stopped part way")?;
+        for file in &plan.files {
+            audit::file_write(&file.path);
+        }
+    }
+
+    let verb = if dry_run::enabled() { "Would import" } else { "Imported" };
+    println!("{} into {}:", verb, tilde(&home.dir));
+    let mut parts: BTreeMap<Part, (Vec<&str>, usize)> = BTreeMap::new();
+    for file in &plan.files {
+        let entry = parts.entry(file.item.part).or_default();
+        entry.0.push(&file.item.name);
+        entry.1 += usize::from(file.replaces);
+    }
+    for (part, (names, replaced)) in parts {
+        let replaced = if replaced > 0 { format!(", {} replaced", replaced) } else { String::new() };
+        println!("  {:<10} {}{}", part.to_string(), describe(&names), replaced);
+    }
+    for migration in &plan.migrations {
+        println!("  config upgraded: {}", migration);
+    }
+    let masked: Vec<&str> = plan
+        .files
+        .iter()
+        .filter(|f| String::from_utf8_lossy(&f.item.bytes).contains(REDACTED))
+        .map(|f| f.item.name.as_str())
+        .collect();
+    if !masked.is_empty() {
+        println!("Secrets were masked on export; fill in {} in: {}", REDACTED, masked.join(", "));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The item names of each part, in `Part` order.
+fn by_part(items: &[Item]) -> BTreeMap<Part, Vec<&str>> {
+    let mut parts: BTreeMap<Part, Vec<&str>> = BTreeMap::new();
+    for item in items {
+        parts.entry(item.part).or_default().push(&item.name);
+    }
+    parts
+}
+
+/// The file itself when there is one, else how many.
+fn describe(names: &[&str]) -> String {
+    match names {
+        [name] => name.to_string(),
+        _ => format!("{} files", names.len()),
+    }
+}
+
+fn write_archive(path: &Path, manifest: &Manifest, items: &[Item]) -> Result<()> {
+    let file = fs::File::create_new(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    append(&mut tar, MANIFEST, serde_json::to_string_pretty(manifest)?.as_bytes())?;
+    for item in items {
+        append(&mut tar, &item.name, &item.bytes)?;
+    }
+    let gz = tar.into_inner().with_context(|| format!("failed to write {}", path.display()))?;
+    gz.finish().with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// One file of the archive, readable by its owner only: the state holds chats and config.
+fn append<W: Write>(tar: &mut tar::Builder<W>, name: &str, bytes: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(now());
+    tar.append_data(&mut header, name, bytes).with_context(|| format!("failed to add {} to the archive", name))
+}
+
+/// The manifest (when there is one) and the items of the archive at `path`.
+fn read_archive(caps: &Capabilities, path: &Path) -> Result<(Option<Manifest>, Vec<Item>)> {
+    let path = Guard::new(caps).check_read(path).context("cannot read the archive")?;
+    let file = fs::File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+    let not_ours = || format!("{} is not an archive aion export wrote", path.display());
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    let mut manifest = None;
+    let mut items = Vec::new();
+    for entry in tar.entries().with_context(not_ours)? {
+        let mut entry = entry.with_context(not_ours)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // As stored: `Part::of` decides what a name may be.
+        let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).with_context(|| format!("failed to read {} from the archive", name))?;
+        match Part::of(&name) {
+            Some(part) => items.push(Item { part, name, bytes }),
+            None if name == MANIFEST => manifest = serde_json::from_slice(&bytes).ok(),
+            None => eprintln!("Warning: skipped {}: not part of AION's state", name),
+        }
+    }
+    Ok((manifest, items))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,1355 @@
+//! Full-screen chat (`ui_mode = "tui"`).
+//!
+//! Notes:
+//! - One `select!` loop drives the view: terminal events, chunks of the reply in flight,
+//!   the request's completion, and blocking saves (`spawn_blocking`). The request future
+//!   lives in `Pending`, so handling a key never drops it.
+//! - Chunks go through a channel and are appended to the last reply between frames.
+//! - `/`-commands run through the same registry as in the REPL (`crate::commands`), with
+//!   the view as their `SessionContext`: notices and errors are added to the conversation.
+//! - Ctrl+C cancels the request in flight; while idle, a second press within
+//!   `DOUBLE_PRESS_WINDOW` leaves the view (restoring the terminal first).
+//! - `/regen` (Ctrl+R) and `/undo` (Ctrl+Z) work as in the REPL. Replaced replies and
+//!   undone exchanges stay on screen, dimmed.
+//! - A reply that stopped early is followed by a notice saying why. One cut off at the
+//!   token limit is picked up with `/continue` (Ctrl+N): the rest shows as a reply of its
+//!   own and is added to the cut-off one in the conversation and the session.
+//! - The session title request runs as a background job alongside the conversation; it
+//!   is aborted on quit and its failures are not shown.
+//! - `/config` opens the config as a tree over the view (see `config_tree`); `/config set`
+//!   and `/config save` work as in the REPL.
+//! - `/provider <kind>` switches as in the REPL; what the kind still needs is asked for in
+//!   a small form over the view (see `provider_form`) instead of inline.
+//! - `/context` and its actions answer with a notice, as in the REPL. So does `/cd`, which
+//!   also moves where Ctrl+O starts and what relative attachment paths mean (`workdir`).
+//! - The view is drawn only when something changed (`frames`): input, a resize, the reply,
+//!   a background job. While a request waits on a rate limit (`providers::limit`), the
+//!   status line says so and counts down, checked every `WAIT_REDRAW` while a reply is pending.
+//! - With `features.retrieval`, Ctrl+O adds the file to the session's `Retriever` instead
+//!   of the input; each message then goes with the parts of the files most like it,
+//!   embedded inside the request future so Ctrl+C cancels that too.
+//! - A file from Ctrl+O, or `/suggest --with-history`'s history, that looks like it holds
+//!   secrets is listed masked (`secrets`) and held back; the next key decides, `y` to go on.
+//! - An ephemeral chat (see `persistence`) is never saved or titled; the status line
+//!   starts with "(ephemeral)", or "(read-only)" in read-only mode.
+//! - The input history is the REPL's (`crate::history`): ↑ on the input's first row and
+//!   ↓ on its last walk it, `/history [n]` lists it, `!<n>` sends an entry again. Ctrl+R
+//!   stays `/regen` here; reverse search is the REPL's.
+
+use super::config_tree::ConfigTree;
+use super::picker::{FilePicker, PickerEvent};
+use super::provider_form::{self, FormEvent, ProviderForm};
+use super::{markdown, splash, TerminalGuard};
+use crate::attach::Attachment;
+use crate::audit;
+use crate::costs;
+use crate::clipboard;
+use crate::i18n;
+use crate::notify;
+use crate::cancel::{self, CancelToken};
+use crate::config::io::{config_file_path, save_config};
+use crate::config::tree;
+use crate::config::{AppConfig, CapabilityDenied, ProviderConfig, ProviderKind};
+use crate::conversation::{Conversation, Exchange, CONTINUE_PROMPT};
+use crate::detect;
+use crate::dry_run;
+use crate::frames::Frames;
+use crate::history::{InputHistory, Walk};
+use crate::persistence;
+use crate::read_only;
+use crate::providers::limit;
+use crate::providers::{self, ChatResponse, LazyClient, ProviderError, Role, StopReason, Usage};
+use crate::persona;
+use crate::redact;
+use crate::retrieval::{Retriever, DEFAULT_TOP_K};
+use crate::secrets::{self, Findings};
+use crate::ui::errors;
+use crate::ui::input::TextInput;
+use crate::commands::{self, CommandState, Override, SessionContext};
+use crate::sessions::{self, Session};
+use crate::suggest;
+use crate::summary::Summary;
+use crate::update;
+use crate::workdir;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use crossterm::{
+    event::{
+        DisableBracketedPaste, EnableBracketedPaste, Event, EventStream, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::supports_keyboard_enhancement,
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::task::{AbortHandle, JoinSet};
+
+/// Most input lines shown before the box scrolls.
+const MAX_INPUT_ROWS: u16 = 6;
+/// Widest path shown in the status bar; it shares the line with provider, model, and tokens.
+/// How often the view is redrawn while a reply is pending, for the rate limit countdown.
+const WAIT_REDRAW: Duration = Duration::from_millis(250);
+
+/// Whether the terminal can host the full-screen chat; otherwise the CLI REPL is used.
+pub fn terminal_capable() -> bool {
+    if std::env::var("TERM").is_ok_and(|t| t == "dumb") {
+        return false;
+    }
+    matches!(crossterm::terminal::size(), Ok((w, h)) if w >= 20 && h >= 8)
+}
+
+pub async fn run(cfg: &AppConfig, splash: Option<&Summary>, resumed: Option<Session>) -> Result<()> {
+    let mut app = ChatApp::new(cfg, resumed)?;
+
+    let guard = TerminalGuard::enter()?;
+    let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    guard
+        .run_with(terminal, async |terminal| {
+            let _keys = InputModes::enable();
+            terminal.clear()?;
+            if let Some(summary) = splash {
+                splash::show(terminal, summary, &cfg.language).await?;
+                terminal.clear()?;
+            }
+            app.event_loop(terminal).await
+        })
+        .await
+}
+
+/// Bracketed paste and, where supported, disambiguated keys (so Shift+Enter is distinct
+/// from Enter). Restored on drop.
+struct InputModes {
+    enhanced: bool,
+}
+
+impl InputModes {
+    fn enable() -> Self {
+        let _ = execute!(io::stdout(), EnableBracketedPaste);
+        let enhanced = supports_keyboard_enhancement().unwrap_or(false)
+            && execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            )
+            .is_ok();
+        Self { enhanced }
+    }
+}
+
+impl Drop for InputModes {
+    fn drop(&mut self) {
+        if self.enhanced {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(io::stdout(), DisableBracketedPaste);
+    }
+}
+
+/* ---------------------------
+   State
+---------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    User,
+    Assistant,
+    Notice,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    kind: EntryKind,
+    text: String,
+    /// Why it left the conversation ("replaced" by `/regen`, "dropped" by `/undo`);
+    /// drawn dimmed, with this as a label.
+    superseded: Option<&'static str>,
+}
+
+enum RequestEvent {
+    Chunk(String),
+    Done(Result<ChatResponse>),
+}
+
+/// The request in flight.
+struct Pending {
+    request: BoxFuture<'static, Result<ChatResponse>>,
+    chunks: UnboundedReceiver<String>,
+    token: CancelToken,
+    prompt: String,
+    provider: ProviderConfig,
+    turn: Turn,
+    /// For `ui.notify_after_secs`.
+    started: Option<notify::Started>,
+}
+
+/// What a request is for.
+enum Turn {
+    /// A new exchange.
+    New,
+    /// `/regen`: the exchange being replaced, put back if the request does not complete,
+    /// and the entry showing its reply.
+    Regen(Exchange, Option<usize>),
+    /// `/continue`: the rest of the last reply.
+    Continue,
+}
+
+impl Pending {
+    /// The next chunk, or the result once the request has finished. Chunks are drained
+    /// before the result is reported.
+    async fn next_event(&mut self) -> RequestEvent {
+        tokio::select! {
+            biased;
+            Some(chunk) = self.chunks.recv() => RequestEvent::Chunk(chunk),
+            result = &mut self.request => RequestEvent::Done(result),
+        }
+    }
+}
+
+/// Resolves with the next event of the request in flight; never while idle.
+async fn next_request_event(pending: &mut Option<Pending>) -> RequestEvent {
+    match pending {
+        Some(p) => p.next_event().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves with the next finished background job; never while there are none.
+async fn next_job(jobs: &mut JoinSet<Job>) -> Job {
+    match jobs.join_next().await {
+        Some(Ok(job)) => job,
+        Some(Err(e)) => Job::Failed(format!("background task failed: {}", e)),
+        None => std::future::pending().await,
+    }
+}
+
+/// Held back because it looks like it holds secrets, until the next key says whether to
+/// use it anyway.
+enum Held {
+    /// A file picked with Ctrl+O, and its path as picked.
+    Attachment { path: PathBuf, attachment: Attachment },
+    /// The variables of `/suggest --with-history`'s template.
+    Suggestion(HashMap<String, String>),
+}
+
+/// Outcome of background work run off the event loop.
+enum Job {
+    Notice(String),
+    Failed(String),
+    /// A generated title for session `session`; `None` when the request failed.
+    Title { session: String, title: Option<String> },
+    /// The clipboard's text, or why it could not be read.
+    Clipboard(Result<String, String>),
+    Done,
+}
+
+struct ChatApp {
+    cfg: AppConfig,
+    client: LazyClient,
+    conversation: Conversation,
+    entries: Vec<Entry>,
+    input: TextInput,
+    /// What was typed, shared with the REPL, and where ↑/↓ are in it.
+    history: InputHistory,
+    walk: Walk,
+    /// Rows scrolled up from the bottom of the conversation; 0 follows new output.
+    scroll_back: u16,
+    /// Page size for PgUp/PgDn, updated on each draw.
+    page: u16,
+    status: String,
+    totals: Usage,
+    pending: Option<Pending>,
+    /// Config and session saves running on the blocking pool, and the title request.
+    jobs: JoinSet<Job>,
+    /// The title request in `jobs`, while one runs.
+    titling: Option<AbortHandle>,
+    /// The wait for the update check at start, in `jobs`, while it runs.
+    update_check: Option<AbortHandle>,
+    /// The Ctrl+O file picker, while open; it takes the keys.
+    picker: Option<FilePicker>,
+    /// Files attached with `features.retrieval`, from the first one on.
+    retriever: Option<Arc<Retriever>>,
+    /// What the secrets question is about, while it is asked; it takes the next key.
+    held: Option<Held>,
+    /// The `/config` view, while open; it takes the keys.
+    config_tree: Option<ConfigTree>,
+    /// The `/provider` form, while open; it takes the keys.
+    provider_form: Option<ProviderForm>,
+    session: Session,
+    state: CommandState,
+    quit: bool,
+}
+
+impl ChatApp {
+    /// A new session, or the `resumed` one with its conversation shown.
+    fn new(cfg: &AppConfig, resumed: Option<Session>) -> Result<Self> {
+        let resuming = resumed.is_some();
+        let session = resumed.unwrap_or_default();
+        audit::set_session(&session.id);
+        costs::set_session(&session.id);
+        let mut app = Self {
+            cfg: cfg.clone(),
+            client: LazyClient::new(cfg),
+            conversation: session.conversation(),
+            entries: Vec::new(),
+            input: TextInput::new(),
+            history: InputHistory::load(cfg.history.max_entries),
+            walk: Walk::default(),
+            scroll_back: 0,
+            page: 10,
+            status: "Enter send | Alt+Enter newline | Ctrl+V paste | Ctrl+O attach | PgUp/PgDn scroll | Ctrl+R regen | Ctrl+N continue | Ctrl+Z undo | /help | Ctrl+C cancel/quit"
+                .to_string(),
+            totals: Usage::default(),
+            pending: None,
+            jobs: JoinSet::new(),
+            titling: None,
+            update_check: None,
+            picker: None,
+            retriever: None,
+            held: None,
+            config_tree: None,
+            provider_form: None,
+            session,
+            state: CommandState::new(cfg),
+            quit: false,
+        };
+        if resuming {
+            let live: Vec<(EntryKind, String)> = app
+                .session
+                .live()
+                .filter_map(|m| match m.role {
+                    Role::User => Some((EntryKind::User, m.content.clone())),
+                    Role::Assistant => Some((EntryKind::Assistant, m.content.clone())),
+                    Role::System => None,
+                })
+                .collect();
+            for (kind, text) in live {
+                app.push(kind, text);
+            }
+            let notice = format!("Resumed session {} ({} exchange(s))", app.session.id, app.conversation.len());
+            app.push(EntryKind::Notice, notice);
+        }
+        Ok(app)
+    }
+
+    async fn event_loop<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let mut events = EventStream::new();
+        let lang = self.cfg.language.clone();
+        self.update_check =
+            Some(self.jobs.spawn(async move { update::notice(lang).await.map(Job::Notice).unwrap_or(Job::Done) }));
+        // Within 150ms, so not worth cancelling at exit.
+        let cfg = self.cfg.clone();
+        self.jobs.spawn(async move { detect::local_tip(&cfg).await.map(Job::Notice).unwrap_or(Job::Done) });
+        let mut frames = Frames::new(None, Instant::now());
+        // Whether the status line showed a rate limit wait, which clears without an event.
+        let mut waited = false;
+        while !self.quit {
+            if frames.take() {
+                terminal.draw(|f| self.draw(f))?;
+            }
+
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                        frames.changed();
+                        match (&mut self.picker, &mut self.config_tree) {
+                            _ if self.held.is_some() => self.answer_held(key).await,
+                            _ if self.provider_form.is_some() => {
+                                let event = self.provider_form.as_mut().map_or(FormEvent::Closed, |f| f.on_key(key));
+                                self.on_provider_form(event);
+                            }
+                            (Some(picker), _) => {
+                                let event = picker.on_key(key);
+                                self.on_picker(event);
+                            }
+                            (None, Some(view)) => {
+                                if !view.on_key(key) {
+                                    self.config_tree = None;
+                                }
+                            }
+                            (None, None) => self.on_key(key).await,
+                        }
+                    }
+                    Some(Ok(Event::Paste(_)))
+                        if self.picker.is_some() || self.config_tree.is_some() || self.provider_form.is_some() => {}
+                    Some(Ok(Event::Paste(text))) => {
+                        self.input.insert_str(&text.replace("\r\n", "\n").replace('\r', "\n"));
+                        frames.changed();
+                    }
+                    Some(Ok(Event::Resize(..))) => frames.changed(),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                    None => self.quit = true,
+                },
+                event = next_request_event(&mut self.pending) => {
+                    match event {
+                        RequestEvent::Chunk(text) => self.append_chunk(&text),
+                        RequestEvent::Done(result) => self.finish(result),
+                    }
+                    frames.changed();
+                }
+                _ = tokio::time::sleep(WAIT_REDRAW), if self.pending.is_some() => {
+                    let waiting = limit::waiting().is_some();
+                    if waiting || waited {
+                        frames.changed();
+                    }
+                    waited = waiting;
+                }
+                job = next_job(&mut self.jobs) => {
+                    frames.changed();
+                    match job {
+                        Job::Notice(text) => self.push(EntryKind::Notice, text),
+                        Job::Failed(text) => self.push(EntryKind::Error, text),
+                        Job::Title { session, title } => self.set_title(session, title),
+                        Job::Clipboard(result) => self.paste(result),
+                        Job::Done => {}
+                    }
+                }
+            }
+        }
+
+        // Dropping the request future cancels it; let pending saves finish.
+        self.pending = None;
+        for task in [self.titling.take(), self.update_check.take()].into_iter().flatten() {
+            task.abort();
+        }
+        while self.jobs.join_next().await.is_some() {}
+        Ok(())
+    }
+
+    /* ---------------------------
+       Input
+    ---------------------------- */
+
+    async fn on_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => self.interrupt(),
+            KeyCode::Char('d') if ctrl && self.input.is_empty() => self.quit = true,
+            KeyCode::Char('u') if ctrl => self.input.clear(),
+            KeyCode::Char('v') if ctrl => self.read_clipboard(),
+            KeyCode::Char('o') if ctrl => self.open_picker(),
+            KeyCode::Char('r') if ctrl && self.idle() => self.regen(None).await,
+            KeyCode::Char('n') if ctrl && self.idle() => self.resume().await,
+            KeyCode::Char('z') if ctrl && self.idle() => commands::undo(self),
+            KeyCode::Enter
+                if key.modifiers.intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+            {
+                self.input.insert('\n')
+            }
+            KeyCode::Enter => self.submit().await,
+            KeyCode::Backspace => {
+                self.input.backspace();
+            }
+            KeyCode::Delete => {
+                self.input.delete();
+            }
+            KeyCode::Left => {
+                self.input.left();
+            }
+            KeyCode::Right => {
+                self.input.right();
+            }
+            KeyCode::Home => self.input.home(),
+            KeyCode::End => self.input.end(),
+            KeyCode::Up if self.input.cursor_row() == 0 => {
+                if let Some(entry) = self.walk.older(&self.history, self.input.as_str()) {
+                    self.input.set(entry);
+                }
+            }
+            KeyCode::Down if self.input.on_last_row() => {
+                if let Some(entry) = self.walk.newer(&self.history) {
+                    self.input.set(entry);
+                }
+            }
+            KeyCode::PageUp => self.scroll_back = self.scroll_back.saturating_add(self.page),
+            KeyCode::PageDown => self.scroll_back = self.scroll_back.saturating_sub(self.page),
+            KeyCode::Char(ch) if !ctrl => self.input.insert(ch),
+            _ => {}
+        }
+    }
+
+    /// Paste the clipboard into the input once it has been read.
+    fn read_clipboard(&mut self) {
+        self.status = "Reading the clipboard…".to_string();
+        self.jobs.spawn(async { Job::Clipboard(clipboard::read().await.map_err(|e| format!("{:#}", e))) });
+    }
+
+    fn paste(&mut self, result: Result<String, String>) {
+        self.status = match result {
+            Ok(text) if text.is_empty() => "The clipboard is empty.".to_string(),
+            Ok(text) => {
+                self.input.insert_str(&text);
+                format!("Pasted {} line(s).", text.lines().count().max(1))
+            }
+            Err(e) => format!("Clipboard unavailable: {}", e),
+        };
+    }
+
+    fn open_picker(&mut self) {
+        if !self.cfg.caps.read_files {
+            let denied = anyhow!(CapabilityDenied { cap: "read_files" });
+            self.status = errors::render(&denied, &self.cfg);
+            return;
+        }
+        match workdir::current(&self.cfg) {
+            Ok(dir) => self.picker = Some(FilePicker::new(dir.path)),
+            Err(e) => self.status = errors::render(&e, &self.cfg),
+        }
+    }
+
+    fn on_picker(&mut self, event: PickerEvent) {
+        match event {
+            PickerEvent::Open => {}
+            PickerEvent::Closed => self.picker = None,
+            PickerEvent::Picked(path) => {
+                self.picker = None;
+                self.attach(&path);
+            }
+        }
+    }
+
+    /// Read `path` and attach it, asking first when it looks like it holds secrets.
+    fn attach(&mut self, path: &Path) {
+        let read = workdir::current(&self.cfg).and_then(|dir| Attachment::read(&self.cfg.caps, &dir.join(path)));
+        let checked = read.and_then(|attachment| {
+            let findings = secrets::scan(&self.cfg, [(path.display().to_string(), attachment.text.as_str())])?;
+            Ok((attachment, findings))
+        });
+        match checked {
+            Ok((attachment, findings)) if !findings.is_empty() => {
+                self.hold(Held::Attachment { path: path.to_path_buf(), attachment }, &findings)
+            }
+            Ok((attachment, _)) => self.add_attachment(path, &attachment),
+            Err(e) => self.status = errors::render(&e, &self.cfg),
+        }
+    }
+
+    /// Put the contents, fenced, into the input on lines of their own; with
+    /// `features.retrieval`, into the session's index.
+    fn add_attachment(&mut self, path: &Path, attachment: &Attachment) {
+        if self.cfg.features.retrieval {
+            return self.index(attachment);
+        }
+        if self.input.cursor_column() > 0 {
+            self.input.insert('\n');
+        }
+        self.input.insert_str(&attachment.fenced());
+        let size = i18n::format::format_bytes(&self.cfg.language, attachment.text.len() as u64);
+        self.status = format!("Attached {} ({}).", path.display(), size);
+    }
+
+    /// List `findings` and ask whether to go on with `held` (`answer_held`).
+    fn hold(&mut self, held: Held, findings: &Findings) {
+        let mut notice = "Possible secrets in what is about to be sent:".to_string();
+        for line in findings.report() {
+            notice.push_str("\n  ");
+            notice.push_str(&line);
+        }
+        self.push(EntryKind::Notice, notice);
+        let action = match held {
+            Held::Attachment { .. } => "Attach",
+            Held::Suggestion(_) => "Send",
+        };
+        self.status = format!("Possible secrets (listed above). y = {} anyway | any other key = Cancel", action);
+        self.held = Some(held);
+    }
+
+    /// `y` goes on with what `hold` held back; any other key drops it.
+    async fn answer_held(&mut self, key: KeyEvent) {
+        let Some(held) = self.held.take() else { return };
+        if key.code != KeyCode::Char('y') || key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.status = "Cancelled; nothing was sent.".to_string();
+            return;
+        }
+        match held {
+            Held::Attachment { path, attachment } => self.add_attachment(&path, &attachment),
+            Held::Suggestion(vars) => {
+                if let Err(e) = self.send_template(suggest::TEMPLATE, vars).await {
+                    self.fail(&e);
+                }
+            }
+        }
+    }
+
+    /// Add `attachment` to the session's index, creating it (and its embedding client) on
+    /// the first one.
+    fn index(&mut self, attachment: &Attachment) {
+        let retriever = match &self.retriever {
+            Some(retriever) => retriever.clone(),
+            None => match providers::build_embedding_client(&self.cfg) {
+                Ok(client) => self.retriever.insert(Arc::new(Retriever::new(client, DEFAULT_TOP_K))).clone(),
+                Err(e) => {
+                    self.status = errors::render(&e, &self.cfg);
+                    return;
+                }
+            },
+        };
+        let passages = retriever.add(&attachment.path.display().to_string(), &attachment.text);
+        self.status = format!(
+            "Indexed {} ({} part(s)); each message sends the parts most like it.",
+            attachment.path.display(),
+            passages
+        );
+    }
+
+    fn interrupt(&mut self) {
+        if let Some(p) = &self.pending {
+            p.token.cancel();
+            return;
+        }
+        if cancel::register_press() {
+            self.quit = true;
+        } else {
+            self.status = "Press Ctrl+C again to exit".to_string();
+        }
+    }
+
+    /// `true` unless a reply is still coming, which the status line then says.
+    fn idle(&mut self) -> bool {
+        if self.pending.is_some() {
+            self.status = "Still waiting for the reply (Ctrl+C cancels it)".to_string();
+            return false;
+        }
+        self.scroll_back = 0;
+        true
+    }
+
+    async fn submit(&mut self) {
+        if self.input.as_str().trim().is_empty() {
+            return;
+        }
+        if self.pending.is_some() {
+            self.status = "Still waiting for the reply (Ctrl+C cancels it)".to_string();
+            return;
+        }
+        let typed = self.input.take();
+        self.walk.reset();
+        self.scroll_back = 0;
+        let entry = match self.history.recall(&typed) {
+            Ok(Some(recalled)) => recalled.to_string(),
+            Ok(None) => typed,
+            Err(e) => return self.push(EntryKind::Error, e.to_string()),
+        };
+        self.remember(&entry);
+        let text = entry.trim().to_string();
+
+        if commands::is_command(&text) {
+            commands::run(&text, self).await;
+        } else {
+            self.start(text, None);
+        }
+    }
+
+    /// `/provider <kind>`: switch, or open the form when the kind needs more.
+    fn switch_provider(&mut self, kind: ProviderKind, save: bool) {
+        let draft = self.draft_for(&kind);
+        match provider_form::needed(&draft) {
+            Some(missing) => self.provider_form = Some(ProviderForm::new(draft, missing, save)),
+            None => {
+                let mut next = self.cfg.clone();
+                next.provider = draft;
+                self.apply_config(next, save);
+            }
+        }
+    }
+
+    fn on_provider_form(&mut self, event: FormEvent) {
+        match event {
+            FormEvent::Open => {}
+            FormEvent::Closed => {
+                self.provider_form = None;
+                self.status = "Provider unchanged.".to_string();
+            }
+            FormEvent::Done { provider, save } => {
+                self.provider_form = None;
+                let mut next = self.cfg.clone();
+                next.provider = *provider;
+                self.apply_config(next, save);
+            }
+        }
+    }
+
+    fn open_config(&mut self) {
+        let keys: Vec<&str> = self.state.edits.keys().collect();
+        match tree::current(&self.cfg, &keys) {
+            Ok(nodes) => {
+                let title = config_file_path().map(|p| i18n::format::tilde(&p)).unwrap_or_default();
+                self.config_tree = Some(ConfigTree::new(title, nodes, keys.len()));
+            }
+            Err(e) => self.push(EntryKind::Error, errors::render(&e, &self.cfg)),
+        }
+    }
+
+    /* ---------------------------
+       Requests
+    ---------------------------- */
+
+    /// Send `prompt` as the next user turn. `client` (with its config) overrides the
+    /// session client for this request only.
+    fn start(&mut self, prompt: String, client: Option<Override>) {
+        self.push(EntryKind::User, prompt.clone());
+        self.request(prompt, client, Turn::New);
+    }
+
+    /// Send `prompt` after the conversation. `client` (with its config) overrides the
+    /// session client for this request only.
+    fn request(&mut self, prompt: String, client: Option<Override>, turn: Turn) {
+        let (client, provider) = match client {
+            Some((client, cfg)) => (client, cfg.provider),
+            None => match self.client.get() {
+                Ok(client) => (client, self.cfg.provider.clone()),
+                Err(e) => {
+                    let e = e.context("failed to create provider client");
+                    self.push(EntryKind::Error, format!("Error: {}", errors::render(&e, &self.cfg)));
+                    match turn {
+                        Turn::New => self.state.last_failed = Some(prompt),
+                        Turn::Regen(exchange, shown) => self.restore(exchange, shown),
+                        Turn::Continue => {}
+                    }
+                    return;
+                }
+            },
+        };
+        self.push(EntryKind::Assistant, String::new());
+
+        let history = self.conversation.with_prompt(&prompt, self.cfg.sessions.max_context_tokens);
+        let messages = persona::compose(&self.cfg, &history);
+        let token = CancelToken::new();
+        let request_token = token.clone();
+        let (tx, chunks) = mpsc::unbounded_channel();
+        // A continuation is not a question; passages would only get in its way.
+        let retriever = self.retriever.clone().filter(|_| !matches!(turn, Turn::Continue));
+
+        let request = Box::pin(async move {
+            let mut messages = messages;
+            if let (Some(retriever), Some(last)) = (retriever, messages.last_mut()) {
+                last.content = retriever.prompt(&last.content, &request_token).await?;
+            }
+            client
+                .chat(&messages, &request_token, &mut |chunk| {
+                    let _ = tx.send(chunk.to_string());
+                })
+                .await
+        });
+
+        self.status = "Waiting for reply…".to_string();
+        self.pending = Some(Pending { request, chunks, token, prompt, provider, turn, started: notify::start() });
+    }
+
+    /// A regeneration did not complete: the old reply is current again.
+    fn restore(&mut self, exchange: Exchange, shown: Option<usize>) {
+        self.conversation.push_exchange(exchange);
+        if let Some(entry) = shown.and_then(|i| self.entries.get_mut(i)) {
+            entry.superseded = None;
+        }
+    }
+
+    fn append_chunk(&mut self, text: &str) {
+        if let Some(last) = self.entries.last_mut() {
+            last.text.push_str(text);
+        }
+    }
+
+    fn finish(&mut self, result: Result<ChatResponse>) {
+        let Some(mut pending) = self.pending.take() else { return };
+        // The request may have sent its last chunks in the same poll that completed it.
+        while let Ok(text) = pending.chunks.try_recv() {
+            self.append_chunk(&text);
+        }
+
+        self.status = "Ready".to_string();
+        if !matches!(&result, Err(e) if matches!(ProviderError::find(e), Some(ProviderError::Cancelled))) {
+            notify::finish(pending.started, notify::Task::Reply { model: &pending.provider.model });
+        }
+        match result {
+            Ok(resp) => {
+                if let Some(u) = resp.usage {
+                    self.totals.input_tokens += u.input_tokens;
+                    self.totals.output_tokens += u.output_tokens;
+                }
+                if let Some(fallback) = &resp.fallback {
+                    self.push(EntryKind::Notice, redact::redact(&fallback.notice()));
+                }
+                if let Some(stop) = resp.stop {
+                    self.push(EntryKind::Notice, stop.notice(&self.cfg.language));
+                }
+                self.state.truncated = resp.stop == Some(StopReason::MaxTokens);
+                if self.state.truncated {
+                    self.status = "Cut off at the token limit | Ctrl+N continue".to_string();
+                }
+                let (kind, model) = resp.answered_by(&pending.provider);
+                match pending.turn {
+                    Turn::New => {
+                        self.session.record(&pending.prompt, &resp.content, kind, model, resp.usage);
+                        self.conversation.push(pending.prompt, resp.content);
+                        self.state.last_failed = None;
+                    }
+                    Turn::Regen(..) => {
+                        self.session.regenerate(&resp.content, kind, model, resp.usage);
+                        self.conversation.push(pending.prompt, resp.content);
+                    }
+                    Turn::Continue => {
+                        self.session.extend_reply(&resp.content, resp.usage);
+                        self.conversation.extend_last(&resp.content);
+                    }
+                }
+                if self.save_session() {
+                    self.request_title();
+                }
+            }
+            Err(e) => {
+                // The failed turn never joined the conversation, so it is not resent.
+                if self.entries.last().is_some_and(|l| l.kind == EntryKind::Assistant && l.text.is_empty()) {
+                    self.entries.pop();
+                }
+                let cancelled = matches!(ProviderError::find(&e), Some(ProviderError::Cancelled));
+                if cancelled {
+                    self.push(EntryKind::Notice, errors::render(&e, &self.cfg));
+                } else {
+                    self.push(EntryKind::Error, format!("Error: {}", errors::render(&e, &self.cfg)));
+                }
+                match pending.turn {
+                    Turn::New if cancelled => self.state.last_failed = None,
+                    Turn::New => self.state.last_failed = Some(pending.prompt),
+                    Turn::Regen(exchange, shown) => self.restore(exchange, shown),
+                    Turn::Continue => {}
+                }
+            }
+        }
+        for warning in costs::take_warnings() {
+            self.push(EntryKind::Notice, warning.to_string());
+        }
+    }
+
+    /// Add `entry` to the input history, and to its file on the blocking pool unless this
+    /// is a dry run. A failed save is not reported: history is a convenience.
+    fn remember(&mut self, entry: &str) {
+        if self.history.push(entry) && !dry_run::enabled() {
+            let mut unsaved = self.history.take_unsaved();
+            self.jobs.spawn_blocking(move || {
+                let _ = unsaved.save();
+                Job::Done
+            });
+        }
+    }
+
+    /// Save the session on the blocking pool (or, with `--dry-run`, say where it would
+    /// go). `true` when a save was started; never when ephemeral.
+    fn save_session(&mut self) -> bool {
+        if !persistence::policy().keeps_history() {
+            return false;
+        }
+        if dry_run::enabled() {
+            if let Ok(path) = self.session.path() {
+                self.push(EntryKind::Notice, dry_run::note_write(&path));
+            }
+            return false;
+        }
+        let session = self.session.clone();
+        self.jobs.spawn_blocking(move || match session.save() {
+            Ok(()) => Job::Done,
+            Err(e) => Job::Failed(format!("Warning: session not saved: {:#}", e)),
+        });
+        true
+    }
+
+    /// Start the title request when the session wants one and none is running.
+    fn request_title(&mut self) {
+        if self.titling.is_some() || !self.session.wants_title(&self.cfg) {
+            return;
+        }
+        let Ok(client) = self.client.get() else { return };
+        let session = self.session.clone();
+        self.titling = Some(self.jobs.spawn(async move {
+            let title = sessions::generate_title(client.as_ref(), &session, &CancelToken::new()).await;
+            Job::Title { session: session.id, title: title.ok() }
+        }));
+    }
+
+    /// Store a finished title request's result, unless `/clear` started a new session since.
+    fn set_title(&mut self, session: String, title: Option<String>) {
+        self.titling = None;
+        let Some(title) = title.filter(|_| session == self.session.id) else { return };
+        self.session.title = Some(title);
+        let session = self.session.clone();
+        self.jobs.spawn_blocking(move || match session.save() {
+            Ok(()) => Job::Done,
+            Err(e) => Job::Failed(format!("Warning: session not saved: {:#}", e)),
+        });
+    }
+
+    fn push(&mut self, kind: EntryKind, text: String) {
+        let text = if kind == EntryKind::Error { redact::redact(&text) } else { text };
+        self.entries.push(Entry { kind, text, superseded: None });
+    }
+
+    /* ---------------------------
+       Drawing
+    ---------------------------- */
+
+    fn draw(&mut self, f: &mut Frame) {
+        let input_rows = (self.input.as_str().split('\n').count() as u16).clamp(1, MAX_INPUT_ROWS);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),
+                Constraint::Length(input_rows + 2),
+                Constraint::Length(1),
+            ])
+            .split(f.size());
+
+        self.draw_conversation(f, chunks[0]);
+        self.draw_input(f, chunks[1]);
+        self.draw_status(f, chunks[2]);
+        if let Some(view) = &self.config_tree {
+            view.render(f, f.size());
+        }
+        if let Some(picker) = &self.picker {
+            picker.render(f, f.size());
+        }
+        if let Some(form) = &self.provider_form {
+            form.render(f, f.size());
+        }
+    }
+
+    fn draw_conversation(&mut self, f: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            " AION ",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ));
+        let inner = block.inner(area);
+        let paragraph = Paragraph::new(conversation_lines(&self.entries)).wrap(Wrap { trim: false });
+
+        let total = paragraph.line_count(inner.width) as u16;
+        let max_scroll = total.saturating_sub(inner.height);
+        self.scroll_back = self.scroll_back.min(max_scroll);
+        self.page = inner.height.saturating_sub(1).max(1);
+
+        f.render_widget(paragraph.block(block).scroll((max_scroll - self.scroll_back, 0)), area);
+    }
+
+    fn draw_input(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title(" Message ");
+        let inner = block.inner(area);
+
+        let lines: Vec<&str> = self.input.as_str().split('\n').collect();
+        // Show the last rows, unless the cursor is on a row above them.
+        let row = self.input.cursor_row();
+        let skip = lines.len().saturating_sub(inner.height as usize).min(row);
+        let visible: Vec<Line> = lines[skip..].iter().map(|l| Line::from(l.to_string())).collect();
+        f.render_widget(Paragraph::new(visible).block(block), area);
+
+        let x = (self.input.cursor_column() as u16).min(inner.width.saturating_sub(1));
+        let y = (row - skip) as u16;
+        f.set_cursor(inner.x + x, inner.y + y);
+    }
+
+    fn draw_status(&self, f: &mut Frame, area: Rect) {
+        let text = format!(
+            " {}{} · {} · tokens {} in / {} out · {}",
+            match (read_only::active(), persistence::policy().ephemeral) {
+                (Some(_), _) => "(read-only) · ",
+                (None, true) => "(ephemeral) · ",
+                (None, false) => "",
+            },
+            self.cfg.provider.kind.name(),
+            self.cfg.provider.model,
+            self.totals.input_tokens,
+            self.totals.output_tokens,
+            match limit::waiting().filter(|_| self.pending.is_some()) {
+                Some(wait) => wait.to_string(),
+                None => self.status.clone(),
+            }
+        );
+        let style = Style::default().fg(Color::Black).bg(Color::Cyan);
+        f.render_widget(Paragraph::new(redact::redact(&text)).style(style), area);
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionContext for ChatApp {
+    fn cfg(&self) -> &AppConfig {
+        &self.cfg
+    }
+
+    fn cfg_mut(&mut self) -> &mut AppConfig {
+        &mut self.cfg
+    }
+
+    fn state(&self) -> &CommandState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut CommandState {
+        &mut self.state
+    }
+
+    fn session(&self) -> &Session {
+        &self.session
+    }
+
+    fn history(&self) -> &InputHistory {
+        &self.history
+    }
+
+    fn conversation_mut(&mut self) -> &mut Conversation {
+        &mut self.conversation
+    }
+
+    fn notice(&mut self, text: String) {
+        self.push(EntryKind::Notice, text);
+    }
+
+    fn error(&mut self, text: String) {
+        self.push(EntryKind::Error, text);
+    }
+
+    fn preview(&mut self, text: String) {
+        self.push(EntryKind::Notice, fence_preview(&text));
+    }
+
+    fn install(&mut self, next: AppConfig) {
+        self.client = LazyClient::with_params(&next, &self.state.params);
+        self.cfg = next;
+    }
+
+    /// Saved on the blocking pool; the outcome shows when the job ends.
+    fn write_config(&mut self, cfg: AppConfig, done: String) {
+        self.jobs.spawn_blocking(move || match save_config(&cfg) {
+            Ok(()) => Job::Notice(done),
+            Err(e) => Job::Failed(format!("{:#}", e)),
+        });
+    }
+
+    fn switch_provider(&mut self, kind: ProviderKind, save: bool) {
+        ChatApp::switch_provider(self, kind, save)
+    }
+
+    fn show_config(&mut self) {
+        self.open_config();
+    }
+
+    fn drop_last(&mut self) -> bool {
+        if self.conversation.pop().is_none() {
+            return false;
+        }
+        if let Some(reply) = self.entries.iter().rposition(|e| e.kind == EntryKind::Assistant && e.superseded.is_none()) {
+            let prompt = self.entries[..reply].iter().rposition(|e| e.kind == EntryKind::User).unwrap_or(reply);
+            for entry in &mut self.entries[prompt..=reply] {
+                if matches!(entry.kind, EntryKind::User | EntryKind::Assistant) {
+                    entry.superseded.get_or_insert("dropped");
+                }
+            }
+        }
+        self.session.undo();
+        self.state.truncated = false;
+        self.save_session();
+        true
+    }
+
+    fn clear(&mut self) {
+        self.conversation.clear();
+        self.entries.clear();
+        self.state.truncated = false;
+        self.session = Session::new();
+        audit::set_session(&self.session.id);
+        costs::set_session(&self.session.id);
+    }
+
+    fn read_clipboard(&mut self) {
+        ChatApp::read_clipboard(self)
+    }
+
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    async fn send_with(&mut self, prompt: String, with: Option<Override>) {
+        self.start(prompt, with);
+    }
+
+    async fn send_suggestion(&mut self, suggestion: suggest::Suggestion) -> Result<()> {
+        if !suggestion.secrets.is_empty() {
+            self.hold(Held::Suggestion(suggestion.vars), &suggestion.secrets);
+            return Ok(());
+        }
+        self.send_template(suggest::TEMPLATE, suggestion.vars).await
+    }
+
+    /// The shown reply is dimmed as replaced, and current again if the request fails.
+    async fn resend(&mut self, last: Exchange, with: Option<Override>) {
+        let shown = self.entries.iter().rposition(|e| e.kind == EntryKind::Assistant && e.superseded.is_none());
+        if let Some(i) = shown {
+            self.entries[i].superseded = Some("replaced");
+        }
+        let model = with.as_ref().map_or(&self.cfg, |(_, cfg)| cfg).provider.model.clone();
+        self.push(EntryKind::Notice, format!("Regenerating with {}…", model));
+        let prompt = last.prompt.clone();
+        self.request(prompt, with, Turn::Regen(last, shown));
+    }
+
+    /// The rest shows as a reply of its own.
+    async fn continue_reply(&mut self) {
+        self.request(CONTINUE_PROMPT.to_string(), None, Turn::Continue);
+    }
+}
+
+/// Keep a dry-run diff out of the Markdown renderer: the summary line, then the diff fenced.
+fn fence_preview(preview: &str) -> String {
+    match preview.split_once('\n') {
+        Some((summary, diff)) => format!("{}\n\n```diff\n{}\n```", summary, diff),
+        None => preview.to_string(),
+    }
+}
+
+fn conversation_lines(entries: &[Entry]) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    for entry in entries {
+        match (entry.kind, entry.superseded) {
+            (EntryKind::User, Some(reason)) => {
+                let label = format!("You ({})", reason);
+                lines.push(Line::from(Span::styled(label, dim.add_modifier(Modifier::BOLD))));
+                lines.extend(entry.text.lines().map(|l| Line::from(Span::styled(l.to_string(), dim))));
+            }
+            (EntryKind::User, None) => {
+                lines.push(Line::from(Span::styled(
+                    "You",
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                )));
+                lines.extend(entry.text.lines().map(|l| Line::from(l.to_string())));
+            }
+            (EntryKind::Assistant, Some(reason)) => {
+                let label = format!("AION ({})", reason);
+                lines.push(Line::from(Span::styled(label, dim.add_modifier(Modifier::BOLD))));
+                lines.extend(markdown::render(&entry.text).into_iter().map(|l| l.style(dim)));
+            }
+            (EntryKind::Assistant, None) => {
+                lines.push(Line::from(Span::styled(
+                    "AION",
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                )));
+                lines.extend(markdown::render(&entry.text));
+            }
+            (EntryKind::Notice, _) => {
+                lines.extend(markdown::render(&entry.text).into_iter().map(|l| l.style(dim)));
+            }
+            (EntryKind::Error, _) => {
+                lines.extend(
+                    entry
+                        .text
+                        .lines()
+                        .map(|l| Line::from(Span::styled(l.to_string(), Style::default().fg(Color::Red)))),
+                );
+            }
+        }
+        lines.push(Line::from(""));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::buffer_rows as rows;
+    use super::*;
+    use crate::providers::{Message, OnChunk};
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+
+    /// Sends `partial`, then waits until cancelled, as a provider does mid-stream.
+    struct Stalls;
+
+    #[async_trait]
+    impl providers::ChatClient for Stalls {
+        async fn chat(&self, _: &[Message], cancel: &CancelToken, on_chunk: &mut OnChunk<'_>) -> Result<ChatResponse> {
+            on_chunk("partial");
+            cancel.cancelled().await;
+            Err(ProviderError::Cancelled.into())
+        }
+    }
+
+    fn stalls() -> Option<Override> {
+        Some((Arc::new(Stalls), AppConfig::new_default()))
+    }
+
+    /// Ctrl+C while a reply streams in, the way the loop's `select!` sees it: the chunk
+    /// that arrived, the key, then the request ending.
+    async fn cancel_midway(app: &mut ChatApp) {
+        assert!(matches!(next_request_event(&mut app.pending).await, RequestEvent::Chunk(c) if c == "partial"));
+        app.append_chunk("partial");
+        app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)).await;
+        assert!(app.pending.as_ref().is_some_and(|p| p.token.is_cancelled()));
+        assert!(!app.quit, "the first Ctrl+C only cancels");
+        match next_request_event(&mut app.pending).await {
+            RequestEvent::Done(result) => app.finish(result),
+            RequestEvent::Chunk(c) => panic!("chunk {:?} after the cancel", c),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_request_leaves_the_conversation_and_session_as_they_were() {
+        let mut app = ChatApp::new(&AppConfig::new_default(), None).unwrap();
+        app.conversation.push("before", "kept");
+        app.start("stop me".to_string(), stalls());
+        cancel_midway(&mut app).await;
+
+        assert!(app.pending.is_none());
+        assert_eq!(app.status, "Ready");
+        assert_eq!(app.conversation.messages().len(), 2);
+        assert_eq!(app.conversation.last(), Some(&Exchange { prompt: "before".into(), reply: "kept".into() }));
+        assert!(app.session.messages.is_empty());
+        // Not offered for /retry either: it was stopped, not failed.
+        assert_eq!(app.state.last_failed, None);
+        assert_eq!(app.entries.last().unwrap().kind, EntryKind::Notice);
+
+        // Ready for the next one.
+        app.start("again".to_string(), stalls());
+        assert!(app.pending.is_some());
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_regen_puts_the_old_reply_back() {
+        let mut app = ChatApp::new(&AppConfig::new_default(), None).unwrap();
+        app.conversation.push("q", "old reply");
+        app.push(EntryKind::User, "q".to_string());
+        app.push(EntryKind::Assistant, "old reply".to_string());
+        let last = app.conversation.pop().unwrap();
+        app.resend(last, stalls()).await;
+        assert_eq!(app.entries[1].superseded, Some("replaced"));
+        cancel_midway(&mut app).await;
+
+        assert_eq!(app.conversation.len(), 1);
+        assert_eq!(app.conversation.last(), Some(&Exchange { prompt: "q".into(), reply: "old reply".into() }));
+        assert_eq!(app.entries[1].superseded, None);
+        assert!(app.session.messages.is_empty());
+    }
+
+    fn render(app: &mut ChatApp, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// A view with an exchange, a replaced reply, a notice, and an error.
+    fn app() -> ChatApp {
+        let mut app = ChatApp::new(&AppConfig::new_default(), None).unwrap();
+        app.status = "Ready".to_string();
+        app.push(EntryKind::User, "What is **2+2**?".to_string());
+        app.push(EntryKind::Assistant, "Five.".to_string());
+        app.entries[1].superseded = Some("replaced");
+        app.push(EntryKind::Notice, "Regenerating with mistral…".to_string());
+        app.push(EntryKind::Assistant, "It is **4**.\n- even\n- small".to_string());
+        app.push(EntryKind::Error, "Error: rate limited".to_string());
+        app.input.insert('h');
+        app.input.insert('i');
+        app
+    }
+
+    #[test]
+    fn draws_the_conversation_input_and_status() {
+        let buffer = render(&mut app(), 48, 22);
+        let expected = [
+            "┌ AION ────────────────────────────────────────┐",
+            "│You                                           │",
+            "│What is **2+2**?                              │",
+            "│                                              │",
+            "│AION (replaced)                               │",
+            "│Five.                                         │",
+            "│                                              │",
+            "│Regenerating with mistral…                    │",
+            "│                                              │",
+            "│AION                                          │",
+            "│It is 4.                                      │",
+            "│• even                                        │",
+            "│• small                                       │",
+            "│                                              │",
+            "│Error: rate limited                           │",
+            "│                                              │",
+            "│                                              │",
+            "└──────────────────────────────────────────────┘",
+            "┌ Message ─────────────────────────────────────┐",
+            "│hi                                            │",
+            "└──────────────────────────────────────────────┘",
+            " Ollama · mistral · tokens 0 in / 0 out · Ready",
+        ];
+        assert_eq!(rows(&buffer), expected);
+
+        // Labels: yours in green, the replaced reply dimmed, the current one in cyan; the
+        // error in red, the status line on cyan.
+        assert_eq!(buffer.get(1, 1).fg, Color::Green);
+        assert_eq!(buffer.get(1, 4).fg, Color::DarkGray);
+        assert_eq!(buffer.get(1, 9).fg, Color::Cyan);
+        assert_eq!(buffer.get(1, 14).fg, Color::Red);
+        assert_eq!(buffer.get(0, 21).bg, Color::Cyan);
+    }
+
+    #[test]
+    fn follows_the_end_of_a_long_conversation() {
+        let mut app = app();
+        for n in 0..20 {
+            app.push(EntryKind::Notice, format!("notice {}", n));
+        }
+        let shown = |rows: Vec<String>| -> Vec<String> {
+            rows[1..7].iter().map(|row| row.trim_matches(|c| c == '│' || c == ' ').to_string()).collect()
+        };
+        assert_eq!(shown(rows(&render(&mut app, 48, 12))), ["notice 17", "", "notice 18", "", "notice 19", ""]);
+
+        // Scrolled back, older rows; past the top, the top.
+        app.scroll_back = 4;
+        assert_eq!(shown(rows(&render(&mut app, 48, 12))), ["notice 15", "", "notice 16", "", "notice 17", ""]);
+        app.scroll_back = u16::MAX;
+        assert_eq!(shown(rows(&render(&mut app, 48, 12)))[..2], ["You", "What is **2+2**?"]);
+    }
+
+    #[test]
+    fn grows_the_input_box_with_its_lines() {
+        let mut app = app();
+        for ch in "\nsecond\nthird".chars() {
+            app.input.insert(ch);
+        }
+        let rows = rows(&render(&mut app, 48, 16));
+        assert_eq!(
+            rows[10..16],
+            [
+                "┌ Message ─────────────────────────────────────┐",
+                "│hi                                            │",
+                "│second                                        │",
+                "│third                                         │",
+                "└──────────────────────────────────────────────┘",
+                " Ollama · mistral · tokens 0 in / 0 out · Ready",
+            ]
+        );
+    }
+
+    #[test]
+    fn wraps_to_the_width_it_is_given() {
+        for width in [60, 80, 120] {
+            let mut app = app();
+            app.push(EntryKind::Assistant, "word ".repeat(40).trim_end().to_string());
+            let rows = rows(&render(&mut app, width, 30));
+            assert!(rows.iter().all(|row| row.chars().count() <= width as usize), "{}", width);
+            // As many words a row as fit between the borders.
+            let per_row = (width as usize - 1) / "word ".len();
+            let wrapped = rows.iter().filter(|row| row.starts_with("│word")).count();
+            assert_eq!(wrapped, 40_usize.div_ceil(per_row), "{} columns", width);
+            assert!(rows[26].starts_with("┌ Message "), "{}: {:?}", width, rows);
+            assert_eq!(rows[29], " Ollama · mistral · tokens 0 in / 0 out · Ready", "{}", width);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! The `/config` view in the chat: the session's config as a collapsible tree, each value
+//! tagged with where it came from (see `config::tree`).
+//!
+//! Notes:
+//! - Keyboard only: ↑/↓ move, → or Enter opens a table, ← closes it (or goes to the
+//!   table above), Esc or `q` closes the view.
+//! - Tables start closed. A closed table is marked when something under it is unsaved or
+//!   differs for this session, so those stay easy to find.
+//! - Values are redacted when the view is built; it is read-only, `/config set` edits.
+
+use super::step_selection;
+use crate::config::tree::{Node, Source};
+use crate::redact;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use std::collections::HashSet;
+
+pub struct ConfigTree {
+    /// Shown in the title: the config file.
+    title: String,
+    nodes: Vec<Node>,
+    /// Paths of the open tables.
+    open: HashSet<String>,
+    unsaved: usize,
+    state: ListState,
+}
+
+/// A visible row: its node and how deep it is.
+struct Row<'a> {
+    node: &'a Node,
+    depth: usize,
+}
+
+impl ConfigTree {
+    pub fn new(title: String, mut nodes: Vec<Node>, unsaved: usize) -> Self {
+        nodes.iter_mut().for_each(redact_values);
+        Self { title, nodes, open: HashSet::new(), unsaved, state: ListState::default().with_selected(Some(0)) }
+    }
+
+    /// `false` once the view is closed.
+    pub fn on_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let index = self.state.selected().unwrap_or(0);
+        let rows = self.rows();
+        let len = rows.len();
+        // The selected table's path, and the row of the table it is in.
+        let table = rows.get(index).filter(|r| r.node.value.is_none()).map(|r| r.node.path.clone());
+        let parent = rows.get(index).and_then(|r| rows[..index].iter().rposition(|p| p.depth < r.depth));
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Up | KeyCode::Down => {
+                step_selection(&mut self.state, len, key.code);
+            }
+            KeyCode::Right | KeyCode::Enter => {
+                if let Some(path) = table {
+                    self.open.insert(path);
+                }
+            }
+            KeyCode::Left => match table {
+                Some(path) if self.open.contains(&path) => {
+                    self.open.remove(&path);
+                }
+                _ if parent.is_some() => self.state.select(parent),
+                _ => {}
+            },
+            _ => {}
+        }
+        true
+    }
+
+    /// The nodes under open tables, in order.
+    fn rows(&self) -> Vec<Row<'_>> {
+        fn add<'a>(rows: &mut Vec<Row<'a>>, open: &HashSet<String>, nodes: &'a [Node], depth: usize) {
+            for node in nodes {
+                rows.push(Row { node, depth });
+                if open.contains(&node.path) {
+                    add(rows, open, &node.children, depth + 1);
+                }
+            }
+        }
+        let mut rows = Vec::new();
+        add(&mut rows, &self.open, &self.nodes, 0);
+        rows
+    }
+
+    /// Drawn centred over `area`.
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let width = (area.width * 8 / 10).max(area.width.min(50));
+        let height = (area.height * 8 / 10).max(area.height.min(10));
+        let rect = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            format!(" Config · {} ", self.title),
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ));
+        let inner = block.inner(rect);
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(inner);
+
+        let items: Vec<ListItem> = self
+            .rows()
+            .iter()
+            .map(|row| {
+                let indent = "  ".repeat(row.depth);
+                let line = match (&row.node.value, &row.node.source) {
+                    (Some(value), Some(source)) => {
+                        let mut spans = vec![Span::raw(format!("{}  {} = {}", indent, row.node.name, value))];
+                        if *source != Source::File {
+                            spans.push(Span::styled(format!("  [{}]", source), source_style(source)));
+                        }
+                        Line::from(spans)
+                    }
+                    _ => {
+                        let marker = if self.open.contains(&row.node.path) { "▾" } else { "▸" };
+                        let mut spans = vec![Span::styled(
+                            format!("{}{} {}", indent, marker, row.node.name),
+                            Style::default().fg(Color::Blue),
+                        )];
+                        if !self.open.contains(&row.node.path) {
+                            if let Some(source) = changed_under(row.node) {
+                                spans.push(Span::styled(format!("  [{} inside]", source), source_style(&source)));
+                            }
+                        }
+                        Line::from(spans)
+                    }
+                };
+                ListItem::new(line)
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = self.state.clone();
+        f.render_stateful_widget(list, rows[0], &mut state);
+
+        let mut hint = "↑/↓ move · →/Enter open · ← close · Esc back".to_string();
+        if self.unsaved > 0 {
+            hint.push_str(&format!(" · {} unsaved: /config save", self.unsaved));
+        }
+        f.render_widget(Paragraph::new(Span::styled(hint, Style::default().fg(Color::DarkGray))), rows[1]);
+    }
+}
+
+fn redact_values(node: &mut Node) {
+    if let Some(value) = &mut node.value {
+        *value = redact::redact(value);
+    }
+    if let Some(Source::Expanded(template)) = &mut node.source {
+        *template = redact::redact(template);
+    }
+    node.children.iter_mut().for_each(redact_values);
+}
+
+/// `Unsaved` when a value under `node` is, else `Session` when one is.
+fn changed_under(node: &Node) -> Option<Source> {
+    let sources: Vec<&Source> = node.walk().into_iter().filter_map(|n| n.source.as_ref()).collect();
+    [Source::Unsaved, Source::Session].into_iter().find(|s| sources.contains(&s))
+}
+
+fn source_style(source: &Source) -> Style {
+    match source {
+        Source::Unsaved => Style::default().fg(Color::Yellow),
+        Source::Session => Style::default().fg(Color::Magenta),
+        Source::Expanded(_) => Style::default().fg(Color::Cyan),
+        Source::Included(_) => Style::default().fg(Color::Blue),
+        Source::File | Source::Default => Style::default().fg(Color::DarkGray),
+    }
+}
@@ -0,0 +1,185 @@
+//! The symbols the wizard draws with: Unicode, or ASCII for terminals that cannot show it.
+//!
+//! Notes:
+//! - ASCII with `--ascii` (`force_ascii`, process-wide like `dry_run`), with
+//!   `ui.ascii_only`, or when the terminal looks limited: `TERM` is `dumb`, `linux`, or a
+//!   hardware terminal type; the locale (`LC_ALL`, `LC_CTYPE`, `LANG`) is set and not
+//!   UTF-8; or, on Windows, the console has no VT processing or is the legacy console
+//!   host (not Windows Terminal, VS Code, ConEmu, or a terminal that sets `TERM`).
+//! - The ASCII set also starts with the spinner off; the animation key still turns it on.
+//! - Render functions take the set from `UiState::glyphs` instead of literals, so both
+//!   sets lay out the same way.
+
+use crate::config::UiConfig;
+use crate::ui::width;
+use ratatui::symbols::border;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FORCE_ASCII: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    /// List and status marker, with its trailing space.
+    pub dot: &'static str,
+    /// Step progress in the block titles: one dot per step, or `[1][2][3][4]` when `None`.
+    pub step_dot: Option<&'static str>,
+    pub border: border::Set,
+    pub up_down: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+    pub left: &'static str,
+    pub right: &'static str,
+    pub left_right: &'static str,
+    /// Between a label and its description.
+    pub dash: &'static str,
+    /// Between parts of a title.
+    pub separator: &'static str,
+    pub ellipsis: &'static str,
+    /// Whether the spinner runs from the start.
+    pub animate: bool,
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    dot: "● ",
+    step_dot: Some("●"),
+    border: border::PLAIN,
+    up_down: "↑↓",
+    up: "↑",
+    down: "↓",
+    left: "←",
+    right: "→",
+    left_right: "←/→",
+    dash: " — ",
+    separator: " · ",
+    ellipsis: "…",
+    animate: true,
+};
+
+pub const ASCII: Glyphs = Glyphs {
+    dot: "* ",
+    step_dot: None,
+    border: border::Set {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        vertical_left: "|",
+        vertical_right: "|",
+        horizontal_top: "-",
+        horizontal_bottom: "-",
+    },
+    up_down: "Up/Down",
+    up: "Up",
+    down: "Down",
+    left: "Left",
+    right: "Right",
+    left_right: "Left/Right",
+    dash: " - ",
+    separator: " - ",
+    ellipsis: "...",
+    animate: false,
+};
+
+/// `--ascii`: use `ASCII` for the rest of the process, whatever `detect` finds.
+pub fn force_ascii() {
+    FORCE_ASCII.store(true, Ordering::Relaxed);
+}
+
+/// The set for this terminal and `ui` (see the module notes).
+pub fn detect(ui: &UiConfig) -> Glyphs {
+    match FORCE_ASCII.load(Ordering::Relaxed) || ui.ascii_only || limited_terminal() {
+        true => ASCII,
+        false => UNICODE,
+    }
+}
+
+impl Glyphs {
+    /// `text` cut to `max` columns with this set's ellipsis.
+    pub fn truncate<'a>(&self, text: &'a str, max: usize) -> Cow<'a, str> {
+        width::truncate(text, max, self.ellipsis)
+    }
+}
+
+fn limited_terminal() -> bool {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    if var("TERM").is_some_and(|t| matches!(t.as_str(), "dumb" | "linux" | "ansi" | "cons25") || t.starts_with("vt")) {
+        return true;
+    }
+    let locale = var("LC_ALL").or_else(|| var("LC_CTYPE")).or_else(|| var("LANG"));
+    if locale.is_some_and(|l| !l.to_ascii_lowercase().replace('-', "").contains("utf8")) {
+        return true;
+    }
+    legacy_console()
+}
+
+#[cfg(windows)]
+fn legacy_console() -> bool {
+    let modern = ["WT_SESSION", "TERM_PROGRAM", "ConEmuANSI", "TERM"].iter().any(|v| std::env::var_os(v).is_some());
+    !modern || !crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+fn legacy_console() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::buffer_rows as rows;
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::text::Line;
+    use ratatui::widgets::{Block, Borders, Paragraph};
+    use ratatui::Terminal;
+
+    /// A bordered list with a step title and a key line, drawn with `glyphs` the way the
+    /// wizard uses each symbol.
+    fn sample(glyphs: Glyphs) -> Vec<String> {
+        let steps = match glyphs.step_dot {
+            Some(dot) => dot.repeat(4),
+            None => "[1][2][3][4]".to_string(),
+        };
+        let lines = vec![
+            Line::from(format!("{}Ollama{}Local models", glyphs.dot, glyphs.dash)),
+            Line::from(format!("{}OpenAI{}{}", glyphs.dot, glyphs.dash, glyphs.truncate("GPT models via the OpenAI API", 14))),
+            Line::from(format!("{} Move {} Back {} Next", glyphs.up_down, glyphs.left, glyphs.right)),
+            Line::from(format!("{} Recent {} Older {} Field", glyphs.up, glyphs.down, glyphs.left_right)),
+        ];
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(glyphs.border)
+            .title(format!("Provider{}{}", glyphs.separator, steps));
+        let mut terminal = Terminal::new(TestBackend::new(40, 6)).unwrap();
+        terminal.draw(|f| f.render_widget(Paragraph::new(lines).block(block), f.size())).unwrap();
+        rows(terminal.backend().buffer())
+    }
+
+    #[test]
+    fn unicode_set() {
+        let expected = [
+            "┌Provider · ●●●●───────────────────────┐",
+            "│● Ollama — Local models               │",
+            "│● OpenAI — GPT models vi…             │",
+            "│↑↓ Move ← Back → Next                 │",
+            "│↑ Recent ↓ Older ←/→ Field            │",
+            "└──────────────────────────────────────┘",
+        ];
+        assert_eq!(sample(UNICODE), expected);
+    }
+
+    #[test]
+    fn ascii_set() {
+        let expected = [
+            "+Provider - [1][2][3][4]---------------+",
+            "|* Ollama - Local models               |",
+            "|* OpenAI - GPT models...              |",
+            "|Up/Down Move Left Back Right Next     |",
+            "|Up Recent Down Older Left/Right Field |",
+            "+--------------------------------------+",
+        ];
+        assert_eq!(sample(ASCII), expected);
+        // Nothing outside ASCII, so it shows on any terminal.
+        assert!(sample(ASCII).iter().all(|row| row.is_ascii()));
+    }
+}
@@ -0,0 +1,122 @@
+//! Wizard key bindings resolved against terminal key events.
+//!
+//! Notes:
+//! - Built from `config.keybindings` (validated with the rest of the config); specs that
+//!   fail to parse are skipped rather than failing the wizard.
+//! - Shift is implied by the character for letters, so it is ignored when matching.
+//! - `label` renders the keys actually bound, for the help panel and status hints; arrow
+//!   keys are drawn with the wizard's `Glyphs`.
+
+use super::glyphs::Glyphs;
+use crate::config::keys::{Key, KeyBindings, KeySpec};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Back,
+    Next,
+    ToggleColors,
+    ToggleAnimation,
+    /// Go to step n (1-based).
+    JumpStep(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(KeySpec, Action)>,
+    glyphs: Glyphs,
+}
+
+impl KeyMap {
+    pub fn new(config: &KeyBindings, glyphs: Glyphs) -> Self {
+        let mut bindings = Vec::new();
+        for (name, specs) in config.entries() {
+            let Some(action) = action(name) else { continue };
+            bindings.extend(specs.iter().filter_map(|s| s.parse().ok()).map(|spec| (spec, action)));
+        }
+        Self { bindings, glyphs }
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(spec, _)| matches(spec, key))
+            .map(|(_, action)| *action)
+    }
+
+    /// The keys bound to `action`, e.g. "Esc / ← / b"; "unbound" when there are none.
+    pub fn label(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(spec, _)| key_label(spec, &self.glyphs))
+            .collect();
+        if keys.is_empty() {
+            "unbound".to_string()
+        } else {
+            keys.join(" / ")
+        }
+    }
+}
+
+fn action(name: &str) -> Option<Action> {
+    Some(match name {
+        "quit" => Action::Quit,
+        "back" => Action::Back,
+        "next" => Action::Next,
+        "toggle_colors" => Action::ToggleColors,
+        "toggle_animation" => Action::ToggleAnimation,
+        _ => Action::JumpStep(name.strip_prefix("jump_step_")?.parse().ok()?),
+    })
+}
+
+fn matches(spec: &KeySpec, key: &KeyEvent) -> bool {
+    let code = match spec.key {
+        Key::Char(c) => KeyCode::Char(c),
+        Key::Enter => KeyCode::Enter,
+        Key::Esc => KeyCode::Esc,
+        Key::Backspace => KeyCode::Backspace,
+        Key::Tab => KeyCode::Tab,
+        Key::Delete => KeyCode::Delete,
+        Key::Up => KeyCode::Up,
+        Key::Down => KeyCode::Down,
+        Key::Left => KeyCode::Left,
+        Key::Right => KeyCode::Right,
+        Key::Home => KeyCode::Home,
+        Key::End => KeyCode::End,
+        Key::PageUp => KeyCode::PageUp,
+        Key::PageDown => KeyCode::PageDown,
+        Key::F(n) => KeyCode::F(n),
+    };
+    key.code == code
+        && key.modifiers.contains(KeyModifiers::CONTROL) == spec.ctrl
+        && key.modifiers.contains(KeyModifiers::ALT) == spec.alt
+}
+
+fn key_label(spec: &KeySpec, glyphs: &Glyphs) -> String {
+    let key = match spec.key {
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char(c) if spec.ctrl || spec.alt => c.to_ascii_uppercase().to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Enter => "Enter".to_string(),
+        Key::Esc => "Esc".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Up => glyphs.up.to_string(),
+        Key::Down => glyphs.down.to_string(),
+        Key::Left => glyphs.left.to_string(),
+        Key::Right => glyphs.right.to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PgUp".to_string(),
+        Key::PageDown => "PgDn".to_string(),
+        Key::F(n) => format!("F{}", n),
+    };
+    let ctrl = if spec.ctrl { "Ctrl+" } else { "" };
+    let alt = if spec.alt { "Alt+" } else { "" };
+    format!("{}{}{}", ctrl, alt, key)
+}
@@ -0,0 +1,95 @@
+//! Minimal Markdown to ratatui `Line`s for the chat pane.
+//!
+//! Notes:
+//! - Covers what models commonly emit: headings, bullet lists, fenced code blocks,
+//!   `inline code`, and **bold**. Anything else renders as plain text.
+//! - Fenced code is kept verbatim (no inline parsing) so snippets can be copied as-is.
+//! - An unterminated fence (e.g. mid-stream) styles the rest of the text as code.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+fn s_code() -> Style {
+    Style::default().fg(Color::Yellow)
+}
+
+fn s_heading() -> Style {
+    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+}
+
+pub fn render(text: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+
+    for raw in text.lines() {
+        let trimmed = raw.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            lines.push(Line::from(Span::styled(raw.to_string(), Style::default().fg(Color::DarkGray))));
+            continue;
+        }
+        if in_fence {
+            lines.push(Line::from(Span::styled(raw.to_string(), s_code())));
+            continue;
+        }
+
+        let heading = trimmed.trim_start_matches('#');
+        if trimmed.starts_with('#') && heading.starts_with(' ') {
+            lines.push(Line::from(Span::styled(heading.trim().to_string(), s_heading())));
+            continue;
+        }
+
+        let indent = &raw[..raw.len() - trimmed.len()];
+        if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            let mut spans = vec![Span::raw(format!("{}• ", indent))];
+            spans.extend(inline(item));
+            lines.push(Line::from(spans));
+            continue;
+        }
+
+        lines.push(Line::from(inline(raw)));
+    }
+    lines
+}
+
+/// Split a line into spans for `code` and **bold** runs. Unmatched markers stay literal.
+fn inline(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let (marker, style) = if rest.starts_with('`') {
+            ("`", s_code())
+        } else if rest.starts_with("**") {
+            ("**", Style::default().add_modifier(Modifier::BOLD))
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            plain.push(ch);
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        };
+
+        let body = &rest[marker.len()..];
+        match body.find(marker) {
+            Some(end) if end > 0 => {
+                if !plain.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut plain)));
+                }
+                spans.push(Span::styled(body[..end].to_string(), style));
+                rest = &body[end + marker.len()..];
+            }
+            _ => {
+                plain.push_str(marker);
+                rest = body;
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span::raw(plain));
+    }
+    spans
+}
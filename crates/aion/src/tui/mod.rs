@@ -1,8 +1,262 @@
+pub mod chat;
+mod config_tree;
+pub mod glyphs;
+mod keymap;
+pub mod markdown;
+mod picker;
+mod provider_form;
+mod splash;
+mod tasks;
 pub mod wizard;
 
 use crate::config::AppConfig;
+use crate::providers::limit::{self, Wait};
+use crate::sessions::Session;
+use crate::setup::Step;
+use crate::summary::Summary;
 use anyhow::Result;
+use crossterm::{
+    event::KeyCode,
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::Backend, widgets::ListState, Terminal};
+use std::io;
+use std::ops::AsyncFnOnce;
+use std::panic::{self, PanicHookInfo};
+use std::sync::Arc;
 
-pub fn run_wizard(existing: &AppConfig) -> Result<AppConfig> {
-    wizard::run(existing)
-}
\ No newline at end of file
+pub async fn run_wizard(existing: &AppConfig, start: Option<Step>) -> Result<AppConfig> {
+    wizard::run(existing, start).await
+}
+
+/// The chat view, after `splash` if given (the startup summary).
+pub async fn run_chat(cfg: &AppConfig, splash: Option<&Summary>, resumed: Option<Session>) -> Result<()> {
+    chat::run(cfg, splash, resumed).await
+}
+
+/// Move a list's selection for ↑/↓, staying within its `len` items. `false` for other
+/// keys.
+fn step_selection(state: &mut ListState, len: usize, code: KeyCode) -> bool {
+    let cur = state.selected().unwrap_or(0);
+    let next = match code {
+        KeyCode::Up => cur.saturating_sub(1),
+        KeyCode::Down => (cur + 1).min(len.saturating_sub(1)),
+        _ => return false,
+    };
+    state.select(Some(next));
+    true
+}
+
+/// Where a list cursor kept by value (a locale code, a provider kind) is in `items` as
+/// they are listed now; the first item when the value is gone. Lists rebuilt from data
+/// that can change between frames keep the same item under the cursor this way, which
+/// an index kept in a `ListState` would not.
+fn cursor_index<T>(items: &[T], is_cursor: impl Fn(&T) -> bool) -> usize {
+    items.iter().position(is_cursor).unwrap_or(0)
+}
+
+/// A rendered buffer's text, one string per row with trailing spaces dropped, for
+/// comparing a view drawn on a `TestBackend` with the rows it should show.
+#[cfg(test)]
+fn buffer_rows(buffer: &ratatui::buffer::Buffer) -> Vec<String> {
+    let width = buffer.area.width as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>().trim_end().to_string())
+        .collect()
+}
+
+/// Held by tests that swap the process-wide panic hook, themselves or through the code
+/// they run, so that one does not take or restore another's hook.
+#[cfg(test)]
+pub(crate) static PANIC_HOOK_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Switching the terminal to full screen and back. `Screen::TERMINAL` is the real one;
+/// tests pass their own to see when the guard restores it.
+#[derive(Clone, Copy)]
+struct Screen {
+    enter: fn() -> io::Result<()>,
+    restore: fn(),
+}
+
+impl Screen {
+    const TERMINAL: Screen = Screen { enter: enter_terminal, restore: restore_terminal };
+}
+
+/// Raw mode + alternate screen for the lifetime of a full-screen view.
+///
+/// While it is alive a panic hook restores the terminal before the panic message is
+/// printed, so a panic inside a view does not leave the shell in raw mode. Rate limit
+/// waits are not printed meanwhile; the chat view shows them in its status line.
+struct TerminalGuard {
+    screen: Screen,
+    previous_hook: Option<Arc<PanicHook>>,
+    reporter: Option<fn(&Wait)>,
+}
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        Self::enter_with(Screen::TERMINAL)
+    }
+
+    fn enter_with(screen: Screen) -> Result<Self> {
+        let previous_hook: Arc<PanicHook> = Arc::new(panic::take_hook());
+        let chained = Arc::clone(&previous_hook);
+        panic::set_hook(Box::new(move |info| {
+            (screen.restore)();
+            chained(info);
+        }));
+
+        let guard = Self { screen, previous_hook: Some(previous_hook), reporter: limit::set_reporter(None) };
+        (screen.enter)()?;
+        Ok(guard)
+    }
+
+    /// Run `view` on `terminal`, then leave full screen: however `view` ends, a panic
+    /// included, the terminal is restored.
+    async fn run_with<B: Backend, T>(
+        self,
+        mut terminal: Terminal<B>,
+        view: impl AsyncFnOnce(&mut Terminal<B>) -> Result<T>,
+    ) -> Result<T> {
+        let out = view(&mut terminal).await;
+        drop(self);
+        out
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        (self.screen.restore)();
+        limit::set_reporter(self.reporter);
+        // The hook cannot be swapped while unwinding; it stays installed and is harmless.
+        if std::thread::panicking() {
+            return;
+        }
+        drop(panic::take_hook());
+        if let Some(Ok(previous)) = self.previous_hook.take().map(Arc::try_unwrap) {
+            panic::set_hook(previous);
+        }
+    }
+}
+
+fn enter_terminal() -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)
+}
+
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::widgets::Paragraph;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    static FULL_SCREEN: AtomicBool = AtomicBool::new(false);
+    /// The panic message, and whether the screen had been restored when it was printed.
+    static PRINTED: Mutex<Option<(String, bool)>> = Mutex::new(None);
+
+    fn enter() -> io::Result<()> {
+        FULL_SCREEN.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn restore() {
+        FULL_SCREEN.store(false, Ordering::SeqCst);
+    }
+
+    const SCREEN: Screen = Screen { enter, restore };
+
+    fn run_view(panics: bool) -> std::thread::Result<Result<&'static str>> {
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            rt.block_on(async {
+                let terminal = Terminal::new(TestBackend::new(20, 4)).unwrap();
+                let guard = TerminalGuard::enter_with(SCREEN).unwrap();
+                guard
+                    .run_with(terminal, async |terminal: &mut Terminal<TestBackend>| {
+                        assert!(FULL_SCREEN.load(Ordering::SeqCst));
+                        terminal.draw(|f| f.render_widget(Paragraph::new("drawing"), f.size()))?;
+                        if panics {
+                            panic!("layout went wrong");
+                        }
+                        Ok("done")
+                    })
+                    .await
+            })
+        })
+        .join()
+    }
+
+    #[test]
+    fn the_screen_is_restored_before_a_panic_is_printed() {
+        let _hook = PANIC_HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let harness_hook = panic::take_hook();
+        panic::set_hook(Box::new(|info| {
+            let message = info.payload().downcast_ref::<&str>().map(|m| m.to_string()).unwrap_or_default();
+            *PRINTED.lock().unwrap() = Some((message, !FULL_SCREEN.load(Ordering::SeqCst)));
+        }));
+
+        assert_eq!(run_view(false).unwrap().unwrap(), "done");
+        assert!(!FULL_SCREEN.load(Ordering::SeqCst));
+        assert_eq!(PRINTED.lock().unwrap().take(), None);
+
+        let outcome = run_view(true);
+        // The guard's hook stays installed after a panic; put the harness's back.
+        drop(panic::take_hook());
+        panic::set_hook(harness_hook);
+
+        assert!(outcome.is_err());
+        assert!(!FULL_SCREEN.load(Ordering::SeqCst));
+        assert_eq!(PRINTED.lock().unwrap().take(), Some(("layout went wrong".to_string(), true)));
+    }
+
+    #[test]
+    fn a_view_cancelled_mid_loop_leaves_full_screen_and_its_hook() {
+        let _hook = PANIC_HOOK_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let harness_hook = panic::take_hook();
+        panic::set_hook(Box::new(|info| {
+            let message = info.payload().downcast_ref::<&str>().map(|m| m.to_string()).unwrap_or_default();
+            *PRINTED.lock().unwrap() = Some((message, !FULL_SCREEN.load(Ordering::SeqCst)));
+        }));
+
+        // The view's `select!` waits on input that never comes; the caller gives up on it.
+        let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let outcome = rt.block_on(async {
+            let terminal = Terminal::new(TestBackend::new(20, 4)).unwrap();
+            let guard = TerminalGuard::enter_with(SCREEN).unwrap();
+            let view = guard.run_with(terminal, async |terminal: &mut Terminal<TestBackend>| {
+                terminal.draw(|f| f.render_widget(Paragraph::new("waiting"), f.size()))?;
+                tokio::select! {
+                    _ = std::future::pending::<()>() => {}
+                    _ = tokio::time::sleep(Duration::from_secs(60)) => {}
+                }
+                Ok(())
+            });
+            tokio::time::timeout(Duration::from_millis(20), view).await
+        });
+        assert!(outcome.is_err());
+        assert!(!FULL_SCREEN.load(Ordering::SeqCst));
+
+        // The guard's hook is gone: a later panic reaches the previous one without
+        // restoring a screen nobody entered.
+        FULL_SCREEN.store(true, Ordering::SeqCst);
+        let panicked = panic::catch_unwind(|| panic!("after the view"));
+        FULL_SCREEN.store(false, Ordering::SeqCst);
+        panic::set_hook(harness_hook);
+
+        assert!(panicked.is_err());
+        assert_eq!(PRINTED.lock().unwrap().take(), Some(("after the view".to_string(), false)));
+    }
+}
@@ -0,0 +1,334 @@
+//! A minimal file picker over the current directory (Ctrl+O in the chat view).
+//!
+//! Notes:
+//! - Keyboard only: typing filters the entries (case-insensitive substring), ↑/↓ move,
+//!   Enter opens a directory or picks a file, Backspace on an empty filter goes to the
+//!   parent directory, Esc closes.
+//! - Directories first, then files, each by name. Hidden entries are listed only while
+//!   the filter starts with `.`.
+//! - An unreadable directory shows its error in place of the entries.
+//! - It only picks a path: reading the file, and the `caps` checks, are the caller's.
+
+use super::step_selection;
+use crate::i18n::format::tilde;
+use crate::ui::input::TextInput;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    is_dir: bool,
+}
+
+/// What a key did to the picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerEvent {
+    /// Still open.
+    Open,
+    /// A file was picked; relative to the starting directory when inside it.
+    Picked(PathBuf),
+    Closed,
+}
+
+pub struct FilePicker {
+    /// Where the picker opened, for relative paths.
+    start: PathBuf,
+    dir: PathBuf,
+    entries: Vec<Entry>,
+    error: Option<String>,
+    filter: TextInput,
+    state: ListState,
+}
+
+impl FilePicker {
+    pub fn new(dir: PathBuf) -> Self {
+        let mut picker = Self {
+            start: dir.clone(),
+            dir,
+            entries: Vec::new(),
+            error: None,
+            filter: TextInput::new(),
+            state: ListState::default(),
+        };
+        picker.load();
+        picker
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> PickerEvent {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return PickerEvent::Closed,
+            KeyCode::Char('c') if ctrl => return PickerEvent::Closed,
+            KeyCode::Up | KeyCode::Down => {
+                let len = self.visible().len();
+                step_selection(&mut self.state, len, key.code);
+            }
+            KeyCode::Enter => return self.choose(),
+            KeyCode::Backspace if self.filter.is_empty() => {
+                if let Some(parent) = self.dir.parent() {
+                    self.dir = parent.to_path_buf();
+                    self.load();
+                }
+            }
+            KeyCode::Backspace => {
+                self.filter.backspace();
+                self.state.select(Some(0));
+            }
+            KeyCode::Left => {
+                self.filter.left();
+            }
+            KeyCode::Right => {
+                self.filter.right();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.filter.insert(c);
+                self.state.select(Some(0));
+            }
+            _ => {}
+        }
+        PickerEvent::Open
+    }
+
+    fn choose(&mut self) -> PickerEvent {
+        let index = self.state.selected().unwrap_or(0);
+        let Some(entry) = self.visible().get(index).cloned() else {
+            return PickerEvent::Open;
+        };
+        let path = self.dir.join(&entry.name);
+        if entry.is_dir {
+            self.dir = path;
+            self.load();
+            return PickerEvent::Open;
+        }
+        match path.strip_prefix(&self.start) {
+            Ok(relative) => PickerEvent::Picked(relative.to_path_buf()),
+            Err(_) => PickerEvent::Picked(path),
+        }
+    }
+
+    /// List `dir`, clearing the filter.
+    fn load(&mut self) {
+        self.filter.clear();
+        self.state.select(Some(0));
+        match list(&self.dir) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(e) => {
+                self.entries.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// The entries matching the filter, in display order.
+    fn visible(&self) -> Vec<&Entry> {
+        let filter = self.filter.as_str().to_lowercase();
+        self.entries
+            .iter()
+            .filter(|e| filter.starts_with('.') || !e.name.starts_with('.'))
+            .filter(|e| e.name.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    /// Drawn centred over `area`.
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let width = (area.width * 7 / 10).max(area.width.min(40));
+        let height = (area.height * 7 / 10).max(area.height.min(10));
+        let rect = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+
+        let title = format!(" Attach a file · {} ", tilde(&self.dir));
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            title,
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ));
+        let inner = block.inner(rect);
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+            .split(inner);
+
+        let prompt = "Filter: ";
+        f.render_widget(Paragraph::new(format!("{}{}", prompt, self.filter.as_str())), rows[0]);
+        let x = (prompt.len() + self.filter.cursor_column()) as u16;
+        f.set_cursor(rows[0].x + x.min(rows[0].width.saturating_sub(1)), rows[0].y);
+
+        let dim = Style::default().fg(Color::DarkGray);
+        let visible = self.visible();
+        if let Some(error) = &self.error {
+            f.render_widget(Paragraph::new(Span::styled(error.clone(), Style::default().fg(Color::Red))), rows[1]);
+        } else if visible.is_empty() {
+            f.render_widget(Paragraph::new(Span::styled("No matching files", dim)), rows[1]);
+        } else {
+            let items: Vec<ListItem> = visible
+                .iter()
+                .map(|e| match e.is_dir {
+                    true => {
+                        ListItem::new(Line::from(Span::styled(format!("{}/", e.name), Style::default().fg(Color::Blue))))
+                    }
+                    false => ListItem::new(e.name.clone()),
+                })
+                .collect();
+            let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            let mut state = self.state.clone();
+            f.render_stateful_widget(list, rows[1], &mut state);
+        }
+
+        let hint = "↑/↓ move · Enter open/attach · Backspace parent · Esc close";
+        f.render_widget(Paragraph::new(Span::styled(hint, dim)), rows[2]);
+    }
+}
+
+/// `dir`'s entries, directories first, then files, each by name.
+fn list(dir: &Path) -> std::io::Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| Entry {
+            name: e.file_name().to_string_lossy().into_owned(),
+            // Follows links, so a link to a directory opens like one.
+            is_dir: e.path().is_dir(),
+        })
+        .collect();
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::buffer_rows as rows;
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+    use ratatui::Terminal;
+    use tempfile::TempDir;
+
+    /// `src/`, `docs/`, `.git/`, `Cargo.toml`, `README.md`, and `.env`.
+    fn project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for name in ["src", "docs", ".git"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        for name in ["Cargo.toml", "README.md", ".env"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        dir
+    }
+
+    fn render(picker: &FilePicker) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal.draw(|f| picker.render(f, f.size())).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    fn key(picker: &mut FilePicker, code: KeyCode) -> PickerEvent {
+        picker.on_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    /// Everything under the title row, which names the (temporary) directory.
+    fn body(picker: &FilePicker) -> Vec<String> {
+        let rows = rows(&render(picker));
+        assert!(rows[0].starts_with("┌ Attach a file · "), "{}", rows[0]);
+        rows[1..].to_vec()
+    }
+
+    #[test]
+    fn lists_directories_first_without_hidden_entries() {
+        let dir = project();
+        let picker = FilePicker::new(dir.path().to_path_buf());
+        let buffer = render(&picker);
+        let name = dir.path().file_name().unwrap().to_str().unwrap();
+        assert!(rows(&buffer)[0].contains(name), "{}", rows(&buffer)[0]);
+        assert_eq!(
+            body(&picker),
+            [
+                "│Filter:                               │",
+                "│docs/                                 │",
+                "│src/                                  │",
+                "│Cargo.toml                            │",
+                "│README.md                             │",
+                "│                                      │",
+                "│                                      │",
+                "│↑/↓ move · Enter open/attach · Backspa│",
+                "└──────────────────────────────────────┘",
+            ]
+        );
+        // The first entry is selected; directories are blue.
+        assert!(buffer.get(1, 2).modifier.contains(Modifier::REVERSED));
+        assert!(!buffer.get(1, 3).modifier.contains(Modifier::REVERSED));
+        assert_eq!(buffer.get(2, 3).fg, Color::Blue);
+    }
+
+    #[test]
+    fn filtering_narrows_the_list_and_shows_hidden_entries_for_a_dot() {
+        let dir = project();
+        let mut picker = FilePicker::new(dir.path().to_path_buf());
+        // Case-insensitive, anywhere in the name.
+        key(&mut picker, KeyCode::Char('R'));
+        assert_eq!(&body(&picker)[..4], [
+            "│Filter: R                             │",
+            "│src/                                  │",
+            "│Cargo.toml                            │",
+            "│README.md                             │",
+        ]);
+        key(&mut picker, KeyCode::Char('e'));
+        assert_eq!(&body(&picker)[..3], [
+            "│Filter: Re                            │",
+            "│README.md                             │",
+            "│                                      │",
+        ]);
+
+        key(&mut picker, KeyCode::Backspace);
+        key(&mut picker, KeyCode::Backspace);
+        key(&mut picker, KeyCode::Char('.'));
+        assert_eq!(&body(&picker)[..5], [
+            "│Filter: .                             │",
+            "│.git/                                 │",
+            "│.env                                  │",
+            "│Cargo.toml                            │",
+            "│README.md                             │",
+        ]);
+
+        key(&mut picker, KeyCode::Char('z'));
+        assert_eq!(&body(&picker)[..2], [
+            "│Filter: .z                            │",
+            "│No matching files                     │",
+        ]);
+    }
+
+    #[test]
+    fn enter_opens_a_directory_and_picks_a_file_relative_to_the_start() {
+        let dir = project();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let mut picker = FilePicker::new(dir.path().to_path_buf());
+        key(&mut picker, KeyCode::Down);
+        assert_eq!(key(&mut picker, KeyCode::Enter), PickerEvent::Open);
+        assert_eq!(&body(&picker)[..2], [
+            "│Filter:                               │",
+            "│main.rs                               │",
+        ]);
+        assert_eq!(key(&mut picker, KeyCode::Enter), PickerEvent::Picked(PathBuf::from("src/main.rs")));
+        assert_eq!(key(&mut picker, KeyCode::Esc), PickerEvent::Closed);
+    }
+
+    #[test]
+    fn an_unreadable_directory_shows_its_error() {
+        let dir = project();
+        let picker = FilePicker::new(dir.path().join("missing"));
+        let buffer = render(&picker);
+        assert!(rows(&buffer)[2].starts_with("│No such file or directory"), "{}", rows(&buffer)[2]);
+        assert_eq!(buffer.get(1, 2).fg, Color::Red);
+    }
+}
@@ -0,0 +1,167 @@
+//! The form `/provider <kind>` opens in the chat view when the kind needs settings the
+//! config lacks (see `config::fields::missing`).
+//!
+//! Notes:
+//! - One input per missing field, filled in with the kind's default. ↑/↓ and Tab move
+//!   between them, Enter goes to the next one and applies from the last, Ctrl+S applies
+//!   and saves, Esc closes.
+//! - A field failing its check, or a key variable that is not set, keeps the form open
+//!   with the reason under the inputs; an unset variable adds the key field if it was not
+//!   asked for.
+//! - It only builds the provider: switching and saving are the caller's.
+
+use crate::config::fields::{self, Required};
+use crate::config::ProviderConfig;
+use crate::providers;
+use crate::ui::input::TextInput;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// What a key did to the form.
+#[derive(Debug, Clone)]
+pub enum FormEvent {
+    Open,
+    Closed,
+    /// Every field passed; switch to `provider`, saving it if `save`.
+    Done { provider: Box<ProviderConfig>, save: bool },
+}
+
+pub struct ProviderForm {
+    draft: ProviderConfig,
+    inputs: Vec<(Required, TextInput)>,
+    focus: usize,
+    error: Option<String>,
+    /// `/provider <kind> --save`: Enter saves too, not only Ctrl+S.
+    save: bool,
+}
+
+impl ProviderForm {
+    pub fn new(draft: ProviderConfig, missing: Vec<Required>, save: bool) -> Self {
+        let mut form = Self { draft, inputs: Vec::new(), focus: 0, error: None, save };
+        // A key variable that is there but not set is asked for again, with the reason.
+        if !missing.contains(&Required::ApiKeyEnv) {
+            if let Err(e) = providers::resolve_api_key(&form.draft) {
+                form.error = Some(e.to_string());
+                form.add(Required::ApiKeyEnv);
+            }
+        }
+        for field in missing {
+            form.add(field);
+        }
+        form
+    }
+
+    /// An input for `field`, unless there is one, holding its value or the kind's default.
+    fn add(&mut self, field: Required) {
+        if self.inputs.iter().any(|(f, _)| *f == field) {
+            return;
+        }
+        let value = field.value(&self.draft).filter(|v| !v.trim().is_empty()).or(field.default_for(&self.draft.kind));
+        self.inputs.push((field, TextInput::with_text(value.unwrap_or_default())));
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> FormEvent {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let count = self.inputs.len();
+        let input = &mut self.inputs[self.focus].1;
+        match key.code {
+            KeyCode::Esc => return FormEvent::Closed,
+            KeyCode::Char('c') if ctrl => return FormEvent::Closed,
+            KeyCode::Char('s') if ctrl => return self.submit(true),
+            KeyCode::Enter if self.focus + 1 < count => self.focus += 1,
+            KeyCode::Enter => return self.submit(self.save),
+            KeyCode::Down | KeyCode::Tab => self.focus = (self.focus + 1) % count,
+            KeyCode::Up | KeyCode::BackTab => self.focus = (self.focus + count - 1) % count,
+            KeyCode::Backspace => {
+                input.backspace();
+            }
+            KeyCode::Delete => {
+                input.delete();
+            }
+            KeyCode::Left => {
+                input.left();
+            }
+            KeyCode::Right => {
+                input.right();
+            }
+            KeyCode::Home => input.home(),
+            KeyCode::End => input.end(),
+            KeyCode::Char('u') if ctrl => input.clear(),
+            KeyCode::Char(c) if !ctrl => input.insert(c),
+            _ => {}
+        }
+        FormEvent::Open
+    }
+
+    fn submit(&mut self, save: bool) -> FormEvent {
+        for (i, (field, input)) in self.inputs.iter().enumerate() {
+            let check = field.check(&self.draft.kind, input.as_str());
+            if check.is_error() {
+                self.focus = i;
+                self.error = Some(format!("{}: {}", field.key(), check.message()));
+                return FormEvent::Open;
+            }
+            field.set(&mut self.draft, input.as_str());
+        }
+        if let Err(e) = providers::resolve_api_key(&self.draft) {
+            self.add(Required::ApiKeyEnv);
+            self.focus = self.inputs.iter().position(|(f, _)| *f == Required::ApiKeyEnv).unwrap_or(0);
+            self.error = Some(e.to_string());
+            return FormEvent::Open;
+        }
+        FormEvent::Done { provider: Box::new(self.draft.clone()), save }
+    }
+
+    /// Drawn centred over `area`.
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let width = area.width.min(72);
+        let height = (self.inputs.len() as u16 + 5).min(area.height);
+        let rect = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+
+        let title = format!(" Switch to {} ", self.draft.kind);
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            title,
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ));
+        let inner = block.inner(rect);
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+
+        let label_width = self.inputs.iter().map(|(field, _)| field.label().len()).max().unwrap_or(0) + 2;
+        let mut lines = Vec::new();
+        for (i, (field, input)) in self.inputs.iter().enumerate() {
+            let style = match i == self.focus {
+                true => Style::default().add_modifier(Modifier::BOLD),
+                false => Style::default(),
+            };
+            let label = format!("{:width$}", format!("{}:", field.label()), width = label_width);
+            lines.push(Line::from(vec![Span::styled(label, style), Span::raw(input.as_str().to_string())]));
+        }
+        lines.push(Line::from(""));
+        let dim = Style::default().fg(Color::DarkGray);
+        lines.push(match &self.error {
+            Some(error) => Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))),
+            None => Line::from(Span::styled(format!("Model: {}", self.draft.model), dim)),
+        });
+        lines.push(Line::from(Span::styled("Enter next/apply · Ctrl+S apply and save · ↑/↓ move · Esc cancel", dim)));
+        f.render_widget(Paragraph::new(lines), inner);
+
+        let x = (label_width + self.inputs[self.focus].1.cursor_column()) as u16;
+        f.set_cursor(inner.x + x.min(inner.width.saturating_sub(1)), inner.y + self.focus as u16);
+    }
+}
+
+/// The inputs `/provider` needs for `draft`, or `None` when it can be switched to as is.
+pub fn needed(draft: &ProviderConfig) -> Option<Vec<Required>> {
+    let missing = fields::missing(draft);
+    match missing.is_empty() && providers::resolve_api_key(draft).is_ok() {
+        true => None,
+        false => Some(missing),
+    }
+}
@@ -0,0 +1,103 @@
+//! The startup summary as a brief screen before the chat view (`ui_mode = "tui"`).
+//!
+//! Notes:
+//! - Same rows and warnings as the printed summary (`summary::rows`, `warning_lines`).
+//! - Up for `SPLASH_TIME`, or `WARNING_TIME` when there are warnings to read; any key
+//!   dismisses it early and is not passed on to the chat input.
+
+use crate::i18n;
+use crate::summary::{self, Summary};
+use crate::ui::width::width;
+use anyhow::Result;
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures_util::StreamExt;
+use ratatui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use std::time::Duration;
+
+const SPLASH_TIME: Duration = Duration::from_millis(1500);
+const WARNING_TIME: Duration = Duration::from_secs(4);
+
+/// Show `summary` until the time is up or a key is pressed.
+pub(super) async fn show<B: Backend>(terminal: &mut Terminal<B>, summary: &Summary, lang: &str) -> Result<()> {
+    let lines = lines(summary, lang);
+    terminal.draw(|f| draw(f, &lines))?;
+
+    let wait = if summary.has_warnings() { WARNING_TIME } else { SPLASH_TIME };
+    let deadline = tokio::time::sleep(wait);
+    tokio::pin!(deadline);
+    let mut events = EventStream::new();
+    loop {
+        tokio::select! {
+            _ = &mut deadline => return Ok(()),
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => return Ok(()),
+                Some(Ok(Event::Resize(..))) => {
+                    terminal.draw(|f| draw(f, &lines))?;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+fn lines(s: &Summary, lang: &str) -> Vec<Line<'static>> {
+    let rows = summary::rows(s, lang);
+    let label_width = rows.iter().map(|r| width(&r.label)).max().unwrap_or(0);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![Line::from(Span::styled(summary::title(lang), bold.fg(Color::Cyan))), Line::default()];
+    for row in rows {
+        let pad = " ".repeat(label_width - width(&row.label) + 2);
+        let mut spans = vec![Span::styled(row.label, bold), Span::raw(pad)];
+        for (i, part) in row.parts.into_iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(", "));
+            }
+            spans.push(match part.risky {
+                true => Span::styled(part.text, bold.fg(Color::Red)),
+                false => Span::raw(part.text),
+            });
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let warnings = summary::warning_lines(s, lang);
+    if !warnings.is_empty() {
+        lines.push(Line::default());
+    }
+    let label = i18n::text(lang, "summary.warning_label", "Warning", &[]);
+    for warning in warnings {
+        lines.push(Line::from(Span::styled(format!("{}: {}", label, warning), Style::default().fg(Color::Yellow))));
+    }
+
+    let hint = i18n::text(lang, "summary.continue", "Press any key to continue", &[]);
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(hint, Style::default().fg(Color::DarkGray))));
+    lines
+}
+
+/// `lines` in a box centred on the screen, as wide as they need within it.
+fn draw(f: &mut Frame, lines: &[Line]) {
+    let area = f.size();
+    let content = lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16;
+    let w = (content + 4).min(area.width);
+    let paragraph = Paragraph::new(lines.to_vec()).wrap(Wrap { trim: false });
+    let h = (paragraph.line_count(w.saturating_sub(4)) as u16 + 2).min(area.height);
+    let rect = Rect::new(area.x + (area.width - w) / 2, area.y + (area.height - h) / 2, w, h);
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        " AION ",
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    ));
+    f.render_widget(Clear, rect);
+    f.render_widget(paragraph.block(block.padding(Padding::horizontal(1))), rect);
+}
@@ -0,0 +1,256 @@
+//! Background work for the full-screen views: at most one task per kind, cancelled by
+//! kind, results read from the event loop.
+//!
+//! Notes:
+//! - Tasks are tokio tasks on the caller's runtime. Each gets a `TaskSender` for
+//!   progress and a `CancelToken`; what the future returns is its last message.
+//! - `spawn` replaces a running task of the same kind. `cancel` aborts the task and
+//!   cancels its token, so work the abort cannot reach (a stream being read) stops too.
+//!   Messages already sent by a replaced or cancelled task are dropped, never delivered.
+//! - With a timeout, a task that runs longer is cancelled and reported as `TimedOut`.
+//! - Dropping the pool cancels everything, so leaving a view (or quitting) leaves
+//!   nothing running behind it.
+//! - `next` never resolves while nothing runs, so it can sit in a `select!` as is.
+
+use crate::cancel::CancelToken;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::AbortHandle;
+
+/// What a task reported.
+#[derive(Debug)]
+pub enum TaskEvent<M> {
+    Message(M),
+    TimedOut,
+}
+
+/// Sends progress messages from inside a task.
+pub struct TaskSender<K, M> {
+    kind: K,
+    id: u64,
+    tx: UnboundedSender<Envelope<K, M>>,
+}
+
+impl<K: Clone, M> TaskSender<K, M> {
+    pub fn send(&self, message: M) {
+        let _ = self.tx.send(Envelope { kind: self.kind.clone(), id: self.id, body: Body::Message(message) });
+    }
+}
+
+struct Envelope<K, M> {
+    kind: K,
+    id: u64,
+    body: Body<M>,
+}
+
+enum Body<M> {
+    Message(M),
+    /// The task ended; `None` when it timed out.
+    Done(Option<M>),
+}
+
+struct Running {
+    id: u64,
+    abort: AbortHandle,
+    token: CancelToken,
+}
+
+pub struct TaskPool<K, M> {
+    tx: UnboundedSender<Envelope<K, M>>,
+    rx: UnboundedReceiver<Envelope<K, M>>,
+    running: HashMap<K, Running>,
+    next_id: u64,
+}
+
+impl<K, M> TaskPool<K, M>
+where
+    K: Copy + Eq + Hash + Send + 'static,
+    M: Send + 'static,
+{
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { tx, rx, running: HashMap::new(), next_id: 0 }
+    }
+
+    /// Start `task` as the `kind` task, cancelling the one running before it.
+    pub fn spawn<F, Fut>(&mut self, kind: K, timeout: Option<Duration>, task: F)
+    where
+        F: FnOnce(TaskSender<K, M>, CancelToken) -> Fut,
+        Fut: Future<Output = M> + Send + 'static,
+    {
+        self.cancel(kind);
+        self.next_id += 1;
+        let id = self.next_id;
+        let token = CancelToken::new();
+        let sender = TaskSender { kind, id, tx: self.tx.clone() };
+        let work = task(sender, token.clone());
+        let tx = self.tx.clone();
+        let handle = tokio::spawn(async move {
+            let result = match timeout {
+                Some(limit) => tokio::time::timeout(limit, work).await.ok(),
+                None => Some(work.await),
+            };
+            let _ = tx.send(Envelope { kind, id, body: Body::Done(result) });
+        });
+        self.running.insert(kind, Running { id, abort: handle.abort_handle(), token });
+    }
+
+    pub fn is_running(&self, kind: K) -> bool {
+        self.running.contains_key(&kind)
+    }
+
+    /// Stop the `kind` task, if one runs. Nothing it sent is delivered afterwards.
+    pub fn cancel(&mut self, kind: K) {
+        if let Some(task) = self.running.remove(&kind) {
+            task.token.cancel();
+            task.abort.abort();
+        }
+    }
+
+    /// The next message from a running task.
+    pub async fn next(&mut self) -> (K, TaskEvent<M>) {
+        loop {
+            let Some(envelope) = self.rx.recv().await else {
+                return std::future::pending().await;
+            };
+            if self.running.get(&envelope.kind).map(|t| t.id) != Some(envelope.id) {
+                continue;
+            }
+            let event = match envelope.body {
+                Body::Message(message) => TaskEvent::Message(message),
+                Body::Done(result) => {
+                    self.running.remove(&envelope.kind);
+                    match result {
+                        Some(message) => TaskEvent::Message(message),
+                        None => TaskEvent::TimedOut,
+                    }
+                }
+            };
+            return (envelope.kind, event);
+        }
+    }
+}
+
+impl<K, M> Drop for TaskPool<K, M> {
+    fn drop(&mut self) {
+        for task in self.running.values() {
+            task.token.cancel();
+            task.abort.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Kind {
+        Models,
+        Probe,
+    }
+
+    /// A `kind` task that reports `name` started, then takes `delay` to finish as `name` done.
+    fn spawn_slow(pool: &mut TaskPool<Kind, String>, kind: Kind, name: &'static str, delay: Duration) {
+        pool.spawn(kind, None, move |tx, _| async move {
+            tx.send(format!("{} started", name));
+            tokio::time::sleep(delay).await;
+            format!("{} done", name)
+        });
+    }
+
+    /// What `pool` reports until nothing runs, as text.
+    async fn drain(pool: &mut TaskPool<Kind, String>) -> Vec<String> {
+        let mut seen = Vec::new();
+        while pool.is_running(Kind::Models) || pool.is_running(Kind::Probe) {
+            seen.push(match pool.next().await {
+                (kind, TaskEvent::Message(m)) => format!("{:?}: {}", kind, m),
+                (kind, TaskEvent::TimedOut) => format!("{:?}: timed out", kind),
+            });
+        }
+        seen
+    }
+
+    #[tokio::test]
+    async fn the_event_loop_keeps_running_while_a_task_works() {
+        let mut pool = TaskPool::new();
+        spawn_slow(&mut pool, Kind::Models, "list", Duration::from_millis(300));
+        let mut frames = tokio::time::interval(Duration::from_millis(10));
+        let mut drawn = 0;
+        let mut events = Vec::new();
+        // As the views' loops do: draw on a tick, handle whatever the pool reports.
+        while pool.is_running(Kind::Models) {
+            tokio::select! {
+                _ = frames.tick() => drawn += 1,
+                (_, event) = pool.next() => events.push(event),
+            }
+        }
+        assert!(drawn >= 10, "only {} frames while the task ran", drawn);
+        let events: Vec<String> = events.into_iter().map(|e| format!("{:?}", e)).collect();
+        assert_eq!(events, [r#"Message("list started")"#, r#"Message("list done")"#]);
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_the_task_and_drops_what_it_sent() {
+        let mut pool = TaskPool::new();
+        let (token_tx, mut token_rx) = mpsc::unbounded_channel();
+        pool.spawn(Kind::Models, None, move |tx, token| {
+            token_tx.send(token.clone()).unwrap();
+            async move {
+                tx.send("partial".to_string());
+                token.cancelled().await;
+                "finished anyway".to_string()
+            }
+        });
+        let token = token_rx.recv().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        pool.cancel(Kind::Models);
+        assert!(!pool.is_running(Kind::Models));
+        assert!(token.is_cancelled());
+        // "partial" was sent before the cancel, yet only the other task is heard from.
+        spawn_slow(&mut pool, Kind::Probe, "probe", Duration::from_millis(10));
+        assert_eq!(drain(&mut pool).await, ["Probe: probe started", "Probe: probe done"]);
+    }
+
+    #[tokio::test]
+    async fn one_task_per_kind_and_kinds_run_side_by_side() {
+        let mut pool = TaskPool::new();
+        let started = Instant::now();
+        spawn_slow(&mut pool, Kind::Models, "first", Duration::from_millis(200));
+        spawn_slow(&mut pool, Kind::Models, "second", Duration::from_millis(200));
+        spawn_slow(&mut pool, Kind::Probe, "probe", Duration::from_millis(200));
+
+        let mut seen = drain(&mut pool).await;
+        seen.sort();
+        assert_eq!(seen, ["Models: second done", "Models: second started", "Probe: probe done", "Probe: probe started"]);
+        // Together, not one after the other.
+        assert!(started.elapsed() < Duration::from_millis(380), "took {:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn a_task_past_its_timeout_is_reported_and_dropping_the_pool_cancels() {
+        let mut pool: TaskPool<Kind, String> = TaskPool::new();
+        pool.spawn(Kind::Probe, Some(Duration::from_millis(20)), |_, _| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            "never".to_string()
+        });
+        assert_eq!(drain(&mut pool).await, ["Probe: timed out"]);
+
+        let (token_tx, mut token_rx) = mpsc::unbounded_channel();
+        pool.spawn(Kind::Models, None, move |_, token| {
+            token_tx.send(token.clone()).unwrap();
+            async move {
+                token.cancelled().await;
+                String::new()
+            }
+        });
+        let token = token_rx.recv().await.unwrap();
+        drop(pool);
+        assert!(token.is_cancelled());
+    }
+}
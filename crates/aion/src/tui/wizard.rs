@@ -1,209 +1,320 @@
-use crate::config::{allowed_languages, AppConfig, ProviderKind};
-use anyhow::{anyhow, Result};
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+use super::glyphs::{self, Glyphs};
+use super::keymap::{Action, KeyMap};
+use super::tasks::{TaskEvent, TaskPool};
+use super::{cursor_index, step_selection, TerminalGuard};
+use crate::audit;
+use crate::catalog::{self, Catalog};
+use crate::config::diff::{config_changes, new_lines, Change, DiffLine};
+use crate::config::io::{config_file_path, render_config};
+use crate::config::draft::{Field, WizardDraft};
+use crate::config::fields::Check;
+use crate::config::{AppConfig, ConfigError, ProviderKind};
+use crate::dry_run;
+use crate::frames::Frames;
+use crate::guard::Guard;
+use crate::i18n::format::format_bytes;
+use crate::providers::ollama::{OllamaClient, PullProgress};
+use crate::providers::ChatClient;
+use crate::recent::RecentModels;
+use crate::redact;
+use crate::setup::{
+    self, feature_labels, import_label, language_options, LangOption, provider_description, provider_label, provider_options,
+    scope_fields, Step, WizardModel,
 };
+use crate::ui::input::TextInput;
+use crate::ui::width::width;
+use anyhow::{anyhow, Result};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt;
 use ratatui::{
-    backend::CrosstermBackend,
+    backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
-use std::io::{self, Stdout};
+use std::io;
 use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Step {
-    Language,
-    Provider,
-    Model,
-    Summary,
+/// Offered when Ollama has no models installed yet.
+const SUGGESTED_OLLAMA_MODELS: &[&str] = &["mistral", "llama3", "qwen2.5", "phi3"];
+
+const TICK_RATE: Duration = Duration::from_millis(90);
+
+/// Below this width the help panel is hidden; `?` or F1 shows it as an overlay.
+const NARROW_WIDTH: u16 = 70;
+/// From this width on the help panel gets 60% and the step's content 40%.
+const WIDE_WIDTH: u16 = 110;
+
+/// What the Ollama tags endpoint reported when the provider was chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OllamaModels {
+    /// Not Ollama, network disabled, or not checked yet.
+    Unknown,
+    /// No answer, or none within `network.timeout_secs`.
+    Unreachable,
+    Installed(Vec<String>),
+    NoneInstalled,
 }
 
-impl Step {
-    fn title(self) -> &'static str {
-        match self {
-            Step::Language => "Step 1/4: Language",
-            Step::Provider => "Step 2/4: Provider",
-            Step::Model => "Step 3/4: Model",
-            Step::Summary => "Step 4/4: Summary",
-        }
-    }
+/// Model pull flow for an empty Ollama install.
+enum PullState {
+    Idle,
+    /// Showing the `ollama pull` command; `error` is set after a failed attempt.
+    Offer { model: String, error: Option<String> },
+    Running(PullTask),
+}
 
-    fn prev(self) -> Option<Self> {
-        match self {
-            Step::Language => None,
-            Step::Provider => Some(Step::Language),
-            Step::Model => Some(Step::Provider),
-            Step::Summary => Some(Step::Model),
-        }
-    }
+struct PullTask {
+    model: String,
+    last: PullProgress,
+}
 
-    fn next(self) -> Option<Self> {
-        match self {
-            Step::Language => Some(Step::Provider),
-            Step::Provider => Some(Step::Model),
-            Step::Model => Some(Step::Summary),
-            Step::Summary => None,
-        }
-    }
+/// Background work of the wizard; at most one of each runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Task {
+    /// Installed models, for the model step; limited to `network.timeout_secs`.
+    OllamaTags,
+    /// `ollama pull`; a download takes as long as it takes, so no timeout.
+    Pull,
 }
 
-struct TerminalGuard;
+/// The model step's text inputs, in Tab order.
+const INPUTS: [Field; 3] = [Field::Model, Field::BaseUrl, Field::ApiKeyEnv];
 
-impl TerminalGuard {
-    fn enter() -> Result<Self> {
-        enable_raw_mode()?;
-        execute!(io::stdout(), EnterAlternateScreen)?;
-        Ok(Self)
+fn input_label(field: Field) -> &'static str {
+    match field {
+        Field::BaseUrl => "Endpoint (base_url)",
+        Field::ApiKeyEnv => "API key variable (api_key_env)",
+        _ => "Model",
     }
 }
 
-impl Drop for TerminalGuard {
-    fn drop(&mut self) {
-        let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
-    }
+/// The input after `field` (`forward`) or before it, wrapping around.
+fn next_input(field: Field, forward: bool) -> Field {
+    let i = INPUTS.iter().position(|f| *f == field).unwrap_or(0);
+    let step = if forward { 1 } else { INPUTS.len() - 1 };
+    INPUTS[(i + step) % INPUTS.len()]
 }
 
-#[derive(Debug, Clone)]
-struct LangOption {
-    code: &'static str,
-    name: &'static str,
-    supported: bool,
+enum TaskMessage {
+    Tags(OllamaModels),
+    PullProgress(PullProgress),
+    PullDone(Result<(), String>),
 }
 
-#[derive(Debug, Clone)]
 struct UiState {
-    step: Step,
     status: String,
 
-    lang_state: ListState,
-    provider_state: ListState,
-
-    model_input: String,
+    import_state: ListState,
+    /// The language under the cursor, by code; see `cursor_index`.
+    lang_cursor: String,
+    /// The provider under the cursor.
+    provider_cursor: ProviderKind,
+
+    model_input: TextInput,
+    /// `provider.base_url` and `provider.api_key_env` as typed; empty means the default.
+    base_url_input: TextInput,
+    api_key_env_input: TextInput,
+    /// The input typed keys go to.
+    field: Field,
+    /// Models saved before, per provider; offered under the model input.
+    recent: RecentModels,
+
+    ollama: OllamaModels,
+    /// Known model names, for the model hint; Ollama's installed ones join it once listed.
+    catalog: Catalog,
+    /// The tags request (started when Ollama is chosen) and a running pull.
+    tasks: TaskPool<Task, TaskMessage>,
+    suggest_state: ListState,
+    pull: PullState,
+    /// Model the user chose but did not pull; the command is printed after the wizard.
+    pending_pull: Option<String>,
 
     use_colors: bool,
     use_animation: bool,
+    /// Unicode or ASCII symbols; see `glyphs::detect`.
+    glyphs: Glyphs,
+
+    keys: KeyMap,
+
+    /// Help shown over the content (`?` / F1), for terminals too narrow for the panel.
+    help_overlay: bool,
+
+    /// Summary step shows the config.toml that will be written instead of the summary.
+    preview: bool,
+    preview_scroll: u16,
+    /// The saved config as `save_config` would write it; empty when there is no file yet.
+    saved_config: String,
+    /// Quitting would discard changes; asking first (`quit`).
+    confirm_quit: bool,
 
     tick: u64,
-    last_tick: Instant,
 }
 
 impl UiState {
-    fn new(existing: &AppConfig) -> Self {
-        let langs = language_options();
-        let providers = provider_options();
-
-        let mut lang_state = ListState::default();
-        let lang_idx = langs
-            .iter()
-            .position(|x| x.code == existing.language.as_str())
-            .unwrap_or(0);
-        lang_state.select(Some(lang_idx));
-
-        let mut provider_state = ListState::default();
-        let provider_idx = providers
-            .iter()
-            .position(|p| *p == existing.provider.kind)
-            .unwrap_or(0);
-        provider_state.select(Some(provider_idx));
-
-        Self {
-            step: Step::Language,
-            status:
-                "↑↓ Navigate | Enter Next | Esc/Backspace/← Back | q Quit | C Colors | A Animation"
-                    .to_string(),
-            lang_state,
-            provider_state,
-            model_input: existing.provider.model.clone(),
+    fn new(existing: &AppConfig, start: Option<Step>, wiz: &WizardModel) -> Self {
+        let glyphs = glyphs::detect(&existing.ui);
+        let keys = KeyMap::new(&existing.keybindings, glyphs);
+        let saved_config = match config_file_path() {
+            Ok(path) if path.exists() => render_config(existing).unwrap_or_default(),
+            _ => String::new(),
+        };
+        let status = match start {
+            Some(step) => format!("{}: {} returns to the summary", wiz.title(step, false), keys.label(Action::Next)),
+            None => format!(
+                "{} Navigate | {} Next | {} Back | {} Quit | {} Colors | {} Animation",
+                glyphs.up_down,
+                keys.label(Action::Next),
+                keys.label(Action::Back),
+                keys.label(Action::Quit),
+                keys.label(Action::ToggleColors),
+                keys.label(Action::ToggleAnimation),
+            ),
+        };
+        let mut ui = Self {
+            status,
+            import_state: {
+                let mut state = ListState::default();
+                state.select(Some(0));
+                state
+            },
+            lang_cursor: existing.language.clone(),
+            provider_cursor: existing.provider.kind.clone(),
+            model_input: TextInput::with_text(existing.provider.model.clone()),
+            base_url_input: TextInput::with_text(existing.provider.base_url.clone().unwrap_or_default()),
+            api_key_env_input: TextInput::with_text(existing.provider.api_key_env.clone().unwrap_or_default()),
+            field: Field::Model,
+            recent: RecentModels::load(),
+            ollama: OllamaModels::Unknown,
+            catalog: Catalog::load(),
+            tasks: TaskPool::new(),
+            suggest_state: {
+                let mut state = ListState::default();
+                state.select(Some(0));
+                state
+            },
+            pull: PullState::Idle,
+            pending_pull: None,
             use_colors: true,
-            use_animation: true,
+            use_animation: glyphs.animate,
+            glyphs,
+            keys,
+            help_overlay: false,
+            preview: false,
+            preview_scroll: 0,
+            saved_config,
+            confirm_quit: false,
             tick: 0,
-            last_tick: Instant::now(),
+        };
+        // The provider step starts the probe; opening at the model step starts it here.
+        if start == Some(Step::Model) {
+            probe_ollama(&mut ui, existing);
         }
+        ui
     }
 }
 
-/* ---------------------------
-   Customization points
-   - Add languages in language_options()
-   - Add providers in provider_options()
-   - Adjust UI strings in help_text()
----------------------------- */
+impl UiState {
+    /// Whether keys are being typed into a text field (the model name).
+    fn editing_text(&self, step: Step) -> bool {
+        step == Step::Model && self.ollama != OllamaModels::NoneInstalled
+    }
 
-fn language_options() -> Vec<LangOption> {
-    let supported = allowed_languages();
-
-    // Add more languages here. Only supported languages will be selectable until config allows them.
-    // When you expand config/mod.rs allowed_languages(), these will automatically become selectable.
-    let all = vec![
-        ("en", "English"),
-        ("ar", "العربية"),
-        ("no", "Norsk"),
-        ("zh", "中文"),
-        ("es", "Español"),
-        ("fr", "Français"),
-        ("de", "Deutsch"),
-        ("tr", "Türkçe"),
-        ("ru", "Русский"),
-        ("ja", "日本語"),
-        ("ko", "한국어"),
-    ];
+    fn input(&self, field: Field) -> &TextInput {
+        match field {
+            Field::BaseUrl => &self.base_url_input,
+            Field::ApiKeyEnv => &self.api_key_env_input,
+            _ => &self.model_input,
+        }
+    }
 
-    all.into_iter()
-        .map(|(code, name)| LangOption {
-            code,
-            name,
-            supported: supported.contains(code),
-        })
-        .collect()
-}
+    fn input_mut(&mut self) -> &mut TextInput {
+        match self.field {
+            Field::BaseUrl => &mut self.base_url_input,
+            Field::ApiKeyEnv => &mut self.api_key_env_input,
+            _ => &mut self.model_input,
+        }
+    }
 
-fn provider_options() -> Vec<ProviderKind> {
-    // Add more providers here.
-    vec![
-        ProviderKind::Ollama,
-        ProviderKind::OpenAI,
-        ProviderKind::Claude,
-        ProviderKind::OpenRouter,
-    ]
-}
+    /// The language list's selection, found by code in `langs` as listed now.
+    fn lang_state(&self, langs: &[LangOption]) -> ListState {
+        ListState::default().with_selected(Some(cursor_index(langs, |l| l.code == self.lang_cursor)))
+    }
+
+    /// The provider list's selection, found by kind in `providers` as listed now.
+    fn provider_state(&self, providers: &[ProviderKind]) -> ListState {
+        ListState::default().with_selected(Some(cursor_index(providers, |p| *p == self.provider_cursor)))
+    }
+
+    /// Fill the inputs from the draft, e.g. after a provider change brought its defaults.
+    fn load_inputs(&mut self, draft: &WizardDraft) {
+        self.model_input.set(draft.text(Field::Model).to_string());
+        self.base_url_input.set(draft.text(Field::BaseUrl).to_string());
+        self.api_key_env_input.set(draft.text(Field::ApiKeyEnv).to_string());
+    }
 
-fn provider_name(p: &ProviderKind) -> &'static str {
-    match p {
-        ProviderKind::Ollama => "Ollama",
-        ProviderKind::OpenAI => "OpenAI",
-        ProviderKind::Claude => "Claude",
-        ProviderKind::OpenRouter => "OpenRouter",
+    /// The hint for `field`: the draft's check, with the catalog's notice on a model.
+    fn check(&self, draft: &WizardDraft, field: Field) -> Check {
+        let check = draft.check(field);
+        match (&check, field) {
+            (Check::Ok(_), Field::Model) => match self.catalog.notice(draft.kind(), draft.text(field)) {
+                Some(notice) => Check::Warning(notice),
+                None => check,
+            },
+            _ => check,
+        }
     }
 }
 
-fn help_text(step: Step) -> Text<'static> {
+/* ---------------------------
+   Customization points
+   - Languages and providers are listed in `setup`
+   - Adjust UI strings in help_text()
+---------------------------- */
+
+fn help_text(step: Step, keys: &KeyMap, g: &Glyphs) -> Text<'static> {
+    let next = keys.label(Action::Next);
+    let back = format!("Back: {}", keys.label(Action::Back));
+    let quit = format!("Quit: {} (without saving)", keys.label(Action::Quit));
+
     let lines: Vec<Line> = match step {
+        Step::Import => vec![
+            Line::from("AION found provider settings from your environment or other tools."),
+            Line::from(""),
+            Line::from(format!("{} on one to use it and go straight to the summary,", next)),
+            Line::from("or pick \"Set up manually\" to go through every step."),
+            Line::from(""),
+            Line::from(format!("Keys: {} move, {} select", g.up_down, next)),
+            Line::from(quit),
+        ],
         Step::Language => vec![
             Line::from("Choose the UI language for AION."),
             Line::from(""),
-            Line::from("Keys: ↑↓ move, Enter next"),
-            Line::from("Back: Esc / Backspace / ← / b"),
-            Line::from("Quit: q (without saving)"),
-            Line::from("Toggle: C colors, A animation"),
+            Line::from(format!("Keys: {} move, {} next", g.up_down, next)),
+            Line::from(back),
+            Line::from(quit),
+            Line::from(format!(
+                "Toggle: {} colors, {} animation",
+                keys.label(Action::ToggleColors),
+                keys.label(Action::ToggleAnimation)
+            )),
             Line::from(""),
             Line::from("Note: Non-supported languages are shown but not selectable yet."),
         ],
         Step::Provider => vec![
             Line::from("Choose your AI provider."),
             Line::from(""),
-            Line::from("Keys: ↑↓ move, Enter next"),
-            Line::from("Back: Esc / Backspace / ← / b"),
-            Line::from("Quit: q (without saving)"),
+            Line::from(format!("Keys: {} move, {} next", g.up_down, next)),
+            Line::from(back),
+            Line::from(quit),
         ],
         Step::Model => vec![
             Line::from("Type the model name."),
+            Line::from("Tab moves to the endpoint (base_url) and the variable holding"),
+            Line::from("the API key (api_key_env); empty uses the provider's default."),
+            Line::from("The line under each input says what will be used, or what is wrong."),
             Line::from(""),
             Line::from("Examples:"),
             Line::from(" - Ollama: mistral, llama3, qwen2.5"),
@@ -211,15 +322,23 @@ fn help_text(step: Step) -> Text<'static> {
             Line::from(" - Claude: claude-3.5-sonnet"),
             Line::from(" - OpenRouter: meta-llama/llama-3.1-70b-instruct"),
             Line::from(""),
-            Line::from("Keys: type, Backspace delete, Enter next"),
-            Line::from("Back: Esc / Backspace / ← / b"),
-            Line::from("Quit: q (without saving)"),
+            Line::from("Ollama with no models installed: pick one from the list,"),
+            Line::from("then p to pull it now or s to pull it later yourself."),
+            Line::from(""),
+            Line::from("Models saved before are listed under the input: Alt+1..9"),
+            Line::from(format!("picks one, {} step through them from an empty input.", g.up_down)),
+            Line::from(""),
+            Line::from(format!("Keys: type, {} Home End move, Tab next input, {} next", g.left_right, next)),
+            Line::from(back),
+            // Letters are typed here, so a quit key bound to one does not apply.
+            Line::from("Quit: Ctrl+C (without saving)"),
         ],
         Step::Summary => vec![
             Line::from("Review settings."),
-            Line::from("Enter = Save & exit"),
-            Line::from("Back: Esc / Backspace / ← / b"),
-            Line::from("Quit: q (without saving)"),
+            Line::from(format!("{} = Save & exit", next)),
+            Line::from(format!("p = Preview config.toml ({} scroll, p again to close)", g.up_down)),
+            Line::from(back),
+            Line::from(quit),
             Line::from(""),
             Line::from("Cargo tip: pass args after --"),
             Line::from("Example: cargo run -p aion -- --setup"),
@@ -290,8 +409,42 @@ fn s_inactive(ui: &UiState) -> Style {
     }
 }
 
+fn s_dim(ui: &UiState) -> Style {
+    if ui.use_colors {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    }
+}
+
+fn s_warning(ui: &UiState) -> Style {
+    if ui.use_colors {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    }
+}
+
+fn s_check(ui: &UiState, check: &Check) -> Style {
+    match check {
+        Check::Ok(_) => s_active(ui),
+        Check::Warning(_) => s_warning(ui),
+        Check::Error(_) => s_inactive(ui),
+    }
+}
+
+/// The hint under an input: what will be used, or what is wrong with it.
+fn hint_line(ui: &UiState, check: &Check) -> Line<'static> {
+    let prefix = match check {
+        Check::Ok(_) => "",
+        Check::Warning(_) => "warning: ",
+        Check::Error(_) => "error: ",
+    };
+    Line::from(Span::styled(format!("  {}{}", prefix, check.message()), s_check(ui, check)))
+}
+
 fn dot_span(ui: &UiState, is_cursor: bool, is_active: bool, is_valid: bool) -> Span<'static> {
-    let symbol = "● ";
+    let symbol = ui.glyphs.dot;
     if !ui.use_colors {
         return Span::raw(symbol);
     }
@@ -306,47 +459,40 @@ fn dot_span(ui: &UiState, is_cursor: bool, is_active: bool, is_valid: bool) -> S
     }
 }
 
-fn step_dots(ui: &UiState, draft: &AppConfig) -> Line<'static> {
-    let langs = language_options();
-    let lang_done = langs
-        .iter()
-        .any(|l| l.code == draft.language.as_str() && l.supported);
-
-    let provider_done = true; // provider is always set to some value
-    let model_done = !draft.provider.model.trim().is_empty();
-
-    let dot = |active: bool, done: bool| -> Span<'static> {
-        if !ui.use_colors {
-            return Span::raw("●");
-        }
-        let color = if active {
+fn step_dots(ui: &UiState, wiz: &WizardModel) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (i, step) in wiz.shown().into_iter().enumerate() {
+        let done = wiz.step_done(step);
+        let color = if wiz.step == step {
             Color::Cyan
         } else if done {
             Color::Green
         } else {
             Color::Red
         };
-        Span::styled("●", Style::default().fg(color))
-    };
-
-    let d1 = dot(ui.step == Step::Language, lang_done);
-    let d2 = dot(ui.step == Step::Provider, provider_done);
-    let d3 = dot(ui.step == Step::Model, model_done);
-    let d4 = dot(ui.step == Step::Summary, lang_done && provider_done && model_done);
+        let style = if ui.use_colors { Style::default().fg(color) } else { Style::default() };
+        match ui.glyphs.step_dot {
+            Some(dot) => spans.extend([Span::raw(" "), Span::styled(dot, style)]),
+            None => spans.push(Span::styled(format!("[{}]", i + 1), style)),
+        }
+    }
+    Line::from(spans)
+}
 
-    Line::from(vec![
-        Span::raw(" "),
-        d1,
-        Span::raw(" "),
-        d2,
-        Span::raw(" "),
-        d3,
-        Span::raw(" "),
-        d4,
-    ])
+/// A block with the wizard's borders (`Glyphs::border`).
+fn bordered(ui: &UiState) -> Block<'static> {
+    Block::default().borders(Borders::ALL).border_set(ui.glyphs.border)
 }
 
-fn block_with_steps(title: &str, ui: &UiState, draft: &AppConfig) -> Block<'static> {
+/// A bordered block titled `title` plus the step dots; the title is shortened to fit `width`.
+fn block_with_steps(title: &str, ui: &UiState, wiz: &WizardModel, width: u16) -> Block<'static> {
+    // Borders, the two-space gap, and the dots (" ● ● ● ●" or "[1][2][3][4]", one per step).
+    let labels: String = (1..=wiz.shown().len()).map(|i| format!("[{i}]")).collect();
+    let dots = match (ui.use_colors, ui.glyphs.step_dot) {
+        (true, Some(_)) => 2 * wiz.shown().len(),
+        _ => labels.len(),
+    };
+    let title = ui.glyphs.truncate(title, usize::from(width).saturating_sub(2 + 2 + dots));
     if ui.use_colors {
         let mut spans = vec![
             Span::styled(
@@ -357,14 +503,10 @@ fn block_with_steps(title: &str, ui: &UiState, draft: &AppConfig) -> Block<'stat
             ),
             Span::raw("  "),
         ];
-        spans.extend(step_dots(ui, draft).spans);
-        Block::default()
-            .borders(Borders::ALL)
-            .title(Line::from(spans))
+        spans.extend(step_dots(ui, wiz).spans);
+        bordered(ui).title(Line::from(spans))
     } else {
-        Block::default()
-            .borders(Borders::ALL)
-            .title(Line::from(format!("{title}  [1][2][3][4]")))
+        bordered(ui).title(Line::from(format!("{title}  {labels}")))
     }
 }
 
@@ -372,151 +514,300 @@ fn block_with_steps(title: &str, ui: &UiState, draft: &AppConfig) -> Block<'stat
    Public entry
 ---------------------------- */
 
-pub fn run(existing: &AppConfig) -> Result<AppConfig> {
-    let _guard = TerminalGuard::enter().map_err(|e| {
+/// Run the wizard over `existing`, from the first step or from `start`.
+pub async fn run(existing: &AppConfig, start: Option<Step>) -> Result<AppConfig> {
+    let (cfg, pending_pull) = run_ui(existing, start).await?;
+    // Printed after the terminal is restored so it stays on screen.
+    if let Some(model) = pending_pull {
+        println!("No Ollama models are installed. To download the selected one, run:");
+        println!("  ollama pull {}", model);
+        println!();
+    }
+    Ok(cfg)
+}
+
+async fn run_ui(existing: &AppConfig, start: Option<Step>) -> Result<(AppConfig, Option<String>)> {
+    let guard = TerminalGuard::enter().map_err(|e| {
         anyhow!(
-            "Failed to initialize terminal UI. Try Windows Terminal or VS Code terminal. Error: {}",
+            "cannot open the full-screen wizard ({}). Run `aion --setup --plain` to answer its questions \
+             line by line; if the wizard opens but shows boxes or question marks, add --ascii.",
             e
         )
     })?;
+    let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    guard.run_with(terminal, async |terminal| event_loop(terminal, existing, start).await).await
+}
 
-    let stdout: Stdout = io::stdout();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+async fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    existing: &AppConfig,
+    start: Option<Step>,
+) -> Result<(AppConfig, Option<String>)> {
     terminal.clear()?;
 
-    let mut ui = UiState::new(existing);
-    let mut draft = existing.clone();
+    let detected = setup::detect(existing, start).await;
+    let mut wiz = WizardModel::new(existing, start, detected);
+    let mut ui = UiState::new(existing, start, &wiz);
 
-    let tick_rate = Duration::from_millis(90);
+    let mut events = EventStream::new();
+    let animation = |ui: &UiState| ui.use_animation.then_some(TICK_RATE);
+    let mut frames = Frames::new(animation(&ui), Instant::now());
 
     loop {
-        // Animation tick
-        if ui.use_animation && ui.last_tick.elapsed() >= tick_rate {
-            ui.tick = ui.tick.wrapping_add(1);
-            ui.last_tick = Instant::now();
+        if frames.take() {
+            terminal.draw(|f| draw_ui(f, &ui, &wiz))?;
         }
-
-        terminal.draw(|f| draw_ui(f, &ui, &draft))?;
-
-        if event::poll(Duration::from_millis(60))? {
-            let ev = event::read()?;
-            if let Event::Key(key) = ev {
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
-
-                // Global toggles
-                match key.code {
-                    KeyCode::Char('c') | KeyCode::Char('C') => {
-                        ui.use_colors = !ui.use_colors;
-                        ui.status = format!(
-                            "Colors: {} | Animation: {}",
-                            if ui.use_colors { "ON" } else { "OFF" },
-                            if ui.use_animation { "ON" } else { "OFF" }
-                        );
-                        continue;
+        // Without animation there is no deadline, and the branch is disabled.
+        let deadline = frames.deadline();
+        let tick = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into());
+
+        // Any `return` drops `ui`, and its task pool cancels what is still running.
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    let before = wiz.step;
+                    if let Some(done) = handle_key(&mut ui, &mut wiz, key)? {
+                        return Ok(done);
                     }
-                    KeyCode::Char('a') | KeyCode::Char('A') => {
-                        ui.use_animation = !ui.use_animation;
-                        ui.status = format!(
-                            "Colors: {} | Animation: {}",
-                            if ui.use_colors { "ON" } else { "OFF" },
-                            if ui.use_animation { "ON" } else { "OFF" }
-                        );
-                        continue;
+                    if wiz.step != before {
+                        step_changed(&mut ui, &wiz);
                     }
-                    KeyCode::Char('q') => return Err(anyhow!("Wizard cancelled by user")),
-                    _ => {}
+                    frames.set_tick(animation(&ui), Instant::now());
+                    frames.changed();
                 }
-
-                // Back navigation
-                if matches!(
-                    key.code,
-                    KeyCode::Esc | KeyCode::Backspace | KeyCode::Left | KeyCode::Char('b')
-                ) {
-                    if let Some(prev) = ui.step.prev() {
-                        ui.step = prev;
-                        ui.status = "Back to previous step".to_string();
-                    } else {
-                        // If already at the first step, treat as cancel
-                        return Err(anyhow!("Wizard cancelled by user"));
-                    }
-                    continue;
+                Some(Ok(Event::Resize(..))) => frames.changed(),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Err(anyhow!("Wizard cancelled by user")),
+            },
+            _ = tick, if deadline.is_some() => {
+                if frames.tick(Instant::now()) {
+                    ui.tick = ui.tick.wrapping_add(1);
                 }
+            }
+            (task, event) = ui.tasks.next() => {
+                apply_task_event(&mut ui, &mut wiz, task, event);
+                frames.changed();
+            }
+        }
+    }
+}
 
-                // Step handlers
-                match ui.step {
-                    Step::Language => handle_language_step(&mut ui, &mut draft, key.code),
-                    Step::Provider => handle_provider_step(&mut ui, &mut draft, key.code),
-                    Step::Model => handle_model_step(&mut ui, &mut draft, key.code),
-                    Step::Summary => {
-                        if key.code == KeyCode::Enter {
-                            draft.validate()?;
-                            return Ok(draft);
-                        }
-                    }
+/// Handle one key press. `Ok(Some(..))` finishes the wizard; `Err` cancels it.
+fn handle_key(
+    ui: &mut UiState,
+    wiz: &mut WizardModel,
+    key: KeyEvent,
+) -> Result<Option<(AppConfig, Option<String>)>> {
+    // Raw mode delivers Ctrl+C as a key event rather than SIGINT; treat it like `q`.
+    let ctrl_c = key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+
+    // The discard prompt takes the next key: `y`, the quit key or Ctrl+C discards.
+    if ui.confirm_quit {
+        ui.confirm_quit = false;
+        if ctrl_c || key.code == KeyCode::Char('y') || ui.keys.action(&key) == Some(Action::Quit) {
+            return Err(anyhow!("Wizard cancelled by user"));
+        }
+        ui.status = "Kept editing".to_string();
+        return Ok(None);
+    }
+    if ctrl_c {
+        return quit(ui, wiz);
+    }
+
+    // While a pull runs only Esc (cancel) is handled.
+    if let PullState::Running(task) = &ui.pull {
+        if key.code == KeyCode::Esc {
+            let model = task.model.clone();
+            ui.tasks.cancel(Task::Pull);
+            audit::command(&format!("ollama pull {}", model), None, None);
+            ui.pull = PullState::Offer { model, error: None };
+            ui.status = "Pull cancelled".to_string();
+        }
+        return Ok(None);
+    }
+
+    // The help overlay takes every key until it is closed. `?` is typed text on the
+    // model name field, so there only F1 opens it.
+    let help_key = key.code == KeyCode::F(1) || (key.code == KeyCode::Char('?') && !ui.editing_text(wiz.step));
+    if ui.help_overlay {
+        if help_key || key.code == KeyCode::Esc {
+            ui.help_overlay = false;
+        }
+        return Ok(None);
+    }
+    if help_key {
+        ui.help_overlay = true;
+        return Ok(None);
+    }
+
+    // Alt+1..9 picks a recent model on the model name field.
+    if let (true, KeyCode::Char(c @ '1'..='9')) = (ui.editing_text(wiz.step), key.code) {
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            pick_recent(ui, &mut wiz.draft, usize::from(c as u8 - b'1'));
+            return Ok(None);
+        }
+    }
+
+    // Printable keys and the editing keys work on a text field instead of running what
+    // they are bound to ("b", "q", ...); Backspace on an empty field and ← at its start
+    // still go back.
+    let typing = ui.editing_text(wiz.step)
+        && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        && match key.code {
+            KeyCode::Char(_) | KeyCode::Delete | KeyCode::Home | KeyCode::End => true,
+            KeyCode::Backspace => !ui.input(ui.field).is_empty(),
+            KeyCode::Left => !ui.input(ui.field).at_start(),
+            KeyCode::Right => !ui.input(ui.field).at_end(),
+            _ => false,
+        };
+    let action = if typing { None } else { ui.keys.action(&key) };
+    match action {
+        Some(Action::ToggleColors) => {
+            ui.use_colors = !ui.use_colors;
+            ui.status = format!(
+                "Colors: {} | Animation: {}",
+                if ui.use_colors { "ON" } else { "OFF" },
+                if ui.use_animation { "ON" } else { "OFF" }
+            );
+            return Ok(None);
+        }
+        Some(Action::ToggleAnimation) => {
+            ui.use_animation = !ui.use_animation;
+            ui.status = format!(
+                "Colors: {} | Animation: {}",
+                if ui.use_colors { "ON" } else { "OFF" },
+                if ui.use_animation { "ON" } else { "OFF" }
+            );
+            return Ok(None);
+        }
+        Some(Action::Quit) => return quit(ui, wiz),
+        Some(Action::Back) => {
+            if wiz.step == Step::Summary && ui.preview {
+                ui.preview = false;
+                ui.status = "Back to summary".to_string();
+            } else if wiz.back() {
+                ui.status = match wiz.step {
+                    Step::Import => "Back to detected setup".to_string(),
+                    _ => "Back to previous step".to_string(),
+                };
+            } else {
+                // If already at the first step, treat as cancel
+                return quit(ui, wiz);
+            }
+            return Ok(None);
+        }
+        Some(Action::JumpStep(n)) => {
+            if let Some(step) = wiz.nth(n) {
+                wiz.step = step;
+                ui.status = wiz.title(step, false);
+            }
+            return Ok(None);
+        }
+        Some(Action::Next) | None => {}
+    }
+
+    // Step handlers read Enter as "next", whichever key is bound to it.
+    let code = if action == Some(Action::Next) {
+        KeyCode::Enter
+    } else if key.code == KeyCode::Enter {
+        KeyCode::Null
+    } else {
+        key.code
+    };
+
+    match wiz.step {
+        Step::Import => handle_import_step(ui, wiz, code),
+        Step::Language => handle_language_step(ui, wiz, code),
+        Step::Provider => handle_provider_step(ui, wiz, code),
+        Step::Model => handle_model_step(ui, wiz, code),
+        Step::Summary => {
+            if code == KeyCode::Enter {
+                match wiz.finish() {
+                    Ok(cfg) => return Ok(Some((cfg, ui.pending_pull.take()))),
+                    Err(errors) => cannot_save(ui, wiz, &errors),
                 }
+                return Ok(None);
             }
+            handle_summary_step(ui, &wiz.config(), code);
         }
     }
+    Ok(None)
 }
 
 /* ---------------------------
    Step handlers
 ---------------------------- */
 
-fn handle_language_step(ui: &mut UiState, draft: &mut AppConfig, code: KeyCode) {
-    let langs = language_options();
-    let max = langs.len().saturating_sub(1);
+fn handle_import_step(ui: &mut UiState, wiz: &mut WizardModel, code: KeyCode) {
+    // The last entry is "set up manually".
+    let count = wiz.detected.len() + 1;
 
     match code {
-        KeyCode::Up => {
-            let cur = ui.lang_state.selected().unwrap_or(0);
-            ui.lang_state.select(Some(cur.saturating_sub(1)));
+        KeyCode::Up | KeyCode::Down => {
+            step_selection(&mut ui.import_state, count, code);
         }
-        KeyCode::Down => {
-            let cur = ui.lang_state.selected().unwrap_or(0);
-            ui.lang_state.select(Some((cur + 1).min(max)));
+        KeyCode::Enter => {
+            let idx = ui.import_state.selected().unwrap_or(0);
+            let Some(found) = wiz.detected.get(idx).cloned() else {
+                wiz.advance();
+                ui.status = "Manual setup".to_string();
+                return;
+            };
+            wiz.adopt(&found);
+            ui.load_inputs(&wiz.draft);
+            ui.provider_cursor = wiz.draft.kind().clone();
+            ui.ollama = OllamaModels::Unknown;
+            probe_ollama(ui, &wiz.config());
+            ui.pull = PullState::Idle;
+            ui.status = format!("Adopted {}", import_label(&found, wiz.draft.language()));
+        }
+        _ => {}
+    }
+}
+
+fn handle_language_step(ui: &mut UiState, wiz: &mut WizardModel, code: KeyCode) {
+    let langs = language_options();
+    let mut state = ui.lang_state(&langs);
+
+    match code {
+        KeyCode::Up | KeyCode::Down => {
+            step_selection(&mut state, langs.len(), code);
+            if let Some(l) = state.selected().and_then(|i| langs.get(i)) {
+                ui.lang_cursor = l.code.clone();
+            }
         }
         KeyCode::Enter => {
-            let idx = ui.lang_state.selected().unwrap_or(0);
-            if let Some(sel) = langs.get(idx) {
-                if !sel.supported {
-                    ui.status = "This language is not supported yet".to_string();
-                    return;
-                }
-                draft.language = sel.code.to_string();
-                if let Some(next) = ui.step.next() {
-                    ui.step = next;
-                }
-                ui.status = "Language selected".to_string();
+            if let Some(sel) = state.selected().and_then(|i| langs.get(i)) {
+                ui.status = match wiz.set_language(&sel.code) {
+                    Ok(()) => "Language selected".to_string(),
+                    Err(e) => e.to_string(),
+                };
             }
         }
         _ => {}
     }
 }
 
-fn handle_provider_step(ui: &mut UiState, draft: &mut AppConfig, code: KeyCode) {
+fn handle_provider_step(ui: &mut UiState, wiz: &mut WizardModel, code: KeyCode) {
     let providers = provider_options();
-    let max = providers.len().saturating_sub(1);
+    let mut state = ui.provider_state(&providers);
 
     match code {
-        KeyCode::Up => {
-            let cur = ui.provider_state.selected().unwrap_or(0);
-            ui.provider_state.select(Some(cur.saturating_sub(1)));
-        }
-        KeyCode::Down => {
-            let cur = ui.provider_state.selected().unwrap_or(0);
-            ui.provider_state.select(Some((cur + 1).min(max)));
+        KeyCode::Up | KeyCode::Down => {
+            step_selection(&mut state, providers.len(), code);
+            if let Some(kind) = state.selected().and_then(|i| providers.get(i)) {
+                ui.provider_cursor = kind.clone();
+            }
         }
         KeyCode::Enter => {
-            let idx = ui.provider_state.selected().unwrap_or(0);
-            if let Some(kind) = providers.get(idx).cloned() {
-                draft.provider.kind = kind;
-                if let Some(next) = ui.step.next() {
-                    ui.step = next;
+            if let Some(kind) = state.selected().and_then(|i| providers.get(i)).cloned() {
+                if wiz.set_provider(kind) {
+                    ui.load_inputs(&wiz.draft);
                 }
+                ui.ollama = OllamaModels::Unknown;
+                probe_ollama(ui, &wiz.config());
+                ui.pull = PullState::Idle;
                 ui.status = "Provider selected".to_string();
             }
         }
@@ -524,39 +815,277 @@ fn handle_provider_step(ui: &mut UiState, draft: &mut AppConfig, code: KeyCode)
     }
 }
 
-fn handle_model_step(ui: &mut UiState, draft: &mut AppConfig, code: KeyCode) {
+fn handle_model_step(ui: &mut UiState, wiz: &mut WizardModel, code: KeyCode) {
+    if ui.ollama == OllamaModels::NoneInstalled {
+        return handle_ollama_suggestions(ui, wiz, code);
+    }
+
     match code {
-        KeyCode::Backspace => {
-            ui.model_input.pop();
-            draft.provider.model = ui.model_input.clone();
+        KeyCode::Tab => ui.field = next_input(ui.field, true),
+        KeyCode::BackTab => ui.field = next_input(ui.field, false),
+        KeyCode::Backspace if ui.input_mut().backspace() => apply_input(ui, wiz),
+        KeyCode::Delete if ui.input_mut().delete() => apply_input(ui, wiz),
+        KeyCode::Left => {
+            ui.input_mut().left();
+        }
+        KeyCode::Right => {
+            ui.input_mut().right();
         }
+        KeyCode::Home => ui.input_mut().home(),
+        KeyCode::End => ui.input_mut().end(),
         KeyCode::Enter => {
-            let trimmed = ui.model_input.trim();
-            if trimmed.is_empty() {
-                ui.status = "Model cannot be empty".to_string();
+            if let Some((field, check)) =
+                INPUTS.into_iter().map(|f| (f, ui.check(&wiz.draft, f))).find(|(_, c)| c.is_error())
+            {
+                ui.field = field;
+                ui.status = format!("{}: {}", input_label(field), check.message());
+                return;
+            }
+            ui.status = match wiz.set_model(ui.model_input.as_str()) {
+                Ok(()) => "Model selected".to_string(),
+                Err(e) => e.to_string(),
+            };
+        }
+        KeyCode::Char(c) if !c.is_control() => {
+            ui.input_mut().insert(c);
+            apply_input(ui, wiz);
+        }
+        // ↑/↓ step through the recent models while the input is empty or holds one of them.
+        KeyCode::Up | KeyCode::Down if ui.field == Field::Model => {
+            let draft = &mut wiz.draft;
+            let recent = ui.recent.models(draft.kind());
+            let current = recent.iter().position(|m| m == ui.model_input.as_str());
+            let next = match (code, current) {
+                (KeyCode::Up, None) if ui.model_input.is_empty() => Some(0),
+                (KeyCode::Up, Some(i)) => Some(i + 1),
+                (KeyCode::Down, Some(i)) => i.checked_sub(1),
+                _ => None,
+            };
+            if let Some(i) = next {
+                pick_recent(ui, draft, i);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Copy the focused input to the draft, as typed.
+fn apply_input(ui: &UiState, wiz: &mut WizardModel) {
+    wiz.draft.set_text(ui.field, ui.input(ui.field).as_str());
+}
+
+/// Put the `index`th recent model (0 = most recent) in the model input, if there is one.
+fn pick_recent(ui: &mut UiState, draft: &mut WizardDraft, index: usize) {
+    if let Some(model) = ui.recent.models(draft.kind()).get(index) {
+        ui.model_input.set(model.clone());
+        draft.set_text(Field::Model, model);
+        ui.status = format!("Recent model {}: {}", index + 1, model);
+    }
+}
+
+/* ---------------------------
+   Ollama model pull
+---------------------------- */
+
+/// Cancel the wizard; when that would discard changes, ask first.
+fn quit(ui: &mut UiState, wiz: &WizardModel) -> Result<Option<(AppConfig, Option<String>)>> {
+    if discarded(wiz).is_empty() {
+        return Err(anyhow!("Wizard cancelled by user"));
+    }
+    ui.confirm_quit = true;
+    ui.help_overlay = false;
+    ui.status = "Discard these changes? y = Discard and quit | any other key = Keep editing".to_string();
+    Ok(None)
+}
+
+/// What quitting now would throw away: where the draft differs from the starting config.
+fn discarded(wiz: &WizardModel) -> Vec<DiffLine> {
+    config_changes(&wiz.existing, &wiz.config()).unwrap_or_default()
+}
+
+/// After a failed save: go to the first problem's step (and input), and say what it is.
+fn cannot_save(ui: &mut UiState, wiz: &mut WizardModel, errors: &[(Field, ConfigError)]) {
+    wiz.show_problems(errors);
+    let Some((field, first)) = errors.first() else { return };
+    if INPUTS.contains(field) {
+        ui.field = *field;
+    }
+    ui.status = match errors.len() {
+        1 => format!("Cannot save: {}", first),
+        n => format!("Cannot save: {} (and {} more)", first, n - 1),
+    };
+}
+
+/// Ask the Ollama server which models are installed. `None` (no request) when the
+/// provider is not Ollama or network access is disabled.
+fn handle_summary_step(ui: &mut UiState, draft: &AppConfig, code: KeyCode) {
+    match code {
+        KeyCode::Char('p') => {
+            ui.preview = !ui.preview;
+            ui.preview_scroll = 0;
+            ui.status = if !ui.preview {
+                "Back to summary".to_string()
+            } else if ui.saved_config.is_empty() {
+                "Preview: no config file yet, every line is new".to_string()
             } else {
-                draft.provider.model = trimmed.to_string();
-                if let Some(next) = ui.step.next() {
-                    ui.step = next;
-                }
-                ui.status = "Model selected".to_string();
+                "Preview: highlighted lines differ from the saved config".to_string()
+            };
+        }
+        KeyCode::Up if ui.preview => ui.preview_scroll = ui.preview_scroll.saturating_sub(1),
+        KeyCode::Down if ui.preview => {
+            let lines = render_config(draft).map(|t| t.lines().count()).unwrap_or(0);
+            let last = u16::try_from(lines.saturating_sub(1)).unwrap_or(u16::MAX);
+            ui.preview_scroll = ui.preview_scroll.saturating_add(1).min(last);
+        }
+        _ => {}
+    }
+}
+
+fn probe_ollama(ui: &mut UiState, draft: &AppConfig) {
+    ui.tasks.cancel(Task::OllamaTags);
+    let guard = Guard::for_app(draft);
+    if draft.provider.kind != ProviderKind::Ollama || guard.check_network(&draft.provider.effective_base_url()).is_err() {
+        return;
+    }
+    let Ok(client) = OllamaClient::new(draft.provider.effective_base_url(), draft.provider.model.clone()) else {
+        return;
+    };
+    ui.tasks.spawn(Task::OllamaTags, Some(draft.network.timeout()), |_, _| async move {
+        TaskMessage::Tags(match client.list_models().await {
+            Ok(models) if models.is_empty() => OllamaModels::NoneInstalled,
+            Ok(models) => OllamaModels::Installed(models.into_iter().map(|m| m.id).collect()),
+            Err(_) => OllamaModels::Unreachable,
+        })
+    });
+}
+
+/// Keep background work in step with the wizard: the tags are only wanted around the
+/// provider and model steps. Leaving them cancels the request; coming back to the model
+/// step reuses its result, or asks again when it never arrived.
+fn step_changed(ui: &mut UiState, wiz: &WizardModel) {
+    match wiz.step {
+        Step::Provider => {}
+        Step::Model => {
+            if ui.ollama == OllamaModels::Unknown && !ui.tasks.is_running(Task::OllamaTags) {
+                probe_ollama(ui, &wiz.config());
             }
         }
-        KeyCode::Char(c) => {
-            if !c.is_control() {
-                ui.model_input.push(c);
-                draft.provider.model = ui.model_input.clone();
+        _ => ui.tasks.cancel(Task::OllamaTags),
+    }
+}
+
+fn handle_ollama_suggestions(ui: &mut UiState, wiz: &mut WizardModel, code: KeyCode) {
+    if let PullState::Offer { model, .. } = &ui.pull {
+        let model = model.clone();
+        match code {
+            KeyCode::Char('p') | KeyCode::Enter => {
+                if !wiz.draft.caps.run_commands {
+                    ui.status = "Running commands is disabled (caps.run_commands = false); press s to continue".to_string();
+                } else if dry_run::enabled() {
+                    ui.status = format!("{}; press s to continue", dry_run::note_run(&format!("ollama pull {}", model), None));
+                } else {
+                    start_pull(ui, &wiz.config(), &model);
+                    ui.status = format!("Pulling {}... (Esc cancels)", model);
+                }
+            }
+            KeyCode::Char('s') => {
+                ui.pending_pull = Some(model);
+                ui.pull = PullState::Idle;
+                wiz.step = Step::Summary;
+                ui.status = "Model selected; pull it after setup".to_string();
             }
+            KeyCode::Up | KeyCode::Down => ui.pull = PullState::Idle,
+            _ => {}
+        }
+        return;
+    }
+
+    let max = SUGGESTED_OLLAMA_MODELS.len().saturating_sub(1);
+    match code {
+        KeyCode::Up | KeyCode::Down => {
+            step_selection(&mut ui.suggest_state, SUGGESTED_OLLAMA_MODELS.len(), code);
+        }
+        KeyCode::Enter => {
+            let idx = ui.suggest_state.selected().unwrap_or(0);
+            let model = SUGGESTED_OLLAMA_MODELS[idx.min(max)].to_string();
+            wiz.draft.set_text(Field::Model, &model);
+            ui.model_input.set(model.clone());
+            ui.pull = PullState::Offer { model, error: None };
         }
         _ => {}
     }
 }
 
+fn start_pull(ui: &mut UiState, draft: &AppConfig, model: &str) {
+    let base_url = draft.provider.effective_base_url();
+    let request_model = model.to_string();
+    ui.tasks.spawn(Task::Pull, None, |progress, token| async move {
+        let result = async {
+            let client = OllamaClient::new(base_url, request_model.clone())?;
+            client
+                .pull(&request_model, &token, &mut |p| progress.send(TaskMessage::PullProgress(p.clone())))
+                .await
+        };
+        TaskMessage::PullDone(result.await.map_err(|e| format!("{:#}", e)))
+    });
+    ui.pull = PullState::Running(PullTask { model: model.to_string(), last: PullProgress::default() });
+}
+
+/// Apply what a background task reported.
+fn apply_task_event(ui: &mut UiState, wiz: &mut WizardModel, task: Task, event: TaskEvent<TaskMessage>) {
+    match (task, event) {
+        (_, TaskEvent::Message(TaskMessage::Tags(models))) => {
+            if let OllamaModels::Installed(names) = &models {
+                if !dry_run::enabled() {
+                    let _ = catalog::store(&ProviderKind::Ollama, names);
+                }
+                ui.catalog = std::mem::take(&mut ui.catalog).with(&ProviderKind::Ollama, names);
+            }
+            ui.ollama = models;
+        }
+        (Task::OllamaTags, TaskEvent::TimedOut) => ui.ollama = OllamaModels::Unreachable,
+        (_, TaskEvent::Message(TaskMessage::PullProgress(p))) => apply_pull_progress(ui, wiz, p),
+        (_, TaskEvent::Message(TaskMessage::PullDone(result))) => finish_pull(ui, wiz, result),
+        // Pulls have no timeout.
+        (Task::Pull, TaskEvent::TimedOut) => {}
+    }
+}
+
+fn apply_pull_progress(ui: &mut UiState, wiz: &WizardModel, p: PullProgress) {
+    let PullState::Running(task) = &mut ui.pull else { return };
+    if let (Some(done), Some(total)) = (p.completed, p.total) {
+        let lang = wiz.draft.language();
+        ui.status =
+            format!("Pulling {}: {} / {} (Esc cancels)", task.model, format_bytes(lang, done), format_bytes(lang, total));
+    }
+    task.last = p;
+}
+
+/// On completion move on, or back to the offer.
+fn finish_pull(ui: &mut UiState, wiz: &mut WizardModel, outcome: Result<(), String>) {
+    let PullState::Running(task) = &ui.pull else { return };
+    let model = task.model.clone();
+    audit::command(&format!("ollama pull {}", model), None, Some(if outcome.is_ok() { 0 } else { 1 }));
+    match outcome {
+        Ok(()) => {
+            ui.ollama = OllamaModels::Installed(vec![model.clone()]);
+            ui.pending_pull = None;
+            ui.pull = PullState::Idle;
+            wiz.step = Step::Summary;
+            ui.status = format!("Pulled {}", model);
+        }
+        Err(error) => {
+            ui.status = "Pull failed".to_string();
+            ui.pull = PullState::Offer { model, error: Some(error) };
+        }
+    }
+}
+
 /* ---------------------------
    Rendering
 ---------------------------- */
 
-fn draw_ui(f: &mut Frame, ui: &UiState, draft: &AppConfig) {
+fn draw_ui(f: &mut Frame, ui: &UiState, wiz: &WizardModel) {
     let size = f.size();
 
     let outer = Layout::default()
@@ -564,33 +1093,41 @@ fn draw_ui(f: &mut Frame, ui: &UiState, draft: &AppConfig) {
         .constraints([Constraint::Length(3), Constraint::Min(10), Constraint::Length(3)])
         .split(size);
 
-    // Header
+    // Header; the percentage is dropped before the title is shortened.
+    let header_width = usize::from(outer[0].width.saturating_sub(2));
+    let title = match wiz.title(wiz.step, true) {
+        t if width(&t) <= header_width => t,
+        _ => wiz.title(wiz.step, false),
+    };
     let header_text = if ui.use_animation {
-        format!("{}  {}{}", ui.step.title(), spinner_frame(ui.tick), dots_frame(ui.tick))
+        format!("{}  {}{}", title, spinner_frame(ui.tick), dots_frame(ui.tick))
     } else {
-        ui.step.title().to_string()
+        title
     };
 
-    let header = Paragraph::new(header_text)
+    let header = Paragraph::new(ui.glyphs.truncate(&header_text, header_width).into_owned())
         .style(s_title(ui))
-        .block(Block::default().borders(Borders::ALL).title("AION Setup Wizard"))
+        .block(bordered(ui).title(ui.glyphs.truncate("AION Setup Wizard", header_width).into_owned()))
         .wrap(Wrap { trim: true });
     f.render_widget(header, outer[0]);
 
-    let mid = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(outer[1]);
+    // Content beside the help panel; narrow terminals give the content the full width.
+    let (content, help_area) = match outer[1].width {
+        w if w < NARROW_WIDTH => (outer[1], None),
+        w => {
+            let content_pct = if w >= WIDE_WIDTH { 40 } else { 50 };
+            let mid = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(content_pct), Constraint::Percentage(100 - content_pct)])
+                .split(outer[1]);
+            (mid[0], Some(mid[1]))
+        }
+    };
 
     // Help panel
-    let help = Paragraph::new(help_text(ui.step))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(Span::styled("Help", s_help_title(ui))),
-        )
-        .wrap(Wrap { trim: true });
-    f.render_widget(help, mid[1]);
+    if let Some(area) = help_area {
+        f.render_widget(help_panel(ui, wiz.step), area);
+    }
 
     // Footer
     let footer_text = if ui.use_animation {
@@ -598,34 +1135,103 @@ fn draw_ui(f: &mut Frame, ui: &UiState, draft: &AppConfig) {
     } else {
         ui.status.clone()
     };
-    let footer = Paragraph::new(footer_text)
+    let footer_width = usize::from(outer[2].width.saturating_sub(2));
+    let footer = Paragraph::new(ui.glyphs.truncate(&footer_text, footer_width).into_owned())
         .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(Span::styled("Status", s_help_title(ui))),
+            bordered(ui).title(Span::styled(
+                match help_area {
+                    Some(_) => "Status".to_string(),
+                    None => format!("Status{}F1 help", ui.glyphs.separator),
+                },
+                s_help_title(ui),
+            )),
         )
         .wrap(Wrap { trim: true });
     f.render_widget(footer, outer[2]);
 
     // Left content
-    match ui.step {
-        Step::Language => render_language(f, ui, draft, mid[0]),
-        Step::Provider => render_provider(f, ui, draft, mid[0]),
-        Step::Model => render_model(f, ui, draft, mid[0]),
-        Step::Summary => render_summary(f, ui, draft, mid[0]),
+    match wiz.step {
+        Step::Import => render_import(f, ui, wiz, content),
+        Step::Language => render_language(f, ui, wiz, content),
+        Step::Provider => render_provider(f, ui, wiz, content),
+        Step::Model => render_model(f, ui, wiz, content),
+        Step::Summary if ui.preview => render_preview(f, ui, wiz, content),
+        Step::Summary => render_summary(f, ui, wiz, content),
+    }
+
+    if ui.help_overlay {
+        f.render_widget(Clear, outer[1]);
+        f.render_widget(help_panel(ui, wiz.step), outer[1]);
     }
+    if ui.confirm_quit {
+        f.render_widget(Clear, outer[1]);
+        f.render_widget(discard_panel(ui, wiz), outer[1]);
+    }
+}
+
+/// The changes quitting would discard, in the summary's colors.
+fn discard_panel(ui: &UiState, wiz: &WizardModel) -> Paragraph<'static> {
+    let mut lines = vec![Line::from("Quitting now discards:")];
+    lines.extend(discarded(wiz).iter().map(|line| change_line(ui, line)));
+    lines.extend([
+        Line::from(""),
+        Line::from(format!("y/{} = Discard and quit", ui.keys.label(Action::Quit))),
+        Line::from("Any other key = Keep editing"),
+    ]);
+    Paragraph::new(Text::from(lines))
+        .block(bordered(ui).title(Span::styled("Discard changes?", s_warning(ui))))
+        .wrap(Wrap { trim: true })
+}
+
+fn help_panel(ui: &UiState, step: Step) -> Paragraph<'static> {
+    let title = if ui.help_overlay { "Help (Esc to close)" } else { "Help" };
+    Paragraph::new(help_text(step, &ui.keys, &ui.glyphs))
+        .block(bordered(ui).title(Span::styled(title, s_help_title(ui))))
+        .wrap(Wrap { trim: true })
 }
 
-fn render_language(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
+fn render_import(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
+    let cursor = ui.import_state.selected().unwrap_or(0);
+    let label_width = usize::from(area.width.saturating_sub(4));
+
+    let labels = wiz
+        .detected
+        .iter()
+        .map(|d| import_label(d, wiz.draft.language()))
+        .chain(std::iter::once("Set up manually".to_string()));
+    let items: Vec<ListItem> = labels
+        .enumerate()
+        .map(|(i, label)| {
+            let is_cursor = i == cursor;
+            let style = if is_cursor { s_cursor(ui) } else { Style::default() };
+            ListItem::new(Line::from(vec![
+                dot_span(ui, is_cursor, false, true),
+                Span::styled(ui.glyphs.truncate(&label, label_width).into_owned(), style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block_with_steps("Detected setup", ui, wiz, area.width))
+        .highlight_symbol("");
+
+    let mut state = ui.import_state.clone();
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_language(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
     let langs = language_options();
-    let cursor = ui.lang_state.selected().unwrap_or(0);
+    let mut state = ui.lang_state(&langs);
+    let cursor = state.selected().unwrap_or(0);
+    // Inside the borders, after the "● " marker.
+    let label_width = usize::from(area.width.saturating_sub(4));
 
     let items: Vec<ListItem> = langs
         .iter()
         .enumerate()
         .map(|(i, l)| {
             let is_cursor = i == cursor;
-            let is_active = l.code == draft.language.as_str();
+            let is_active = l.code == wiz.draft.language();
             let is_valid = l.supported;
             let dot = dot_span(ui, is_cursor, is_active, is_valid);
 
@@ -634,6 +1240,7 @@ fn render_language(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
             } else {
                 format!("{} ({}) - Not supported yet", l.name, l.code)
             };
+            let label = ui.glyphs.truncate(&label, label_width).into_owned();
 
             let label_style = if is_cursor {
                 s_cursor(ui)
@@ -650,23 +1257,24 @@ fn render_language(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
         .collect();
 
     let list = List::new(items)
-        .block(block_with_steps("Language", ui, draft))
+        .block(block_with_steps("Language", ui, wiz, area.width))
         .highlight_symbol("");
 
-    let mut state = ui.lang_state.clone();
     f.render_stateful_widget(list, area, &mut state);
 }
 
-fn render_provider(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
+fn render_provider(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
     let providers = provider_options();
-    let cursor = ui.provider_state.selected().unwrap_or(0);
+    let mut state = ui.provider_state(&providers);
+    let cursor = state.selected().unwrap_or(0);
+    let label_width = usize::from(area.width.saturating_sub(4));
 
     let items: Vec<ListItem> = providers
         .iter()
         .enumerate()
         .map(|(i, p)| {
             let is_cursor = i == cursor;
-            let is_active = *p == draft.provider.kind;
+            let is_active = p == wiz.draft.kind();
             let dot = dot_span(ui, is_cursor, is_active, true);
 
             let label_style = if is_cursor {
@@ -677,85 +1285,454 @@ fn render_provider(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
                 Style::default()
             };
 
+            let label = provider_label(p, wiz.draft.language());
+            let description = format!("{}{}", ui.glyphs.dash, provider_description(p, wiz.draft.language()));
+            let description_width = label_width.saturating_sub(width(&label));
             ListItem::new(Line::from(vec![
                 dot,
-                Span::styled(provider_name(p).to_string(), label_style),
+                Span::styled(ui.glyphs.truncate(&label, label_width).into_owned(), label_style),
+                Span::styled(ui.glyphs.truncate(&description, description_width).into_owned(), s_dim(ui)),
             ]))
         })
         .collect();
 
     let list = List::new(items)
-        .block(block_with_steps("Provider", ui, draft))
+        .block(block_with_steps("Provider", ui, wiz, area.width))
         .highlight_symbol("");
 
-    let mut state = ui.provider_state.clone();
     f.render_stateful_widget(list, area, &mut state);
 }
 
-fn render_model(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
+fn render_model(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
+    if ui.ollama == OllamaModels::NoneInstalled {
+        return render_ollama_suggestions(f, ui, wiz, area);
+    }
+
     let parts = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(7), Constraint::Length(3)])
         .split(area);
 
-    let is_valid = !ui.model_input.trim().is_empty();
-    let dot = dot_span(ui, false, is_valid, is_valid);
-
-    let title = format!("Model ({})", provider_name(&draft.provider.kind));
-    let content = Line::from(vec![
-        dot,
-        Span::styled(
-            ui.model_input.clone(),
-            if ui.use_colors {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().add_modifier(Modifier::BOLD)
-            },
-        ),
-    ]);
+    let title = format!("Model ({})", wiz.draft.kind().name());
+    let mut lines = vec![Line::from("Type the model name (Tab for the endpoint and key), then press Enter:")];
+    let mut cursor_line = 0;
+    for field in INPUTS {
+        let check = ui.check(&wiz.draft, field);
+        let focused = field == ui.field;
+        let dot = match ui.use_colors {
+            true => Span::styled(ui.glyphs.dot, s_check(ui, &check).add_modifier(Modifier::BOLD)),
+            false => Span::raw(ui.glyphs.dot),
+        };
+        let input = if focused { s_warning(ui).add_modifier(Modifier::BOLD) } else { Style::default() };
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(input_label(field), if focused { s_cursor(ui) } else { s_dim(ui) })));
+        if focused {
+            cursor_line = lines.len();
+        }
+        lines.push(Line::from(vec![dot, Span::styled(ui.input(field).as_str().to_string(), input)]));
+        lines.push(hint_line(ui, &check));
+    }
+    match &ui.ollama {
+        OllamaModels::Installed(models) => {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!("Installed: {}", models.join(", "))));
+        }
+        OllamaModels::Unreachable => {
+            lines.push(Line::from(""));
+            lines.push(Line::from("Ollama is not reachable; is `ollama serve` running?"));
+        }
+        OllamaModels::Unknown | OllamaModels::NoneInstalled => {}
+    }
+    let recent = ui.recent.models(wiz.draft.kind());
+    if !recent.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Recent (Alt+number, or {} on an empty input):", ui.glyphs.up)));
+        let width = usize::from(parts[0].width.saturating_sub(7));
+        for (i, model) in recent.iter().take(9).enumerate() {
+            let style = if model == ui.model_input.as_str() { s_cursor(ui) } else { Style::default() };
+            lines.push(Line::from(vec![
+                Span::raw(format!(" {}. ", i + 1)),
+                Span::styled(ui.glyphs.truncate(model, width).into_owned(), style),
+            ]));
+        }
+    }
 
-    let input = Paragraph::new(Text::from(vec![
-        Line::from("Type model name then press Enter:"),
-        Line::from(""),
-        content,
-    ]))
-    .block(block_with_steps(&title, ui, draft))
+    // The terminal cursor goes after the focused input's cursor, past the "● " and any
+    // wrapped lines above it.
+    let block = block_with_steps(&title, ui, wiz, parts[0].width);
+    let inner = block.inner(parts[0]);
+    let above = Paragraph::new(lines[..cursor_line].to_vec()).wrap(Wrap { trim: false }).line_count(inner.width);
+    let column = 2 + ui.input(ui.field).cursor_column();
+    let inner_width = usize::from(inner.width.max(1));
+    let (x, y) = (column % inner_width, above + column / inner_width);
+
+    let input = Paragraph::new(Text::from(lines))
+    .block(block)
     .wrap(Wrap { trim: false });
 
     f.render_widget(input, parts[0]);
+    if !ui.help_overlay && y < usize::from(inner.height) {
+        f.set_cursor(inner.x + x as u16, inner.y + y as u16);
+    }
 
-    let keys = Paragraph::new("Enter Next | Esc/Backspace/←/b Back | q Quit")
-        .block(Block::default().borders(Borders::ALL).title("Keys"))
+    let keys = Paragraph::new(format!("Enter Next | Tab Field | Esc/{} Back | Ctrl+C Quit", ui.glyphs.left))
+        .block(bordered(ui).title("Keys"))
         .wrap(Wrap { trim: true });
 
     f.render_widget(keys, parts[1]);
 }
 
-fn render_summary(f: &mut Frame, ui: &UiState, draft: &AppConfig, area: Rect) {
-    let lines = vec![
-        Line::from(vec![
-            Span::styled("● ", if ui.use_colors { Style::default().fg(Color::Green) } else { Style::default() }),
-            Span::raw(format!("Language: {}", draft.language)),
-        ]),
-        Line::from(vec![
-            Span::styled("● ", if ui.use_colors { Style::default().fg(Color::Green) } else { Style::default() }),
-            Span::raw(format!("Provider: {}", provider_name(&draft.provider.kind))),
-        ]),
-        Line::from(vec![
-            Span::styled("● ", if ui.use_colors { Style::default().fg(Color::Green) } else { Style::default() }),
-            Span::raw(format!("Model: {}", draft.provider.model)),
-        ]),
+fn render_ollama_suggestions(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(7), Constraint::Length(3)])
+        .split(area);
+    let title = "Model (Ollama: none installed)";
+
+    let (body, keys) = match &ui.pull {
+        PullState::Idle => {
+            let cursor = ui.suggest_state.selected().unwrap_or(0);
+            let items: Vec<ListItem> = SUGGESTED_OLLAMA_MODELS
+                .iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    let is_cursor = i == cursor;
+                    let style = if is_cursor { s_cursor(ui) } else { Style::default() };
+                    ListItem::new(Line::from(vec![
+                        dot_span(ui, is_cursor, false, true),
+                        Span::styled(m.to_string(), style),
+                    ]))
+                })
+                .collect();
+            let list = List::new(items).block(block_with_steps(title, ui, wiz, parts[0].width));
+            let mut state = ui.suggest_state.clone();
+            f.render_stateful_widget(list, parts[0], &mut state);
+            (None, format!("{} Choose | Enter Select | Esc Back | q Quit", ui.glyphs.up_down))
+        }
+        PullState::Offer { model, error } => {
+            let mut lines = vec![
+                Line::from("No models are installed. Download the selected one with:"),
+                Line::from(""),
+                Line::from(Span::styled(format!("  ollama pull {}", model), s_cursor(ui))),
+                Line::from(""),
+            ];
+            if !wiz.draft.caps.run_commands {
+                lines.push(Line::from("Running it here is disabled (caps.run_commands = false)."));
+            }
+            if let Some(e) = error {
+                lines.push(Line::from(Span::styled(format!("Pull failed: {}", e), s_inactive(ui))));
+            }
+            (Some(lines), format!("p/Enter Pull now | s Pull later | {} Other model | Esc Back", ui.glyphs.up_down))
+        }
+        PullState::Running(task) => {
+            let inner = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(4), Constraint::Length(3), Constraint::Min(0)])
+                .split(parts[0]);
+            let status = Paragraph::new(vec![
+                Line::from(format!("Pulling {}", task.model)),
+                Line::from(task.last.status.clone()),
+            ])
+            .block(block_with_steps(title, ui, wiz, inner[0].width));
+            f.render_widget(status, inner[0]);
+
+            let ratio = task.last.ratio().unwrap_or(0.0);
+            let gauge = Gauge::default()
+                .block(bordered(ui))
+                .gauge_style(s_active(ui))
+                .ratio(ratio);
+            f.render_widget(gauge, inner[1]);
+            (None, "Esc Cancel pull".to_string())
+        }
+    };
+
+    if let Some(lines) = body {
+        let p = Paragraph::new(Text::from(lines))
+            .block(block_with_steps(title, ui, wiz, parts[0].width))
+            .wrap(Wrap { trim: false });
+        f.render_widget(p, parts[0]);
+    }
+
+    let keys = Paragraph::new(keys)
+        .block(bordered(ui).title("Keys"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(keys, parts[1]);
+}
+
+fn render_summary(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
+    let (draft, original) = (&wiz.config(), &wiz.existing);
+    // Over a saved config each row says how it compares with it; a first setup is all new.
+    // Rows of the answers read the draft's fields, the rest the changes to the config.
+    let changes = match ui.saved_config.is_empty() {
+        true => None,
+        false => config_changes(original, draft).ok(),
+    };
+    let answered = wiz.draft.changed(original);
+    let features = |cfg: &AppConfig| match feature_labels(cfg) {
+        labels if labels.is_empty() => "none".to_string(),
+        labels => labels.join(", "),
+    };
+    let lang = &draft.language;
+    let mut rows = vec![
+        ("Language", "language", Some(original.language.clone()), draft.language.clone()),
+        (
+            "Provider",
+            "provider.kind",
+            Some(provider_label(&original.provider.kind, lang)),
+            provider_label(&draft.provider.kind, lang),
+        ),
+        ("Model", "provider.model", Some(original.provider.model.clone()), draft.provider.model.clone()),
+    ];
+    let saved_scope = scope_fields(original);
+    rows.extend(scope_fields(draft).into_iter().map(|(label, key, value)| {
+        let before = saved_scope.iter().find(|(_, k, _)| *k == key).map(|(_, _, v)| v.clone());
+        (label, key, before, value)
+    }));
+    rows.push(("Features", "features", Some(features(original)), features(draft)));
+
+    let mut lines: Vec<Line> = rows
+        .iter()
+        .map(|(label, key, before, after)| {
+            let changed = changes.as_ref().map(|c| match Field::ASKED.into_iter().find(|f| f.key() == *key) {
+                Some(field) => answered.contains(&field),
+                None => c.iter().any(|l| l.is_under(key)),
+            });
+            summary_row(ui, label, before.as_deref(), after, changed)
+        })
+        .collect();
+    // The rest of what differs: endpoint, key variable, settings taken from an import.
+    if let Some(changes) = &changes {
+        let rest = changes.iter().filter(|l| !rows.iter().any(|(_, key, _, _)| l.is_under(key)));
+        lines.extend(rest.map(|line| change_line(ui, line)));
+    }
+    let warn = if ui.use_colors { Style::default().fg(Color::Yellow) } else { Style::default() };
+    for warning in draft.consistency_warnings() {
+        lines.push(Line::from(Span::styled(format!("Warning: {}", warning), warn)));
+    }
+    lines.extend([
         Line::from(""),
         Line::from("Enter = Save & exit"),
-        Line::from("Esc/Backspace/←/b = Back"),
+        Line::from("p = Preview config.toml"),
+        Line::from(format!("Esc/Backspace/{}/b = Back", ui.glyphs.left)),
         Line::from("q = Quit without saving"),
-    ];
+    ]);
 
     let p = Paragraph::new(Text::from(lines))
-        .block(block_with_steps("Summary", ui, draft))
+        .block(block_with_steps("Summary", ui, wiz, area.width))
         .wrap(Wrap { trim: true });
 
     f.render_widget(p, area);
-}
\ No newline at end of file
+}
+
+/// A summary row. `changed` is `None` on a first setup (every row is new); over a saved
+/// config an unchanged row is dim, a changed one reads `old → new` in yellow, and a
+/// setting the saved config lacks is green.
+fn summary_row(ui: &UiState, label: &str, before: Option<&str>, after: &str, changed: Option<bool>) -> Line<'static> {
+    let (style, text) = match (changed, before) {
+        (None, _) => return Line::from(vec![Span::styled(ui.glyphs.dot, s_active(ui)), Span::raw(format!("{}: {}", label, after))]),
+        (Some(false), _) => (s_dim(ui), format!("{}: {}", label, after)),
+        (Some(true), Some(before)) => (s_warning(ui), format!("{}: {} {} {}", label, before, ui.glyphs.right, after)),
+        (Some(true), None) => (s_active(ui), format!("{}: {} (new)", label, after)),
+    };
+    Line::from(vec![Span::styled(ui.glyphs.dot, style), Span::styled(text, style)])
+}
+
+/// One line of `config_changes`, styled like `summary_row`; secrets are masked.
+fn change_line(ui: &UiState, line: &DiffLine) -> Line<'static> {
+    let (style, text) = match &line.change {
+        Change::Added(value) => (s_active(ui), format!("{} = {} (new)", line.key, value)),
+        Change::Removed(value) => (s_inactive(ui), format!("{} = {} (removed)", line.key, value)),
+        Change::Changed { before, after } => {
+            (s_warning(ui), format!("{}: {} {} {}", line.key, before, ui.glyphs.right, after))
+        }
+    };
+    Line::from(vec![Span::styled(ui.glyphs.dot, style), Span::styled(redact::redact(&text), style)])
+}
+
+/// The config.toml the wizard will write, with lines that differ from the saved file highlighted.
+fn render_preview(f: &mut Frame, ui: &UiState, wiz: &WizardModel, area: Rect) {
+    let text = match render_config(&wiz.config()) {
+        Ok(text) => text,
+        Err(e) => format!("# could not render the config: {:#}", e),
+    };
+    let changed = if ui.use_colors {
+        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().add_modifier(Modifier::BOLD)
+    };
+
+    let lines: Vec<Line> = text
+        .lines()
+        .zip(new_lines(&ui.saved_config, &text))
+        .map(|(line, is_new)| {
+            let marker = if is_new { "+ " } else { "  " };
+            let style = if is_new { changed } else { Style::default() };
+            Line::from(Span::styled(format!("{}{}", marker, line), style))
+        })
+        .collect();
+
+    let p = Paragraph::new(Text::from(lines))
+        .block(block_with_steps("config.toml (preview)", ui, wiz, area.width))
+        .scroll((ui.preview_scroll, 0));
+
+    f.render_widget(p, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::buffer_rows as rows;
+    use super::*;
+    use crate::i18n::LocaleManager;
+    use ratatui::backend::TestBackend;
+
+    fn render(width: u16, overlay: bool) -> Vec<String> {
+        let mut cfg = AppConfig::new_default();
+        cfg.ui.ascii_only = true;
+        let mut wiz = WizardModel::new(&cfg, None, Vec::new());
+        wiz.step = Step::Provider;
+        let mut ui = UiState::new(&cfg, None, &wiz);
+        ui.use_animation = false;
+        ui.help_overlay = overlay;
+        let mut terminal = Terminal::new(TestBackend::new(width, 16)).unwrap();
+        terminal.draw(|f| draw_ui(f, &ui, &wiz)).unwrap();
+        rows(terminal.backend().buffer())
+    }
+
+    /// The language list as it is with `extra` locale files installed over the bundled ones.
+    fn languages_with(extra: &[&str]) -> Vec<LangOption> {
+        let dir = tempfile::TempDir::new().unwrap();
+        for code in extra {
+            let text = format!("[meta]\ncode = \"{0}\"\nname = \"{0}\"\nnative = \"{0}\"\ndirection = \"ltr\"\nstatus = \"partial\"\n", code);
+            std::fs::write(dir.path().join(format!("{}.toml", code)), text).unwrap();
+        }
+        let locales = LocaleManager::load_from(&[dir.path().to_path_buf()], &Default::default()).unwrap();
+        setup::language_options_from(Some(&locales))
+    }
+
+    fn codes(langs: &[LangOption]) -> Vec<&str> {
+        langs.iter().map(|l| l.code.as_str()).collect()
+    }
+
+    /// Locales installed or removed while the wizard is open shift the list; the cursor
+    /// stays on the language it was on.
+    #[test]
+    fn the_language_cursor_survives_a_locale_reload() {
+        let mut cfg = AppConfig::new_default();
+        cfg.language = "no".to_string();
+        let wiz = WizardModel::new(&cfg, None, Vec::new());
+        let mut ui = UiState::new(&cfg, None, &wiz);
+
+        let before = languages_with(&[]);
+        assert_eq!(codes(&before), ["ar", "en", "no", "zh"]);
+        assert_eq!(ui.lang_state(&before).selected(), Some(2));
+
+        let after = languages_with(&["de", "fi"]);
+        assert_eq!(codes(&after), ["ar", "de", "en", "fi", "no", "zh"]);
+        assert_eq!(ui.lang_state(&after).selected(), Some(4));
+        // Moving goes from there, not from where the index was.
+        let mut state = ui.lang_state(&after);
+        step_selection(&mut state, after.len(), KeyCode::Up);
+        assert_eq!(after[state.selected().unwrap()].code, "fi");
+
+        // A cursor on a language whose file is then removed falls back to the first one.
+        ui.lang_cursor = "de".to_string();
+        assert_eq!(ui.lang_state(&after).selected(), Some(1));
+        assert_eq!(ui.lang_state(&before).selected(), Some(0));
+    }
+
+    /// Below `NARROW_WIDTH` the content takes the width and the status title offers F1.
+    #[test]
+    fn narrow_terminals_get_the_content_alone() {
+        let expected = [
+            "+AION Setup Wizard-----------------------------------------+",
+            "|Step 2/4: Provider (50%)                                  |",
+            "+----------------------------------------------------------+",
+            "+Provider  [1][2][3][4]------------------------------------+",
+            "|* Claude - Claude models via the Anthropic API            |",
+            "|* Ollama - Local models, no API key                       |",
+            "|* OpenAI - GPT models via the OpenAI API                  |",
+            "|* OpenRouter - Many hosted models behind one API key      |",
+            "|                                                          |",
+            "|                                                          |",
+            "|                                                          |",
+            "|                                                          |",
+            "+----------------------------------------------------------+",
+            "+Status - F1 help------------------------------------------+",
+            "|Up/Down Navigate | Enter Next | Esc / Backspace / Left... |",
+            "+----------------------------------------------------------+",
+        ];
+        assert_eq!(render(60, false), expected);
+    }
+
+    /// From `NARROW_WIDTH`, content and help side by side, half each.
+    #[test]
+    fn middle_widths_split_content_and_help_evenly() {
+        let expected = [
+            "+AION Setup Wizard-------------------------------------------------------------+",
+            "|Step 2/4: Provider (50%)                                                      |",
+            "+------------------------------------------------------------------------------+",
+            "+Provider  [1][2][3][4]----------------++Help----------------------------------+",
+            "|* Claude - Claude models via the An...||Choose your AI provider.              |",
+            "|* Ollama - Local models, no API key   ||                                      |",
+            "|* OpenAI - GPT models via the OpenA...||Keys: Up/Down move, Enter next        |",
+            "|* OpenRouter - Many hosted models b...||Back: Esc / Backspace / Left / b      |",
+            "|                                      ||Quit: q (without saving)              |",
+            "|                                      ||                                      |",
+            "|                                      ||                                      |",
+            "|                                      ||                                      |",
+            "+--------------------------------------++--------------------------------------+",
+            "+Status------------------------------------------------------------------------+",
+            "|Up/Down Navigate | Enter Next | Esc / Backspace / Left / b Back | q Quit |... |",
+            "+------------------------------------------------------------------------------+",
+        ];
+        assert_eq!(render(80, false), expected);
+    }
+
+    /// From `WIDE_WIDTH`, 40% for the content and 60% for the help.
+    #[test]
+    fn wide_terminals_give_help_the_larger_part() {
+        let expected = [
+            "+AION Setup Wizard-----------------------------------------------------------------------------------------------------+",
+            "|Step 2/4: Provider (50%)                                                                                              |",
+            "+----------------------------------------------------------------------------------------------------------------------+",
+            "+Provider  [1][2][3][4]------------------------++Help------------------------------------------------------------------+",
+            "|* Claude - Claude models via the Anthropic API||Choose your AI provider.                                              |",
+            "|* Ollama - Local models, no API key           ||                                                                      |",
+            "|* OpenAI - GPT models via the OpenAI API      ||Keys: Up/Down move, Enter next                                        |",
+            "|* OpenRouter - Many hosted models behind on...||Back: Esc / Backspace / Left / b                                      |",
+            "|                                              ||Quit: q (without saving)                                              |",
+            "|                                              ||                                                                      |",
+            "|                                              ||                                                                      |",
+            "|                                              ||                                                                      |",
+            "+----------------------------------------------++----------------------------------------------------------------------+",
+            "+Status----------------------------------------------------------------------------------------------------------------+",
+            "|Up/Down Navigate | Enter Next | Esc / Backspace / Left / b Back | q Quit | c / C Colors | a / A Animation             |",
+            "+----------------------------------------------------------------------------------------------------------------------+",
+        ];
+        assert_eq!(render(120, false), expected);
+    }
+
+    /// F1 on a narrow terminal: the help over the content, saying how to close it.
+    #[test]
+    fn the_help_overlay_covers_the_content() {
+        let expected = [
+            "+AION Setup Wizard-----------------------------------------+",
+            "|Step 2/4: Provider (50%)                                  |",
+            "+----------------------------------------------------------+",
+            "+Help (Esc to close)---------------------------------------+",
+            "|Choose your AI provider.                                  |",
+            "|                                                          |",
+            "|Keys: Up/Down move, Enter next                            |",
+            "|Back: Esc / Backspace / Left / b                          |",
+            "|Quit: q (without saving)                                  |",
+            "|                                                          |",
+            "|                                                          |",
+            "|                                                          |",
+            "+----------------------------------------------------------+",
+            "+Status - F1 help------------------------------------------+",
+            "|Up/Down Navigate | Enter Next | Esc / Backspace / Left... |",
+            "+----------------------------------------------------------+",
+        ];
+        assert_eq!(render(60, true), expected);
+    }
+}
@@ -0,0 +1,224 @@
+//! Console output behind an output-mode abstraction.
+//!
+//! Notes:
+//! - Decorative output (banner, environment info, timestamp) is suppressed by `--quiet`,
+//!   by `--output json`, and automatically when stdout is not a TTY. Otherwise
+//!   `ui.show_banner` / `ui.show_env_info` and `--no-banner` choose what is shown, except
+//!   on the first run, which shows everything.
+//! - Command output (summaries, reports) is always printed, as text or as JSON.
+//! - When the full-screen chat view follows, the startup summary is shown as its splash
+//!   instead of being printed (main.rs).
+//! - Errors go to stderr through anyhow in main.rs and are never suppressed.
+
+use crate::config::AppConfig;
+use crate::redact;
+use crate::summary::{self, Summary};
+use clap::ValueEnum;
+use crossterm::tty::IsTty;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Console {
+    format: OutputFormat,
+    quiet: bool,
+    decorations: bool,
+}
+
+/// Per-run settings for `Console::print_boot`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BootOptions {
+    /// No config existed before this run: everything is shown, whatever the settings.
+    pub first_run: bool,
+    /// `--no-banner`: nothing is shown (except on the first run).
+    pub no_banner: bool,
+}
+
+impl Console {
+    pub fn new(format: OutputFormat, quiet: bool) -> Self {
+        Self::for_terminal(format, quiet, io::stdout().is_tty())
+    }
+
+    /// `new`, told whether stdout is a terminal rather than checking.
+    fn for_terminal(format: OutputFormat, quiet: bool, tty: bool) -> Self {
+        let decorations = !quiet && format == OutputFormat::Text && tty;
+        Self {
+            format,
+            quiet,
+            decorations,
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Whether decorative output (banner, boot lines, ready prompt) should be printed.
+    pub fn decorations(&self) -> bool {
+        self.decorations
+    }
+
+    /// Startup banner, environment info, and boot lines, as `cfg.ui` and `opts` allow.
+    /// Nothing is printed when decorations are off.
+    pub fn print_boot(&self, cfg: &AppConfig, opts: BootOptions) {
+        let _ = self.boot_to(&mut io::stdout().lock(), cfg, opts);
+    }
+
+    /// `print_boot`, to `out`.
+    fn boot_to(&self, out: &mut impl Write, cfg: &AppConfig, opts: BootOptions) -> io::Result<()> {
+        match self.decorations {
+            true => write_boot(out, cfg, opts),
+            false => Ok(()),
+        }
+    }
+
+    /// Config summary (`crate::summary`). This is command output, so it is printed even
+    /// when not on a TTY, as a table on one; `--quiet` suppresses it only on the startup
+    /// path (see `print_startup_summary`).
+    pub fn print_config_summary(&self, summary: &Summary, lang: &str) -> io::Result<()> {
+        if self.is_json() {
+            return self.print_json(summary);
+        }
+        summary::write_text(&mut io::stdout().lock(), summary, lang, self.decorations)
+    }
+
+    pub fn print_startup_summary(&self, summary: &Summary, lang: &str) -> io::Result<()> {
+        if self.quiet && !self.is_json() {
+            return Ok(());
+        }
+        self.print_config_summary(summary, lang)
+    }
+
+    pub fn prompt_ready(&self) {
+        if !self.decorations {
+            return;
+        }
+        print!("AION is ready > ");
+        let _ = io::stdout().flush();
+    }
+
+    pub fn print_json<T: Serialize>(&self, value: &T) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+        println!("{}", redact::redact(&text));
+        Ok(())
+    }
+}
+
+/// The boot output `print_boot` shows, written to `out`.
+fn write_boot(out: &mut impl Write, cfg: &AppConfig, opts: BootOptions) -> io::Result<()> {
+    let (banner, env_info) = if opts.first_run {
+        (true, true)
+    } else if opts.no_banner {
+        (false, false)
+    } else {
+        (cfg.ui.show_banner, cfg.ui.show_env_info)
+    };
+
+    if banner {
+        writeln!(out)?;
+        writeln!(out, "==============================================================")?;
+        writeln!(out, "                      AION CORE INITIALIZED                    ")?;
+        writeln!(out, "==============================================================")?;
+        writeln!(out)?;
+    }
+    if env_info {
+        writeln!(out, "System Information:")?;
+        writeln!(out, "  OS Architecture : {}", std::env::consts::ARCH)?;
+        writeln!(out, "  Operating System: {}", std::env::consts::OS)?;
+        writeln!(out)?;
+    }
+    if banner {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => writeln!(out, "Startup Timestamp: {}", d.as_secs())?,
+            Err(_) => writeln!(out, "Startup Timestamp: unavailable")?,
+        }
+        writeln!(out)?;
+        writeln!(out, "Core Status: OK")?;
+        writeln!(out, "Runtime Status: OK")?;
+        writeln!(out, "Initialization Complete")?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BANNER: &str = "                      AION CORE INITIALIZED                    ";
+
+    fn boot(console: Console, cfg: &AppConfig, opts: BootOptions) -> String {
+        let mut out = Vec::new();
+        console.boot_to(&mut out, cfg, opts).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// The boot lines with the timestamp's value left out.
+    fn lines(text: &str) -> Vec<&str> {
+        text.lines().map(|l| if l.starts_with("Startup Timestamp: ") { "Startup Timestamp: …" } else { l }).collect()
+    }
+
+    #[test]
+    fn quiet_json_and_piped_output_have_no_boot_lines() {
+        let cfg = AppConfig::new_default();
+        let first = BootOptions { first_run: true, no_banner: false };
+        for console in [
+            Console::for_terminal(OutputFormat::Text, true, true),
+            Console::for_terminal(OutputFormat::Json, false, true),
+            Console::for_terminal(OutputFormat::Text, false, false),
+        ] {
+            assert!(!console.decorations());
+            assert_eq!(boot(console, &cfg, first), "", "{:?}", console);
+        }
+    }
+
+    #[test]
+    fn a_terminal_gets_the_banner_and_environment() {
+        let console = Console::for_terminal(OutputFormat::Text, false, true);
+        let cfg = AppConfig::new_default();
+        let text = boot(console, &cfg, BootOptions::default());
+        let os = format!("  Operating System: {}", std::env::consts::OS);
+        assert_eq!(
+            lines(&text),
+            [
+                "",
+                "==============================================================",
+                BANNER,
+                "==============================================================",
+                "",
+                "System Information:",
+                &format!("  OS Architecture : {}", std::env::consts::ARCH),
+                &os,
+                "",
+                "Startup Timestamp: …",
+                "",
+                "Core Status: OK",
+                "Runtime Status: OK",
+                "Initialization Complete",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_and_no_banner_choose_what_shows_except_on_the_first_run() {
+        let console = Console::for_terminal(OutputFormat::Text, false, true);
+        let mut cfg = AppConfig::new_default();
+        cfg.ui.show_banner = false;
+        assert_eq!(lines(&boot(console, &cfg, BootOptions::default()))[0], "System Information:");
+        cfg.ui.show_env_info = false;
+        assert_eq!(boot(console, &cfg, BootOptions::default()), "");
+
+        let everything = boot(console, &AppConfig::new_default(), BootOptions::default());
+        assert_eq!(lines(&boot(console, &cfg, BootOptions { first_run: true, no_banner: true })), lines(&everything));
+        let cfg = AppConfig::new_default();
+        assert_eq!(boot(console, &cfg, BootOptions { first_run: false, no_banner: true }), "");
+    }
+}
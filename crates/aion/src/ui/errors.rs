@@ -0,0 +1,167 @@
+//! User-facing error messages.
+//!
+//! Notes:
+//! - `render` walks the error chain. Context added above a typed error is kept as a
+//!   prefix; the typed error itself (`ProviderError`, `ConfigError`, `CapabilityDenied`,
+//!   `PathDenied`, `HostDenied`, `ReadOnlyMode`, `BudgetExceeded`) is replaced by its localized message and ends the chain.
+//! - A cap read-only mode turned off reads as read-only mode, like `ReadOnlyMode`.
+//! - Errors without a typed cause render as the plain context chain.
+//! - A `ConfigError` found in the config file also gets `config_file_details`: the line,
+//!   the value, and a fix (see `config::locate`), printed under the message.
+//! - The result is always redacted.
+
+use crate::config::locate::{Diagnostic, InvalidConfigFile};
+use crate::config::{AppConfig, CapabilityDenied, ConfigError};
+use crate::costs::{BudgetExceeded, Limit};
+use crate::guard::{HostDenied, PathDenied};
+use crate::i18n::text;
+use crate::providers::{error as provider_error, ProviderError};
+use crate::read_only::{self, ReadOnlyMode};
+use crate::redact;
+use std::error::Error;
+
+/// The message to show for `e`, in `cfg.language`.
+pub fn render(e: &anyhow::Error, cfg: &AppConfig) -> String {
+    let mut parts = Vec::new();
+    for cause in e.chain() {
+        if let Some(message) = localized(cause, cfg) {
+            parts.push(message);
+            return redact::redact(&parts.join(": "));
+        }
+        parts.push(cause.to_string());
+    }
+    redact::error_chain(e)
+}
+
+/// Where in the config file the `ConfigError` in `e` is, when `load_config` found it
+/// there and the file can still be read.
+pub fn config_diagnostic(e: &anyhow::Error) -> Option<Diagnostic> {
+    let file = e.downcast_ref::<InvalidConfigFile>()?;
+    let err = e.chain().find_map(|c| c.downcast_ref::<ConfigError>())?;
+    let text = std::fs::read_to_string(&file.path).ok()?;
+    Some(Diagnostic::new(err, &file.path, &text))
+}
+
+/// `config_diagnostic` as lines to print under the error, in `lang`.
+pub fn config_file_details(e: &anyhow::Error, lang: &str) -> Option<String> {
+    config_diagnostic(e).map(|d| redact::redact(&d.render(lang)))
+}
+
+fn localized(cause: &(dyn Error + 'static), cfg: &AppConfig) -> Option<String> {
+    let lang = cfg.language.as_str();
+    if let Some(err) = cause.downcast_ref::<ProviderError>() {
+        return Some(provider_error::describe(err, cfg));
+    }
+    if let Some(err) = cause.downcast_ref::<ReadOnlyMode>() {
+        return Some(read_only_mode(err, lang));
+    }
+    if let Some(err) = cause.downcast_ref::<CapabilityDenied>() {
+        if let Some(denied) = read_only::denied_cap(err.cap) {
+            return Some(read_only_mode(&denied, lang));
+        }
+        return Some(text(
+            lang,
+            &format!("capability_error.{}", err.cap),
+            &err.to_string(),
+            &[],
+        ));
+    }
+    if let Some(err) = cause.downcast_ref::<PathDenied>() {
+        let path = ("path", err.path.display().to_string());
+        let list = ("list", err.list.to_string());
+        let (name, vars) = match &err.rule {
+            Some(rule) => ("path_denied", vec![path, list, ("rule", rule.clone())]),
+            None => ("path_not_allowed", vec![path, list]),
+        };
+        return Some(text(lang, &format!("capability_error.{}", name), &err.to_string(), &vars));
+    }
+    if let Some(err) = cause.downcast_ref::<HostDenied>() {
+        let host = ("host", err.host.clone());
+        let list = ("list", err.list.to_string());
+        let (name, vars) = match &err.rule {
+            Some(rule) => ("host_denied", vec![host, list, ("rule", rule.clone())]),
+            None => ("host_not_allowed", vec![host, list]),
+        };
+        return Some(text(lang, &format!("capability_error.{}", name), &err.to_string(), &vars));
+    }
+    if let Some(err) = cause.downcast_ref::<BudgetExceeded>() {
+        let name = match err.limit {
+            Limit::Session => "budget.exceeded_session",
+            Limit::Month => "budget.exceeded_month",
+        };
+        let vars = [
+            ("budget", format!("{:.2}", err.budget)),
+            ("spent", format!("{:.2}", err.spent)),
+            ("key", err.limit.key().to_string()),
+        ];
+        return Some(text(lang, name, &err.to_string(), &vars));
+    }
+    cause.downcast_ref::<ConfigError>().map(|err| config_error(err, lang))
+}
+
+fn read_only_mode(err: &ReadOnlyMode, lang: &str) -> String {
+    text(lang, &format!("read_only.{}", err.action), &err.to_string(), &[("flag", err.trigger.name().to_string())])
+}
+
+fn config_error(err: &ConfigError, lang: &str) -> String {
+    let (name, vars) = match err {
+        ConfigError::UnsupportedVersion(v) => ("unsupported_version", vec![("version", v.to_string())]),
+        ConfigError::InvalidLanguage(l) => ("invalid_language", vec![("language", l.clone())]),
+        ConfigError::EmptyModel => ("empty_model", vec![]),
+        ConfigError::MissingBaseUrl => ("missing_base_url", vec![]),
+        ConfigError::MissingApiKeyEnv => ("missing_api_key_env", vec![]),
+        ConfigError::InvalidBaseUrl { value, reason } => {
+            ("invalid_base_url", vec![("value", value.clone()), ("reason", reason.clone())])
+        }
+        ConfigError::InvalidApiKeyEnv { value, reason } => {
+            ("invalid_api_key_env", vec![("value", format!("{:?}", value)), ("reason", reason.clone())])
+        }
+        ConfigError::ParamOutOfRange { name, value, range } => (
+            "param_out_of_range",
+            vec![("name", name.to_string()), ("value", value.clone()), ("range", range.to_string())],
+        ),
+        ConfigError::InvalidScopeId { field, value } => {
+            ("invalid_scope_id", vec![("field", field.to_string()), ("value", format!("{:?}", value))])
+        }
+        ConfigError::Inconsistent(w) => ("inconsistent", vec![("warning", w.to_string())]),
+        ConfigError::ReadOnly { path, reason } => {
+            ("read_only", vec![("path", path.clone()), ("reason", reason.clone())])
+        }
+        ConfigError::InvalidKey { action, spec } => {
+            ("invalid_key", vec![("action", action.to_string()), ("spec", spec.clone())])
+        }
+        ConfigError::DuplicateKey { spec, first, second } => (
+            "duplicate_key",
+            vec![("spec", spec.clone()), ("first", first.to_string()), ("second", second.to_string())],
+        ),
+        ConfigError::InvalidFallback { index, error } => {
+            ("invalid_fallback", vec![("index", index.to_string()), ("error", config_error(error, lang))])
+        }
+        ConfigError::NestedFallbacks { index } => ("nested_fallbacks", vec![("index", index.to_string())]),
+        ConfigError::InvalidStyle { field, value, expected } => (
+            "invalid_style",
+            vec![("field", field.to_string()), ("value", value.clone()), ("expected", expected.clone())],
+        ),
+        ConfigError::UnknownVariable { field, name } => {
+            ("unknown_variable", vec![("field", field.clone()), ("name", name.clone())])
+        }
+        ConfigError::MalformedVariable { field, value } => {
+            ("malformed_variable", vec![("field", field.clone()), ("value", value.clone())])
+        }
+        ConfigError::ExtraOptionConflict { name, setting } => {
+            ("extra_option_conflict", vec![("name", name.clone()), ("setting", setting.to_string())])
+        }
+        ConfigError::InvalidExtraOption { name, reason } => {
+            ("invalid_extra_option", vec![("name", name.clone()), ("reason", reason.clone())])
+        }
+        ConfigError::InvalidHostPattern { list, pattern, reason } => (
+            "invalid_host_pattern",
+            vec![("list", list.to_string()), ("pattern", pattern.clone()), ("reason", reason.clone())],
+        ),
+        ConfigError::InvalidSecretPattern { pattern, reason } => {
+            ("invalid_secret_pattern", vec![("pattern", pattern.clone()), ("reason", reason.clone())])
+        }
+        ConfigError::InvalidBudget { key, reason } => ("invalid_budget", vec![("key", key.clone()), ("reason", reason.clone())]),
+    };
+    text(lang, &format!("config_error.{}", name), &err.to_string(), &vars)
+}
@@ -0,0 +1,226 @@
+//! An editable line of text with a cursor, shared by the text inputs.
+//!
+//! Notes:
+//! - The cursor is a byte offset that always sits between grapheme clusters, so
+//!   Backspace, Delete, and ←/→ treat an emoji ZWJ sequence, a flag, or an Arabic letter
+//!   with its harakat as one character.
+//! - Typing a combining mark or a ZWJ right after a letter joins it to that letter; the
+//!   cursor stays after the joined cluster.
+//! - Columns are terminal columns (`super::width`), so a CJK character moves the cursor
+//!   by two. Newlines (the chat input) start a new row; wrapping is left to the caller.
+//! - Used by the wizard's model step, the REPL line reader, and the chat view's input.
+
+use super::width::width;
+use unicode_segmentation::GraphemeCursor;
+
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset into `text`, on a grapheme boundary.
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `text` with the cursor at its end.
+    pub fn with_text(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = text.len();
+        Self { text, cursor }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the text and put the cursor at its end.
+    pub fn set(&mut self, text: impl Into<String>) {
+        *self = Self::with_text(text);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The text, leaving the input empty.
+    pub fn take(&mut self) -> String {
+        std::mem::take(self).text
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        self.snap();
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+        self.snap();
+    }
+
+    /// Remove the cluster before the cursor; `false` at the start.
+    pub fn backspace(&mut self) -> bool {
+        let Some(start) = self.prev_boundary() else {
+            return false;
+        };
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+        true
+    }
+
+    /// Remove the cluster after the cursor; `false` at the end.
+    pub fn delete(&mut self) -> bool {
+        let Some(end) = self.next_boundary() else {
+            return false;
+        };
+        self.text.replace_range(self.cursor..end, "");
+        true
+    }
+
+    pub fn left(&mut self) -> bool {
+        self.prev_boundary().map(|at| self.cursor = at).is_some()
+    }
+
+    pub fn right(&mut self) -> bool {
+        self.next_boundary().map(|at| self.cursor = at).is_some()
+    }
+
+    /// To the start of the cursor's row.
+    pub fn home(&mut self) {
+        self.cursor = self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1);
+    }
+
+    /// To the end of the cursor's row.
+    pub fn end(&mut self) {
+        self.cursor += self.text[self.cursor..].find('\n').unwrap_or(self.text.len() - self.cursor);
+    }
+
+    pub fn end_of_text(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    pub fn at_start(&self) -> bool {
+        self.cursor == 0
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.cursor == self.text.len()
+    }
+
+    /// Row of the cursor: the newlines before it.
+    pub fn cursor_row(&self) -> usize {
+        self.text[..self.cursor].matches('\n').count()
+    }
+
+    /// No newline after the cursor.
+    pub fn on_last_row(&self) -> bool {
+        !self.text[self.cursor..].contains('\n')
+    }
+
+    /// Column of the cursor in its row, in terminal columns.
+    pub fn cursor_column(&self) -> usize {
+        let before = &self.text[..self.cursor];
+        width(before.rsplit('\n').next().unwrap_or(before))
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        GraphemeCursor::new(self.cursor, self.text.len(), true).prev_boundary(&self.text, 0).ok().flatten()
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        GraphemeCursor::new(self.cursor, self.text.len(), true).next_boundary(&self.text, 0).ok().flatten()
+    }
+
+    /// Move the cursor forward to a boundary if an insert joined clusters across it.
+    fn snap(&mut self) {
+        let mut at = GraphemeCursor::new(self.cursor, self.text.len(), true);
+        if !at.is_boundary(&self.text, 0).unwrap_or(true) {
+            self.cursor = self.next_boundary().unwrap_or(self.text.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        text.chars().for_each(|c| input.insert(c));
+        input
+    }
+
+    #[test]
+    fn combining_marks_join_the_letter_before_them() {
+        // e + combining acute, and ب with a kasra and a shadda.
+        for text in ["e\u{301}", "\u{628}\u{650}\u{651}"] {
+            let mut input = typed(text);
+            assert!(input.at_end(), "{:?}", text);
+            assert_eq!(input.cursor_column(), 1, "{:?}", text);
+            assert!(input.left() && input.at_start(), "{:?}", text);
+            assert!(input.delete() && input.is_empty(), "{:?}", text);
+        }
+
+        // A mark typed after a letter mid-line joins it; the cursor stays after the cluster.
+        let mut input = TextInput::with_text("ab");
+        input.left();
+        input.insert('\u{301}');
+        assert_eq!(input.as_str(), "a\u{301}b");
+        assert_eq!(input.cursor_column(), 1);
+        assert!(input.right() && input.at_end());
+    }
+
+    #[test]
+    fn an_emoji_zwj_sequence_is_one_character() {
+        // Family: man, ZWJ, woman, ZWJ, girl; typed a code point at a time.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let mut input = typed(&format!("a{}b", family));
+        input.left();
+        assert_eq!(input.cursor_column(), 3);
+        assert!(input.left());
+        assert_eq!(input.cursor_column(), 1);
+        assert!(input.delete());
+        assert_eq!(input.as_str(), "ab");
+
+        // A flag is two regional indicators.
+        let mut input = typed("\u{1F1F3}\u{1F1F4}");
+        assert!(input.left() && input.at_start());
+    }
+
+    #[test]
+    fn cjk_characters_are_two_columns_wide() {
+        let mut input = typed("你好");
+        assert_eq!(input.cursor_column(), 4);
+        input.left();
+        assert_eq!(input.cursor_column(), 2);
+        input.home();
+        assert_eq!(input.cursor_column(), 0);
+
+        let mut input = typed("ab\n你好x");
+        assert_eq!((input.cursor_row(), input.cursor_column()), (1, 5));
+        input.home();
+        assert_eq!((input.cursor_row(), input.cursor_column()), (1, 0));
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_cluster() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let mut input = typed(&format!("x你e\u{301}{}", family));
+        assert!(input.backspace());
+        assert_eq!(input.as_str(), "x你e\u{301}");
+        assert!(input.backspace());
+        assert_eq!(input.as_str(), "x你");
+        assert!(input.backspace());
+        assert_eq!(input.as_str(), "x");
+        assert!(input.backspace() && input.is_empty());
+        assert!(!input.backspace());
+    }
+}
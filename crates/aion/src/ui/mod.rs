@@ -0,0 +1,6 @@
+pub mod console;
+pub mod errors;
+pub mod input;
+pub mod notify;
+pub mod reply;
+pub mod width;
@@ -0,0 +1,23 @@
+//! Desktop notifications for `aion_core::notify`, with the `desktop-notify` feature.
+//!
+//! Notes:
+//! - Not over SSH (`SSH_TTY` is set): the desktop there is someone else's, or none.
+//! - A platform or session without a notification service is skipped silently; the bell
+//!   still rings.
+
+use crate::notify::{self, Notice};
+
+/// Send notices to the desktop as well, where there is one. Before `notify::init`.
+pub fn install() {
+    if cfg!(feature = "desktop-notify") && std::env::var_os("SSH_TTY").is_none() {
+        notify::set_desktop(Some(show));
+    }
+}
+
+#[cfg(feature = "desktop-notify")]
+fn show(notice: &Notice) {
+    let _ = notify_rust::Notification::new().appname("aion").summary(&notice.title).body(&notice.body).show();
+}
+
+#[cfg(not(feature = "desktop-notify"))]
+fn show(_: &Notice) {}
@@ -0,0 +1,114 @@
+//! How a reply is printed in CLI mode (the REPL, `aion "prompt"`).
+//!
+//! Notes:
+//! - On a terminal the reply is word-wrapped to its width as it streams in
+//!   (`aion_core::wrap`); redirected output is written exactly as received.
+//! - Once complete, a reply taller than the terminal opens in `$PAGER` (`less -R`, or
+//!   `more` on Windows), so it can be read from the top: `ui.pager = "auto"`. `always`
+//!   (or `--pager`) pages every reply, `never` none. Never when stdout is redirected.
+//! - A pager that cannot be started is reported as a warning; the reply was already
+//!   printed in full.
+
+use crate::cancel;
+use crate::config::{Pager, UiConfig};
+use crate::wrap::{wrap, Wrapper};
+use anyhow::{Context, Result};
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FORCE_PAGER: AtomicBool = AtomicBool::new(false);
+
+/// `--pager`: page every reply for the rest of the process, whatever `ui.pager` says.
+pub fn force_pager() {
+    FORCE_PAGER.store(true, Ordering::Relaxed);
+}
+
+/// Prints one reply; see the module notes.
+pub struct ReplyPrinter {
+    pager: Pager,
+    /// Columns and rows, when stdout is a terminal.
+    size: Option<(usize, usize)>,
+    wrapper: Option<Wrapper>,
+    /// The reply as received, for the pager.
+    text: String,
+}
+
+impl ReplyPrinter {
+    pub fn new(ui: &UiConfig) -> Self {
+        let size = io::stdout()
+            .is_tty()
+            .then(terminal::size)
+            .and_then(Result::ok)
+            .map(|(cols, rows)| (usize::from(cols), usize::from(rows)));
+        let pager = if FORCE_PAGER.load(Ordering::Relaxed) { Pager::Always } else { ui.pager };
+        Self { pager, size, wrapper: size.map(|(cols, _)| Wrapper::new(cols)), text: String::new() }
+    }
+
+    /// Print a piece of the reply as it arrives.
+    pub fn chunk(&mut self, chunk: &str) {
+        self.text.push_str(chunk);
+        let out = match &mut self.wrapper {
+            Some(wrapper) => wrapper.push(chunk),
+            None => chunk.to_string(),
+        };
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(out.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    /// Print what is held back and end the line.
+    pub fn finish(&mut self) {
+        if let Some(wrapper) = &mut self.wrapper {
+            print!("{}", wrapper.finish());
+        }
+        println!();
+    }
+
+    /// Show the complete reply in the pager, when `ui.pager` calls for it.
+    pub fn page(&self) {
+        let Some((cols, rows)) = self.size else { return };
+        let text = wrap(&self.text, cols);
+        let page = match self.pager {
+            Pager::Always => !self.text.trim().is_empty(),
+            Pager::Auto => text.lines().count() >= rows,
+            Pager::Never => false,
+        };
+        if page {
+            if let Err(e) = show(&text) {
+                eprintln!("Warning: {:#}", e);
+            }
+        }
+    }
+}
+
+/// `$PAGER`, split on spaces, or the platform's default.
+fn pager_command() -> Vec<String> {
+    match std::env::var("PAGER") {
+        Ok(pager) if !pager.trim().is_empty() => pager.split_whitespace().map(str::to_string).collect(),
+        _ if cfg!(windows) => vec!["more".to_string()],
+        _ => vec!["less".to_string(), "-R".to_string()],
+    }
+}
+
+/// `text` in the pager, until it is closed.
+fn show(text: &str) -> Result<()> {
+    let command = pager_command();
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("could not start the pager ({}); set $PAGER or ui.pager = \"never\"", command[0]))?;
+    // Ctrl+C is the pager's while it is open; one press is not the REPL's double press.
+    let _token = cancel::begin();
+    if let Some(mut stdin) = child.stdin.take() {
+        // A pager closed before reading everything is not an error.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let status = child.wait();
+    cancel::end();
+    status.with_context(|| format!("the pager ({}) failed", command[0]))?;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Fitting text into fixed-width terminal cells.
+//!
+//! Notes:
+//! - Widths are terminal columns as measured by `unicode-width` (CJK counts double).
+//! - `truncate` cuts between grapheme clusters, so a base letter keeps its combining
+//!   marks (Arabic harakat), and emoji sequences stay whole.
+//! - Used by the wizard, which draws translated strings in fixed-size blocks, and by
+//!   `aion locales check --widths`.
+
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Display width of `text` in terminal columns.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// `text` cut to at most `max` columns, ending in `ellipsis` (`…`, or `...` where that
+/// cannot be shown) when anything was dropped.
+pub fn truncate<'a>(text: &'a str, max: usize, ellipsis: &'static str) -> Cow<'a, str> {
+    if width(text) <= max {
+        return Cow::Borrowed(text);
+    }
+    let budget = max.saturating_sub(width(ellipsis));
+    if budget == 0 {
+        return Cow::Borrowed(if max < width(ellipsis) { "" } else { ellipsis });
+    }
+
+    let mut out = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let w = width(grapheme);
+        if used + w > budget {
+            break;
+        }
+        out.push_str(grapheme);
+        used += w;
+    }
+    let keep = out.trim_end().len();
+    out.truncate(keep);
+    out.push_str(ellipsis);
+    Cow::Owned(out)
+}
@@ -0,0 +1,434 @@
+//! `aion self-update`, and the check at start (`updates.check_on_start`).
+//!
+//! Notes:
+//! - The latest release of the repository named in Cargo.toml is read from the GitHub
+//!   API; drafts and pre-releases are never offered. Needs `caps.network`, and its host
+//!   lists to allow the API and the download hosts (`crate::guard`). Proxies come
+//!   from `HTTPS_PROXY` / `HTTP_PROXY` / `NO_PROXY`, as for every request, and
+//!   `network.timeout_secs` bounds the API call.
+//! - Versions compare numerically (`major.minor.patch`, a leading `v` ignored); a
+//!   pre-release sorts before its release.
+//! - A release carries one executable per platform, `aion-<os>-<arch>` (`.exe` on
+//!   Windows, e.g. `aion-linux-x86_64`), and `SHA256SUMS` in `sha256sum` format. A build
+//!   without a checksum is not installed.
+//! - The download is written next to the executable, verified, and renamed over it, so
+//!   the swap is atomic. A running `.exe` cannot be replaced on Windows: it is renamed to
+//!   `aion.old.exe` first, and the next update removes that file.
+//! - Replacing the executable needs `caps.write_files` and passes the path lists
+//!   (`crate::guard`); it is audited. With `--dry-run` the build is downloaded and
+//!   verified, then nothing is written.
+//! - The check at start runs on its own thread, at most once a day (the result is kept
+//!   in `update_check.json` in the state dir), and fails silently. A newer version is
+//!   announced once, at the ready prompt (`take_notice`, `notice`).
+//! - Debug builds read the release from `AION_RELEASES_URL` when it is set, for testing
+//!   against a local server.
+
+use crate::audit;
+use crate::cli::SelfUpdateArgs;
+use crate::config::io::load_config;
+use crate::paths;
+use crate::config::AppConfig;
+use crate::dry_run;
+use crate::guard::Guard;
+use crate::i18n;
+use crate::persistence;
+use crate::i18n::format::{format_bytes, tilde};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot::{self, error::TryRecvError, Receiver};
+
+/// The running version.
+pub const CURRENT: &str = env!("CARGO_PKG_VERSION");
+
+const STATE_FILE_NAME: &str = "update_check.json";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Seconds between checks at start.
+const CHECK_INTERVAL: u64 = 24 * 60 * 60;
+/// Lines of release notes shown by `--check`.
+const CHANGELOG_LINES: usize = 20;
+#[cfg(debug_assertions)]
+const RELEASES_ENV: &str = "AION_RELEASES_URL";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub html_url: String,
+    /// `YYYY-MM-DDTHH:MM:SSZ`.
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+impl Release {
+    /// The tag without a leading `v`.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn is_newer(&self) -> bool {
+        compare(self.version(), CURRENT) == Ordering::Greater
+    }
+
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|a| a.name == name)
+    }
+}
+
+/* ---------------------------
+   aion self-update
+---------------------------- */
+
+pub async fn run(args: &SelfUpdateArgs) -> Result<ExitCode> {
+    let cfg = load_config().context("failed to load config")?;
+    let release = latest(&cfg).await?;
+    if !release.is_newer() {
+        println!("AION {} is up to date (latest release: {}).", CURRENT, release.tag_name);
+        return Ok(ExitCode::SUCCESS);
+    }
+    print_release(&release);
+    if args.check {
+        println!("Run `aion self-update` to install it.");
+        return Ok(ExitCode::SUCCESS);
+    }
+    println!();
+    install(&cfg, &release).await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_release(release: &Release) {
+    let published = release.published_at.as_deref().map(|d| format!(", published {}", &d[..d.len().min(10)]));
+    println!("AION {} is available (installed: {}{}).", release.version(), CURRENT, published.unwrap_or_default());
+    let notes = changelog_excerpt(release.body.as_deref().unwrap_or(""));
+    if !notes.is_empty() {
+        println!();
+        for line in notes {
+            println!("  {}", line);
+        }
+    }
+    if !release.html_url.is_empty() {
+        println!("\nRelease notes: {}", release.html_url);
+    }
+}
+
+/// The first `CHANGELOG_LINES` lines of `body`, without trailing blank lines.
+fn changelog_excerpt(body: &str) -> Vec<String> {
+    let lines: Vec<&str> = body.lines().map(str::trim_end).collect();
+    let mut out: Vec<String> = lines.iter().take(CHANGELOG_LINES).map(|l| l.to_string()).collect();
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    if lines.len() > CHANGELOG_LINES {
+        out.push("…".to_string());
+    }
+    out
+}
+
+async fn install(cfg: &AppConfig, release: &Release) -> Result<()> {
+    let name = asset_name();
+    let asset = release
+        .asset(&name)
+        .with_context(|| format!("release {} has no build for this platform ({})", release.tag_name, name))?;
+    let sums = release.asset(CHECKSUMS_ASSET).with_context(|| {
+        format!("release {} has no {}; an unverified build is not installed", release.tag_name, CHECKSUMS_ASSET)
+    })?;
+    let exe = std::env::current_exe().context("failed to locate the running executable")?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    let exe = Guard::new(&cfg.caps).check_write(&exe).context("cannot replace the executable")?;
+
+    let guard = Guard::for_app(cfg);
+    for url in [&sums.browser_download_url, &asset.browser_download_url] {
+        guard.check_network(url)?;
+    }
+    let client = client()?;
+    let sums = String::from_utf8(download(&client, sums).await?).context("the checksums file is not text")?;
+    let expected =
+        checksum_for(&sums, &name).with_context(|| format!("{} has no checksum for {}", CHECKSUMS_ASSET, name))?;
+    println!("Downloading {} ({})...", name, format_bytes(&cfg.language, asset.size));
+    let bytes = download(&client, asset).await?;
+    let actual = sha256_hex(&bytes);
+    if actual != expected {
+        bail!("checksum mismatch for {} (expected {}, got {}); nothing was installed", name, expected, actual);
+    }
+
+    if dry_run::enabled() {
+        println!("[dry-run] would replace {} with AION {} (checksum verified)", tilde(&exe), release.version());
+        return Ok(());
+    }
+    replace(&exe, &bytes)?;
+    audit::file_write(&exe);
+    println!("Updated AION {} -> {} ({}).", CURRENT, release.version(), tilde(&exe));
+    Ok(())
+}
+
+/// This platform's asset, e.g. `aion-linux-x86_64`.
+pub fn asset_name() -> String {
+    format!("aion-{}-{}{}", std::env::consts::OS, std::env::consts::ARCH, std::env::consts::EXE_SUFFIX)
+}
+
+/// The lowercase hex digest for `name` in `sha256sum` output (`<hex>  <name>`, or
+/// `<hex> *<name>` for binary mode).
+fn checksum_for(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, bytes).as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write `bytes` next to `exe`, then swap them in.
+fn replace(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = exe.parent().context("the executable has no parent directory")?;
+    let temp = dir.join(format!(".aion-update-{}", std::process::id()));
+    fs::write(&temp, bytes).with_context(|| format!("failed to write {}", temp.display()))?;
+    let result = swap(exe, &temp);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(unix)]
+fn swap(exe: &Path, new: &Path) -> Result<()> {
+    let permissions = fs::metadata(exe).with_context(|| format!("failed to read {}", exe.display()))?.permissions();
+    fs::set_permissions(new, permissions).with_context(|| format!("failed to make {} executable", new.display()))?;
+    fs::rename(new, exe).with_context(|| format!("failed to replace {}", exe.display()))
+}
+
+/// Windows keeps a running `.exe` open, but lets it be renamed.
+#[cfg(windows)]
+fn swap(exe: &Path, new: &Path) -> Result<()> {
+    let old = exe.with_extension("old.exe");
+    let _ = fs::remove_file(&old);
+    fs::rename(exe, &old).with_context(|| format!("failed to move {} aside", exe.display()))?;
+    if let Err(e) = fs::rename(new, exe) {
+        let _ = fs::rename(&old, exe);
+        return Err(e).with_context(|| format!("failed to replace {}", exe.display()));
+    }
+    Ok(())
+}
+
+/* ---------------------------
+   GitHub
+---------------------------- */
+
+/// The latest published release.
+pub async fn latest(cfg: &AppConfig) -> Result<Release> {
+    let url = Guard::for_app(cfg).check_network(&releases_url())?;
+    audit::network(url.as_str());
+    let resp = client()?
+        .get(url.as_str())
+        .timeout(cfg.network.timeout())
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .with_context(|| format!("could not reach GitHub ({})", url))?;
+    match resp.status().as_u16() {
+        200 => {}
+        404 => bail!("{} has no published releases", repository()),
+        403 | 429 => bail!("GitHub refused the request (rate limit reached?); try again later"),
+        status => bail!("GitHub answered HTTP {} for {}", status, url),
+    }
+    resp.json().await.context("could not read the release from GitHub")
+}
+
+/// `owner/name` from the `repository` in Cargo.toml.
+fn repository() -> &'static str {
+    env!("CARGO_PKG_REPOSITORY").trim_start_matches("https://github.com/").trim_end_matches('/')
+}
+
+fn releases_url() -> String {
+    #[cfg(debug_assertions)]
+    if let Ok(url) = std::env::var(RELEASES_ENV) {
+        return url;
+    }
+    format!("https://api.github.com/repos/{}/releases/latest", repository())
+}
+
+/// GitHub refuses requests without a User-Agent.
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(format!("aion/{}", CURRENT))
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()?)
+}
+
+async fn download(client: &reqwest::Client, asset: &Asset) -> Result<Vec<u8>> {
+    audit::network(&asset.browser_download_url);
+    let resp = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .with_context(|| format!("failed to download {}", asset.name))?;
+    if !resp.status().is_success() {
+        bail!("failed to download {}: HTTP {}", asset.name, resp.status().as_u16());
+    }
+    let bytes = resp.bytes().await.with_context(|| format!("failed to download {}", asset.name))?;
+    Ok(bytes.to_vec())
+}
+
+/// `a` against `b`: `major.minor.patch` numerically, then a pre-release before none.
+fn compare(a: &str, b: &str) -> Ordering {
+    let parse = |v: &str| {
+        let v = v.trim().trim_start_matches('v');
+        let v = v.split('+').next().unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (v, None),
+        };
+        let mut parts = core.split('.').map(|p| p.parse::<u64>().unwrap_or(0));
+        let mut next = || parts.next().unwrap_or(0);
+        ((next(), next(), next()), pre)
+    };
+    let (a_core, a_pre) = parse(a);
+    let (b_core, b_pre) = parse(b);
+    a_core.cmp(&b_core).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(&b),
+    })
+}
+
+/* ---------------------------
+   Check at start
+---------------------------- */
+
+/// What the last check found.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    /// Unix seconds of the last successful check.
+    checked_at: u64,
+    latest: Option<String>,
+    /// The version last announced at the ready prompt.
+    announced: Option<String>,
+}
+
+impl State {
+    /// Empty when missing or unreadable.
+    fn load() -> Self {
+        state_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Best effort: a lost state only means an extra check or notice. Not written in an
+    /// ephemeral process.
+    fn save(&self) {
+        if !persistence::policy().keeps_history() {
+            return;
+        }
+        let Ok(path) = state_path() else {
+            return;
+        };
+        let Ok(text) = serde_json::to_string_pretty(self) else {
+            return;
+        };
+        if paths::state_dir().and_then(|dir| paths::ensure(&dir)).is_ok() && fs::write(&path, text).is_ok() {
+            audit::file_write(&path);
+        }
+    }
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(STATE_FILE_NAME))
+}
+
+/// The latest version found by the check at start, once it has finished.
+static PENDING: Mutex<Option<Receiver<Option<String>>>> = Mutex::new(None);
+
+/// Start the check, when `updates.check_on_start` and `caps.network` allow it. Returns
+/// at once; within a day of the last check its result is reused.
+pub fn check_in_background(cfg: &AppConfig) {
+    if !cfg.updates.check_on_start || !cfg.caps.network {
+        return;
+    }
+    let (tx, rx) = oneshot::channel();
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = Some(rx);
+    }
+    let state = State::load();
+    if now().saturating_sub(state.checked_at) < CHECK_INTERVAL {
+        let _ = tx.send(state.latest);
+        return;
+    }
+    // Its own runtime: the main one is single-threaded and the REPL blocks it on input.
+    let cfg = cfg.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build();
+        let found = runtime.ok().and_then(|rt| rt.block_on(latest(&cfg)).ok()).map(|r| r.version().to_string());
+        if let Some(version) = &found {
+            let mut state = State::load();
+            state.checked_at = now();
+            state.latest = Some(version.clone());
+            state.save();
+        }
+        let _ = tx.send(found);
+    });
+}
+
+/// The notice for a newer version not announced before, if the check has finished.
+/// Never waits.
+pub fn take_notice(lang: &str) -> Option<String> {
+    let mut pending = PENDING.lock().ok()?;
+    let found = match pending.as_mut()?.try_recv() {
+        Ok(found) => found,
+        Err(TryRecvError::Empty) => return None,
+        Err(TryRecvError::Closed) => None,
+    };
+    *pending = None;
+    drop(pending);
+    announce(found?, lang)
+}
+
+/// Like `take_notice`, but waits for the check to finish.
+pub async fn notice(lang: String) -> Option<String> {
+    let rx = PENDING.lock().ok()?.take()?;
+    announce(rx.await.ok()??, &lang)
+}
+
+fn announce(version: String, lang: &str) -> Option<String> {
+    if compare(&version, CURRENT) != Ordering::Greater {
+        return None;
+    }
+    let mut state = State::load();
+    if state.announced.as_deref() == Some(version.as_str()) {
+        return None;
+    }
+    state.announced = Some(version.clone());
+    state.save();
+    Some(i18n::text(
+        lang,
+        "update.available",
+        "AION {version} is available (you have {current}). Run `aion self-update` to install it.",
+        &[("version", version), ("current", CURRENT.to_string())],
+    ))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,133 @@
+//! `aion usage`: the tokens and cost recorded for cloud providers (see `aion_core::costs`).
+//!
+//! Notes:
+//! - Without `--month`, one row per month in the ledger. With it, that month (this one
+//!   when no value is given) per provider and model, and the monthly budget when set.
+//! - Months are UTC, as in the ledger. Requests to a model without a known price count
+//!   in the tokens but not the cost; their cost cell says how many there were.
+//! - Only reads the ledger; a missing one is an empty report.
+
+use crate::cli::UsageArgs;
+use crate::config::io::load_config;
+use crate::costs::{self, Clock, Ledger, Line, Record, SystemClock, Totals};
+use crate::ui::console::Console;
+use crate::ui::width::width;
+use anyhow::Result;
+use serde::Serialize;
+use std::process::ExitCode;
+
+/// `--output json` with `--month`.
+#[derive(Debug, Serialize)]
+struct MonthReport<'a> {
+    month: &'a str,
+    models: &'a [Line],
+    total: Totals,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget_usd: Option<f64>,
+}
+
+/// `--output json` without `--month`: one per month.
+#[derive(Debug, Serialize)]
+struct MonthTotals {
+    month: String,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+pub fn run(console: &Console, args: &UsageArgs) -> Result<ExitCode> {
+    let ledger = Ledger::open(&costs::ledger_dir()?, false);
+    match args.month.as_deref() {
+        Some(month) => {
+            let month = match month {
+                "" => costs::month_of(SystemClock.now()),
+                month => month.to_string(),
+            };
+            let records = ledger.month(&month);
+            let models = costs::summarize(&records);
+            let total = total(&records);
+            let budget_usd = load_config().ok().and_then(|cfg| cfg.budget.per_month_usd);
+            if console.is_json() {
+                console.print_json(&MonthReport { month: &month, models: &models, total, budget_usd })?;
+            } else {
+                print_month(&month, &models, total, budget_usd);
+            }
+        }
+        None => {
+            let months: Vec<MonthTotals> = ledger
+                .months()
+                .into_iter()
+                .map(|month| MonthTotals { totals: total(&ledger.month(&month)), month })
+                .collect();
+            if console.is_json() {
+                console.print_json(&months)?;
+            } else if months.is_empty() {
+                println!("No usage recorded yet.");
+            } else {
+                let rows = months.iter().map(|m| row(m.month.clone(), String::new(), m.totals)).collect::<Vec<_>>();
+                print_table(["MONTH", "", "REQUESTS", "INPUT", "OUTPUT", "COST"], &rows);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn total(records: &[Record]) -> Totals {
+    let mut total = Totals::default();
+    records.iter().for_each(|r| total.add(r));
+    total
+}
+
+fn print_month(month: &str, models: &[Line], total: Totals, budget_usd: Option<f64>) {
+    if models.is_empty() {
+        println!("No usage recorded in {}.", month);
+        return;
+    }
+    println!("{} (UTC)", month);
+    let mut rows: Vec<[String; 6]> = models.iter().map(|l| row(l.provider.to_string(), l.model.clone(), l.totals)).collect();
+    rows.push(row("TOTAL".to_string(), String::new(), total));
+    print_table(["PROVIDER", "MODEL", "REQUESTS", "INPUT", "OUTPUT", "COST"], &rows);
+    if let Some(budget) = budget_usd {
+        println!(
+            "Monthly budget: ${:.2} of ${:.2} spent ({:.0}%)",
+            total.cost_usd,
+            budget,
+            total.cost_usd / budget * 100.0
+        );
+    }
+}
+
+fn row(first: String, second: String, t: Totals) -> [String; 6] {
+    let cost = match (t.requests - t.unpriced, t.unpriced) {
+        (_, 0) => format!("${:.2}", t.cost_usd),
+        (0, n) => format!("- ({} unpriced)", n),
+        (_, n) => format!("${:.2} + {} unpriced", t.cost_usd, n),
+    };
+    [first, second, t.requests.to_string(), t.input_tokens.to_string(), t.output_tokens.to_string(), cost]
+}
+
+/// The first two columns left-aligned, the numbers right-aligned.
+fn print_table(header: [&str; 6], rows: &[[String; 6]]) {
+    let mut widths = header.map(width);
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(width(cell));
+        }
+    }
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .filter(|(_, (_, w))| *w > 0)
+            .map(|(i, (cell, w))| match i {
+                0 | 1 => format!("{}{}", cell, " ".repeat(w - width(cell))),
+                _ => format!("{}{}", " ".repeat(w - width(cell)), cell),
+            })
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    println!("{}", line(&header.map(String::from)));
+    for row in rows {
+        println!("{}", line(row));
+    }
+}
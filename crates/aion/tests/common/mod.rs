@@ -0,0 +1,37 @@
+//! Helpers shared by the tests in `tests/`, which drive the built binary.
+
+// Each test file uses only some of these.
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::path::Path;
+
+/// `aion` with its config, state, cache, and home all under `dir`.
+pub fn aion(dir: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("aion").unwrap();
+    cmd.env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("AION_STATE_DIR", dir.join("state"))
+        .env("AION_CACHE_DIR", dir.join("cache"))
+        .env("RUST_BACKTRACE", "0")
+        .env_remove("AION_READ_ONLY")
+        .arg("--config")
+        .arg(dir.join("config.toml"));
+    cmd
+}
+
+/// `output` parsed as JSON; the text itself in the panic when it is not.
+pub fn json(output: &[u8]) -> Value {
+    serde_json::from_slice(output).unwrap_or_else(|e| panic!("{}: {}", e, String::from_utf8_lossy(output)))
+}
+
+/// What `cmd` printed, trimmed, once it succeeded.
+pub fn stdout(cmd: &mut Command) -> String {
+    String::from_utf8_lossy(&cmd.assert().success().get_output().stdout).trim().to_string()
+}
+
+/// What `cmd` wrote to stderr, once it failed.
+pub fn stderr(cmd: &mut Command) -> String {
+    String::from_utf8_lossy(&cmd.assert().failure().get_output().stderr).into_owned()
+}
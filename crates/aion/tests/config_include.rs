@@ -0,0 +1,63 @@
+//! `include = [...]` in the config file, read and written through the CLI.
+
+use common::{aion, stderr, stdout};
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+/// A config written by `aion init`, with `drop` removed and `include` added at the top.
+fn config_including(dir: &Path, include: &str, drop: &[&str]) {
+    aion(dir).arg("init").assert().success();
+    let path = dir.join("config.toml");
+    let text = std::fs::read_to_string(&path).unwrap();
+    let kept: Vec<&str> = text.lines().filter(|l| !drop.iter().any(|key| l.starts_with(&format!("{} =", key)))).collect();
+    std::fs::write(&path, format!("include = {}\n{}\n", include, kept.join("\n"))).unwrap();
+}
+
+#[test]
+fn nested_includes_fill_in_and_are_not_saved_back() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("team")).unwrap();
+    std::fs::write(dir.path().join("team/base.toml"), "language = \"en\"\n[history]\nmax_entries = 7\n").unwrap();
+    std::fs::write(dir.path().join("team/aion.toml"), "include = [\"./base.toml\"]\nlanguage = \"ar\"\n").unwrap();
+    config_including(dir.path(), r#"["team/aion.toml", "personal.toml?"]"#, &["language", "max_entries"]);
+
+    assert_eq!(stdout(aion(dir.path()).args(["config", "get", "language"])), "ar");
+    assert_eq!(stdout(aion(dir.path()).args(["config", "get", "history.max_entries"])), "7");
+
+    aion(dir.path()).args(["config", "set", "provider.params.temperature", "0.5"]).assert().success();
+    let saved = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(saved.contains("temperature = 0.5") && saved.contains("team/aion.toml"), "{}", saved);
+    assert!(!saved.contains("max_entries") && !saved.lines().any(|l| l.starts_with("language")), "{}", saved);
+
+    // The optional file, once there, wins over the team's.
+    std::fs::write(dir.path().join("personal.toml"), "[history]\nmax_entries = 9\n").unwrap();
+    assert_eq!(stdout(aion(dir.path()).args(["config", "get", "history.max_entries"])), "9");
+}
+
+#[test]
+fn an_include_cycle_fails_to_load_naming_the_chain() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.toml"), "include = [\"b.toml\"]\n").unwrap();
+    std::fs::write(dir.path().join("b.toml"), "include = [\"config.toml\"]\n").unwrap();
+    config_including(dir.path(), r#"["a.toml"]"#, &[]);
+
+    let stderr = stderr(aion(dir.path()).args(["config", "get", "language"]));
+    assert!(stderr.contains("include cycle:"), "{}", stderr);
+    assert!(stderr.contains("a.toml -> ") && stderr.contains("b.toml -> "), "{}", stderr);
+}
+
+#[test]
+fn a_missing_include_fails_to_load_unless_optional() {
+    let dir = TempDir::new().unwrap();
+    config_including(dir.path(), r#"["missing.toml"]"#, &[]);
+    let stderr = stderr(aion(dir.path()).args(["config", "get", "language"]));
+    assert!(stderr.contains("missing.toml, which does not exist"), "{}", stderr);
+    assert!(stderr.contains("end the path with `?`"), "{}", stderr);
+
+    let path = dir.path().join("config.toml");
+    let text = std::fs::read_to_string(&path).unwrap().replace("missing.toml", "missing.toml?");
+    std::fs::write(&path, text).unwrap();
+    assert_eq!(stdout(aion(dir.path()).args(["config", "get", "language"])), "en");
+}
@@ -0,0 +1,54 @@
+//! `aion config upgrade` over the config files in `tests/fixtures/config`.
+
+use common::{aion, stderr, stdout};
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config").join(name)).unwrap()
+}
+
+/// A temp dir whose config.toml is the fixture `name`.
+fn with_config(name: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("config.toml"), fixture(name)).unwrap();
+    dir
+}
+
+#[test]
+fn an_unversioned_file_is_upgraded_to_version_1() {
+    let dir = with_config("v0.toml");
+    let path = dir.path().join("config.toml");
+
+    let out = stdout(aion(dir.path()).args(["config", "upgrade", "--yes"]));
+    assert_eq!(out, fixture("v0.upgrade.txt").replace("{path}", &path.display().to_string()).trim());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), fixture("v1.toml"));
+}
+
+#[test]
+fn a_current_file_is_left_alone() {
+    let dir = with_config("v1.toml");
+    let path = dir.path().join("config.toml");
+
+    let out = stdout(aion(dir.path()).args(["config", "upgrade", "--yes"]));
+    assert_eq!(out, format!("{} is up to date (version 1).", path.display()));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), fixture("v1.toml"));
+}
+
+#[test]
+fn without_yes_or_a_terminal_nothing_is_written() {
+    let dir = with_config("v0.toml");
+    let err = stderr(aion(dir.path()).args(["config", "upgrade"]));
+    assert!(err.contains("rerun with --yes"), "{}", err);
+    assert_eq!(std::fs::read_to_string(dir.path().join("config.toml")).unwrap(), fixture("v0.toml"));
+}
+
+#[test]
+fn a_newer_version_is_refused() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("config.toml"), fixture("v1.toml").replace("version = 1", "version = 2")).unwrap();
+    let err = stderr(aion(dir.path()).args(["config", "upgrade", "--yes"]));
+    assert!(err.contains("config version 2 is newer than this build supports (1)"), "{}", err);
+}
@@ -0,0 +1,30 @@
+//! `aion config validate`: consistency warnings pass unless `--strict` makes them errors.
+
+use common::{aion, json, stderr, stdout};
+use tempfile::TempDir;
+
+mod common;
+
+#[test]
+fn strict_turns_consistency_warnings_into_errors() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).arg("init").assert().success();
+    aion(dir.path()).args(["config", "set", "caps.network", "false"]).assert().success();
+
+    let report = stdout(aion(dir.path()).args(["config", "validate"]));
+    assert!(report.contains("Warning: web_in_terminal is on but network access is disabled"), "{}", report);
+
+    let error = stderr(aion(dir.path()).args(["--strict", "config", "validate"]));
+    assert!(error.starts_with("Error: config validation failed: Inconsistent config: web_in_terminal"), "{}", error);
+    assert!(error.contains("fix: set caps.network = true, or features.web_in_terminal = false"), "{}", error);
+
+    let out = aion(dir.path()).args(["--strict", "--output", "json", "config", "validate"]).assert().code(1);
+    let report = json(&out.get_output().stdout);
+    assert_eq!((report["valid"].as_bool(), report["key"].as_str()), (Some(false), Some("features.web_in_terminal")));
+
+    // Nothing left to warn about: strict passes too.
+    for key in ["features.web_in_terminal", "features.safe_execute"] {
+        aion(dir.path()).args(["config", "set", key, "false"]).assert().success();
+    }
+    aion(dir.path()).args(["--strict", "config", "validate"]).assert().success();
+}
@@ -0,0 +1,84 @@
+//! `aion doctor` against the configs in `tests/fixtures/doctor`.
+
+use common::{aion, json};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/doctor").join(name)).unwrap()
+}
+
+/// A server on a free local port that answers every request with an empty 200; its URL.
+fn serve_ok() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut conn in listener.incoming().flatten() {
+            let _ = conn.read(&mut [0; 4096]);
+            let _ = conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        }
+    });
+    url
+}
+
+/// `aion doctor --json` under `dir`: whether it succeeded, and each check's status and detail by name.
+fn doctor(dir: &Path) -> (bool, Vec<(String, String, String)>) {
+    let out = aion(dir).args(["doctor", "--json"]).output().unwrap();
+    let report = json(&out.stdout);
+    assert_eq!(report["ok"], out.status.success(), "{}", report);
+    let field = |check: &Value, key: &str| check[key].as_str().unwrap().to_string();
+    let checks = report["checks"].as_array().unwrap().iter().map(|c| (field(c, "name"), field(c, "status"), field(c, "detail")));
+    (out.status.success(), checks.collect())
+}
+
+fn status<'a>(checks: &'a [(String, String, String)], name: &str) -> (&'a str, &'a str) {
+    let (_, status, detail) = checks.iter().find(|c| c.0 == name).unwrap_or_else(|| panic!("no check {:?}", name));
+    (status, detail)
+}
+
+#[test]
+fn a_config_that_does_not_parse_fails_and_is_left_alone() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, fixture("broken.toml")).unwrap();
+
+    let (ok, checks) = doctor(dir.path());
+    assert!(!ok);
+    assert_eq!(status(&checks, "config file exists").0, "pass");
+    let (parses, detail) = status(&checks, "config file parses");
+    assert_eq!(parses, "fail");
+    assert!(detail.contains("invalid table header"), "{}", detail);
+    assert_eq!(status(&checks, "config file validates"), ("fail", "skipped: config could not be parsed"));
+    for name in ["features match caps", "provider api key", "provider endpoint reachable", "local providers"] {
+        assert_eq!(status(&checks, name), ("warn", "skipped: config could not be parsed"), "{}", name);
+    }
+    // Doctor only looks: the file is not repaired or replaced, and nothing else is created.
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), fixture("broken.toml"));
+    assert!(!dir.path().join("config").exists());
+}
+
+#[test]
+fn a_healthy_setup_passes_every_check() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).args(["init", "--install-locales"]).assert().success();
+    let url = serve_ok();
+    std::fs::write(dir.path().join("config.toml"), fixture("healthy.toml").replace("{base_url}", &url)).unwrap();
+
+    let (ok, checks) = doctor(dir.path());
+    assert!(ok, "{:?}", checks);
+    for (name, status, detail) in &checks {
+        // Tests run without a terminal.
+        if name != "terminal" {
+            assert_eq!(status, "pass", "{}: {}", name, detail);
+        }
+    }
+    assert_eq!(status(&checks, "provider api key").1, "not required for Ollama");
+    assert_eq!(status(&checks, "provider endpoint reachable").1, format!("{} (HTTP 200)", url));
+    assert!(status(&checks, "locales").1.ends_with("(ar loaded)"), "{:?}", checks);
+    let version = format!("aion {} (config version 1, supported 1)", env!("CARGO_PKG_VERSION"));
+    assert_eq!(status(&checks, "version").1, version);
+}
@@ -0,0 +1,74 @@
+//! `--dry-run` previews every write and leaves the directory it would write to unchanged.
+
+use common::{aion, stdout};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+mod common;
+
+/// Every file and directory under `dir`, with each file's contents and modification time.
+fn snapshot(dir: &Path) -> BTreeMap<String, Option<(Vec<u8>, SystemTime)>> {
+    let mut found = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(next) = pending.pop() {
+        for entry in std::fs::read_dir(&next).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.strip_prefix(dir).unwrap().display().to_string();
+            if path.is_dir() {
+                pending.push(path);
+                found.insert(name, None);
+            } else {
+                let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+                found.insert(name, Some((std::fs::read(&path).unwrap(), modified)));
+            }
+        }
+    }
+    found
+}
+
+#[test]
+fn nothing_is_created_in_an_empty_dir() {
+    let dir = TempDir::new().unwrap();
+    let text = stdout(aion(dir.path()).args(["--dry-run", "init", "--provider", "openai", "--model", "gpt-4o"]));
+    assert!(text.contains("[dry-run] would create") && text.contains("+model = \"gpt-4o\""), "{}", text);
+
+    let setup = ["--dry-run", "--setup", "--non-interactive", "--provider", "claude", "--model", "claude-3-5-haiku-latest"];
+    let text = stdout(aion(dir.path()).args(setup));
+    assert!(text.contains("+kind = \"Claude\""), "{}", text);
+
+    assert_eq!(snapshot(dir.path()), BTreeMap::new());
+}
+
+#[test]
+fn an_existing_setup_is_left_as_it_was() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).arg("init").assert().success();
+    for (key, value) in [("history.retention_days", "1"), ("caps.write_files", "true"), ("caps.run_commands", "true")] {
+        aion(dir.path()).args(["config", "set", key, value]).assert().success();
+    }
+    // A session old enough for `sessions prune` to remove.
+    let sessions = dir.path().join("state/sessions");
+    std::fs::create_dir_all(&sessions).unwrap();
+    let session = sessions.join("3f9c2a7e5d1b4c08a6e2f1d0b9c8a7e6.json");
+    std::fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/../aion-core/fixtures/sessions/session.json"), &session).unwrap();
+    let old = SystemTime::now() - Duration::from_secs(30 * 86_400);
+    std::fs::File::options().write(true).open(&session).unwrap().set_modified(old).unwrap();
+
+    let before = snapshot(dir.path());
+    let dry_run = |args: &[&str]| stdout(aion(dir.path()).arg("--dry-run").args(args));
+
+    let text = dry_run(&["config", "set", "provider.model", "llama3"]);
+    assert!(text.contains("-model = \"mistral\"") && text.contains("+model = \"llama3\""), "{}", text);
+    assert!(dry_run(&["sessions", "prune"]).contains("[dry-run] would remove"));
+    let exports = dir.path().join("exports");
+    assert!(dry_run(&["sessions", "export", "--all", "--out", exports.to_str().unwrap()]).contains("[dry-run] would"));
+    let ran = dir.path().join("ran");
+    let text = dry_run(&["exec", "--", "touch", ran.to_str().unwrap()]);
+    assert!(text.contains("[dry-run] would run") && text.contains("touch"), "{}", text);
+    dry_run(&["cache", "clear"]);
+    dry_run(&["--setup", "--non-interactive", "--provider", "openai", "--model", "gpt-4o"]);
+
+    assert_eq!(snapshot(dir.path()), before);
+}
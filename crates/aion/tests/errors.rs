@@ -0,0 +1,29 @@
+//! What a failed command prints: a typed error in the UI language, anything else as its
+//! one-line context chain, never Rust's multi-line `Caused by:` form.
+
+use common::{aion, stderr};
+use tempfile::TempDir;
+
+mod common;
+
+#[test]
+fn config_errors_are_localized() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).arg("init").assert().success();
+
+    let en = stderr(aion(dir.path()).args(["config", "set", "provider.model", ""]));
+    assert!(en.starts_with("Error: ") && en.contains("provider.model"), "{}", en);
+    let ar = stderr(aion(dir.path()).args(["--lang", "ar", "config", "set", "provider.model", ""]));
+    assert_eq!(ar.trim_end(), "Error: لم يُحدَّد نموذج. اضبط provider.model في config.toml، أو شغّل `aion --setup`.");
+}
+
+#[test]
+fn other_errors_print_on_one_line() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).arg("init").assert().success();
+
+    for lang in ["en", "ar"] {
+        let text = stderr(aion(dir.path()).args(["--lang", lang, "config", "get", "nope"]));
+        assert_eq!(text, "Error: no config key named 'nope'\n", "--lang {}", lang);
+    }
+}
@@ -0,0 +1,27 @@
+//! `aion --help` in Arabic, against the snapshot in `tests/snapshots`.
+
+use common::{aion, stdout};
+use tempfile::TempDir;
+
+mod common;
+
+#[test]
+fn help_in_arabic_matches_the_snapshot() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).args(["init", "--install-locales"]).assert().success();
+
+    let help = stdout(aion(dir.path()).args(["--lang", "ar", "--help"]));
+    let snapshot = include_str!("snapshots/help.ar.txt");
+    assert_eq!(help, snapshot.trim(), "update tests/snapshots/help.ar.txt if the change is intended");
+
+    // The config's language is used when there is no --lang.
+    aion(dir.path()).args(["config", "set", "language", "ar"]).assert().success();
+    assert_eq!(stdout(aion(dir.path()).arg("--help")), snapshot.trim());
+}
+
+#[test]
+fn help_in_arabic_needs_no_installed_locales() {
+    let dir = TempDir::new().unwrap();
+    let help = stdout(aion(dir.path()).args(["--lang", "ar", "--help"]));
+    assert_eq!(help, include_str!("snapshots/help.ar.txt").trim(), "the bundled ar locale is not used");
+}
@@ -0,0 +1,103 @@
+//! Locale files are read when a string from them is needed, not all at startup.
+
+use common::{aion, json};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+mod common;
+
+/// Installed locales under `dir` (`$XDG_CONFIG_HOME/aion/locales`), plus a `tr.toml` that does not parse.
+fn with_broken_tr() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).args(["init", "--install-locales"]).assert().success();
+    std::fs::write(locales(dir.path()).join("tr.toml"), "[meta\nname = \"Türkçe\"\n").unwrap();
+    dir
+}
+
+fn locales(dir: &Path) -> PathBuf {
+    dir.join("config/aion/locales")
+}
+
+fn warnings(dir: &Path, args: &[&str]) -> String {
+    let out = aion(dir).args(args).assert().success();
+    String::from_utf8_lossy(&out.get_output().stderr).into_owned()
+}
+
+#[test]
+fn startup_does_not_read_other_locales() {
+    let dir = with_broken_tr();
+    for args in [&["usage"][..], &["--lang", "ar", "usage"], &["--lang", "ar", "--help"]] {
+        let stderr = warnings(dir.path(), args);
+        assert!(!stderr.contains("tr.toml"), "{:?}: {}", args, stderr);
+    }
+
+    // The language in use is read at startup, so its problems show there.
+    std::fs::write(locales(dir.path()).join("ar.toml"), "[meta\n").unwrap();
+    let stderr = warnings(dir.path(), &["--lang", "ar", "usage"]);
+    assert!(stderr.contains("Warning: locale file") && stderr.contains("ar.toml skipped"), "{}", stderr);
+    assert!(!stderr.contains("tr.toml"), "{}", stderr);
+}
+
+/// Every locale file is swapped for a FIFO, so opening one blocks until the timeout.
+#[cfg(unix)]
+#[test]
+fn version_help_and_config_get_read_no_locale_file() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).args(["init", "--install-locales"]).assert().success();
+    aion(dir.path()).args(["config", "set", "language", "ar"]).assert().success();
+    for entry in std::fs::read_dir(locales(dir.path())).unwrap() {
+        let path = entry.unwrap().path();
+        std::fs::remove_file(&path).unwrap();
+        assert!(std::process::Command::new("mkfifo").arg(&path).status().unwrap().success());
+    }
+
+    let runs = [&["--version"][..], &["--lang", "en", "--help"], &["config", "get", "language"], &["--lang", "ar", "config", "get", "language"]];
+    for args in runs {
+        let out = aion(dir.path()).args(args).timeout(Duration::from_secs(10)).assert().success();
+        let stderr = String::from_utf8_lossy(&out.get_output().stderr);
+        assert!(!stderr.contains("locale"), "{:?}: {}", args, stderr);
+    }
+}
+
+/// `config get` is what scripts and prompts call; keep its cold start well under a human-noticeable delay.
+#[test]
+fn config_get_starts_within_budget() {
+    let dir = with_broken_tr();
+    let fastest = (0..5)
+        .map(|_| {
+            let started = Instant::now();
+            aion(dir.path()).args(["config", "get", "language"]).assert().success();
+            started.elapsed()
+        })
+        .min()
+        .unwrap();
+    assert!(fastest < Duration::from_millis(500), "aion config get language took {:?}", fastest);
+}
+
+#[test]
+fn doctor_and_locales_check_read_them_all() {
+    let dir = with_broken_tr();
+
+    let out = aion(dir.path()).args(["doctor", "--json"]).output().unwrap();
+    let report = json(&out.stdout);
+    let check = report["checks"].as_array().unwrap().iter().find(|c| c["name"] == "locales").unwrap();
+    assert_eq!(check["status"], "warn");
+    assert!(check["detail"].as_str().unwrap().contains("tr.toml (Failed to parse locale file"), "{}", check);
+
+    let out = aion(dir.path()).args(["locales", "check"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&out.get_output().stderr);
+    assert!(stderr.contains("tr.toml"), "{}", stderr);
+}
+
+#[test]
+fn fresh_install_uses_the_bundled_locales() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).arg("init").assert().success();
+    assert_eq!(std::fs::read_dir(locales(dir.path())).unwrap().count(), 0);
+
+    let out = aion(dir.path()).args(["--lang", "ar", "status", "--json"]).output().unwrap();
+    let locale = &json(&out.stdout)["locale"];
+    assert_eq!(locale["status"], "ok", "{}", locale);
+    assert_eq!(locale["language"], "ar", "{}", locale);
+}
@@ -0,0 +1,71 @@
+//! `aion locales check` over the locale files in `tests/fixtures/locales`: `de` is
+//! partial but sound, `fr` claims to be full and is not.
+
+use common::{aion, json, stdout};
+use serde_json::Value;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+/// A temp dir with the fixture locales installed next to the bundled ones.
+fn with_fixture_locales() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let installed = dir.path().join("config/aion/locales");
+    std::fs::create_dir_all(&installed).unwrap();
+    for code in ["de", "fr"] {
+        let file = format!("{}.toml", code);
+        std::fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/locales").join(&file), installed.join(&file)).unwrap();
+    }
+    dir
+}
+
+fn report<'a>(reports: &'a Value, code: &str) -> &'a Value {
+    reports.as_array().unwrap().iter().find(|r| r["code"] == code).unwrap_or_else(|| panic!("no {}: {}", code, reports))
+}
+
+#[test]
+fn a_partial_locale_with_matching_placeholders_passes() {
+    let dir = with_fixture_locales();
+    let out = stdout(aion(dir.path()).args(["locales", "check", "de"]));
+    let first = out.lines().next().unwrap();
+    assert!(first.starts_with("de (German): ") && first.contains("% translated, "), "{}", out);
+    // Missing keys are listed, and fall back to English: they do not fail the check.
+    assert!(out.lines().any(|l| l == "  missing   providers.ollama.name"), "{}", out);
+    assert!(!out.contains("required") && !out.contains("mismatch"), "{}", out);
+}
+
+#[test]
+fn missing_provider_labels_and_placeholder_mismatches_fail() {
+    let dir = with_fixture_locales();
+    let out = aion(dir.path()).args(["locales", "check", "fr"]).assert().code(1);
+    let out = String::from_utf8_lossy(&out.get_output().stdout).into_owned();
+    for line in [
+        "  required  providers.openai.name (no translation)",
+        "  required  providers.claude.description (no translation)",
+        "  mismatch  summary.context_truncated (placeholders {sent} {size} in en, {sent} here)",
+        "  mismatch  summary.warning.missing_api_key (placeholders {provider} {var} in en, {fournisseur} {var} here)",
+    ] {
+        assert!(out.lines().any(|l| l == line), "{:?} not in\n{}", line, out);
+    }
+    assert!(!out.contains("providers.ollama.name (no translation)"), "{}", out);
+    assert!(!out.contains("mismatch  update.available"), "{}", out);
+
+    // All installed locales: the bundled ones and de pass, fr fails the run.
+    let out = aion(dir.path()).args(["--output", "json", "locales", "check"]).assert().code(1);
+    let reports = json(&out.get_output().stdout);
+    for code in ["en", "ar", "de"] {
+        assert_eq!(report(&reports, code)["missing_required"], serde_json::json!([]), "{}", code);
+        assert_eq!(report(&reports, code)["placeholders"], serde_json::json!([]), "{}", code);
+    }
+    let fr = report(&reports, "fr");
+    assert_eq!(fr["missing_required"].as_array().unwrap().len(), 6);
+    assert_eq!(
+        fr["placeholders"],
+        serde_json::json!([
+            {"key": "summary.context_truncated", "expected": ["sent", "size"], "found": ["sent"]},
+            {"key": "summary.warning.missing_api_key", "expected": ["provider", "var"], "found": ["fournisseur", "var"]},
+        ])
+    );
+    assert!(fr["coverage"].as_f64().unwrap() < 0.1);
+}
@@ -0,0 +1,85 @@
+//! `aion chat --provider mock`, replaying the scripts in `tests/fixtures/mock`.
+
+use aion_core::costs::{self, Clock, SystemClock};
+use assert_cmd::Command;
+use common::{json, stdout};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+mod common;
+
+/// `aion chat --provider mock` under `dir` (see `common::aion`), replaying `script`. The
+/// config must exist; `chat` does not create it.
+fn chat(dir: &Path, script: &str) -> Command {
+    let mut cmd = common::aion(dir);
+    cmd.env("AION_MOCK_PROVIDER", Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mock").join(script));
+    cmd.args(["chat", "--provider", "mock"]);
+    cmd
+}
+
+#[test]
+fn a_429_is_retried_after_the_wait_it_asks_for() {
+    let dir = TempDir::new().unwrap();
+    common::aion(dir.path()).arg("init").assert().success();
+    let started = Instant::now();
+    let out = chat(dir.path(), "retry-429.json").args(["--output", "json", "hi"]).assert().success();
+    assert!(started.elapsed() >= Duration::from_secs(1), "Retry-After: 1 was not waited out");
+    let reply = json(&out.get_output().stdout);
+    assert_eq!(reply["content"], "Answered after a wait");
+    assert_eq!(reply["usage"]["output_tokens"], 4);
+
+    // A wait longer than a retry is worth is not taken: the request fails as rate limited.
+    let out = chat(dir.path(), "rate-limited.json").arg("hi").assert().code(4);
+    let stderr = String::from_utf8_lossy(&out.get_output().stderr).into_owned();
+    assert!(stderr.to_lowercase().contains("rate limit"), "{}", stderr);
+
+    // The same error, localized; the exit code does not depend on the language.
+    let out = chat(dir.path(), "rate-limited.json").args(["--lang", "ar", "hi"]).assert().code(4);
+    let stderr = String::from_utf8_lossy(&out.get_output().stderr).into_owned();
+    assert_eq!(stderr.trim_end(), "Error: المزوّد يحدّ من عدد الطلبات. أعد المحاولة بعد 600 ثانية (/retry في المحادثة).");
+}
+
+#[test]
+fn a_reply_cut_at_the_token_limit_says_so() {
+    let dir = TempDir::new().unwrap();
+    common::aion(dir.path()).arg("init").assert().success();
+    let out = chat(dir.path(), "max-tokens.json").arg("how do I install it?").assert().success();
+    let output = out.get_output();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "Step one: install the package. Step two:");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.lines().any(|l| l
+            == "[Response truncated at the token limit: /continue picks it up, or increase provider.params.max_tokens]"),
+        "{}",
+        stderr
+    );
+
+    let out = chat(dir.path(), "max-tokens.json").args(["--output", "json", "again"]).assert().success();
+    assert_eq!(json(&out.get_output().stdout)["stop"], "max_tokens");
+}
+
+#[test]
+fn a_priced_reply_is_costed_in_usage() {
+    let dir = TempDir::new().unwrap();
+    common::aion(dir.path()).args(["init", "--provider", "openai", "--model", "gpt-4o-mini"]).assert().success();
+    common::aion(dir.path()).args(["config", "set", "budget.per_month_usd", "3"]).assert().success();
+    chat(dir.path(), "priced.json").arg("hi").assert().success();
+
+    // gpt-4o-mini: $0.15 in and $0.60 out per million tokens.
+    let report = stdout(common::aion(dir.path()).args(["usage", "--month", &costs::month_of(SystemClock.now())]));
+    let line = |start: &str| report.lines().find(|l| l.starts_with(start)).unwrap_or_else(|| panic!("{}", report)).to_string();
+    let cells = |line: String| line.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+    assert_eq!(cells(line("OpenAI")), ["OpenAI", "gpt-4o-mini", "1", "1000000", "1000000", "$0.75"]);
+    assert_eq!(cells(line("TOTAL")), ["TOTAL", "1", "1000000", "1000000", "$0.75"]);
+    assert_eq!(line("Monthly budget"), "Monthly budget: $0.75 of $3.00 spent (25%)");
+}
+
+#[test]
+fn mock_needs_a_script() {
+    let dir = TempDir::new().unwrap();
+    common::aion(dir.path()).arg("init").assert().success();
+    let out = common::aion(dir.path()).env_remove("AION_MOCK_PROVIDER").args(["chat", "--provider", "mock", "hi"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&out.get_output().stderr).into_owned();
+    assert!(stderr.contains("--provider mock replays a script; set AION_MOCK_PROVIDER to its path"), "{}", stderr);
+}
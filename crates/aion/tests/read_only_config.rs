@@ -0,0 +1,96 @@
+//! A config directory that cannot be written: AION runs on defaults in memory, and saves
+//! fail with one clear line. Unix permissions only; root writes through them, so there
+//! the tests say so and check nothing.
+#![cfg(unix)]
+
+use common::{aion, stderr};
+use std::fs::{self, Permissions};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+/// Make `dir` read-only (0o555). `false` when writes still go through (root).
+fn lock(dir: &Path) -> bool {
+    fs::set_permissions(dir, Permissions::from_mode(0o555)).unwrap();
+    let probe = dir.join("probe");
+    if fs::write(&probe, "").is_ok() {
+        let _ = fs::remove_file(probe);
+        eprintln!("skipped: {} is still writable after chmod 0o555 (running as root?)", dir.display());
+        return false;
+    }
+    true
+}
+
+/// Writable again, so `TempDir` can remove it.
+fn unlock(dir: &Path) {
+    fs::set_permissions(dir, Permissions::from_mode(0o755)).unwrap();
+}
+
+/// A temp dir for `common::aion` whose state and cache dirs stay writable.
+fn home() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for sub in ["state", "cache", "config"] {
+        fs::create_dir(dir.path().join(sub)).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn first_run_uses_defaults_in_memory_and_saves_fail_cleanly() {
+    let dir = home();
+    if !lock(dir.path()) {
+        return unlock(dir.path());
+    }
+    let path = dir.path().join("config.toml");
+
+    // The REPL still starts, with one line saying why nothing was saved.
+    let out = aion(dir.path()).write_stdin("/exit\n").assert().success();
+    let output = out.get_output();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Config loaded successfully"));
+    let err = String::from_utf8_lossy(&output.stderr);
+    let warning = format!(
+        "Warning: using default settings for this run; config not saved: configuration is read-only: cannot write {}: ",
+        path.display()
+    );
+    let line = err.lines().find(|l| l.starts_with("Warning: using default")).unwrap_or_else(|| panic!("{}", err));
+    assert!(line.starts_with(&warning) && line.ends_with("(use --config <file> for a writable location)"), "{}", line);
+    assert!(!path.exists());
+
+    // Saving on purpose is an error, with the way out, and exit code 1.
+    let out = aion(dir.path()).arg("init").assert().code(1);
+    let err = String::from_utf8_lossy(&out.get_output().stderr).into_owned();
+    assert_eq!(err.lines().count(), 1, "{}", err);
+    assert!(err.starts_with("Error: failed to save config: Configuration is read-only: cannot write "), "{}", err);
+    assert!(err.trim_end().ends_with("Pass --config <file> to use a writable location."), "{}", err);
+    assert!(!path.exists());
+
+    unlock(dir.path());
+}
+
+#[test]
+fn config_set_on_a_read_only_file_fails_and_leaves_it_alone() {
+    let dir = home();
+    aion(dir.path()).arg("init").assert().success();
+    let path = dir.path().join("config.toml");
+    let before = fs::read_to_string(&path).unwrap();
+    fs::set_permissions(&path, Permissions::from_mode(0o444)).unwrap();
+    if !lock(dir.path()) {
+        return unlock(dir.path());
+    }
+
+    let err = stderr(aion(dir.path()).args(["config", "set", "language", "ar"]));
+    assert_eq!(
+        err.trim_end(),
+        format!(
+            "Error: failed to save config: Configuration is read-only: cannot write {} (Permission denied (os error 13)). \
+             Pass --config <file> to use a writable location.",
+            path.display()
+        )
+    );
+    aion(dir.path()).args(["config", "set", "language", "ar"]).assert().code(1);
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+
+    unlock(dir.path());
+}
@@ -0,0 +1,99 @@
+//! `aion report`: the bundle is unpacked and read back, and no secret survives in it.
+
+use common::{aion, stdout};
+use std::io::Read;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+const KEY: &str = "sk-report-0123456789abcdefghijklmnop";
+const URL_PASSWORD: &str = "hunter2-url-password";
+
+/// An initialized config under `dir` whose key is read from `AION_REPORT_TEST_KEY`, with
+/// the key in http.log and in a saved session, and a password in `provider.base_url`.
+/// Writing files is allowed, once the report is seen to need it.
+fn leaky_setup(dir: &Path) {
+    aion(dir).arg("init").assert().success();
+    aion(dir).args(["config", "set", "provider.api_key_env", "AION_REPORT_TEST_KEY"]).assert().success();
+    let url = format!("http://aion:{}@localhost:11434", URL_PASSWORD);
+    aion(dir).args(["config", "set", "provider.base_url", &url]).assert().success();
+    std::fs::create_dir_all(dir.join("state/sessions")).unwrap();
+    let session = serde_json::json!({
+        "id": "20260101-000000-report", "created_at": 1767225600, "updated_at": 1767225660,
+        "messages": [
+            {"role": "user", "content": format!("is {} still valid?", KEY), "at": 1767225600},
+            {"role": "assistant", "content": "It is.", "at": 1767225660},
+        ],
+    });
+    std::fs::write(dir.join("state/sessions/20260101-000000-report.json"), session.to_string()).unwrap();
+    let log = format!("> POST /v1/chat/completions\n> authorization: Bearer {}\n< 200 OK\n{} in a body\n", KEY, KEY);
+    std::fs::write(dir.join("state/http.log"), log).unwrap();
+    let err = common::stderr(&mut report(dir));
+    assert!(err.contains("caps.write_files = true"), "{}", err);
+    aion(dir).args(["config", "set", "caps.write_files", "true"]).assert().success();
+}
+
+fn report(dir: &Path) -> assert_cmd::Command {
+    let mut cmd = aion(dir);
+    cmd.env("AION_REPORT_TEST_KEY", KEY).current_dir(dir).args(["report", "--include-session"]);
+    cmd
+}
+
+/// Each file of the zip at `path`, by name.
+fn unzip(path: &Path) -> Vec<(String, String)> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+    (0..archive.len())
+        .map(|i| {
+            let mut file = archive.by_index(i).unwrap();
+            let mut text = String::new();
+            file.read_to_string(&mut text).unwrap();
+            (file.name().to_string(), text)
+        })
+        .collect()
+}
+
+fn assert_clean(files: &[(String, String)]) {
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["config.toml", "doctor.json", "version.txt", "http.log", "locales.txt", "session.json"]);
+    for (name, text) in files {
+        assert!(!text.contains(KEY), "{} leaks the key:\n{}", name, text);
+        assert!(!text.contains(URL_PASSWORD), "{} leaks the URL password:\n{}", name, text);
+    }
+}
+
+#[test]
+fn the_zip_holds_every_file_with_secrets_masked() {
+    let dir = TempDir::new().unwrap();
+    leaky_setup(dir.path());
+    let out = stdout(report(dir.path()).args(["--out", "bundle.zip"]));
+    let path = dir.path().join("bundle.zip");
+    assert!(out.ends_with(&format!("Wrote {}", path.display())), "{}", out);
+    assert!(!out.contains(KEY), "{}", out);
+
+    let files = unzip(&path);
+    assert_clean(&files);
+    let file = |name: &str| files.iter().find(|(n, _)| n == name).map(|(_, text)| text.as_str()).unwrap();
+    // What was there is still there, masked: the bundle is not just empty.
+    assert!(file("config.toml").contains("api_key_env = \"AION_REPORT_TEST_KEY\""), "{}", file("config.toml"));
+    assert!(file("http.log").contains("< 200 OK"), "{}", file("http.log"));
+    assert!(file("http.log").contains("***REDACTED***"), "{}", file("http.log"));
+    assert!(file("session.json").contains("still valid?"), "{}", file("session.json"));
+    assert!(file("version.txt").starts_with(&format!("aion {}\n", env!("CARGO_PKG_VERSION"))), "{}", file("version.txt"));
+    assert!(file("locales.txt").lines().any(|l| l.starts_with("en\t")), "{}", file("locales.txt"));
+}
+
+#[test]
+fn no_zip_writes_the_same_files_masked() {
+    let dir = TempDir::new().unwrap();
+    leaky_setup(dir.path());
+    stdout(report(dir.path()).args(["--no-zip", "--out", "bundle"]));
+
+    let order = ["config.toml", "doctor.json", "version.txt", "http.log", "locales.txt", "session.json"];
+    let files: Vec<(String, String)> = order
+        .iter()
+        .map(|name| (name.to_string(), std::fs::read_to_string(dir.path().join("bundle").join(name)).unwrap()))
+        .collect();
+    assert_clean(&files);
+    assert_eq!(std::fs::read_dir(dir.path().join("bundle")).unwrap().count(), order.len());
+}
@@ -0,0 +1,68 @@
+//! `aion --setup --non-interactive --output json`, driven as a script would.
+
+use assert_cmd::Command;
+use common::json;
+use serde_json::Value;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+/// `aion --setup --non-interactive --output json` under `dir` (see `common::aion`).
+fn aion(dir: &Path) -> Command {
+    let mut cmd = common::aion(dir);
+    cmd.args(["--setup", "--non-interactive", "--output", "json"]);
+    cmd
+}
+
+#[test]
+fn saves_and_lists_changed_fields() {
+    let dir = TempDir::new().unwrap();
+    let out = aion(dir.path()).args(["--provider", "openai", "--model", "gpt-4o"]).assert().success();
+    let result = json(&out.get_output().stdout);
+
+    assert_eq!(result["status"], "saved");
+    assert_eq!(result["config_path"], dir.path().join("config.toml").display().to_string());
+    let changed: Vec<&str> = result["changed_fields"].as_array().unwrap().iter().map(|v| v.as_str().unwrap()).collect();
+    for key in ["provider.kind", "provider.model", "provider.api_key_env"] {
+        assert!(changed.contains(&key), "{} not in {:?}", key, changed);
+    }
+    let saved = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(saved.contains("model = \"gpt-4o\""), "{}", saved);
+
+    // The same answers again change nothing.
+    let out = aion(dir.path()).args(["--provider", "openai", "--model", "gpt-4o"]).assert().success();
+    assert_eq!(json(&out.get_output().stdout)["changed_fields"], Value::Array(vec![]));
+}
+
+#[test]
+fn reports_every_invalid_answer() {
+    let dir = TempDir::new().unwrap();
+    let out = aion(dir.path())
+        .args(["--provider", "openrouter", "--model", "", "--base-url", "", "--api-key-env", "MY KEY"])
+        .assert()
+        .failure();
+    let result = json(&out.get_output().stdout);
+
+    assert_eq!(result["status"], "error");
+    let errors = result["errors"].as_array().unwrap();
+    let found: Vec<(&str, &str)> = errors.iter().map(|e| (e["field"].as_str().unwrap(), e["code"].as_str().unwrap())).collect();
+    assert_eq!(
+        found,
+        [("provider.model", "EmptyModel"), ("provider.base_url", "MissingBaseUrl"), ("provider.api_key_env", "InvalidApiKeyEnv")]
+    );
+    assert!(errors.iter().all(|e| !e["message"].as_str().unwrap().is_empty()));
+    let saved = std::fs::read_to_string(dir.path().join("config.toml")).unwrap_or_default();
+    assert!(!saved.contains("OpenRouter"), "{}", saved);
+}
+
+#[test]
+fn strict_reports_consistency_warnings() {
+    let dir = TempDir::new().unwrap();
+    // The default features include safe_execute, which the default capabilities cannot run.
+    let out = aion(dir.path()).arg("--strict").assert().failure();
+    let result = json(&out.get_output().stdout);
+    assert_eq!(result["status"], "error");
+    assert_eq!(result["errors"][0]["code"], "Inconsistent");
+    assert_eq!(result["errors"][0]["field"], "features.safe_execute");
+}
@@ -0,0 +1,69 @@
+//! `aion --setup --plain` answered line by line on stdin, as a script or screen reader would.
+
+use assert_cmd::Command;
+use common::stdout;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+/// `aion --setup --plain` under `dir` (see `common::aion`), with nothing for the wizard's
+/// first screen to detect.
+fn aion(dir: &Path) -> Command {
+    let mut cmd = common::aion(dir);
+    cmd.env_remove("OPENAI_API_KEY").env_remove("ANTHROPIC_API_KEY").env_remove("OPENROUTER_API_KEY");
+    cmd.args(["--setup", "--plain"]);
+    cmd
+}
+
+fn get(dir: &Path, key: &str) -> String {
+    stdout(common::aion(dir).args(["config", "get", key]))
+}
+
+#[test]
+fn saves_the_scripted_answers() {
+    let dir = TempDir::new().unwrap();
+    let answers = [
+        "",            // Language: keep en
+        "7",           // Provider: out of range, asked again
+        "b",           // back to Language
+        "",            // Language: keep en
+        "3",           // Provider: OpenAI
+        "gpt-4o-mini", // Model
+        "",            // Endpoint: keep the default
+        "",            // API key variable: keep OPENAI_API_KEY
+        "n", "n", "y", "n", "", // features
+        "y", "yes", "y", "N",   // capabilities
+        "y",           // Save
+    ];
+    let out = aion(dir.path()).write_stdin(answers.join("\n") + "\n").assert().success();
+    let transcript = String::from_utf8_lossy(&out.get_output().stdout).into_owned();
+    assert!(transcript.contains("Please enter a number from 1 to 4."), "{}", transcript);
+    // Piped answers are echoed after their question.
+    assert!(transcript.contains("Model [") && transcript.contains("(b back, q quit): gpt-4o-mini\n"), "{}", transcript);
+    assert!(transcript.contains("Capabilities: read files on, write files on, network on, run commands off"), "{}", transcript);
+
+    assert_eq!(get(dir.path(), "language"), "en");
+    assert_eq!(get(dir.path(), "provider.kind"), "OpenAI");
+    assert_eq!(get(dir.path(), "provider.model"), "gpt-4o-mini");
+    assert_eq!(get(dir.path(), "provider.api_key_env"), "OPENAI_API_KEY");
+    assert_eq!(get(dir.path(), "features.system_scan"), "false");
+    assert_eq!(get(dir.path(), "features.command_suggestions"), "true");
+    assert_eq!(get(dir.path(), "features.retrieval"), "false");
+    assert_eq!(get(dir.path(), "caps.write_files"), "true");
+    assert_eq!(get(dir.path(), "caps.run_commands"), "false");
+}
+
+#[test]
+fn declining_or_running_out_of_answers_keeps_the_default_config() {
+    let dir = TempDir::new().unwrap();
+    // Ollama with a model other than the default, then no to saving.
+    let declined = ["", "2", "llama3", "", "", "", "", "", "", "", "", "", "", "n"];
+    let out = aion(dir.path()).write_stdin(declined.join("\n") + "\n").assert().failure();
+    assert!(String::from_utf8_lossy(&out.get_output().stderr).contains("Wizard cancelled by user"));
+    assert_eq!(get(dir.path(), "provider.model"), "mistral");
+
+    let out = aion(dir.path()).write_stdin("\n2\nllama3\n").assert().failure();
+    assert!(String::from_utf8_lossy(&out.get_output().stderr).contains("Wizard cancelled: input ended"));
+    assert_eq!(get(dir.path(), "provider.model"), "mistral");
+}
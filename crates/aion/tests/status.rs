@@ -0,0 +1,85 @@
+//! `aion status` against the config dir in `tests/fixtures/status`: a config, and two
+//! saved sessions under `state/`.
+
+use common::{aion, json, stdout};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+mod common;
+
+/// A temp dir holding a copy of the fixture dir.
+fn fixture_dir() -> TempDir {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/status");
+    let dir = TempDir::new().unwrap();
+    for name in files(&fixture) {
+        let to = dir.path().join(&name);
+        std::fs::create_dir_all(to.parent().unwrap()).unwrap();
+        std::fs::copy(fixture.join(&name), to).unwrap();
+    }
+    dir
+}
+
+/// Every file under `dir`, relative to it, sorted.
+fn files(dir: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path) -> Vec<PathBuf> {
+        let entries = std::fs::read_dir(dir).unwrap().map(|e| e.unwrap().path());
+        entries.flat_map(|path| if path.is_dir() { walk(&path) } else { vec![path] }).collect()
+    }
+    let mut found: Vec<PathBuf> = walk(dir).iter().map(|p| p.strip_prefix(dir).unwrap().to_path_buf()).collect();
+    found.sort();
+    found
+}
+
+#[test]
+fn every_section_reads_the_fixture_and_nothing_is_written() {
+    let dir = fixture_dir();
+    let before = files(dir.path());
+
+    let out = aion(dir.path()).args(["status", "--json"]).assert().success();
+    let report = json(&out.get_output().stdout);
+    assert_eq!(report["config"]["status"], "ok");
+    assert_eq!(report["config"]["path"], dir.path().join("config.toml").display().to_string());
+    assert_eq!(report["config"]["version"], 1);
+    assert_eq!(report["provider"]["kind"], "OpenAI");
+    assert_eq!(report["provider"]["model"], "gpt-4o-mini");
+    assert_eq!(report["provider"]["api_key_env"], "AION_STATUS_TEST_KEY");
+    assert_eq!(report["provider"]["api_key_set"], false);
+    assert_eq!(report["summary"]["runtime_warnings"][0]["id"], "missing_api_key");
+    // Two sessions; the second has one reply without usage, which counts as zero.
+    assert_eq!(report["sessions"]["count"], 2);
+    assert_eq!(report["sessions"]["last_updated"], "2026-03-02T10:02:00Z");
+    assert_eq!(report["sessions"]["input_tokens"], 320);
+    assert_eq!(report["sessions"]["output_tokens"], 80);
+    assert_eq!(report["locale"]["language"], "ar");
+    assert_eq!(report["locale"]["coverage"], 100);
+    assert_eq!(report["endpoint"]["reason"], "caps.network is disabled");
+    assert_eq!(report["health"]["reason"], "no provider.fallbacks configured");
+
+    let report = json(&aion(dir.path()).env("AION_STATUS_TEST_KEY", "sk-test").args(["status", "--json"]).output().unwrap().stdout);
+    assert_eq!(report["provider"]["api_key_set"], true);
+    assert_eq!(report["summary"]["runtime_warnings"], serde_json::json!([]));
+
+    let text = stdout(aion(dir.path()).args(["--lang", "en", "status"]));
+    assert!(text.lines().any(|l| l == "Sessions   2 saved, last 2026-03-02T10:02:00Z, 320 in / 80 out tokens"), "{}", text);
+    assert!(text.lines().any(|l| l == "Endpoint   skipped: caps.network is disabled"), "{}", text);
+
+    assert_eq!(files(dir.path()), before);
+}
+
+#[test]
+fn a_broken_config_fails_its_section_and_the_rest_still_report() {
+    let dir = fixture_dir();
+    std::fs::write(dir.path().join("config.toml"), "[provider\n").unwrap();
+
+    let out = aion(dir.path()).args(["status", "--json"]).assert().code(1);
+    let report = json(&out.get_output().stdout);
+    assert_eq!(report["config"]["status"], "error");
+    assert!(report["config"]["error"].as_str().unwrap().starts_with("failed to parse config file: "), "{}", report);
+    for section in ["provider", "endpoint", "health", "summary"] {
+        assert_eq!(report[section]["reason"], "config unavailable", "{}", section);
+    }
+    assert_eq!(report["sessions"]["status"], "ok");
+    assert_eq!(report["sessions"]["count"], 2);
+    assert_eq!(report["locale"]["status"], "ok");
+    assert_eq!(std::fs::read_to_string(dir.path().join("config.toml")).unwrap(), "[provider\n");
+}
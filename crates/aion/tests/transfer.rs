@@ -0,0 +1,99 @@
+//! `aion export` writes a `.tar.gz`; `aion import` restores it into another config dir.
+
+use common::{aion, stdout};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+mod common;
+
+const SESSION: &str = "sessions/20260101-000000-move.json";
+
+/// What `aion export` bundles under `dir`: a config that allows writing the archive (and
+/// the recent models `init` records), a user template, a locale, and a saved session.
+fn populated(dir: &Path) {
+    aion(dir).arg("init").assert().success();
+    aion(dir).args(["config", "set", "caps.write_files", "true"]).assert().success();
+    let config_dir = dir.join("config/aion");
+    std::fs::create_dir_all(config_dir.join("locales")).unwrap();
+    std::fs::write(config_dir.join("templates.toml"), "[greet]\nbody = \"Say hello to {input}\"\n").unwrap();
+    std::fs::write(config_dir.join("locales/pirate.toml"), "[meta]\nname = \"Pirate\"\n").unwrap();
+    std::fs::create_dir_all(dir.join("state/sessions")).unwrap();
+    let session = serde_json::json!({
+        "id": "20260101-000000-move", "created_at": 1767225600, "updated_at": 1767225660,
+        "messages": [{"role": "user", "content": "moving day", "at": 1767225600}],
+    });
+    std::fs::write(dir.join("state").join(SESSION), session.to_string()).unwrap();
+}
+
+/// Each file of the gzipped tar at `path`, by name, in archive order.
+fn untar(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(bytes[..2], [0x1f, 0x8b], "{} is not gzip", path.display());
+    let mut archive = tar::Archive::new(GzDecoder::new(&bytes[..]));
+    archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let mut entry = entry.unwrap();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            (entry.path().unwrap().to_string_lossy().into_owned(), data)
+        })
+        .collect()
+}
+
+/// Where `name` from the archive lives under a dir set up by `common::aion`.
+fn installed(dir: &Path, name: &str) -> PathBuf {
+    match name {
+        "config.toml" => dir.join("config.toml"),
+        _ if name == "recent_models.json" || name.starts_with("sessions/") => dir.join("state").join(name),
+        _ => dir.join("config/aion").join(name),
+    }
+}
+
+#[test]
+fn the_tar_gz_round_trips_into_an_empty_config_dir() {
+    let dir = TempDir::new().unwrap();
+    populated(dir.path());
+    let path = dir.path().join("move.tar.gz");
+    let out = stdout(aion(dir.path()).current_dir(dir.path()).args(["export", "--include-sessions", "--out", "move.tar.gz"]));
+    assert!(out.ends_with(&format!("Wrote {}", path.display())), "{}", out);
+
+    let files = untar(&path);
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["manifest.json", "config.toml", "templates.toml", "recent_models.json", "locales/pirate.toml", SESSION]);
+    let manifest: serde_json::Value = serde_json::from_slice(&files[0].1).unwrap();
+    assert_eq!(manifest["aion"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest["parts"], serde_json::json!(["config", "templates", "models", "locales", "sessions"]));
+
+    let new = TempDir::new().unwrap();
+    let out = stdout(aion(new.path()).arg("import").arg(&path));
+    assert!(out.lines().any(|l| l == format!("  sessions   {}", SESSION)), "{}", out);
+    for (name, bytes) in &files[1..] {
+        assert_eq!(&std::fs::read(installed(new.path(), name)).unwrap(), bytes, "{}", name);
+    }
+    assert_eq!(stdout(aion(new.path()).args(["config", "get", "caps.write_files"])), "true");
+
+    // Nothing is replaced a second time without --force.
+    let err = common::stderr(aion(new.path()).arg("import").arg(&path));
+    assert!(err.contains("nothing was imported"), "{}", err);
+}
+
+#[test]
+fn without_out_the_archive_is_a_tar_gz_in_the_current_dir_and_sessions_stay() {
+    let dir = TempDir::new().unwrap();
+    populated(dir.path());
+    stdout(aion(dir.path()).current_dir(dir.path()).arg("export"));
+
+    let written: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("aion-backup-"))
+        .collect();
+    assert_eq!(written.len(), 1, "{:?}", written);
+    assert!(written[0].ends_with(".tar.gz"), "{}", written[0]);
+    let files = untar(&dir.path().join(&written[0]));
+    assert!(files.iter().all(|(name, _)| !name.starts_with("sessions/")), "{:?}", files.iter().map(|f| &f.0).collect::<Vec<_>>());
+}
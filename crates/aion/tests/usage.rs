@@ -0,0 +1,75 @@
+//! `aion usage` over a seeded ledger, and the budget stopping a cloud request.
+
+use aion_core::costs::{self, Clock, SystemClock};
+use common::{aion, json};
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+fn seed(dir: &Path, month: &str, lines: &[&str]) {
+    let usage = dir.join("state").join("usage");
+    std::fs::create_dir_all(&usage).unwrap();
+    std::fs::write(usage.join(format!("{}.jsonl", month)), lines.join("\n")).unwrap();
+}
+
+#[test]
+fn reports_a_month_per_model() {
+    let dir = TempDir::new().unwrap();
+    seed(
+        dir.path(),
+        "2026-10",
+        &[
+            r#"{"at":1790000000,"session":"a","provider":"OpenAI","model":"gpt-4o-mini","input_tokens":1000,"output_tokens":200,"cost_usd":0.5}"#,
+            r#"{"at":1790000100,"session":"a","provider":"OpenAI","model":"gpt-4o-mini","input_tokens":500,"output_tokens":100,"cost_usd":0.25}"#,
+            r#"{"at":1790000200,"session":"b","provider":"OpenAI","model":"my-finetune","input_tokens":10,"output_tokens":2}"#,
+            r#"{"at":1790000300,"session":"b","provider":"Open"#,
+        ],
+    );
+
+    let out = aion(dir.path()).args(["--output", "json", "usage", "--month", "2026-10"]).assert().success();
+    let report = json(&out.get_output().stdout);
+    assert_eq!(report["month"], "2026-10");
+    assert_eq!(report["total"]["requests"], 3);
+    assert_eq!(report["total"]["input_tokens"], 1510);
+    assert_eq!(report["total"]["unpriced"], 1);
+    assert_eq!(report["total"]["cost_usd"], 0.75);
+    let models: Vec<&str> = report["models"].as_array().unwrap().iter().map(|m| m["model"].as_str().unwrap()).collect();
+    assert_eq!(models, ["gpt-4o-mini", "my-finetune"]);
+
+    let out = aion(dir.path()).arg("usage").assert().success();
+    let text = String::from_utf8_lossy(&out.get_output().stdout).into_owned();
+    assert!(text.contains("2026-10") && text.contains("$0.75 + 1 unpriced"), "{}", text);
+}
+
+#[test]
+fn an_empty_ledger_is_an_empty_report() {
+    let dir = TempDir::new().unwrap();
+    let out = aion(dir.path()).arg("usage").assert().success();
+    assert_eq!(String::from_utf8_lossy(&out.get_output().stdout).trim(), "No usage recorded yet.");
+    aion(dir.path()).args(["usage", "--month", "2026-13"]).assert().failure();
+}
+
+#[test]
+fn refuses_a_cloud_request_over_the_monthly_budget() {
+    let dir = TempDir::new().unwrap();
+    aion(dir.path()).args(["init", "--provider", "openai", "--model", "gpt-4o-mini", "--api-key-env", "TEST_KEY"]).assert().success();
+    aion(dir.path()).args(["config", "set", "budget.per_month_usd", "0.5"]).assert().success();
+    let month = costs::month_of(SystemClock.now());
+    seed(
+        dir.path(),
+        &month,
+        &[r#"{"at":0,"provider":"OpenAI","model":"gpt-4o-mini","input_tokens":1,"output_tokens":1,"cost_usd":0.6}"#],
+    );
+    let script = dir.path().join("script.json");
+    std::fs::write(&script, r#"{"steps":[{"content":"hello"}]}"#).unwrap();
+
+    let out = aion(dir.path())
+        .env("TEST_KEY", "x")
+        .env("AION_MOCK_PROVIDER", &script)
+        .args(["chat", "hi"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&out.get_output().stderr).into_owned();
+    assert!(stderr.contains("aion config set budget.per_month_usd"), "{}", stderr);
+}